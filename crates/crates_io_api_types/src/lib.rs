@@ -6,10 +6,12 @@ pub mod trustpub;
 pub use self::external_urls::remove_blocked_urls;
 pub use self::krate_publish::{EncodableCrateDependency, PublishMetadata};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use crates_io_database::models::download::{CargoVersionDownload, ClientDownload, DownloadClient};
 use crates_io_database::models::{
-    ApiToken, Category, Crate, Dependency, DependencyKind, Keyword, PublicUser, ReverseDependency,
-    Team, TopVersions, TrustpubData, User, Version, VersionDownload, VersionOwnerAction,
+    Advisory, ApiToken, Category, Crate, Dependency, DependencyKind, Keyword, PublicUser,
+    ReverseDependency, Team, TopVersions, TrustpubData, User, Version, VersionDownload,
+    VersionOwnerAction,
 };
 use serde::{Deserialize, Serialize};
 
@@ -236,6 +238,61 @@ impl From<VersionDownload> for EncodableVersionDownload {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
+#[schema(as = CargoVersionDownload)]
+pub struct EncodableCargoVersionDownload {
+    /// The `major.minor` version of cargo that downloaded the crate.
+    #[schema(example = "1.85")]
+    pub cargo_version: String,
+
+    /// The number of downloads by this cargo version on the given date.
+    #[schema(example = 123)]
+    pub downloads: i32,
+
+    /// The date this download count is for.
+    #[schema(example = "2019-12-13")]
+    pub date: String,
+}
+
+impl From<CargoVersionDownload> for EncodableCargoVersionDownload {
+    fn from(download: CargoVersionDownload) -> Self {
+        Self {
+            cargo_version: download.cargo_version,
+            downloads: download.downloads,
+            date: download.date.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
+#[schema(as = ClientDownload)]
+pub struct EncodableClientDownload {
+    /// The class of client that downloaded the crate.
+    ///
+    /// `ci` refers to cargo running on a CI service. Downloads by `mirror`
+    /// tools are not included in the regular download counts.
+    #[schema(value_type = String, example = "cargo")]
+    pub client: DownloadClient,
+
+    /// The number of downloads by this class of client on the given date.
+    #[schema(example = 123)]
+    pub downloads: i32,
+
+    /// The date this download count is for.
+    #[schema(example = "2019-12-13")]
+    pub date: String,
+}
+
+impl From<ClientDownload> for EncodableClientDownload {
+    fn from(download: ClientDownload) -> Self {
+        Self {
+            client: download.client,
+            downloads: download.downloads,
+            date: download.date.to_string(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
 #[schema(as = Keyword)]
 pub struct EncodableKeyword {
@@ -365,6 +422,11 @@ pub struct EncodableCrate {
 
     /// Whether this crate can only be published via Trusted Publishing.
     pub trustpub_only: bool,
+
+    /// Information about the deprecation of this crate, if its owners have
+    /// marked it as deprecated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation: Option<EncodableCrateDeprecation>,
 }
 
 impl EncodableCrate {
@@ -391,6 +453,9 @@ impl EncodableCrate {
             documentation,
             repository,
             trustpub_only,
+            deprecated_at,
+            deprecation_message,
+            successor,
             ..
         } = krate;
         let versions_link = match versions {
@@ -410,6 +475,12 @@ impl EncodableCrate {
         }
         let yanked = yanked.unwrap_or_default();
 
+        let deprecation = deprecated_at.map(|deprecated_at| EncodableCrateDeprecation {
+            deprecated_at,
+            message: deprecation_message,
+            successor,
+        });
+
         let max_version = top_versions
             .and_then(|v| v.highest.as_ref())
             .map(|v| v.to_string())
@@ -457,6 +528,7 @@ impl EncodableCrate {
             description,
             repository,
             trustpub_only,
+            deprecation,
             links: EncodableCrateLinks {
                 version_downloads: format!("/api/v1/crates/{name}/downloads"),
                 versions: versions_link,
@@ -495,6 +567,22 @@ impl EncodableCrate {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
+#[schema(as = CrateDeprecation)]
+pub struct EncodableCrateDeprecation {
+    /// The date and time when the crate was marked as deprecated.
+    #[schema(example = "2019-12-13T13:46:41Z")]
+    pub deprecated_at: DateTime<Utc>,
+
+    /// A message from the owners explaining the deprecation, if any.
+    #[schema(example = "This crate is no longer maintained.")]
+    pub message: Option<String>,
+
+    /// The name of the crate that the owners recommend using instead, if any.
+    #[schema(example = "serde_json")]
+    pub successor: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
 #[schema(as = CrateLinks)]
 pub struct EncodableCrateLinks {
@@ -602,7 +690,7 @@ pub struct EncodableTeam {
     #[schema(example = "https://avatars2.githubusercontent.com/u/1234567?v=4")]
     pub avatar: Option<String>,
 
-    /// The GitHub profile URL of the team.
+    /// The GitHub or GitLab profile URL of the team.
     #[schema(example = "https://github.com/rust-lang")]
     pub url: Option<String>,
 }
@@ -958,6 +1046,39 @@ pub struct EncodableVersion {
     /// happens in an asynchronous background job.
     #[schema(value_type = Option<HashMap<String, serde_json::Value>>)]
     pub linecounts: Option<serde_json::Value>,
+
+    /// Comparison of this version with the previous semver-compatible
+    /// version, including warnings about changes that are likely to break
+    /// users of the crate, like removed features.
+    ///
+    /// Status: **Unstable**
+    ///
+    /// This field is only present on the endpoint for a single version, and
+    /// only once the version has been checked, which happens in an
+    /// asynchronous background job. Versions without a previous compatible
+    /// version are not checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<HashMap<String, serde_json::Value>>)]
+    pub compatibility_report: Option<serde_json::Value>,
+
+    /// The date and time when this version was uploaded as a staged version.
+    ///
+    /// Status: **Unstable**
+    ///
+    /// This field is only present for staged versions, which are hidden from
+    /// the index and the version listings until an owner promotes them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "2019-12-13T13:46:41Z")]
+    pub staged_at: Option<DateTime<Utc>>,
+
+    /// The known security advisories affecting this version.
+    ///
+    /// Status: **Unstable**
+    ///
+    /// This field is only present on the endpoints that load the advisories,
+    /// and only if there are any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub advisories: Vec<EncodableAdvisory>,
 }
 
 impl EncodableVersion {
@@ -990,6 +1111,7 @@ impl EncodableVersion {
             repository,
             trustpub_data,
             linecounts,
+            staged_at,
             ..
         } = version;
 
@@ -1030,6 +1152,9 @@ impl EncodableVersion {
             repository,
             trustpub_data,
             linecounts,
+            compatibility_report: None,
+            staged_at,
+            advisories: vec![],
             published_by: published_by.map(PublicUser::into),
             audit_actions: audit_actions
                 .into_iter()
@@ -1043,6 +1168,58 @@ impl EncodableVersion {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
+#[schema(as = Advisory)]
+pub struct EncodableAdvisory {
+    /// The ID of the advisory in the RustSec advisory database.
+    #[schema(example = "RUSTSEC-2019-0001")]
+    pub id: String,
+
+    /// A one-line summary of the advisory.
+    #[schema(example = "Uncontrolled recursion leads to abort in HTML serialization")]
+    pub title: String,
+
+    /// The date on which the advisory was reported.
+    #[schema(example = "2019-04-27")]
+    pub date: NaiveDate,
+
+    /// A URL with more information about the advisory, if any.
+    #[schema(example = "https://github.com/rust-ammonia/ammonia/blob/master/CHANGELOG.md#210")]
+    pub url: Option<String>,
+
+    /// Other identifiers of the vulnerability, e.g. CVE IDs.
+    #[schema(example = json!(["CVE-2019-15542"]))]
+    pub aliases: Vec<String>,
+
+    /// The kind of informational advisory (e.g. `unmaintained` or
+    /// `unsound`), or `null` if the advisory is about a vulnerability.
+    #[schema(example = json!(null))]
+    pub informational: Option<String>,
+}
+
+impl From<Advisory> for EncodableAdvisory {
+    fn from(advisory: Advisory) -> Self {
+        let Advisory {
+            id,
+            title,
+            date,
+            url,
+            aliases,
+            informational,
+            ..
+        } = advisory;
+
+        Self {
+            id,
+            title,
+            date,
+            url,
+            aliases,
+            informational,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
 #[schema(as = VersionLinks)]
 pub struct EncodableVersionLinks {
@@ -1177,6 +1354,9 @@ mod tests {
             }],
             trustpub_data: None,
             linecounts: None,
+            compatibility_report: None,
+            staged_at: None,
+            advisories: vec![],
         };
         let json = serde_json::to_string(&ver).unwrap();
         assert_some!(json.as_str().find(r#""updated_at":"2017-01-06T14:23:11Z""#));
@@ -1225,6 +1405,7 @@ mod tests {
             },
             exact_match: false,
             trustpub_only: false,
+            deprecation: None,
         };
         let json = serde_json::to_string(&crt).unwrap();
         assert_some!(json.as_str().find(r#""updated_at":"2017-01-06T14:23:11Z""#));
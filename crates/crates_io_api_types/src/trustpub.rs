@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 #[schema(as = GitHubConfig)]
@@ -74,3 +75,71 @@ pub struct NewGitLabConfig {
     #[schema(example = json!(null))]
     pub environment: Option<String>,
 }
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(as = OidcConfig)]
+pub struct OidcConfig {
+    #[schema(example = 42)]
+    pub id: i32,
+    #[schema(example = "regex")]
+    #[serde(rename = "crate")]
+    pub krate: String,
+    /// Identifier of the OIDC provider.
+    #[schema(example = "buildkite")]
+    pub provider: String,
+    /// Provider-specific identifier of the repository or pipeline.
+    #[schema(example = "rust-lang/regex")]
+    pub repository: String,
+    #[schema(example = json!(null))]
+    pub repository_owner_id: Option<String>,
+    /// Additional JWT claims that must match for a token exchange to succeed.
+    #[schema(example = json!({ "step_key": "publish" }))]
+    pub claims: BTreeMap<String, String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[schema(as = NewOidcConfig)]
+pub struct NewOidcConfig {
+    #[schema(example = "regex")]
+    #[serde(rename = "crate")]
+    pub krate: String,
+    /// Identifier of the OIDC provider.
+    #[schema(example = "buildkite")]
+    pub provider: String,
+    /// Provider-specific identifier of the repository or pipeline.
+    #[schema(example = "rust-lang/regex")]
+    pub repository: String,
+    /// Additional JWT claims that must match for a token exchange to succeed.
+    #[schema(example = json!({ "step_key": "publish" }))]
+    #[serde(default)]
+    pub claims: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(as = Provenance)]
+pub struct Provenance {
+    /// Identifier of the key that was used to sign the attestation.
+    #[schema(example = "0f8b2e5c9d7a4f1b3e6d8c0a2b4f6e8d0c2a4b6f8e0d2c4a6b8f0e2d4c6a8b0f")]
+    pub key_id: String,
+    /// Date and time when the attestation was issued.
+    pub created_at: DateTime<Utc>,
+    /// DSSE envelope containing the signed in-toto statement with a SLSA
+    /// provenance predicate.
+    #[schema(value_type = Object)]
+    pub envelope: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(as = ProvenanceKey)]
+pub struct ProvenanceKey {
+    /// Identifier of the key (the hex-encoded SHA256 hash of the public key).
+    #[schema(example = "0f8b2e5c9d7a4f1b3e6d8c0a2b4f6e8d0c2a4b6f8e0d2c4a6b8f0e2d4c6a8b0f")]
+    pub key_id: String,
+    /// Signature algorithm of the key.
+    #[schema(example = "ed25519")]
+    pub algorithm: &'static str,
+    /// Base64-encoded raw public key.
+    #[schema(example = "GbW0X0Ss8qNfWIZoz64Q3XQxq5qJ1n8vZy4WzQ3Np9A=")]
+    pub public_key: String,
+}
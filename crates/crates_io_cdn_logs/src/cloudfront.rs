@@ -6,7 +6,7 @@
 use crate::DownloadsMap;
 use crate::date::parse_date;
 use crate::paths::parse_path;
-use crate::user_agent::should_count_user_agent;
+use crate::user_agent::{ClientInfo, ClientKind, should_count_user_agent};
 use std::borrow::Cow;
 use tokio::io::{AsyncBufRead, AsyncBufReadExt};
 use tracing::{instrument, warn};
@@ -105,7 +105,11 @@ pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Resul
             continue;
         }
 
-        if user_agent.is_some_and(|ua| !should_count_user_agent(ua)) {
+        // Downloads by mirrors are not counted, but they are still tracked
+        // in the per-client download counts.
+        let counted = user_agent.is_none_or(should_count_user_agent);
+        let client = ClientInfo::from_user_agent(user_agent);
+        if !counted && client.kind != ClientKind::Mirror {
             // Ignore requests from user agents that should not be counted.
             continue;
         }
@@ -136,7 +140,10 @@ pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Resul
             continue;
         };
 
-        downloads.add(name, version, date);
+        downloads.add_client(&name, date, client);
+        if counted {
+            downloads.add(name, version, date);
+        }
     }
 
     Ok(downloads)
@@ -184,6 +191,14 @@ mod tests {
             2024-01-17  tar@0.4.38 .. 1
         }
         ");
+
+        assert_debug_snapshot!(client_totals(&downloads), @r#"
+        {
+            "cargo 1.71": 8,
+            "cargo 1.74": 11,
+            "client cargo": 19,
+        }
+        "#);
     }
 
     #[tokio::test]
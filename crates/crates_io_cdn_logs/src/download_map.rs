@@ -1,3 +1,4 @@
+use crate::user_agent::{CargoVersion, ClientInfo, ClientKind};
 use chrono::NaiveDate;
 use derive_more::Deref;
 use foldhash::quality::FixedState;
@@ -5,32 +6,75 @@ use semver::Version;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
+type Map<K> = HashMap<K, u64, FixedState>;
+
+/// The download counts of a log file.
+///
+/// Dereferences to the per-version download counts. The counts per cargo
+/// version and per client class are only tracked per crate, and are
+/// available via [`DownloadsMap::cargo_versions()`] and
+/// [`DownloadsMap::clients()`].
 #[derive(Clone, Default, Deref)]
-pub struct DownloadsMap(HashMap<(String, Version, NaiveDate), u64, FixedState>);
+pub struct DownloadsMap {
+    #[deref]
+    versions: Map<(String, Version, NaiveDate)>,
+    cargo_versions: Map<(String, CargoVersion, NaiveDate)>,
+    clients: Map<(String, ClientKind, NaiveDate)>,
+}
 
 impl DownloadsMap {
     pub fn new() -> Self {
-        Self(HashMap::default())
+        Self::default()
     }
 
     /// Increments the download count for the given crate version on the given date.
     pub fn add(&mut self, name: String, version: Version, date: NaiveDate) {
-        *self.0.entry((name, version, date)).or_default() += 1;
+        *self.versions.entry((name, version, date)).or_default() += 1;
+    }
+
+    /// Increments the download counts for the cargo version and the client
+    /// class of the given client on the given date.
+    pub fn add_client(&mut self, name: &str, date: NaiveDate, client: ClientInfo) {
+        if let Some(cargo_version) = client.cargo_version {
+            let key = (name.to_string(), cargo_version, date);
+            *self.cargo_versions.entry(key).or_default() += 1;
+        }
+
+        let key = (name.to_string(), client.kind, date);
+        *self.clients.entry(key).or_default() += 1;
+    }
+
+    /// Returns an iterator over `(crate, cargo_version, date, downloads)` tuples.
+    pub fn cargo_versions(&self) -> impl Iterator<Item = (&str, CargoVersion, NaiveDate, u64)> {
+        self.cargo_versions
+            .iter()
+            .map(|((name, version, date), downloads)| (name.as_str(), *version, *date, *downloads))
+    }
+
+    /// Returns an iterator over `(crate, client, date, downloads)` tuples.
+    pub fn clients(&self) -> impl Iterator<Item = (&str, ClientKind, NaiveDate, u64)> {
+        self.clients
+            .iter()
+            .map(|((name, kind, date), downloads)| (name.as_str(), *kind, *date, *downloads))
     }
 
     /// Returns a [`HashSet`] of all crate names in the map.
     pub fn unique_crates(&self) -> HashSet<&str> {
-        self.0.keys().map(|(krate, _, _)| krate.as_str()).collect()
+        self.versions
+            .keys()
+            .map(|(krate, _, _)| krate.as_str())
+            .collect()
     }
 
     /// Returns the total number of downloads across all crates and versions.
     pub fn sum_downloads(&self) -> u64 {
-        self.0.values().sum()
+        self.versions.values().sum()
     }
 
-    /// Converts the map into a vector of `(crate, version, date, downloads)` tuples.
+    /// Converts the per-version download counts into a vector of
+    /// `(crate, version, date, downloads)` tuples.
     pub fn into_vec(self) -> Vec<(String, Version, NaiveDate, u64)> {
-        self.0
+        self.versions
             .into_iter()
             .map(|((name, version, date), downloads)| (name, version, date, downloads))
             .collect()
@@ -40,7 +84,7 @@ impl DownloadsMap {
 impl Debug for DownloadsMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut downloads = self
-            .0
+            .versions
             .iter()
             .map(|((krate, version, date), downloads)| (date, krate, version, downloads))
             .collect::<Vec<_>>();
@@ -62,6 +106,7 @@ impl Debug for DownloadsMap {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::user_agent::ClientInfo;
     use chrono::NaiveDate;
     use insta::assert_debug_snapshot;
     use semver::Version;
@@ -107,4 +152,41 @@ mod tests {
         }
         ");
     }
+
+    #[test]
+    fn test_add_client() {
+        let mut downloads = DownloadsMap::new();
+        let date = "2023-12-25".parse::<NaiveDate>().unwrap();
+
+        let cargo = ClientInfo::from_user_agent(Some("cargo/1.74.0 (ecb9851af 2023-10-18)"));
+        let mirror = ClientInfo::from_user_agent(Some("panamax/1.0.14"));
+        downloads.add_client("xmas", date, cargo);
+        downloads.add_client("xmas", date, cargo);
+        downloads.add_client("xmas", date, mirror);
+
+        let mut cargo_versions = downloads
+            .cargo_versions()
+            .map(|(name, version, date, count)| {
+                format!("{date}  {name} cargo/{version} .. {count}")
+            })
+            .collect::<Vec<_>>();
+        cargo_versions.sort();
+        assert_eq!(cargo_versions, ["2023-12-25  xmas cargo/1.74 .. 2"]);
+
+        let mut clients = downloads
+            .clients()
+            .map(|(name, client, date, count)| format!("{date}  {name} {client} .. {count}"))
+            .collect::<Vec<_>>();
+        clients.sort();
+        assert_eq!(
+            clients,
+            [
+                "2023-12-25  xmas cargo .. 2",
+                "2023-12-25  xmas mirror .. 1"
+            ]
+        );
+
+        // The per-client counts don't affect the per-version counts
+        assert!(downloads.is_empty());
+    }
 }
@@ -6,7 +6,7 @@ mod json;
 
 use crate::DownloadsMap;
 use crate::paths::parse_path;
-use crate::user_agent::should_count_user_agent;
+use crate::user_agent::{ClientInfo, ClientKind, should_count_user_agent};
 use std::borrow::Cow;
 use tokio::io::{AsyncBufRead, AsyncBufReadExt};
 use tracing::{debug_span, instrument, warn};
@@ -48,10 +48,11 @@ pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Resul
             continue;
         }
 
-        if json
-            .user_agent()
-            .is_some_and(|ua| !should_count_user_agent(ua))
-        {
+        // Downloads by mirrors are not counted, but they are still tracked
+        // in the per-client download counts.
+        let counted = json.user_agent().is_none_or(should_count_user_agent);
+        let client = ClientInfo::from_user_agent(json.user_agent());
+        if !counted && client.kind != ClientKind::Mirror {
             // Ignore requests from user agents that should not be counted.
             continue;
         }
@@ -71,7 +72,10 @@ pub async fn count_downloads(reader: impl AsyncBufRead + Unpin) -> anyhow::Resul
             continue;
         };
 
-        downloads.add(name, version, date);
+        downloads.add_client(&name, date, client);
+        if counted {
+            downloads.add(name, version, date);
+        }
     }
 
     Ok(downloads)
@@ -217,5 +221,14 @@ mod tests {
             2025-10-26  unicode-normalization@0.1.22 .. 1
         }
         ");
+
+        assert_debug_snapshot!(client_totals(&downloads), @r#"
+        {
+            "cargo 1.88": 2,
+            "cargo 1.90": 14,
+            "cargo 1.92": 1,
+            "client cargo": 17,
+        }
+        "#);
     }
 }
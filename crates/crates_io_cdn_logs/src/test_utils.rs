@@ -1,3 +1,5 @@
+use crate::DownloadsMap;
+use std::collections::BTreeMap;
 use tracing::dispatcher::DefaultGuard;
 use tracing::subscriber;
 use tracing_subscriber::fmt;
@@ -9,3 +11,19 @@ use tracing_subscriber::fmt;
 pub fn enable_tracing_output() -> DefaultGuard {
     subscriber::set_default(fmt().compact().with_test_writer().finish())
 }
+
+/// Sums up the per-cargo-version and per-client download counts across all
+/// crates and dates, to keep the snapshots compact.
+pub fn client_totals(downloads: &DownloadsMap) -> BTreeMap<String, u64> {
+    let mut totals = BTreeMap::new();
+
+    for (_, cargo_version, _, count) in downloads.cargo_versions() {
+        *totals.entry(format!("cargo {cargo_version}")).or_default() += count;
+    }
+
+    for (_, client, _, count) in downloads.clients() {
+        *totals.entry(format!("client {client}")).or_default() += count;
+    }
+
+    totals
+}
@@ -1,3 +1,25 @@
+use percent_encoding::percent_decode_str;
+use std::fmt::{Display, Formatter};
+
+/// User agent prefixes of tools that are known to mirror crate files from
+/// crates.io.
+const MIRROR_PREFIXES: &[&str] = &["panamax", "romt", "crates-mirror", "cargo-cacher"];
+
+/// Markers in the user agent that indicate a download from a CI service.
+///
+/// cargo itself does not include this information, but CI setups commonly
+/// customize the user agent via the `http.user-agent` config option.
+const CI_MARKERS: &[&str] = &[
+    "github-actions",
+    "gitlab-ci",
+    "buildkite",
+    "circleci",
+    "travis-ci",
+    "jenkins",
+    "azure-pipelines",
+    "teamcity",
+];
+
 /// Determines if downloads from the given user agent should be counted.
 ///
 /// Returns `true` if the download should be counted, `false` otherwise.
@@ -13,6 +35,131 @@ pub fn should_count_user_agent(user_agent: &str) -> bool {
         || suffix.starts_with("%20")
 }
 
+/// The class of client that downloaded a crate file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ClientKind {
+    /// A regular cargo invocation.
+    Cargo,
+    /// A cargo invocation on a CI service.
+    Ci,
+    /// A tool that mirrors crate files, e.g. `panamax`.
+    Mirror,
+    /// Any other client, or a request without user agent.
+    Other,
+}
+
+impl ClientKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClientKind::Cargo => "cargo",
+            ClientKind::Ci => "ci",
+            ClientKind::Mirror => "mirror",
+            ClientKind::Other => "other",
+        }
+    }
+}
+
+impl Display for ClientKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The `major.minor` version of cargo that downloaded a crate file.
+///
+/// Patch versions and release channels are discarded, since they are not
+/// relevant for deciding on a minimum supported Rust version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CargoVersion {
+    pub major: u64,
+    pub minor: u64,
+}
+
+impl Display for CargoVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The information that could be extracted from a user agent string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub kind: ClientKind,
+    pub cargo_version: Option<CargoVersion>,
+}
+
+impl ClientInfo {
+    /// Parses the given user agent, which may be percent-encoded like in the
+    /// CloudFront logs.
+    pub fn from_user_agent(user_agent: Option<&str>) -> Self {
+        let Some(user_agent) = user_agent else {
+            return Self::other();
+        };
+
+        let user_agent = percent_decode_str(user_agent).decode_utf8_lossy();
+
+        if MIRROR_PREFIXES
+            .iter()
+            .any(|prefix| starts_with_ignore_ascii_case(&user_agent, prefix))
+        {
+            return Self {
+                kind: ClientKind::Mirror,
+                cargo_version: None,
+            };
+        }
+
+        let Some(version) = user_agent
+            .strip_prefix("cargo/")
+            .or_else(|| user_agent.strip_prefix("cargo "))
+        else {
+            return Self::other();
+        };
+
+        let is_ci = CI_MARKERS
+            .iter()
+            .any(|marker| contains_ignore_ascii_case(version, marker));
+
+        Self {
+            kind: if is_ci {
+                ClientKind::Ci
+            } else {
+                ClientKind::Cargo
+            },
+            cargo_version: parse_cargo_version(version),
+        }
+    }
+
+    fn other() -> Self {
+        Self {
+            kind: ClientKind::Other,
+            cargo_version: None,
+        }
+    }
+}
+
+/// Parses the `major.minor` part of strings like `1.74.0 (ecb9851af 2023-10-18)`
+/// or `1.92.0-nightly`.
+fn parse_cargo_version(version: &str) -> Option<CargoVersion> {
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some(CargoVersion { major, minor })
+}
+
+fn starts_with_ignore_ascii_case(haystack: &str, prefix: &str) -> bool {
+    haystack
+        .as_bytes()
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix.as_bytes()))
+}
+
+fn contains_ignore_ascii_case(haystack: &str, needle: &str) -> bool {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .any(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +199,61 @@ mod tests {
         assert!(!should_count_user_agent("cargo"));
         assert!(!should_count_user_agent("cargo-"));
     }
+
+    fn client_info(user_agent: &str) -> (ClientKind, Option<String>) {
+        let info = ClientInfo::from_user_agent(Some(user_agent));
+        (
+            info.kind,
+            info.cargo_version.map(|version| version.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_client_info() {
+        let cargo = |version: &str| (ClientKind::Cargo, Some(version.to_string()));
+
+        assert_eq!(
+            client_info("cargo/1.92.0-nightly (344c4567c 2025-10-21)"),
+            cargo("1.92")
+        );
+        assert_eq!(
+            client_info("cargo/1.88.0 (873a06493 2025-05-10)"),
+            cargo("1.88")
+        );
+        assert_eq!(client_info("cargo 1.74.0"), cargo("1.74"));
+        assert_eq!(client_info("cargo%2F1.74.0"), cargo("1.74"));
+        assert_eq!(
+            client_info("cargo%201.74.0%20(ecb9851af%202023-10-18)"),
+            cargo("1.74")
+        );
+        assert_eq!(client_info("cargo/"), (ClientKind::Cargo, None));
+        assert_eq!(client_info("cargo/1"), (ClientKind::Cargo, None));
+
+        // CI services
+        assert_eq!(
+            client_info("cargo/1.88.0 (873a06493 2025-05-10) GitHub-Actions"),
+            (ClientKind::Ci, Some("1.88".to_string()))
+        );
+        assert_eq!(
+            client_info("cargo%201.74.0%20gitlab-ci"),
+            (ClientKind::Ci, Some("1.74".to_string()))
+        );
+
+        // Mirrors
+        assert_eq!(client_info("panamax/1.0.14"), (ClientKind::Mirror, None));
+        assert_eq!(client_info("Romt/0.3"), (ClientKind::Mirror, None));
+
+        // Everything else
+        assert_eq!(client_info("Mozilla/5.0"), (ClientKind::Other, None));
+        assert_eq!(
+            client_info("Bazel%2Frelease%207.6.2"),
+            (ClientKind::Other, None)
+        );
+        assert_eq!(client_info("cargo-binstall/1.0"), (ClientKind::Other, None));
+        assert_eq!(client_info(""), (ClientKind::Other, None));
+
+        let info = ClientInfo::from_user_agent(None);
+        assert_eq!(info.kind, ClientKind::Other);
+        assert_eq!(info.cargo_version, None);
+    }
 }
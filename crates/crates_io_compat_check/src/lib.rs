@@ -0,0 +1,413 @@
+#![doc = include_str!("../README.md")]
+
+use crates_io_cargo_toml::{Dependency, DepsSet, Manifest};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The result of comparing a version of a crate to the previous
+/// semver-compatible version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Report {
+    /// The version that this version was compared to.
+    pub previous_version: String,
+    pub features: FeatureChanges,
+    /// Changes to the normal and build dependencies, sorted by kind, target
+    /// and name. Dev-dependencies are ignored.
+    pub dependencies: Vec<DependencyChange>,
+    /// The change of the `rust-version` field, if it was changed.
+    pub rust_version: Option<RustVersionChange>,
+    /// Human-readable descriptions of the changes that are likely to break
+    /// users of the crate.
+    pub warnings: Vec<String>,
+}
+
+/// Changes to the features of a crate, including the implicit features of
+/// optional dependencies.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Features that enable a different set of features or dependencies.
+    pub changed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyChange {
+    /// The name of the dependency in the manifest, which may differ from
+    /// the name of the package if the dependency was renamed.
+    pub name: String,
+    pub kind: DependencyKind,
+    /// The `cfg()` expression or target triple of a platform-specific
+    /// dependency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// The version requirement in the previous version, or `None` if the
+    /// dependency was added.
+    pub previous_req: Option<String>,
+    /// The version requirement in this version, or `None` if the dependency
+    /// was removed.
+    pub req: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Normal,
+    Build,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustVersionChange {
+    pub previous: Option<String>,
+    pub current: Option<String>,
+}
+
+/// Returns whether cargo considers the two versions to be compatible, i.e.
+/// whether a caret requirement on the lower version allows the higher one.
+pub fn is_compatible(a: &Version, b: &Version) -> bool {
+    compat_key(a.major, a.minor, a.patch) == compat_key(b.major, b.minor, b.patch)
+}
+
+/// Compares the manifest of a version of a crate to the manifest of the
+/// previous semver-compatible version.
+pub fn compare(
+    previous_version: &Version,
+    previous: &Manifest,
+    version: &Version,
+    current: &Manifest,
+) -> Report {
+    let is_patch_release =
+        previous_version.major == version.major && previous_version.minor == version.minor;
+
+    let mut warnings = Vec::new();
+
+    let previous_features = features(previous);
+    let current_features = features(current);
+
+    let mut features = FeatureChanges::default();
+    for (name, enables) in &previous_features {
+        let Some(current_enables) = current_features.get(name) else {
+            warnings.push(format!("The `{name}` feature was removed"));
+            features.removed.push(name.clone());
+            continue;
+        };
+
+        if enables != current_enables {
+            features.changed.push(name.clone());
+        }
+
+        for value in enables.difference(current_enables) {
+            warnings.push(format!("The `{name}` feature no longer enables `{value}`"));
+        }
+    }
+    for name in current_features.keys() {
+        if !previous_features.contains_key(name) {
+            features.added.push(name.clone());
+        }
+    }
+
+    let previous_dependencies = dependencies(previous);
+    let current_dependencies = dependencies(current);
+
+    let keys = previous_dependencies
+        .keys()
+        .chain(current_dependencies.keys())
+        .collect::<BTreeSet<_>>();
+
+    let mut dependencies = Vec::new();
+    for key in keys {
+        let previous_req = previous_dependencies.get(key).map(|dep| dep.req());
+        let req = current_dependencies.get(key).map(|dep| dep.req());
+        if previous_req == req {
+            continue;
+        }
+
+        let (kind, target, name) = key;
+        if *kind == DependencyKind::Normal
+            && let (Some(previous_req), Some(req)) = (previous_req, req)
+            && !is_compatible_req(previous_req, req)
+        {
+            warnings.push(format!(
+                "The `{name}` dependency was changed from `{previous_req}` to the incompatible \
+                `{req}`, which is a breaking change if its types are part of the public API"
+            ));
+        }
+
+        dependencies.push(DependencyChange {
+            name: name.to_string(),
+            kind: *kind,
+            target: target.map(ToString::to_string),
+            previous_req: previous_req.map(ToString::to_string),
+            req: req.map(ToString::to_string),
+        });
+    }
+
+    let previous_rust_version = rust_version(previous);
+    let current_rust_version = rust_version(current);
+
+    let rust_version = (previous_rust_version != current_rust_version).then(|| {
+        if is_patch_release && let Some(current) = &current_rust_version {
+            let raised = match &previous_rust_version {
+                Some(previous) => parse_rust_version(current) > parse_rust_version(previous),
+                None => true,
+            };
+
+            if raised {
+                warnings.push(format!(
+                    "The minimum supported Rust version was raised to {current} in a patch release"
+                ));
+            }
+        }
+
+        RustVersionChange {
+            previous: previous_rust_version,
+            current: current_rust_version,
+        }
+    });
+
+    Report {
+        previous_version: previous_version.to_string(),
+        features,
+        dependencies,
+        rust_version,
+        warnings,
+    }
+}
+
+/// Returns the features of the crate, including the implicit features of
+/// optional dependencies that are not referenced with the `dep:` syntax.
+fn features(manifest: &Manifest) -> BTreeMap<String, BTreeSet<String>> {
+    let mut features = manifest
+        .features
+        .iter()
+        .flatten()
+        .map(|(name, values)| (name.clone(), values.iter().cloned().collect()))
+        .collect::<BTreeMap<_, BTreeSet<_>>>();
+
+    let explicit_deps = features
+        .values()
+        .flatten()
+        .filter_map(|value| value.strip_prefix("dep:"))
+        .map(ToString::to_string)
+        .collect::<BTreeSet<_>>();
+
+    for ((kind, _, name), dep) in dependencies(manifest) {
+        if kind == DependencyKind::Normal && dep.optional() && !explicit_deps.contains(name) {
+            let implicit = BTreeSet::from([format!("dep:{name}")]);
+            features.entry(name.to_string()).or_insert(implicit);
+        }
+    }
+
+    features
+}
+
+type DependencyKey<'a> = (DependencyKind, Option<&'a str>, &'a str);
+
+/// Returns the normal and build dependencies of the crate, including the
+/// platform-specific ones.
+fn dependencies(manifest: &Manifest) -> BTreeMap<DependencyKey<'_>, &Dependency> {
+    fn add<'a>(
+        map: &mut BTreeMap<DependencyKey<'a>, &'a Dependency>,
+        kind: DependencyKind,
+        target: Option<&'a str>,
+        deps: Option<&'a DepsSet>,
+    ) {
+        for (name, dep) in deps.into_iter().flatten() {
+            map.insert((kind, target, name.as_str()), dep);
+        }
+    }
+
+    let mut map = BTreeMap::new();
+    add(
+        &mut map,
+        DependencyKind::Normal,
+        None,
+        manifest.dependencies.as_ref(),
+    );
+    add(
+        &mut map,
+        DependencyKind::Build,
+        None,
+        manifest.build_dependencies.as_ref(),
+    );
+
+    for (target, deps) in manifest.target.iter().flatten() {
+        let target = Some(target.as_str());
+        add(
+            &mut map,
+            DependencyKind::Normal,
+            target,
+            Some(&deps.dependencies),
+        );
+        add(
+            &mut map,
+            DependencyKind::Build,
+            target,
+            Some(&deps.build_dependencies),
+        );
+    }
+
+    map
+}
+
+fn rust_version(manifest: &Manifest) -> Option<String> {
+    let package = manifest.package.as_ref()?;
+    package.rust_version.clone()?.as_local()
+}
+
+/// Parses a `rust-version` like `1.70` into its numeric components, so that
+/// `1.9` and `1.10` are ordered correctly.
+fn parse_rust_version(rust_version: &str) -> Vec<u64> {
+    rust_version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Returns whether the lowest versions that are allowed by the two version
+/// requirements are compatible with each other.
+///
+/// Requirements that can't be parsed are treated as compatible, to avoid
+/// warnings that can't be explained to the user.
+fn is_compatible_req(a: &str, b: &str) -> bool {
+    let (Ok(a), Ok(b)) = (VersionReq::parse(a), VersionReq::parse(b)) else {
+        return true;
+    };
+
+    let (Some(a), Some(b)) = (a.comparators.first(), b.comparators.first()) else {
+        return true;
+    };
+
+    let a = compat_key(a.major, a.minor.unwrap_or(0), a.patch.unwrap_or(0));
+    let b = compat_key(b.major, b.minor.unwrap_or(0), b.patch.unwrap_or(0));
+    a == b
+}
+
+/// Returns the left-most non-zero version component, which has to match for
+/// two versions to be compatible.
+fn compat_key(major: u64, minor: u64, patch: u64) -> (u64, u64, u64) {
+    match (major, minor) {
+        (0, 0) => (0, 0, patch),
+        (0, minor) => (0, minor, 0),
+        (major, _) => (major, 0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_debug_snapshot;
+
+    fn manifest(toml: &str) -> Manifest {
+        Manifest::from_slice(toml.as_bytes()).unwrap()
+    }
+
+    fn version(version: &str) -> Version {
+        version.parse().unwrap()
+    }
+
+    const PREVIOUS: &str = r#"
+[package]
+name = "foo"
+version = "1.2.0"
+rust-version = "1.60"
+
+[dependencies]
+serde = "1.0"
+log = { version = "0.4", optional = true }
+regex = { version = "1.5", optional = true }
+rand = "0.7"
+
+[build-dependencies]
+cc = "1.0"
+
+[dev-dependencies]
+tokio = "1"
+
+[features]
+default = ["std", "logging"]
+std = []
+logging = ["dep:log"]
+"#;
+
+    #[test]
+    fn test_is_compatible() {
+        assert!(is_compatible(&version("1.2.0"), &version("1.9.3")));
+        assert!(is_compatible(&version("0.3.0"), &version("0.3.1")));
+        assert!(is_compatible(&version("1.0.0"), &version("1.1.0-beta.1")));
+        assert!(!is_compatible(&version("1.2.0"), &version("2.0.0")));
+        assert!(!is_compatible(&version("0.3.0"), &version("0.4.0")));
+        assert!(!is_compatible(&version("0.0.1"), &version("0.0.2")));
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let manifest = manifest(PREVIOUS);
+        let report = compare(&version("1.2.0"), &manifest, &version("1.2.1"), &manifest);
+        assert_eq!(report.features, FeatureChanges::default());
+        assert_eq!(report.dependencies, vec![]);
+        assert_eq!(report.rust_version, None);
+        assert_eq!(report.warnings, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_patch_release() {
+        let current = r#"
+[package]
+name = "foo"
+version = "1.2.1"
+rust-version = "1.70"
+
+[dependencies]
+serde = "1.0.100"
+log = { version = "0.4", optional = true }
+rand = "0.8"
+once_cell = "1"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[dev-dependencies]
+tokio = "2"
+
+[features]
+default = ["std"]
+std = []
+logging = ["dep:log"]
+tracing = []
+"#;
+
+        let previous = manifest(PREVIOUS);
+        let current = manifest(current);
+        let report = compare(&version("1.2.0"), &previous, &version("1.2.1"), &current);
+        assert_debug_snapshot!(report);
+    }
+
+    #[test]
+    fn test_minor_release() {
+        let current = PREVIOUS
+            .replace(r#"rust-version = "1.60""#, r#"rust-version = "1.65""#)
+            .replace("[package]", "[package]\nedition = \"2021\"");
+
+        let previous = manifest(PREVIOUS);
+        let current = manifest(&current);
+        let report = compare(&version("1.2.0"), &previous, &version("1.3.0"), &current);
+
+        // Raising the MSRV in a minor release is common practice
+        assert_eq!(report.warnings, Vec::<String>::new());
+        assert_eq!(
+            report.rust_version,
+            Some(RustVersionChange {
+                previous: Some("1.60".to_string()),
+                current: Some("1.65".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rust_version() {
+        assert!(parse_rust_version("1.10") > parse_rust_version("1.9"));
+        assert!(parse_rust_version("1.70.1") > parse_rust_version("1.70"));
+    }
+}
@@ -10,11 +10,12 @@
 //! verify a single file without parsing the zip central directory.
 
 use anyhow::{Context, bail};
-use flate2::read::GzDecoder;
+use flate2::read::{DeflateDecoder, GzDecoder};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::io::{Read, Seek, Write};
+use std::ops::Range;
 use zip::write::SimpleFileOptions;
 use zip::{CompressionMethod, ZipWriter};
 
@@ -53,6 +54,62 @@ pub struct FileEntry {
     pub sha256: String,
 }
 
+impl FileEntry {
+    /// Byte range of this entry's compressed payload in the zip, suitable
+    /// for a ranged fetch.
+    pub fn range(&self) -> Range<u64> {
+        self.data_offset..self.data_offset + self.compressed_size
+    }
+
+    /// Decompresses the payload fetched from [`range`](Self::range) and
+    /// checks it against the size and sha256 recorded in the manifest.
+    pub fn decode(&self, payload: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let path = &self.path;
+
+        if payload.len() as u64 != self.compressed_size {
+            bail!(
+                "Payload of `{path}` has {} bytes, expected {}",
+                payload.len(),
+                self.compressed_size
+            );
+        }
+
+        let contents = match self.compression.as_str() {
+            "deflate" => {
+                // Read at most one byte more than expected, so that a corrupt
+                // payload can not make us inflate an arbitrary amount of data.
+                let limit = self.uncompressed_size + 1;
+                let mut contents = Vec::new();
+                DeflateDecoder::new(payload)
+                    .take(limit)
+                    .read_to_end(&mut contents)
+                    .with_context(|| format!("Failed to decompress `{path}`"))?;
+                contents
+            }
+            "store" => payload.to_vec(),
+            other => bail!("Unexpected compression method `{other}` for `{path}`"),
+        };
+
+        if contents.len() as u64 != self.uncompressed_size {
+            bail!(
+                "Contents of `{path}` have {} bytes, expected {}",
+                contents.len(),
+                self.uncompressed_size
+            );
+        }
+
+        let sha256 = hex::encode(Sha256::digest(&contents));
+        if sha256 != self.sha256 {
+            bail!(
+                "Checksum mismatch for `{path}`: expected {}, got {sha256}",
+                self.sha256
+            );
+        }
+
+        Ok(contents)
+    }
+}
+
 /// Builds a deterministic zip from a `.crate` (gzipped tarball) and returns its
 /// [`Manifest`].
 pub fn build_zip<R: Read + Seek, W: Read + Write + Seek>(
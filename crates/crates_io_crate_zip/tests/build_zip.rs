@@ -1,4 +1,4 @@
-use claims::{assert_err, assert_none, assert_ok, assert_some, assert_some_eq};
+use claims::{assert_err, assert_none, assert_ok, assert_ok_eq, assert_some, assert_some_eq};
 use crates_io_crate_zip::{FileEntry, Manifest, build_zip};
 use crates_io_tarball::TarballBuilder;
 use flate2::read::DeflateDecoder;
@@ -216,3 +216,37 @@ fn offsets_and_hashes_are_real() {
         assert_eq!(from_offsets, expected);
     }
 }
+
+#[test]
+fn decode_returns_verified_contents() {
+    let (zip_bytes, manifest) = build(&test_crate());
+
+    let file = assert_some!(manifest.files.iter().find(|f| f.path == "src/lib.rs"));
+    let range = file.range();
+    let payload = &zip_bytes[range.start as usize..range.end as usize];
+    assert_ok_eq!(file.decode(payload), b"pub fn foo() {}\n");
+
+    let empty = assert_some!(manifest.files.iter().find(|f| f.path == "empty.txt"));
+    let range = empty.range();
+    let payload = &zip_bytes[range.start as usize..range.end as usize];
+    assert_ok_eq!(empty.decode(payload), b"");
+}
+
+#[test]
+fn decode_rejects_mismatching_contents() {
+    let (zip_bytes, manifest) = build(&test_crate());
+
+    let file = assert_some!(manifest.files.iter().find(|f| f.path == "src/lib.rs"));
+    let range = file.range();
+    let payload = &zip_bytes[range.start as usize..range.end as usize];
+
+    // A truncated payload is rejected before it is decompressed.
+    let error = assert_err!(file.decode(&payload[1..]));
+    assert_snapshot!(error, @"Payload of `src/lib.rs` has 17 bytes, expected 18");
+
+    // A manifest entry with a different hash does not match the contents.
+    let mut tampered = file.clone();
+    tampered.sha256 = sha256_hex(b"fn main() {}\n");
+    let error = assert_err!(tampered.decode(payload));
+    assert_snapshot!(error, @"Checksum mismatch for `src/lib.rs`: expected 536e506bb90914c243a12b397b9a998f85ae2cbd9ba02dfd03a9e155ca5ca0f4, got 8f46e21779e9807d1c3d9828c172b1cac2f2b98924494a59d1512ca82ea42fb8");
+}
@@ -11,6 +11,8 @@ pg_enum! {
         Publish = 0,
         Yank = 1,
         Unyank = 2,
+        Promote = 3,
+        ApprovePromotion = 4,
     }
 }
 
@@ -20,6 +22,8 @@ impl From<VersionAction> for &'static str {
             VersionAction::Publish => "publish",
             VersionAction::Yank => "yank",
             VersionAction::Unyank => "unyank",
+            VersionAction::Promote => "promote",
+            VersionAction::ApprovePromotion => "approve_promotion",
         }
     }
 }
@@ -0,0 +1,76 @@
+use chrono::NaiveDate;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::schema::{advisories, version_advisories};
+
+/// A security advisory, imported from a RustSec-format advisory database.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = advisories, check_for_backend(diesel::pg::Pg))]
+pub struct Advisory {
+    pub id: String,
+    pub crate_name: String,
+    pub title: String,
+    pub description: String,
+    pub date: NaiveDate,
+    pub url: Option<String>,
+    pub aliases: Vec<String>,
+    pub informational: Option<String>,
+    pub withdrawn: Option<NaiveDate>,
+}
+
+impl Advisory {
+    /// Loads the advisories affecting any of the given versions, together
+    /// with the ID of the affected version.
+    ///
+    /// The advisories of each version are sorted by their ID.
+    pub async fn for_versions(
+        mut conn: &AsyncPgConnection,
+        version_ids: &[i32],
+    ) -> QueryResult<Vec<(i32, Advisory)>> {
+        if version_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        version_advisories::table
+            .inner_join(advisories::table)
+            .filter(version_advisories::version_id.eq_any(version_ids))
+            .order((version_advisories::version_id, advisories::id))
+            .select((version_advisories::version_id, Advisory::as_select()))
+            .load(&mut conn)
+            .await
+    }
+}
+
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = advisories, check_for_backend(diesel::pg::Pg))]
+pub struct NewAdvisory<'a> {
+    pub id: &'a str,
+    pub crate_name: &'a str,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub date: NaiveDate,
+    pub url: Option<&'a str>,
+    pub aliases: &'a [String],
+    pub informational: Option<&'a str>,
+    pub withdrawn: Option<NaiveDate>,
+    pub patched: Vec<String>,
+    pub unaffected: Vec<String>,
+}
+
+impl NewAdvisory<'_> {
+    /// Inserts the advisory, or updates it if an advisory with the same ID
+    /// was imported before.
+    pub async fn upsert(&self, mut conn: &AsyncPgConnection) -> QueryResult<()> {
+        diesel::insert_into(advisories::table)
+            .values(self)
+            .on_conflict(advisories::id)
+            .do_update()
+            .set((self, advisories::updated_at.eq(now)))
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use diesel::prelude::*;
 use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 use secrecy::SecretString;
 
+use crate::models::two_factor::has_two_factor;
 use crate::models::{CrateOwner, User};
 use crate::schema::{crate_owner_invitations, crates, users};
 
@@ -114,6 +115,18 @@ impl CrateOwnerInvitation {
             return Err(AcceptError::EmailNotVerified { crate_name });
         }
 
+        // The crate may have started to require two-factor authentication
+        // after the invitation was created.
+        let (crate_name, require_owner_2fa) = crates::table
+            .find(self.crate_id)
+            .select((crates::name, crates::require_owner_2fa))
+            .first::<(String, bool)>(conn)
+            .await?;
+
+        if require_owner_2fa && !has_two_factor(conn, user.id).await? {
+            return Err(AcceptError::TwoFactorRequired { crate_name });
+        }
+
         conn.transaction(async |conn| {
             CrateOwner::from_invite(&self).insert(conn).await?;
 
@@ -146,4 +159,6 @@ pub enum AcceptError {
     Expired { crate_name: String },
     #[error("Email verification required")]
     EmailNotVerified { crate_name: String },
+    #[error("Two-factor authentication required")]
+    TwoFactorRequired { crate_name: String },
 }
@@ -0,0 +1,103 @@
+use crate::models::{Crate, CrateOwner, OwnerKind, User};
+use crate::schema::{crate_owners, crate_readers, crates};
+use diesel::dsl::{self, exists};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::Integer;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+/// A user that is allowed to read an access-controlled crate in private
+/// registry mode.
+///
+/// Crates without any readers can be read by all authenticated users. Once a
+/// crate has at least one reader, it can only be read by its readers and by
+/// the users that own it. Members of team owners need to be added as readers
+/// explicitly, since team membership is not stored in the database.
+#[derive(Insertable, Queryable, Identifiable, Associations, Clone, Copy, Debug)]
+#[diesel(
+    table_name = crate_readers,
+    check_for_backend(diesel::pg::Pg),
+    primary_key(crate_id, user_id),
+    belongs_to(Crate),
+    belongs_to(User),
+)]
+pub struct CrateReader {
+    pub crate_id: i32,
+    pub user_id: i32,
+}
+
+impl CrateReader {
+    /// Returns whether the crate has any readers, which means that read
+    /// access to it is restricted.
+    pub async fn is_restricted(mut conn: &AsyncPgConnection, crate_id: i32) -> QueryResult<bool> {
+        diesel::select(exists(
+            crate_readers::table.filter(crate_readers::crate_id.eq(crate_id)),
+        ))
+        .get_result(&mut conn)
+        .await
+    }
+
+    /// Returns whether the crate with the given name has any readers.
+    ///
+    /// Returns `false` if the crate does not exist.
+    pub async fn is_restricted_by_name(
+        mut conn: &AsyncPgConnection,
+        crate_name: &str,
+    ) -> QueryResult<bool> {
+        diesel::select(exists(
+            crate_readers::table
+                .inner_join(crates::table)
+                .filter(crates::name.eq(crate_name)),
+        ))
+        .get_result(&mut conn)
+        .await
+    }
+
+    /// Returns the IDs of all crates with restricted read access.
+    #[dsl::auto_type(no_type_alias)]
+    pub fn restricted_crate_ids() -> _ {
+        crate_readers::table.select(crate_readers::crate_id)
+    }
+
+    /// Returns the IDs of the crates with restricted read access that the
+    /// user is not allowed to read.
+    pub fn hidden_crate_ids(user_id: i32) -> crate_readers::BoxedQuery<'static, Pg, Integer> {
+        let readers = diesel::alias!(crate_readers as readers);
+        let readable = readers
+            .select(readers.field(crate_readers::crate_id))
+            .filter(readers.field(crate_readers::user_id).eq(user_id));
+
+        let owned = CrateOwner::by_owner_kind(OwnerKind::User)
+            .select(crate_owners::crate_id)
+            .filter(crate_owners::owner_id.eq(user_id));
+
+        crate_readers::table
+            .select(crate_readers::crate_id)
+            .filter(crate_readers::crate_id.ne_all(readable))
+            .filter(crate_readers::crate_id.ne_all(owned))
+            .into_boxed()
+    }
+
+    /// Returns whether the user is allowed to read the crate.
+    pub async fn can_read(
+        mut conn: &AsyncPgConnection,
+        crate_id: i32,
+        user_id: i32,
+    ) -> QueryResult<bool> {
+        if !Self::is_restricted(conn, crate_id).await? {
+            return Ok(true);
+        }
+
+        let is_reader = crate_readers::table
+            .filter(crate_readers::crate_id.eq(crate_id))
+            .filter(crate_readers::user_id.eq(user_id));
+
+        let is_owner = CrateOwner::by_owner_kind(OwnerKind::User)
+            .filter(crate_owners::crate_id.eq(crate_id))
+            .filter(crate_owners::owner_id.eq(user_id));
+
+        diesel::select(exists(is_reader).or(exists(is_owner)))
+            .get_result(&mut conn)
+            .await
+    }
+}
@@ -56,6 +56,8 @@ impl Ord for Version {
 /// 2. The highest non-yanked version.
 /// 3. The highest version.
 ///
+/// Staged versions are not taken into account until they have been promoted.
+///
 /// The default version is then written to the `default_versions` table.
 #[instrument(skip(conn))]
 pub async fn update_default_version(
@@ -124,6 +126,7 @@ async fn calculate_default_version(
     debug!("Loading default version for the crate…");
     Version::query()
         .filter(versions::crate_id.eq(crate_id))
+        .filter(versions::staged_at.is_null())
         .order((
             // 1. Non-yanked first
             versions::yanked,
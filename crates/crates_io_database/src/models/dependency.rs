@@ -1,4 +1,4 @@
-use crate::models::{Crate, Version};
+use crate::models::{Crate, CrateReader, Version};
 use crate::pg_enum;
 use crate::schema::*;
 use crates_io_index::DependencyKind as IndexDependencyKind;
@@ -43,15 +43,29 @@ pub struct ReverseDependency {
 impl ReverseDependency {
     /// Returns a page of reverse dependencies, ordered by the dependent crate's
     /// total downloads.
+    ///
+    /// If `reader_id` is set, dependent crates that the user is not allowed to
+    /// read are left out.
     #[instrument(skip_all, fields(crate_id))]
     pub async fn page_for_crate(
         crate_id: i32,
+        reader_id: Option<i32>,
         mut conn: &AsyncPgConnection,
         offset: i64,
         limit: i64,
     ) -> QueryResult<Vec<Self>> {
-        Self::query()
+        let mut query = Self::query()
             .filter(reverse_dependencies::target_crate_id.eq(crate_id))
+            .into_boxed();
+
+        if let Some(user_id) = reader_id {
+            query = query.filter(
+                reverse_dependencies::dependent_crate_id
+                    .ne_all(CrateReader::hidden_crate_ids(user_id)),
+            );
+        }
+
+        query
             .order((
                 reverse_dependencies::dependent_downloads.desc(),
                 reverse_dependencies::dependent_crate_id.desc(),
@@ -63,13 +77,27 @@ impl ReverseDependency {
     }
 
     /// Returns the total number of reverse dependencies for the crate.
+    ///
+    /// If `reader_id` is set, dependent crates that the user is not allowed to
+    /// read are not counted.
     #[instrument(skip_all, fields(crate_id))]
-    pub async fn count_for_crate(crate_id: i32, mut conn: &AsyncPgConnection) -> QueryResult<i64> {
-        reverse_dependencies::table
+    pub async fn count_for_crate(
+        crate_id: i32,
+        reader_id: Option<i32>,
+        mut conn: &AsyncPgConnection,
+    ) -> QueryResult<i64> {
+        let mut query = reverse_dependencies::table
             .filter(reverse_dependencies::target_crate_id.eq(crate_id))
-            .count()
-            .get_result(&mut conn)
-            .await
+            .into_boxed();
+
+        if let Some(user_id) = reader_id {
+            query = query.filter(
+                reverse_dependencies::dependent_crate_id
+                    .ne_all(CrateReader::hidden_crate_ids(user_id)),
+            );
+        }
+
+        query.count().get_result(&mut conn).await
     }
 }
 
@@ -1,6 +1,7 @@
 use crate::SemverVersion;
 use crate::models::Version as FullVersion;
-use crate::schema::{version_downloads, versions};
+use crate::pg_enum;
+use crate::schema::{cargo_version_downloads, client_downloads, version_downloads, versions};
 use chrono::NaiveDate;
 use diesel::prelude::*;
 
@@ -16,6 +17,35 @@ pub struct VersionDownload {
     pub downloads: i32,
 }
 
+/// The number of downloads of a crate on a given day by a `major.minor`
+/// version of cargo.
+#[derive(HasQuery, Debug, Clone)]
+#[diesel(table_name = cargo_version_downloads)]
+pub struct CargoVersionDownload {
+    pub date: NaiveDate,
+    pub cargo_version: String,
+    pub downloads: i32,
+}
+
+pg_enum! {
+    /// The class of client that downloaded a crate file.
+    pub enum DownloadClient {
+        Cargo = 0,
+        Ci = 1,
+        Mirror = 2,
+        Other = 3,
+    }
+}
+
+/// The number of downloads of a crate on a given day by a class of client.
+#[derive(HasQuery, Debug, Clone, Copy)]
+#[diesel(table_name = client_downloads)]
+pub struct ClientDownload {
+    pub date: NaiveDate,
+    pub client: DownloadClient,
+    pub downloads: i32,
+}
+
 /// A subset of the columns of the `versions` table.
 ///
 /// This struct is used to load all versions of a crate from the database,
@@ -35,6 +35,13 @@ pub struct Crate {
     pub max_upload_size: Option<i32>,
     pub max_features: Option<i16>,
     pub trustpub_only: bool,
+    pub require_promotion_review: bool,
+    pub require_owner_2fa: bool,
+    /// The time when the owners marked this crate as deprecated.
+    pub deprecated_at: Option<DateTime<Utc>>,
+    pub deprecation_message: Option<String>,
+    /// The name of the crate that the owners recommend using instead.
+    pub successor: Option<String>,
 }
 
 /// We literally never want to select `textsearchable_index_col`
@@ -51,6 +58,11 @@ type AllColumns = (
     crates::max_upload_size,
     crates::max_features,
     crates::trustpub_only,
+    crates::require_promotion_review,
+    crates::require_owner_2fa,
+    crates::deprecated_at,
+    crates::deprecation_message,
+    crates::successor,
 );
 
 pub const ALL_COLUMNS: AllColumns = (
@@ -65,6 +77,11 @@ pub const ALL_COLUMNS: AllColumns = (
     crates::max_upload_size,
     crates::max_features,
     crates::trustpub_only,
+    crates::require_promotion_review,
+    crates::require_owner_2fa,
+    crates::deprecated_at,
+    crates::deprecation_message,
+    crates::successor,
 );
 
 type All = diesel::dsl::Select<crates::table, diesel::dsl::AsSelect<Crate, diesel::pg::Pg>>;
@@ -193,11 +210,12 @@ impl Crate {
 
     /// Return both the newest (most recently updated) and
     /// highest version (in semver order) for the current crate,
-    /// where all top versions are not yanked.
+    /// where all top versions are neither yanked nor staged.
     pub async fn top_versions(&self, mut conn: &AsyncPgConnection) -> QueryResult<TopVersions> {
         Ok(TopVersions::from_date_version_pairs(
             Version::belonging_to(self)
                 .filter(versions::yanked.eq(false))
+                .filter(versions::staged_at.is_null())
                 .select((versions::created_at, versions::num))
                 .load(&mut conn)
                 .await?,
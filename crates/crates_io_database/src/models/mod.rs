@@ -1,4 +1,5 @@
 pub use self::action::{NewVersionOwnerAction, VersionAction, VersionOwnerAction};
+pub use self::advisory::{Advisory, NewAdvisory};
 pub use self::category::{Category, CrateCategory, NewCategory};
 pub use self::cloudfront_invalidation_queue::{
     CloudFrontDistribution, CloudFrontInvalidationQueueItem,
@@ -6,6 +7,7 @@ pub use self::cloudfront_invalidation_queue::{
 pub use self::crate_owner_invitation::{
     CrateOwnerInvitation, NewCrateOwnerInvitation, NewCrateOwnerInvitationOutcome,
 };
+pub use self::crate_reader::CrateReader;
 pub use self::default_versions::{update_default_version, verify_default_version};
 pub use self::deleted_crate::NewDeletedCrate;
 pub use self::dependency::{Dependency, DependencyKind, ReverseDependency};
@@ -14,19 +16,32 @@ pub use self::email::{Email, NewEmail};
 pub use self::follow::Follow;
 pub use self::keyword::{CrateKeyword, Keyword};
 pub use self::krate::{Crate, CrateName, NewCrate};
+pub use self::notification_preference::{
+    NewNotificationPreference, NotificationCategory, NotificationPreference, NotificationSettings,
+    PublishDelivery,
+};
 pub use self::owner::{CrateOwner, Owner, OwnerKind};
+pub use self::scan_finding::{NewScanFinding, ScanFinding};
 pub use self::team::{NewTeam, Team};
 pub use self::token::ApiToken;
 pub use self::trustpub::TrustpubData;
-pub use self::user::{NewOauthGithub, NewUser, OauthGithub, PublicUser, User};
+pub use self::two_factor::{
+    NewTotpCredential, NewWebauthnCredential, TotpCredential, WebauthnCredential,
+};
+pub use self::user::{
+    LinkedIdentity, NewLinkedIdentity, NewOauthGithub, NewUser, OauthGithub, PublicUser, User,
+};
 pub use self::version::{NewVersion, TopVersions, Version};
+pub use self::webhook::{NewWebhook, NewWebhookDelivery, Webhook, WebhookDelivery, WebhookEvent};
 
 pub mod helpers;
 
 mod action;
+mod advisory;
 pub mod category;
 mod cloudfront_invalidation_queue;
 pub mod crate_owner_invitation;
+mod crate_reader;
 pub mod default_versions;
 mod deleted_crate;
 pub mod dependency;
@@ -35,10 +50,14 @@ mod email;
 mod follow;
 mod keyword;
 pub mod krate;
+mod notification_preference;
 mod owner;
+pub mod scan_finding;
 pub mod team;
 pub mod token;
 pub mod trustpub;
+pub mod two_factor;
 pub mod user;
 pub mod version;
 pub mod versions_published_by;
+mod webhook;
@@ -0,0 +1,283 @@
+use crate::models::{Crate, User};
+use crate::schema::{notification_preferences, notification_settings, users};
+use bon::Builder;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use rand::distr::{Alphanumeric, SampleString};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+const UNSUBSCRIBE_TOKEN_LENGTH: usize = 32;
+
+/// The categories of emails that users can opt out of.
+///
+/// Security-relevant emails, e.g. about exposed API tokens, ownership
+/// changes or Trusted Publishing configuration changes, are not part of any
+/// category and are always sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    /// A new version of a crate owned by the user was published.
+    Publish,
+    /// The user was invited to become an owner of a crate.
+    OwnerInvite,
+    /// An API token of the user is about to expire.
+    TokenExpiry,
+}
+
+impl NotificationCategory {
+    pub const ALL: [Self; 3] = [Self::Publish, Self::OwnerInvite, Self::TokenExpiry];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Publish => "publish",
+            Self::OwnerInvite => "owner_invite",
+            Self::TokenExpiry => "token_expiry",
+        }
+    }
+}
+
+impl fmt::Display for NotificationCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NotificationCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.as_str() == s)
+            .ok_or_else(|| format!("unknown notification category `{s}`"))
+    }
+}
+
+/// How publish notifications are delivered to a user.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishDelivery {
+    /// Every publish notification is sent as a separate email.
+    #[default]
+    Immediate,
+    /// Publish notifications are batched into a daily digest email.
+    Daily,
+    /// Publish notifications are batched into a weekly digest email.
+    Weekly,
+}
+
+impl PublishDelivery {
+    pub const ALL: [Self; 3] = [Self::Immediate, Self::Daily, Self::Weekly];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+}
+
+impl fmt::Display for PublishDelivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PublishDelivery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|delivery| delivery.as_str() == s)
+            .ok_or_else(|| format!("unknown publish delivery `{s}`"))
+    }
+}
+
+/// An opt-in or opt-out of a user for a category of emails.
+///
+/// Preferences with a `crate_id` only apply to the emails about that crate,
+/// and take precedence over the preference for the whole category. Without
+/// any preference, emails are sent. The preference for publish notifications
+/// of all crates is stored in `users.publish_notifications` instead.
+#[derive(Debug, Clone, Identifiable, Associations, HasQuery)]
+#[diesel(table_name = notification_preferences, belongs_to(User), belongs_to(Crate))]
+pub struct NotificationPreference {
+    pub id: i32,
+    pub user_id: i32,
+    pub category: String,
+    pub crate_id: Option<i32>,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationPreference {
+    /// Returns whether emails of the category, optionally about a specific
+    /// crate, should be sent to the user.
+    pub async fn is_enabled(
+        mut conn: &AsyncPgConnection,
+        user_id: i32,
+        category: NotificationCategory,
+        crate_id: Option<i32>,
+    ) -> QueryResult<bool> {
+        let preferences = notification_preferences::table
+            .filter(notification_preferences::user_id.eq(user_id))
+            .filter(notification_preferences::category.eq(category.as_str()))
+            .filter(
+                notification_preferences::crate_id
+                    .is_null()
+                    .or(notification_preferences::crate_id.eq(crate_id)),
+            )
+            .select((
+                notification_preferences::crate_id,
+                notification_preferences::enabled,
+            ))
+            .load::<(Option<i32>, bool)>(&mut conn)
+            .await?;
+
+        let crate_preference = preferences.iter().find(|(crate_id, _)| crate_id.is_some());
+        let category_preference = preferences.iter().find(|(crate_id, _)| crate_id.is_none());
+        if let Some((_, enabled)) = crate_preference.or(category_preference) {
+            return Ok(*enabled);
+        }
+
+        if category == NotificationCategory::Publish {
+            return users::table
+                .find(user_id)
+                .select(users::publish_notifications)
+                .get_result(&mut conn)
+                .await;
+        }
+
+        Ok(true)
+    }
+}
+
+#[derive(Insertable, Debug, Builder)]
+#[diesel(table_name = notification_preferences, check_for_backend(diesel::pg::Pg))]
+pub struct NewNotificationPreference<'a> {
+    pub user_id: i32,
+    pub category: &'a str,
+    pub crate_id: Option<i32>,
+    pub enabled: bool,
+}
+
+impl NewNotificationPreference<'_> {
+    /// Inserts the preference, or updates an existing preference of the user
+    /// for the same category and crate.
+    pub async fn upsert(&self, mut conn: &AsyncPgConnection) -> QueryResult<()> {
+        diesel::insert_into(notification_preferences::table)
+            .values(self)
+            .on_conflict((
+                notification_preferences::user_id,
+                notification_preferences::category,
+                notification_preferences::crate_id,
+            ))
+            .do_update()
+            .set((
+                notification_preferences::enabled.eq(excluded(notification_preferences::enabled)),
+                notification_preferences::updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// The email notification settings of a user.
+///
+/// The row is created on demand, users without a row use the defaults.
+#[derive(Debug, Clone, Identifiable, Associations, HasQuery)]
+#[diesel(table_name = notification_settings, primary_key(user_id), belongs_to(User))]
+pub struct NotificationSettings {
+    pub user_id: i32,
+    pub publish_delivery: String,
+    pub unsubscribe_token: String,
+}
+
+impl NotificationSettings {
+    /// Returns how publish notifications are delivered to the user.
+    pub async fn publish_delivery(
+        mut conn: &AsyncPgConnection,
+        user_id: i32,
+    ) -> QueryResult<PublishDelivery> {
+        let delivery = notification_settings::table
+            .find(user_id)
+            .select(notification_settings::publish_delivery)
+            .get_result::<String>(&mut conn)
+            .await
+            .optional()?;
+
+        Ok(delivery
+            .and_then(|delivery| delivery.parse().ok())
+            .unwrap_or_default())
+    }
+
+    /// Changes how publish notifications are delivered to the user.
+    pub async fn set_publish_delivery(
+        mut conn: &AsyncPgConnection,
+        user_id: i32,
+        delivery: PublishDelivery,
+    ) -> QueryResult<()> {
+        diesel::insert_into(notification_settings::table)
+            .values((
+                notification_settings::user_id.eq(user_id),
+                notification_settings::publish_delivery.eq(delivery.as_str()),
+                notification_settings::unsubscribe_token.eq(generate_unsubscribe_token()),
+            ))
+            .on_conflict(notification_settings::user_id)
+            .do_update()
+            .set(notification_settings::publish_delivery.eq(delivery.as_str()))
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the token of the one-click unsubscribe links in the emails
+    /// sent to the user, generating it if the user doesn't have one yet.
+    pub async fn unsubscribe_token(
+        mut conn: &AsyncPgConnection,
+        user_id: i32,
+    ) -> QueryResult<String> {
+        diesel::insert_into(notification_settings::table)
+            .values((
+                notification_settings::user_id.eq(user_id),
+                notification_settings::unsubscribe_token.eq(generate_unsubscribe_token()),
+            ))
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .await?;
+
+        notification_settings::table
+            .find(user_id)
+            .select(notification_settings::unsubscribe_token)
+            .get_result(&mut conn)
+            .await
+    }
+
+    /// Returns the ID of the user with the given unsubscribe token.
+    pub async fn find_user_by_unsubscribe_token(
+        mut conn: &AsyncPgConnection,
+        token: &str,
+    ) -> QueryResult<Option<i32>> {
+        notification_settings::table
+            .filter(notification_settings::unsubscribe_token.eq(token))
+            .select(notification_settings::user_id)
+            .get_result(&mut conn)
+            .await
+            .optional()
+    }
+}
+
+fn generate_unsubscribe_token() -> String {
+    Alphanumeric.sample_string(&mut rand::rng(), UNSUBSCRIBE_TOKEN_LENGTH)
+}
@@ -0,0 +1,47 @@
+use crate::schema::version_scan_findings;
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+/// A finding of the content scanner for one of the files of a version.
+#[derive(Debug, Identifiable, HasQuery)]
+#[diesel(table_name = version_scan_findings)]
+pub struct ScanFinding {
+    pub id: i64,
+    pub version_id: i32,
+    pub kind: String,
+    pub path: String,
+    pub line: Option<i32>,
+    pub description: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = version_scan_findings, check_for_backend(diesel::pg::Pg))]
+pub struct NewScanFinding<'a> {
+    pub version_id: i32,
+    pub kind: &'a str,
+    pub path: &'a str,
+    pub line: Option<i32>,
+    pub description: &'a str,
+}
+
+/// Replaces the findings of a version, so that re-scanning a version does
+/// not result in duplicate findings.
+pub async fn replace(
+    version_id: i32,
+    findings: &[NewScanFinding<'_>],
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<()> {
+    conn.transaction(async |conn| {
+        let query =
+            version_scan_findings::table.filter(version_scan_findings::version_id.eq(version_id));
+        diesel::delete(query).execute(conn).await?;
+
+        diesel::insert_into(version_scan_findings::table)
+            .values(findings)
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
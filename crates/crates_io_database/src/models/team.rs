@@ -5,49 +5,64 @@ use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use crate::models::{Crate, CrateOwner, OwnerKind};
 use crate::schema::{crate_owners, teams};
 
-/// For now, just a GitHub Team. Can be upgraded to other teams
-/// later if desirable.
+/// A GitHub team or a GitLab group that can own crates.
 #[derive(HasQuery, Identifiable, serde::Serialize, serde::Deserialize, Debug)]
 pub struct Team {
     /// Unique table id
     pub id: i32,
-    /// "github:org:team"
-    /// An opaque unique ID, that was at one point parsed out to query GitHub.
-    /// We only query membership with github using the `github_id`, though.
+    /// "github:org:team" or "gitlab:group/subgroup"
+    /// An opaque unique ID, that was at one point parsed out to query GitHub
+    /// or GitLab. We only query membership using the `github_id` or
+    /// `gitlab_id`, though.
     /// This is the only name we should ever talk to Cargo about.
     pub login: String,
     /// The GitHub API works on team ID numbers. This can change, if a team
     /// is deleted and then recreated with the same name!!!
-    pub github_id: i32,
+    pub github_id: Option<i32>,
+    /// The GitLab API works on group ID numbers. Like GitHub team IDs, these
+    /// change if a group is deleted and then recreated with the same path.
+    pub gitlab_id: Option<i64>,
     /// Sugary goodness
     pub name: Option<String>,
     pub avatar: Option<String>,
     /// The GitHub Organization ID this team sits under
-    pub org_id: i32,
+    pub org_id: Option<i32>,
 }
 
 #[derive(Insertable, AsChangeset, Debug, Builder)]
 #[diesel(table_name = teams, check_for_backend(diesel::pg::Pg))]
 pub struct NewTeam<'a> {
     pub login: &'a str,
-    pub github_id: i32,
+    pub github_id: Option<i32>,
+    pub gitlab_id: Option<i64>,
     pub name: Option<&'a str>,
     pub avatar: Option<&'a str>,
-    pub org_id: i32,
+    pub org_id: Option<i32>,
 }
 
 impl NewTeam<'_> {
     pub async fn create_or_update(&self, mut conn: &AsyncPgConnection) -> QueryResult<Team> {
         use diesel::insert_into;
 
-        insert_into(teams::table)
-            .values(self)
-            .on_conflict(teams::github_id)
-            .do_update()
-            .set(self)
-            .returning(Team::as_returning())
-            .get_result(&mut conn)
-            .await
+        let query = insert_into(teams::table).values(self);
+
+        if self.gitlab_id.is_some() {
+            query
+                .on_conflict(teams::gitlab_id)
+                .do_update()
+                .set(self)
+                .returning(Team::as_returning())
+                .get_result(&mut conn)
+                .await
+        } else {
+            query
+                .on_conflict(teams::github_id)
+                .do_update()
+                .set(self)
+                .returning(Team::as_returning())
+                .get_result(&mut conn)
+                .await
+        }
     }
 }
 
@@ -63,18 +78,30 @@ impl Team {
 
     /// Splits the login into provider, organization, and team name.
     ///
-    /// Returns `None` if the login format is invalid.
+    /// Returns `None` if the login format is invalid. GitLab group logins
+    /// don't have a team name, see [`Team::gitlab_group_path()`] instead.
     pub fn split_login(&self) -> Option<(&str, &str, &str)> {
         let (provider, rest) = self.login.split_once(':')?;
         let (org, team) = rest.split_once(':')?;
         Some((provider, org, team))
     }
 
+    /// Returns the full path of the GitLab group (e.g. `group/subgroup`).
+    ///
+    /// Returns `None` for GitHub teams or invalid login formats.
+    pub fn gitlab_group_path(&self) -> Option<&str> {
+        let path = self.login.strip_prefix("gitlab:")?;
+        (!path.is_empty() && !path.contains(':')).then_some(path)
+    }
+
     /// Returns the URL for the team.
     ///
-    /// Currently only supports GitHub teams. Returns `None` for other providers
-    /// or invalid login formats.
+    /// Returns `None` for unknown providers or invalid login formats.
     pub fn url(&self) -> Option<String> {
+        if let Some(path) = self.gitlab_group_path() {
+            return Some(format!("https://gitlab.com/{path}"));
+        }
+
         let (provider, org, _team) = self.split_login()?;
         match provider {
             "github" => Some(format!("https://github.com/{org}")),
@@ -91,10 +118,11 @@ mod tests {
         Team {
             id: 1,
             login: login.to_string(),
-            github_id: 1000,
+            github_id: Some(1000),
+            gitlab_id: None,
             name: None,
             avatar: None,
-            org_id: 2000,
+            org_id: Some(2000),
         }
     }
 
@@ -156,6 +184,13 @@ mod tests {
             assert_eq!(team.url(), None);
         }
 
+        #[test]
+        fn gitlab_group() {
+            let team = new_team("gitlab:my-group/my-subgroup");
+            let expected = "https://gitlab.com/my-group/my-subgroup";
+            assert_eq!(team.url(), Some(expected.to_string()));
+        }
+
         #[test]
         fn invalid_format_returns_none() {
             let team = new_team("github:rust-lang");
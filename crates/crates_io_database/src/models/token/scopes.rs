@@ -16,6 +16,9 @@ pub enum EndpointScope {
     TrustedPublishing,
     Yank,
     ChangeOwners,
+    UpdateCrate,
+    UpdateVersion,
+    RebuildDocs,
 }
 
 impl From<&EndpointScope> for &[u8] {
@@ -26,6 +29,9 @@ impl From<&EndpointScope> for &[u8] {
             EndpointScope::TrustedPublishing => b"trusted-publishing",
             EndpointScope::Yank => b"yank",
             EndpointScope::ChangeOwners => b"change-owners",
+            EndpointScope::UpdateCrate => b"update-crate",
+            EndpointScope::UpdateVersion => b"update-version",
+            EndpointScope::RebuildDocs => b"rebuild-docs",
         }
     }
 }
@@ -47,6 +53,9 @@ impl TryFrom<&[u8]> for EndpointScope {
             b"trusted-publishing" => Ok(EndpointScope::TrustedPublishing),
             b"yank" => Ok(EndpointScope::Yank),
             b"change-owners" => Ok(EndpointScope::ChangeOwners),
+            b"update-crate" => Ok(EndpointScope::UpdateCrate),
+            b"update-version" => Ok(EndpointScope::UpdateVersion),
+            b"rebuild-docs" => Ok(EndpointScope::RebuildDocs),
             _ => Err("Unrecognized enum variant".to_string()),
         }
     }
@@ -143,7 +152,10 @@ mod tests {
         assert(EndpointScope::ChangeOwners, "\"change-owners\"");
         assert(EndpointScope::PublishNew, "\"publish-new\"");
         assert(EndpointScope::PublishUpdate, "\"publish-update\"");
+        assert(EndpointScope::RebuildDocs, "\"rebuild-docs\"");
         assert(EndpointScope::TrustedPublishing, "\"trusted-publishing\"");
+        assert(EndpointScope::UpdateCrate, "\"update-crate\"");
+        assert(EndpointScope::UpdateVersion, "\"update-version\"");
         assert(EndpointScope::Yank, "\"yank\"");
     }
 
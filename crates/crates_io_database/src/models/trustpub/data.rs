@@ -20,6 +20,9 @@ pub enum TrustpubData {
         run_id: String,
         /// SHA of the commit
         sha: String,
+        /// Workflow ref (e.g. "octo-org/octo-repo/.github/workflows/release.yml@refs/heads/main")
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        workflow_ref: Option<String>,
     },
     #[serde(rename = "gitlab")]
     GitLab {
@@ -29,6 +32,21 @@ pub enum TrustpubData {
         job_id: String,
         /// SHA of the commit
         sha: String,
+        /// CI config ref URI (e.g. "gitlab.com/rust-lang/cargo//.gitlab-ci.yml@refs/heads/main")
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ci_config_ref_uri: Option<String>,
+    },
+    /// A generic OIDC provider (see `crates_io_trustpub::provider`)
+    #[serde(rename = "oidc")]
+    Oidc {
+        /// Issuer URL of the OIDC provider (e.g. `https://agent.buildkite.com`)
+        issuer: String,
+        /// Provider-specific repository identifier (e.g. "acme-inc/my-pipeline")
+        repository: String,
+        /// CI job or run ID
+        run_id: String,
+        /// SHA of the commit
+        sha: String,
     },
 }
 
@@ -57,6 +75,7 @@ mod tests {
             repository: "octo-org/octo-repo".to_string(),
             run_id: "example-run-id".to_string(),
             sha: "example-sha".to_string(),
+            workflow_ref: None,
         };
 
         assert_json_snapshot!(data, @r#"
@@ -75,6 +94,7 @@ mod tests {
             project_path: "rust-lang/cargo".to_string(),
             job_id: "example-job-id".to_string(),
             sha: "example-sha".to_string(),
+            ci_config_ref_uri: None,
         };
 
         assert_json_snapshot!(data, @r#"
@@ -86,4 +106,46 @@ mod tests {
         }
         "#);
     }
+
+    #[test]
+    fn test_github_serialization_with_workflow_ref() {
+        let data = TrustpubData::GitHub {
+            repository: "octo-org/octo-repo".to_string(),
+            run_id: "example-run-id".to_string(),
+            sha: "example-sha".to_string(),
+            workflow_ref: Some(
+                "octo-org/octo-repo/.github/workflows/release.yml@refs/heads/main".to_string(),
+            ),
+        };
+
+        assert_json_snapshot!(data, @r#"
+        {
+          "provider": "github",
+          "repository": "octo-org/octo-repo",
+          "run_id": "example-run-id",
+          "sha": "example-sha",
+          "workflow_ref": "octo-org/octo-repo/.github/workflows/release.yml@refs/heads/main"
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_oidc_serialization() {
+        let data = TrustpubData::Oidc {
+            issuer: "https://agent.buildkite.com".to_string(),
+            repository: "acme-inc/my-pipeline".to_string(),
+            run_id: "example-job-id".to_string(),
+            sha: "example-sha".to_string(),
+        };
+
+        assert_json_snapshot!(data, @r#"
+        {
+          "provider": "oidc",
+          "issuer": "https://agent.buildkite.com",
+          "repository": "acme-inc/my-pipeline",
+          "run_id": "example-job-id",
+          "sha": "example-sha"
+        }
+        "#);
+    }
 }
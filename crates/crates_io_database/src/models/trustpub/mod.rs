@@ -1,11 +1,15 @@
 mod data;
 mod github_config;
 mod gitlab_config;
+mod oidc_config;
+mod provenance;
 mod token;
 mod used_jti;
 
 pub use self::data::TrustpubData;
 pub use self::github_config::{GitHubConfig, NewGitHubConfig};
 pub use self::gitlab_config::{GitLabConfig, NewGitLabConfig};
+pub use self::oidc_config::{NewOidcConfig, OidcConfig};
+pub use self::provenance::{NewProvenance, Provenance};
 pub use self::token::NewToken;
 pub use self::used_jti::NewUsedJti;
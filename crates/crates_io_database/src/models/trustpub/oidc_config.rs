@@ -0,0 +1,123 @@
+use crate::schema::trustpub_configs_oidc;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Trusted Publishing configuration for a generic OIDC provider.
+///
+/// See `crates_io_trustpub::provider` for the list of supported providers.
+#[derive(Debug, Identifiable, HasQuery, Serialize)]
+#[diesel(table_name = trustpub_configs_oidc)]
+pub struct OidcConfig {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub crate_id: i32,
+    pub provider: String,
+    pub repository: String,
+    pub repository_owner_id: Option<String>,
+    pub claims: serde_json::Value,
+}
+
+impl OidcConfig {
+    pub async fn count_for_crate(mut conn: &AsyncPgConnection, crate_id: i32) -> QueryResult<i64> {
+        trustpub_configs_oidc::table
+            .filter(trustpub_configs_oidc::crate_id.eq(crate_id))
+            .count()
+            .get_result(&mut conn)
+            .await
+    }
+
+    /// Returns the additional claims that a JWT has to match.
+    ///
+    /// Fails if the `claims` column does not contain a JSON object of strings.
+    pub fn claim_restrictions(&self) -> serde_json::Result<BTreeMap<String, String>> {
+        serde_json::from_value(self.claims.clone())
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = trustpub_configs_oidc, check_for_backend(diesel::pg::Pg))]
+pub struct NewOidcConfig<'a> {
+    pub crate_id: i32,
+    pub provider: &'a str,
+    pub repository: &'a str,
+    pub claims: serde_json::Value,
+}
+
+impl NewOidcConfig<'_> {
+    pub async fn insert(&self, mut conn: &AsyncPgConnection) -> QueryResult<OidcConfig> {
+        self.insert_into(trustpub_configs_oidc::table)
+            .returning(OidcConfig::as_returning())
+            .get_result(&mut conn)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::krate::*;
+    use crate::schema::crates;
+    use crates_io_test_db::TestDatabase;
+    use diesel_async::RunQueryDsl;
+    use insta::assert_debug_snapshot;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_oidc_config_insert_and_retrieve() {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.async_connect().await;
+
+        let test_crate = diesel::insert_into(crates::table)
+            .values((crates::name.eq("test-crate"),))
+            .returning(Crate::as_returning())
+            .get_result(&mut conn)
+            .await
+            .unwrap();
+
+        let new_config = NewOidcConfig {
+            crate_id: test_crate.id,
+            provider: "buildkite",
+            repository: "acme-inc/my-pipeline",
+            claims: json!({ "step_key": "publish" }),
+        };
+
+        let inserted_config = new_config.insert(&conn).await.unwrap();
+
+        let retrieved_config = OidcConfig::query()
+            .filter(trustpub_configs_oidc::id.eq(inserted_config.id))
+            .first(&mut conn)
+            .await
+            .unwrap();
+
+        insta::with_settings!({ filters => vec![(r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?Z", "[datetime]")] }, {
+            assert_debug_snapshot!(retrieved_config, @r#"
+            OidcConfig {
+                id: 1,
+                created_at: [datetime],
+                crate_id: 1,
+                provider: "buildkite",
+                repository: "acme-inc/my-pipeline",
+                repository_owner_id: None,
+                claims: Object {
+                    "step_key": String("publish"),
+                },
+            }
+            "#);
+        });
+
+        assert_debug_snapshot!(retrieved_config.claim_restrictions().unwrap(), @r#"
+        {
+            "step_key": "publish",
+        }
+        "#);
+
+        let malformed_config = OidcConfig {
+            claims: json!({ "step_key": 1 }),
+            ..retrieved_config
+        };
+        assert!(malformed_config.claim_restrictions().is_err());
+    }
+}
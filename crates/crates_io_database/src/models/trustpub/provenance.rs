@@ -0,0 +1,33 @@
+use crate::schema::trustpub_provenance;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+/// Signed provenance attestation of a version that was published via
+/// Trusted Publishing.
+#[derive(Debug, Identifiable, HasQuery)]
+#[diesel(table_name = trustpub_provenance, primary_key(version_id))]
+pub struct Provenance {
+    pub version_id: i32,
+    pub created_at: DateTime<Utc>,
+    pub key_id: String,
+    pub envelope: serde_json::Value,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = trustpub_provenance, check_for_backend(diesel::pg::Pg))]
+pub struct NewProvenance<'a> {
+    pub version_id: i32,
+    pub key_id: &'a str,
+    pub envelope: serde_json::Value,
+}
+
+impl NewProvenance<'_> {
+    pub async fn insert(&self, mut conn: &AsyncPgConnection) -> QueryResult<()> {
+        self.insert_into(trustpub_provenance::table)
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+use bon::Builder;
+use chrono::{DateTime, Utc};
+use diesel::dsl::{exists, select};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::models::User;
+use crate::schema::{recovery_codes, totp_credentials, webauthn_credentials};
+
+/// An authenticator app of a user, which generates time-based one-time
+/// passwords.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations)]
+#[diesel(
+    table_name = totp_credentials,
+    check_for_backend(diesel::pg::Pg),
+    primary_key(user_id),
+    belongs_to(User),
+)]
+pub struct TotpCredential {
+    pub user_id: i32,
+    /// The shared secret, encrypted at rest in our database.
+    pub encrypted_secret: Vec<u8>,
+    /// The time when the setup was confirmed with a valid code, or `None`
+    /// if the setup is still pending.
+    pub confirmed_at: Option<DateTime<Utc>>,
+    /// The time step of the last accepted code.
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TotpCredential {
+    /// Loads the authenticator app of a user, including pending setups.
+    pub async fn find(mut conn: &AsyncPgConnection, user_id: i32) -> QueryResult<Option<Self>> {
+        totp_credentials::table
+            .find(user_id)
+            .select(TotpCredential::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+}
+
+/// Represents a new, pending setup of an authenticator app.
+#[derive(Insertable, Debug, Builder)]
+#[diesel(table_name = totp_credentials, check_for_backend(diesel::pg::Pg))]
+pub struct NewTotpCredential<'a> {
+    pub user_id: i32,
+    pub encrypted_secret: &'a [u8],
+}
+
+impl NewTotpCredential<'_> {
+    /// Inserts the pending setup, replacing any other pending setup of the
+    /// user.
+    ///
+    /// Confirmed authenticator apps are never replaced. `false` is returned
+    /// if the user already has one.
+    pub async fn insert_pending(&self, mut conn: &AsyncPgConnection) -> QueryResult<bool> {
+        let pending = totp_credentials::table
+            .find(self.user_id)
+            .filter(totp_credentials::confirmed_at.is_null());
+
+        diesel::delete(pending).execute(&mut conn).await?;
+
+        let inserted = diesel::insert_into(totp_credentials::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .await?;
+
+        Ok(inserted > 0)
+    }
+}
+
+/// A security key or passkey of a user.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations)]
+#[diesel(
+    table_name = webauthn_credentials,
+    check_for_backend(diesel::pg::Pg),
+    belongs_to(User),
+)]
+pub struct WebauthnCredential {
+    pub id: i32,
+    pub user_id: i32,
+    /// The ID that the authenticator assigned to the credential.
+    pub credential_id: Vec<u8>,
+    /// The public key of the credential, in uncompressed SEC1 encoding.
+    pub public_key: Vec<u8>,
+    /// The last known value of the signature counter.
+    pub sign_count: i64,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl WebauthnCredential {
+    /// Loads the security keys and passkeys of a user, ordered by the time
+    /// they were registered.
+    pub async fn for_user(mut conn: &AsyncPgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        webauthn_credentials::table
+            .filter(webauthn_credentials::user_id.eq(user_id))
+            .order(webauthn_credentials::id)
+            .select(WebauthnCredential::as_select())
+            .load(&mut conn)
+            .await
+    }
+}
+
+/// Represents a new security key or passkey to be inserted into the
+/// `webauthn_credentials` table.
+#[derive(Insertable, Debug, Builder)]
+#[diesel(table_name = webauthn_credentials, check_for_backend(diesel::pg::Pg))]
+pub struct NewWebauthnCredential<'a> {
+    pub user_id: i32,
+    pub credential_id: &'a [u8],
+    pub public_key: &'a [u8],
+    pub sign_count: i64,
+    pub name: &'a str,
+}
+
+impl NewWebauthnCredential<'_> {
+    /// Inserts the credential, unless a credential with the same ID has
+    /// been registered before.
+    ///
+    /// Returns `false` if the credential already exists.
+    pub async fn insert(&self, mut conn: &AsyncPgConnection) -> QueryResult<bool> {
+        let inserted = diesel::insert_into(webauthn_credentials::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .await?;
+
+        Ok(inserted > 0)
+    }
+}
+
+/// Replaces the recovery codes of a user with the given code hashes.
+pub async fn replace_recovery_codes(
+    mut conn: &AsyncPgConnection,
+    user_id: i32,
+    code_hashes: &[Vec<u8>],
+) -> QueryResult<()> {
+    diesel::delete(recovery_codes::table)
+        .filter(recovery_codes::user_id.eq(user_id))
+        .execute(&mut conn)
+        .await?;
+
+    let new_codes = code_hashes
+        .iter()
+        .map(|code_hash| {
+            (
+                recovery_codes::user_id.eq(user_id),
+                recovery_codes::code_hash.eq(code_hash),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    diesel::insert_into(recovery_codes::table)
+        .values(new_codes)
+        .execute(&mut conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes the recovery code with the given hash.
+///
+/// Returns `false` if the user does not have an unused recovery code with
+/// this hash.
+pub async fn use_recovery_code(
+    mut conn: &AsyncPgConnection,
+    user_id: i32,
+    code_hash: &[u8],
+) -> QueryResult<bool> {
+    let deleted = diesel::delete(recovery_codes::table.find((user_id, code_hash)))
+        .execute(&mut conn)
+        .await?;
+
+    Ok(deleted > 0)
+}
+
+/// Returns whether the user has enabled two-factor authentication, i.e.
+/// whether they have a confirmed authenticator app or at least one security
+/// key or passkey.
+///
+/// Recovery codes on their own don't count as a second factor.
+pub async fn has_two_factor(mut conn: &AsyncPgConnection, user_id: i32) -> QueryResult<bool> {
+    let totp = totp_credentials::table
+        .filter(totp_credentials::user_id.eq(user_id))
+        .filter(totp_credentials::confirmed_at.is_not_null());
+
+    let webauthn = webauthn_credentials::table.filter(webauthn_credentials::user_id.eq(user_id));
+
+    select(exists(totp).or(exists(webauthn)))
+        .get_result(&mut conn)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NewUser;
+    use claims::{assert_ok, assert_ok_eq};
+    use crates_io_test_db::TestDatabase;
+    use diesel::dsl::now;
+
+    async fn insert_user(conn: &AsyncPgConnection) -> i32 {
+        let new_user = NewUser::builder()
+            .gh_id(42)
+            .gh_login("ghost")
+            .username("ghost")
+            .build();
+
+        assert_ok!(new_user.insert(conn).await)
+    }
+
+    #[tokio::test]
+    async fn pending_totp_setups_are_replaced() {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.async_connect().await;
+        let user_id = insert_user(&conn).await;
+
+        let new_credential = |encrypted_secret| {
+            NewTotpCredential::builder()
+                .user_id(user_id)
+                .encrypted_secret(encrypted_secret)
+                .build()
+        };
+
+        assert_ok_eq!(new_credential(b"first").insert_pending(&conn).await, true);
+        assert_ok_eq!(new_credential(b"second").insert_pending(&conn).await, true);
+        assert_ok_eq!(has_two_factor(&conn, user_id).await, false);
+
+        let credential = assert_ok!(TotpCredential::find(&conn, user_id).await).unwrap();
+        assert_eq!(credential.encrypted_secret, b"second");
+
+        assert_ok!(
+            diesel::update(totp_credentials::table.find(user_id))
+                .set(totp_credentials::confirmed_at.eq(now))
+                .execute(&mut conn)
+                .await
+        );
+        assert_ok_eq!(has_two_factor(&conn, user_id).await, true);
+
+        // Confirmed authenticator apps are not replaced
+        assert_ok_eq!(new_credential(b"third").insert_pending(&conn).await, false);
+        let credential = assert_ok!(TotpCredential::find(&conn, user_id).await).unwrap();
+        assert_eq!(credential.encrypted_secret, b"second");
+    }
+
+    #[tokio::test]
+    async fn webauthn_credentials() {
+        let test_db = TestDatabase::new();
+        let conn = test_db.async_connect().await;
+        let user_id = insert_user(&conn).await;
+
+        let new_credential = NewWebauthnCredential::builder()
+            .user_id(user_id)
+            .credential_id(b"credential-id")
+            .public_key(b"public-key")
+            .sign_count(0)
+            .name("YubiKey")
+            .build();
+
+        assert_ok_eq!(new_credential.insert(&conn).await, true);
+        assert_ok_eq!(new_credential.insert(&conn).await, false);
+        assert_ok_eq!(has_two_factor(&conn, user_id).await, true);
+
+        let credentials = assert_ok!(WebauthnCredential::for_user(&conn, user_id).await);
+        assert_eq!(credentials.len(), 1);
+        assert_eq!(credentials[0].name, "YubiKey");
+    }
+
+    #[tokio::test]
+    async fn recovery_codes_can_only_be_used_once() {
+        let test_db = TestDatabase::new();
+        let conn = test_db.async_connect().await;
+        let user_id = insert_user(&conn).await;
+
+        let code_hashes = vec![b"first".to_vec(), b"second".to_vec()];
+        assert_ok!(replace_recovery_codes(&conn, user_id, &code_hashes).await);
+
+        assert_ok_eq!(use_recovery_code(&conn, user_id, b"first").await, true);
+        assert_ok_eq!(use_recovery_code(&conn, user_id, b"first").await, false);
+        assert_ok_eq!(use_recovery_code(&conn, user_id, b"unknown").await, false);
+
+        // Recovery codes on their own don't enable two-factor authentication
+        assert_ok_eq!(has_two_factor(&conn, user_id).await, false);
+
+        // Replacing the codes invalidates the remaining old ones
+        assert_ok!(replace_recovery_codes(&conn, user_id, &[b"third".to_vec()]).await);
+        assert_ok_eq!(use_recovery_code(&conn, user_id, b"second").await, false);
+        assert_ok_eq!(use_recovery_code(&conn, user_id, b"third").await, true);
+    }
+}
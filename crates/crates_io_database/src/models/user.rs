@@ -9,7 +9,7 @@ use serde::Serialize;
 
 use crate::fns::lower;
 use crate::models::{Crate, CrateOwner, Email, OwnerKind};
-use crate::schema::{crate_owners, emails, oauth_github, users};
+use crate::schema::{crate_owners, emails, linked_identities, oauth_github, users};
 
 // Diesel validates a correlated subquery against the outer query source. `PublicUser` already
 // joins `oauth_github`, so reusing that table in this `EXISTS` expression would make it appear
@@ -29,6 +29,26 @@ pub fn github_username_matches() -> _ {
     exists(account.filter(belongs_to_user).filter(username_matches))
 }
 
+#[diesel::dsl::auto_type]
+fn gitlab_account_id() -> _ {
+    let provider: &'static str = LinkedIdentity::GITLAB;
+    linked_identities::table
+        .filter(linked_identities::user_id.eq(users::id))
+        .filter(linked_identities::provider.eq(provider))
+        .select(linked_identities::account_id)
+        .single_value()
+}
+
+#[diesel::dsl::auto_type]
+fn gitlab_encrypted_token() -> _ {
+    let provider: &'static str = LinkedIdentity::GITLAB;
+    linked_identities::table
+        .filter(linked_identities::user_id.eq(users::id))
+        .filter(linked_identities::provider.eq(provider))
+        .select(linked_identities::encrypted_token)
+        .single_value()
+}
+
 /// Public data for a crates.io user.
 #[derive(Clone, Debug, HasQuery, Serialize)]
 #[diesel(
@@ -74,6 +94,13 @@ pub struct User {
     #[diesel(select_expression = oauth_github::encrypted_token.nullable())]
     #[serde(skip)]
     pub gh_encrypted_token: Option<Vec<u8>>,
+    /// The ID of the linked GitLab account, if any.
+    #[diesel(select_expression = gitlab_account_id())]
+    pub gl_account_id: Option<String>,
+    /// The encrypted OAuth access token of the linked GitLab account, if any.
+    #[diesel(select_expression = gitlab_encrypted_token())]
+    #[serde(skip)]
+    pub gl_encrypted_token: Option<Vec<u8>>,
     pub account_lock_reason: Option<String>,
     pub account_lock_until: Option<DateTime<Utc>>,
     pub is_admin: bool,
@@ -130,6 +157,11 @@ impl User {
             .await
             .optional()
     }
+
+    /// Returns the numeric ID of the linked GitLab account, if any.
+    pub fn gitlab_user_id(&self) -> Option<i64> {
+        self.gl_account_id.as_deref()?.parse().ok()
+    }
 }
 
 /// Represents a new user record insertable to the `users` table
@@ -237,3 +269,75 @@ impl NewOauthGithub<'_> {
         Ok(())
     }
 }
+
+/// Represents an account of an external identity provider linked to a user
+/// record. Stored in the `linked_identities` table.
+#[derive(Associations, Identifiable, Selectable, Queryable, Debug, Clone)]
+#[diesel(
+    table_name = linked_identities,
+    check_for_backend(diesel::pg::Pg),
+    primary_key(provider, account_id),
+    belongs_to(User),
+)]
+pub struct LinkedIdentity {
+    /// The ID of the account at the identity provider.
+    pub account_id: String,
+    pub avatar: Option<String>,
+    /// The time when the account was linked.
+    pub created_at: DateTime<Utc>,
+    pub email: Option<String>,
+    /// The refresh token for the access token, encrypted at rest in our
+    /// database. Only stored for providers with expiring access tokens.
+    pub encrypted_refresh_token: Option<Vec<u8>>,
+    /// The access token for this account, encrypted at rest in our database.
+    /// Only stored for providers whose API we use after signing in.
+    pub encrypted_token: Option<Vec<u8>>,
+    /// The last time the account details were fetched from the identity provider.
+    pub last_sync: DateTime<Utc>,
+    pub login: Option<String>,
+    /// The ID of the identity provider, e.g. [`LinkedIdentity::GITLAB`].
+    pub provider: String,
+    /// Foreign key to the `users` table.
+    pub user_id: i32,
+}
+
+impl LinkedIdentity {
+    /// The provider ID of linked GitLab accounts.
+    pub const GITLAB: &'static str = "gitlab";
+
+    /// Loads the linked identities of a user, ordered by provider.
+    pub async fn for_user(mut conn: &AsyncPgConnection, user_id: i32) -> QueryResult<Vec<Self>> {
+        linked_identities::table
+            .filter(linked_identities::user_id.eq(user_id))
+            .order(linked_identities::provider)
+            .select(LinkedIdentity::as_select())
+            .load(&mut conn)
+            .await
+    }
+}
+
+/// Represents a new link between a crates.io user and an account of an external
+/// identity provider to be inserted into the `linked_identities` table.
+#[derive(Insertable, Debug, Builder)]
+#[diesel(table_name = linked_identities, check_for_backend(diesel::pg::Pg))]
+pub struct NewLinkedIdentity<'a> {
+    pub provider: &'a str,
+    pub account_id: &'a str,
+    pub user_id: i32,
+    pub login: Option<&'a str>,
+    pub email: Option<&'a str>,
+    pub avatar: Option<&'a str>,
+    pub encrypted_token: Option<&'a [u8]>,
+    pub encrypted_refresh_token: Option<&'a [u8]>,
+}
+
+impl NewLinkedIdentity<'_> {
+    pub async fn insert(&self, mut conn: &AsyncPgConnection) -> QueryResult<()> {
+        diesel::insert_into(linked_identities::table)
+            .values(self)
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+}
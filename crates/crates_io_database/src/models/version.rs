@@ -44,6 +44,9 @@ pub struct Version {
     /// SHA256 checksum of the zip source archive manifest,
     /// or `None` if it has not been built yet.
     pub zip_json_sha256: Option<Vec<u8>>,
+    /// Time at which the version was uploaded as a staged version,
+    /// or `None` if it is visible.
+    pub staged_at: Option<DateTime<Utc>>,
 }
 
 impl Version {
@@ -121,6 +124,7 @@ pub struct NewVersion<'a> {
     keywords: Option<&'a [&'a str]>,
     trustpub_data: Option<&'a TrustpubData>,
     linecounts: Option<serde_json::Value>,
+    staged_at: Option<&'a DateTime<Utc>>,
 }
 
 impl NewVersion<'_> {
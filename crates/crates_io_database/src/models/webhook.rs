@@ -0,0 +1,142 @@
+use crate::models::{Crate, User};
+use crate::schema::{webhook_deliveries, webhooks};
+use bon::Builder;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The types of crate events that can be delivered to webhooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A new version was published, or a staged version was promoted.
+    Publish,
+    /// A version was yanked.
+    Yank,
+    /// A version was unyanked.
+    Unyank,
+    /// A user or team was added as an owner.
+    OwnerAdd,
+    /// A user or team was removed as an owner.
+    OwnerRemove,
+    /// A Trusted Publishing configuration was created.
+    TrustpubConfigCreate,
+    /// A Trusted Publishing configuration was deleted.
+    TrustpubConfigDelete,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 7] = [
+        Self::Publish,
+        Self::Yank,
+        Self::Unyank,
+        Self::OwnerAdd,
+        Self::OwnerRemove,
+        Self::TrustpubConfigCreate,
+        Self::TrustpubConfigDelete,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Publish => "publish",
+            Self::Yank => "yank",
+            Self::Unyank => "unyank",
+            Self::OwnerAdd => "owner_add",
+            Self::OwnerRemove => "owner_remove",
+            Self::TrustpubConfigCreate => "trustpub_config_create",
+            Self::TrustpubConfigDelete => "trustpub_config_delete",
+        }
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|event| event.as_str() == s)
+            .ok_or_else(|| format!("unknown webhook event `{s}`"))
+    }
+}
+
+/// A subscription of a user for the events of the crates they own.
+///
+/// Webhooks without a `crate_id` receive the events of all crates that the
+/// user owns. Events are only delivered while the user is an owner of the
+/// crate.
+#[derive(Debug, Clone, Identifiable, Associations, HasQuery)]
+#[diesel(table_name = webhooks, belongs_to(User), belongs_to(Crate))]
+pub struct Webhook {
+    pub id: i32,
+    pub user_id: i32,
+    pub crate_id: Option<i32>,
+    pub url: String,
+    pub encrypted_secret: Vec<u8>,
+    pub events: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub async fn count_for_user(mut conn: &AsyncPgConnection, user_id: i32) -> QueryResult<i64> {
+        webhooks::table
+            .filter(webhooks::user_id.eq(user_id))
+            .count()
+            .get_result(&mut conn)
+            .await
+    }
+}
+
+#[derive(Insertable, Debug, Builder)]
+#[diesel(table_name = webhooks, check_for_backend(diesel::pg::Pg))]
+pub struct NewWebhook<'a> {
+    pub user_id: i32,
+    pub crate_id: Option<i32>,
+    pub url: &'a str,
+    pub encrypted_secret: &'a [u8],
+    pub events: &'a [&'a str],
+}
+
+impl NewWebhook<'_> {
+    pub async fn insert(&self, mut conn: &AsyncPgConnection) -> QueryResult<Webhook> {
+        diesel::insert_into(webhooks::table)
+            .values(self)
+            .returning(Webhook::as_returning())
+            .get_result(&mut conn)
+            .await
+    }
+}
+
+/// An event that was sent, or is about to be sent, to a webhook.
+#[derive(Debug, Clone, Identifiable, Associations, HasQuery)]
+#[diesel(table_name = webhook_deliveries, belongs_to(Webhook))]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = webhook_deliveries, check_for_backend(diesel::pg::Pg))]
+pub struct NewWebhookDelivery<'a> {
+    pub webhook_id: i32,
+    pub event: &'a str,
+    pub payload: &'a serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
@@ -12,6 +12,41 @@ pub mod sql_types {
     pub struct Ltree;
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Security advisories imported from a RustSec-format advisory database
+    advisories (id) {
+        /// Other identifiers of the vulnerability, e.g. CVE IDs
+        aliases -> Array<Text>,
+        /// Name of the affected crate. The crate does not necessarily exist on this registry.
+        crate_name -> Text,
+        /// Date and time when the advisory was first imported
+        created_at -> Timestamptz,
+        /// The date on which the advisory was reported
+        date -> Date,
+        /// Markdown description of the advisory
+        description -> Text,
+        /// The ID of the advisory, e.g. `RUSTSEC-2019-0001`
+        id -> Text,
+        /// The kind of informational advisory (e.g. `unmaintained`), or NULL for vulnerabilities
+        informational -> Nullable<Text>,
+        /// Version requirements matching the versions that contain a fix
+        patched -> Array<Text>,
+        /// One-line summary of the advisory
+        title -> Text,
+        /// Version requirements matching the versions that were never affected
+        unaffected -> Array<Text>,
+        /// Date and time when the advisory was last imported
+        updated_at -> Timestamptz,
+        /// URL with more information about the advisory
+        url -> Nullable<Text>,
+        /// The date on which the advisory was withdrawn, if it was. Withdrawn advisories are not linked to any versions.
+        withdrawn -> Nullable<Date>,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -77,6 +112,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// State of the recurring background jobs that are scheduled by the background worker itself
+    background_job_schedules (job_type) {
+        /// Type of the scheduled background job
+        job_type -> Text,
+        /// Date and time when the job was last enqueued by the scheduler
+        last_run_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -127,6 +175,50 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         retries -> Int4,
+        /// Date and time before which the job must not be run, or `NULL` if it can be run immediately
+        run_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Background jobs that have exhausted their retry policy and are no longer attempted by the background workers
+    background_jobs_dead (id) {
+        /// Date and time when the job was originally enqueued
+        created_at -> Timestamptz,
+        /// Serialized data of the background job
+        data -> Jsonb,
+        /// Date and time when the job failed for the last time
+        failed_at -> Timestamptz,
+        /// Identifier of the job in the `background_jobs` table
+        id -> Int8,
+        /// Type of the background job
+        job_type -> Text,
+        /// Error message of the last failed attempt
+        last_error -> Nullable<Text>,
+        /// Priority of the background job
+        priority -> Int2,
+        /// Number of times the job was retried before it was moved to this table
+        retries -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Number of downloads per crate and day, broken down by the `major.minor` version of cargo that downloaded the crate files
+    cargo_version_downloads (crate_id, date, cargo_version) {
+        /// The `major.minor` version of cargo, as reported in the user agent
+        cargo_version -> Varchar,
+        /// Crate ID foreign key
+        crate_id -> Int4,
+        /// The day on which the crate files were downloaded
+        date -> Date,
+        /// The number of downloads
+        downloads -> Int4,
     }
 }
 
@@ -178,6 +270,23 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Number of downloads per crate and day, broken down by the class of client that downloaded the crate files
+    client_downloads (crate_id, date, client) {
+        /// The class of client: 0 = cargo, 1 = cargo on a CI service, 2 = mirroring tool, 3 = other
+        client -> Int4,
+        /// Crate ID foreign key
+        crate_id -> Int4,
+        /// The day on which the crate files were downloaded
+        date -> Date,
+        /// The number of downloads. Downloads by mirroring tools are not included in the regular download counts.
+        downloads -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -208,6 +317,23 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Rate limiting buckets for actions that are performed on behalf of a crate instead of a user (e.g. Trusted Publishing token exchanges)
+    crate_limit_buckets (crate_id, action) {
+        /// The rate-limited action (see `LimitedAction`)
+        action -> Int4,
+        /// Unique identifier of the crate that the bucket belongs to
+        crate_id -> Int4,
+        /// Date and time when the bucket was last refilled
+        last_refill -> Timestamptz,
+        /// Number of remaining tokens in the bucket
+        tokens -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -302,6 +428,38 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Per-crate overrides of the burst size of crate rate limiting buckets
+    crate_rate_overrides (crate_id, action) {
+        /// The rate-limited action (see `LimitedAction`)
+        action -> Int4,
+        /// Burst size that is used instead of the default
+        burst -> Int4,
+        /// Unique identifier of the crate that the override applies to
+        crate_id -> Int4,
+        /// Date and time when the override expires, or `NULL` if it does not expire
+        expires_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Users that are allowed to read access-controlled crates in private registry mode. Crates without any readers can be read by all authenticated users.
+    crate_readers (crate_id, user_id) {
+        /// The access-controlled crate
+        crate_id -> Int4,
+        /// The time when read access was granted
+        created_at -> Timestamptz,
+        /// The user that is allowed to read the crate
+        user_id -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -316,6 +474,10 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         created_at -> Timestamptz,
+        /// The time when the owners marked this crate as deprecated, or NULL if it is not deprecated
+        deprecated_at -> Nullable<Timestamptz>,
+        /// Optional message from the owners explaining the deprecation
+        deprecation_message -> Nullable<Varchar>,
         /// The `description` column of the `crates` table.
         ///
         /// Its SQL type is `Nullable<Varchar>`.
@@ -370,6 +532,12 @@ diesel::table! {
         ///
         /// (Automatically generated by Diesel.)
         repository -> Nullable<Varchar>,
+        /// When true, all owners of this crate must have two-factor authentication enabled
+        require_owner_2fa -> Bool,
+        /// When true, new versions of this crate are always staged, and have to be promoted by an owner other than the one who published them
+        require_promotion_review -> Bool,
+        /// Optional name of the crate that the owners recommend using instead
+        successor -> Nullable<Varchar>,
         /// The `textsearchable_index_col` column of the `crates` table.
         ///
         /// Its SQL type is `Tsvector`.
@@ -647,6 +815,35 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Accounts of external identity providers other than GitHub (e.g. GitLab or generic OpenID Connect providers) that are linked to a crates.io account and can be used to sign in
+    linked_identities (provider, account_id) {
+        /// ID of the account at the identity provider (the `sub` claim for OpenID Connect providers)
+        account_id -> Varchar,
+        /// Avatar URL of the account at the identity provider, if available
+        avatar -> Nullable<Varchar>,
+        /// The time when the account was linked
+        created_at -> Timestamptz,
+        /// Email address of the account at the identity provider, if available
+        email -> Nullable<Varchar>,
+        /// Encrypted refresh token, used to replace the access token once it has expired
+        encrypted_refresh_token -> Nullable<Bytea>,
+        /// Encrypted access token, only stored for providers whose API is used after signing in (e.g. for GitLab group membership checks)
+        encrypted_token -> Nullable<Bytea>,
+        /// The last time the account details were fetched from the identity provider
+        last_sync -> Timestamptz,
+        /// Username of the account at the identity provider, if available
+        login -> Nullable<Varchar>,
+        /// ID of the identity provider, e.g. `gitlab` or the ID of a configured OpenID Connect provider
+        provider -> Varchar,
+        /// Crates.io user ID foreign key
+        user_id -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -664,6 +861,42 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Opt-ins and opt-outs of users for the categories of emails that are not security-relevant
+    notification_preferences (id) {
+        /// The category of emails, e.g. `publish` or `owner_invite`
+        category -> Varchar,
+        /// The crate that the preference is restricted to, or NULL for the preference of the whole category. Crate-specific preferences take precedence.
+        crate_id -> Nullable<Int4>,
+        /// Whether emails of the category are sent to the user
+        enabled -> Bool,
+        /// Unique identifier of the preference
+        id -> Int4,
+        /// The time when the preference was last changed
+        updated_at -> Timestamptz,
+        /// The user that the preference belongs to
+        user_id -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Email notification settings of users. Users without a row use the defaults.
+    notification_settings (user_id) {
+        /// How publish notifications are delivered: `immediate`, or batched into a `daily` or `weekly` digest
+        publish_delivery -> Varchar,
+        /// Secret token of the one-click unsubscribe links in the emails sent to the user
+        unsubscribe_token -> Varchar,
+        /// The user that the settings belong to
+        user_id -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -685,6 +918,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Publish notifications that are waiting to be sent as part of a digest email
+    pending_publish_notifications (user_id, version_id) {
+        /// The time when the notification was queued
+        created_at -> Timestamptz,
+        /// The user that the notification is sent to
+        user_id -> Int4,
+        /// The version that was published
+        version_id -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -809,6 +1057,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Unused single-use recovery codes, which can be used instead of a second factor. Codes are deleted once they are used.
+    recovery_codes (user_id, code_hash) {
+        /// SHA-256 hash of the normalized recovery code
+        code_hash -> Bytea,
+        /// The time when the recovery code was generated
+        created_at -> Timestamptz,
+        /// Crates.io user ID foreign key
+        user_id -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -871,14 +1134,16 @@ diesel::table! {
         /// (Automatically generated by Diesel.)
         avatar -> Nullable<Varchar>,
         /// Unique team ID on the GitHub API. When teams are recreated with the same name then they will still get a different ID, so this allows us to avoid potential name reuse attacks.
-        github_id -> Int4,
+        github_id -> Nullable<Int4>,
+        /// Unique group ID on the GitLab API. When groups are recreated with the same path then they will still get a different ID, so this allows us to avoid potential name reuse attacks.
+        gitlab_id -> Nullable<Int8>,
         /// The `id` column of the `teams` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
         id -> Int4,
-        /// Example: `github:foo:bar` means the `bar` team of the `foo` GitHub organization.
+        /// Example: `github:foo:bar` means the `bar` team of the `foo` GitHub organization, and `gitlab:foo/bar` means the `bar` subgroup of the `foo` GitLab group.
         login -> Varchar,
         /// The `name` column of the `teams` table.
         ///
@@ -887,7 +1152,26 @@ diesel::table! {
         /// (Automatically generated by Diesel.)
         name -> Nullable<Varchar>,
         /// Unique organization ID on the GitHub API. When organizations are recreated with the same name then they will still get a different ID, so this allows us to avoid potential name reuse attacks.
-        org_id -> Int4,
+        org_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Authenticator apps that are used as a second factor, using time-based one-time passwords (TOTP)
+    totp_credentials (user_id) {
+        /// The time when the user confirmed the setup with a valid code, or NULL if the setup is still pending
+        confirmed_at -> Nullable<Timestamptz>,
+        /// The time when the setup was started
+        created_at -> Timestamptz,
+        /// Encrypted shared secret of the authenticator app
+        encrypted_secret -> Bytea,
+        /// Time step of the last accepted code, to make sure that every code is only accepted once
+        last_used_step -> Nullable<Int8>,
+        /// Crates.io user ID foreign key
+        user_id -> Int4,
     }
 }
 
@@ -941,6 +1225,46 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Trusted Publisher configuration for generic OIDC providers (e.g. Buildkite)
+    trustpub_configs_oidc (id) {
+        /// Additional JWT claims that must match for a token exchange to succeed (e.g. `{"step_key": "publish"}`)
+        claims -> Jsonb,
+        /// Unique identifier of the crate that this configuration is for
+        crate_id -> Int4,
+        /// Date and time when the configuration was created
+        created_at -> Timestamptz,
+        /// Unique identifier of the `trustpub_configs_oidc` row
+        id -> Int4,
+        /// Identifier of the OIDC provider (e.g. `buildkite`)
+        provider -> Varchar,
+        /// Provider-specific identifier of the repository or pipeline that is allowed to publish the crate (e.g. `acme-inc/my-pipeline`)
+        repository -> Varchar,
+        /// Immutable ID of the repository owner, populated on first token exchange for resurrection attack protection (if supported by the provider)
+        repository_owner_id -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Signed provenance attestations for versions published via Trusted Publishing
+    trustpub_provenance (version_id) {
+        /// Date and time when the attestation was issued
+        created_at -> Timestamptz,
+        /// DSSE envelope containing the signed in-toto statement with a SLSA provenance predicate
+        envelope -> Jsonb,
+        /// Identifier of the key that was used to sign the attestation (hex-encoded SHA256 hash of the Ed25519 public key)
+        key_id -> Varchar,
+        /// Unique identifier of the version that this attestation is for
+        version_id -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -1038,6 +1362,34 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Links between versions and the security advisories that affect them
+    version_advisories (version_id, advisory_id) {
+        /// ID of the advisory
+        advisory_id -> Text,
+        /// Unique identifier of the affected version
+        version_id -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Comparisons of the features, dependencies and `rust-version` of versions with their previous semver-compatible version
+    version_compatibility_reports (version_id) {
+        /// The time when the version was checked
+        created_at -> Timestamptz,
+        /// The changes compared to the previous compatible version, and warnings about changes that are likely to break users of the crate
+        report -> Jsonb,
+        /// The version that was checked
+        version_id -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -1126,6 +1478,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Findings of the content scanner for the files of published versions, visible to the crate owners and admins
+    version_scan_findings (id) {
+        /// Human-readable description of the finding
+        description -> Text,
+        /// Unique identifier of the finding
+        id -> Int8,
+        /// Category of the finding (`secret`, `binary` or `build_script`)
+        kind -> Text,
+        /// Line number of the finding, if it is specific to a line
+        line -> Nullable<Int4>,
+        /// Path of the file, relative to the root of the crate
+        path -> Text,
+        /// Unique identifier of the version that the finding belongs to
+        version_id -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::Tsvector;
@@ -1226,6 +1599,8 @@ diesel::table! {
         rust_version -> Nullable<Varchar>,
         /// Order-preserving bytea representation of the version number for sorting purposes.
         semver_ord_v2 -> Bytea,
+        /// Date and time when the version was uploaded as a staged version, or NULL if the version is visible. Staged versions are hidden from the index and the API listings until they are promoted.
+        staged_at -> Nullable<Timestamptz>,
         /// SHA256 checksum of the crate tarball, stored as 32 raw bytes.
         tar_sha256 -> Bytea,
         /// JSONB data containing JWT claims from the trusted publisher (e.g., GitHub Actions context like repository, run_id, sha)
@@ -1274,12 +1649,95 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Security keys and passkeys that are used as a second factor via WebAuthn
+    webauthn_credentials (id) {
+        /// The time when the credential was registered
+        created_at -> Timestamptz,
+        /// ID that the authenticator assigned to the credential
+        credential_id -> Bytea,
+        /// Unique identifier of the credential
+        id -> Int4,
+        /// The last time the credential was used for a verification
+        last_used_at -> Nullable<Timestamptz>,
+        /// Name of the credential chosen by the user, e.g. the name of the security key
+        name -> Varchar,
+        /// ES256 public key of the credential, in uncompressed SEC1 encoding
+        public_key -> Bytea,
+        /// Last known value of the signature counter of the authenticator, used to detect cloned authenticators
+        sign_count -> Int8,
+        /// Crates.io user ID foreign key
+        user_id -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Log of the events that were sent, or are about to be sent, to webhooks
+    webhook_deliveries (id) {
+        /// The number of delivery attempts so far
+        attempts -> Int4,
+        /// The time when the event happened
+        created_at -> Timestamptz,
+        /// The time when the event was delivered successfully
+        delivered_at -> Nullable<Timestamptz>,
+        /// The error of the last attempt, if it failed
+        error -> Nullable<Varchar>,
+        /// The type of the event, e.g. `publish` or `yank`
+        event -> Varchar,
+        /// Unique identifier of the delivery, also sent in the `X-Crates-Io-Delivery` header
+        id -> Int8,
+        /// The time of the last delivery attempt
+        last_attempt_at -> Nullable<Timestamptz>,
+        /// The JSON payload that is sent to the webhook
+        payload -> Jsonb,
+        /// The HTTP status code of the response to the last attempt, if a response was received
+        response_status -> Nullable<Int4>,
+        /// The webhook that the event is sent to
+        webhook_id -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
+    /// Webhook subscriptions of users for events of the crates they own
+    webhooks (id) {
+        /// The crate whose events are delivered, or NULL for the events of all crates owned by the user
+        crate_id -> Nullable<Int4>,
+        /// The time when the webhook was created
+        created_at -> Timestamptz,
+        /// Encrypted secret that is used to sign the payloads
+        encrypted_secret -> Bytea,
+        /// The types of events that are delivered, e.g. `publish` or `yank`
+        events -> Array<Text>,
+        /// Unique identifier of the webhook
+        id -> Int4,
+        /// The URL that the events are sent to
+        url -> Varchar,
+        /// The user that created the webhook
+        user_id -> Int4,
+    }
+}
+
 diesel::joinable!(api_tokens -> users (user_id));
+diesel::joinable!(cargo_version_downloads -> crates (crate_id));
+diesel::joinable!(client_downloads -> crates (crate_id));
 diesel::joinable!(crate_downloads -> crates (crate_id));
+diesel::joinable!(crate_limit_buckets -> crates (crate_id));
 diesel::joinable!(crate_owner_invitations -> crates (crate_id));
 diesel::joinable!(crate_owners -> crates (crate_id));
 diesel::joinable!(crate_owners -> teams (owner_id));
 diesel::joinable!(crate_owners -> users (owner_id));
+diesel::joinable!(crate_rate_overrides -> crates (crate_id));
+diesel::joinable!(crate_readers -> crates (crate_id));
+diesel::joinable!(crate_readers -> users (user_id));
 diesel::joinable!(crates_categories -> categories (category_id));
 diesel::joinable!(crates_categories -> crates (crate_id));
 diesel::joinable!(crates_keywords -> crates (crate_id));
@@ -1292,30 +1750,56 @@ diesel::joinable!(dependencies -> versions (version_id));
 diesel::joinable!(emails -> users (user_id));
 diesel::joinable!(follows -> crates (crate_id));
 diesel::joinable!(follows -> users (user_id));
+diesel::joinable!(linked_identities -> users (user_id));
+diesel::joinable!(notification_preferences -> crates (crate_id));
+diesel::joinable!(notification_preferences -> users (user_id));
+diesel::joinable!(notification_settings -> users (user_id));
 diesel::joinable!(oauth_github -> users (user_id));
+diesel::joinable!(pending_publish_notifications -> users (user_id));
+diesel::joinable!(pending_publish_notifications -> versions (version_id));
 diesel::joinable!(publish_limit_buckets -> users (user_id));
 diesel::joinable!(publish_rate_overrides -> users (user_id));
 diesel::joinable!(readme_renderings -> versions (version_id));
 diesel::joinable!(recent_crate_downloads -> crates (crate_id));
+diesel::joinable!(recovery_codes -> users (user_id));
 diesel::joinable!(reverse_dependencies -> versions (version_id));
+diesel::joinable!(totp_credentials -> users (user_id));
 diesel::joinable!(trustpub_configs_github -> crates (crate_id));
 diesel::joinable!(trustpub_configs_gitlab -> crates (crate_id));
+diesel::joinable!(trustpub_configs_oidc -> crates (crate_id));
+diesel::joinable!(trustpub_provenance -> versions (version_id));
+diesel::joinable!(version_advisories -> advisories (advisory_id));
+diesel::joinable!(version_advisories -> versions (version_id));
+diesel::joinable!(version_compatibility_reports -> versions (version_id));
 diesel::joinable!(version_downloads -> versions (version_id));
 diesel::joinable!(version_owner_actions -> api_tokens (api_token_id));
 diesel::joinable!(version_owner_actions -> users (user_id));
 diesel::joinable!(version_owner_actions -> versions (version_id));
+diesel::joinable!(version_scan_findings -> versions (version_id));
 diesel::joinable!(versions -> crates (crate_id));
 diesel::joinable!(versions -> users (published_by));
 diesel::joinable!(versions_published_by -> versions (version_id));
+diesel::joinable!(webauthn_credentials -> users (user_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> crates (crate_id));
+diesel::joinable!(webhooks -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    advisories,
     api_tokens,
+    background_job_schedules,
     background_jobs,
+    background_jobs_dead,
+    cargo_version_downloads,
     categories,
+    client_downloads,
     cloudfront_invalidation_queue,
     crate_downloads,
+    crate_limit_buckets,
     crate_owner_invitations,
     crate_owners,
+    crate_rate_overrides,
+    crate_readers,
     crates,
     crates_categories,
     crates_keywords,
@@ -1325,24 +1809,38 @@ diesel::allow_tables_to_appear_in_same_query!(
     emails,
     follows,
     keywords,
+    linked_identities,
     metadata,
+    notification_preferences,
+    notification_settings,
     oauth_github,
+    pending_publish_notifications,
     processed_log_files,
     publish_limit_buckets,
     publish_rate_overrides,
     readme_renderings,
     recent_crate_downloads,
+    recovery_codes,
     reserved_crate_names,
     reserved_usernames,
     reverse_dependencies,
     teams,
+    totp_credentials,
     trustpub_configs_github,
     trustpub_configs_gitlab,
+    trustpub_configs_oidc,
+    trustpub_provenance,
     trustpub_tokens,
     trustpub_used_jtis,
     users,
+    version_advisories,
+    version_compatibility_reports,
     version_downloads,
     version_owner_actions,
+    version_scan_findings,
     versions,
     versions_published_by,
+    webauthn_credentials,
+    webhook_deliveries,
+    webhooks,
 );
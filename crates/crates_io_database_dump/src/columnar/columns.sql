@@ -0,0 +1 @@
+\copy (SELECT table_name, column_name, udt_name, is_nullable FROM information_schema.columns WHERE table_schema = current_schema() ORDER BY table_name, ordinal_position) TO 'columnar/columns.csv' WITH CSV HEADER
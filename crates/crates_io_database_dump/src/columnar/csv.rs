@@ -0,0 +1,157 @@
+use anyhow::anyhow;
+use std::io::BufRead;
+
+/// A reader for the CSV files exported by `psql`.
+///
+/// In contrast to general purpose CSV readers, it distinguishes `NULL`
+/// values, which are exported as unquoted empty fields, from empty strings,
+/// which are exported as `""`.
+pub struct CsvReader<R> {
+    reader: R,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: Vec::new(),
+        }
+    }
+
+    /// Reads the next record, or returns `None` at the end of the file.
+    pub fn read_record(&mut self) -> anyhow::Result<Option<Vec<Option<String>>>> {
+        let mut fields = Vec::new();
+        let mut field = Vec::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+
+        loop {
+            self.line.clear();
+            if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+                if in_quotes {
+                    return Err(anyhow!("Unexpected end of file in quoted field"));
+                }
+                if fields.is_empty() && field.is_empty() && !quoted {
+                    return Ok(None);
+                }
+                fields.push(finish_field(field, quoted)?);
+                return Ok(Some(fields));
+            }
+
+            let mut bytes = self.line.iter().copied().peekable();
+            while let Some(byte) = bytes.next() {
+                if in_quotes {
+                    if byte != b'"' {
+                        field.push(byte);
+                    } else if bytes.next_if_eq(&b'"').is_some() {
+                        field.push(b'"');
+                    } else {
+                        in_quotes = false;
+                    }
+                    continue;
+                }
+
+                match byte {
+                    b'"' => {
+                        in_quotes = true;
+                        quoted = true;
+                    }
+                    b',' => {
+                        fields.push(finish_field(std::mem::take(&mut field), quoted)?);
+                        quoted = false;
+                    }
+                    b'\n' => {
+                        fields.push(finish_field(field, quoted)?);
+                        return Ok(Some(fields));
+                    }
+                    _ => field.push(byte),
+                }
+            }
+        }
+    }
+}
+
+fn finish_field(field: Vec<u8>, quoted: bool) -> anyhow::Result<Option<String>> {
+    if field.is_empty() && !quoted {
+        return Ok(None);
+    }
+
+    Ok(Some(String::from_utf8(field)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_debug_snapshot;
+
+    fn read_all(content: &str) -> Vec<Vec<Option<String>>> {
+        let mut reader = CsvReader::new(content.as_bytes());
+        let mut records = Vec::new();
+        while let Some(record) = reader.read_record().unwrap() {
+            records.push(record);
+        }
+        records
+    }
+
+    #[test]
+    fn test_read_records() {
+        let content =
+            "id,name,description\n1,foo,\n2,\"\",\"multi\nline, \"\"quoted\"\"\"\n3,bar,baz";
+        assert_debug_snapshot!(read_all(content), @r#"
+        [
+            [
+                Some(
+                    "id",
+                ),
+                Some(
+                    "name",
+                ),
+                Some(
+                    "description",
+                ),
+            ],
+            [
+                Some(
+                    "1",
+                ),
+                Some(
+                    "foo",
+                ),
+                None,
+            ],
+            [
+                Some(
+                    "2",
+                ),
+                Some(
+                    "",
+                ),
+                Some(
+                    "multi\nline, \"quoted\"",
+                ),
+            ],
+            [
+                Some(
+                    "3",
+                ),
+                Some(
+                    "bar",
+                ),
+                Some(
+                    "baz",
+                ),
+            ],
+        ]
+        "#);
+
+        assert!(read_all("").is_empty());
+    }
+
+    #[test]
+    fn test_unterminated_quotes() {
+        let mut reader = CsvReader::new("id\n\"1\n".as_bytes());
+        assert!(reader.read_record().unwrap().is_some());
+        assert!(reader.read_record().is_err());
+    }
+}
@@ -0,0 +1,351 @@
+//! Typed exports of the public database dump.
+//!
+//! The CSV files of the dump are converted into Parquet files and a SQLite
+//! database. The types of the columns are read from the `information_schema`
+//! of the exported database, so that both formats preserve them.
+
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+mod csv;
+mod parquet;
+mod sqlite;
+
+pub use self::csv::CsvReader;
+pub use self::parquet::write_parquet_file;
+pub use self::sqlite::write_sqlite_database;
+
+/// A `psql` script that exports the types of all columns of the current
+/// schema to `columnar/columns.csv`.
+pub const COLUMNS_SCRIPT: &str = include_str!("columns.sql");
+
+/// The type of a column, derived from the name of its PostgreSQL type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnType {
+    Bool,
+    Int16,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    Bytes,
+    Date,
+    Timestamp,
+    TimestampTz,
+    Json,
+    /// All other types, including `varchar`, `numeric`, enums and `ltree`,
+    /// are exported as their text representation.
+    Text,
+}
+
+impl ColumnType {
+    fn from_udt_name(name: &str) -> Self {
+        match name {
+            "bool" => Self::Bool,
+            "int2" => Self::Int16,
+            "int4" => Self::Int32,
+            "int8" => Self::Int64,
+            "float4" => Self::Float32,
+            "float8" => Self::Float64,
+            "bytea" => Self::Bytes,
+            "date" => Self::Date,
+            "timestamp" => Self::Timestamp,
+            "timestamptz" => Self::TimestampTz,
+            "json" | "jsonb" => Self::Json,
+            _ => Self::Text,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub ty: ColumnType,
+    /// Whether the column is a one-dimensional array of [`Self::ty`].
+    pub array: bool,
+    pub nullable: bool,
+}
+
+impl Column {
+    /// Parses a field of a CSV file exported by `psql`.
+    pub fn parse(&self, field: Option<&str>) -> anyhow::Result<Value> {
+        let Some(field) = field else {
+            return Ok(Value::Null);
+        };
+
+        if !self.array {
+            return parse_scalar(self.ty, field);
+        }
+
+        let elements = parse_array(field)?
+            .into_iter()
+            .map(|element| match element {
+                Some(element) => parse_scalar(self.ty, &element),
+                None => Ok(Value::Null),
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Value::Array(elements))
+    }
+}
+
+/// The columns of an exported table, in the order of its CSV file.
+#[derive(Clone, Debug)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<Column>,
+}
+
+/// The types of the columns of all tables, as exported by [`COLUMNS_SCRIPT`].
+pub struct ColumnTypes(BTreeMap<(String, String), Column>);
+
+impl ColumnTypes {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut reader = CsvReader::new(BufReader::new(file));
+        reader.read_record()?;
+
+        let mut columns = BTreeMap::new();
+        while let Some(record) = reader.read_record()? {
+            let [Some(table), Some(name), Some(udt_name), Some(nullable)] = &record[..] else {
+                return Err(anyhow!("Unexpected record in {}", path.display()));
+            };
+
+            let (ty, array) = match udt_name.strip_prefix('_') {
+                Some(element) => (ColumnType::from_udt_name(element), true),
+                None => (ColumnType::from_udt_name(udt_name), false),
+            };
+
+            let column = Column {
+                name: name.clone(),
+                ty,
+                array,
+                nullable: nullable == "YES",
+            };
+            columns.insert((table.clone(), name.clone()), column);
+        }
+
+        Ok(Self(columns))
+    }
+
+    /// Builds the schema of `table` for the columns of its CSV file.
+    pub fn table_schema(
+        &self,
+        table: &str,
+        header: &[Option<String>],
+    ) -> anyhow::Result<TableSchema> {
+        let columns = header
+            .iter()
+            .map(|name| {
+                let name = name.as_deref().unwrap_or_default();
+                self.0
+                    .get(&(table.to_string(), name.to_string()))
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Unknown column `{table}.{name}`"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(TableSchema {
+            name: table.to_string(),
+            columns,
+        })
+    }
+}
+
+/// A parsed field of a CSV file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<Utc>),
+    Text(String),
+    Array(Vec<Value>),
+}
+
+fn parse_scalar(ty: ColumnType, field: &str) -> anyhow::Result<Value> {
+    let value = match ty {
+        ColumnType::Bool => match field {
+            "t" => Value::Bool(true),
+            "f" => Value::Bool(false),
+            _ => return Err(anyhow!("Invalid boolean `{field}`")),
+        },
+        ColumnType::Int16 | ColumnType::Int32 | ColumnType::Int64 => Value::Int(field.parse()?),
+        ColumnType::Float32 | ColumnType::Float64 => Value::Float(field.parse()?),
+        ColumnType::Bytes => Value::Bytes(parse_bytes(field)?),
+        ColumnType::Date => Value::Date(NaiveDate::parse_from_str(field, "%Y-%m-%d")?),
+        ColumnType::Timestamp => Value::Timestamp(NaiveDateTime::parse_from_str(
+            field,
+            "%Y-%m-%d %H:%M:%S%.f",
+        )?),
+        ColumnType::TimestampTz => {
+            let timestamp = DateTime::parse_from_str(field, "%Y-%m-%d %H:%M:%S%.f%#z")?;
+            Value::TimestampTz(timestamp.to_utc())
+        }
+        ColumnType::Json | ColumnType::Text => Value::Text(field.to_string()),
+    };
+
+    Ok(value)
+}
+
+/// Parses the hex format of `bytea` values, e.g. `\x0123`.
+fn parse_bytes(field: &str) -> anyhow::Result<Vec<u8>> {
+    let hex = field
+        .strip_prefix("\\x")
+        .ok_or_else(|| anyhow!("Invalid bytea value `{field}`"))?;
+
+    if hex.len() % 2 != 0 {
+        return Err(anyhow!("Invalid bytea value `{field}`"));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| Ok(u8::from_str_radix(&hex[i..i + 2], 16)?))
+        .collect()
+}
+
+/// Parses the text representation of a one-dimensional array, e.g.
+/// `{foo,"bar baz",NULL}`.
+fn parse_array(field: &str) -> anyhow::Result<Vec<Option<String>>> {
+    let inner = field
+        .strip_prefix('{')
+        .and_then(|field| field.strip_suffix('}'))
+        .ok_or_else(|| anyhow!("Invalid array `{field}`"))?;
+
+    let mut elements = Vec::new();
+    if inner.is_empty() {
+        return Ok(elements);
+    }
+
+    let mut element = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            '\\' => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| anyhow!("Invalid array `{field}`"))?;
+                element.push(escaped);
+            }
+            ',' if !in_quotes => {
+                elements.push(array_element(std::mem::take(&mut element), quoted));
+                quoted = false;
+            }
+            '{' if !in_quotes => {
+                return Err(anyhow!("Multi-dimensional arrays are not supported"));
+            }
+            _ => element.push(c),
+        }
+    }
+    elements.push(array_element(element, quoted));
+
+    Ok(elements)
+}
+
+fn array_element(element: String, quoted: bool) -> Option<String> {
+    (quoted || element != "NULL").then_some(element)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_debug_snapshot;
+
+    fn column(ty: ColumnType, array: bool) -> Column {
+        Column {
+            name: "column".to_string(),
+            ty,
+            array,
+            nullable: true,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let parse = |ty, field| column(ty, false).parse(field).unwrap();
+
+        assert_eq!(parse(ColumnType::Text, None), Value::Null);
+        assert_eq!(
+            parse(ColumnType::Text, Some("")),
+            Value::Text(String::new())
+        );
+        assert_eq!(parse(ColumnType::Bool, Some("t")), Value::Bool(true));
+        assert_eq!(parse(ColumnType::Int64, Some("-42")), Value::Int(-42));
+        assert_eq!(parse(ColumnType::Float64, Some("1.5")), Value::Float(1.5));
+        assert_eq!(
+            parse(ColumnType::Bytes, Some("\\x00ff")),
+            Value::Bytes(vec![0, 255])
+        );
+        assert_debug_snapshot!(parse(ColumnType::Date, Some("2014-11-10")), @"
+        Date(
+            2014-11-10,
+        )
+        ");
+        assert_debug_snapshot!(parse(ColumnType::Timestamp, Some("2017-01-06 14:23:11.123456")), @"
+        Timestamp(
+            2017-01-06T14:23:11.123456,
+        )
+        ");
+        assert_debug_snapshot!(parse(ColumnType::TimestampTz, Some("2017-01-06 14:23:11+01")), @"
+        TimestampTz(
+            2017-01-06T13:23:11Z,
+        )
+        ");
+
+        assert!(column(ColumnType::Bool, false).parse(Some("yes")).is_err());
+        assert!(
+            column(ColumnType::Bytes, false)
+                .parse(Some("\\x0"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_array() {
+        let parse = |ty, field| column(ty, true).parse(Some(field)).unwrap();
+
+        assert_eq!(parse(ColumnType::Text, "{}"), Value::Array(vec![]));
+        assert_debug_snapshot!(parse(ColumnType::Text, r#"{foo,"bar, \"baz\"",NULL,"NULL"}"#), @r#"
+        Array(
+            [
+                Text(
+                    "foo",
+                ),
+                Text(
+                    "bar, \"baz\"",
+                ),
+                Null,
+                Text(
+                    "NULL",
+                ),
+            ],
+        )
+        "#);
+        assert_eq!(
+            parse(ColumnType::Int32, "{1,2}"),
+            Value::Array(vec![Value::Int(1), Value::Int(2)])
+        );
+
+        assert!(
+            column(ColumnType::Int32, true)
+                .parse(Some("{{1},{2}}"))
+                .is_err()
+        );
+        assert!(column(ColumnType::Int32, true).parse(Some("1,2")).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use super::{Column, ColumnType, ColumnTypes, CsvReader, TableSchema, Value};
+use anyhow::{Context, anyhow};
+use arrow_array::{
+    ArrayRef, BinaryArray, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, ListArray, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::DateTime;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::debug;
+
+/// The number of rows that are written to the Parquet file at once.
+const BATCH_SIZE: usize = 64 * 1024;
+
+/// Converts the CSV file of `table` into a Parquet file. Returns the number
+/// of rows.
+pub fn write_parquet_file(
+    csv_path: &Path,
+    parquet_path: &Path,
+    table: &str,
+    types: &ColumnTypes,
+) -> anyhow::Result<u64> {
+    debug!(?parquet_path, "Writing Parquet file…");
+    let csv_file =
+        File::open(csv_path).with_context(|| format!("Failed to open {}", csv_path.display()))?;
+    let mut reader = CsvReader::new(BufReader::new(csv_file));
+
+    let header = reader
+        .read_record()?
+        .ok_or_else(|| anyhow!("{} is missing a header", csv_path.display()))?;
+    let schema = types.table_schema(table, &header)?;
+    let arrow_schema = Arc::new(arrow_schema(&schema));
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let file = File::create(parquet_path)?;
+    let mut writer = ArrowWriter::try_new(file, arrow_schema.clone(), Some(properties))?;
+
+    let mut count = 0;
+    let mut batch = vec![Vec::with_capacity(BATCH_SIZE); schema.columns.len()];
+    loop {
+        let record = reader.read_record()?;
+        if let Some(record) = &record {
+            if record.len() != schema.columns.len() {
+                return Err(anyhow!("Unexpected number of fields in `{table}` record"));
+            }
+
+            for ((values, column), field) in batch.iter_mut().zip(&schema.columns).zip(record) {
+                values.push(column.parse(field.as_deref())?);
+            }
+            count += 1;
+        }
+
+        let batch_len = batch.first().map(Vec::len).unwrap_or_default();
+        if batch_len == BATCH_SIZE || (record.is_none() && batch_len > 0) {
+            let columns = batch
+                .iter_mut()
+                .zip(&schema.columns)
+                .map(|(values, column)| column_array(column, &std::mem::take(values)))
+                .collect::<anyhow::Result<_>>()?;
+
+            let record_batch = RecordBatch::try_new(arrow_schema.clone(), columns)
+                .with_context(|| format!("Failed to create record batch for `{table}`"))?;
+            writer.write(&record_batch)?;
+        }
+
+        if record.is_none() {
+            break;
+        }
+    }
+
+    writer.close()?;
+    Ok(count)
+}
+
+fn arrow_schema(schema: &TableSchema) -> Schema {
+    let fields = schema
+        .columns
+        .iter()
+        .map(|column| {
+            let data_type = match column.array {
+                true => DataType::List(Arc::new(Field::new_list_field(data_type(column.ty), true))),
+                false => data_type(column.ty),
+            };
+            Field::new(&column.name, data_type, column.nullable)
+        })
+        .collect::<Vec<_>>();
+
+    Schema::new(fields)
+}
+
+fn data_type(ty: ColumnType) -> DataType {
+    match ty {
+        ColumnType::Bool => DataType::Boolean,
+        ColumnType::Int16 => DataType::Int16,
+        ColumnType::Int32 => DataType::Int32,
+        ColumnType::Int64 => DataType::Int64,
+        ColumnType::Float32 => DataType::Float32,
+        ColumnType::Float64 => DataType::Float64,
+        ColumnType::Bytes => DataType::Binary,
+        ColumnType::Date => DataType::Date32,
+        ColumnType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, None),
+        ColumnType::TimestampTz => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        ColumnType::Json | ColumnType::Text => DataType::Utf8,
+    }
+}
+
+fn column_array(column: &Column, values: &[Value]) -> anyhow::Result<ArrayRef> {
+    if !column.array {
+        return scalar_array(column.ty, values);
+    }
+
+    let mut elements = Vec::new();
+    let mut lengths = Vec::with_capacity(values.len());
+    let mut validity = Vec::with_capacity(values.len());
+    for value in values {
+        match value {
+            Value::Array(array) => {
+                elements.extend_from_slice(array);
+                lengths.push(array.len());
+                validity.push(true);
+            }
+            _ => {
+                lengths.push(0);
+                validity.push(false);
+            }
+        }
+    }
+
+    let field = Arc::new(Field::new_list_field(data_type(column.ty), true));
+    let offsets = OffsetBuffer::<i32>::try_from_lengths(lengths)?;
+    let values = scalar_array(column.ty, &elements)?;
+    let nulls = Some(NullBuffer::from(validity));
+    Ok(Arc::new(ListArray::try_new(field, offsets, values, nulls)?))
+}
+
+fn scalar_array(ty: ColumnType, values: &[Value]) -> anyhow::Result<ArrayRef> {
+    let ints = || {
+        values.iter().map(|value| match value {
+            Value::Int(int) => Some(*int),
+            _ => None,
+        })
+    };
+    let floats = || {
+        values.iter().map(|value| match value {
+            Value::Float(float) => Some(*float),
+            _ => None,
+        })
+    };
+
+    let array: ArrayRef = match ty {
+        ColumnType::Bool => Arc::new(BooleanArray::from_iter(values.iter().map(
+            |value| match value {
+                Value::Bool(bool) => Some(*bool),
+                _ => None,
+            },
+        ))),
+        ColumnType::Int16 => Arc::new(
+            ints()
+                .map(|int| int.map(i16::try_from).transpose())
+                .collect::<Result<Int16Array, _>>()?,
+        ),
+        ColumnType::Int32 => Arc::new(
+            ints()
+                .map(|int| int.map(i32::try_from).transpose())
+                .collect::<Result<Int32Array, _>>()?,
+        ),
+        ColumnType::Int64 => Arc::new(Int64Array::from_iter(ints())),
+        ColumnType::Float32 => Arc::new(Float32Array::from_iter(
+            floats().map(|float| float.map(|float| float as f32)),
+        )),
+        ColumnType::Float64 => Arc::new(Float64Array::from_iter(floats())),
+        ColumnType::Bytes => Arc::new(BinaryArray::from_iter(values.iter().map(
+            |value| match value {
+                Value::Bytes(bytes) => Some(bytes),
+                _ => None,
+            },
+        ))),
+        ColumnType::Date => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Date(date) => {
+                        let days = date
+                            .signed_duration_since(DateTime::UNIX_EPOCH.date_naive())
+                            .num_days();
+                        Some(i32::try_from(days)).transpose()
+                    }
+                    _ => Ok(None),
+                })
+                .collect::<Result<Date32Array, _>>()?,
+        ),
+        ColumnType::Timestamp => Arc::new(TimestampMicrosecondArray::from_iter(values.iter().map(
+            |value| match value {
+                Value::Timestamp(timestamp) => Some(timestamp.and_utc().timestamp_micros()),
+                _ => None,
+            },
+        ))),
+        ColumnType::TimestampTz => Arc::new(
+            TimestampMicrosecondArray::from_iter(values.iter().map(|value| match value {
+                Value::TimestampTz(timestamp) => Some(timestamp.timestamp_micros()),
+                _ => None,
+            }))
+            .with_timezone("UTC"),
+        ),
+        ColumnType::Json | ColumnType::Text => Arc::new(StringArray::from_iter(values.iter().map(
+            |value| match value {
+                Value::Text(text) => Some(text),
+                _ => None,
+            },
+        ))),
+    };
+
+    Ok(array)
+}
@@ -0,0 +1,142 @@
+use super::{ColumnType, ColumnTypes, CsvReader, TableSchema, Value};
+use anyhow::{Context, anyhow};
+use rusqlite::Connection;
+use rusqlite::types::Value as SqliteValue;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use tracing::debug;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// Imports the CSV files of `tables` in `data_dir` into a new SQLite database
+/// at `path`.
+///
+/// Dates and timestamps are stored as ISO 8601 text in UTC, booleans as `0`
+/// and `1`, and arrays as JSON arrays.
+pub fn write_sqlite_database(
+    data_dir: &Path,
+    tables: &[String],
+    types: &ColumnTypes,
+    path: &Path,
+) -> anyhow::Result<()> {
+    debug!(?path, "Writing SQLite database…");
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch("PRAGMA journal_mode = OFF; PRAGMA synchronous = OFF;")?;
+
+    for table in tables {
+        let csv_path = data_dir.join(table).with_extension("csv");
+        let csv_file = File::open(&csv_path)
+            .with_context(|| format!("Failed to open {}", csv_path.display()))?;
+        let mut reader = CsvReader::new(BufReader::new(csv_file));
+
+        let header = reader
+            .read_record()?
+            .ok_or_else(|| anyhow!("{} is missing a header", csv_path.display()))?;
+        let schema = types.table_schema(table, &header)?;
+
+        debug!("Importing `{table}` into SQLite database…");
+        let tx = conn.transaction()?;
+        tx.execute(&create_table_sql(&schema), [])?;
+
+        {
+            let mut statement = tx.prepare(&insert_sql(&schema))?;
+            while let Some(record) = reader.read_record()? {
+                if record.len() != schema.columns.len() {
+                    return Err(anyhow!("Unexpected number of fields in `{table}` record"));
+                }
+
+                let params = schema
+                    .columns
+                    .iter()
+                    .zip(record)
+                    .map(|(column, field)| Ok(sqlite_value(column.parse(field.as_deref())?)))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                statement.execute(rusqlite::params_from_iter(params))?;
+            }
+        }
+
+        tx.commit()
+            .with_context(|| format!("Failed to import `{table}` into SQLite database"))?;
+    }
+
+    Ok(())
+}
+
+fn create_table_sql(schema: &TableSchema) -> String {
+    let columns = schema
+        .columns
+        .iter()
+        .map(|column| {
+            let ty = match (column.array, column.ty) {
+                (true, _) => "TEXT",
+                (
+                    false,
+                    ColumnType::Bool | ColumnType::Int16 | ColumnType::Int32 | ColumnType::Int64,
+                ) => "INTEGER",
+                (false, ColumnType::Float32 | ColumnType::Float64) => "REAL",
+                (false, ColumnType::Bytes) => "BLOB",
+                (false, _) => "TEXT",
+            };
+            let not_null = if column.nullable { "" } else { " NOT NULL" };
+            format!("\"{}\" {ty}{not_null}", column.name)
+        })
+        .collect::<Vec<_>>();
+
+    format!("CREATE TABLE \"{}\" ({})", schema.name, columns.join(", "))
+}
+
+fn insert_sql(schema: &TableSchema) -> String {
+    let columns = schema
+        .columns
+        .iter()
+        .map(|column| format!("\"{}\"", column.name))
+        .collect::<Vec<_>>();
+    let placeholders = vec!["?"; columns.len()];
+
+    format!(
+        "INSERT INTO \"{}\" ({}) VALUES ({})",
+        schema.name,
+        columns.join(", "),
+        placeholders.join(", ")
+    )
+}
+
+fn sqlite_value(value: Value) -> SqliteValue {
+    match value {
+        Value::Null => SqliteValue::Null,
+        Value::Bool(bool) => SqliteValue::Integer(bool.into()),
+        Value::Int(int) => SqliteValue::Integer(int),
+        Value::Float(float) => SqliteValue::Real(float),
+        Value::Bytes(bytes) => SqliteValue::Blob(bytes),
+        Value::Date(date) => SqliteValue::Text(date.format(DATE_FORMAT).to_string()),
+        Value::Timestamp(timestamp) => {
+            SqliteValue::Text(timestamp.format(TIMESTAMP_FORMAT).to_string())
+        }
+        Value::TimestampTz(timestamp) => {
+            SqliteValue::Text(timestamp.format(TIMESTAMP_FORMAT).to_string())
+        }
+        Value::Text(text) => SqliteValue::Text(text),
+        Value::Array(values) => {
+            let values = values.into_iter().map(json_value).collect();
+            SqliteValue::Text(serde_json::Value::Array(values).to_string())
+        }
+    }
+}
+
+fn json_value(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(bool) => bool.into(),
+        Value::Int(int) => int.into(),
+        Value::Float(float) => float.into(),
+        Value::Bytes(bytes) => bytes.into(),
+        Value::Date(date) => date.format(DATE_FORMAT).to_string().into(),
+        Value::Timestamp(timestamp) => timestamp.format(TIMESTAMP_FORMAT).to_string().into(),
+        Value::TimestampTz(timestamp) => timestamp.format(TIMESTAMP_FORMAT).to_string().into(),
+        Value::Text(text) => text.into(),
+        Value::Array(values) => values.into_iter().map(json_value).collect(),
+    }
+}
@@ -24,6 +24,38 @@ pub struct TableConfig {
     pub columns: BTreeMap<String, ColumnVisibility>,
     #[serde(default)]
     pub column_defaults: BTreeMap<String, String>,
+    /// Configures how the table is exported in delta dumps. Tables without
+    /// this configuration are exported in full in every delta dump.
+    pub delta: Option<DeltaConfig>,
+}
+
+/// Configuration for the incremental export of a table. The `key` field lists
+/// the columns that identify a row, i.e. the primary key of the table. The
+/// `cursor` field is a valid SQL expression that evaluates to the time of the
+/// last change of a row, which is compared against the change cursor of the
+/// table to find the rows that changed since the previous dump. The
+/// `related` field lists other tables whose changes also count as changes of
+/// the rows they refer to. If `deletions` is set, the keys of all exported
+/// rows are compared against the ones of the previous dump to find the rows
+/// that were deleted.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeltaConfig {
+    pub key: Vec<String>,
+    pub cursor: String,
+    #[serde(default)]
+    pub related: Vec<RelatedConfig>,
+    #[serde(default)]
+    pub deletions: bool,
+}
+
+/// A table whose changes count as changes of the rows of another table. The
+/// `key` field lists the columns that refer to the key of the other table,
+/// and the `cursor` field is the change cursor of the related table itself.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RelatedConfig {
+    pub table: String,
+    pub key: Vec<String>,
+    pub cursor: String,
 }
 
 /// Maps table names to the respective configurations. Used to load `dump_db.toml`.
@@ -31,6 +63,14 @@ pub struct TableConfig {
 #[serde(transparent)]
 pub struct VisibilityConfig(pub BTreeMap<String, TableConfig>);
 
+impl TableConfig {
+    pub fn has_public_columns(&self) -> bool {
+        self.columns
+            .values()
+            .any(|&vis| vis == ColumnVisibility::Public)
+    }
+}
+
 impl VisibilityConfig {
     pub fn get() -> Self {
         toml::from_str(include_str!("dump-db.toml")).unwrap()
@@ -0,0 +1,324 @@
+use crate::configuration::VisibilityConfig;
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// How far the change cursors lag behind the time of a dump.
+///
+/// Rows are exported based on the time of their last change, but changes of
+/// transactions that were still running while the dump was created only
+/// become visible later. Exporting these rows again in the next delta dump is
+/// harmless, since the import script upserts them.
+pub const CURSOR_OVERLAP: TimeDelta = TimeDelta::minutes(30);
+
+/// How a table is exported in a delta dump.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableMode {
+    /// All rows are exported, and replace the rows of the table on import.
+    Full,
+    /// Only the rows that changed since the change cursor of the table are
+    /// exported, and upserted on import.
+    Delta,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TableManifest {
+    pub mode: TableMode,
+    /// The change cursor of the table, for tables in `delta` mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+    /// Whether the dump contains a `deleted/<table>.csv` file with the keys of
+    /// the rows that were deleted since the previous dump.
+    #[serde(default)]
+    pub deletions: bool,
+}
+
+/// The contents of the `manifest.json` file of a delta dump.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeltaManifest {
+    /// The time of this dump.
+    pub timestamp: DateTime<Utc>,
+    /// The time of the dump that this delta is based on. The delta can be
+    /// applied to any database that contains a full or delta dump from this
+    /// time or later, but older than [`Self::timestamp`].
+    pub previous: DateTime<Utc>,
+    pub crates_io_commit: String,
+    pub tables: BTreeMap<String, TableManifest>,
+}
+
+impl DeltaManifest {
+    /// Determines how the tables are exported, based on the state of the
+    /// previous dump.
+    pub fn new(timestamp: DateTime<Utc>, previous: &DeltaState) -> Self {
+        let config = VisibilityConfig::get();
+
+        let tables = config
+            .0
+            .iter()
+            .filter(|(_, table)| table.has_public_columns())
+            .map(|(name, table)| {
+                let since = previous.cursors.get(name).copied();
+                let manifest = match (&table.delta, since) {
+                    (Some(delta), Some(since)) => TableManifest {
+                        mode: TableMode::Delta,
+                        since: Some(since),
+                        deletions: delta.deletions && previous.has_keys(name),
+                    },
+                    _ => TableManifest {
+                        mode: TableMode::Full,
+                        since: None,
+                        deletions: false,
+                    },
+                };
+                (name.clone(), manifest)
+            })
+            .collect();
+
+        Self {
+            timestamp,
+            previous: previous.timestamp,
+            crates_io_commit: crates_io_version::commit()
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| "unknown".to_owned()),
+            tables,
+        }
+    }
+}
+
+/// The state that is carried over from one delta dump to the next.
+///
+/// Besides the `state.json` file, the state directory contains the sorted
+/// keys of the tables with deletion tracking in the `keys/` directory.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeltaState {
+    /// The time of the dump that this state belongs to.
+    pub timestamp: DateTime<Utc>,
+    /// The change cursors of the tables. Rows that changed after the cursor
+    /// of their table are exported in the next delta dump.
+    pub cursors: BTreeMap<String, DateTime<Utc>>,
+    #[serde(skip)]
+    directory: PathBuf,
+}
+
+impl DeltaState {
+    /// Creates the state of a dump from `timestamp`, whose keys were
+    /// exported to the `keys/` directory of `directory`.
+    pub fn new(timestamp: DateTime<Utc>, directory: &Path) -> Self {
+        let cursor = timestamp - CURSOR_OVERLAP;
+        let cursors = VisibilityConfig::get()
+            .0
+            .into_iter()
+            .filter(|(_, table)| table.delta.is_some())
+            .map(|(name, _)| (name, cursor))
+            .collect();
+
+        Self {
+            timestamp,
+            cursors,
+            directory: directory.to_path_buf(),
+        }
+    }
+
+    /// Reads the state from the `state.json` file in `directory`.
+    pub fn read(directory: &Path) -> anyhow::Result<Self> {
+        let path = directory.join("state.json");
+        let file =
+            File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+        let mut state: Self =
+            serde_json::from_reader(BufReader::new(file)).context("Failed to parse state.json")?;
+
+        state.directory = directory.to_path_buf();
+        Ok(state)
+    }
+
+    /// Writes the state to the `state.json` file in its directory.
+    pub fn write(&self) -> anyhow::Result<()> {
+        let path = self.directory.join("state.json");
+        debug!(?path, "Writing state.json file…");
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub(crate) fn keys_path(&self, table: &str) -> PathBuf {
+        self.directory
+            .join("keys")
+            .join(table)
+            .with_extension("csv")
+    }
+
+    fn has_keys(&self, table: &str) -> bool {
+        self.keys_path(table).exists()
+    }
+
+    /// Packs the state into a tarball, so that it can be stored until the
+    /// next delta dump.
+    pub fn archive(&self) -> anyhow::Result<tempfile::NamedTempFile> {
+        debug!("Creating state tarball…");
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let encoder =
+            flate2::write::GzEncoder::new(tempfile.as_file(), flate2::Compression::default());
+
+        let mut tar = tar::Builder::new(encoder);
+        tar.append_path_with_name(self.directory.join("state.json"), "state.json")?;
+        tar.append_dir_all("keys", self.directory.join("keys"))?;
+        tar.into_inner()?.finish()?;
+
+        Ok(tempfile)
+    }
+
+    /// Unpacks a tarball created by [`Self::archive()`] into `directory`.
+    pub fn unpack(archive: impl Read, directory: &Path) -> anyhow::Result<Self> {
+        debug!(?directory, "Unpacking state tarball…");
+        let decoder = flate2::read::GzDecoder::new(archive);
+        tar::Archive::new(decoder)
+            .unpack(directory)
+            .context("Failed to unpack state tarball")?;
+
+        Self::read(directory)
+    }
+}
+
+/// Writes the keys that are in the sorted `previous` keys file, but not in the
+/// sorted `current` keys file, to `deleted`.
+///
+/// Both files are expected to be CSV files with a header, whose rows are
+/// sorted by the text representation of their columns, as generated by the
+/// export script. Returns the number of deleted keys.
+pub fn write_deleted_keys(previous: &Path, current: &Path, deleted: &Path) -> anyhow::Result<u64> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .with_context(|| format!("Failed to open {}", path.display()))
+    };
+
+    let mut previous = open(previous)?.lines();
+    let mut current = open(current)?.lines();
+    let mut deleted = BufWriter::new(File::create(deleted)?);
+
+    let header = current
+        .next()
+        .ok_or_else(|| anyhow!("Keys file is missing a header"))??;
+    if previous.next().transpose()?.as_ref() != Some(&header) {
+        return Err(anyhow!("Keys files have different headers"));
+    }
+    writeln!(deleted, "{header}")?;
+
+    let mut count = 0;
+    let mut current_key = current.next().transpose()?;
+    while let Some(previous_key) = previous.next().transpose()? {
+        // Skip the keys that were added since the previous dump
+        while let Some(key) = &current_key
+            && compare_keys(key, &previous_key) == Ordering::Less
+        {
+            current_key = current.next().transpose()?;
+        }
+
+        if current_key.as_ref() == Some(&previous_key) {
+            current_key = current.next().transpose()?;
+        } else {
+            writeln!(deleted, "{previous_key}")?;
+            count += 1;
+        }
+    }
+
+    deleted.flush()?;
+    Ok(count)
+}
+
+/// Compares two CSV rows column by column, which matches the `ORDER BY`
+/// clause of the exported keys, as long as the keys do not need quoting.
+fn compare_keys(a: &str, b: &str) -> Ordering {
+    a.split(',').cmp(b.split(','))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+    use std::fs;
+
+    fn deleted_keys(previous: &str, current: &str) -> String {
+        let tempdir = tempfile::tempdir().unwrap();
+        let p = tempdir.path();
+
+        fs::write(p.join("previous.csv"), previous).unwrap();
+        fs::write(p.join("current.csv"), current).unwrap();
+
+        let deleted = p.join("deleted.csv");
+        write_deleted_keys(&p.join("previous.csv"), &p.join("current.csv"), &deleted).unwrap();
+        fs::read_to_string(deleted).unwrap()
+    }
+
+    #[test]
+    fn test_write_deleted_keys() {
+        let previous = "id\n1\n10\n2\n3\n5\n";
+        let current = "id\n10\n11\n3\n4\n6\n";
+        assert_snapshot!(deleted_keys(previous, current), @r"
+        id
+        1
+        2
+        5
+        ");
+
+        let previous = "crate_id,owner_id,owner_kind\n1,1,0\n1,2,0\n1,2,1\n2,1,0\n";
+        let current = "crate_id,owner_id,owner_kind\n1,2,0\n1,3,0\n";
+        assert_snapshot!(deleted_keys(previous, current), @r"
+        crate_id,owner_id,owner_kind
+        1,1,0
+        1,2,1
+        2,1,0
+        ");
+
+        assert_snapshot!(deleted_keys("id\n", "id\n1\n"), @"id");
+    }
+
+    #[test]
+    fn test_state_archive() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let state_dir = tempdir.path().join("state");
+        fs::create_dir_all(state_dir.join("keys")).unwrap();
+        fs::write(state_dir.join("keys/crates.csv"), "id\n1\n").unwrap();
+
+        let timestamp = "2026-01-02T03:04:05Z".parse().unwrap();
+        let state = DeltaState::new(timestamp, &state_dir);
+        state.write().unwrap();
+        assert_eq!(state.cursors["crates"], timestamp - CURSOR_OVERLAP);
+        assert!(!state.cursors.contains_key("users"));
+
+        let archive = state.archive().unwrap();
+        let unpacked_dir = tempdir.path().join("unpacked");
+        let unpacked = DeltaState::unpack(File::open(archive.path()).unwrap(), &unpacked_dir);
+        let unpacked = unpacked.unwrap();
+        assert_eq!(unpacked.timestamp, timestamp);
+        assert_eq!(unpacked.cursors, state.cursors);
+        assert!(unpacked.has_keys("crates"));
+        assert!(!unpacked.has_keys("versions"));
+    }
+
+    #[test]
+    fn test_write_deleted_keys_with_different_headers() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let p = tempdir.path();
+
+        fs::write(p.join("previous.csv"), "id\n1\n").unwrap();
+        fs::write(p.join("current.csv"), "crate_id\n1\n").unwrap();
+
+        let deleted = p.join("deleted.csv");
+        let result = write_deleted_keys(&p.join("previous.csv"), &p.join("current.csv"), &deleted);
+        assert!(result.is_err());
+    }
+}
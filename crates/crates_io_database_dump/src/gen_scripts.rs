@@ -1,14 +1,42 @@
 use crate::configuration::{ColumnVisibility, TableConfig, VisibilityConfig};
+use crate::delta::{DeltaManifest, TableMode};
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use std::{fs::File, path::Path};
 use tracing::debug;
 
-pub fn gen_scripts(export_script: &Path, import_script: &Path) -> anyhow::Result<()> {
+pub fn gen_scripts(
+    export_script: &Path,
+    import_script: &Path,
+    timestamp: &DateTime<Utc>,
+) -> anyhow::Result<()> {
     let config = VisibilityConfig::get();
     let export_sql = File::create(export_script).context("Failed to create export script file")?;
     let import_sql = File::create(import_script).context("Failed to create import script file")?;
-    config.gen_psql_scripts(export_sql, import_sql)
+    config.gen_psql_scripts(export_sql, import_sql, timestamp)
+}
+
+/// Generates the scripts of a delta dump. The import script applies the delta
+/// on top of the database dump it is based on, and refuses to run if the
+/// dumps are not imported in order.
+pub fn gen_delta_scripts(
+    export_script: &Path,
+    import_script: &Path,
+    manifest: &DeltaManifest,
+) -> anyhow::Result<()> {
+    let config = VisibilityConfig::get();
+    let export_sql = File::create(export_script).context("Failed to create export script file")?;
+    let import_sql = File::create(import_script).context("Failed to create import script file")?;
+    config.gen_delta_psql_scripts(export_sql, import_sql, manifest)
+}
+
+/// Generates a script that only exports the keys that are needed to detect
+/// deleted rows in the next delta dump.
+pub fn gen_keys_script(export_script: &Path) -> anyhow::Result<()> {
+    let config = VisibilityConfig::get();
+    let export_sql = File::create(export_script).context("Failed to create export script file")?;
+    config.gen_keys_psql_script(export_sql)
 }
 
 /// Subset of the configuration data to be passed on to the template.
@@ -26,13 +54,47 @@ struct ColumnDefault<'a> {
     value: &'a str,
 }
 
+/// Additional configuration data of a table in a delta dump.
+#[derive(Debug, Serialize)]
+struct DeltaTableContext<'a> {
+    #[serde(flatten)]
+    table: HandlebarsTableContext<'a>,
+    /// Export only the rows that changed since this time, and upsert them on
+    /// import. If `None`, the table is exported and imported in full.
+    since: Option<String>,
+    /// Condition that matches the rows that changed since `since`.
+    changed_condition: Option<String>,
+    key: Option<String>,
+    /// `SET` clause for the public columns that are not part of the key.
+    updates: Option<String>,
+    /// `ORDER BY` clause for the exported keys, or `None` if the keys are not
+    /// exported.
+    key_order: Option<String>,
+    /// Condition that matches the rows of the table with the ones in the
+    /// table of deleted keys, or `None` if deletions are not applied.
+    deleted_condition: Option<String>,
+}
+
+fn quoted(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|column| format!("\"{column}\""))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl TableConfig {
-    fn template_context<'a>(&'a self, name: &'a str) -> Option<HandlebarsTableContext<'a>> {
-        let columns = self
-            .columns
+    fn public_columns(&self) -> impl Iterator<Item = &String> {
+        self.columns
             .iter()
             .filter(|&(_, &vis)| vis == ColumnVisibility::Public)
-            .map(|(col, _)| format!("\"{col}\""))
+            .map(|(col, _)| col)
+    }
+
+    fn template_context<'a>(&'a self, name: &'a str) -> Option<HandlebarsTableContext<'a>> {
+        let columns = self
+            .public_columns()
+            .map(|col| format!("\"{col}\""))
             .collect::<Vec<String>>()
             .join(", ");
         if columns.is_empty() {
@@ -55,64 +117,228 @@ impl TableConfig {
             })
         }
     }
+
+    fn delta_template_context<'a>(
+        &'a self,
+        name: &'a str,
+        manifest: Option<&DeltaManifest>,
+    ) -> Option<DeltaTableContext<'a>> {
+        let table = self.template_context(name)?;
+
+        let mut context = DeltaTableContext {
+            table,
+            since: None,
+            changed_condition: None,
+            key: None,
+            updates: None,
+            key_order: None,
+            deleted_condition: None,
+        };
+
+        let Some(delta) = &self.delta else {
+            return Some(context);
+        };
+
+        context.key = Some(quoted(&delta.key));
+
+        // Sort the keys by their text representation, so that the keys of
+        // two dumps can be compared without loading them into memory.
+        if delta.deletions {
+            let key_order = delta
+                .key
+                .iter()
+                .map(|column| format!("\"{column}\"::text COLLATE \"C\""))
+                .collect::<Vec<_>>()
+                .join(", ");
+            context.key_order = Some(key_order);
+        }
+
+        let table_manifest = manifest.and_then(|manifest| manifest.tables.get(name));
+        if let Some(table_manifest) = table_manifest
+            && table_manifest.mode == TableMode::Delta
+        {
+            context.since = table_manifest.since.map(|since| since.to_rfc3339());
+            context.changed_condition = context.since.as_ref().map(|since| {
+                let since = format!("'{since}'::timestamptz");
+
+                // The related tables are checked with a single subquery each,
+                // instead of looking up the related rows of every row.
+                let mut conditions = vec![format!("({}) > {since}", delta.cursor)];
+                for related in &delta.related {
+                    conditions.push(format!(
+                        "({}) IN (SELECT {} FROM \"{}\" WHERE ({}) > {since})",
+                        quoted(&delta.key),
+                        quoted(&related.key),
+                        related.table,
+                        related.cursor,
+                    ));
+                }
+                conditions.join(" OR ")
+            });
+
+            let updates = self
+                .public_columns()
+                .filter(|column| !delta.key.contains(column))
+                .map(|column| format!("\"{column}\" = EXCLUDED.\"{column}\""))
+                .collect::<Vec<_>>();
+            if !updates.is_empty() {
+                context.updates = Some(updates.join(", "));
+            }
+
+            if table_manifest.deletions {
+                let condition = delta
+                    .key
+                    .iter()
+                    .map(|column| {
+                        format!("\"{name}\".\"{column}\" = \"deleted_keys_{name}\".\"{column}\"")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" AND ");
+                context.deleted_condition = Some(condition);
+            }
+        }
+
+        Some(context)
+    }
 }
 
 /// Subset of the configuration data to be passed on to the template.
 #[derive(Debug, Serialize)]
 struct TemplateContext<'a> {
     tables: Vec<HandlebarsTableContext<'a>>,
+    timestamp: String,
+}
+
+/// Subset of the configuration data and the delta manifest to be passed on to
+/// the delta templates.
+#[derive(Debug, Serialize)]
+struct DeltaTemplateContext<'a> {
+    tables: Vec<DeltaTableContext<'a>>,
+    timestamp: Option<String>,
+    previous: Option<String>,
+    keys_only: bool,
 }
 
 impl VisibilityConfig {
-    fn template_context(&self) -> TemplateContext<'_> {
+    fn template_context(&self, timestamp: &DateTime<Utc>) -> TemplateContext<'_> {
         let tables = self
             .topological_sort()
             .into_iter()
             .filter_map(|table| self.0[table].template_context(table))
             .collect();
-        TemplateContext { tables }
+        let timestamp = timestamp.to_rfc3339();
+        TemplateContext { tables, timestamp }
     }
 
-    fn gen_psql_scripts<W>(&self, mut export_writer: W, mut import_writer: W) -> anyhow::Result<()>
+    fn delta_template_context(&self, manifest: Option<&DeltaManifest>) -> DeltaTemplateContext<'_> {
+        let tables = self
+            .topological_sort()
+            .into_iter()
+            .filter_map(|table| self.0[table].delta_template_context(table, manifest))
+            .collect();
+        DeltaTemplateContext {
+            tables,
+            timestamp: manifest.map(|manifest| manifest.timestamp.to_rfc3339()),
+            previous: manifest.map(|manifest| manifest.previous.to_rfc3339()),
+            keys_only: manifest.is_none(),
+        }
+    }
+
+    fn gen_psql_scripts<W>(
+        &self,
+        export_writer: W,
+        import_writer: W,
+        timestamp: &DateTime<Utc>,
+    ) -> anyhow::Result<()>
     where
         W: std::io::Write,
     {
-        use minijinja::Environment;
-
-        let mut env = Environment::new();
-        env.add_template("dump-export.sql", include_str!("dump-export.sql.j2"))
-            .context("Failed to load dump-export.sql.j2 template")?;
-        env.add_template("dump-import.sql", include_str!("dump-import.sql.j2"))
-            .context("Failed to load dump-import.sql.j2 template")?;
-
-        let context = self.template_context();
+        let context = self.template_context(timestamp);
+        render_scripts(
+            ("dump-export.sql", include_str!("dump-export.sql.j2")),
+            ("dump-import.sql", include_str!("dump-import.sql.j2")),
+            &context,
+            export_writer,
+            import_writer,
+        )
+    }
 
-        debug!("Rendering dump-export.sql file…");
-        let export_sql = env
-            .get_template("dump-export.sql")
-            .unwrap()
-            .render(&context)
-            .context("Failed to render dump-export.sql file")?;
+    fn gen_delta_psql_scripts<W>(
+        &self,
+        export_writer: W,
+        import_writer: W,
+        manifest: &DeltaManifest,
+    ) -> anyhow::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let context = self.delta_template_context(Some(manifest));
+        render_scripts(
+            ("delta-export.sql", include_str!("delta-export.sql.j2")),
+            ("delta-import.sql", include_str!("delta-import.sql.j2")),
+            &context,
+            export_writer,
+            import_writer,
+        )
+    }
 
-        debug!("Rendering dump-import.sql file…");
-        let import_sql = env
-            .get_template("dump-import.sql")
-            .unwrap()
-            .render(&context)
-            .context("Failed to render dump-import.sql file")?;
+    fn gen_keys_psql_script<W>(&self, mut export_writer: W) -> anyhow::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let context = self.delta_template_context(None);
+        let export_sql = render_template(
+            "delta-export.sql",
+            include_str!("delta-export.sql.j2"),
+            &context,
+        )?;
 
-        debug!("Writing dump-export.sql file…");
+        debug!("Writing delta-export.sql file…");
         export_writer
             .write_all(export_sql.as_bytes())
-            .context("Failed to write dump-export.sql file")?;
+            .context("Failed to write delta-export.sql file")
+    }
+}
 
-        debug!("Writing dump-import.sql file…");
-        import_writer
-            .write_all(import_sql.as_bytes())
-            .context("Failed to write dump-import.sql file")?;
+fn render_template<S: Serialize>(name: &str, source: &str, context: &S) -> anyhow::Result<String> {
+    use minijinja::Environment;
 
-        Ok(())
-    }
+    let mut env = Environment::new();
+    env.add_template(name, source)
+        .with_context(|| format!("Failed to load {name}.j2 template"))?;
+
+    debug!("Rendering {name} file…");
+    env.get_template(name)
+        .unwrap()
+        .render(context)
+        .with_context(|| format!("Failed to render {name} file"))
+}
+
+fn render_scripts<S, W>(
+    (export_name, export_source): (&str, &str),
+    (import_name, import_source): (&str, &str),
+    context: &S,
+    mut export_writer: W,
+    mut import_writer: W,
+) -> anyhow::Result<()>
+where
+    S: Serialize,
+    W: std::io::Write,
+{
+    let export_sql = render_template(export_name, export_source, context)?;
+    let import_sql = render_template(import_name, import_source, context)?;
+
+    debug!("Writing {export_name} file…");
+    export_writer
+        .write_all(export_sql.as_bytes())
+        .with_context(|| format!("Failed to write {export_name} file"))?;
+
+    debug!("Writing {import_name} file…");
+    import_writer
+        .write_all(import_sql.as_bytes())
+        .with_context(|| format!("Failed to write {import_name} file"))?;
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,5 +1,7 @@
 #![doc = include_str!("../README.md")]
 
+use crate::columnar::{ColumnTypes, write_parquet_file, write_sqlite_database};
+use crate::delta::write_deleted_keys;
 use anyhow::{Context, anyhow};
 use serde::Serialize;
 use std::fs;
@@ -9,11 +11,14 @@ use std::process::Command;
 use tracing::debug;
 use zip::write::SimpleFileOptions;
 
+mod columnar;
 mod configuration;
+mod delta;
 mod gen_scripts;
 
 pub use configuration::VisibilityConfig;
-pub use gen_scripts::gen_scripts;
+pub use delta::{CURSOR_OVERLAP, DeltaManifest, DeltaState, TableManifest, TableMode};
+pub use gen_scripts::{gen_delta_scripts, gen_keys_script, gen_scripts};
 
 /// Manages the export directory.
 ///
@@ -76,13 +81,132 @@ impl DumpDirectory {
             .context("Failed to create database dump")
     }
 
+    /// Converts the CSV files of the `data` directory into Parquet files and a
+    /// SQLite database in the `columnar` directory, using the column types of
+    /// the database at `database_url`. Has to be called after
+    /// [`Self::populate()`].
+    pub fn populate_columnar(&self, database_url: &str) -> anyhow::Result<()> {
+        let columnar_dir = self.path().join("columnar");
+        fs::create_dir_all(columnar_dir.join("parquet"))
+            .context("Failed to create `columnar` directory")?;
+
+        debug!("Exporting column types…");
+        let script = columnar_dir.join("columns.sql");
+        fs::write(&script, columnar::COLUMNS_SCRIPT).context("Failed to write columns.sql")?;
+        self.run_psql_in(&script, self.path(), database_url)?;
+        let types = ColumnTypes::read(&columnar_dir.join("columns.csv"))?;
+
+        let data_dir = self.path().join("data");
+        let visibility_config = VisibilityConfig::get();
+        let tables = visibility_config
+            .topological_sort()
+            .into_iter()
+            .filter(|table| data_dir.join(table).with_extension("csv").exists())
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        for table in &tables {
+            let csv_path = data_dir.join(table).with_extension("csv");
+            let parquet_path = columnar_dir.join("parquet").join(table);
+            let parquet_path = parquet_path.with_extension("parquet");
+            let count = write_parquet_file(&csv_path, &parquet_path, table, &types)
+                .with_context(|| format!("Failed to write Parquet file for `{table}`"))?;
+            debug!("Wrote {count} rows of `{table}` to Parquet file");
+        }
+
+        let sqlite_path = columnar_dir.join("db-dump.sqlite");
+        write_sqlite_database(&data_dir, &tables, &types, &sqlite_path)
+            .context("Failed to write SQLite database")
+    }
+
+    /// Generates a delta export directory (README, manifest,
+    /// `export.sql`/`import.sql`, and CSV files with the changed and deleted
+    /// rows) with the changes since the `previous` dump.
+    ///
+    /// Returns the manifest of the delta dump, and the state for the next
+    /// delta dump. If there is no previous dump, only the state is generated,
+    /// and no manifest is returned.
+    pub fn populate_delta(
+        &self,
+        database_url: &str,
+        previous: Option<&DeltaState>,
+    ) -> anyhow::Result<(Option<DeltaManifest>, DeltaState)> {
+        let state_dir = self.path().join("state");
+        fs::create_dir_all(state_dir.join("keys")).context("Failed to create `keys` directory")?;
+        let state = DeltaState::new(self.timestamp, &state_dir);
+
+        let Some(previous) = previous else {
+            debug!("Generating export.sql file for the keys…");
+            let export_script = state_dir.join("export.sql");
+            gen_keys_script(&export_script).context("Failed to generate export script")?;
+            self.run_psql_in(&export_script, self.path(), database_url)?;
+
+            state.write().context("Failed to write state.json file")?;
+            return Ok((None, state));
+        };
+
+        let manifest = DeltaManifest::new(self.timestamp, previous);
+
+        self.add_delta_readme()
+            .context("Failed to write README.md file")?;
+
+        let path = self.path().join("manifest.json");
+        debug!(?path, "Writing manifest.json file…");
+        let file = File::create(path).context("Failed to create manifest.json file")?;
+        serde_json::to_writer_pretty(file, &manifest)
+            .context("Failed to write manifest.json file")?;
+
+        debug!("Generating export.sql and import.sql files…");
+        let export_script = self.path().join("export.sql");
+        let import_script = self.path().join("import.sql");
+        gen_delta_scripts(&export_script, &import_script, &manifest)
+            .context("Failed to generate export/import scripts")?;
+
+        debug!("Filling data folder…");
+        fs::create_dir(self.path().join("data")).context("Failed to create `data` directory")?;
+        self.run_psql(&export_script, database_url)?;
+
+        let deletions = manifest
+            .tables
+            .iter()
+            .filter(|(_, table)| table.deletions)
+            .map(|(name, _)| name);
+
+        let deleted_dir = self.path().join("deleted");
+        for table in deletions {
+            if !deleted_dir.exists() {
+                fs::create_dir(&deleted_dir).context("Failed to create `deleted` directory")?;
+            }
+
+            let deleted = deleted_dir.join(table).with_extension("csv");
+            let count = write_deleted_keys(
+                &previous.keys_path(table),
+                &state.keys_path(table),
+                &deleted,
+            )
+            .with_context(|| format!("Failed to find deleted rows of `{table}`"))?;
+            debug!("Found {count} deleted rows in `{table}`");
+        }
+
+        state.write().context("Failed to write state.json file")?;
+        Ok((Some(manifest), state))
+    }
+
     fn add_readme(&self) -> anyhow::Result<()> {
+        self.write_readme(include_bytes!("readme_for_tarball.md"))
+    }
+
+    fn add_delta_readme(&self) -> anyhow::Result<()> {
+        self.write_readme(include_bytes!("readme_for_delta_tarball.md"))
+    }
+
+    fn write_readme(&self, content: &[u8]) -> anyhow::Result<()> {
         use std::io::Write;
 
         let path = self.path().join("README.md");
         debug!(?path, "Writing README.md file…");
         let mut readme = File::create(path)?;
-        readme.write_all(include_bytes!("readme_for_tarball.md"))?;
+        readme.write_all(content)?;
         Ok(())
     }
 
@@ -146,7 +270,7 @@ impl DumpDirectory {
         debug!("Generating export.sql and import.sql files…");
         let export_script = self.path().join("export.sql");
         let import_script = self.path().join("import.sql");
-        gen_scripts(&export_script, &import_script)
+        gen_scripts(&export_script, &import_script, &self.timestamp)
             .context("Failed to generate export/import scripts")?;
 
         debug!("Filling data folder…");
@@ -156,6 +280,17 @@ impl DumpDirectory {
     }
 
     pub fn run_psql(&self, script: &Path, database_url: &str) -> anyhow::Result<()> {
+        self.run_psql_in(script, script.parent().unwrap(), database_url)
+    }
+
+    /// Runs a psql script with `directory` as the working directory, which
+    /// relative paths in the script are resolved against.
+    fn run_psql_in(
+        &self,
+        script: &Path,
+        directory: &Path,
+        database_url: &str,
+    ) -> anyhow::Result<()> {
         debug!(?script, "Running psql script…");
         let psql_script =
             File::open(script).with_context(|| format!("Failed to open {}", script.display()))?;
@@ -164,7 +299,7 @@ impl DumpDirectory {
         let psql = Command::new(&program)
             .arg("--no-psqlrc")
             .arg(database_url)
-            .current_dir(script.parent().unwrap())
+            .current_dir(directory)
             .stdin(psql_script)
             .stdout(std::process::Stdio::null())
             .stderr(std::process::Stdio::piped())
@@ -240,20 +375,35 @@ pub fn create_archives(export_dir: &Path, tarball_prefix: &Path) -> anyhow::Resu
     debug!("Appending `data` directory to zip file…");
     zip.add_directory("data", SimpleFileOptions::default())?;
 
-    for table in sorted_tables {
-        let csv_path = export_dir.join("data").join(table).with_extension("csv");
-        if csv_path.exists() {
-            let name = tarball_prefix
-                .join("data")
-                .join(table)
-                .with_extension("csv");
-            debug!("Appending `{name:?}` file to tarball…");
-            tar.append_path_with_name(&csv_path, name)?;
-
-            let name = PathBuf::from("data").join(table).with_extension("csv");
-            debug!("Appending `{name:?}` file to zip file…");
-            zip.start_file_from_path(&name, SimpleFileOptions::default())?;
-            std::io::copy(&mut File::open(csv_path)?, &mut zip)?;
+    for table in &sorted_tables {
+        append_csv_file(
+            &mut tar,
+            &mut zip,
+            export_dir,
+            tarball_prefix,
+            "data",
+            table,
+        )?;
+    }
+
+    // Delta dumps contain the keys of deleted rows in the `deleted` directory.
+    if export_dir.join("deleted").exists() {
+        let path = tarball_prefix.join("deleted");
+        debug!("Appending `deleted` directory to tarball…");
+        tar.append_dir(path, export_dir.join("deleted"))?;
+
+        debug!("Appending `deleted` directory to zip file…");
+        zip.add_directory("deleted", SimpleFileOptions::default())?;
+
+        for table in &sorted_tables {
+            append_csv_file(
+                &mut tar,
+                &mut zip,
+                export_dir,
+                tarball_prefix,
+                "deleted",
+                table,
+            )?;
         }
     }
 
@@ -266,9 +416,92 @@ pub fn create_archives(export_dir: &Path, tarball_prefix: &Path) -> anyhow::Resu
     })
 }
 
+pub struct ColumnarArchives {
+    /// An uncompressed tarball of the Parquet files, which are compressed
+    /// individually.
+    pub parquet: tempfile::NamedTempFile,
+    /// The gzip-compressed SQLite database.
+    pub sqlite: tempfile::NamedTempFile,
+}
+
+/// Packs the output of [`DumpDirectory::populate_columnar()`] for upload.
+pub fn create_columnar_archives(
+    export_dir: &Path,
+    tarball_prefix: &Path,
+) -> anyhow::Result<ColumnarArchives> {
+    let columnar_dir = export_dir.join("columnar");
+
+    debug!("Creating Parquet tarball file…");
+    let parquet_tempfile = tempfile::NamedTempFile::new()?;
+    let mut tar = tar::Builder::new(parquet_tempfile.as_file());
+
+    debug!("Appending `{tarball_prefix:?}` directory to Parquet tarball…");
+    tar.append_dir(tarball_prefix, export_dir)?;
+    tar.append_path_with_name(
+        export_dir.join("metadata.json"),
+        tarball_prefix.join("metadata.json"),
+    )?;
+
+    let visibility_config = VisibilityConfig::get();
+    for table in visibility_config.topological_sort() {
+        let path = columnar_dir.join("parquet").join(table);
+        let path = path.with_extension("parquet");
+        if path.exists() {
+            let name = tarball_prefix.join(table).with_extension("parquet");
+            debug!("Appending `{name:?}` file to Parquet tarball…");
+            tar.append_path_with_name(&path, name)?;
+        }
+    }
+    tar.into_inner()?;
+
+    debug!("Compressing SQLite database…");
+    let sqlite_tempfile = tempfile::NamedTempFile::new()?;
+    let mut encoder =
+        flate2::write::GzEncoder::new(sqlite_tempfile.as_file(), flate2::Compression::default());
+    std::io::copy(
+        &mut File::open(columnar_dir.join("db-dump.sqlite"))?,
+        &mut encoder,
+    )?;
+    encoder.finish()?;
+
+    Ok(ColumnarArchives {
+        parquet: parquet_tempfile,
+        sqlite: sqlite_tempfile,
+    })
+}
+
+/// Appends the CSV file of `table` in the `directory` of the export directory
+/// to the archives, if it exists.
+fn append_csv_file<W: std::io::Write, Z: std::io::Write + std::io::Seek>(
+    tar: &mut tar::Builder<W>,
+    zip: &mut zip::ZipWriter<Z>,
+    export_dir: &Path,
+    tarball_prefix: &Path,
+    directory: &str,
+    table: &str,
+) -> anyhow::Result<()> {
+    let csv_path = export_dir.join(directory).join(table).with_extension("csv");
+    if csv_path.exists() {
+        let name = tarball_prefix
+            .join(directory)
+            .join(table)
+            .with_extension("csv");
+        debug!("Appending `{name:?}` file to tarball…");
+        tar.append_path_with_name(&csv_path, name)?;
+
+        let name = PathBuf::from(directory).join(table).with_extension("csv");
+        debug!("Appending `{name:?}` file to zip file…");
+        zip.start_file_from_path(&name, SimpleFileOptions::default())?;
+        std::io::copy(&mut File::open(csv_path)?, zip)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{TimeZone, Utc};
     use crates_io_env_vars::var_parsed;
     use crates_io_test_db::TestDatabase;
     use flate2::read::GzDecoder;
@@ -363,11 +596,196 @@ mod tests {
         // TODO: Consistency checks on the re-imported data?
     }
 
+    #[test]
+    fn restricted_crates_are_left_out() {
+        use diesel::RunQueryDsl;
+        use diesel::sql_query;
+
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.connect();
+
+        let mut execute = |query: &str| {
+            sql_query(query).execute(&mut conn).unwrap();
+        };
+
+        execute("INSERT INTO users (id, gh_id, gh_login, username) VALUES (1, 1, 'foo', 'foo')");
+        execute("INSERT INTO crates (id, name) VALUES (1, 'foo'), (2, 'bar')");
+        execute(
+            "INSERT INTO versions (id, crate_id, num, num_no_build, crate_size, tar_sha256) \
+            VALUES (1, 1, '1.0.0', '1.0.0', 42, decode(repeat('ab', 32), 'hex')), \
+            (2, 2, '1.0.0', '1.0.0', 42, decode(repeat('ab', 32), 'hex'))",
+        );
+        execute(
+            "INSERT INTO dependencies (version_id, crate_id, req, optional, default_features, features, kind) \
+            VALUES (2, 1, '^1', false, true, '{}', 0), (1, 2, '^1', false, true, '{}', 0)",
+        );
+        execute("INSERT INTO crate_readers (crate_id, user_id) VALUES (2, 1)");
+
+        let directory = DumpDirectory::create(postgres_bin_dir()).unwrap();
+        directory
+            .populate(test_db.url(), Some(test_db.schema()))
+            .unwrap();
+
+        let read_csv = |table: &str| {
+            let path = directory.path().join("data").join(format!("{table}.csv"));
+            fs::read_to_string(path).unwrap()
+        };
+
+        assert_snapshot!(read_csv("crates").lines().skip(1).count(), @"1");
+        assert!(read_csv("crates").contains(",foo,"));
+        assert!(!read_csv("crates").contains(",bar,"));
+        assert_snapshot!(read_csv("versions").lines().skip(1).count(), @"1");
+        assert_snapshot!(read_csv("dependencies").lines().skip(1).count(), @"0");
+        assert_snapshot!(read_csv("default_versions").lines().skip(1).count(), @"1");
+    }
+
+    #[test]
+    fn dump_columnar_formats() {
+        use arrow_array::cast::AsArray;
+        use diesel::RunQueryDsl;
+        use diesel::sql_query;
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.connect();
+
+        let mut execute = |query: &str| {
+            sql_query(query).execute(&mut conn).unwrap();
+        };
+
+        execute(
+            "INSERT INTO crates (id, name, description) VALUES (1, 'foo', NULL), (2, 'bar', '')",
+        );
+        execute(
+            "INSERT INTO versions (crate_id, num, num_no_build, crate_size, tar_sha256, bin_names) \
+            VALUES (1, '1.0.0', '1.0.0', 42, decode(repeat('ab', 32), 'hex'), ARRAY['foo', 'bar, baz'])",
+        );
+
+        let directory = DumpDirectory::create(postgres_bin_dir()).unwrap();
+        directory
+            .populate(test_db.url(), Some(test_db.schema()))
+            .unwrap();
+        directory.populate_columnar(test_db.url()).unwrap();
+
+        let parquet_dir = directory.path().join("columnar/parquet");
+        let file = File::open(parquet_dir.join("crates.parquet")).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        let fields = builder
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                let nullable = if field.is_nullable() { "" } else { " NOT NULL" };
+                format!("{}: {}{nullable}", field.name(), field.data_type())
+            })
+            .collect::<Vec<_>>();
+        assert_snapshot!(fields.join("\n"), @r#"
+        created_at: Timestamp(µs, "UTC") NOT NULL
+        deprecated_at: Timestamp(µs, "UTC")
+        deprecation_message: Utf8
+        description: Utf8
+        documentation: Utf8
+        homepage: Utf8
+        id: Int32 NOT NULL
+        max_features: Int16
+        max_upload_size: Int32
+        name: Utf8 NOT NULL
+        readme: Utf8
+        repository: Utf8
+        require_owner_2fa: Boolean NOT NULL
+        require_promotion_review: Boolean NOT NULL
+        successor: Utf8
+        trustpub_only: Boolean NOT NULL
+        updated_at: Timestamp(µs, "UTC") NOT NULL
+        "#);
+
+        let batch = builder.build().unwrap().next().unwrap().unwrap();
+        let descriptions = batch
+            .column_by_name("description")
+            .unwrap()
+            .as_string::<i32>();
+        let mut descriptions = descriptions.iter().collect::<Vec<_>>();
+        descriptions.sort();
+        assert_eq!(descriptions, [None, Some("")]);
+
+        let file = File::open(parquet_dir.join("versions.parquet")).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        let batch = builder.build().unwrap().next().unwrap().unwrap();
+        let bin_names = batch.column_by_name("bin_names").unwrap().as_list::<i32>();
+        let bin_names = bin_names.value(0);
+        let bin_names = bin_names.as_string::<i32>().iter().collect::<Vec<_>>();
+        assert_eq!(bin_names, [Some("foo"), Some("bar, baz")]);
+
+        let sqlite_path = directory.path().join("columnar/db-dump.sqlite");
+        let sqlite = rusqlite::Connection::open(sqlite_path).unwrap();
+        let mut statement = sqlite
+            .prepare("SELECT name, description FROM crates ORDER BY id")
+            .unwrap();
+        let crates = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<Vec<(String, Option<String>)>, _>>()
+            .unwrap();
+        assert_eq!(
+            crates,
+            [
+                ("foo".to_string(), None),
+                ("bar".to_string(), Some(String::new())),
+            ]
+        );
+
+        let (bin_names, tar_sha256) = sqlite
+            .query_row("SELECT bin_names, tar_sha256 FROM versions", [], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })
+            .unwrap();
+        assert_snapshot!(bin_names, @r#"["foo","bar, baz"]"#);
+        assert_eq!(tar_sha256, [0xab; 32]);
+
+        let archives = create_columnar_archives(directory.path(), Path::new("0000-00-00")).unwrap();
+        let mut tar = Archive::new(File::open(archives.parquet.path()).unwrap());
+        let paths = tar
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect::<Vec<_>>();
+        assert_debug_snapshot!(paths, @r#"
+        [
+            "0000-00-00",
+            "0000-00-00/metadata.json",
+            "0000-00-00/advisories.parquet",
+            "0000-00-00/categories.parquet",
+            "0000-00-00/crate_downloads.parquet",
+            "0000-00-00/crates.parquet",
+            "0000-00-00/keywords.parquet",
+            "0000-00-00/metadata.parquet",
+            "0000-00-00/oauth_github.parquet",
+            "0000-00-00/reserved_crate_names.parquet",
+            "0000-00-00/reserved_usernames.parquet",
+            "0000-00-00/teams.parquet",
+            "0000-00-00/users.parquet",
+            "0000-00-00/cargo_version_downloads.parquet",
+            "0000-00-00/client_downloads.parquet",
+            "0000-00-00/crates_categories.parquet",
+            "0000-00-00/crates_keywords.parquet",
+            "0000-00-00/crate_owners.parquet",
+            "0000-00-00/deleted_crates.parquet",
+            "0000-00-00/versions.parquet",
+            "0000-00-00/default_versions.parquet",
+            "0000-00-00/dependencies.parquet",
+            "0000-00-00/trustpub_provenance.parquet",
+            "0000-00-00/version_advisories.parquet",
+            "0000-00-00/version_downloads.parquet",
+        ]
+        "#);
+    }
+
     #[test]
     fn test_sql_scripts() {
         let db = TestDatabase::new();
 
-        let directory = DumpDirectory::create(postgres_bin_dir()).unwrap();
+        let mut directory = DumpDirectory::create(postgres_bin_dir()).unwrap();
+        directory.timestamp = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
         directory.populate(db.url(), Some(db.schema())).unwrap();
 
         insta::glob!(directory.path(), "{import,export}.sql", |path| {
@@ -375,4 +793,129 @@ mod tests {
             assert_snapshot!(content);
         });
     }
+
+    #[test]
+    fn test_delta_sql_scripts() {
+        let timestamp = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
+
+        let state_dir = tempfile::tempdir().unwrap();
+        let keys_dir = state_dir.path().join("keys");
+        fs::create_dir(&keys_dir).unwrap();
+        fs::write(keys_dir.join("crates.csv"), "id\n").unwrap();
+
+        let previous = DeltaState::new(timestamp - chrono::TimeDelta::hours(1), state_dir.path());
+        let manifest = DeltaManifest::new(timestamp, &previous);
+
+        let directory = tempfile::tempdir().unwrap();
+        let export_script = directory.path().join("export.sql");
+        let import_script = directory.path().join("import.sql");
+        gen_delta_scripts(&export_script, &import_script, &manifest).unwrap();
+
+        insta::glob!(directory.path(), "{import,export}.sql", |path| {
+            let content = fs::read_to_string(path).unwrap();
+            assert_snapshot!(content);
+        });
+    }
+
+    #[test]
+    fn dump_delta_and_apply_it() {
+        use diesel::RunQueryDsl;
+        use diesel::sql_query;
+
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.connect();
+
+        let mut execute = |query: &str| {
+            sql_query(query).execute(&mut conn).unwrap();
+        };
+
+        execute("INSERT INTO crates (id, name) VALUES (1, 'foo'), (2, 'bar')");
+        execute(
+            "INSERT INTO versions (id, crate_id, num, num_no_build, crate_size, tar_sha256, updated_at) \
+            VALUES (1, 1, '1.0.0', '1.0.0', 42, decode(repeat('ab', 32), 'hex'), now() - interval '1 day'), \
+            (2, 1, '1.1.0', '1.1.0', 42, decode(repeat('ab', 32), 'hex'), now() - interval '1 day')",
+        );
+
+        // Without a previous state, only the state is generated
+        let directory = DumpDirectory::create(postgres_bin_dir()).unwrap();
+        let (manifest, state) = directory.populate_delta(test_db.url(), None).unwrap();
+        assert!(manifest.is_none());
+        assert!(!directory.path().join("data").exists());
+        let keys = fs::read_to_string(state.keys_path("crates")).unwrap();
+        assert_snapshot!(keys, @r"
+        id
+        1
+        2
+        ");
+
+        execute("UPDATE crates SET description = 'updated' WHERE id = 1");
+        execute("DELETE FROM crates WHERE id = 2");
+        execute("INSERT INTO crates (id, name) VALUES (3, 'baz')");
+        execute("INSERT INTO version_downloads (version_id, downloads) VALUES (1, 5)");
+
+        let delta = DumpDirectory::create(postgres_bin_dir()).unwrap();
+        let (manifest, _) = delta.populate_delta(test_db.url(), Some(&state)).unwrap();
+        let manifest = manifest.unwrap();
+
+        // Versions are exported when their download counts change
+        let versions = fs::read_to_string(delta.path().join("data/versions.csv")).unwrap();
+        assert_eq!(versions.lines().count(), 2);
+        assert!(versions.contains(",1.0.0,"));
+        assert_eq!(manifest.previous, state.timestamp);
+        assert_eq!(manifest.tables["crates"].mode, TableMode::Delta);
+        assert!(manifest.tables["crates"].deletions);
+        assert_eq!(manifest.tables["users"].mode, TableMode::Full);
+
+        let deleted = fs::read_to_string(delta.path().join("deleted/crates.csv")).unwrap();
+        assert_snapshot!(deleted, @r"
+        id
+        2
+        ");
+
+        // Restore the state of the previous dump, and apply the delta to it
+        execute("UPDATE crates SET description = NULL WHERE id = 1");
+        execute("INSERT INTO crates (id, name) VALUES (2, 'bar')");
+        execute("DELETE FROM crates WHERE id = 3");
+        execute(
+            "CREATE TABLE db_dump_imports (timestamp TIMESTAMPTZ NOT NULL, imported_at TIMESTAMPTZ NOT NULL DEFAULT now())",
+        );
+        execute(&format!(
+            "INSERT INTO db_dump_imports (timestamp) VALUES ('{}')",
+            state.timestamp.to_rfc3339()
+        ));
+
+        let import_script = delta.path().join("import.sql");
+        delta.run_psql(&import_script, test_db.url()).unwrap();
+
+        #[derive(diesel::QueryableByName)]
+        struct Crate {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            name: String,
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+            description: Option<String>,
+        }
+
+        let crates: Vec<Crate> = sql_query("SELECT name, description FROM crates ORDER BY id")
+            .load(&mut conn)
+            .unwrap();
+        let crates = crates
+            .into_iter()
+            .map(|krate| (krate.name, krate.description))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            crates,
+            [
+                ("foo".to_string(), Some("updated".to_string())),
+                ("baz".to_string(), None),
+            ]
+        );
+
+        // The same delta can not be applied twice
+        let error = delta.run_psql(&import_script, test_db.url()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("is not newer than the latest import")
+        );
+    }
 }
@@ -0,0 +1,398 @@
+#![doc = include_str!("../README.md")]
+
+#[macro_use]
+extern crate tracing;
+
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, header};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use url::Url;
+
+type Result<T> = std::result::Result<T, GitLabError>;
+
+/// Authentication mode for a request to the GitLab API.
+#[derive(Debug, Clone)]
+pub enum GitLabAuth {
+    /// OAuth bearer token authentication.
+    Bearer { token: SecretString },
+}
+
+impl GitLabAuth {
+    /// Creates a [`GitLabAuth::Bearer`] authentication mode from a bearer
+    /// token.
+    pub fn bearer(token: impl Into<SecretString>) -> Self {
+        GitLabAuth::Bearer {
+            token: token.into(),
+        }
+    }
+
+    /// Applies this authentication mode to the given request builder.
+    fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            GitLabAuth::Bearer { token } => request.bearer_auth(token.expose_secret()),
+        }
+    }
+}
+
+#[cfg_attr(feature = "mock", mockall::automock)]
+#[async_trait]
+pub trait GitLabClient: Send + Sync {
+    /// Returns the user that the access token belongs to.
+    async fn current_user(&self, auth: &GitLabAuth) -> Result<GitLabUser>;
+
+    /// Looks up a group by its full path (e.g. `"my-group/my-subgroup"`).
+    async fn group_by_path(&self, path: &str, auth: &GitLabAuth) -> Result<GitLabGroup>;
+
+    /// Returns the membership of a user in a group, including memberships
+    /// that are inherited from ancestor groups.
+    ///
+    /// Returns `None` if the user is not a member of the group.
+    async fn group_membership(
+        &self,
+        group_id: i64,
+        user_id: i64,
+        auth: &GitLabAuth,
+    ) -> Result<Option<GitLabGroupMembership>>;
+
+    /// Exchanges a refresh token for a new access token.
+    ///
+    /// GitLab access tokens expire after two hours, and the refresh token
+    /// can only be used once, so the returned refresh token replaces it.
+    async fn refresh_token(&self, refresh_token: &SecretString) -> Result<GitLabTokens>;
+}
+
+/// Credentials of the OAuth application that is used to link GitLab
+/// accounts, which are needed to refresh expired access tokens.
+#[derive(Debug, Clone)]
+pub struct GitLabOAuthApp {
+    pub client_id: String,
+    pub client_secret: SecretString,
+    pub redirect_uri: String,
+}
+
+#[derive(Debug)]
+pub struct RealGitLabClient {
+    client: Client,
+    base_url: Url,
+    oauth_app: Option<GitLabOAuthApp>,
+}
+
+impl RealGitLabClient {
+    pub fn new(client: Client) -> Self {
+        let base_url = Url::parse("https://gitlab.com/api/v4/").expect("base URL must parse");
+        Self::with_base_url(client, base_url)
+    }
+
+    fn with_base_url(client: Client, base_url: Url) -> Self {
+        Self {
+            client,
+            base_url,
+            oauth_app: None,
+        }
+    }
+
+    /// Sets the OAuth application credentials that are used to refresh
+    /// expired access tokens.
+    pub fn with_oauth_app(mut self, oauth_app: GitLabOAuthApp) -> Self {
+        self.oauth_app = Some(oauth_app);
+        self
+    }
+
+    /// Sends a GET request to the GitLab API.
+    async fn request<T>(&self, url: &str, auth: &GitLabAuth) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let url = self
+            .base_url
+            .join(url.trim_start_matches('/'))
+            .map_err(|e| GitLabError::Other(e.into()))?;
+        info!("GitLab request: GET {url}");
+
+        let request = self
+            .client
+            .get(url)
+            .header(header::ACCEPT, "application/json")
+            .header(header::USER_AGENT, "crates.io (https://crates.io)");
+
+        let response = auth.apply(request).send().await?.error_for_status()?;
+
+        let headers = response.headers();
+        let remaining = headers.get("ratelimit-remaining");
+        let limit = headers.get("ratelimit-limit");
+        debug!("GitLab rate limit remaining: {remaining:?}/{limit:?}");
+
+        response.json().await.map_err(Into::into)
+    }
+}
+
+#[async_trait]
+impl GitLabClient for RealGitLabClient {
+    async fn current_user(&self, auth: &GitLabAuth) -> Result<GitLabUser> {
+        self.request("/user", auth).await
+    }
+
+    async fn group_by_path(&self, path: &str, auth: &GitLabAuth) -> Result<GitLabGroup> {
+        // The API accepts the URL-encoded full path in place of the group ID
+        let url = format!("/groups/{}", path.replace('/', "%2F"));
+        self.request(&url, auth).await
+    }
+
+    async fn group_membership(
+        &self,
+        group_id: i64,
+        user_id: i64,
+        auth: &GitLabAuth,
+    ) -> Result<Option<GitLabGroupMembership>> {
+        let url = format!("/groups/{group_id}/members/all/{user_id}");
+        match self.request(&url, auth).await {
+            Ok(membership) => Ok(Some(membership)),
+            Err(GitLabError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn refresh_token(&self, refresh_token: &SecretString) -> Result<GitLabTokens> {
+        let Some(oauth_app) = &self.oauth_app else {
+            let error = anyhow::anyhow!("GitLab OAuth application is not configured");
+            return Err(GitLabError::Other(error));
+        };
+
+        // The token endpoint is not part of the versioned API
+        let url = self
+            .base_url
+            .join("/oauth/token")
+            .map_err(|e| GitLabError::Other(e.into()))?;
+        info!("GitLab request: POST {url}");
+
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("client_id", &oauth_app.client_id)
+            .append_pair("client_secret", oauth_app.client_secret.expose_secret())
+            .append_pair("refresh_token", refresh_token.expose_secret())
+            .append_pair("grant_type", "refresh_token")
+            .append_pair("redirect_uri", &oauth_app.redirect_uri)
+            .finish();
+
+        let response = self
+            .client
+            .post(url)
+            .header(header::ACCEPT, "application/json")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header(header::USER_AGENT, "crates.io (https://crates.io)")
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        response.json().await.map_err(Into::into)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GitLabError {
+    #[error(transparent)]
+    Unauthorized(anyhow::Error),
+    #[error(transparent)]
+    Forbidden(anyhow::Error),
+    #[error(transparent)]
+    NotFound(anyhow::Error),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl From<reqwest::Error> for GitLabError {
+    fn from(error: reqwest::Error) -> Self {
+        use reqwest::StatusCode as Status;
+
+        match error.status() {
+            Some(Status::UNAUTHORIZED) => Self::Unauthorized(error.into()),
+            Some(Status::FORBIDDEN) => Self::Forbidden(error.into()),
+            Some(Status::NOT_FOUND) => Self::NotFound(error.into()),
+            _ => Self::Other(error.into()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabUser {
+    pub id: i64,
+    pub username: String,
+    pub name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+/// Tokens returned by the GitLab OAuth token endpoint.
+#[derive(Debug, Deserialize)]
+pub struct GitLabTokens {
+    pub access_token: SecretString,
+    pub refresh_token: SecretString,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabGroup {
+    /// Unique GitLab ID of the group (needed for membership queries)
+    pub id: i64,
+    /// Pretty name of the group, including the names of its ancestors
+    pub full_name: Option<String>,
+    /// Full path of the group (e.g. `"my-group/my-subgroup"`)
+    pub full_path: String,
+    pub avatar_url: Option<String>,
+}
+
+/// Access levels of group members.
+///
+/// See <https://docs.gitlab.com/api/members/#roles>.
+pub mod access_level {
+    pub const GUEST: i32 = 10;
+    pub const REPORTER: i32 = 20;
+    pub const DEVELOPER: i32 = 30;
+    pub const MAINTAINER: i32 = 40;
+    pub const OWNER: i32 = 50;
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabGroupMembership {
+    pub access_level: i32,
+    pub state: String,
+}
+
+impl GitLabGroupMembership {
+    /// Members with at least the "Developer" role can publish crates owned by
+    /// the group.
+    pub fn is_active_developer(&self) -> bool {
+        self.state == "active" && self.access_level >= access_level::DEVELOPER
+    }
+
+    /// Members with at least the "Maintainer" role can add the group as an
+    /// owner of a crate.
+    pub fn is_active_maintainer(&self) -> bool {
+        self.state == "active" && self.access_level >= access_level::MAINTAINER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Server, ServerOpts};
+
+    async fn mock_server() -> Server {
+        Server::new_with_opts_async(ServerOpts {
+            assert_on_drop: true,
+            ..Default::default()
+        })
+        .await
+    }
+
+    fn client_with_server(server: &Server) -> RealGitLabClient {
+        let base_url = Url::parse(&format!("{}/api/v4/", server.url())).unwrap();
+        RealGitLabClient::with_base_url(Client::new(), base_url)
+    }
+
+    #[tokio::test]
+    async fn group_by_path_encodes_subgroups() {
+        let mut server = mock_server().await;
+        let _mock = server
+            .mock("GET", "/api/v4/groups/my-group%2Fmy-subgroup")
+            .match_header("authorization", "Bearer test-token")
+            .with_status(200)
+            .with_body(
+                r#"{
+                    "id": 42,
+                    "name": "My Subgroup",
+                    "full_name": "My Group / My Subgroup",
+                    "full_path": "my-group/my-subgroup",
+                    "avatar_url": null
+                }"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = client_with_server(&server);
+        let auth = GitLabAuth::bearer("test-token");
+        let group = client
+            .group_by_path("my-group/my-subgroup", &auth)
+            .await
+            .unwrap();
+
+        assert_eq!(group.id, 42);
+        assert_eq!(group.full_path, "my-group/my-subgroup");
+        assert_eq!(group.full_name.as_deref(), Some("My Group / My Subgroup"));
+    }
+
+    #[tokio::test]
+    async fn group_membership_returns_access_level() {
+        let mut server = mock_server().await;
+        let _mock = server
+            .mock("GET", "/api/v4/groups/42/members/all/7")
+            .with_status(200)
+            .with_body(r#"{"id": 7, "username": "jdoe", "access_level": 30, "state": "active"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = client_with_server(&server);
+        let auth = GitLabAuth::bearer("test-token");
+        let membership = client.group_membership(42, 7, &auth).await.unwrap();
+
+        let membership = membership.unwrap();
+        assert!(membership.is_active_developer());
+        assert!(!membership.is_active_maintainer());
+    }
+
+    #[tokio::test]
+    async fn group_membership_returns_none_for_non_members() {
+        let mut server = mock_server().await;
+        let _mock = server
+            .mock("GET", "/api/v4/groups/42/members/all/7")
+            .with_status(404)
+            .with_body(r#"{"message": "404 Not found"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = client_with_server(&server);
+        let auth = GitLabAuth::bearer("test-token");
+        let membership = client.group_membership(42, 7, &auth).await.unwrap();
+
+        assert!(membership.is_none());
+    }
+
+    #[tokio::test]
+    async fn refresh_token_returns_new_tokens() {
+        let mut server = mock_server().await;
+        let _mock = server
+            .mock("POST", "/oauth/token")
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("client_id".into(), "client-id".into()),
+                mockito::Matcher::UrlEncoded("client_secret".into(), "client-secret".into()),
+                mockito::Matcher::UrlEncoded("refresh_token".into(), "old-refresh".into()),
+                mockito::Matcher::UrlEncoded("grant_type".into(), "refresh_token".into()),
+            ]))
+            .with_status(200)
+            .with_body(
+                r#"{
+                    "access_token": "new-access",
+                    "token_type": "Bearer",
+                    "expires_in": 7200,
+                    "refresh_token": "new-refresh",
+                    "created_at": 1607635748
+                }"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = client_with_server(&server).with_oauth_app(GitLabOAuthApp {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            redirect_uri: "https://crates.io/gitlab/callback".into(),
+        });
+        let tokens = client.refresh_token(&"old-refresh".into()).await.unwrap();
+
+        assert_eq!(tokens.access_token.expose_secret(), "new-access");
+        assert_eq!(tokens.refresh_token.expose_secret(), "new-refresh");
+    }
+}
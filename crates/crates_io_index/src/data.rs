@@ -53,6 +53,22 @@ pub struct Crate {
     /// `--precise` flag of `cargo update`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub v: Option<u32>,
+    /// Set on all entries of a crate that was marked as deprecated by its
+    /// owners.
+    ///
+    /// Cargo ignores this field, but other tooling can read it to warn about
+    /// dependencies on deprecated crates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<Deprecation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// The name of the crate that should be used instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub successor: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
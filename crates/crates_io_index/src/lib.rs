@@ -16,6 +16,6 @@ pub mod testing;
 
 pub use crate::commit_builder::CommitBuilder;
 pub use crate::credentials::Credentials;
-pub use crate::data::{Crate, Dependency, DependencyKind};
+pub use crate::data::{Crate, Dependency, DependencyKind, Deprecation};
 pub use crate::repo::{Repository, RepositoryConfig};
 pub use crate::ser::write_crates;
@@ -51,6 +51,7 @@ mod tests {
             rust_version: None,
             pubtime: Some(pubtime),
             v: None,
+            deprecated: None,
         };
         let mut buffer = Vec::new();
         assert_ok!(write_crate(&krate, &mut buffer));
@@ -79,6 +80,7 @@ mod tests {
                 rust_version: None,
                 pubtime: None,
                 v: None,
+                deprecated: None,
             })
             .collect::<Vec<_>>();
 
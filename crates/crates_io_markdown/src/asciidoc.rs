@@ -0,0 +1,1560 @@
+//! Renders the subset of AsciiDoc that is commonly used in READMEs.
+//!
+//! Preprocessor directives like `include::[]` and `ifdef::[]` can't be
+//! resolved for a single file and are skipped, together with comments.
+
+use crate::html::{
+    ALERTS, dedent, escape, escape_attribute, lines, push_alert, push_code_block, push_escaped,
+    push_heading, push_table, skip_blank_lines,
+};
+use comrak::Anchorizer;
+use std::collections::HashMap;
+
+/// The values of the intrinsic attributes for special characters.
+const INTRINSIC_ATTRIBUTES: [(&str, &str); 18] = [
+    ("amp", "&"),
+    ("apos", "'"),
+    ("asterisk", "*"),
+    ("backslash", "\\"),
+    ("backtick", "`"),
+    ("blank", ""),
+    ("caret", "^"),
+    ("empty", ""),
+    ("endsb", "]"),
+    ("gt", ">"),
+    ("lt", "<"),
+    ("nbsp", "\u{a0}"),
+    ("plus", "+"),
+    ("quot", "\""),
+    ("sp", " "),
+    ("startsb", "["),
+    ("tilde", "~"),
+    ("vbar", "|"),
+];
+
+/// Renders AsciiDoc to HTML. The result still needs to be sanitized.
+pub(crate) fn to_html(text: &str, syntax_highlighting: bool) -> String {
+    let lines = lines(text);
+    let mut renderer = Renderer::new(&lines, syntax_highlighting);
+
+    let mut html = String::new();
+    renderer.render_blocks(&lines, &mut html);
+    renderer.render_footnotes(&mut html);
+    html
+}
+
+/// The attributes, ID and title of the next block.
+#[derive(Default)]
+struct BlockMetadata {
+    attributes: Vec<String>,
+    id: Option<String>,
+    title: Option<String>,
+}
+
+impl BlockMetadata {
+    /// Returns the block style, i.e. the first positional attribute.
+    fn style(&self) -> Option<&str> {
+        let style = self.attributes.first()?.split(['#', '%', '.']).next()?;
+        Some(style).filter(|style| !style.is_empty() && !style.contains('='))
+    }
+
+    fn positional(&self, position: usize) -> Option<&str> {
+        self.attributes
+            .get(position)
+            .map(String::as_str)
+            .filter(|value| !value.contains('='))
+    }
+
+    fn named(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find_map(|attribute| {
+            let (key, value) = attribute.split_once('=')?;
+            (key.trim() == name).then(|| value.trim().trim_matches('"'))
+        })
+    }
+
+    fn has_option(&self, option: &str) -> bool {
+        let shorthand = self
+            .attributes
+            .first()
+            .is_some_and(|style| style.split('%').skip(1).any(|o| o == option));
+        let named = self
+            .named("options")
+            .or(self.named("opts"))
+            .is_some_and(|options| options.split(',').any(|o| o.trim() == option));
+        shorthand || named
+    }
+}
+
+struct ListItem {
+    marker: String,
+    text: Vec<String>,
+    /// Blocks attached to the item with list continuations (`+`).
+    blocks: Vec<String>,
+}
+
+/// A section title that can be the target of a cross reference.
+struct Section {
+    /// The ID of the rendered heading, without the `user-content-` prefix.
+    anchor: String,
+    title: String,
+}
+
+struct Renderer {
+    attributes: HashMap<String, String>,
+    /// The section titles by their explicit and AsciiDoc default IDs.
+    sections: HashMap<String, Section>,
+    footnotes: Vec<String>,
+    anchorizer: Anchorizer,
+    syntax_highlighting: bool,
+}
+
+impl Renderer {
+    fn new(lines: &[String], syntax_highlighting: bool) -> Self {
+        let attributes = INTRINSIC_ATTRIBUTES
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Self {
+            attributes,
+            sections: collect_sections(lines),
+            footnotes: Vec::new(),
+            anchorizer: Anchorizer::new(),
+            syntax_highlighting,
+        }
+    }
+
+    fn render_blocks(&mut self, lines: &[String], html: &mut String) {
+        let mut metadata = BlockMetadata::default();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i].as_str();
+
+            if line.trim().is_empty() {
+                i += 1;
+                continue;
+            }
+
+            if is_delimiter(line) && line.starts_with('/') {
+                i = delimited_block_end(lines, i).1;
+                continue;
+            }
+
+            if is_comment(line) {
+                i += 1;
+                continue;
+            }
+
+            if is_preprocessor_directive(line) || line == "<<<" || line.starts_with("toc::[") {
+                i += 1;
+                continue;
+            }
+
+            if let Some((name, value)) = attribute_entry(line) {
+                match value {
+                    Some(value) => {
+                        let value = self.substitute_attributes(&value);
+                        self.attributes.insert(name, value);
+                    }
+                    None => {
+                        self.attributes.remove(&name);
+                    }
+                }
+                i += 1;
+                continue;
+            }
+
+            if let Some(attributes) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if !line.starts_with("[[") {
+                    metadata.attributes = split_attributes(attributes);
+                }
+                metadata.id = block_id(line).or(metadata.id);
+                i += 1;
+                continue;
+            }
+
+            if let Some(title) = line.strip_prefix('.')
+                && !title.starts_with(['.', ' '])
+                && !title.is_empty()
+            {
+                metadata.title = Some(title.to_string());
+                i += 1;
+                continue;
+            }
+
+            let metadata = std::mem::take(&mut metadata);
+            if let Some((level, title)) = heading(line) {
+                let content = self.inline(title);
+                let id = metadata.id.as_deref();
+                push_heading(html, &mut self.anchorizer, level, id, &content);
+                i += 1;
+                continue;
+            }
+
+            if let Some(title) = &metadata.title {
+                let title = self.inline(title);
+                html.push_str(&format!("<p><strong>{title}</strong></p>\n"));
+            }
+
+            if is_delimiter(line) {
+                let (content_end, next) = delimited_block_end(lines, i);
+                self.delimited_block(line, &lines[i + 1..content_end], &metadata, html);
+                i = next;
+            } else if matches!(line, "'''" | "---" | "***" | "- - -" | "* * *") {
+                html.push_str("<hr />\n");
+                i += 1;
+            } else if let Some(image) = line.strip_prefix("image::") {
+                let image = self.image(image);
+                html.push_str(&format!("<p>{image}</p>\n"));
+                i += 1;
+            } else if list_marker(line).is_some() {
+                i = self.list(lines, i, html);
+            } else if description_list_item(line).is_some() {
+                i = self.description_list(lines, i, html);
+            } else if line.starts_with(' ') {
+                let end = paragraph_end(lines, i);
+                let code = dedent(&lines[i..end]).join("\n");
+                push_code_block(html, None, &code, self.syntax_highlighting);
+                i = end;
+            } else {
+                let end = paragraph_end(lines, i);
+                self.paragraph(&lines[i..end], &metadata, html);
+                i = end;
+            }
+        }
+    }
+
+    fn render_nested(&mut self, lines: &[String]) -> String {
+        let mut html = String::new();
+        self.render_blocks(lines, &mut html);
+        html
+    }
+
+    fn delimited_block(
+        &mut self,
+        delimiter: &str,
+        content: &[String],
+        metadata: &BlockMetadata,
+        html: &mut String,
+    ) {
+        let admonition = metadata
+            .style()
+            .map(str::to_lowercase)
+            .filter(|style| ALERTS.contains(&style.as_str()));
+
+        if let Some(language) = delimiter.strip_prefix("```") {
+            let language = Some(language.trim()).filter(|language| !language.is_empty());
+            push_code_block(
+                html,
+                language,
+                &content.join("\n"),
+                self.syntax_highlighting,
+            );
+            return;
+        }
+
+        match &delimiter[..2] {
+            "--" if delimiter.len() > 2 => {
+                let language = match metadata.style() {
+                    Some("source") | None => metadata
+                        .positional(1)
+                        .or_else(|| self.attributes.get("source-language").map(String::as_str)),
+                    Some(_) => None,
+                };
+                let language = language.map(ToString::to_string);
+                push_code_block(
+                    html,
+                    language.as_deref(),
+                    &content.join("\n"),
+                    self.syntax_highlighting,
+                );
+            }
+            ".." => push_code_block(html, None, &content.join("\n"), self.syntax_highlighting),
+            "++" => {
+                html.push_str(&content.join("\n"));
+                html.push('\n');
+            }
+            "__" => {
+                let content = self.render_nested(content);
+                html.push_str(&format!("<blockquote>\n{content}</blockquote>\n"));
+            }
+            "|=" => self.table(content, metadata, html),
+            _ => {
+                let content = self.render_nested(content);
+                match admonition {
+                    Some(kind) => push_alert(html, &kind, None, &content),
+                    None => html.push_str(&content),
+                }
+            }
+        }
+    }
+
+    fn paragraph(&mut self, lines: &[String], metadata: &BlockMetadata, html: &mut String) {
+        let lines = lines
+            .iter()
+            .filter(|line| !is_comment(line))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let mut text = lines.join("\n");
+        let mut admonition = metadata
+            .style()
+            .map(str::to_lowercase)
+            .filter(|style| ALERTS.contains(&style.as_str()));
+
+        if let Some((label, rest)) = text.split_once(": ")
+            && ALERTS.contains(&label.to_lowercase().as_str())
+            && label.chars().all(|c| c.is_ascii_uppercase())
+        {
+            admonition = Some(label.to_lowercase());
+            text = rest.to_string();
+        }
+
+        // Lines ending with ` +` are followed by a hard line break.
+        let hardbreaks = metadata.has_option("hardbreaks");
+        let mut segments = vec![String::new()];
+        for line in text.split('\n') {
+            let (line, hard_break) = match line.strip_suffix(" +") {
+                Some(line) => (line, true),
+                None => (line, hardbreaks),
+            };
+
+            let segment = segments.last_mut().unwrap();
+            if !segment.is_empty() {
+                segment.push('\n');
+            }
+            segment.push_str(line);
+            if hard_break {
+                segments.push(String::new());
+            }
+        }
+        if segments.len() > 1 && segments.last().is_some_and(String::is_empty) {
+            segments.pop();
+        }
+
+        let content = segments
+            .iter()
+            .map(|segment| self.inline(segment))
+            .collect::<Vec<_>>()
+            .join("<br>\n");
+
+        let paragraph = format!("<p>{content}</p>\n");
+        match admonition {
+            Some(kind) => push_alert(html, &kind, None, &paragraph),
+            None => html.push_str(&paragraph),
+        }
+    }
+
+    fn list(&mut self, lines: &[String], mut i: usize, html: &mut String) -> usize {
+        let mut items = Vec::new();
+        while let Some((marker, text)) = lines.get(i).and_then(|line| list_marker(line)) {
+            let mut item = ListItem {
+                marker,
+                text: vec![text.to_string()],
+                blocks: Vec::new(),
+            };
+            i += 1;
+
+            while let Some(line) = lines.get(i) {
+                if line.trim().is_empty() || list_marker(line).is_some() || line == "+" {
+                    break;
+                }
+                item.text.push(line.trim().to_string());
+                i += 1;
+            }
+
+            while lines.get(i).is_some_and(|line| line == "+") {
+                let start = i + 1;
+                let end = match lines.get(start) {
+                    Some(line) if is_delimiter(line) => delimited_block_end(lines, start).1,
+                    Some(_) => paragraph_end(lines, start),
+                    None => start,
+                };
+                item.blocks.extend_from_slice(&lines[start..end]);
+                item.blocks.push(String::new());
+                i = end;
+            }
+
+            items.push(item);
+
+            let j = skip_blank_lines(lines, i);
+            if lines.get(j).is_some_and(|line| list_marker(line).is_some()) {
+                i = j;
+            } else {
+                break;
+            }
+        }
+
+        self.render_list_items(&items, html);
+        i
+    }
+
+    /// Renders list items. Items with a different marker than the first one
+    /// are nested in the preceding item.
+    fn render_list_items(&mut self, items: &[ListItem], html: &mut String) {
+        let marker = &items[0].marker;
+        let ordered = !marker.starts_with(['*', '-']);
+        html.push_str(if ordered { "<ol>\n" } else { "<ul>\n" });
+
+        let mut i = 0;
+        while i < items.len() {
+            let item = &items[i];
+            let children = items[i + 1..]
+                .iter()
+                .take_while(|child| child.marker != *marker)
+                .count();
+
+            let text = item.text.join("\n");
+            let text = match text.strip_prefix("[x] ").or(text.strip_prefix("[*] ")) {
+                Some(text) => format!(
+                    "<input type=\"checkbox\" checked=\"\" disabled=\"\"> {}",
+                    self.inline(text)
+                ),
+                None => match text.strip_prefix("[ ] ") {
+                    Some(text) => format!(
+                        "<input type=\"checkbox\" disabled=\"\"> {}",
+                        self.inline(text)
+                    ),
+                    None => self.inline(&text),
+                },
+            };
+
+            html.push_str("<li>");
+            if item.blocks.is_empty() {
+                html.push_str(&text);
+                if children > 0 {
+                    html.push('\n');
+                }
+            } else {
+                html.push_str(&format!("\n<p>{text}</p>\n"));
+                let blocks = self.render_nested(&item.blocks);
+                html.push_str(&blocks);
+            }
+            if children > 0 {
+                self.render_list_items(&items[i + 1..=i + children], html);
+            }
+            html.push_str("</li>\n");
+
+            i += children + 1;
+        }
+
+        html.push_str(if ordered { "</ol>\n" } else { "</ul>\n" });
+    }
+
+    fn description_list(&mut self, lines: &[String], mut i: usize, html: &mut String) -> usize {
+        html.push_str("<dl>\n");
+        while let Some((term, definition)) = lines.get(i).and_then(|l| description_list_item(l)) {
+            let mut definition = vec![definition.to_string()];
+            i += 1;
+            while let Some(line) = lines.get(i) {
+                if line.trim().is_empty() || description_list_item(line).is_some() {
+                    break;
+                }
+                definition.push(line.trim().to_string());
+                i += 1;
+            }
+
+            // The definition may also start on the next line.
+            if definition.iter().all(|line| line.is_empty()) {
+                let j = skip_blank_lines(lines, i);
+                if let Some(line) = lines.get(j)
+                    && description_list_item(line).is_none()
+                    && !is_delimiter(line)
+                    && line.starts_with(' ')
+                {
+                    let end = paragraph_end(lines, j);
+                    definition = lines[j..end].iter().map(|l| l.trim().to_string()).collect();
+                    i = end;
+                }
+            }
+
+            let term = self.inline(term);
+            let definition = self.inline(definition.join("\n").trim());
+            html.push_str(&format!("<dt>{term}</dt>\n<dd>{definition}</dd>\n"));
+
+            let j = skip_blank_lines(lines, i);
+            if lines
+                .get(j)
+                .is_some_and(|l| description_list_item(l).is_some())
+            {
+                i = j;
+            } else {
+                break;
+            }
+        }
+        html.push_str("</dl>\n");
+        i
+    }
+
+    fn table(&mut self, content: &[String], metadata: &BlockMetadata, html: &mut String) {
+        let rows = content
+            .iter()
+            .map(|line| strip_row_specifier(line.trim_start()))
+            .collect::<Vec<_>>();
+
+        // The number of columns is determined by the cells of the first line,
+        // which is the header if it is followed by a blank line.
+        let first_row_cells = rows
+            .first()
+            .and_then(|line| line.strip_prefix('|'))
+            .map(|line| split_cells(line).len());
+        let implicit_header =
+            first_row_cells.is_some() && rows.len() > 2 && rows[1].trim().is_empty();
+
+        let mut cells: Vec<String> = Vec::new();
+        for line in rows.iter().filter(|line| !line.trim().is_empty()) {
+            match line.strip_prefix('|') {
+                Some(line) => cells.extend(split_cells(line)),
+                None => match cells.last_mut() {
+                    Some(cell) => {
+                        cell.push('\n');
+                        cell.push_str(line.trim());
+                    }
+                    None => cells.push(line.trim().to_string()),
+                },
+            }
+        }
+
+        let columns = metadata
+            .named("cols")
+            .map(column_count)
+            .or(first_row_cells)
+            .unwrap_or(cells.len())
+            .max(1);
+
+        let mut rows = cells
+            .chunks(columns)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| self.inline(cell.trim()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let has_header =
+            metadata.has_option("header") || (implicit_header && !metadata.has_option("noheader"));
+        let header = (has_header && !rows.is_empty()).then(|| rows.remove(0));
+        push_table(html, header.as_deref(), &rows);
+    }
+
+    fn image(&mut self, macro_text: &str) -> String {
+        let Some((target, attributes)) = macro_text.split_once('[') else {
+            return escape(macro_text);
+        };
+        let attributes = split_attributes(attributes.trim_end_matches(']'));
+        let metadata = BlockMetadata {
+            attributes,
+            ..Default::default()
+        };
+
+        let target = self.substitute_attributes(target);
+        let target = match self.attributes.get("imagesdir") {
+            Some(dir) if !target.contains(':') && !target.starts_with('/') => {
+                format!("{}/{target}", dir.trim_end_matches('/'))
+            }
+            _ => target,
+        };
+
+        let mut image = format!("<img src=\"{}\"", escape_attribute(&target));
+        let alt = metadata.named("alt").or(metadata.positional(0));
+        if let Some(alt) = alt.filter(|alt| !alt.is_empty()) {
+            image.push_str(&format!(" alt=\"{}\"", escape_attribute(alt)));
+        }
+        let width = metadata.named("width").or(metadata.positional(1));
+        if let Some(width) = width.filter(|width| !width.is_empty()) {
+            image.push_str(&format!(" width=\"{}\"", escape_attribute(width)));
+        }
+        let height = metadata.named("height").or(metadata.positional(2));
+        if let Some(height) = height.filter(|height| !height.is_empty()) {
+            image.push_str(&format!(" height=\"{}\"", escape_attribute(height)));
+        }
+        if let Some(align) = metadata.named("align") {
+            image.push_str(&format!(" align=\"{}\"", escape_attribute(align)));
+        }
+        image.push('>');
+
+        match metadata.named("link") {
+            Some(link) => format!("<a href=\"{}\">{image}</a>", escape_attribute(link)),
+            None => image,
+        }
+    }
+
+    fn render_footnotes(&mut self, html: &mut String) {
+        if self.footnotes.is_empty() {
+            return;
+        }
+
+        html.push_str("<section class=\"footnotes\">\n<ol>\n");
+        for (i, footnote) in self.footnotes.iter().enumerate() {
+            let number = i + 1;
+            html.push_str(&format!(
+                "<li id=\"user-content-_footnotedef_{number}\">{footnote}</li>\n"
+            ));
+        }
+        html.push_str("</ol>\n</section>\n");
+    }
+
+    fn substitute_attributes(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let value = rest
+                .find('}')
+                .and_then(|end| Some((self.attributes.get(&rest[1..end])?, end)));
+            match value {
+                Some((value, end)) if !result.ends_with('\\') => {
+                    result.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                _ => {
+                    result.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// Renders inline markup.
+    fn inline(&mut self, text: &str) -> String {
+        let text = self.substitute_attributes(text);
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut html = String::new();
+
+        let mut p = 0;
+        while p < chars.len() {
+            let c = chars[p];
+            let next = chars.get(p + 1).copied();
+            let can_start = p == 0 || !is_word_char(chars[p - 1]);
+
+            if c == '\\' && next.is_some_and(|next| "*_`#+[{<:\\".contains(next)) {
+                push_escaped(&mut html, &next.unwrap_or_default().to_string());
+                p += 2;
+                continue;
+            }
+
+            let end = match c {
+                '*' | '_' | '`' | '#' if next == Some(c) => {
+                    let delimiter = [c, c];
+                    find_unconstrained(&chars, p + 2, &delimiter).map(|end| {
+                        let text = chars[p + 2..end].iter().collect::<String>();
+                        self.push_quoted(c, &text, &mut html);
+                        end + 2
+                    })
+                }
+                '*' | '_' | '`' | '#' | '+' if can_start => {
+                    find_constrained(&chars, p + 1, c).map(|end| {
+                        let text = chars[p + 1..end].iter().collect::<String>();
+                        self.push_quoted(c, &text, &mut html);
+                        end + 1
+                    })
+                }
+                '<' if next == Some('<') => self.cross_reference(&chars, p, &mut html),
+                _ if can_start && c.is_alphabetic() => self.inline_macro(&chars, p, &mut html),
+                _ => None,
+            };
+
+            match end {
+                Some(end) => p = end,
+                None => {
+                    push_escaped(&mut html, &c.to_string());
+                    p += 1;
+                }
+            }
+        }
+
+        html
+    }
+
+    fn push_quoted(&mut self, delimiter: char, text: &str, html: &mut String) {
+        match delimiter {
+            '*' => html.push_str(&format!("<strong>{}</strong>", self.inline(text))),
+            '_' => html.push_str(&format!("<em>{}</em>", self.inline(text))),
+            '#' => html.push_str(&format!("<mark>{}</mark>", self.inline(text))),
+            '`' => {
+                // `+text+` is a literal monospace phrase.
+                let text = text
+                    .strip_prefix('+')
+                    .and_then(|text| text.strip_suffix('+'))
+                    .unwrap_or(text);
+                html.push_str(&format!("<code>{}</code>", escape(text)));
+            }
+            _ => push_escaped(html, text),
+        }
+    }
+
+    /// Renders a cross reference, e.g. `<<installation,Installation>>`.
+    fn cross_reference(&mut self, chars: &[char], p: usize, html: &mut String) -> Option<usize> {
+        let rest = chars[p + 2..].iter().collect::<String>();
+        let end = rest.find(">>")?;
+        let (id, label) = match rest[..end].split_once(',') {
+            Some((id, label)) => (id.trim(), label.trim()),
+            None => (rest[..end].trim(), ""),
+        };
+        if id.is_empty() || id.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let section = self.sections.get(id);
+        let href = match (section, id.split_once('#')) {
+            (Some(section), _) => format!("#user-content-{}", section.anchor),
+            (None, Some((document, fragment))) => format!("{document}#{fragment}"),
+            (None, None) => format!("#user-content-{id}"),
+        };
+        let label = match (label, section) {
+            ("", Some(section)) => {
+                let title = section.title.clone();
+                self.inline(&title)
+            }
+            ("", None) => escape(id),
+            (label, _) => self.inline(label),
+        };
+        html.push_str(&format!(
+            "<a href=\"{}\">{label}</a>",
+            escape_attribute(&href)
+        ));
+
+        Some(p + 2 + rest[..end].chars().count() + 2)
+    }
+
+    /// Renders URLs and inline macros like `link:`, `image:` and `kbd:`.
+    fn inline_macro(&mut self, chars: &[char], p: usize, html: &mut String) -> Option<usize> {
+        let name_length = chars[p..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .count();
+        if chars.get(p + name_length) != Some(&':') {
+            return None;
+        }
+
+        let rest = chars[p..].iter().collect::<String>();
+
+        if ["https://", "http://", "ftp://", "irc://"]
+            .iter()
+            .any(|scheme| rest.starts_with(scheme))
+        {
+            let target = rest
+                .split(|c: char| c.is_whitespace() || matches!(c, '[' | '<' | '>' | '"'))
+                .next()?;
+            if rest[target.len()..].starts_with('[')
+                && let Some((label, length)) = macro_content(&rest[target.len()..])
+            {
+                self.push_link(target, &label, html);
+                return Some(p + target.chars().count() + length);
+            }
+
+            let target = target.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'']);
+            self.push_link(target, "", html);
+            return Some(p + target.chars().count());
+        }
+
+        let (name, target) = rest.split_once(':')?;
+        if !name.chars().all(|c| c.is_ascii_alphabetic()) || target.starts_with(':') {
+            return None;
+        }
+
+        let target_length = target.find('[')?;
+        let (content, length) = macro_content(&target[target_length..])?;
+        let target = &target[..target_length];
+        if target.contains(char::is_whitespace) {
+            return None;
+        }
+
+        match name {
+            "link" | "mailto" | "xref" if !target.is_empty() => {
+                let target = match name {
+                    "mailto" => format!("mailto:{target}"),
+                    _ => target.to_string(),
+                };
+                self.push_link(&target, &content, html);
+            }
+            "image" if !target.is_empty() => {
+                let image = self.image(&format!("{target}[{content}]"));
+                html.push_str(&image);
+            }
+            "kbd" => html.push_str(&format!("<kbd>{}</kbd>", escape(&content))),
+            "btn" | "menu" => html.push_str(&format!("<b>{}</b>", escape(&content))),
+            "pass" => html.push_str(&content),
+            "footnote" => {
+                let footnote = self.inline(&content);
+                self.footnotes.push(footnote);
+                let number = self.footnotes.len();
+                html.push_str(&format!(
+                    "<sup>[<a href=\"#user-content-_footnotedef_{number}\">{number}</a>]</sup>"
+                ));
+            }
+            "anchor" => {}
+            _ => return None,
+        }
+
+        Some(p + name.chars().count() + 1 + target.chars().count() + length)
+    }
+
+    fn push_link(&mut self, target: &str, label: &str, html: &mut String) {
+        // Named attributes like `window=_blank` follow the link text.
+        let label = match label.contains('=') {
+            true => label.split(',').next().unwrap_or_default().trim(),
+            false => label.trim(),
+        };
+        // A trailing caret is a shorthand for opening the link in a new window.
+        let label = label.strip_suffix('^').unwrap_or(label).trim_matches('"');
+        let label = match label {
+            "" => escape(target.strip_prefix("mailto:").unwrap_or(target)),
+            label => self.inline(label),
+        };
+        html.push_str(&format!(
+            "<a href=\"{}\">{label}</a>",
+            escape_attribute(target)
+        ));
+    }
+}
+
+/// Collects the section titles of the document.
+///
+/// The IDs of the rendered headings are generated like in rendered Markdown,
+/// so cross references to the AsciiDoc default IDs (e.g. `_getting_started`)
+/// have to be mapped to them.
+fn collect_sections(lines: &[String]) -> HashMap<String, Section> {
+    let mut sections = HashMap::new();
+    let mut anchorizer = Anchorizer::new();
+    let mut explicit_id = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        if is_delimiter(line) {
+            i = delimited_block_end(lines, i).1;
+            explicit_id = None;
+            continue;
+        }
+        i += 1;
+
+        if let Some(id) = block_id(line) {
+            explicit_id = Some(id);
+        } else if let Some((_, title)) = heading(line) {
+            // The anchors of the rendered headings are based on the rendered
+            // titles, which don't contain the formatting marks.
+            let plain_title = title.replace(['*', '`', '#', '+'], "");
+            let anchor = match explicit_id.take() {
+                Some(id) => {
+                    let section = Section {
+                        anchor: id.clone(),
+                        title: title.to_string(),
+                    };
+                    sections.insert(id.clone(), section);
+                    id
+                }
+                None => anchorizer.anchorize(&plain_title),
+            };
+
+            let section = Section {
+                anchor,
+                title: title.to_string(),
+            };
+            sections.entry(default_id(title)).or_insert(section);
+        } else if !line.starts_with('[') && !line.trim().is_empty() {
+            explicit_id = None;
+        }
+    }
+
+    sections
+}
+
+/// Returns the AsciiDoc default ID of a section title, e.g.
+/// `_getting_started` for `Getting Started`.
+fn default_id(title: &str) -> String {
+    let mut id = String::from("_");
+    for c in title.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            id.push(c);
+        } else if (c.is_whitespace() || matches!(c, '-' | '.' | '_')) && !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+
+    match id.trim_end_matches('_') {
+        "" => id,
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Parses the ID of a block anchor, e.g. `[[id]]` or `[#id]`.
+fn block_id(line: &str) -> Option<String> {
+    let attributes = line.strip_prefix('[')?.strip_suffix(']')?;
+    let id = match attributes.strip_prefix('[') {
+        Some(anchor) => anchor.strip_suffix(']')?.split(',').next()?,
+        None => {
+            let style = attributes.split(',').next()?;
+            let (_, id) = style.split_once('#')?;
+            id.split(['.', '%']).next()?
+        }
+    };
+
+    Some(id.trim().to_string()).filter(|id| !id.is_empty())
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with("//") && !line.starts_with("///")
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Finds the end of a constrained quoted text, e.g. `*strong*`.
+fn find_constrained(chars: &[char], start: usize, delimiter: char) -> Option<usize> {
+    if chars
+        .get(start)
+        .is_none_or(|c| c.is_whitespace() || *c == delimiter)
+    {
+        return None;
+    }
+
+    (start + 1..chars.len()).find(|&q| {
+        chars[q] == delimiter
+            && !chars[q - 1].is_whitespace()
+            && chars.get(q + 1).is_none_or(|c| !is_word_char(*c))
+    })
+}
+
+/// Finds the end of an unconstrained quoted text, e.g. `**strong**`.
+fn find_unconstrained(chars: &[char], start: usize, delimiter: &[char]) -> Option<usize> {
+    if start >= chars.len() || chars[start].is_whitespace() {
+        return None;
+    }
+
+    (start + 1..chars.len()).find(|&q| chars[q..].starts_with(delimiter))
+}
+
+/// Parses the content of a macro in square brackets, and returns it
+/// together with its length including the brackets.
+fn macro_content(text: &str) -> Option<(String, usize)> {
+    let text = text.strip_prefix('[')?;
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth == 0 => {
+                let content = text[..i].replace("\\]", "]");
+                return Some((content, text[..i].chars().count() + 2));
+            }
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits a list of attributes, e.g. `source,rust,linenums`.
+fn split_attributes(attributes: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut attribute = String::new();
+    let mut in_quotes = false;
+    for c in attributes.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                attribute.push(c);
+            }
+            ',' if !in_quotes => result.push(std::mem::take(&mut attribute).trim().to_string()),
+            _ => attribute.push(c),
+        }
+    }
+    result.push(attribute.trim().to_string());
+    result
+}
+
+/// Splits the cells of a table row. Cell specifiers like `2+` or `a` are
+/// ignored.
+fn split_cells(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => {
+                let content = std::mem::take(&mut cell);
+                let content = strip_cell_specifier(&content);
+                cells.push(content.trim().to_string());
+            }
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// Removes a cell specifier of the next cell from the end of a cell, e.g.
+/// `foo 2+` of `|foo 2+|bar`.
+fn strip_cell_specifier(cell: &str) -> &str {
+    match cell.rsplit_once(' ') {
+        Some((content, specifier))
+            if !specifier.is_empty()
+                && specifier
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || matches!(c, '+' | '*' | '.' | 'a'))
+                && specifier.contains(['+', '*']) =>
+        {
+            content
+        }
+        _ => cell,
+    }
+}
+
+/// Removes a cell specifier from the start of a row, e.g. `2+` of `2+|foo`.
+fn strip_row_specifier(line: &str) -> &str {
+    match line.find('|') {
+        Some(position)
+            if position > 0
+                && position <= 4
+                && line[..position].chars().all(|c| {
+                    c.is_ascii_digit() || matches!(c, '+' | '*' | '.' | 'a' | 'l' | 'm')
+                }) =>
+        {
+            &line[position..]
+        }
+        _ => line,
+    }
+}
+
+/// Returns the number of columns of a `cols` attribute, e.g. `3*` or `1,2`.
+fn column_count(cols: &str) -> usize {
+    cols.split(',')
+        .map(|column| match column.trim().split_once('*') {
+            Some((count, _)) => count.parse().unwrap_or(1),
+            None => 1,
+        })
+        .sum()
+}
+
+fn is_preprocessor_directive(line: &str) -> bool {
+    ["include::", "ifdef::", "ifndef::", "ifeval::", "endif::"]
+        .iter()
+        .any(|directive| line.starts_with(directive))
+        && line.ends_with(']')
+}
+
+/// Parses an attribute entry, e.g. `:toc: left` or `:toc!:`.
+fn attribute_entry(line: &str) -> Option<(String, Option<String>)> {
+    let (name, value) = line.strip_prefix(':')?.split_once(':')?;
+    if !value.is_empty() && !value.starts_with(' ') {
+        return None;
+    }
+
+    let (name, unset) = match name.strip_suffix('!').or(name.strip_prefix('!')) {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+
+    let value = (!unset).then(|| value.trim().to_string());
+    Some((name.to_lowercase(), value))
+}
+
+/// Parses a section title, e.g. `== Installation`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let marker = line.chars().next().filter(|c| *c == '=' || *c == '#')?;
+    let level = line.chars().take_while(|c| *c == marker).count();
+    let title = line[level..].strip_prefix(' ')?.trim();
+    (level <= 6 && !title.is_empty()).then_some((level, title))
+}
+
+fn is_delimiter(line: &str) -> bool {
+    if line.starts_with("```") {
+        return true;
+    }
+    if line == "--" || line.starts_with("|===") && line.chars().skip(1).all(|c| c == '=') {
+        return true;
+    }
+
+    let mut chars = line.chars();
+    let first = chars.next();
+    line.len() >= 4
+        && first.is_some_and(|first| "-.+_=*/".contains(first) && chars.all(|c| c == first))
+}
+
+/// Returns the end of the content of the delimited block starting at `i`,
+/// and the position after its closing delimiter.
+fn delimited_block_end(lines: &[String], i: usize) -> (usize, usize) {
+    let delimiter = if lines[i].starts_with("```") {
+        "```"
+    } else {
+        lines[i].as_str()
+    };
+
+    lines[i + 1..]
+        .iter()
+        .position(|line| line == delimiter)
+        .map_or((lines.len(), lines.len()), |end| (i + 1 + end, i + 2 + end))
+}
+
+/// Returns the end of the paragraph starting at `i`.
+fn paragraph_end(lines: &[String], i: usize) -> usize {
+    i + 1
+        + lines[i + 1..]
+            .iter()
+            .take_while(|line| {
+                !line.trim().is_empty()
+                    && !is_delimiter(line)
+                    && !is_preprocessor_directive(line)
+                    && *line != "+"
+            })
+            .count()
+}
+
+/// Parses the marker of a list item, e.g. `**` or `.`, and returns it
+/// together with the text of the item.
+fn list_marker(line: &str) -> Option<(String, &str)> {
+    let line = line.trim_start();
+    if let Some((number, text)) = line.split_once(". ")
+        && !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+        && !text.trim().is_empty()
+    {
+        return Some((".".to_string(), text.trim_start()));
+    }
+
+    let marker_char = line
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '*' | '-' | '.'))?;
+
+    let marker_length = match marker_char {
+        '-' => 1,
+        _ => line.chars().take_while(|c| *c == marker_char).count(),
+    };
+    let text = line[marker_length..].strip_prefix(' ')?.trim_start();
+    if text.is_empty() || marker_length > 5 {
+        return None;
+    }
+
+    Some((line[..marker_length].to_string(), text))
+}
+
+/// Parses a description list item, e.g. `CPU:: The brain of the computer`.
+fn description_list_item(line: &str) -> Option<(&str, &str)> {
+    let (term, definition) = line.split_once("::")?;
+    if term.trim().is_empty()
+        || term.starts_with(' ')
+        || term.contains("://")
+        || !(definition.is_empty() || definition.starts_with(' '))
+    {
+        return None;
+    }
+
+    Some((term.trim(), definition.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::assert_never_panics;
+    use crate::{RenderOptions, asciidoc_to_html};
+    use insta::assert_snapshot;
+
+    fn render(text: &str) -> String {
+        asciidoc_to_html(text, None, "", RenderOptions::default())
+    }
+
+    #[test]
+    fn section_titles() {
+        let text = "\
+= Title
+:toc:
+
+== Section
+
+[[custom-id]]
+=== Subsection
+
+== Section
+";
+        assert_snapshot!(render(text), @r##"
+        <h1 id="user-content-title">Title<a href="#user-content-title" aria-label="Link to heading 'Title'" rel="nofollow noopener noreferrer"></a></h1>
+        <h2 id="user-content-section">Section<a href="#user-content-section" aria-label="Link to heading 'Section'" rel="nofollow noopener noreferrer"></a></h2>
+        <h3 id="user-content-custom-id">Subsection<a href="#user-content-custom-id" aria-label="Link to heading 'Subsection'" rel="nofollow noopener noreferrer"></a></h3>
+        <h2 id="user-content-section-1">Section<a href="#user-content-section-1" aria-label="Link to heading 'Section'" rel="nofollow noopener noreferrer"></a></h2>
+        "##);
+    }
+
+    #[test]
+    fn inline_markup() {
+        let text = "_emphasis_, *strong*, `literal`, #mark#, **un**constrained, \
+snake_case_name, \\*escaped* text, kbd:[Ctrl+C] and +*passthrough*+ with a +
+hard line break.";
+        assert_snapshot!(render(text), @"
+        <p><em>emphasis</em>, <strong>strong</strong>, <code>literal</code>, <mark>mark</mark>, <strong>un</strong>constrained, snake_case_name, *escaped* text, <kbd>Ctrl+C</kbd> and *passthrough* with a<br>
+        hard line break.</p>
+        ");
+    }
+
+    #[test]
+    fn links() {
+        let text = "\
+:url-repo: https://github.com/rust-lang/crates.io
+
+https://rust-lang.org/[Rust], https://crates.io, {url-repo}[repository^],
+link:docs/README.adoc[docs], mailto:help@crates.io[email us], <<_getting_started>>
+and <<custom-id,custom>>.footnote:[A footnote.]
+
+== Getting Started
+
+[#custom-id]
+== Custom
+";
+        assert_snapshot!(render(text), @r##"
+        <p><a href="https://rust-lang.org/" rel="nofollow noopener noreferrer">Rust</a>, <a href="https://crates.io" rel="nofollow noopener noreferrer">https://crates.io</a>, <a href="https://github.com/rust-lang/crates.io" rel="nofollow noopener noreferrer">repository</a>,
+        <a rel="nofollow noopener noreferrer">docs</a>, <a href="mailto:help@crates.io" rel="nofollow noopener noreferrer">email us</a>, <a href="#user-content-getting-started" rel="nofollow noopener noreferrer">Getting Started</a>
+        and <a href="#user-content-custom-id" rel="nofollow noopener noreferrer">custom</a>.<sup>[<a href="#user-content-_footnotedef_1" rel="nofollow noopener noreferrer">1</a>]</sup></p>
+        <h2 id="user-content-getting-started">Getting Started<a href="#user-content-getting-started" aria-label="Link to heading 'Getting Started'" rel="nofollow noopener noreferrer"></a></h2>
+        <h2 id="user-content-custom-id">Custom<a href="#user-content-custom-id" aria-label="Link to heading 'Custom'" rel="nofollow noopener noreferrer"></a></h2>
+        <section class="footnotes">
+        <ol>
+        <li id="user-content-_footnotedef_1">A footnote.</li>
+        </ol>
+        </section>
+        "##);
+    }
+
+    #[test]
+    fn images() {
+        let text = "\
+image::https://img.shields.io/crates/v/foo.svg[Version,link=https://crates.io/crates/foo]
+
+Inline image:logo.png[Logo,64,64] in text.
+";
+        assert_snapshot!(render(text), @r#"
+        <p><a href="https://crates.io/crates/foo" rel="nofollow noopener noreferrer"><img src="https://img.shields.io/crates/v/foo.svg" alt="Version"></a></p>
+        <p>Inline <img height="64" alt="Logo" width="64"> in text.</p>
+        "#);
+    }
+
+    #[test]
+    fn lists() {
+        let text = "\
+* first
+** nested
+* second
++
+Attached paragraph.
+
+//-
+. one
+. two
+
+1. three
+
+//-
+* [x] done
+* [ ] todo
+
+CPU:: The brain of the computer.
+RAM::
+  Memory.
+";
+        assert_snapshot!(render(text), @r#"
+        <ul>
+        <li>first
+        <ul>
+        <li>nested</li>
+        </ul>
+        </li>
+        <li>
+        <p>second</p>
+        <p>Attached paragraph.</p>
+        </li>
+        </ul>
+        <ol>
+        <li>one</li>
+        <li>two</li>
+        <li>three</li>
+        </ol>
+        <ul>
+        <li><input type="checkbox" checked="" disabled=""> done</li>
+        <li><input type="checkbox" disabled=""> todo</li>
+        </ul>
+        <dl>
+        <dt>CPU</dt>
+        <dd>The brain of the computer.</dd>
+        <dt>RAM</dt>
+        <dd>Memory.</dd>
+        </dl>
+        "#);
+    }
+
+    #[test]
+    fn code_blocks() {
+        let text = "\
+:source-language: rust
+
+[source,toml]
+----
+[dependencies]
+----
+
+----
+fn main() {}
+----
+
+```shell
+cargo build
+```
+
+....
+literal <block>
+....
+
+  indented literal
+";
+        assert_snapshot!(render(text), @r#"
+        <pre><code class="language-toml">[dependencies]
+        </code></pre>
+        <pre><code class="language-rust">fn main() {}
+        </code></pre>
+        <pre><code class="">cargo build
+        </code></pre>
+        <pre><code>literal &lt;block&gt;
+        </code></pre>
+        <pre><code>indented literal
+        </code></pre>
+        "#);
+    }
+
+    #[test]
+    fn admonitions() {
+        let text = "\
+NOTE: A note.
+
+[WARNING]
+====
+A warning.
+====
+
+[TIP]
+A tip.
+";
+        assert_snapshot!(render(text), @r#"
+        <div class="markdown-alert markdown-alert-note">
+        <p class="markdown-alert-title">Note</p>
+        <p>A note.</p>
+        </div>
+        <div class="markdown-alert markdown-alert-warning">
+        <p class="markdown-alert-title">Warning</p>
+        <p>A warning.</p>
+        </div>
+        <div class="markdown-alert markdown-alert-tip">
+        <p class="markdown-alert-title">Tip</p>
+        <p>A tip.</p>
+        </div>
+        "#);
+    }
+
+    #[test]
+    fn tables() {
+        let text = "\
+|===
+|A |B
+
+|1 |2
+|3 |4
+|===
+
+[cols=\"2*\", options=\"header\"]
+|===
+|A
+|B
+|1
+|2
+|===
+";
+        assert_snapshot!(render(text), @"
+        <table>
+        <thead>
+        <tr>
+        <th>A</th>
+        <th>B</th>
+        </tr>
+        </thead>
+        <tbody>
+        <tr>
+        <td>1</td>
+        <td>2</td>
+        </tr>
+        <tr>
+        <td>3</td>
+        <td>4</td>
+        </tr>
+        </tbody>
+        </table>
+        <table>
+        <thead>
+        <tr>
+        <th>A</th>
+        <th>B</th>
+        </tr>
+        </thead>
+        <tbody>
+        <tr>
+        <td>1</td>
+        <td>2</td>
+        </tr>
+        </tbody>
+        </table>
+        ");
+    }
+
+    #[test]
+    fn skipped_markup() {
+        let text = "\
+// A comment
+////
+A comment block.
+////
+include::CHANGELOG.adoc[]
+ifdef::env-github[]
+Content
+endif::[]
+
+.Title
+____
+A quote.
+____
+
+'''
+";
+        assert_snapshot!(render(text), @"
+        <p>Content</p>
+        <p><strong>Title</strong></p>
+        <blockquote>
+        <p>A quote.</p>
+        </blockquote>
+        <hr>
+        ");
+    }
+
+    #[test]
+    fn never_panics() {
+        const SAMPLE: &str = "\
+= Title
+:toc:
+:url-repo: https://github.com/rust-lang/crates.io
+
+== Section
+
+[[custom-id]]
+=== Subsection
+
+*bold* _italic_ `mono` kbd:[Ctrl+C] +*passthrough*+ with a +
+hard line break.footnote:[A footnote.]
+
+https://rust-lang.org/[Rust], {url-repo}[repository^], <<custom-id,custom>>
+
+image::https://img.shields.io/crates/v/foo.svg[Version,link=https://crates.io/crates/foo]
+
+* first
+** nested
++
+Attached paragraph.
+
+. one
+* [x] done
+
+CPU:: The brain.
+RAM::
+  Memory.
+
+[source,toml]
+----
+[dependencies]
+----
+
+....
+literal <block>
+....
+
+NOTE: A note.
+
+[WARNING]
+====
+A warning.
+====
+
+[cols=\"2*\", options=\"header\"]
+|===
+|A |B
+|1 |2
+|===
+
+////
+A comment block.
+////
+____
+A quote.
+____
+";
+
+        let fragments = [
+            "\n",
+            "\n\n",
+            " ",
+            "  ",
+            "\t",
+            "=",
+            "== ",
+            "*",
+            "**",
+            "_",
+            "`",
+            "+",
+            "#",
+            "|",
+            "|===",
+            "----",
+            "....",
+            "====",
+            "____",
+            "////",
+            "//",
+            ".",
+            ". ",
+            "* ",
+            "::",
+            ":",
+            "[",
+            "]",
+            "[[",
+            "]]",
+            "<<",
+            ">>",
+            "{",
+            "}",
+            "a",
+            "word",
+            "ü",
+            "<",
+            "\\",
+            "[source,rust]",
+            "[cols=\"2*\"]",
+            "NOTE: ",
+            "image::",
+            "link:",
+            "footnote:[",
+            "kbd:[",
+            "https://",
+            ":attr: value",
+        ];
+        assert_never_panics(&fragments, SAMPLE, render);
+    }
+}
@@ -0,0 +1,575 @@
+//! Server-side syntax highlighting of Rust, TOML and shell code blocks.
+//!
+//! The highlighted code is wrapped in `<span>` elements with `hl-*` classes,
+//! which are allowed by the sanitizer. Styling them is left to the consumer
+//! of the rendered HTML.
+
+use crate::html::push_escaped;
+use comrak::adapters::SyntaxHighlighterAdapter;
+use comrak::html::write_opening_tag;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The classes of the highlighted tokens.
+pub(crate) const CLASSES: [&str; 13] = [
+    "hl-attribute",
+    "hl-comment",
+    "hl-function",
+    "hl-keyword",
+    "hl-lifetime",
+    "hl-literal",
+    "hl-macro",
+    "hl-number",
+    "hl-property",
+    "hl-string",
+    "hl-table",
+    "hl-type",
+    "hl-variable",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "type", "unsafe", "use",
+    "where", "while", "yield",
+];
+
+const RUST_PRIMITIVES: &[&str] = &[
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str", "u8", "u16",
+    "u32", "u64", "u128", "usize",
+];
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "do", "done", "elif", "else", "esac", "fi", "for", "function", "if", "in", "select",
+    "then", "until", "while",
+];
+
+/// The languages that are highlighted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Language {
+    Rust,
+    Toml,
+    Shell,
+}
+
+impl Language {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Some(Self::Rust),
+            "toml" => Some(Self::Toml),
+            "sh" | "bash" | "shell" | "zsh" => Some(Self::Shell),
+            _ => None,
+        }
+    }
+}
+
+/// Appends the highlighted HTML of `code`, or just the escaped `code` if the
+/// language is not supported.
+pub(crate) fn push_highlighted(html: &mut String, language: Option<&str>, code: &str) {
+    let mut output = Output { html };
+    match language.and_then(Language::from_name) {
+        Some(Language::Rust) => rust(code, &mut output),
+        Some(Language::Toml) => toml(code, &mut output),
+        Some(Language::Shell) => shell(code, &mut output),
+        None => output.plain(code),
+    }
+}
+
+/// Highlights the fenced code blocks of rendered Markdown.
+pub(crate) struct CodeBlockHighlighter;
+
+impl SyntaxHighlighterAdapter for CodeBlockHighlighter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn fmt::Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> fmt::Result {
+        let mut html = String::with_capacity(code.len());
+        push_highlighted(&mut html, lang, code);
+        output.write_str(&html)
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn fmt::Write,
+        attributes: HashMap<&'static str, Cow<'_, str>>,
+    ) -> fmt::Result {
+        write_opening_tag(output, "pre", attributes)
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn fmt::Write,
+        attributes: HashMap<&'static str, Cow<'_, str>>,
+    ) -> fmt::Result {
+        write_opening_tag(output, "code", attributes)
+    }
+}
+
+struct Output<'a> {
+    html: &'a mut String,
+}
+
+impl Output<'_> {
+    fn plain(&mut self, text: &str) {
+        push_escaped(self.html, text);
+    }
+
+    fn token(&mut self, class: &str, text: &str) {
+        self.html.push_str("<span class=\"");
+        self.html.push_str(class);
+        self.html.push_str("\">");
+        push_escaped(self.html, text);
+        self.html.push_str("</span>");
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the length in bytes of the prefix whose characters match `f`.
+fn prefix_len(text: &str, f: impl Fn(char) -> bool) -> usize {
+    text.find(|c| !f(c)).unwrap_or(text.len())
+}
+
+/// Returns the length in bytes of the first character of `text`.
+fn first_char_len(text: &str) -> usize {
+    text.chars().next().map_or(0, char::len_utf8)
+}
+
+/// Returns the length of a string that starts with `quote` at the beginning
+/// of `text`, handling backslash escapes if `escapes` is set. Unterminated
+/// strings extend to the end of `text`.
+fn quoted_len(text: &str, quote: &str, escapes: bool) -> usize {
+    let mut i = quote.len();
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with(quote) {
+            return i + quote.len();
+        }
+        if escapes && rest.starts_with('\\') {
+            i += 1;
+        }
+        i += text[i..].chars().next().map_or(1, char::len_utf8);
+    }
+    text.len()
+}
+
+fn rust(code: &str, output: &mut Output<'_>) {
+    let mut i = 0;
+    while i < code.len() {
+        let rest = &code[i..];
+        let (class, len) = rust_token(rest);
+        let len = len.max(first_char_len(rest)).min(rest.len());
+        match class {
+            Some(class) => output.token(class, &rest[..len]),
+            None => output.plain(&rest[..len]),
+        }
+        i += len;
+    }
+}
+
+fn rust_token(rest: &str) -> (Option<&'static str>, usize) {
+    if rest.starts_with("//") {
+        return (Some("hl-comment"), prefix_len(rest, |c| c != '\n'));
+    }
+
+    if rest.starts_with("/*") {
+        let mut depth = 0;
+        let mut i = 0;
+        while i < rest.len() {
+            if rest[i..].starts_with("/*") {
+                depth += 1;
+                i += 2;
+            } else if rest[i..].starts_with("*/") {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    break;
+                }
+            } else {
+                i += first_char_len(&rest[i..]);
+            }
+        }
+        return (Some("hl-comment"), i);
+    }
+
+    let unprefixed = rest
+        .strip_prefix('b')
+        .or_else(|| rest.strip_prefix('c'))
+        .unwrap_or(rest);
+    let prefix = rest.len() - unprefixed.len();
+
+    if let Some(raw) = unprefixed.strip_prefix('r') {
+        let hashes = prefix_len(raw, |c| c == '#');
+        if raw[hashes..].starts_with('"') {
+            let terminator = format!("\"{}", "#".repeat(hashes));
+            let body = &raw[hashes + 1..];
+            let len = body
+                .find(&terminator)
+                .map_or(body.len(), |end| end + terminator.len());
+            return (Some("hl-string"), prefix + 1 + hashes + 1 + len);
+        }
+    }
+
+    if unprefixed.starts_with('"') {
+        return (
+            Some("hl-string"),
+            prefix + quoted_len(unprefixed, "\"", true),
+        );
+    }
+
+    if let Some(body) = unprefixed.strip_prefix('\'') {
+        let char_len = match body.strip_prefix('\\') {
+            Some(escaped) => 1 + prefix_len(escaped, |c| c != '\'' && c != '\n'),
+            None => first_char_len(body),
+        };
+        if char_len > 0 && body[char_len..].starts_with('\'') {
+            return (Some("hl-string"), prefix + 1 + char_len + 1);
+        }
+        if prefix == 0 && body.starts_with(is_ident_start) {
+            return (Some("hl-lifetime"), 1 + prefix_len(body, is_ident_continue));
+        }
+    }
+
+    if rest.starts_with(|c: char| c.is_ascii_digit()) {
+        let mut len = 0;
+        loop {
+            len += prefix_len(&rest[len..], is_ident_continue);
+            let fraction = &rest[len..];
+            if fraction.starts_with('.') && fraction[1..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                len += 1;
+            } else {
+                break;
+            }
+        }
+        return (Some("hl-number"), len);
+    }
+
+    if rest.starts_with("#[") || rest.starts_with("#![") {
+        let mut depth = 0;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return (Some("hl-attribute"), i + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return (Some("hl-attribute"), rest.len());
+    }
+
+    if rest.starts_with(is_ident_start) {
+        let len = prefix_len(rest, is_ident_continue);
+        let ident = &rest[..len];
+        let next = &rest[len..];
+
+        let class = if RUST_KEYWORDS.contains(&ident) {
+            Some("hl-keyword")
+        } else if ident == "true" || ident == "false" {
+            Some("hl-literal")
+        } else if next.starts_with('!') && !next.starts_with("!=") {
+            return (Some("hl-macro"), len + 1);
+        } else if RUST_PRIMITIVES.contains(&ident) || ident.starts_with(char::is_uppercase) {
+            Some("hl-type")
+        } else if next.starts_with('(') {
+            Some("hl-function")
+        } else {
+            None
+        };
+        return (class, len);
+    }
+
+    (None, 0)
+}
+
+fn toml(code: &str, output: &mut Output<'_>) {
+    // Whether the next token is a key, which is the case at the start of a
+    // line and after `{` and `,` in inline tables.
+    let mut expect_key = true;
+    // The open brackets of arrays and inline tables.
+    let mut brackets = Vec::new();
+
+    let mut i = 0;
+    while i < code.len() {
+        let rest = &code[i..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        let (class, len) = if c == '\n' {
+            expect_key = brackets.is_empty();
+            (None, 1)
+        } else if c.is_whitespace() {
+            (None, prefix_len(rest, |c| c.is_whitespace() && c != '\n'))
+        } else if c == '#' {
+            (Some("hl-comment"), prefix_len(rest, |c| c != '\n'))
+        } else if expect_key && c == '[' && brackets.is_empty() {
+            expect_key = false;
+            (Some("hl-table"), table_header_len(rest))
+        } else if expect_key && c != '}' {
+            expect_key = false;
+            (Some("hl-property"), key_len(rest))
+        } else if rest.starts_with("\"\"\"") {
+            (Some("hl-string"), quoted_len(rest, "\"\"\"", true))
+        } else if rest.starts_with("'''") {
+            (Some("hl-string"), quoted_len(rest, "'''", false))
+        } else if c == '"' {
+            (Some("hl-string"), quoted_len(rest, "\"", true))
+        } else if c == '\'' {
+            (Some("hl-string"), quoted_len(rest, "'", false))
+        } else if c == '[' || c == '{' {
+            brackets.push(c);
+            expect_key = c == '{';
+            (None, 1)
+        } else if c == ']' || c == '}' {
+            brackets.pop();
+            (None, 1)
+        } else if c == ',' {
+            expect_key = brackets.last() == Some(&'{');
+            (None, 1)
+        } else if c.is_ascii_alphanumeric() || c == '+' || c == '-' {
+            let len = prefix_len(rest, |c| c.is_ascii_alphanumeric() || "_.:+-".contains(c));
+            let word = &rest[..len];
+            let unsigned = word.trim_start_matches(['+', '-']);
+            let class = if word == "true" || word == "false" {
+                Some("hl-literal")
+            } else if unsigned.starts_with(|c: char| c.is_ascii_digit())
+                || unsigned == "inf"
+                || unsigned == "nan"
+            {
+                Some("hl-number")
+            } else {
+                None
+            };
+            (class, len)
+        } else {
+            (None, c.len_utf8())
+        };
+
+        let len = len.max(c.len_utf8());
+        match class {
+            Some(class) => output.token(class, &rest[..len]),
+            None => output.plain(&rest[..len]),
+        }
+        i += len;
+    }
+}
+
+/// Returns the length of a `[table]` or `[[array]]` header, without a
+/// trailing comment.
+fn table_header_len(text: &str) -> usize {
+    let line = &text[..prefix_len(text, |c| c != '\n')];
+    line.rfind(']').map_or(line.len(), |end| end + 1)
+}
+
+/// Returns the length of a key, up to the `=` sign or the end of the line.
+fn key_len(text: &str) -> usize {
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with(['=', '\n', '#', ',', '}']) {
+            break;
+        }
+        i += match rest.chars().next() {
+            Some('"') => quoted_len(rest, "\"", true),
+            Some('\'') => quoted_len(rest, "'", false),
+            Some(c) => c.len_utf8(),
+            None => 1,
+        };
+    }
+    text[..i].trim_end().len()
+}
+
+fn shell(code: &str, output: &mut Output<'_>) {
+    // Whether the next word is the name of a command.
+    let mut command = true;
+    // Whether the previous character separates words.
+    let mut word_start = true;
+
+    let mut i = 0;
+    while i < code.len() {
+        let rest = &code[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        let line_start = i == 0 || code[..i].ends_with('\n');
+
+        let (class, len) = if c == '\n' {
+            command = !code[..i].ends_with('\\');
+            (None, 1)
+        } else if c.is_whitespace() {
+            (None, prefix_len(rest, |c| c.is_whitespace() && c != '\n'))
+        } else if line_start && rest.starts_with("$ ") {
+            // A prompt
+            (None, 2)
+        } else if c == '#' && word_start {
+            (Some("hl-comment"), prefix_len(rest, |c| c != '\n'))
+        } else if c == '"' {
+            (Some("hl-string"), quoted_len(rest, "\"", true))
+        } else if c == '\'' {
+            (Some("hl-string"), quoted_len(rest, "'", false))
+        } else if c == '$' {
+            let name = &rest[1..];
+            let len = if name.starts_with('{') {
+                name.find('}').map_or(name.len(), |end| end + 1)
+            } else if name.starts_with(is_ident_start) {
+                prefix_len(name, is_ident_continue)
+            } else if name.starts_with(|c: char| c.is_ascii_digit() || "@#?$!*-".contains(c)) {
+                1
+            } else {
+                0
+            };
+            if len == 0 {
+                command = name.starts_with('(');
+                (None, 1)
+            } else {
+                (Some("hl-variable"), 1 + len)
+            }
+        } else if ";|&(`{".contains(c) {
+            command = true;
+            (None, 1)
+        } else if ")<>}".contains(c) {
+            (None, 1)
+        } else {
+            let len = prefix_len(rest, |c| !c.is_whitespace() && !";|&()<>\"'$`".contains(c));
+            let word = &rest[..len];
+            if command && SHELL_KEYWORDS.contains(&word) {
+                command = !matches!(word, "case" | "for" | "function" | "select");
+                (Some("hl-keyword"), len)
+            } else if command
+                && let Some((name, value)) = word.split_once('=')
+                && name.starts_with(is_ident_start)
+                && name.chars().all(is_ident_continue)
+            {
+                // An assignment in front of the command
+                output.token("hl-variable", name);
+                output.plain("=");
+                output.plain(value);
+                word_start = false;
+                i += len;
+                continue;
+            } else if command {
+                command = false;
+                (Some("hl-function"), len)
+            } else {
+                (None, len)
+            }
+        };
+
+        let len = len.max(first_char_len(rest));
+        match class {
+            Some(class) => output.token(class, &rest[..len]),
+            None => output.plain(&rest[..len]),
+        }
+        word_start = rest[..len].ends_with(char::is_whitespace);
+        i += len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::push_highlighted;
+    use insta::assert_snapshot;
+
+    fn highlight(language: &str, code: &str) -> String {
+        let mut html = String::new();
+        push_highlighted(&mut html, Some(language), code);
+        html
+    }
+
+    #[test]
+    fn rust() {
+        let code = r##"#[derive(Debug)]
+struct Foo<'a> {
+    name: &'a str, // the name
+}
+
+/* block /* nested */ comment */
+fn main() {
+    let foo = Foo { name: "a \"quoted\" name" };
+    let raw = r#"raw "string""#;
+    println!("{foo:?} {} {}", 'x', 1.5e3 as u8 + 0xff);
+    let _ = true != false;
+}"##;
+        assert_snapshot!(highlight("rust", code), @r#"
+        <span class="hl-attribute">#[derive(Debug)]</span>
+        <span class="hl-keyword">struct</span> <span class="hl-type">Foo</span>&lt;<span class="hl-lifetime">&#x27;a</span>&gt; {
+            name: &amp;<span class="hl-lifetime">&#x27;a</span> <span class="hl-type">str</span>, <span class="hl-comment">// the name</span>
+        }
+
+        <span class="hl-comment">/* block /* nested */ comment */</span>
+        <span class="hl-keyword">fn</span> <span class="hl-function">main</span>() {
+            <span class="hl-keyword">let</span> foo = <span class="hl-type">Foo</span> { name: <span class="hl-string">&quot;a \&quot;quoted\&quot; name&quot;</span> };
+            <span class="hl-keyword">let</span> raw = <span class="hl-string">r#&quot;raw &quot;string&quot;&quot;#</span>;
+            <span class="hl-macro">println!</span>(<span class="hl-string">&quot;{foo:?} {} {}&quot;</span>, <span class="hl-string">&#x27;x&#x27;</span>, <span class="hl-number">1.5e3</span> <span class="hl-keyword">as</span> <span class="hl-type">u8</span> + <span class="hl-number">0xff</span>);
+            <span class="hl-keyword">let</span> _ = <span class="hl-literal">true</span> != <span class="hl-literal">false</span>;
+        }
+        "#);
+    }
+
+    #[test]
+    fn toml() {
+        let code = r#"[package]
+name = "foo" # the name
+version = '1.0.0'
+"edition" = 2024
+
+[dependencies]
+serde = { version = "1", features = ["derive"], default-features = false }
+time.version = 0.3
+date = 1979-05-27T07:32:00Z
+description = """
+multi-line
+"""
+
+[[bin]]
+name = "foo""#;
+        assert_snapshot!(highlight("toml", code), @r#"
+        <span class="hl-table">[package]</span>
+        <span class="hl-property">name</span> = <span class="hl-string">&quot;foo&quot;</span> <span class="hl-comment"># the name</span>
+        <span class="hl-property">version</span> = <span class="hl-string">&#x27;1.0.0&#x27;</span>
+        <span class="hl-property">&quot;edition&quot;</span> = <span class="hl-number">2024</span>
+
+        <span class="hl-table">[dependencies]</span>
+        <span class="hl-property">serde</span> = { <span class="hl-property">version</span> = <span class="hl-string">&quot;1&quot;</span>, <span class="hl-property">features</span> = [<span class="hl-string">&quot;derive&quot;</span>], <span class="hl-property">default-features</span> = <span class="hl-literal">false</span> }
+        <span class="hl-property">time.version</span> = <span class="hl-number">0.3</span>
+        <span class="hl-property">date</span> = <span class="hl-number">1979-05-27T07:32:00Z</span>
+        <span class="hl-property">description</span> = <span class="hl-string">&quot;&quot;&quot;
+        multi-line
+        &quot;&quot;&quot;</span>
+
+        <span class="hl-table">[[bin]]</span>
+        <span class="hl-property">name</span> = <span class="hl-string">&quot;foo&quot;</span>
+        "#);
+    }
+
+    #[test]
+    fn shell() {
+        let code = r#"$ cargo install --locked foo
+# a comment
+RUST_LOG=debug cargo run -- "$HOME/foo" 'bar' | grep -v ${PATTERN} # done
+if [ -f Cargo.toml ]; then echo "found #1"; fi
+for crate in $(ls crates); do echo $crate; done"#;
+        assert_snapshot!(highlight("bash", code), @r#"
+        $ <span class="hl-function">cargo</span> install --locked foo
+        <span class="hl-comment"># a comment</span>
+        <span class="hl-variable">RUST_LOG</span>=debug <span class="hl-function">cargo</span> run -- <span class="hl-string">&quot;$HOME/foo&quot;</span> <span class="hl-string">&#x27;bar&#x27;</span> | <span class="hl-function">grep</span> -v <span class="hl-variable">${PATTERN}</span> <span class="hl-comment"># done</span>
+        <span class="hl-keyword">if</span> <span class="hl-function">[</span> -f Cargo.toml ]; <span class="hl-keyword">then</span> <span class="hl-function">echo</span> <span class="hl-string">&quot;found #1&quot;</span>; <span class="hl-keyword">fi</span>
+        <span class="hl-keyword">for</span> crate in $(<span class="hl-function">ls</span> crates); <span class="hl-keyword">do</span> <span class="hl-function">echo</span> <span class="hl-variable">$crate</span>; <span class="hl-keyword">done</span>
+        "#);
+    }
+
+    #[test]
+    fn unsupported_language() {
+        assert_snapshot!(highlight("python", "print('<foo>')"), @"print(&#x27;&lt;foo&gt;&#x27;)");
+    }
+}
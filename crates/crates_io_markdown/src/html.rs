@@ -0,0 +1,195 @@
+//! Helpers for the renderers of the formats that are not rendered by
+//! `comrak`.
+//!
+//! The generated HTML follows the structure of the rendered Markdown, so that
+//! the same sanitizer settings and stylesheets apply to all formats.
+
+use crate::highlight::push_highlighted;
+use comrak::Anchorizer;
+use htmlescape::{encode_attribute, encode_minimal};
+
+/// The admonition types that are rendered like GitHub-style alerts.
+pub(crate) const ALERTS: [&str; 5] = ["note", "tip", "important", "warning", "caution"];
+
+/// Escapes text content.
+pub(crate) fn escape(text: &str) -> String {
+    encode_minimal(text)
+}
+
+/// Escapes the value of a double-quoted attribute.
+pub(crate) fn escape_attribute(value: &str) -> String {
+    encode_attribute(value)
+}
+
+/// Appends escaped text content.
+pub(crate) fn push_escaped(html: &mut String, text: &str) {
+    html.push_str(&encode_minimal(text));
+}
+
+/// Removes all tags from the given HTML and decodes the remaining entities.
+pub(crate) fn to_plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    htmlescape::decode_html(&text).unwrap_or(text)
+}
+
+/// Appends a heading with an anchor link, in the same format as the headings
+/// of rendered Markdown.
+pub(crate) fn push_heading(
+    html: &mut String,
+    anchorizer: &mut Anchorizer,
+    level: usize,
+    id: Option<&str>,
+    content: &str,
+) {
+    let level = level.clamp(1, 6);
+    let text = to_plain_text(content);
+    let id = match id {
+        Some(id) => format!("user-content-{id}"),
+        None => format!("user-content-{}", anchorizer.anchorize(&text)),
+    };
+    let id = escape_attribute(&id);
+    let label = escape_attribute(&format!("Link to heading '{text}'"));
+
+    html.push_str(&format!(
+        "<h{level} id=\"{id}\">{content}<a href=\"#{id}\" aria-label=\"{label}\"></a></h{level}>\n"
+    ));
+}
+
+/// Appends a code block. The `language` is turned into a `language-*` class,
+/// which is removed by the sanitizer unless it is supported by the frontend.
+/// The code is highlighted on the server if `syntax_highlighting` is set.
+pub(crate) fn push_code_block(
+    html: &mut String,
+    language: Option<&str>,
+    code: &str,
+    syntax_highlighting: bool,
+) {
+    match language
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+    {
+        Some(language) => {
+            let class = escape_attribute(&format!("language-{}", language.to_lowercase()));
+            html.push_str(&format!("<pre><code class=\"{class}\">"));
+        }
+        None => html.push_str("<pre><code>"),
+    }
+
+    match syntax_highlighting {
+        true => push_highlighted(html, language, code),
+        false => push_escaped(html, code),
+    }
+    if !code.is_empty() && !code.ends_with('\n') {
+        html.push('\n');
+    }
+    html.push_str("</code></pre>\n");
+}
+
+/// Appends an admonition in the format of a GitHub-style alert. `kind` must
+/// be one of [`ALERTS`].
+pub(crate) fn push_alert(html: &mut String, kind: &str, title: Option<&str>, content: &str) {
+    let title = match title {
+        Some(title) => escape(title),
+        None => {
+            let mut chars = kind.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+    };
+
+    html.push_str(&format!(
+        "<div class=\"markdown-alert markdown-alert-{kind}\">\n<p class=\"markdown-alert-title\">{title}</p>\n{content}</div>\n"
+    ));
+}
+
+/// Appends a table. The cells are expected to be HTML already.
+pub(crate) fn push_table(html: &mut String, header: Option<&[String]>, rows: &[Vec<String>]) {
+    html.push_str("<table>\n");
+    if let Some(header) = header {
+        html.push_str("<thead>\n<tr>\n");
+        for cell in header {
+            html.push_str(&format!("<th>{cell}</th>\n"));
+        }
+        html.push_str("</tr>\n</thead>\n");
+    }
+    if !rows.is_empty() {
+        html.push_str("<tbody>\n");
+        for row in rows {
+            html.push_str("<tr>\n");
+            for cell in row {
+                html.push_str(&format!("<td>{cell}</td>\n"));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</tbody>\n");
+    }
+    html.push_str("</table>\n");
+}
+
+/// Returns the content of a list item or definition, without the enclosing
+/// paragraph if it only consists of a single one.
+pub(crate) fn unwrap_paragraph(html: &str) -> &str {
+    html.strip_prefix("<p>")
+        .and_then(|html| html.strip_suffix("</p>\n"))
+        .filter(|content| !content.contains("<p>") && !content.contains("</p>"))
+        .unwrap_or(html)
+}
+
+/// Returns the number of leading spaces of a line.
+pub(crate) fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Removes the common indentation of the given lines.
+pub(crate) fn dedent(lines: &[String]) -> Vec<String> {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+        .min()
+        .unwrap_or_default();
+
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default().to_string())
+        .collect()
+}
+
+/// Returns the index of the first non-blank line at or after `i`.
+pub(crate) fn skip_blank_lines(lines: &[String], mut i: usize) -> usize {
+    while lines.get(i).is_some_and(|line| line.trim().is_empty()) {
+        i += 1;
+    }
+    i
+}
+
+/// Splits the text into lines, with tabs expanded to spaces and trailing
+/// whitespace removed.
+pub(crate) fn lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| {
+            let mut expanded = String::with_capacity(line.len());
+            for c in line.trim_end().chars() {
+                if c == '\t' {
+                    let width = 8 - expanded.chars().count() % 8;
+                    expanded.extend(std::iter::repeat_n(' ', width));
+                } else {
+                    expanded.push(c);
+                }
+            }
+            expanded
+        })
+        .collect()
+}
@@ -7,9 +7,37 @@ use std::borrow::Cow;
 use std::path::Path;
 use url::Url;
 
+mod asciidoc;
+mod highlight;
+mod html;
+mod rst;
+#[cfg(test)]
+mod test_utils;
+mod toc;
+
+pub use crate::toc::Heading;
+
+/// Options for [`render_readme`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderOptions {
+    /// Highlight fenced Rust, TOML and shell code blocks on the server,
+    /// using `<span>` elements with `hl-*` classes.
+    pub syntax_highlighting: bool,
+}
+
+/// A README file rendered by [`render_readme`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderedReadme {
+    /// The sanitized HTML.
+    pub html: String,
+    /// The headings of the HTML that can be linked to, in document order.
+    pub table_of_contents: Vec<Heading>,
+}
+
 /// Context for markdown to HTML rendering.
 struct MarkdownRenderer<'a> {
     html_sanitizer: Builder<'a>,
+    options: RenderOptions,
 }
 
 impl<'a> MarkdownRenderer<'a> {
@@ -17,7 +45,11 @@ impl<'a> MarkdownRenderer<'a> {
     ///
     /// Per `text_to_html`, `base_url` is the base URL prepended to any
     /// relative links in the input document.  See that function for more detail.
-    fn new(base_url: Option<&'a str>, base_dir: &'a str) -> MarkdownRenderer<'a> {
+    fn new(
+        base_url: Option<&'a str>,
+        base_dir: &'a str,
+        options: RenderOptions,
+    ) -> MarkdownRenderer<'a> {
         let allowed_classes = hashmap(&[
             (
                 "code",
@@ -58,6 +90,7 @@ impl<'a> MarkdownRenderer<'a> {
                 ]),
             ),
             ("p", hashset(&["markdown-alert-title"])),
+            ("span", hashset(&highlight::CLASSES)),
         ]);
         let sanitize_url = UrlRelative::Custom(Box::new(SanitizeUrl::new(base_url, base_dir)));
 
@@ -79,12 +112,15 @@ impl<'a> MarkdownRenderer<'a> {
             .allowed_classes(allowed_classes)
             .url_relative(sanitize_url)
             .id_prefix(Some("user-content-"));
-        MarkdownRenderer { html_sanitizer }
+        MarkdownRenderer {
+            html_sanitizer,
+            options,
+        }
     }
 
     /// Renders the given markdown to HTML using the current settings.
     fn to_html(&self, text: &str) -> String {
-        use comrak::{Arena, format_html, options, parse_document};
+        use comrak::{Arena, format_html_with_plugins, options, parse_document};
 
         let render_options = options::Render::builder()
             // The output will be sanitized with `ammonia`
@@ -126,9 +162,20 @@ impl<'a> MarkdownRenderer<'a> {
             }
         });
 
+        let highlighter = highlight::CodeBlockHighlighter;
+        let mut plugins = options::Plugins::default();
+        if self.options.syntax_highlighting {
+            plugins.render.codefence_syntax_highlighter = Some(&highlighter);
+        }
+
         let mut html = String::new();
-        format_html(root, &options, &mut html).unwrap();
-        self.html_sanitizer.clean(&html).to_string()
+        format_html_with_plugins(root, &options, &mut html, &plugins).unwrap();
+        self.sanitize(&html)
+    }
+
+    /// Sanitizes HTML that was rendered from any of the supported formats.
+    fn sanitize(&self, html: &str) -> String {
+        self.html_sanitizer.clean(html).to_string()
     }
 }
 
@@ -258,24 +305,63 @@ impl UrlRelativeEvaluate<'_> for SanitizeUrl {
 
 /// Renders Markdown text to sanitized HTML with a given `base_url`.
 /// See `text_to_html` for the interpretation of `base_url`.
-fn markdown_to_html(text: &str, base_url: Option<&str>, base_dir: &str) -> String {
-    let renderer = MarkdownRenderer::new(base_url, base_dir);
+fn markdown_to_html(
+    text: &str,
+    base_url: Option<&str>,
+    base_dir: &str,
+    options: RenderOptions,
+) -> String {
+    let renderer = MarkdownRenderer::new(base_url, base_dir, options);
     renderer.to_html(text)
 }
 
+/// Renders reStructuredText to sanitized HTML with a given `base_url`.
+/// See `text_to_html` for the interpretation of `base_url`.
+fn rst_to_html(
+    text: &str,
+    base_url: Option<&str>,
+    base_dir: &str,
+    options: RenderOptions,
+) -> String {
+    let renderer = MarkdownRenderer::new(base_url, base_dir, options);
+    renderer.sanitize(&rst::to_html(text, options.syntax_highlighting))
+}
+
+/// Renders AsciiDoc to sanitized HTML with a given `base_url`.
+/// See `text_to_html` for the interpretation of `base_url`.
+fn asciidoc_to_html(
+    text: &str,
+    base_url: Option<&str>,
+    base_dir: &str,
+    options: RenderOptions,
+) -> String {
+    let renderer = MarkdownRenderer::new(base_url, base_dir, options);
+    renderer.sanitize(&asciidoc::to_html(text, options.syntax_highlighting))
+}
+
 /// Any file with a filename ending in one of these extensions will be rendered as Markdown.
 /// Note we also render a file as Markdown if _no_ extension is on the filename.
 static MARKDOWN_EXTENSIONS: [&str; 7] =
     ["md", "markdown", "mdown", "mdwn", "mkd", "mkdn", "mkdown"];
 
+/// Any file with a filename ending in one of these extensions will be rendered as
+/// reStructuredText.
+static RST_EXTENSIONS: [&str; 2] = ["rst", "rest"];
+
+/// Any file with a filename ending in one of these extensions will be rendered as AsciiDoc.
+static ASCIIDOC_EXTENSIONS: [&str; 3] = ["adoc", "asciidoc", "asc"];
+
 /// Renders a text file to sanitized HTML.  An appropriate rendering method is chosen depending
 /// on the extension of the supplied `filename`.
 ///
 /// The returned text will not contain any harmful HTML tag or attribute (such as iframe,
 /// onclick, onmouseover, etc.).
 ///
+/// Markdown, reStructuredText and AsciiDoc files are supported. Files in any other format
+/// are rendered as plain text.
+///
 /// The `base_url` parameter will be used as the base for any relative links found in the
-/// Markdown, as long as its host part is github.com, gitlab.com, or bitbucket.org.  The
+/// document, as long as its host part is github.com, gitlab.com, or bitbucket.org.  The
 /// supplied URL will be used as a directory base whether or not the relative link is
 /// prefixed with '/'.  If `None` is passed, relative links will be omitted.
 ///
@@ -294,6 +380,31 @@ pub fn text_to_html<P: AsRef<Path>>(
     base_url: Option<&str>,
     pkg_path_in_vcs: Option<P>,
 ) -> String {
+    let options = RenderOptions::default();
+    render_readme(text, readme_path_in_pkg, base_url, pkg_path_in_vcs, options).html
+}
+
+/// Renders a README file like [`text_to_html`], and additionally generates
+/// the table of contents of the rendered HTML.
+///
+/// # Examples
+///
+/// ```
+/// use crates_io_markdown::{RenderOptions, render_readme};
+///
+/// let text = "# Usage\n\n```toml\nserde = \"1\"\n```";
+/// let options = RenderOptions { syntax_highlighting: true };
+/// let rendered = render_readme(text, "README.md", None, None, options);
+/// assert_eq!(rendered.table_of_contents[0].id, "user-content-usage");
+/// assert!(rendered.html.contains("<span class=\"hl-property\">serde</span>"));
+/// ```
+pub fn render_readme<P: AsRef<Path>>(
+    text: &str,
+    readme_path_in_pkg: P,
+    base_url: Option<&str>,
+    pkg_path_in_vcs: Option<P>,
+    options: RenderOptions,
+) -> RenderedReadme {
     let path_in_vcs = match pkg_path_in_vcs {
         None => readme_path_in_pkg.as_ref().to_path_buf(),
         Some(pkg_path_in_vcs) => pkg_path_in_vcs.as_ref().join(readme_path_in_pkg),
@@ -301,14 +412,38 @@ pub fn text_to_html<P: AsRef<Path>>(
 
     let base_dir = path_in_vcs.parent().and_then(|p| p.to_str()).unwrap_or("");
 
+    let html = render_html(text, &path_in_vcs, base_url, base_dir, options);
+
+    let table_of_contents = toc::table_of_contents(&html);
+    RenderedReadme {
+        html,
+        table_of_contents,
+    }
+}
+
+/// Renders a text file to sanitized HTML, depending on the extension of `path_in_vcs`.
+fn render_html(
+    text: &str,
+    path_in_vcs: &Path,
+    base_url: Option<&str>,
+    base_dir: &str,
+    options: RenderOptions,
+) -> String {
     if path_in_vcs.extension().is_none() {
-        return markdown_to_html(text, base_url, base_dir);
+        return markdown_to_html(text, base_url, base_dir, options);
     }
 
-    if let Some(ext) = path_in_vcs.extension().and_then(|ext| ext.to_str())
-        && MARKDOWN_EXTENSIONS.contains(&ext.to_lowercase().as_str())
-    {
-        return markdown_to_html(text, base_url, base_dir);
+    if let Some(ext) = path_in_vcs.extension().and_then(|ext| ext.to_str()) {
+        let ext = ext.to_lowercase();
+        if MARKDOWN_EXTENSIONS.contains(&ext.as_str()) {
+            return markdown_to_html(text, base_url, base_dir, options);
+        }
+        if RST_EXTENSIONS.contains(&ext.as_str()) {
+            return rst_to_html(text, base_url, base_dir, options);
+        }
+        if ASCIIDOC_EXTENSIONS.contains(&ext.as_str()) {
+            return asciidoc_to_html(text, base_url, base_dir, options);
+        }
     }
 
     encode_minimal(text).replace('\n', "<br>\n")
@@ -334,18 +469,21 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use insta::assert_snapshot;
+    use insta::{assert_debug_snapshot, assert_snapshot};
 
     #[test]
     fn empty_text() {
         let text = "";
-        assert_eq!(markdown_to_html(text, None, ""), "");
+        assert_eq!(
+            markdown_to_html(text, None, "", RenderOptions::default()),
+            ""
+        );
     }
 
     #[test]
     fn text_with_script_tag() {
         let text = "foo_readme\n\n<script>alert('Hello World')</script>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r"
         <p>foo_readme</p>
         &lt;script&gt;alert('Hello World')&lt;/script&gt;
         ");
@@ -354,7 +492,7 @@ mod tests {
     #[test]
     fn text_with_iframe_tag() {
         let text = "foo_readme\n\n<iframe>alert('Hello World')</iframe>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r"
         <p>foo_readme</p>
         &lt;iframe&gt;alert('Hello World')&lt;/iframe&gt;
         ");
@@ -363,7 +501,7 @@ mod tests {
     #[test]
     fn text_with_unknown_tag() {
         let text = "foo_readme\n\n<unknown>alert('Hello World')</unknown>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r"
         <p>foo_readme</p>
         <p>alert('Hello World')</p>
         ");
@@ -372,7 +510,7 @@ mod tests {
     #[test]
     fn text_with_kbd_tag() {
         let text = "foo_readme\n\nHello <kbd>alert('Hello World')</kbd>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r"
         <p>foo_readme</p>
         <p>Hello <kbd>alert('Hello World')</kbd></p>
         ");
@@ -381,7 +519,7 @@ mod tests {
     #[test]
     fn text_with_inline_javascript() {
         let text = r#"foo_readme\n\n<a href="https://crates.io/crates/cargo-registry" onclick="window.alert('Got you')">Crate page</a>"#;
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"<p>foo_readme\n\n<a href="https://crates.io/crates/cargo-registry" rel="nofollow noopener noreferrer">Crate page</a></p>"#);
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"<p>foo_readme\n\n<a href="https://crates.io/crates/cargo-registry" rel="nofollow noopener noreferrer">Crate page</a></p>"#);
     }
 
     // See https://github.com/kivikakk/comrak/issues/37. This panic happened
@@ -389,13 +527,13 @@ mod tests {
     #[test]
     fn text_with_fancy_single_quotes() {
         let text = "wb’";
-        assert_snapshot!(markdown_to_html(text, None, ""), @"<p>wb’</p>");
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @"<p>wb’</p>");
     }
 
     #[test]
     fn code_block_with_syntax_highlighting() {
         let code_block = "```rust\nprintln!(\"Hello World\");\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(code_block, None, "", RenderOptions::default()), @r#"
         <pre><code class="language-rust">println!("Hello World");
         </code></pre>
         "#);
@@ -404,7 +542,7 @@ mod tests {
     #[test]
     fn code_block_with_mermaid_highlighting() {
         let code_block = "```mermaid\ngraph LR\nA --> C\nC --> A\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(code_block, None, "", RenderOptions::default()), @r#"
         <pre><code class="language-mermaid">graph LR
         A --&gt; C
         C --&gt; A
@@ -415,7 +553,7 @@ mod tests {
     #[test]
     fn code_block_with_syntax_highlighting_even_if_annot_has_no_run() {
         let code_block = "```rust, no_run\nprintln!(\"Hello World\");\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(code_block, None, "", RenderOptions::default()), @r#"
         <pre><code class="language-rust">println!("Hello World");
         </code></pre>
         "#);
@@ -424,19 +562,19 @@ mod tests {
     #[test]
     fn code_block_with_syntax_highlighting_with_aliases() {
         let code_block = "```rs, no_run\nprintln!(\"Hello World\");\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(code_block, None, "", RenderOptions::default()), @r#"
         <pre><code class="language-rs">println!("Hello World");
         </code></pre>
         "#);
 
         let code_block = "```markup, no_run\n<hello>World</hello>\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(code_block, None, "", RenderOptions::default()), @r#"
         <pre><code class="language-markup">&lt;hello&gt;World&lt;/hello&gt;
         </code></pre>
         "#);
 
         let code_block = "```clike, no_run\nint main() { }\n```";
-        assert_snapshot!(markdown_to_html(code_block, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(code_block, None, "", RenderOptions::default()), @r#"
         <pre><code class="language-clike">int main() { }
         </code></pre>
         "#);
@@ -445,13 +583,13 @@ mod tests {
     #[test]
     fn text_with_forbidden_class_attribute() {
         let text = "<p class='bad-class'>Hello World!</p>";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"<p class="">Hello World!</p>"#);
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"<p class="">Hello World!</p>"#);
     }
 
     #[test]
     fn text_with_footnote() {
         let text = "Hello World![^1]\n\n[^1]: Hello Ferris, actually!";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r##"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r##"
         <p>Hello World!<sup><a href="#user-content-fn-1" id="user-content-fnref-1" rel="nofollow noopener noreferrer">1</a></sup></p>
         <section class="footnotes">
         <ol>
@@ -479,7 +617,7 @@ There can also be some text in between!
 
     Add as many paragraphs as you like."#;
 
-        assert_snapshot!(markdown_to_html(text, None, ""), @r##"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r##"
         <p>Here's a simple footnote,<sup><a href="#user-content-fn-1" id="user-content-fnref-1" rel="nofollow noopener noreferrer">1</a></sup> and here's a longer one.<sup><a href="#user-content-fn-bignote" id="user-content-fnref-bignote" rel="nofollow noopener noreferrer">2</a></sup></p>
         <p>There can also be some text in between!</p>
         <section class="footnotes">
@@ -515,7 +653,7 @@ There can also be some text in between!
                     if extra_slash { "/" } else { "" },
                 );
 
-                let result = markdown_to_html(absolute, Some(&url), "");
+                let result = markdown_to_html(absolute, Some(&url), "", RenderOptions::default());
                 assert_eq!(
                     result,
                     format!(
@@ -523,7 +661,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(relative, Some(&url), "");
+                let result = markdown_to_html(relative, Some(&url), "", RenderOptions::default());
                 assert_eq!(
                     result,
                     format!(
@@ -531,7 +669,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(image, Some(&url), "");
+                let result = markdown_to_html(image, Some(&url), "", RenderOptions::default());
                 assert_eq!(
                     result,
                     format!(
@@ -539,7 +677,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(html_image, Some(&url), "");
+                let result = markdown_to_html(html_image, Some(&url), "", RenderOptions::default());
                 assert_eq!(
                     result,
                     format!(
@@ -547,7 +685,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(svg, Some(&url), "");
+                let result = markdown_to_html(svg, Some(&url), "", RenderOptions::default());
                 assert_eq!(
                     result,
                     format!(
@@ -555,7 +693,7 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(svg, Some(&url), "subdir");
+                let result = markdown_to_html(svg, Some(&url), "subdir", RenderOptions::default());
                 assert_eq!(
                     result,
                     format!(
@@ -563,7 +701,8 @@ There can also be some text in between!
                     )
                 );
 
-                let result = markdown_to_html(svg, Some(&url), "subdir1/subdir2");
+                let result =
+                    markdown_to_html(svg, Some(&url), "subdir1/subdir2", RenderOptions::default());
                 assert_eq!(
                     result,
                     format!(
@@ -573,7 +712,12 @@ There can also be some text in between!
             }
         }
 
-        let result = markdown_to_html(absolute, Some("https://google.com/"), "");
+        let result = markdown_to_html(
+            absolute,
+            Some("https://google.com/"),
+            "",
+            RenderOptions::default(),
+        );
         assert_eq!(
             result,
             "<p><a rel=\"nofollow noopener noreferrer\">hi</a></p>\n"
@@ -584,16 +728,16 @@ There can also be some text in between!
     fn absolute_links_dont_get_resolved() {
         let text = "[![crates.io](https://img.shields.io/crates/v/clap.svg)](https://crates.io/crates/clap)";
         let repository = "https://github.com/kbknapp/clap-rs/";
-        assert_snapshot!(markdown_to_html(text, Some(repository), ""), @r#"<p><a href="https://crates.io/crates/clap" rel="nofollow noopener noreferrer"><img src="https://img.shields.io/crates/v/clap.svg" alt="crates.io"></a></p>"#);
+        assert_snapshot!(markdown_to_html(text, Some(repository), "", RenderOptions::default()), @r#"<p><a href="https://crates.io/crates/clap" rel="nofollow noopener noreferrer"><img src="https://img.shields.io/crates/v/clap.svg" alt="crates.io"></a></p>"#);
     }
 
     #[test]
     fn rustdoc_links() {
         let repository = "https://github.com/foo/bar/";
 
-        assert_snapshot!(markdown_to_html("[stylish](::stylish)", Some(repository), ""), @r#"<p><a rel="nofollow noopener noreferrer">stylish</a></p>"#);
+        assert_snapshot!(markdown_to_html("[stylish](::stylish)", Some(repository), "", RenderOptions::default()), @r#"<p><a rel="nofollow noopener noreferrer">stylish</a></p>"#);
 
-        assert_snapshot!(markdown_to_html("[Display](stylish::Display)", Some(repository), ""), @r#"<p><a rel="nofollow noopener noreferrer">Display</a></p>"#);
+        assert_snapshot!(markdown_to_html("[Display](stylish::Display)", Some(repository), "", RenderOptions::default()), @r#"<p><a rel="nofollow noopener noreferrer">Display</a></p>"#);
     }
 
     #[test]
@@ -621,7 +765,7 @@ There can also be some text in between!
 
     #[test]
     fn text_to_html_renders_other_things() {
-        for f in &["readme.exe", "readem.org", "blah.adoc"] {
+        for f in &["readme.exe", "readem.org", "blah.txt"] {
             assert_eq!(
                 text_to_html("<script>lobster</script>\n\nis my friend\n", f, None, None),
                 "&lt;script&gt;lobster&lt;/script&gt;<br>\n<br>\nis my friend<br>\n"
@@ -629,10 +773,124 @@ There can also be some text in between!
         }
     }
 
+    #[test]
+    fn text_to_html_renders_rst() {
+        for f in &["README.rst", "readme.REST", "s/readme.rst"] {
+            assert_eq!(
+                text_to_html("*lobster*", f, None, None),
+                "<p><em>lobster</em></p>\n"
+            );
+        }
+
+        assert_snapshot!(text_to_html("`lobster <docs/lobster>`_", "s/README.rst", Some("https://github.com/rust-lang/test"), Some("path/in/vcs")), @r#"<p><a href="https://github.com/rust-lang/test/blob/HEAD/path/in/vcs/s/docs/lobster" rel="nofollow noopener noreferrer">lobster</a></p>"#);
+        assert_snapshot!(text_to_html(".. image:: docs/lobster.svg", "README.rst", Some("https://github.com/rust-lang/test"), None), @r#"<p><img src="https://github.com/rust-lang/test/raw/HEAD/docs/lobster.svg?sanitize=true"></p>"#);
+        assert_snapshot!(text_to_html("<script>lobster</script>\n\n.. raw:: html\n\n   <script>lobster</script>", "README.rst", None, None), @"<p>&lt;script&gt;lobster&lt;/script&gt;</p>");
+    }
+
+    #[test]
+    fn text_to_html_renders_asciidoc() {
+        for f in &["README.adoc", "readme.ASCIIDOC", "s/readme.asc"] {
+            assert_eq!(
+                text_to_html("_lobster_", f, None, None),
+                "<p><em>lobster</em></p>\n"
+            );
+        }
+
+        assert_snapshot!(text_to_html("link:docs/lobster[lobster]", "s/README.adoc", Some("https://github.com/rust-lang/test"), Some("path/in/vcs")), @r#"<p><a href="https://github.com/rust-lang/test/blob/HEAD/path/in/vcs/s/docs/lobster" rel="nofollow noopener noreferrer">lobster</a></p>"#);
+        assert_snapshot!(text_to_html("image::docs/lobster.svg[]", "README.adoc", Some("https://github.com/rust-lang/test"), None), @r#"<p><img src="https://github.com/rust-lang/test/raw/HEAD/docs/lobster.svg?sanitize=true"></p>"#);
+        assert_snapshot!(text_to_html("<script>lobster</script>\n\n++++\n<script>lobster</script>\n++++", "README.adoc", None, None), @"<p>&lt;script&gt;lobster&lt;/script&gt;</p>");
+    }
+
+    #[test]
+    fn render_readme_highlights_code_blocks() {
+        let text =
+            "```rust,ignore\nfn main() {}\n```\n\n```sh\n$ cargo run\n```\n\n```json\n{}\n```";
+        let options = RenderOptions {
+            syntax_highlighting: true,
+        };
+        assert_snapshot!(render_readme(text, "README.md", None, None, options).html, @r#"
+        <pre><code class="language-rust"><span class="hl-keyword">fn</span> <span class="hl-function">main</span>() {}
+        </code></pre>
+        <pre><code class="">$ <span class="hl-function">cargo</span> run
+        </code></pre>
+        <pre><code class="language-json">{}
+        </code></pre>
+        "#);
+
+        let text = ".. code-block:: toml\n\n   [dependencies]\n   foo = \"1\"";
+        assert_snapshot!(render_readme(text, "README.rst", None, None, options).html, @r#"
+        <pre><code class="language-toml"><span class="hl-table">[dependencies]</span>
+        <span class="hl-property">foo</span> = <span class="hl-string">"1"</span>
+        </code></pre>
+        "#);
+
+        let text = "[source,rust]\n----\nlet x = 1;\n----";
+        assert_snapshot!(render_readme(text, "README.adoc", None, None, options).html, @r#"
+        <pre><code class="language-rust"><span class="hl-keyword">let</span> x = <span class="hl-number">1</span>;
+        </code></pre>
+        "#);
+
+        let options = RenderOptions::default();
+        assert_snapshot!(render_readme("```rust\nfn main() {}\n```", "README.md", None, None, options).html, @r#"
+        <pre><code class="language-rust">fn main() {}
+        </code></pre>
+        "#);
+    }
+
+    #[test]
+    fn render_readme_table_of_contents() {
+        let text = "# Foo\n\n## Usage\n\n## `Foo` & Bar\n\n<h2>Raw</h2>\n\n## Usage";
+        let rendered = render_readme(text, "README.md", None, None, RenderOptions::default());
+        assert_debug_snapshot!(rendered.table_of_contents, @r#"
+        [
+            Heading {
+                level: 1,
+                id: "user-content-foo",
+                title: "Foo",
+            },
+            Heading {
+                level: 2,
+                id: "user-content-usage",
+                title: "Usage",
+            },
+            Heading {
+                level: 2,
+                id: "user-content-foo--bar",
+                title: "Foo & Bar",
+            },
+            Heading {
+                level: 2,
+                id: "user-content-usage-1",
+                title: "Usage",
+            },
+        ]
+        "#);
+
+        let text = "Foo\n===\n\nUsage\n-----";
+        let rendered = render_readme(text, "README.rst", None, None, RenderOptions::default());
+        assert_debug_snapshot!(rendered.table_of_contents, @r#"
+        [
+            Heading {
+                level: 1,
+                id: "user-content-foo",
+                title: "Foo",
+            },
+            Heading {
+                level: 2,
+                id: "user-content-usage",
+                title: "Usage",
+            },
+        ]
+        "#);
+
+        let rendered = render_readme(text, "README.txt", None, None, RenderOptions::default());
+        assert_eq!(rendered.table_of_contents, vec![]);
+    }
+
     #[test]
     fn heading_anchor_is_accessible_and_resolves() {
         let text = "# My crate\n\nHello, world!\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r##"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r##"
         <h1 id="user-content-my-crate">My crate<a href="#user-content-my-crate" aria-label="Link to heading 'My crate'" rel="nofollow noopener noreferrer"></a></h1>
         <p>Hello, world!</p>
         "##);
@@ -642,7 +900,7 @@ There can also be some text in between!
     fn all_heading_levels_keep_accessible_anchors() {
         let text =
             "## Heading 2\n### Heading 3\n#### Heading 4\n##### Heading 5\n###### Heading 6\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r##"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r##"
         <h2 id="user-content-heading-2">Heading 2<a href="#user-content-heading-2" aria-label="Link to heading 'Heading 2'" rel="nofollow noopener noreferrer"></a></h2>
         <h3 id="user-content-heading-3">Heading 3<a href="#user-content-heading-3" aria-label="Link to heading 'Heading 3'" rel="nofollow noopener noreferrer"></a></h3>
         <h4 id="user-content-heading-4">Heading 4<a href="#user-content-heading-4" aria-label="Link to heading 'Heading 4'" rel="nofollow noopener noreferrer"></a></h4>
@@ -655,7 +913,7 @@ There can also be some text in between!
     fn manual_anchor_is_sanitized() {
         let text =
             "<h1><a href=\"#my-crate\" id=\"my-crate\"></a>My crate</h1>\n<p>Hello, world!</p>\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r##"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r##"
         <h1><a href="#my-crate" id="user-content-my-crate" rel="nofollow noopener noreferrer"></a>My crate</h1>
         <p>Hello, world!</p>
         "##);
@@ -664,13 +922,13 @@ There can also be some text in between!
     #[test]
     fn tables_with_rowspan_and_colspan() {
         let text = "<table><tr><th rowspan=\"1\" colspan=\"2\">Target</th></tr></table>\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"<table><tbody><tr><th rowspan="1" colspan="2">Target</th></tr></tbody></table>"#);
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"<table><tbody><tr><th rowspan="1" colspan="2">Target</th></tr></tbody></table>"#);
     }
 
     #[test]
     fn definition_lists() {
         let text = "First term\n: First definition\n\nSecond term\n: Second definition\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"
         <dl>
         <dt>First term</dt>
         <dd>First definition</dd>
@@ -683,7 +941,7 @@ There can also be some text in between!
     #[test]
     fn text_alignment() {
         let text = "<h1 align=\"center\">foo-bar</h1>\n<h5 align=\"center\">Hello World!</h5>\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"
         <h1 align="center">foo-bar</h1>
         <h5 align="center">Hello World!</h5>
         "#);
@@ -692,7 +950,7 @@ There can also be some text in between!
     #[test]
     fn image_alignment() {
         let text = "<p align=\"center\"><img src=\"https://img.shields.io/crates/v/clap.svg\" alt=\"\"></p>\n";
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"<p align="center"><img src="https://img.shields.io/crates/v/clap.svg" alt=""></p>"#);
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"<p align="center"><img src="https://img.shields.io/crates/v/clap.svg" alt=""></p>"#);
     }
 
     #[test]
@@ -703,7 +961,7 @@ There can also be some text in between!
     <img src="https://test.crates.io/logo.svg" alt="logo" width="200">
 </picture>
         "#;
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"
         <picture>
             <source media="(prefers-color-scheme: dark)" srcset="https://test.crates.io/logo_dark.svg">
             <img src="https://test.crates.io/logo.svg" alt="logo" width="200">
@@ -718,7 +976,7 @@ There can also be some text in between!
 - [ ] [link](https://crates.io)
 - [ ] [link](#anchor)
         "#;
-        assert_snapshot!(markdown_to_html(text, None, ""), @r##"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r##"
         <ul>
         <li><input type="checkbox" disabled=""> <code>c</code></li>
         <li><input type="checkbox" disabled=""> <a href="https://crates.io" rel="nofollow noopener noreferrer">link</a></li>
@@ -733,7 +991,7 @@ There can also be some text in between!
 > [!note]
 > Hello, world!
         "#;
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"
         <div class="markdown-alert markdown-alert-note">
         <p class="markdown-alert-title">Note</p>
         <p>Hello, world!</p>
@@ -750,7 +1008,7 @@ Hello,
 world!
 >>>
 "#;
-        assert_snapshot!(markdown_to_html(text, None, ""), @r#"
+        assert_snapshot!(markdown_to_html(text, None, "", RenderOptions::default()), @r#"
         <div class="markdown-alert markdown-alert-note">
         <p class="markdown-alert-title">Note</p>
         <p>Hello,</p>
@@ -0,0 +1,1727 @@
+//! Renders the subset of reStructuredText that is commonly used in READMEs.
+//!
+//! Directives that don't produce any visible content in a README (e.g.
+//! `include` or `toctree`) are skipped, as are unknown directives and
+//! comments.
+
+use crate::html::{
+    ALERTS, dedent, escape, escape_attribute, indentation, lines, push_alert, push_code_block,
+    push_escaped, push_heading, push_table, skip_blank_lines, unwrap_paragraph,
+};
+use comrak::Anchorizer;
+use std::collections::HashMap;
+
+/// The maximum nesting depth of substitutions, to prevent endless recursion.
+const MAX_SUBSTITUTION_DEPTH: usize = 8;
+
+/// Renders reStructuredText to HTML. The result still needs to be sanitized.
+pub(crate) fn to_html(text: &str, syntax_highlighting: bool) -> String {
+    let lines = lines(text);
+    let mut renderer = Renderer::new(&lines, syntax_highlighting);
+
+    let mut html = String::new();
+    renderer.render_blocks(&lines, &mut html);
+    html
+}
+
+enum Substitution {
+    Image {
+        uri: String,
+        options: HashMap<String, String>,
+    },
+    Replace(String),
+}
+
+struct Renderer {
+    /// The URLs of the named hyperlink targets, by normalized name.
+    targets: HashMap<String, String>,
+    /// The URLs of the anonymous hyperlink targets, in document order.
+    anonymous_targets: Vec<String>,
+    next_anonymous_target: usize,
+    substitutions: HashMap<String, Substitution>,
+    substitution_depth: usize,
+    /// The adornment styles of the section titles, in order of appearance.
+    /// The position of a style determines the level of its sections.
+    title_styles: Vec<(char, bool)>,
+    /// The language of literal blocks, as set by the `highlight` directive.
+    highlight_language: Option<String>,
+    anchorizer: Anchorizer,
+    syntax_highlighting: bool,
+}
+
+impl Renderer {
+    /// Creates a new renderer, with the hyperlink targets and substitutions
+    /// of the whole document, since they may be referenced before they are
+    /// defined.
+    fn new(lines: &[String], syntax_highlighting: bool) -> Self {
+        let mut renderer = Self {
+            targets: HashMap::new(),
+            anonymous_targets: Vec::new(),
+            next_anonymous_target: 0,
+            substitutions: HashMap::new(),
+            substitution_depth: 0,
+            title_styles: Vec::new(),
+            highlight_language: None,
+            anchorizer: Anchorizer::new(),
+            syntax_highlighting,
+        };
+
+        for (i, line) in lines.iter().enumerate() {
+            let indent = indentation(line);
+            let continuation = lines[i + 1..]
+                .iter()
+                .take_while(|line| !line.trim().is_empty() && indentation(line) > indent)
+                .map(|line| line.trim())
+                .collect::<Vec<_>>();
+
+            let line = line.trim();
+            if let Some(uri) = line.strip_prefix("__ ") {
+                renderer
+                    .anonymous_targets
+                    .push(uri.trim().to_string() + &continuation.concat());
+                continue;
+            }
+
+            let Some(rest) = line.strip_prefix(".. ") else {
+                continue;
+            };
+
+            if let Some(uri) = rest.strip_prefix("__:") {
+                renderer
+                    .anonymous_targets
+                    .push(uri.trim().to_string() + &continuation.concat());
+            } else if let Some(target) = rest.strip_prefix('_') {
+                let (name, uri) = match target.strip_prefix('`') {
+                    Some(target) => match target.split_once("`:") {
+                        Some(target) => target,
+                        None => continue,
+                    },
+                    None => match target.split_once(':') {
+                        Some(target) => target,
+                        None => continue,
+                    },
+                };
+
+                let uri = uri.trim().to_string() + &continuation.concat();
+                if !uri.is_empty() {
+                    renderer.targets.insert(normalize_name(name), uri);
+                }
+            } else if let Some(definition) = rest.strip_prefix('|')
+                && let Some((name, directive)) = definition.split_once('|')
+            {
+                let directive = directive.trim();
+                let substitution = if let Some(uri) = directive.strip_prefix("image::") {
+                    let (options, _) = split_options(&continuation);
+                    Substitution::Image {
+                        uri: uri.trim().to_string(),
+                        options,
+                    }
+                } else if let Some(text) = directive.strip_prefix("replace::") {
+                    let mut text = text.trim().to_string();
+                    for line in &continuation {
+                        text.push(' ');
+                        text.push_str(line);
+                    }
+                    Substitution::Replace(text)
+                } else {
+                    continue;
+                };
+
+                renderer
+                    .substitutions
+                    .insert(normalize_name(name), substitution);
+            }
+        }
+
+        renderer
+    }
+
+    fn render_blocks(&mut self, lines: &[String], html: &mut String) {
+        let mut i = 0;
+        while i < lines.len() {
+            let line = &lines[i];
+            if line.trim().is_empty() {
+                i += 1;
+                continue;
+            }
+
+            if indentation(line) > 0 {
+                let (block, next) = indented_block(lines, i);
+                html.push_str("<blockquote>\n");
+                self.render_blocks(&block, html);
+                html.push_str("</blockquote>\n");
+                i = next;
+            } else if let Some(next) = self.section_title(lines, i, html) {
+                i = next;
+            } else if is_adornment(line) && line.len() >= 4 {
+                html.push_str("<hr />\n");
+                i += 1;
+            } else if line == ".." || line.starts_with(".. ") {
+                i = self.explicit_markup(lines, i, html);
+            } else if line.starts_with("__ ") {
+                // Anonymous hyperlink targets have been collected already.
+                i = indented_block(lines, i + 1).1;
+            } else if bullet(line).is_some() {
+                i = self.bullet_list(lines, i, html);
+            } else if enumerator(line).is_some() {
+                i = self.enumerated_list(lines, i, html);
+            } else if field_marker(line).is_some() {
+                i = self.field_list(lines, i, html);
+            } else if is_grid_table_border(line) {
+                i = self.grid_table(lines, i, html);
+            } else if is_simple_table_border(line) {
+                i = self.simple_table(lines, i, html);
+            } else if line.starts_with(">>>") {
+                let end = block_end(lines, i);
+                push_code_block(
+                    html,
+                    None,
+                    &lines[i..end].join("\n"),
+                    self.syntax_highlighting,
+                );
+                i = end;
+            } else if line == "|" || line.starts_with("| ") {
+                i = self.line_block(lines, i, html);
+            } else if is_definition_list_item(lines, i) {
+                i = self.definition_list(lines, i, html);
+            } else {
+                i = self.paragraph(lines, i, html);
+            }
+        }
+    }
+
+    fn render_nested(&mut self, lines: &[String]) -> String {
+        let mut html = String::new();
+        self.render_blocks(lines, &mut html);
+        html
+    }
+
+    /// Renders a section title, if the lines at `i` are one.
+    fn section_title(&mut self, lines: &[String], i: usize, html: &mut String) -> Option<usize> {
+        let line = &lines[i];
+        let (title, underline, overline, next) = if is_adornment(line) {
+            let title = lines.get(i + 1)?;
+            let underline = lines.get(i + 2)?;
+            if title.trim().is_empty() || underline != line {
+                return None;
+            }
+            (title.trim(), underline, true, i + 3)
+        } else {
+            let underline = lines.get(i + 1)?;
+            if !is_adornment(underline) || underline.chars().count() < line.chars().count() {
+                return None;
+            }
+            (line.as_str(), underline, false, i + 2)
+        };
+
+        let style = (underline.chars().next()?, overline);
+        let level = match self.title_styles.iter().position(|s| *s == style) {
+            Some(position) => position + 1,
+            None => {
+                self.title_styles.push(style);
+                self.title_styles.len()
+            }
+        };
+
+        let content = self.inline(title);
+        push_heading(html, &mut self.anchorizer, level, None, &content);
+        Some(next)
+    }
+
+    /// Renders a directive, a footnote or a comment. Hyperlink targets and
+    /// substitution definitions have been collected already.
+    fn explicit_markup(&mut self, lines: &[String], i: usize, html: &mut String) -> usize {
+        let (body, next) = indented_block(lines, i + 1);
+        let rest = lines[i].trim_start_matches('.').trim_start();
+
+        if rest.starts_with('_') || rest.starts_with('|') {
+            return next;
+        }
+
+        if let Some(footnote) = rest.strip_prefix('[')
+            && let Some((label, text)) = footnote.split_once(']')
+        {
+            let mut text = text.trim().to_string();
+            for line in &body {
+                text.push('\n');
+                text.push_str(line.trim());
+            }
+            let content = self.inline(text.trim());
+            html.push_str(&format!("<p>[{}] {content}</p>\n", escape(label)));
+            return next;
+        }
+
+        let Some((name, arguments)) = rest.split_once("::") else {
+            return next;
+        };
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return next;
+        }
+
+        let arguments = arguments.trim();
+        let (options, content) = split_options(&body);
+        let name = name.to_lowercase();
+        match name.as_str() {
+            "code" | "code-block" | "sourcecode" => {
+                let language = Some(arguments)
+                    .filter(|language| !language.is_empty())
+                    .or(self.highlight_language.as_deref());
+                push_code_block(
+                    html,
+                    language,
+                    &trimmed_lines(&content).join("\n"),
+                    self.syntax_highlighting,
+                );
+            }
+            "highlight" => {
+                self.highlight_language = Some(arguments.to_string());
+            }
+            "image" => {
+                let image = self.image(arguments, &options);
+                html.push_str(&format!("<p>{image}</p>\n"));
+            }
+            "figure" => {
+                let image = self.image(arguments, &options);
+                html.push_str(&format!("<p>{image}</p>\n"));
+                self.render_blocks(&content, html);
+            }
+            "admonition" => {
+                let content = self.render_nested(&content);
+                push_alert(html, "note", Some(arguments), &content);
+            }
+            "raw" => {
+                if arguments.split_whitespace().any(|format| format == "html") {
+                    html.push_str(&content.join("\n"));
+                    html.push('\n');
+                }
+            }
+            "parsed-literal" => {
+                let content = self.inline(&trimmed_lines(&content).join("\n"));
+                html.push_str(&format!("<pre>{content}</pre>\n"));
+            }
+            "math" => {
+                push_code_block(
+                    html,
+                    None,
+                    &trimmed_lines(&content).join("\n"),
+                    self.syntax_highlighting,
+                );
+            }
+            "rubric" => {
+                let title = self.inline(arguments);
+                html.push_str(&format!("<p><strong>{title}</strong></p>\n"));
+            }
+            "topic" | "sidebar" => {
+                let title = self.inline(arguments);
+                html.push_str(&format!("<p><strong>{title}</strong></p>\n"));
+                self.render_blocks(&content, html);
+            }
+            "container" | "class" | "compound" | "only" => {
+                self.render_blocks(&content, html);
+            }
+            "epigraph" | "highlights" | "pull-quote" => {
+                html.push_str("<blockquote>\n");
+                self.render_blocks(&content, html);
+                html.push_str("</blockquote>\n");
+            }
+            "list-table" => self.list_table(&options, &content, html),
+            "csv-table" => self.csv_table(&options, &content, html),
+            _ => {
+                if let Some(kind) = alert_kind(&name) {
+                    // The arguments of an admonition are the first line of its content.
+                    let mut lines = vec![arguments.to_string()];
+                    lines.extend(body);
+                    let content = self.render_nested(&lines);
+                    push_alert(html, kind, None, &content);
+                }
+            }
+        }
+
+        next
+    }
+
+    fn image(&mut self, uri: &str, options: &HashMap<String, String>) -> String {
+        let mut image = format!("<img src=\"{}\"", escape_attribute(uri));
+        for name in ["alt", "width", "height", "align"] {
+            if let Some(value) = options.get(name) {
+                let value = match name {
+                    "width" | "height" => value.trim_end_matches("px"),
+                    _ => value,
+                };
+                image.push_str(&format!(" {name}=\"{}\"", escape_attribute(value)));
+            }
+        }
+        image.push('>');
+
+        match options.get("target") {
+            Some(target) => {
+                let href = match target.strip_suffix('_') {
+                    Some(name) => self.reference_url(name.trim_matches('`')),
+                    None => target.clone(),
+                };
+                format!("<a href=\"{}\">{image}</a>", escape_attribute(&href))
+            }
+            None => image,
+        }
+    }
+
+    fn bullet_list(&mut self, lines: &[String], i: usize, html: &mut String) -> usize {
+        let kind = bullet(&lines[i]).map(|(bullet, _)| bullet);
+        let (items, next) = list_items(lines, i, |line| {
+            bullet(line)
+                .filter(|(bullet, _)| Some(*bullet) == kind)
+                .map(|(_, column)| column)
+        });
+
+        html.push_str("<ul>\n");
+        for (_, body) in items {
+            self.list_item(&body, html);
+        }
+        html.push_str("</ul>\n");
+        next
+    }
+
+    fn enumerated_list(&mut self, lines: &[String], i: usize, html: &mut String) -> usize {
+        let start = enumerator(&lines[i]).and_then(|(number, _)| number);
+        let (items, next) = list_items(lines, i, |line| enumerator(line).map(|(_, column)| column));
+
+        match start {
+            Some(start) if start != 1 => html.push_str(&format!("<ol start=\"{start}\">\n")),
+            _ => html.push_str("<ol>\n"),
+        }
+        for (_, body) in items {
+            self.list_item(&body, html);
+        }
+        html.push_str("</ol>\n");
+        next
+    }
+
+    fn list_item(&mut self, body: &[String], html: &mut String) {
+        let content = self.render_nested(body);
+        match unwrap_paragraph(&content) {
+            content if content.ends_with('\n') => html.push_str(&format!("<li>\n{content}</li>\n")),
+            content => html.push_str(&format!("<li>{content}</li>\n")),
+        }
+    }
+
+    fn field_list(&mut self, lines: &[String], i: usize, html: &mut String) -> usize {
+        let (items, next) = list_items(lines, i, field_marker);
+
+        html.push_str("<dl>\n");
+        for (marker, body) in items {
+            let name = self.inline(marker.trim_matches(':'));
+            let content = self.render_nested(&body);
+            html.push_str(&format!("<dt>{name}</dt>\n"));
+            html.push_str(&format!("<dd>{}</dd>\n", unwrap_paragraph(&content)));
+        }
+        html.push_str("</dl>\n");
+        next
+    }
+
+    fn definition_list(&mut self, lines: &[String], mut i: usize, html: &mut String) -> usize {
+        html.push_str("<dl>\n");
+        loop {
+            // Classifiers are not rendered.
+            let term = lines[i].split(" : ").next().unwrap_or_default();
+            let term = self.inline(term);
+            let (definition, next) = indented_block(lines, i + 1);
+            let definition = self.render_nested(&definition);
+            html.push_str(&format!("<dt>{term}</dt>\n"));
+            html.push_str(&format!("<dd>{}</dd>\n", unwrap_paragraph(&definition)));
+
+            i = next;
+            let j = skip_blank_lines(lines, i);
+            if j < lines.len() && is_definition_list_item(lines, j) {
+                i = j;
+            } else {
+                break;
+            }
+        }
+        html.push_str("</dl>\n");
+        i
+    }
+
+    fn line_block(&mut self, lines: &[String], i: usize, html: &mut String) -> usize {
+        let end = block_end(lines, i);
+        let mut block_lines: Vec<String> = Vec::new();
+        for line in &lines[i..end] {
+            match line.strip_prefix('|') {
+                Some(line) => block_lines.push(line.trim().to_string()),
+                None => {
+                    if let Some(last) = block_lines.last_mut() {
+                        last.push(' ');
+                        last.push_str(line.trim());
+                    }
+                }
+            }
+        }
+
+        let content = block_lines
+            .iter()
+            .map(|line| self.inline(line))
+            .collect::<Vec<_>>();
+        html.push_str(&format!("<p>{}</p>\n", content.join("<br>\n")));
+        end
+    }
+
+    fn paragraph(&mut self, lines: &[String], i: usize, html: &mut String) -> usize {
+        let mut end = i;
+        while end < lines.len() && !lines[end].trim().is_empty() && indentation(&lines[end]) == 0 {
+            end += 1;
+        }
+
+        let text = lines[i..end].join("\n");
+        // A paragraph ending with `::` introduces a literal block.
+        let (text, literal) = match text.strip_suffix("::") {
+            Some(text) if text.is_empty() || text.ends_with(char::is_whitespace) => {
+                (text.trim_end(), true)
+            }
+            Some(_) => (&text[..text.len() - 1], true),
+            None => (text.as_str(), false),
+        };
+
+        if !text.is_empty() {
+            let content = self.inline(text);
+            html.push_str(&format!("<p>{content}</p>\n"));
+        }
+
+        let start = skip_blank_lines(lines, end);
+        if literal && lines.get(start).is_some_and(|line| indentation(line) > 0) {
+            let (block, next) = indented_block(lines, start);
+            let language = self.highlight_language.as_deref();
+            push_code_block(html, language, &block.join("\n"), self.syntax_highlighting);
+            return next;
+        }
+
+        end
+    }
+
+    fn grid_table(&mut self, lines: &[String], i: usize, html: &mut String) -> usize {
+        let end = i + lines[i..]
+            .iter()
+            .take_while(|line| line.starts_with('+') || line.starts_with('|'))
+            .count();
+        let table = lines[i..end]
+            .iter()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let boundaries = table[0]
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == '+')
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+
+        // Tables with cells spanning multiple rows or columns are rendered as
+        // they are.
+        let is_simple = table.iter().all(|line| {
+            let is_separator = line[0] == '+';
+            line.len() == table[0].len()
+                && boundaries
+                    .iter()
+                    .all(|b| line[*b] == '+' || line[*b] == '|')
+                && (!is_separator || line.iter().all(|c| matches!(c, '+' | '-' | '=')))
+        });
+        if !is_simple {
+            push_code_block(
+                html,
+                None,
+                &lines[i..end].join("\n"),
+                self.syntax_highlighting,
+            );
+            return end;
+        }
+
+        let columns = boundaries.len() - 1;
+        let mut header_rows = 0;
+        let mut rows = Vec::new();
+        let mut row: Vec<Vec<String>> = vec![Vec::new(); columns];
+        for line in &table[1..] {
+            if line[0] == '+' {
+                if row.iter().any(|cell| !cell.is_empty()) {
+                    let cells = std::mem::replace(&mut row, vec![Vec::new(); columns]);
+                    rows.push(cells);
+                }
+                if line.contains(&'=') {
+                    header_rows = rows.len();
+                }
+            } else {
+                for (cell, bounds) in row.iter_mut().zip(boundaries.windows(2)) {
+                    let content = line[bounds[0] + 1..bounds[1]].iter().collect::<String>();
+                    cell.push(content.trim_end().to_string());
+                }
+            }
+        }
+
+        let mut rows = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|cell| {
+                        let content = self.render_nested(&dedent(&cell));
+                        unwrap_paragraph(&content).trim_end().to_string()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let header = (header_rows > 0).then(|| rows.remove(0));
+        push_table(html, header.as_deref(), &rows);
+        end
+    }
+
+    fn simple_table(&mut self, lines: &[String], i: usize, html: &mut String) -> usize {
+        let border = lines[i].chars().collect::<Vec<_>>();
+        let starts = (0..border.len())
+            .filter(|&p| border[p] == '=' && (p == 0 || border[p - 1] == ' '))
+            .collect::<Vec<_>>();
+
+        let mut header = None;
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut end = i + 1;
+        while end < lines.len() {
+            let line = &lines[end];
+            end += 1;
+
+            if is_simple_table_border(line) {
+                // The table ends with a border that is followed by a blank line.
+                if lines.get(end).is_none_or(|line| line.trim().is_empty()) {
+                    break;
+                }
+                header = Some(std::mem::take(&mut rows));
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let chars = line.chars().collect::<Vec<_>>();
+            let cells = starts
+                .iter()
+                .enumerate()
+                .map(|(column, &start)| {
+                    let end = match starts.get(column + 1) {
+                        Some(&end) => end.min(chars.len()),
+                        None => chars.len(),
+                    };
+                    let start = start.min(end);
+                    chars[start..end]
+                        .iter()
+                        .collect::<String>()
+                        .trim()
+                        .to_string()
+                })
+                .collect::<Vec<_>>();
+
+            // Rows with an empty first column continue the previous row.
+            match rows.last_mut() {
+                Some(row) if cells[0].is_empty() => {
+                    for (cell, content) in row.iter_mut().zip(cells) {
+                        if !content.is_empty() {
+                            cell.push(' ');
+                            cell.push_str(&content);
+                        }
+                    }
+                }
+                _ => rows.push(cells),
+            }
+        }
+
+        let mut render = |rows: Vec<Vec<String>>| {
+            rows.into_iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| self.inline(cell.trim()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Two adjacent borders result in an empty header section, which is
+        // treated like a table without header.
+        let header = header
+            .and_then(|header| header.into_iter().next())
+            .map(|row| render(vec![row]).remove(0));
+        let rows = render(rows);
+        push_table(html, header.as_deref(), &rows);
+        end
+    }
+
+    fn list_table(
+        &mut self,
+        options: &HashMap<String, String>,
+        content: &[String],
+        html: &mut String,
+    ) {
+        let start = skip_blank_lines(content, 0);
+        if start >= content.len() {
+            return;
+        }
+
+        let (items, _) = list_items(content, start, |line| bullet(line).map(|(_, c)| c));
+        let mut rows = items
+            .into_iter()
+            .map(|(_, row)| {
+                let start = skip_blank_lines(&row, 0);
+                if start >= row.len() {
+                    return Vec::new();
+                }
+
+                let (cells, _) = list_items(&row, start, |line| bullet(line).map(|(_, c)| c));
+                cells
+                    .into_iter()
+                    .map(|(_, cell)| {
+                        let content = self.render_nested(&cell);
+                        unwrap_paragraph(&content).trim_end().to_string()
+                    })
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+
+        let header = match options.get("header-rows").map(String::as_str) {
+            Some("0") | None => None,
+            Some(_) if !rows.is_empty() => Some(rows.remove(0)),
+            Some(_) => None,
+        };
+        push_table(html, header.as_deref(), &rows);
+    }
+
+    fn csv_table(
+        &mut self,
+        options: &HashMap<String, String>,
+        content: &[String],
+        html: &mut String,
+    ) {
+        let mut rows = content
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| split_csv(line))
+            .collect::<Vec<_>>();
+
+        let header = match options.get("header") {
+            Some(header) => Some(split_csv(header)),
+            None => match options.get("header-rows").map(String::as_str) {
+                Some("0") | None => None,
+                Some(_) if !rows.is_empty() => Some(rows.remove(0)),
+                Some(_) => None,
+            },
+        };
+
+        let mut render =
+            |row: Vec<String>| row.iter().map(|cell| self.inline(cell)).collect::<Vec<_>>();
+        let header = header.map(&mut render);
+        let rows = rows.into_iter().map(render).collect::<Vec<_>>();
+        push_table(html, header.as_deref(), &rows);
+    }
+
+    /// Renders inline markup.
+    fn inline(&mut self, text: &str) -> String {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut html = String::new();
+
+        let mut p = 0;
+        while p < chars.len() {
+            let c = chars[p];
+            let next = chars.get(p + 1).copied();
+            let can_start = p == 0 || !chars[p - 1].is_alphanumeric();
+
+            if c == '\\' {
+                if let Some(next) = next.filter(|next| !next.is_whitespace()) {
+                    push_escaped(&mut html, &next.to_string());
+                }
+                p += 2;
+                continue;
+            }
+
+            if can_start {
+                let end = match c {
+                    '`' if next == Some('`') => find_closing(&chars, p + 2, "``").map(|end| {
+                        let code = chars[p + 2..end].iter().collect::<String>();
+                        html.push_str(&format!("<code>{}</code>", escape(&code)));
+                        end + 2
+                    }),
+                    '`' => find_closing(&chars, p + 1, "`")
+                        .map(|end| self.interpreted_text(&chars, p + 1, end, None, &mut html)),
+                    '*' if next == Some('*') => find_closing(&chars, p + 2, "**").map(|end| {
+                        let text = chars[p + 2..end].iter().collect::<String>();
+                        html.push_str(&format!("<strong>{}</strong>", escape(&text)));
+                        end + 2
+                    }),
+                    '*' => find_closing(&chars, p + 1, "*").map(|end| {
+                        let text = chars[p + 1..end].iter().collect::<String>();
+                        html.push_str(&format!("<em>{}</em>", escape(&text)));
+                        end + 1
+                    }),
+                    '|' => find_closing(&chars, p + 1, "|")
+                        .map(|end| self.substitution_reference(&chars, p + 1, end, &mut html)),
+                    ':' => role_prefix(&chars, p).and_then(|(role, start)| {
+                        find_closing(&chars, start, "`").map(|end| {
+                            self.interpreted_text(&chars, start, end, Some(&role), &mut html)
+                        })
+                    }),
+                    '[' => footnote_reference(&chars, p).map(|(label, end)| {
+                        html.push_str(&format!("<sup>[{}]</sup>", escape(&label)));
+                        end
+                    }),
+                    _ if c.is_alphanumeric() => Some(self.word(&chars, p, &mut html)),
+                    _ => None,
+                };
+
+                if let Some(end) = end {
+                    p = end;
+                    continue;
+                }
+            }
+
+            push_escaped(&mut html, &c.to_string());
+            p += 1;
+        }
+
+        html
+    }
+
+    /// Renders a standalone URL, a simple reference (`name_`) or a plain
+    /// word, and returns the position after it.
+    fn word(&mut self, chars: &[char], p: usize, html: &mut String) -> usize {
+        let scheme_length = chars[p..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .count();
+        let rest = match chars.get(p + scheme_length) {
+            Some(':') => chars[p..].iter().collect::<String>(),
+            _ => String::new(),
+        };
+        if ["http://", "https://", "ftp://", "mailto:"]
+            .iter()
+            .any(|scheme| rest.starts_with(scheme))
+        {
+            let url = rest
+                .split(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"'))
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'']);
+            html.push_str(&format!(
+                "<a href=\"{}\">{}</a>",
+                escape_attribute(url),
+                escape(url)
+            ));
+            return p + url.chars().count();
+        }
+
+        let mut end = p;
+        while end < chars.len() {
+            let c = chars[end];
+            let next_is_alphanumeric = chars.get(end + 1).is_some_and(|c| c.is_alphanumeric());
+            if c.is_alphanumeric()
+                || (matches!(c, '-' | '_' | '.' | '+' | ':') && next_is_alphanumeric)
+            {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+
+        let word = chars[p..end].iter().collect::<String>();
+        if chars.get(end) == Some(&'_') {
+            let anonymous = chars.get(end + 1) == Some(&'_');
+            let after = if anonymous { end + 2 } else { end + 1 };
+            if chars.get(after).is_none_or(|c| !c.is_alphanumeric()) {
+                let href = match anonymous {
+                    true => self.next_anonymous_target(),
+                    false => Some(self.reference_url(&word)),
+                };
+                push_link(html, href.as_deref(), &escape(&word));
+                return after;
+            }
+        }
+
+        push_escaped(html, &word);
+        end
+    }
+
+    /// Renders interpreted text, i.e. text in backquotes, which is either a
+    /// hyperlink reference or text with a role. Returns the position after
+    /// it.
+    fn interpreted_text(
+        &mut self,
+        chars: &[char],
+        start: usize,
+        end: usize,
+        role: Option<&str>,
+        html: &mut String,
+    ) -> usize {
+        let text = chars[start..end].iter().collect::<String>();
+        let after = &chars[end + 1..];
+
+        if role.is_none() && after.starts_with(&['_']) {
+            let anonymous = after.starts_with(&['_', '_']);
+            let (label, href) = match embedded_uri(&text) {
+                Some((label, uri)) => {
+                    let href = match uri.strip_suffix('_') {
+                        Some(name) => self.reference_url(name),
+                        None => uri.clone(),
+                    };
+                    (if label.is_empty() { uri } else { label }, Some(href))
+                }
+                None if anonymous => (text.clone(), self.next_anonymous_target()),
+                None => (text.clone(), Some(self.reference_url(&text))),
+            };
+
+            push_link(html, href.as_deref(), &escape(&label));
+            return end + if anonymous { 3 } else { 2 };
+        }
+
+        let (role, end) = match role {
+            Some(role) => (role.to_string(), end + 1),
+            None => match role_suffix(chars, end + 1) {
+                Some((role, end)) => (role, end),
+                None => ("title-reference".to_string(), end + 1),
+            },
+        };
+
+        // Roles like `:ref:` may have the same syntax as embedded URIs.
+        let label = match embedded_uri(&text) {
+            Some((label, _)) if !label.is_empty() => label,
+            _ => text,
+        };
+
+        let tag = match role.as_str() {
+            "code" | "literal" | "samp" | "file" | "command" | "program" | "envvar" | "option"
+            | "func" | "meth" | "class" | "mod" | "attr" | "obj" | "data" | "const" | "exc" => {
+                Some("code")
+            }
+            "emphasis" => Some("em"),
+            "strong" => Some("strong"),
+            "title-reference" | "title" | "t" => Some("cite"),
+            "subscript" | "sub" => Some("sub"),
+            "superscript" | "sup" => Some("sup"),
+            "kbd" => Some("kbd"),
+            _ => None,
+        };
+
+        match tag {
+            Some(tag) => html.push_str(&format!("<{tag}>{}</{tag}>", escape(&label))),
+            None => push_escaped(html, &label),
+        }
+
+        end
+    }
+
+    fn substitution_reference(
+        &mut self,
+        chars: &[char],
+        start: usize,
+        end: usize,
+        html: &mut String,
+    ) -> usize {
+        let name = chars[start..end].iter().collect::<String>();
+        let after = &chars[end + 1..];
+        let (is_reference, end) = if after.starts_with(&['_', '_']) {
+            (true, end + 3)
+        } else if after.starts_with(&['_']) {
+            (true, end + 2)
+        } else {
+            (false, end + 1)
+        };
+
+        let content = self
+            .substitution(&name)
+            .unwrap_or_else(|| escape(&format!("|{name}|")));
+
+        if is_reference {
+            let href = self.reference_url(&name);
+            push_link(html, Some(&href), &content);
+        } else {
+            html.push_str(&content);
+        }
+
+        end
+    }
+
+    fn substitution(&mut self, name: &str) -> Option<String> {
+        if self.substitution_depth >= MAX_SUBSTITUTION_DEPTH {
+            return None;
+        }
+
+        let html = match self.substitutions.get(&normalize_name(name))? {
+            Substitution::Image { uri, options } => {
+                let (uri, options) = (uri.clone(), options.clone());
+                self.image(&uri, &options)
+            }
+            Substitution::Replace(text) => {
+                let text = text.clone();
+                self.substitution_depth += 1;
+                let html = self.inline(&text);
+                self.substitution_depth -= 1;
+                html
+            }
+        };
+
+        Some(html)
+    }
+
+    /// Returns the URL of a named hyperlink reference. References to unknown
+    /// targets are assumed to refer to a section title.
+    fn reference_url(&self, name: &str) -> String {
+        let mut name = normalize_name(name);
+        for _ in 0..MAX_SUBSTITUTION_DEPTH {
+            let Some(uri) = self.targets.get(&name) else {
+                break;
+            };
+
+            // Indirect hyperlink targets refer to another target.
+            match uri.strip_suffix('_') {
+                Some(target) if !target.contains("://") => {
+                    name = normalize_name(target.trim_matches('`'));
+                }
+                _ => return uri.clone(),
+            }
+        }
+
+        format!("#user-content-{}", Anchorizer::new().anchorize(&name))
+    }
+
+    fn next_anonymous_target(&mut self) -> Option<String> {
+        let target = self.anonymous_targets.get(self.next_anonymous_target)?;
+        self.next_anonymous_target += 1;
+        Some(target.clone())
+    }
+}
+
+fn push_link(html: &mut String, href: Option<&str>, content: &str) {
+    match href {
+        Some(href) => html.push_str(&format!(
+            "<a href=\"{}\">{content}</a>",
+            escape_attribute(href)
+        )),
+        None => html.push_str(content),
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Maps the admonition directives to the supported alert types.
+fn alert_kind(directive: &str) -> Option<&'static str> {
+    let kind = match directive {
+        "hint" => "tip",
+        "attention" => "warning",
+        "danger" | "error" => "caution",
+        _ => directive,
+    };
+    ALERTS.into_iter().find(|alert| *alert == kind)
+}
+
+/// Returns whether the line is a section title adornment or a transition.
+fn is_adornment(line: &str) -> bool {
+    let mut chars = line.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_punctuation() && chars.all(|c| c == first))
+}
+
+fn is_grid_table_border(line: &str) -> bool {
+    line.len() > 2
+        && line.starts_with("+-")
+        && line.ends_with('+')
+        && line.chars().all(|c| c == '+' || c == '-')
+}
+
+fn is_simple_table_border(line: &str) -> bool {
+    line.starts_with('=')
+        && line.split_whitespace().count() >= 2
+        && line.chars().all(|c| c == '=' || c == ' ')
+}
+
+fn is_definition_list_item(lines: &[String], i: usize) -> bool {
+    !lines[i].ends_with("::")
+        && lines
+            .get(i + 1)
+            .is_some_and(|line| !line.trim().is_empty() && indentation(line) > 0)
+}
+
+/// Returns the bullet and the column of the text of a bullet list item.
+fn bullet(line: &str) -> Option<(char, usize)> {
+    let bullet = line.chars().next()?;
+    if !matches!(bullet, '-' | '*' | '+' | '•') {
+        return None;
+    }
+
+    let rest = &line[bullet.len_utf8()..];
+    if rest.is_empty() {
+        return Some((bullet, line.len() + 1));
+    }
+    if !rest.starts_with(' ') {
+        return None;
+    }
+
+    Some((bullet, line.len() - rest.trim_start().len()))
+}
+
+/// Returns the number and the column of the text of an enumerated list item.
+fn enumerator(line: &str) -> Option<(Option<u64>, usize)> {
+    let (enumerator, rest) = match line.strip_prefix('(') {
+        Some(line) => line.split_once(')')?,
+        None => {
+            let end = line.find(['.', ')'])?;
+            (&line[..end], &line[end + 1..])
+        }
+    };
+
+    let number = match enumerator {
+        "#" => None,
+        _ if !enumerator.is_empty()
+            && enumerator.len() < 10
+            && enumerator.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            Some(enumerator.parse().ok()?)
+        }
+        _ => return None,
+    };
+
+    if rest.is_empty() {
+        return Some((number, line.len() + 1));
+    }
+    if !rest.starts_with(' ') {
+        return None;
+    }
+
+    Some((number, line.len() - rest.trim_start().len()))
+}
+
+/// Returns the column of the body of a field list item, e.g. `:Author: Me`.
+fn field_marker(line: &str) -> Option<usize> {
+    let name = line.strip_prefix(':')?;
+    if name.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let end = name.find(':')?;
+    if end == 0 {
+        return None;
+    }
+
+    let rest = &name[end + 1..];
+    if rest.is_empty() {
+        return Some(line.len() + 1);
+    }
+    if !rest.starts_with(' ') {
+        return None;
+    }
+
+    Some(line.len() - rest.trim_start().len())
+}
+
+/// Returns the end of the block of non-blank lines starting at `i`.
+fn block_end(lines: &[String], i: usize) -> usize {
+    i + lines[i..]
+        .iter()
+        .take_while(|line| !line.trim().is_empty())
+        .count()
+}
+
+/// Returns the dedented block of indented lines starting at `start`, and the
+/// position after it.
+fn indented_block(lines: &[String], start: usize) -> (Vec<String>, usize) {
+    let mut end = start;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indentation(line) == 0 {
+            break;
+        }
+        end = i + 1;
+    }
+
+    (dedent(&lines[start..end]), end)
+}
+
+/// Removes leading and trailing blank lines.
+fn trimmed_lines(lines: &[String]) -> &[String] {
+    let start = skip_blank_lines(lines, 0);
+    let end = lines.len()
+        - lines
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+    &lines[start.min(end)..end]
+}
+
+/// Collects the items of a list starting at line `i`. The `marker` function
+/// returns the column of the text of a line starting an item of the list.
+///
+/// Returns the markers and the dedented bodies of the items, and the position
+/// after the list.
+fn list_items(
+    lines: &[String],
+    mut i: usize,
+    marker: impl Fn(&str) -> Option<usize>,
+) -> (Vec<(String, Vec<String>)>, usize) {
+    let mut items = Vec::new();
+    while let Some(column) = lines.get(i).and_then(|line| marker(line)) {
+        let line = &lines[i];
+        let first = line.get(column..).unwrap_or_default().to_string();
+        let prefix = line[..column.min(line.len())].trim().to_string();
+
+        let (rest, next) = indented_block(lines, i + 1);
+        let mut body = vec![first];
+        body.extend(rest);
+        items.push((prefix, body));
+
+        i = next;
+        let j = skip_blank_lines(lines, i);
+        if lines.get(j).is_some_and(|line| marker(line).is_some()) {
+            i = j;
+        } else {
+            break;
+        }
+    }
+
+    (items, i)
+}
+
+/// Parses the options at the start of a directive body, e.g. `:alt: text`,
+/// and returns them together with the remaining content.
+fn split_options<S: AsRef<str>>(body: &[S]) -> (HashMap<String, String>, Vec<String>) {
+    let mut options = HashMap::new();
+    let mut lines = body
+        .iter()
+        .map(AsRef::as_ref)
+        .skip_while(|line| line.trim().is_empty());
+
+    let mut content = Vec::new();
+    for line in lines.by_ref() {
+        match line.strip_prefix(':').and_then(|line| line.split_once(':')) {
+            Some((name, value)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                options.insert(name.to_lowercase(), value.trim().to_string());
+            }
+            _ => {
+                content.push(line.to_string());
+                break;
+            }
+        }
+    }
+    content.extend(lines.map(ToString::to_string));
+
+    (options, content)
+}
+
+/// Returns the label and the URI of a reference with an embedded URI, e.g.
+/// `` `Rust <https://rust-lang.org/>`_ ``.
+fn embedded_uri(text: &str) -> Option<(String, String)> {
+    let text = text.strip_suffix('>')?;
+    let (label, uri) = text.rsplit_once('<')?;
+    if !label.is_empty() && !label.ends_with(char::is_whitespace) {
+        return None;
+    }
+
+    let uri = uri.split_whitespace().collect::<String>();
+    Some((label.trim().to_string(), uri))
+}
+
+/// Finds the end of inline markup started before `start`.
+fn find_closing(chars: &[char], start: usize, delimiter: &str) -> Option<usize> {
+    let delimiter = delimiter.chars().collect::<Vec<_>>();
+    if chars.get(start).is_none_or(|c| c.is_whitespace()) {
+        return None;
+    }
+
+    (start + 1..chars.len()).find(|&q| {
+        chars[q..].starts_with(&delimiter)
+            && !chars[q - 1].is_whitespace()
+            && chars[q - 1] != '\\'
+            && chars
+                .get(q + delimiter.len())
+                .is_none_or(|c| !c.is_alphanumeric())
+    })
+}
+
+/// Parses a role before interpreted text, e.g. `` :code:`foo` ``. Returns
+/// the role and the start of the text.
+fn role_prefix(chars: &[char], p: usize) -> Option<(String, usize)> {
+    let (role, end) = role_suffix(chars, p)?;
+    (chars.get(end) == Some(&'`')).then_some((role, end + 1))
+}
+
+/// Parses a role at `p`, e.g. `:code:`, and returns it together with the
+/// position after it.
+fn role_suffix(chars: &[char], p: usize) -> Option<(String, usize)> {
+    if chars.get(p) != Some(&':') {
+        return None;
+    }
+
+    let length = chars[p + 1..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '+' | ':'))
+        .count();
+    // The role name itself may contain colons (e.g. `:py:func:`), so the last
+    // colon ends it.
+    let name = chars[p + 1..p + 1 + length].iter().collect::<String>();
+    let name = name.strip_suffix(':')?;
+    if name.is_empty() {
+        return None;
+    }
+
+    let role = name.rsplit(':').next().unwrap_or(name).to_lowercase();
+    Some((role, p + 1 + length))
+}
+
+/// Parses a footnote or citation reference, e.g. `[1]_`. Returns the label
+/// and the position after it.
+fn footnote_reference(chars: &[char], p: usize) -> Option<(String, usize)> {
+    let length = chars[p + 1..].iter().take_while(|c| **c != ']').count();
+    let end = p + 1 + length;
+    if length == 0 || chars.get(end + 1) != Some(&'_') {
+        return None;
+    }
+
+    let label = chars[p + 1..end].iter().collect::<String>();
+    if label.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some((label, end + 2))
+}
+
+fn split_csv(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::assert_never_panics;
+    use crate::{RenderOptions, rst_to_html};
+    use insta::assert_snapshot;
+
+    fn render(text: &str) -> String {
+        rst_to_html(text, None, "", RenderOptions::default())
+    }
+
+    #[test]
+    fn section_titles() {
+        let text = "\
+=======
+ Title
+=======
+
+Section
+=======
+
+Subsection
+----------
+
+Section
+=======
+";
+        assert_snapshot!(render(text), @r##"
+        <h1 id="user-content-title">Title<a href="#user-content-title" aria-label="Link to heading 'Title'" rel="nofollow noopener noreferrer"></a></h1>
+        <h2 id="user-content-section">Section<a href="#user-content-section" aria-label="Link to heading 'Section'" rel="nofollow noopener noreferrer"></a></h2>
+        <h3 id="user-content-subsection">Subsection<a href="#user-content-subsection" aria-label="Link to heading 'Subsection'" rel="nofollow noopener noreferrer"></a></h3>
+        <h2 id="user-content-section-1">Section<a href="#user-content-section-1" aria-label="Link to heading 'Section'" rel="nofollow noopener noreferrer"></a></h2>
+        "##);
+    }
+
+    #[test]
+    fn inline_markup() {
+        let text = "*emphasis*, **strong**, ``literal``, `title`, :code:`code`, \
+:kbd:`Ctrl` and \\*escaped\\* text with a_b*c* and https://crates.io/. [1]_";
+        assert_snapshot!(render(text), @r#"<p><em>emphasis</em>, <strong>strong</strong>, <code>literal</code>, <cite>title</cite>, <code>code</code>, <kbd>Ctrl</kbd> and *escaped* text with a_b*c* and <a href="https://crates.io/" rel="nofollow noopener noreferrer">https://crates.io/</a>. <sup>[1]</sup></p>"#);
+    }
+
+    #[test]
+    fn hyperlinks() {
+        let text = "\
+`Rust <https://rust-lang.org/>`_, `Cargo`_, crates.io_, `anonymous`__,
+`Section`_ and `indirect`_.
+
+.. _Cargo: https://doc.rust-lang.org/cargo/
+.. _crates.io: https://crates.io/
+.. _indirect: Cargo_
+
+__ https://docs.rs/
+";
+        assert_snapshot!(render(text), @r##"
+        <p><a href="https://rust-lang.org/" rel="nofollow noopener noreferrer">Rust</a>, <a href="https://doc.rust-lang.org/cargo/" rel="nofollow noopener noreferrer">Cargo</a>, <a href="https://crates.io/" rel="nofollow noopener noreferrer">crates.io</a>, <a href="https://docs.rs/" rel="nofollow noopener noreferrer">anonymous</a>,
+        <a href="#user-content-section" rel="nofollow noopener noreferrer">Section</a> and <a href="https://doc.rust-lang.org/cargo/" rel="nofollow noopener noreferrer">indirect</a>.</p>
+        "##);
+    }
+
+    #[test]
+    fn substitutions() {
+        let text = "\
+|build| |version|_ |name|
+
+.. |build| image:: https://github.com/rust-lang/crates.io/workflows/CI/badge.svg
+   :alt: Build status
+   :target: https://github.com/rust-lang/crates.io/actions
+.. |version| image:: https://img.shields.io/crates/v/foo.svg
+.. _version: https://crates.io/crates/foo
+.. |name| replace:: *foo*
+";
+        assert_snapshot!(render(text), @r#"<p><a href="https://github.com/rust-lang/crates.io/actions" rel="nofollow noopener noreferrer"><img src="https://github.com/rust-lang/crates.io/workflows/CI/badge.svg" alt="Build status"></a> <a href="https://crates.io/crates/foo" rel="nofollow noopener noreferrer"><img src="https://img.shields.io/crates/v/foo.svg"></a> <em>foo</em></p>"#);
+    }
+
+    #[test]
+    fn lists() {
+        let text = "\
+- first
+- second
+
+  * nested
+
+3. three
+#. four
+
+:Author: Me
+:Version: 1.0
+
+term
+    definition
+";
+        assert_snapshot!(render(text), @r#"
+        <ul>
+        <li>first</li>
+        <li>
+        <p>second</p>
+        <ul>
+        <li>nested</li>
+        </ul>
+        </li>
+        </ul>
+        <ol start="3">
+        <li>three</li>
+        <li>four</li>
+        </ol>
+        <dl>
+        <dt>Author</dt>
+        <dd>Me</dd>
+        <dt>Version</dt>
+        <dd>1.0</dd>
+        </dl>
+        <dl>
+        <dt>term</dt>
+        <dd>definition</dd>
+        </dl>
+        "#);
+    }
+
+    #[test]
+    fn literal_blocks() {
+        let text = "\
+Example::
+
+    let x = 1;
+
+.. code-block:: rust
+   :linenos:
+
+   fn main() {}
+
+.. highlight:: toml
+
+Expanded ::
+
+    [dependencies]
+
+>>> print(1)
+1
+";
+        assert_snapshot!(render(text), @r#"
+        <p>Example:</p>
+        <pre><code>let x = 1;
+        </code></pre>
+        <pre><code class="language-rust">fn main() {}
+        </code></pre>
+        <p>Expanded</p>
+        <pre><code class="language-toml">[dependencies]
+        </code></pre>
+        <pre><code>&gt;&gt;&gt; print(1)
+        1
+        </code></pre>
+        "#);
+    }
+
+    #[test]
+    fn admonitions() {
+        let text = "\
+.. note:: This is
+   a note.
+
+.. danger::
+
+   Be careful!
+
+.. admonition:: Custom
+
+   Content
+";
+        assert_snapshot!(render(text), @r#"
+        <div class="markdown-alert markdown-alert-note">
+        <p class="markdown-alert-title">Note</p>
+        <p>This is
+        a note.</p>
+        </div>
+        <div class="markdown-alert markdown-alert-caution">
+        <p class="markdown-alert-title">Caution</p>
+        <p>Be careful!</p>
+        </div>
+        <div class="markdown-alert markdown-alert-note">
+        <p class="markdown-alert-title">Custom</p>
+        <p>Content</p>
+        </div>
+        "#);
+    }
+
+    #[test]
+    fn tables() {
+        let text = "\
++-----+-------+
+| A   | B     |
++=====+=======+
+| 1   | ``2`` |
++-----+-------+
+
+=====  =====
+A      B
+=====  =====
+1      2
+3      4
+=====  =====
+
+.. list-table::
+   :header-rows: 1
+
+   * - A
+     - B
+   * - 1
+     - 2
+
+.. csv-table::
+   :header: \"A\", \"B\"
+
+   1, \"2, 3\"
+";
+        assert_snapshot!(render(text), @"
+        <table>
+        <thead>
+        <tr>
+        <th>A</th>
+        <th>B</th>
+        </tr>
+        </thead>
+        <tbody>
+        <tr>
+        <td>1</td>
+        <td><code>2</code></td>
+        </tr>
+        </tbody>
+        </table>
+        <table>
+        <thead>
+        <tr>
+        <th>A</th>
+        <th>B</th>
+        </tr>
+        </thead>
+        <tbody>
+        <tr>
+        <td>1</td>
+        <td>2</td>
+        </tr>
+        <tr>
+        <td>3</td>
+        <td>4</td>
+        </tr>
+        </tbody>
+        </table>
+        <table>
+        <thead>
+        <tr>
+        <th>A</th>
+        <th>B</th>
+        </tr>
+        </thead>
+        <tbody>
+        <tr>
+        <td>1</td>
+        <td>2</td>
+        </tr>
+        </tbody>
+        </table>
+        <table>
+        <thead>
+        <tr>
+        <th>A</th>
+        <th>B</th>
+        </tr>
+        </thead>
+        <tbody>
+        <tr>
+        <td>1</td>
+        <td>2, 3</td>
+        </tr>
+        </tbody>
+        </table>
+        ");
+    }
+
+    #[test]
+    fn skipped_markup() {
+        let text = "\
+.. This is a comment
+   spanning multiple lines.
+
+.. contents::
+
+.. include:: CHANGELOG.rst
+
+.. unknown:: directive
+
+----
+
+Content
+";
+        assert_snapshot!(render(text), @"
+        <hr>
+        <p>Content</p>
+        ");
+    }
+
+    #[test]
+    fn simple_table_without_header() {
+        // Two adjacent borders must not be mistaken for a header
+        assert_snapshot!(render("===== ===\n===== ===\nx\n\n"), @"
+        <table>
+        <tbody>
+        <tr>
+        <td>x</td>
+        <td></td>
+        </tr>
+        </tbody>
+        </table>
+        ");
+    }
+
+    #[test]
+    fn never_panics() {
+        const SAMPLE: &str = "\
+=======
+ Title
+=======
+
+*emphasis* **strong** ``literal`` `Cargo`_ |build| [1]_ :code:`x`
+
+.. _Cargo: https://doc.rust-lang.org/cargo/
+.. |build| image:: https://example.com/badge.svg
+   :target: https://example.com/
+
+- first
+
+  * nested
+
+#. item
+:Field: value
+
+term
+    definition
+
+Example::
+
+    let x = 1;
+
+.. code-block:: rust
+
+   fn main() {}
+
+.. note:: This is
+   a note.
+
++-----+-------+
+| A   | B     |
++=====+=======+
+| 1   | ``2`` |
++-----+-------+
+
+=====  =====
+A      B
+=====  =====
+1      2
+=====  =====
+
+.. list-table::
+   :header-rows: 1
+
+   * - A
+     - B
+
+.. csv-table::
+   :header: \"A\", \"B\"
+
+   1, \"2, 3\"
+";
+
+        let fragments = [
+            "\n",
+            "\n\n",
+            " ",
+            "   ",
+            "\t",
+            "=====",
+            "-----",
+            "+---+",
+            "| a |",
+            "+===+",
+            "*",
+            "**",
+            "`",
+            "``",
+            "_",
+            "__",
+            "|",
+            ":",
+            "::",
+            ".. ",
+            "[1]",
+            "- ",
+            "#. ",
+            "1. ",
+            ">>> ",
+            "a",
+            "word",
+            "ü",
+            "<",
+            "\\",
+            ".. note::",
+            ".. list-table::",
+            ".. csv-table::",
+            ":header-rows: 1",
+            ".. |x| image:: ",
+            ".. _x: ",
+            "`x <y>`_",
+        ];
+        assert_never_panics(&fragments, SAMPLE, render);
+    }
+}
@@ -0,0 +1,45 @@
+//! Helpers for checking that the hand-written renderers never panic.
+
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+/// Small xorshift generator, so that the generated inputs are reproducible
+/// without pulling in a random number generator dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+}
+
+/// Renders random combinations of `fragments`, and every truncation of
+/// `sample`, and panics with the offending input if `render` panics.
+pub fn assert_never_panics(fragments: &[&str], sample: &str, render: impl Fn(&str) -> String) {
+    let check = |input: &str| {
+        let result = catch_unwind(AssertUnwindSafe(|| render(input)));
+        assert!(result.is_ok(), "rendering panicked for input {input:?}");
+    };
+
+    for (index, _) in sample.char_indices() {
+        check(&sample[..index]);
+    }
+
+    let lines = sample.lines().collect::<Vec<_>>();
+    for skip in 0..lines.len() {
+        let mut input = lines.clone();
+        input.remove(skip);
+        check(&input.join("\n"));
+    }
+
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..5000 {
+        let len = rng.next() % 40;
+        let input = (0..len)
+            .map(|_| fragments[rng.next() % fragments.len()])
+            .collect::<String>();
+        check(&input);
+    }
+}
@@ -0,0 +1,111 @@
+//! Generates the table of contents of a rendered README.
+
+use crate::html::to_plain_text;
+use serde::Serialize;
+
+/// A heading of a rendered README.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Heading {
+    /// The level of the heading, from 1 to 6.
+    pub level: u8,
+    /// The `id` of the heading element, e.g. `user-content-usage`.
+    pub id: String,
+    /// The text content of the heading.
+    pub title: String,
+}
+
+/// Collects the headings of sanitized HTML in document order. Headings
+/// without an `id`, e.g. raw HTML headings, can't be linked to and are
+/// skipped.
+pub(crate) fn table_of_contents(html: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+
+    let mut rest = html;
+    while let Some(start) = rest.find("<h") {
+        rest = &rest[start + 2..];
+
+        let Some(level) = rest
+            .bytes()
+            .next()
+            .filter(|level| (b'1'..=b'6').contains(level))
+            .map(|level| level - b'0')
+        else {
+            continue;
+        };
+        if !rest[1..].starts_with([' ', '>']) {
+            continue;
+        }
+
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let attributes = &rest[1..tag_end];
+        rest = &rest[tag_end + 1..];
+
+        let closing_tag = format!("</h{level}>");
+        let Some(content_end) = rest.find(&closing_tag) else {
+            break;
+        };
+        let content = &rest[..content_end];
+        rest = &rest[content_end + closing_tag.len()..];
+
+        let Some(id) = attribute(attributes, "id") else {
+            continue;
+        };
+        let title = to_plain_text(content)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        headings.push(Heading { level, id, title });
+    }
+
+    headings
+}
+
+/// Returns the decoded value of a double-quoted attribute, which is how the
+/// sanitizer serializes all attributes.
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let prefix = format!(" {name}=\"");
+    let start = attributes.find(&prefix)? + prefix.len();
+    let value = &attributes[start..];
+    let value = &value[..value.find('"')?];
+    Some(htmlescape::decode_html(value).unwrap_or_else(|_| value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::table_of_contents;
+    use insta::assert_debug_snapshot;
+
+    #[test]
+    fn headings() {
+        let html = r##"<h1 id="user-content-foo">Foo<a href="#user-content-foo" aria-label="Link to heading 'Foo'" rel="nofollow noopener noreferrer"></a></h1>
+<p>Text</p>
+<h2 id="user-content-foo-amp-bar"><code>Foo</code> &amp;
+bar</h2>
+<hr>
+<h3 align="center">No ID</h3>
+<h6 id="user-content-last">Last</h6>
+"##;
+        assert_debug_snapshot!(table_of_contents(html), @r#"
+        [
+            Heading {
+                level: 1,
+                id: "user-content-foo",
+                title: "Foo",
+            },
+            Heading {
+                level: 2,
+                id: "user-content-foo-amp-bar",
+                title: "Foo & bar",
+            },
+            Heading {
+                level: 6,
+                id: "user-content-last",
+                title: "Last",
+            },
+        ]
+        "#);
+    }
+}
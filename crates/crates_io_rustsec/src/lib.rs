@@ -0,0 +1,338 @@
+#![doc = include_str!("../README.md")]
+
+use chrono::NaiveDate;
+use semver::{Op, Prerelease, Version, VersionReq};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use tracing::warn;
+
+/// The file name prefix of all advisories in the database.
+const ID_PREFIX: &str = "RUSTSEC-";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Failed to read `{path}`: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("Failed to parse advisory: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Missing ```toml front matter")]
+    MissingFrontMatter,
+}
+
+/// A security advisory for a crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advisory {
+    /// The RustSec ID of the advisory, e.g. `RUSTSEC-2019-0001`.
+    pub id: String,
+    /// The name of the affected crate.
+    pub package: String,
+    pub title: String,
+    pub description: String,
+    /// The date on which the advisory was reported.
+    pub date: NaiveDate,
+    pub url: Option<String>,
+    /// Other identifiers of the vulnerability, e.g. CVE IDs.
+    pub aliases: Vec<String>,
+    /// The kind of informational advisory, e.g. `unmaintained`, or `None`
+    /// for vulnerabilities.
+    pub informational: Option<String>,
+    /// The date on which the advisory was withdrawn, if it was.
+    pub withdrawn: Option<NaiveDate>,
+    /// Versions that contain a fix.
+    pub patched: Vec<VersionReq>,
+    /// Versions that were never affected.
+    pub unaffected: Vec<VersionReq>,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: AdvisoryVersions,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    package: String,
+    date: NaiveDate,
+    url: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    informational: Option<String>,
+    withdrawn: Option<NaiveDate>,
+    // Only used by the legacy TOML format. The Markdown format contains
+    // these as a heading and the text below it.
+    title: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct AdvisoryVersions {
+    #[serde(default)]
+    patched: Vec<VersionReq>,
+    #[serde(default)]
+    unaffected: Vec<VersionReq>,
+}
+
+impl Advisory {
+    /// Parses an advisory in the Markdown format with TOML front matter.
+    pub fn from_markdown(content: &str) -> Result<Self, Error> {
+        let content = content.trim_start();
+        let front_matter = content
+            .strip_prefix("```toml")
+            .and_then(|rest| rest.split_once("\n```"))
+            .ok_or(Error::MissingFrontMatter)?;
+
+        let (toml, markdown) = front_matter;
+        let file: AdvisoryFile = toml::from_str(toml)?;
+
+        let markdown = markdown.trim();
+        let (title, description) = match markdown.strip_prefix("# ") {
+            Some(rest) => rest.split_once('\n').unwrap_or((rest, "")),
+            None => ("", markdown),
+        };
+
+        Ok(Self::new(file, title.trim(), description.trim()))
+    }
+
+    /// Parses an advisory in the legacy TOML format.
+    pub fn from_toml(content: &str) -> Result<Self, Error> {
+        let file: AdvisoryFile = toml::from_str(content)?;
+        let title = file.advisory.title.clone().unwrap_or_default();
+        let description = file.advisory.description.clone().unwrap_or_default();
+        Ok(Self::new(file, title.trim(), description.trim()))
+    }
+
+    fn new(file: AdvisoryFile, title: &str, description: &str) -> Self {
+        let AdvisoryFile { advisory, versions } = file;
+        Self {
+            id: advisory.id,
+            package: advisory.package,
+            title: title.to_string(),
+            description: description.to_string(),
+            date: advisory.date,
+            url: advisory.url,
+            aliases: advisory.aliases,
+            informational: advisory.informational,
+            withdrawn: advisory.withdrawn,
+            patched: versions.patched,
+            unaffected: versions.unaffected,
+        }
+    }
+
+    /// Returns whether the given version of the crate is affected by this
+    /// advisory, i.e. whether it matches neither a `patched` nor an
+    /// `unaffected` version requirement.
+    pub fn affects(&self, version: &Version) -> bool {
+        !self
+            .patched
+            .iter()
+            .chain(&self.unaffected)
+            .any(|req| matches(req, version))
+    }
+}
+
+/// Like [`VersionReq::matches`], but also matches pre-release versions
+/// against requirements without a pre-release, based on their ordering.
+///
+/// `>= 2.1.0` does not match `3.0.0-rc.1` according to Cargo's rules, but
+/// the release candidate clearly contains the fix from 2.1.0.
+fn matches(req: &VersionReq, version: &Version) -> bool {
+    if version.pre.is_empty() {
+        return req.matches(version);
+    }
+
+    let release = Version {
+        pre: Prerelease::EMPTY,
+        ..version.clone()
+    };
+
+    req.comparators.iter().all(|comparator| {
+        let bound = Version {
+            major: comparator.major,
+            minor: comparator.minor.unwrap_or(0),
+            patch: comparator.patch.unwrap_or(0),
+            pre: comparator.pre.clone(),
+            build: Default::default(),
+        };
+
+        match comparator.op {
+            Op::GreaterEq => *version >= bound,
+            Op::Less => *version < bound,
+            Op::Greater if comparator.patch.is_some() => *version > bound,
+            Op::LessEq if comparator.patch.is_some() => *version <= bound,
+            Op::Greater | Op::LessEq => comparator.matches(&release),
+            _ => *version >= bound && comparator.matches(&release),
+        }
+    })
+}
+
+/// Loads all advisories from a local checkout of an advisory database.
+///
+/// All `RUSTSEC-*.md` and `RUSTSEC-*.toml` files in the directory and its
+/// subdirectories are loaded. Files that can't be parsed are skipped with a
+/// warning, so that a single broken advisory does not block the import of
+/// all others.
+pub fn load_advisories(path: &Path) -> Result<Vec<Advisory>, Error> {
+    let mut advisories = Vec::new();
+    load_directory(path, &mut advisories)?;
+    advisories.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(advisories)
+}
+
+fn load_directory(path: &Path, advisories: &mut Vec<Advisory>) -> Result<(), Error> {
+    let io_error = |source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    for entry in fs::read_dir(path).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(io_error)?;
+
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+
+        if file_type.is_dir() {
+            // Skip `.git` and similar directories.
+            if !file_name.starts_with('.') {
+                load_directory(&path, advisories)?;
+            }
+            continue;
+        }
+
+        if !file_name.starts_with(ID_PREFIX) {
+            continue;
+        }
+
+        let parse = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md") => Advisory::from_markdown,
+            Some("toml") => Advisory::from_toml,
+            _ => continue,
+        };
+
+        let content = fs::read_to_string(&path).map_err(|source| Error::Io {
+            path: path.clone(),
+            source,
+        })?;
+
+        match parse(&content) {
+            Ok(advisory) => advisories.push(advisory),
+            Err(error) => warn!("Skipping advisory `{}`: {error}", path.display()),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+    use insta::assert_debug_snapshot;
+
+    const MARKDOWN: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2019-0001"
+package = "ammonia"
+date = "2019-04-27"
+url = "https://github.com/rust-ammonia/ammonia/blob/master/CHANGELOG.md#210"
+categories = ["denial-of-service"]
+aliases = ["CVE-2019-15542"]
+
+[versions]
+patched = [">= 2.1.0"]
+unaffected = ["< 1.0.0"]
+```
+
+# Uncontrolled recursion leads to abort in HTML serialization
+
+Affected versions of this crate did use recursion for serialization of HTML
+DOM trees.
+"#;
+
+    const TOML: &str = r#"
+[advisory]
+id = "RUSTSEC-2017-0001"
+package = "sodiumoxide"
+date = "2017-01-26"
+title = "scalarmult() vulnerable to degenerate public keys"
+description = "The `scalarmult()` function included in previous versions of this crate accepted all-zero public keys."
+
+[versions]
+patched = [">= 0.0.14"]
+"#;
+
+    fn version(version: &str) -> Version {
+        version.parse().unwrap()
+    }
+
+    #[test]
+    fn test_from_markdown() {
+        let advisory = assert_ok!(Advisory::from_markdown(MARKDOWN));
+        assert_debug_snapshot!(advisory);
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let advisory = assert_ok!(Advisory::from_toml(TOML));
+        assert_eq!(advisory.id, "RUSTSEC-2017-0001");
+        assert_eq!(
+            advisory.title,
+            "scalarmult() vulnerable to degenerate public keys"
+        );
+        assert!(advisory.description.starts_with("The `scalarmult()`"));
+        assert_eq!(advisory.patched.len(), 1);
+    }
+
+    #[test]
+    fn test_missing_front_matter() {
+        let error = assert_err!(Advisory::from_markdown("# Title"));
+        assert!(matches!(error, Error::MissingFrontMatter));
+    }
+
+    #[test]
+    fn test_affects() {
+        let advisory = assert_ok!(Advisory::from_markdown(MARKDOWN));
+
+        assert!(!advisory.affects(&version("0.7.0")));
+        assert!(advisory.affects(&version("1.0.0")));
+        assert!(advisory.affects(&version("2.0.1")));
+        assert!(advisory.affects(&version("2.1.0-beta.1")));
+        assert!(!advisory.affects(&version("2.1.0")));
+        assert!(!advisory.affects(&version("3.0.0-rc.1")));
+        assert!(!advisory.affects(&version("1.0.0-alpha.1")));
+        assert!(advisory.affects(&version("1.0.1-alpha.1")));
+
+        // Without any patched versions, all versions are affected
+        let advisory = Advisory {
+            patched: vec![],
+            unaffected: vec![],
+            ..advisory
+        };
+        assert!(advisory.affects(&version("3.0.0")));
+    }
+
+    #[test]
+    fn test_load_advisories() {
+        let dir = tempfile::tempdir().unwrap();
+        let crates_dir = dir.path().join("crates");
+        fs::create_dir_all(crates_dir.join("ammonia")).unwrap();
+        fs::create_dir_all(crates_dir.join("sodiumoxide")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+
+        let write = |path: PathBuf, content: &str| fs::write(path, content).unwrap();
+        write(crates_dir.join("ammonia/RUSTSEC-2019-0001.md"), MARKDOWN);
+        write(crates_dir.join("sodiumoxide/RUSTSEC-2017-0001.toml"), TOML);
+        write(crates_dir.join("ammonia/RUSTSEC-2019-9999.md"), "broken");
+        write(dir.path().join("README.md"), "# Advisory database");
+        write(dir.path().join(".git/RUSTSEC-2000-0000.md"), "ignored");
+
+        let advisories = assert_ok!(load_advisories(dir.path()));
+        let ids = advisories.iter().map(|a| a.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["RUSTSEC-2017-0001", "RUSTSEC-2019-0001"]);
+    }
+}
@@ -23,6 +23,7 @@ use tracing::{instrument, warn};
 mod builder;
 mod limit_reader;
 mod manifest;
+pub mod scan;
 mod vcs_info;
 
 const DEFAULT_BUF_SIZE: usize = 128 * 1024;
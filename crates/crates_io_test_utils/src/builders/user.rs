@@ -54,6 +54,8 @@ impl<'a> UserBuilder<'a> {
             gh_id: 123,
             gh_avatar: None,
             gh_encrypted_token: None,
+            gl_account_id: None,
+            gl_encrypted_token: None,
             account_lock_reason: None,
             account_lock_until: None,
             is_admin: false,
@@ -0,0 +1,234 @@
+use crate::buildkite::BUILDKITE_ISSUER_URL;
+use crate::provider::OidcClaims;
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::errors::{Error, ErrorKind};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Claims extracted from a Buildkite Pipelines OIDC token.
+///
+/// This struct is used to decode and validate the JWT token generated by
+/// `buildkite-agent oidc request-token`. It contains the claims that are
+/// relevant for our "Trusted Publishing" implementation.
+///
+/// See <https://buildkite.com/docs/agent/v3/cli-oidc#claims>.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BuildkiteClaims {
+    pub aud: String,
+    #[serde(with = "ts_seconds")]
+    pub iat: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    pub exp: DateTime<Utc>,
+    pub jti: Option<String>,
+
+    pub organization_slug: String,
+    pub pipeline_slug: String,
+    /// Only present if requested via `--claim organization_id`.
+    pub organization_id: Option<String>,
+    pub build_branch: Option<String>,
+    pub build_commit: String,
+    pub step_key: Option<String>,
+    pub job_id: String,
+}
+
+impl BuildkiteClaims {
+    /// Decodes and validates a JWT token, returning the relevant claims if valid.
+    pub fn decode(token: &str, audience: &str, key: &DecodingKey) -> Result<Self, Error> {
+        let validation = validation(audience);
+
+        let claims: Self = jsonwebtoken::decode(token, key, &validation)?.claims;
+
+        let leeway = chrono::TimeDelta::seconds(validation.leeway as i64);
+        if claims.iat > Utc::now() + leeway {
+            return Err(ErrorKind::ImmatureSignature.into());
+        }
+
+        Ok(claims)
+    }
+
+    /// Converts these claims into the provider-independent [`OidcClaims`].
+    ///
+    /// Buildkite tokens don't necessarily contain a `jti` claim, so the
+    /// SHA-256 hash of the token itself is used for replay protection in
+    /// that case.
+    pub(crate) fn into_oidc_claims(self, token: &str) -> OidcClaims {
+        let jti = self.jti.unwrap_or_else(|| {
+            let hash = Sha256::digest(token.as_bytes());
+            hash.iter().map(|byte| format!("{byte:02x}")).collect()
+        });
+
+        let mut claims = BTreeMap::new();
+        if let Some(build_branch) = self.build_branch {
+            claims.insert("build_branch".into(), build_branch);
+        }
+        if let Some(step_key) = self.step_key {
+            claims.insert("step_key".into(), step_key);
+        }
+
+        OidcClaims {
+            jti,
+            exp: self.exp,
+            repository: format!("{}/{}", self.organization_slug, self.pipeline_slug),
+            repository_owner_id: self.organization_id,
+            run_id: self.job_id,
+            sha: self.build_commit,
+            claims,
+        }
+    }
+}
+
+fn validation(audience: &str) -> Validation {
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.leeway = crate::JWT_LEEWAY.num_seconds() as u64;
+    validation.required_spec_claims.insert("iss".into());
+    validation.required_spec_claims.insert("exp".into());
+    validation.required_spec_claims.insert("aud".into());
+    validation.validate_exp = true;
+    validation.validate_aud = true;
+    validation.validate_nbf = true;
+    validation.set_issuer(&[BUILDKITE_ISSUER_URL]);
+    validation.set_audience(&[audience]);
+    validation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_keys::{DECODING_KEY, encode_for_testing};
+    use insta::{assert_compact_debug_snapshot, assert_json_snapshot};
+    use serde_json::json;
+    use std::time::SystemTime;
+
+    const AUDIENCE: &str = "crates.io";
+
+    #[test]
+    fn test_decode() -> anyhow::Result<()> {
+        let now = SystemTime::UNIX_EPOCH.elapsed()?.as_secs();
+
+        let jwt = encode_for_testing(&json!({
+            "iss": "https://agent.buildkite.com",
+            "sub": "organization:acme-inc:pipeline:super-duper-app:ref:refs/heads/main:commit:9f3182061f1e2cca4702c368cbc039b7dc9d4485:step:build",
+            "aud": AUDIENCE,
+            "iat": now,
+            "nbf": now,
+            "exp": now + 5 * 60,
+            "organization_slug": "acme-inc",
+            "pipeline_slug": "super-duper-app",
+            "organization_id": "0184990a-4782-42b5-afc1-16715b10b8ff",
+            "build_number": 1,
+            "build_branch": "main",
+            "build_tag": null,
+            "build_commit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485",
+            "build_source": "ui",
+            "step_key": "build",
+            "job_id": "0184990a-477b-4fa8-9968-496074483cee",
+            "agent_id": "0184990a-4782-42b5-afc1-16715b10b8ff",
+            "runner_environment": "buildkite-hosted"
+        }))?;
+
+        let claims = BuildkiteClaims::decode(&jwt, AUDIENCE, &DECODING_KEY)?;
+        assert_json_snapshot!(claims, { ".iat" => "[datetime]", ".exp" => "[datetime]" }, @r#"
+        {
+          "aud": "crates.io",
+          "iat": "[datetime]",
+          "exp": "[datetime]",
+          "jti": null,
+          "organization_slug": "acme-inc",
+          "pipeline_slug": "super-duper-app",
+          "organization_id": "0184990a-4782-42b5-afc1-16715b10b8ff",
+          "build_branch": "main",
+          "build_commit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485",
+          "step_key": "build",
+          "job_id": "0184990a-477b-4fa8-9968-496074483cee"
+        }
+        "#);
+
+        let oidc_claims = claims.into_oidc_claims(&jwt);
+        assert_eq!(oidc_claims.jti.len(), 64);
+        assert_json_snapshot!(oidc_claims, { ".jti" => "[hash]", ".exp" => "[datetime]" }, @r#"
+        {
+          "jti": "[hash]",
+          "exp": "[datetime]",
+          "repository": "acme-inc/super-duper-app",
+          "repository_owner_id": "0184990a-4782-42b5-afc1-16715b10b8ff",
+          "run_id": "0184990a-477b-4fa8-9968-496074483cee",
+          "sha": "9f3182061f1e2cca4702c368cbc039b7dc9d4485",
+          "claims": {
+            "build_branch": "main",
+            "step_key": "build"
+          }
+        }
+        "#);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_with_jti() -> anyhow::Result<()> {
+        let now = SystemTime::UNIX_EPOCH.elapsed()?.as_secs();
+
+        let jwt = encode_for_testing(&json!({
+            "iss": "https://agent.buildkite.com",
+            "aud": AUDIENCE,
+            "iat": now,
+            "exp": now + 5 * 60,
+            "jti": "235b3a54-b797-45c7-ae9a-f72d7bc6ef5b",
+            "organization_slug": "acme-inc",
+            "pipeline_slug": "super-duper-app",
+            "build_commit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485",
+            "job_id": "0184990a-477b-4fa8-9968-496074483cee",
+        }))?;
+
+        let claims = BuildkiteClaims::decode(&jwt, AUDIENCE, &DECODING_KEY)?;
+        let oidc_claims = claims.into_oidc_claims(&jwt);
+        assert_eq!(oidc_claims.jti, "235b3a54-b797-45c7-ae9a-f72d7bc6ef5b");
+        assert_eq!(oidc_claims.repository_owner_id, None);
+        assert!(oidc_claims.claims.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_wrong_issuer() -> anyhow::Result<()> {
+        let now = SystemTime::UNIX_EPOCH.elapsed()?.as_secs();
+
+        let jwt = encode_for_testing(&json!({
+            "iss": "https://gitlab.com",
+            "aud": AUDIENCE,
+            "iat": now,
+            "exp": now + 5 * 60,
+            "organization_slug": "acme-inc",
+            "pipeline_slug": "super-duper-app",
+            "build_commit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485",
+            "job_id": "0184990a-477b-4fa8-9968-496074483cee",
+        }))?;
+
+        let error = BuildkiteClaims::decode(&jwt, AUDIENCE, &DECODING_KEY).unwrap_err();
+        assert_compact_debug_snapshot!(error, @"Error(InvalidIssuer)");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_wrong_audience() -> anyhow::Result<()> {
+        let now = SystemTime::UNIX_EPOCH.elapsed()?.as_secs();
+
+        let jwt = encode_for_testing(&json!({
+            "iss": "https://agent.buildkite.com",
+            "aud": "https://buildkite.com/acme-inc",
+            "iat": now,
+            "exp": now + 5 * 60,
+            "organization_slug": "acme-inc",
+            "pipeline_slug": "super-duper-app",
+            "build_commit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485",
+            "job_id": "0184990a-477b-4fa8-9968-496074483cee",
+        }))?;
+
+        let error = BuildkiteClaims::decode(&jwt, AUDIENCE, &DECODING_KEY).unwrap_err();
+        assert_compact_debug_snapshot!(error, @"Error(InvalidAudience)");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+mod claims;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod test_helpers;
+
+use crate::provider::{OidcClaims, Provider, ValidationError, validate_repository_length};
+use jsonwebtoken::DecodingKey;
+use jsonwebtoken::errors::Error;
+use regex::regex;
+
+pub use self::claims::BuildkiteClaims;
+
+pub const BUILDKITE_ISSUER_URL: &str = "https://agent.buildkite.com";
+
+/// The Buildkite Pipelines "Trusted Publishing" provider.
+///
+/// The config `repository` has the form `{organization_slug}/{pipeline_slug}`
+/// and configs may additionally be restricted to a `build_branch` and/or a
+/// `step_key`.
+///
+/// See <https://buildkite.com/docs/agent/v3/cli-oidc>.
+pub struct Buildkite;
+
+impl Provider for Buildkite {
+    fn id(&self) -> &'static str {
+        "buildkite"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Buildkite"
+    }
+
+    fn issuer_url(&self) -> &'static str {
+        BUILDKITE_ISSUER_URL
+    }
+
+    fn optional_claims(&self) -> &'static [&'static str] {
+        &["build_branch", "step_key"]
+    }
+
+    fn validate_repository(&self, repository: &str) -> Result<(), ValidationError> {
+        let re_valid_slug = regex!(r"^[a-zA-Z0-9](?:[a-zA-Z0-9_\-]*[a-zA-Z0-9])?$");
+
+        validate_repository_length(repository)?;
+
+        let Some((organization, pipeline)) = repository.split_once('/') else {
+            let message = "expected `{organization_slug}/{pipeline_slug}`";
+            return Err(ValidationError::RepositoryInvalid(message));
+        };
+
+        if !re_valid_slug.is_match(organization) {
+            Err(ValidationError::RepositoryInvalid(
+                "invalid Buildkite organization slug",
+            ))
+        } else if !re_valid_slug.is_match(pipeline) {
+            Err(ValidationError::RepositoryInvalid(
+                "invalid Buildkite pipeline slug",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn decode(&self, token: &str, audience: &str, key: &DecodingKey) -> Result<OidcClaims, Error> {
+        let claims = BuildkiteClaims::decode(token, audience, key)?;
+        Ok(claims.into_oidc_claims(token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::claims::{assert_err, assert_ok};
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_validate_repository() {
+        assert_ok!(Buildkite.validate_repository("acme-inc/my-pipeline"));
+        assert_ok!(Buildkite.validate_repository("acme/a"));
+
+        assert_snapshot!(assert_err!(Buildkite.validate_repository("")), @"Repository may not be empty");
+        assert_snapshot!(assert_err!(Buildkite.validate_repository(&"x".repeat(256))), @"Repository is too long (maximum is 255 characters)");
+        assert_snapshot!(assert_err!(Buildkite.validate_repository("acme-inc")), @"Invalid repository: expected `{organization_slug}/{pipeline_slug}`");
+        assert_snapshot!(assert_err!(Buildkite.validate_repository("/my-pipeline")), @"Invalid repository: invalid Buildkite organization slug");
+        assert_snapshot!(assert_err!(Buildkite.validate_repository("acme-inc/")), @"Invalid repository: invalid Buildkite pipeline slug");
+        assert_snapshot!(assert_err!(Buildkite.validate_repository("acme-inc/my/pipeline")), @"Invalid repository: invalid Buildkite pipeline slug");
+        assert_snapshot!(assert_err!(Buildkite.validate_repository("acme inc/my-pipeline")), @"Invalid repository: invalid Buildkite organization slug");
+    }
+}
@@ -0,0 +1,131 @@
+use super::BUILDKITE_ISSUER_URL;
+use crate::test_keys::encode_for_testing;
+use bon::bon;
+use serde_json::json;
+
+pub const AUDIENCE: &str = "crates.io";
+
+/// A struct representing all the claims in a Buildkite Pipelines OIDC token.
+///
+/// This struct is used to create a JWT for testing purposes.
+#[derive(Debug, serde::Serialize)]
+pub struct FullBuildkiteClaims {
+    pub iss: String,
+    pub nbf: i64,
+    pub exp: i64,
+    pub iat: i64,
+    pub sub: String,
+    pub aud: String,
+
+    pub organization_slug: String,
+    pub pipeline_slug: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization_id: Option<String>,
+    pub build_number: i64,
+    pub build_branch: String,
+    pub build_tag: Option<String>,
+    pub build_commit: String,
+    pub build_source: String,
+    pub step_key: Option<String>,
+    pub job_id: String,
+    pub agent_id: String,
+    pub runner_environment: String,
+}
+
+#[bon]
+impl FullBuildkiteClaims {
+    #[builder]
+    pub fn new(
+        organization: &str,
+        pipeline: &str,
+        organization_id: Option<&str>,
+        branch: Option<&str>,
+        step_key: Option<&str>,
+    ) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        let branch = branch.unwrap_or("main");
+        let commit = "9f3182061f1e2cca4702c368cbc039b7dc9d4485";
+        let step = step_key.unwrap_or_default();
+
+        Self {
+            iss: BUILDKITE_ISSUER_URL.into(),
+            nbf: now,
+            iat: now,
+            exp: now + 5 * 60,
+            sub: format!(
+                "organization:{organization}:pipeline:{pipeline}:ref:refs/heads/{branch}:commit:{commit}:step:{step}"
+            ),
+            aud: AUDIENCE.into(),
+
+            organization_slug: organization.into(),
+            pipeline_slug: pipeline.into(),
+            organization_id: organization_id.map(Into::into),
+            build_number: 1,
+            build_branch: branch.into(),
+            build_tag: None,
+            build_commit: commit.into(),
+            build_source: "webhook".into(),
+            step_key: step_key.map(Into::into),
+            job_id: "0184990a-477b-4fa8-9968-496074483cee".into(),
+            agent_id: "0184990a-4782-42b5-afc1-16715b10b8ff".into(),
+            runner_environment: "buildkite-hosted".into(),
+        }
+    }
+
+    pub fn encoded(&self) -> anyhow::Result<String> {
+        Ok(encode_for_testing(self)?)
+    }
+
+    pub fn as_exchange_body(&self) -> anyhow::Result<String> {
+        let jwt = self.encoded()?;
+        Ok(serde_json::to_string(&json!({ "jwt": jwt }))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_ok;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn test_buildkite_claims() {
+        let claims = FullBuildkiteClaims::builder()
+            .organization("acme-inc")
+            .pipeline("my-pipeline")
+            .step_key("publish")
+            .build();
+
+        assert_json_snapshot!(claims, {
+            ".nbf" => "[timestamp]",
+            ".iat" => "[timestamp]",
+            ".exp" => "[timestamp]",
+        }, @r#"
+        {
+          "iss": "https://agent.buildkite.com",
+          "nbf": "[timestamp]",
+          "exp": "[timestamp]",
+          "iat": "[timestamp]",
+          "sub": "organization:acme-inc:pipeline:my-pipeline:ref:refs/heads/main:commit:9f3182061f1e2cca4702c368cbc039b7dc9d4485:step:publish",
+          "aud": "crates.io",
+          "organization_slug": "acme-inc",
+          "pipeline_slug": "my-pipeline",
+          "build_number": 1,
+          "build_branch": "main",
+          "build_tag": null,
+          "build_commit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485",
+          "build_source": "webhook",
+          "step_key": "publish",
+          "job_id": "0184990a-477b-4fa8-9968-496074483cee",
+          "agent_id": "0184990a-4782-42b5-afc1-16715b10b8ff",
+          "runner_environment": "buildkite-hosted"
+        }
+        "#);
+
+        let encoded = assert_ok!(claims.encoded());
+        assert!(!encoded.is_empty());
+
+        let exchange_body = assert_ok!(claims.as_exchange_body());
+        assert!(exchange_body.contains(&encoded));
+    }
+}
@@ -1,9 +1,12 @@
 #![doc = include_str!("../README.md")]
 
 pub mod access_token;
+pub mod buildkite;
 pub mod github;
 pub mod gitlab;
 pub mod keystore;
+pub mod provenance;
+pub mod provider;
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod test_keys;
 pub mod unverified;
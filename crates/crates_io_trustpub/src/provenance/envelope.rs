@@ -0,0 +1,100 @@
+use super::Statement;
+use anyhow::{Context, Result};
+use aws_lc_rs::signature::{ED25519, UnparsedPublicKey};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+/// The `payloadType` of DSSE envelopes containing in-toto statements.
+pub const PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// A DSSE envelope containing a signed in-toto [`Statement`].
+///
+/// See <https://github.com/secure-systems-lab/dsse/blob/master/envelope.md>.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    /// Base64-encoded in-toto statement.
+    pub payload: String,
+    /// Type of the payload.
+    pub payload_type: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    /// Identifier of the signing key (the hex-encoded SHA256 hash of the
+    /// Ed25519 public key).
+    pub keyid: String,
+    /// Base64-encoded Ed25519 signature of the pre-authentication encoding
+    /// of the payload.
+    pub sig: String,
+}
+
+impl Envelope {
+    pub(crate) fn new(payload: &[u8], key_id: &str, signature: &[u8]) -> Self {
+        Self {
+            payload: STANDARD.encode(payload),
+            payload_type: PAYLOAD_TYPE.into(),
+            signatures: vec![EnvelopeSignature {
+                keyid: key_id.into(),
+                sig: STANDARD.encode(signature),
+            }],
+        }
+    }
+
+    /// Verifies the envelope signatures against the given Ed25519 public key
+    /// and returns the decoded [`Statement`] if any of them is valid.
+    pub fn verify(&self, public_key: &[u8]) -> Result<Statement> {
+        let payload = STANDARD
+            .decode(&self.payload)
+            .context("Invalid envelope payload")?;
+
+        let message = pae(&self.payload_type, &payload);
+        let public_key = UnparsedPublicKey::new(&ED25519, public_key);
+
+        let is_valid = self.signatures.iter().any(|signature| {
+            STANDARD
+                .decode(&signature.sig)
+                .is_ok_and(|sig| public_key.verify(&message, &sig).is_ok())
+        });
+
+        if !is_valid {
+            anyhow::bail!("Invalid envelope signature");
+        }
+
+        serde_json::from_slice(&payload).context("Invalid envelope payload")
+    }
+}
+
+/// Pre-authentication encoding (PAE) of the payload, which is the message
+/// that is actually signed.
+///
+/// See <https://github.com/secure-systems-lab/dsse/blob/master/protocol.md>.
+pub(crate) fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let header = format!(
+        "DSSEv1 {} {payload_type} {} ",
+        payload_type.len(),
+        payload.len()
+    );
+
+    let mut message = Vec::with_capacity(header.len() + payload.len());
+    message.extend_from_slice(header.as_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pae() {
+        let message = pae("http://example.com/HelloWorld", b"hello world");
+        let message = String::from_utf8(message).unwrap();
+        assert_eq!(
+            message,
+            "DSSEv1 29 http://example.com/HelloWorld 11 hello world"
+        );
+    }
+}
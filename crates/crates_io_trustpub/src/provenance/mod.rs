@@ -0,0 +1,231 @@
+//! Signed provenance attestations for crate versions published via
+//! "Trusted Publishing".
+//!
+//! When a crate version is published with a Trusted Publishing token,
+//! crates.io issues an [in-toto](https://in-toto.io/) [`Statement`] with a
+//! [SLSA provenance](https://slsa.dev/spec/v1.0/provenance) predicate that
+//! binds the SHA256 checksum of the `.crate` file to the OIDC claims of the
+//! CI workflow that published it. The statement is signed by the
+//! [`ProvenanceSigner`] and wrapped in a [DSSE](https://github.com/secure-systems-lab/dsse)
+//! [`Envelope`], which allows downstream consumers to verify it offline
+//! using only the public key of crates.io.
+
+mod envelope;
+mod statement;
+
+pub use self::envelope::{Envelope, EnvelopeSignature, PAYLOAD_TYPE};
+pub use self::statement::{
+    BUILD_TYPE, BuildDefinition, BuildMetadata, Builder, ExternalParameters, PREDICATE_TYPE,
+    Provenance, ResourceDescriptor, RunDetails, STATEMENT_TYPE, Statement,
+};
+
+use anyhow::{Context, Result};
+use aws_lc_rs::signature::{Ed25519KeyPair, KeyPair};
+use sha2::{Digest, Sha256};
+
+/// Signs provenance [`Statement`]s using an Ed25519 key.
+pub struct ProvenanceSigner {
+    key_pair: Ed25519KeyPair,
+    key_id: String,
+}
+
+impl ProvenanceSigner {
+    /// Creates a new [`ProvenanceSigner`] from a 32-byte Ed25519 seed.
+    pub fn new(seed: &[u8]) -> Result<Self> {
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(seed)
+            .map_err(|error| anyhow::anyhow!("Invalid Ed25519 seed: {error}"))?;
+
+        let key_id = hex::encode(Sha256::digest(key_pair.public_key().as_ref()));
+
+        Ok(Self { key_pair, key_id })
+    }
+
+    /// Creates a new [`ProvenanceSigner`] with a key for testing purposes.
+    #[cfg(any(test, debug_assertions))]
+    pub fn for_testing() -> Self {
+        Self::new(b"test_seed_32_bytes_long_for_test").unwrap()
+    }
+
+    /// Creates a new [`ProvenanceSigner`] from the environment
+    ///
+    /// Reads the optional `PROVENANCE_SIGNING_KEY` environment variable,
+    /// which should be a 64-character hex string (32 bytes when decoded)
+    /// containing the Ed25519 seed. Returns `None` if the variable is not
+    /// set or empty, which disables the issuing of provenance attestations.
+    pub fn from_environment() -> Result<Option<Self>> {
+        let signing_key = std::env::var("PROVENANCE_SIGNING_KEY").unwrap_or_default();
+        if signing_key.is_empty() {
+            return Ok(None);
+        }
+
+        if signing_key.len() != 64 {
+            anyhow::bail!("PROVENANCE_SIGNING_KEY must be exactly 64 hex characters");
+        }
+
+        let seed = hex::decode(signing_key.as_bytes())
+            .context("PROVENANCE_SIGNING_KEY must be exactly 64 hex characters")?;
+
+        Self::new(&seed).map(Some)
+    }
+
+    /// Identifier of the signing key (the hex-encoded SHA256 hash of the
+    /// public key), used as the `keyid` of the [`EnvelopeSignature`].
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Raw Ed25519 public key, which can be used to verify the signatures.
+    pub fn public_key(&self) -> &[u8] {
+        self.key_pair.public_key().as_ref()
+    }
+
+    /// Serializes the statement and signs it, returning a DSSE [`Envelope`].
+    pub fn sign(&self, statement: &Statement) -> Result<Envelope> {
+        let payload = serde_json::to_vec(statement).context("Failed to serialize statement")?;
+        let signature = self.key_pair.sign(&envelope::pae(PAYLOAD_TYPE, &payload));
+        Ok(Envelope::new(&payload, &self.key_id, signature.as_ref()))
+    }
+}
+
+impl std::fmt::Debug for ProvenanceSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProvenanceSigner")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+    use insta::{assert_json_snapshot, assert_snapshot};
+    use std::collections::BTreeMap;
+
+    fn statement() -> Statement {
+        Statement::new(
+            "foo-1.0.0.crate",
+            &[0xab; 32],
+            Provenance {
+                build_definition: BuildDefinition {
+                    build_type: BUILD_TYPE.into(),
+                    external_parameters: ExternalParameters {
+                        issuer: "https://token.actions.githubusercontent.com".into(),
+                        repository: "rust-lang/foo-rs".into(),
+                        workflow: Some(".github/workflows/publish.yml".into()),
+                        git_ref: Some("refs/heads/main".into()),
+                    },
+                    resolved_dependencies: vec![ResourceDescriptor {
+                        uri: Some("git+https://github.com/rust-lang/foo-rs@refs/heads/main".into()),
+                        name: None,
+                        digest: BTreeMap::from([("gitCommit".into(), "abc123".into())]),
+                    }],
+                },
+                run_details: RunDetails {
+                    builder: Builder {
+                        id: "https://token.actions.githubusercontent.com".into(),
+                    },
+                    metadata: BuildMetadata {
+                        invocation_id: "https://github.com/rust-lang/foo-rs/actions/runs/42".into(),
+                    },
+                },
+            },
+        )
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = ProvenanceSigner::for_testing();
+        let statement = statement();
+
+        let envelope = assert_ok!(signer.sign(&statement));
+        assert_eq!(envelope.payload_type, PAYLOAD_TYPE);
+        assert_eq!(envelope.signatures.len(), 1);
+        assert_eq!(envelope.signatures[0].keyid, signer.key_id());
+
+        let verified = assert_ok!(envelope.verify(signer.public_key()));
+        assert_eq!(verified, statement);
+    }
+
+    #[test]
+    fn test_verify_with_wrong_key() {
+        let signer = ProvenanceSigner::for_testing();
+        let envelope = assert_ok!(signer.sign(&statement()));
+
+        let other = assert_ok!(ProvenanceSigner::new(&[1; 32]));
+        let error = assert_err!(envelope.verify(other.public_key()));
+        assert_snapshot!(error, @"Invalid envelope signature");
+    }
+
+    #[test]
+    fn test_verify_tampered_payload() {
+        let signer = ProvenanceSigner::for_testing();
+        let mut envelope = assert_ok!(signer.sign(&statement()));
+
+        let mut tampered = statement();
+        tampered.subject[0].name = Some("bar-1.0.0.crate".into());
+        let tampered_envelope = assert_ok!(signer.sign(&tampered));
+        envelope.payload = tampered_envelope.payload;
+
+        let error = assert_err!(envelope.verify(signer.public_key()));
+        assert_snapshot!(error, @"Invalid envelope signature");
+    }
+
+    #[test]
+    fn test_statement_serialization() {
+        assert_json_snapshot!(statement(), @r#"
+        {
+          "_type": "https://in-toto.io/Statement/v1",
+          "subject": [
+            {
+              "name": "foo-1.0.0.crate",
+              "digest": {
+                "sha256": "abababababababababababababababababababababababababababababababab"
+              }
+            }
+          ],
+          "predicateType": "https://slsa.dev/provenance/v1",
+          "predicate": {
+            "buildDefinition": {
+              "buildType": "https://crates.io/trusted-publishing/provenance/v1",
+              "externalParameters": {
+                "issuer": "https://token.actions.githubusercontent.com",
+                "repository": "rust-lang/foo-rs",
+                "workflow": ".github/workflows/publish.yml",
+                "ref": "refs/heads/main"
+              },
+              "resolvedDependencies": [
+                {
+                  "uri": "git+https://github.com/rust-lang/foo-rs@refs/heads/main",
+                  "digest": {
+                    "gitCommit": "abc123"
+                  }
+                }
+              ]
+            },
+            "runDetails": {
+              "builder": {
+                "id": "https://token.actions.githubusercontent.com"
+              },
+              "metadata": {
+                "invocationId": "https://github.com/rust-lang/foo-rs/actions/runs/42"
+              }
+            }
+          }
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_key_id() {
+        let signer = ProvenanceSigner::for_testing();
+        assert_eq!(signer.key_id().len(), 64);
+        assert_eq!(signer.public_key().len(), 32);
+    }
+
+    #[test]
+    fn test_invalid_seed() {
+        let error = assert_err!(ProvenanceSigner::new(&[1; 16]));
+        assert_snapshot!(error, @"Invalid Ed25519 seed: InconsistentComponents");
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The `_type` of an in-toto v1 statement.
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// The `predicateType` of a SLSA v1 provenance predicate.
+pub const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+
+/// The `buildType` of the provenance predicates issued by crates.io.
+pub const BUILD_TYPE: &str = "https://crates.io/trusted-publishing/provenance/v1";
+
+/// An in-toto v1 statement with a SLSA v1 provenance predicate.
+///
+/// See <https://github.com/in-toto/attestation/blob/main/spec/v1/statement.md>.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    #[serde(rename = "_type")]
+    pub statement_type: String,
+    pub subject: Vec<ResourceDescriptor>,
+    pub predicate_type: String,
+    pub predicate: Provenance,
+}
+
+impl Statement {
+    /// Creates a new statement for the file with the given name and SHA256
+    /// checksum.
+    pub fn new(name: &str, sha256: &[u8], predicate: Provenance) -> Self {
+        Self {
+            statement_type: STATEMENT_TYPE.into(),
+            subject: vec![ResourceDescriptor {
+                uri: None,
+                name: Some(name.into()),
+                digest: BTreeMap::from([("sha256".into(), hex::encode(sha256))]),
+            }],
+            predicate_type: PREDICATE_TYPE.into(),
+            predicate,
+        }
+    }
+}
+
+/// A SLSA v1 provenance predicate.
+///
+/// See <https://slsa.dev/spec/v1.0/provenance>.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    pub build_definition: BuildDefinition,
+    pub run_details: RunDetails,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildDefinition {
+    pub build_type: String,
+    pub external_parameters: ExternalParameters,
+    pub resolved_dependencies: Vec<ResourceDescriptor>,
+}
+
+/// The verified OIDC claims of the CI workflow that published the crate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalParameters {
+    /// Issuer URL of the OIDC token (e.g. `https://token.actions.githubusercontent.com`)
+    pub issuer: String,
+    /// Repository that requested the OIDC token (e.g. `rust-lang/cargo`)
+    pub repository: String,
+    /// Path of the workflow file, if known (e.g. `.github/workflows/release.yml`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<String>,
+    /// Git ref that the workflow was run for, if known (e.g. `refs/heads/main`)
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunDetails {
+    pub builder: Builder,
+    pub metadata: BuildMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Builder {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildMetadata {
+    pub invocation_id: String,
+}
+
+/// See <https://github.com/in-toto/attestation/blob/main/spec/v1/resource_descriptor.md>.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceDescriptor {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub digest: BTreeMap<String, String>,
+}
@@ -0,0 +1,229 @@
+//! Pluggable "Trusted Publishing" providers.
+//!
+//! GitHub Actions and GitLab CI have dedicated claims types, validation
+//! modules and database tables. Additional OIDC issuers implement the
+//! [`Provider`] trait instead and share the generic `trustpub_configs_oidc`
+//! table, the `/api/v1/trusted_publishing/oidc_configs` endpoints and the
+//! config matching logic in [`OidcClaims::mismatched_claims()`].
+
+use crate::buildkite::Buildkite;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::DecodingKey;
+use jsonwebtoken::errors::Error;
+use std::collections::BTreeMap;
+
+const MAX_FIELD_LENGTH: usize = 255;
+
+/// All generic providers known to crates.io.
+///
+/// Providers still need to be enabled via the `TRUSTPUB_PROVIDERS`
+/// environment variable before tokens from their issuer are accepted.
+pub static PROVIDERS: &[&dyn Provider] = &[&Buildkite];
+
+/// Returns the provider with the given [`Provider::id()`], if it exists.
+pub fn provider_by_id(id: &str) -> Option<&'static dyn Provider> {
+    PROVIDERS
+        .iter()
+        .copied()
+        .find(|provider| provider.id() == id)
+}
+
+/// Returns the provider with the given [`Provider::issuer_url()`], if it exists.
+pub fn provider_by_issuer(issuer_url: &str) -> Option<&'static dyn Provider> {
+    PROVIDERS
+        .iter()
+        .copied()
+        .find(|provider| provider.issuer_url() == issuer_url)
+}
+
+/// An OIDC issuer that can be used for "Trusted Publishing".
+pub trait Provider: Send + Sync {
+    /// Unique identifier of the provider (e.g. `buildkite`).
+    ///
+    /// This value is stored in the `provider` column of the
+    /// `trustpub_configs_oidc` table and used in the `TRUSTPUB_PROVIDERS`
+    /// environment variable, so it must never change.
+    fn id(&self) -> &'static str;
+
+    /// Human-readable name of the provider (e.g. `Buildkite`).
+    fn display_name(&self) -> &'static str;
+
+    /// The `iss` claim value of the tokens issued by this provider.
+    fn issuer_url(&self) -> &'static str;
+
+    /// Names of the claims that a config may additionally be restricted by.
+    fn optional_claims(&self) -> &'static [&'static str];
+
+    /// Validates the provider-specific `repository` value of a config.
+    fn validate_repository(&self, repository: &str) -> Result<(), ValidationError>;
+
+    /// Decodes and validates a JWT, returning the relevant claims if valid.
+    fn decode(&self, token: &str, audience: &str, key: &DecodingKey) -> Result<OidcClaims, Error>;
+}
+
+/// Provider-independent claims extracted from a validated OIDC token.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct OidcClaims {
+    /// Unique token identifier, used for replay protection.
+    pub jti: String,
+    /// Expiration time of the token.
+    pub exp: DateTime<Utc>,
+
+    /// Provider-specific identifier of the repository or pipeline that
+    /// requested the token (e.g. `acme-inc/my-pipeline`).
+    pub repository: String,
+    /// Immutable ID of the owner of the repository, if the provider supplies
+    /// one. Used for resurrection attack protection.
+    pub repository_owner_id: Option<String>,
+    /// ID of the CI job or run that requested the token.
+    pub run_id: String,
+    /// SHA of the commit that is being built.
+    pub sha: String,
+
+    /// Values of the [`Provider::optional_claims()`] that are set in the token.
+    pub claims: BTreeMap<String, String>,
+}
+
+impl OidcClaims {
+    /// Returns the config restrictions that are not satisfied by these claims
+    /// as `(claim, expected value)` pairs.
+    ///
+    /// Values are compared case-insensitively, consistent with the
+    /// environment matching of the GitHub and GitLab providers.
+    pub fn mismatched_claims<'a>(
+        &self,
+        expected: &'a BTreeMap<String, String>,
+    ) -> Vec<(&'a str, &'a str)> {
+        expected
+            .iter()
+            .filter(|(name, expected)| {
+                self.claims
+                    .get(name.as_str())
+                    .is_none_or(|actual| actual.to_lowercase() != expected.to_lowercase())
+            })
+            .map(|(name, expected)| (name.as_str(), expected.as_str()))
+            .collect()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ValidationError {
+    #[error("Repository may not be empty")]
+    RepositoryEmpty,
+    #[error("Repository is too long (maximum is {MAX_FIELD_LENGTH} characters)")]
+    RepositoryTooLong,
+    #[error("Invalid repository: {0}")]
+    RepositoryInvalid(&'static str),
+
+    #[error("Unsupported claim `{0}`")]
+    ClaimUnsupported(String),
+    #[error("Value of claim `{0}` may not be empty (omit the claim instead)")]
+    ClaimEmpty(String),
+    #[error("Value of claim `{0}` is too long (maximum is {MAX_FIELD_LENGTH} characters)")]
+    ClaimTooLong(String),
+    #[error("Value of claim `{0}` contains invalid characters")]
+    ClaimInvalidChars(String),
+}
+
+/// Validates the common length constraints of a `repository` value.
+///
+/// Providers are expected to call this from their
+/// [`Provider::validate_repository()`] implementation before applying any
+/// provider-specific checks.
+pub fn validate_repository_length(repository: &str) -> Result<(), ValidationError> {
+    if repository.is_empty() {
+        Err(ValidationError::RepositoryEmpty)
+    } else if repository.len() > MAX_FIELD_LENGTH {
+        Err(ValidationError::RepositoryTooLong)
+    } else {
+        Ok(())
+    }
+}
+
+/// Validates the additional claim restrictions of a config against the
+/// [`Provider::optional_claims()`] of the given provider.
+pub fn validate_claims(
+    provider: &dyn Provider,
+    claims: &BTreeMap<String, String>,
+) -> Result<(), ValidationError> {
+    for (name, value) in claims {
+        if !provider.optional_claims().contains(&name.as_str()) {
+            return Err(ValidationError::ClaimUnsupported(name.clone()));
+        } else if value.is_empty() {
+            return Err(ValidationError::ClaimEmpty(name.clone()));
+        } else if value.len() > MAX_FIELD_LENGTH {
+            return Err(ValidationError::ClaimTooLong(name.clone()));
+        } else if value.chars().any(|c| c.is_control()) {
+            return Err(ValidationError::ClaimInvalidChars(name.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok, assert_some};
+    use insta::{assert_compact_debug_snapshot, assert_snapshot};
+
+    fn claims() -> OidcClaims {
+        OidcClaims {
+            jti: "jti".into(),
+            exp: Utc::now(),
+            repository: "acme-inc/my-pipeline".into(),
+            repository_owner_id: None,
+            run_id: "42".into(),
+            sha: "abc".into(),
+            claims: BTreeMap::from([
+                ("build_branch".into(), "main".into()),
+                ("step_key".into(), "publish".into()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_provider_lookup() {
+        let provider = assert_some!(provider_by_id("buildkite"));
+        assert_eq!(provider.issuer_url(), "https://agent.buildkite.com");
+
+        let provider = assert_some!(provider_by_issuer("https://agent.buildkite.com"));
+        assert_eq!(provider.id(), "buildkite");
+
+        assert!(provider_by_id("github").is_none());
+        assert!(provider_by_issuer("https://gitlab.com").is_none());
+    }
+
+    #[test]
+    fn test_mismatched_claims() {
+        let claims = claims();
+
+        let expected = BTreeMap::new();
+        assert_compact_debug_snapshot!(claims.mismatched_claims(&expected), @"[]");
+
+        let expected = BTreeMap::from([("build_branch".into(), "MAIN".into())]);
+        assert_compact_debug_snapshot!(claims.mismatched_claims(&expected), @"[]");
+
+        let expected = BTreeMap::from([
+            ("build_branch".into(), "release".into()),
+            ("step_key".into(), "publish".into()),
+        ]);
+        assert_compact_debug_snapshot!(claims.mismatched_claims(&expected), @r#"[("build_branch", "release")]"#);
+
+        let expected = BTreeMap::from([("unknown".into(), "foo".into())]);
+        assert_compact_debug_snapshot!(claims.mismatched_claims(&expected), @r#"[("unknown", "foo")]"#);
+    }
+
+    #[test]
+    fn test_validate_claims() {
+        let claims = |name: &str, value: &str| BTreeMap::from([(name.into(), value.into())]);
+
+        assert_ok!(validate_claims(&Buildkite, &BTreeMap::new()));
+        assert_ok!(validate_claims(&Buildkite, &claims("step_key", "publish")));
+
+        assert_snapshot!(assert_err!(validate_claims(&Buildkite, &claims("foo", "bar"))), @"Unsupported claim `foo`");
+        assert_snapshot!(assert_err!(validate_claims(&Buildkite, &claims("step_key", ""))), @"Value of claim `step_key` may not be empty (omit the claim instead)");
+        assert_snapshot!(assert_err!(validate_claims(&Buildkite, &claims("step_key", &"x".repeat(256)))), @"Value of claim `step_key` is too long (maximum is 255 characters)");
+        assert_snapshot!(assert_err!(validate_claims(&Buildkite, &claims("step_key", "foo\nbar"))), @"Value of claim `step_key` contains invalid characters");
+    }
+}
@@ -0,0 +1,5 @@
+#![doc = include_str!("../README.md")]
+
+pub mod recovery_codes;
+pub mod totp;
+pub mod webauthn;
@@ -0,0 +1,75 @@
+//! Single-use recovery codes, which can be used instead of a second factor
+//! if the user lost access to their authenticator app or security keys.
+//!
+//! Only the SHA-256 hashes of the codes are stored. The codes themselves are
+//! shown to the user once, right after they were generated.
+
+use rand::RngExt;
+use sha2::{Digest, Sha256};
+
+/// Number of recovery codes that are generated at once.
+pub const COUNT: usize = 10;
+
+/// Characters that recovery codes consist of. Similar looking characters
+/// like `0`/`o` and `1`/`l` are left out.
+const ALPHABET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+
+/// Number of characters on each side of the dash.
+const HALF_LEN: usize = 5;
+
+/// Generates a new set of random recovery codes in the format `xxxxx-xxxxx`.
+pub fn generate() -> Vec<String> {
+    let mut rng = rand::rng();
+    let mut random_chars = |len| {
+        (0..len)
+            .map(|_| ALPHABET[rng.random_range(0..ALPHABET.len())] as char)
+            .collect::<String>()
+    };
+
+    (0..COUNT)
+        .map(|_| format!("{}-{}", random_chars(HALF_LEN), random_chars(HALF_LEN)))
+        .collect()
+}
+
+/// Hashes a recovery code for storage or lookup.
+///
+/// The code is normalized before hashing, so that codes are accepted
+/// regardless of case, whitespace and dashes.
+pub fn hash(code: &str) -> Vec<u8> {
+    let normalized = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect::<String>();
+
+    Sha256::digest(normalized.as_bytes()).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn generated_codes() {
+        let codes = generate();
+        assert_eq!(codes.len(), COUNT);
+        assert_eq!(codes.iter().collect::<HashSet<_>>().len(), COUNT);
+
+        for code in &codes {
+            let (first, second) = code.split_once('-').unwrap();
+            assert_eq!(first.len(), HALF_LEN);
+            assert_eq!(second.len(), HALF_LEN);
+            assert!(code.bytes().all(|b| b == b'-' || ALPHABET.contains(&b)));
+        }
+    }
+
+    #[test]
+    fn hash_normalizes_codes() {
+        let expected = hash("abcde-fghij");
+        assert_eq!(hash("ABCDE-FGHIJ"), expected);
+        assert_eq!(hash(" abcde fghij "), expected);
+        assert_eq!(hash("abcdefghij"), expected);
+        assert_ne!(hash("abcde-fghik"), expected);
+    }
+}
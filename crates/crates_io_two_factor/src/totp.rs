@@ -0,0 +1,235 @@
+//! Time-based one-time passwords as specified in
+//! [RFC 6238](https://datatracker.ietf.org/doc/html/rfc6238).
+//!
+//! The parameters are the defaults that all common authenticator apps
+//! support: HMAC-SHA1, six digits and a time step of 30 seconds.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use subtle::ConstantTimeEq;
+use url::Url;
+
+/// Number of digits of the generated codes.
+const DIGITS: u32 = 6;
+
+/// Duration of a time step in seconds.
+const STEP: i64 = 30;
+
+/// Length of generated secrets in bytes, as recommended by
+/// [RFC 4226](https://datatracker.ietf.org/doc/html/rfc4226#section-4).
+const SECRET_LEN: usize = 20;
+
+/// Number of time steps before and after the current one for which codes
+/// are still accepted, to account for clock drift and slow typing.
+const SKEW: i64 = 1;
+
+/// The shared secret of a TOTP credential.
+#[derive(Clone)]
+pub struct Secret(Vec<u8>);
+
+impl Secret {
+    /// Generates a new random secret.
+    pub fn generate() -> Self {
+        Self(rand::random::<[u8; SECRET_LEN]>().to_vec())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the unpadded base32 encoding of the secret, which users can
+    /// enter manually into their authenticator app.
+    pub fn to_base32(&self) -> String {
+        base32_encode(&self.0)
+    }
+
+    /// Returns the `otpauth://` URI of this secret, which is usually
+    /// displayed as a QR code to set up authenticator apps.
+    ///
+    /// See <https://github.com/google/google-authenticator/wiki/Key-Uri-Format>.
+    pub fn provisioning_uri(&self, issuer: &str, account_name: &str) -> String {
+        let mut url = Url::parse("otpauth://totp/").expect("valid base URL");
+        url.set_path(&format!("{issuer}:{account_name}"));
+        url.query_pairs_mut()
+            .append_pair("secret", &self.to_base32())
+            .append_pair("issuer", issuer);
+        url.into()
+    }
+
+    /// Generates the code for the given time step.
+    pub fn code_at(&self, step: i64) -> String {
+        let code = hotp(&self.0, step as u64);
+        format!("{code:0width$}", width = DIGITS as usize)
+    }
+
+    /// Verifies a code that was entered at the given time.
+    ///
+    /// Codes of time steps up to and including `last_used_step` are rejected,
+    /// so that every code can only be used once.
+    ///
+    /// Returns the time step of the code if it is valid.
+    pub fn verify(
+        &self,
+        code: &str,
+        time: DateTime<Utc>,
+        last_used_step: Option<i64>,
+    ) -> Option<i64> {
+        let code = code
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+
+        if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let current_step = time_step(time);
+        (current_step - SKEW..=current_step + SKEW)
+            .filter(|step| last_used_step.is_none_or(|last_used_step| *step > last_used_step))
+            .find(|step| bool::from(self.code_at(*step).as_bytes().ct_eq(code.as_bytes())))
+    }
+}
+
+/// Returns the time step of the given time.
+pub fn time_step(time: DateTime<Utc>) -> i64 {
+    time.timestamp().div_euclid(STEP)
+}
+
+/// Calculates an HMAC-based one-time password as specified in
+/// [RFC 4226](https://datatracker.ietf.org/doc/html/rfc4226#section-5.3).
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+    let bytes = [
+        hash[offset],
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ];
+    let truncated = u32::from_be_bytes(bytes) & 0x7fff_ffff;
+
+    truncated % 10u32.pow(DIGITS)
+}
+
+/// Encodes the given bytes with the base32 alphabet of
+/// [RFC 4648](https://datatracker.ietf.org/doc/html/rfc4648#section-6),
+/// without padding.
+fn base32_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut output = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | u16::from(byte);
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            output.push(ALPHABET[usize::from((buffer >> bits) & 0x1f)] as char);
+        }
+    }
+
+    if bits > 0 {
+        output.push(ALPHABET[usize::from((buffer << (5 - bits)) & 0x1f)] as char);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_none, assert_some_eq};
+
+    /// The secret of the test vectors in RFC 6238.
+    fn rfc_secret() -> Secret {
+        Secret::from_bytes(b"12345678901234567890".to_vec())
+    }
+
+    fn time(timestamp: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(timestamp, 0).unwrap()
+    }
+
+    #[test]
+    fn rfc_6238_test_vectors() {
+        // The RFC uses eight digits, so only the last six are compared here.
+        let secret = rfc_secret();
+        assert_eq!(secret.code_at(time_step(time(59))), "287082");
+        assert_eq!(secret.code_at(time_step(time(1111111109))), "081804");
+        assert_eq!(secret.code_at(time_step(time(1111111111))), "050471");
+        assert_eq!(secret.code_at(time_step(time(1234567890))), "005924");
+        assert_eq!(secret.code_at(time_step(time(2000000000))), "279037");
+        assert_eq!(secret.code_at(time_step(time(20000000000))), "353130");
+    }
+
+    #[test]
+    fn verify() {
+        let secret = rfc_secret();
+        let now = time(1111111111);
+        let step = time_step(now);
+
+        assert_some_eq!(secret.verify("050471", now, None), step);
+        assert_some_eq!(secret.verify("050 471", now, None), step);
+        assert_none!(secret.verify("050472", now, None));
+        assert_none!(secret.verify("50471", now, None));
+        assert_none!(secret.verify("", now, None));
+
+        // Codes of the previous and next time step are accepted too
+        assert_some_eq!(
+            secret.verify(&secret.code_at(step - 1), now, None),
+            step - 1
+        );
+        assert_some_eq!(
+            secret.verify(&secret.code_at(step + 1), now, None),
+            step + 1
+        );
+        assert_none!(secret.verify(&secret.code_at(step - 2), now, None));
+        assert_none!(secret.verify(&secret.code_at(step + 2), now, None));
+    }
+
+    #[test]
+    fn verify_rejects_reused_codes() {
+        let secret = rfc_secret();
+        let now = time(1111111111);
+        let step = time_step(now);
+
+        assert_none!(secret.verify("050471", now, Some(step)));
+        assert_none!(secret.verify(&secret.code_at(step - 1), now, Some(step - 1)));
+        assert_some_eq!(secret.verify("050471", now, Some(step - 1)), step);
+    }
+
+    #[test]
+    fn generated_secrets_are_random() {
+        let secret = Secret::generate();
+        assert_eq!(secret.as_bytes().len(), SECRET_LEN);
+        assert_ne!(secret.as_bytes(), Secret::generate().as_bytes());
+    }
+
+    #[test]
+    fn base32() {
+        // Test vectors of RFC 4648, without padding
+        assert_eq!(base32_encode(b""), "");
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"fo"), "MZXQ");
+        assert_eq!(base32_encode(b"foo"), "MZXW6");
+        assert_eq!(base32_encode(b"foob"), "MZXW6YQ");
+        assert_eq!(base32_encode(b"fooba"), "MZXW6YTB");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+    }
+
+    #[test]
+    fn provisioning_uri() {
+        let uri = rfc_secret().provisioning_uri("crates.io", "j doe");
+        insta::assert_snapshot!(uri, @"otpauth://totp/crates.io:j%20doe?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=crates.io");
+    }
+}
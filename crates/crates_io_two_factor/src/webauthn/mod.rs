@@ -0,0 +1,550 @@
+//! Verification of [WebAuthn](https://www.w3.org/TR/webauthn-3/) responses
+//! from security keys and passkeys.
+//!
+//! Only the parts of the specification that crates.io needs are implemented:
+//!
+//! - Credentials must use the ES256 algorithm (ECDSA with P-256 and SHA-256),
+//!   which all FIDO2 authenticators and passkey providers support.
+//! - Registrations request the `none` attestation conveyance, so attestation
+//!   statements are not verified and all authenticators are accepted.
+
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod test_helpers;
+
+use base64::Engine;
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use ciborium::Value;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// The COSE algorithm identifier of ES256.
+///
+/// See <https://www.iana.org/assignments/cose/cose.xhtml#algorithms>.
+pub const ES256: i64 = -7;
+
+/// Length of generated challenges in bytes.
+const CHALLENGE_LEN: usize = 32;
+
+/// The "user present" flag of the authenticator data.
+const FLAG_USER_PRESENT: u8 = 0x01;
+
+/// The "attested credential data included" flag of the authenticator data.
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("invalid client data: {0}")]
+    InvalidClientData(String),
+    #[error("unexpected client data type `{0}`")]
+    UnexpectedType(String),
+    #[error("challenge does not match")]
+    ChallengeMismatch,
+    #[error("origin `{0}` is not allowed")]
+    OriginMismatch(String),
+    #[error("invalid attestation object")]
+    InvalidAttestationObject,
+    #[error("invalid authenticator data")]
+    InvalidAuthenticatorData,
+    #[error("relying party ID does not match")]
+    RpIdMismatch,
+    #[error("user presence was not confirmed")]
+    UserNotPresent,
+    #[error("unsupported credential public key, only ES256 is supported")]
+    UnsupportedPublicKey,
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("signature counter did not increase, the authenticator may have been cloned")]
+    CounterRegression,
+}
+
+/// The relying party, i.e. the website that credentials are scoped to.
+#[derive(Debug, Clone, Copy)]
+pub struct RelyingParty<'a> {
+    /// The relying party ID, which is the domain name of the website.
+    pub id: &'a str,
+    /// The origins that responses are accepted from.
+    pub origins: &'a [String],
+}
+
+/// Generates a random challenge for a registration or authentication
+/// ceremony.
+pub fn generate_challenge() -> Vec<u8> {
+    rand::random::<[u8; CHALLENGE_LEN]>().to_vec()
+}
+
+/// A new credential, as returned by a successful registration ceremony.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewCredential {
+    /// The ID that the authenticator assigned to the credential.
+    pub id: Vec<u8>,
+    /// The public key of the credential, in uncompressed SEC1 encoding.
+    pub public_key: Vec<u8>,
+    /// The initial value of the signature counter.
+    pub sign_count: u32,
+}
+
+/// Verifies the response of a registration ceremony, i.e. the result of
+/// `navigator.credentials.create()`.
+///
+/// See <https://www.w3.org/TR/webauthn-3/#sctn-registering-a-new-credential>.
+pub fn verify_registration(
+    rp: RelyingParty<'_>,
+    challenge: &[u8],
+    client_data_json: &[u8],
+    attestation_object: &[u8],
+) -> Result<NewCredential, Error> {
+    verify_client_data(rp, "webauthn.create", challenge, client_data_json)?;
+
+    let auth_data = parse_attestation_object(attestation_object)?;
+    let auth_data = AuthenticatorData::parse(&auth_data)?;
+    auth_data.verify(rp)?;
+
+    let (id, public_key) = auth_data
+        .attested_credential
+        .ok_or(Error::InvalidAuthenticatorData)?;
+
+    Ok(NewCredential {
+        id,
+        public_key,
+        sign_count: auth_data.sign_count,
+    })
+}
+
+/// Verifies the response of an authentication ceremony, i.e. the result of
+/// `navigator.credentials.get()`, against a stored credential.
+///
+/// Returns the new value of the signature counter.
+///
+/// See <https://www.w3.org/TR/webauthn-3/#sctn-verifying-assertion>.
+pub fn verify_authentication(
+    rp: RelyingParty<'_>,
+    challenge: &[u8],
+    public_key: &[u8],
+    sign_count: u32,
+    client_data_json: &[u8],
+    authenticator_data: &[u8],
+    signature: &[u8],
+) -> Result<u32, Error> {
+    verify_client_data(rp, "webauthn.get", challenge, client_data_json)?;
+
+    let auth_data = AuthenticatorData::parse(authenticator_data)?;
+    auth_data.verify(rp)?;
+
+    let key = VerifyingKey::from_sec1_bytes(public_key).map_err(|_| Error::UnsupportedPublicKey)?;
+    let signature = Signature::from_der(signature).map_err(|_| Error::InvalidSignature)?;
+
+    let mut signed_data = authenticator_data.to_vec();
+    signed_data.extend_from_slice(&Sha256::digest(client_data_json));
+    key.verify(&signed_data, &signature)
+        .map_err(|_| Error::InvalidSignature)?;
+
+    // Authenticators that don't implement a signature counter always
+    // return zero.
+    if (auth_data.sign_count != 0 || sign_count != 0) && auth_data.sign_count <= sign_count {
+        return Err(Error::CounterRegression);
+    }
+
+    Ok(auth_data.sign_count)
+}
+
+/// The fields of the `CollectedClientData` that are verified.
+///
+/// See <https://www.w3.org/TR/webauthn-3/#dictionary-client-data>.
+#[derive(Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    ty: String,
+    challenge: String,
+    origin: String,
+}
+
+fn verify_client_data(
+    rp: RelyingParty<'_>,
+    expected_type: &str,
+    challenge: &[u8],
+    client_data_json: &[u8],
+) -> Result<(), Error> {
+    let client_data: ClientData = serde_json::from_slice(client_data_json)
+        .map_err(|error| Error::InvalidClientData(error.to_string()))?;
+
+    if client_data.ty != expected_type {
+        return Err(Error::UnexpectedType(client_data.ty));
+    }
+
+    let client_challenge = BASE64_URL_SAFE_NO_PAD
+        .decode(&client_data.challenge)
+        .map_err(|_| Error::ChallengeMismatch)?;
+    if client_challenge != challenge {
+        return Err(Error::ChallengeMismatch);
+    }
+
+    if !rp.origins.contains(&client_data.origin) {
+        return Err(Error::OriginMismatch(client_data.origin));
+    }
+
+    Ok(())
+}
+
+/// Extracts the authenticator data from an attestation object.
+///
+/// See <https://www.w3.org/TR/webauthn-3/#sctn-attestation>.
+fn parse_attestation_object(attestation_object: &[u8]) -> Result<Vec<u8>, Error> {
+    let value: Value =
+        ciborium::from_reader(attestation_object).map_err(|_| Error::InvalidAttestationObject)?;
+
+    let map = value
+        .into_map()
+        .map_err(|_| Error::InvalidAttestationObject)?;
+    map.into_iter()
+        .find(|(key, _)| key.as_text() == Some("authData"))
+        .and_then(|(_, value)| value.into_bytes().ok())
+        .ok_or(Error::InvalidAttestationObject)
+}
+
+/// The parsed authenticator data.
+///
+/// See <https://www.w3.org/TR/webauthn-3/#sctn-authenticator-data>.
+struct AuthenticatorData {
+    rp_id_hash: [u8; 32],
+    flags: u8,
+    sign_count: u32,
+    /// The ID and public key of the credential, if included.
+    attested_credential: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl AuthenticatorData {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        let (rp_id_hash, rest) = data
+            .split_first_chunk::<32>()
+            .ok_or(Error::InvalidAuthenticatorData)?;
+        let (&flags, rest) = rest.split_first().ok_or(Error::InvalidAuthenticatorData)?;
+        let (sign_count, rest) = rest
+            .split_first_chunk::<4>()
+            .ok_or(Error::InvalidAuthenticatorData)?;
+
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+            Some(parse_attested_credential_data(rest)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rp_id_hash: *rp_id_hash,
+            flags,
+            sign_count: u32::from_be_bytes(*sign_count),
+            attested_credential,
+        })
+    }
+
+    fn verify(&self, rp: RelyingParty<'_>) -> Result<(), Error> {
+        if self.rp_id_hash[..] != Sha256::digest(rp.id.as_bytes())[..] {
+            return Err(Error::RpIdMismatch);
+        }
+
+        if self.flags & FLAG_USER_PRESENT == 0 {
+            return Err(Error::UserNotPresent);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the attested credential data and returns the credential ID and
+/// its public key in uncompressed SEC1 encoding.
+///
+/// See <https://www.w3.org/TR/webauthn-3/#sctn-attested-credential-data>.
+fn parse_attested_credential_data(data: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    // The AAGUID of the authenticator is not used, since attestation
+    // statements are not verified.
+    let (_aaguid, rest) = data
+        .split_first_chunk::<16>()
+        .ok_or(Error::InvalidAuthenticatorData)?;
+    let (id_len, rest) = rest
+        .split_first_chunk::<2>()
+        .ok_or(Error::InvalidAuthenticatorData)?;
+
+    let id_len = usize::from(u16::from_be_bytes(*id_len));
+    let (id, mut rest) = rest
+        .split_at_checked(id_len)
+        .ok_or(Error::InvalidAuthenticatorData)?;
+
+    // The public key may be followed by extension data, so only a single
+    // CBOR value is read from the remaining data.
+    let public_key: Value =
+        ciborium::from_reader(&mut rest).map_err(|_| Error::InvalidAuthenticatorData)?;
+
+    Ok((id.to_vec(), parse_cose_key(public_key)?))
+}
+
+/// Converts an ES256 public key in COSE format to uncompressed SEC1
+/// encoding.
+///
+/// See <https://www.rfc-editor.org/rfc/rfc9053.html#section-7.1.1>.
+fn parse_cose_key(key: Value) -> Result<Vec<u8>, Error> {
+    const KTY: i64 = 1;
+    const ALG: i64 = 3;
+    const CRV: i64 = -1;
+    const X: i64 = -2;
+    const Y: i64 = -3;
+
+    const KTY_EC2: i64 = 2;
+    const CRV_P256: i64 = 1;
+
+    let map = key.into_map().map_err(|_| Error::UnsupportedPublicKey)?;
+    let param = |label: i64| {
+        map.iter()
+            .find(|(key, _)| key.as_integer() == Some(label.into()))
+            .map(|(_, value)| value)
+    };
+    let integer_param = |label| {
+        param(label)
+            .and_then(Value::as_integer)
+            .and_then(|value| i64::try_from(value).ok())
+    };
+    let coordinate = |label| {
+        param(label)
+            .and_then(Value::as_bytes)
+            .filter(|bytes| bytes.len() == 32)
+    };
+
+    if integer_param(KTY) != Some(KTY_EC2)
+        || integer_param(ALG) != Some(ES256)
+        || integer_param(CRV) != Some(CRV_P256)
+    {
+        return Err(Error::UnsupportedPublicKey);
+    }
+
+    let (Some(x), Some(y)) = (coordinate(X), coordinate(Y)) else {
+        return Err(Error::UnsupportedPublicKey);
+    };
+
+    let mut public_key = Vec::with_capacity(65);
+    public_key.push(0x04);
+    public_key.extend_from_slice(x);
+    public_key.extend_from_slice(y);
+
+    // Make sure that the point is actually on the curve
+    VerifyingKey::from_sec1_bytes(&public_key).map_err(|_| Error::UnsupportedPublicKey)?;
+
+    Ok(public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::SoftwareAuthenticator;
+    use super::*;
+    use claims::{assert_err_eq, assert_ok, assert_ok_eq};
+
+    const RP_ID: &str = "crates.io";
+    const ORIGIN: &str = "https://crates.io";
+
+    fn rp(origins: &[String]) -> RelyingParty<'_> {
+        RelyingParty { id: RP_ID, origins }
+    }
+
+    fn origins() -> Vec<String> {
+        vec![ORIGIN.to_string()]
+    }
+
+    #[test]
+    fn registration() {
+        let authenticator = SoftwareAuthenticator::new(b"credential-id");
+        let challenge = generate_challenge();
+        let response = authenticator.register(RP_ID, ORIGIN, &challenge);
+
+        let origins = origins();
+        let credential = assert_ok!(verify_registration(
+            rp(&origins),
+            &challenge,
+            &response.client_data_json,
+            &response.attestation_object,
+        ));
+
+        assert_eq!(credential.id, b"credential-id");
+        assert_eq!(credential.public_key, authenticator.public_key());
+        assert_eq!(credential.sign_count, 0);
+    }
+
+    #[test]
+    fn registration_with_invalid_responses() {
+        let authenticator = SoftwareAuthenticator::new(b"credential-id");
+        let challenge = generate_challenge();
+        let origins = origins();
+
+        let response = authenticator.register(RP_ID, ORIGIN, &challenge);
+        let result = verify_registration(
+            rp(&origins),
+            &generate_challenge(),
+            &response.client_data_json,
+            &response.attestation_object,
+        );
+        assert_err_eq!(result, Error::ChallengeMismatch);
+
+        let response = authenticator.register(RP_ID, "https://evil.example", &challenge);
+        let result = verify_registration(
+            rp(&origins),
+            &challenge,
+            &response.client_data_json,
+            &response.attestation_object,
+        );
+        assert_err_eq!(result, Error::OriginMismatch("https://evil.example".into()));
+
+        let response = authenticator.register("evil.example", ORIGIN, &challenge);
+        let result = verify_registration(
+            rp(&origins),
+            &challenge,
+            &response.client_data_json,
+            &response.attestation_object,
+        );
+        assert_err_eq!(result, Error::RpIdMismatch);
+
+        let response = authenticator.register(RP_ID, ORIGIN, &challenge);
+        let result = verify_registration(
+            rp(&origins),
+            &challenge,
+            &response.client_data_json,
+            b"invalid",
+        );
+        assert_err_eq!(result, Error::InvalidAttestationObject);
+
+        // An authentication response can't be used for registration
+        let mut authenticator = authenticator;
+        let response = authenticator.authenticate(RP_ID, ORIGIN, &challenge);
+        let result = verify_registration(
+            rp(&origins),
+            &challenge,
+            &response.client_data_json,
+            &response.authenticator_data,
+        );
+        assert_err_eq!(result, Error::UnexpectedType("webauthn.get".into()));
+    }
+
+    #[test]
+    fn authentication() {
+        let mut authenticator = SoftwareAuthenticator::new(b"credential-id");
+        let public_key = authenticator.public_key();
+        let challenge = generate_challenge();
+        let origins = origins();
+
+        let response = authenticator.authenticate(RP_ID, ORIGIN, &challenge);
+        let result = verify_authentication(
+            rp(&origins),
+            &challenge,
+            &public_key,
+            0,
+            &response.client_data_json,
+            &response.authenticator_data,
+            &response.signature,
+        );
+        assert_ok_eq!(result, 1);
+
+        // The same response can't be used again
+        let result = verify_authentication(
+            rp(&origins),
+            &challenge,
+            &public_key,
+            1,
+            &response.client_data_json,
+            &response.authenticator_data,
+            &response.signature,
+        );
+        assert_err_eq!(result, Error::CounterRegression);
+
+        // Signatures of other keys are rejected
+        let other_key = SoftwareAuthenticator::new(b"other").public_key();
+        let result = verify_authentication(
+            rp(&origins),
+            &challenge,
+            &other_key,
+            0,
+            &response.client_data_json,
+            &response.authenticator_data,
+            &response.signature,
+        );
+        assert_err_eq!(result, Error::InvalidSignature);
+
+        let response = authenticator.authenticate(RP_ID, ORIGIN, &challenge);
+        let result = verify_authentication(
+            rp(&origins),
+            &generate_challenge(),
+            &public_key,
+            1,
+            &response.client_data_json,
+            &response.authenticator_data,
+            &response.signature,
+        );
+        assert_err_eq!(result, Error::ChallengeMismatch);
+    }
+
+    #[test]
+    fn authentication_without_signature_counter() {
+        let mut authenticator = SoftwareAuthenticator::new(b"credential-id").without_counter();
+        let public_key = authenticator.public_key();
+        let challenge = generate_challenge();
+        let origins = origins();
+
+        for _ in 0..2 {
+            let response = authenticator.authenticate(RP_ID, ORIGIN, &challenge);
+            let result = verify_authentication(
+                rp(&origins),
+                &challenge,
+                &public_key,
+                0,
+                &response.client_data_json,
+                &response.authenticator_data,
+                &response.signature,
+            );
+            assert_ok_eq!(result, 0);
+        }
+    }
+
+    #[test]
+    fn unsupported_public_keys() {
+        let key = |entries: Vec<(i64, Value)>| {
+            let entries = entries
+                .into_iter()
+                .map(|(label, value)| (Value::from(label), value))
+                .collect();
+            Value::Map(entries)
+        };
+
+        let authenticator = SoftwareAuthenticator::new(b"credential-id");
+        let public_key = authenticator.public_key();
+        let x = Value::Bytes(public_key[1..33].to_vec());
+        let y = Value::Bytes(public_key[33..].to_vec());
+
+        let valid = key(vec![
+            (1, 2.into()),
+            (3, ES256.into()),
+            (-1, 1.into()),
+            (-2, x.clone()),
+            (-3, y.clone()),
+        ]);
+        assert_ok_eq!(parse_cose_key(valid), public_key);
+
+        // RS256
+        let rsa = key(vec![(1, 3.into()), (3, (-257).into())]);
+        assert_err_eq!(parse_cose_key(rsa), Error::UnsupportedPublicKey);
+
+        // Ed25519
+        let okp = key(vec![
+            (1, 1.into()),
+            (3, (-8).into()),
+            (-1, 6.into()),
+            (-2, x.clone()),
+        ]);
+        assert_err_eq!(parse_cose_key(okp), Error::UnsupportedPublicKey);
+
+        // A point that is not on the curve
+        let invalid_point = key(vec![
+            (1, 2.into()),
+            (3, ES256.into()),
+            (-1, 1.into()),
+            (-2, x),
+            (-3, Value::Bytes(vec![0; 32])),
+        ]);
+        assert_err_eq!(parse_cose_key(invalid_point), Error::UnsupportedPublicKey);
+    }
+}
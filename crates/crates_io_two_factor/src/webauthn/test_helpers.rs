@@ -0,0 +1,151 @@
+//! A software implementation of a WebAuthn authenticator, which creates the
+//! responses that browsers would return for a security key.
+
+use super::{ES256, FLAG_ATTESTED_CREDENTIAL_DATA, FLAG_USER_PRESENT};
+use base64::Engine;
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use ciborium::Value;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use sha2::{Digest, Sha256};
+
+/// The response of `navigator.credentials.create()`.
+pub struct RegistrationResponse {
+    pub client_data_json: Vec<u8>,
+    pub attestation_object: Vec<u8>,
+}
+
+/// The response of `navigator.credentials.get()`.
+pub struct AuthenticationResponse {
+    pub client_data_json: Vec<u8>,
+    pub authenticator_data: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+pub struct SoftwareAuthenticator {
+    credential_id: Vec<u8>,
+    signing_key: SigningKey,
+    /// The signature counter, or `None` if the authenticator does not
+    /// implement one.
+    sign_count: Option<u32>,
+}
+
+impl SoftwareAuthenticator {
+    /// Creates an authenticator with a single credential.
+    ///
+    /// The key pair is derived from the credential ID, so that the same ID
+    /// always results in the same public key.
+    pub fn new(credential_id: &[u8]) -> Self {
+        let seed = Sha256::digest(credential_id);
+        let signing_key = SigningKey::from_slice(&seed).expect("valid private key");
+
+        Self {
+            credential_id: credential_id.to_vec(),
+            signing_key,
+            sign_count: Some(0),
+        }
+    }
+
+    /// Disables the signature counter, like many passkey providers do.
+    pub fn without_counter(self) -> Self {
+        Self {
+            sign_count: None,
+            ..self
+        }
+    }
+
+    pub fn credential_id(&self) -> &[u8] {
+        &self.credential_id
+    }
+
+    /// Returns the public key of the credential in uncompressed SEC1
+    /// encoding.
+    pub fn public_key(&self) -> Vec<u8> {
+        let point = self.signing_key.verifying_key().to_sec1_point(false);
+        point.as_bytes().to_vec()
+    }
+
+    pub fn register(&self, rp_id: &str, origin: &str, challenge: &[u8]) -> RegistrationResponse {
+        let client_data_json = client_data_json("webauthn.create", origin, challenge);
+
+        let mut auth_data = self.authenticator_data(rp_id, FLAG_ATTESTED_CREDENTIAL_DATA, 0);
+        auth_data.extend_from_slice(&[0; 16]);
+        auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+        auth_data.extend_from_slice(&self.credential_id);
+        ciborium::into_writer(&self.cose_key(), &mut auth_data).expect("valid CBOR");
+
+        let attestation_object = Value::Map(vec![
+            ("fmt".into(), "none".into()),
+            ("attStmt".into(), Value::Map(vec![])),
+            ("authData".into(), Value::Bytes(auth_data)),
+        ]);
+
+        let mut attestation_object_bytes = Vec::new();
+        ciborium::into_writer(&attestation_object, &mut attestation_object_bytes)
+            .expect("valid CBOR");
+
+        RegistrationResponse {
+            client_data_json,
+            attestation_object: attestation_object_bytes,
+        }
+    }
+
+    pub fn authenticate(
+        &mut self,
+        rp_id: &str,
+        origin: &str,
+        challenge: &[u8],
+    ) -> AuthenticationResponse {
+        let client_data_json = client_data_json("webauthn.get", origin, challenge);
+
+        let sign_count = match &mut self.sign_count {
+            Some(sign_count) => {
+                *sign_count += 1;
+                *sign_count
+            }
+            None => 0,
+        };
+        let authenticator_data = self.authenticator_data(rp_id, 0, sign_count);
+
+        let mut signed_data = authenticator_data.clone();
+        signed_data.extend_from_slice(&Sha256::digest(&client_data_json));
+        let signature: Signature = self.signing_key.sign(&signed_data);
+
+        AuthenticationResponse {
+            client_data_json,
+            authenticator_data,
+            signature: signature.to_der().as_bytes().to_vec(),
+        }
+    }
+
+    fn authenticator_data(&self, rp_id: &str, flags: u8, sign_count: u32) -> Vec<u8> {
+        let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(FLAG_USER_PRESENT | flags);
+        data.extend_from_slice(&sign_count.to_be_bytes());
+        data
+    }
+
+    fn cose_key(&self) -> Value {
+        let public_key = self.public_key();
+        let (x, y) = public_key[1..].split_at(32);
+
+        Value::Map(vec![
+            (1.into(), 2.into()),
+            (3.into(), ES256.into()),
+            ((-1).into(), 1.into()),
+            ((-2).into(), Value::Bytes(x.to_vec())),
+            ((-3).into(), Value::Bytes(y.to_vec())),
+        ])
+    }
+}
+
+fn client_data_json(ty: &str, origin: &str, challenge: &[u8]) -> Vec<u8> {
+    let client_data = serde_json::json!({
+        "type": ty,
+        "challenge": BASE64_URL_SAFE_NO_PAD.encode(challenge),
+        "origin": origin,
+        "crossOrigin": false,
+    });
+
+    serde_json::to_vec(&client_data).expect("valid JSON")
+}
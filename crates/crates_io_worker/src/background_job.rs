@@ -1,13 +1,16 @@
+use crate::Backoff;
 use crate::errors::EnqueueError;
 use crate::schema::background_jobs;
-use diesel::dsl::{exists, not};
-use diesel::sql_types::{Int2, Jsonb, Text};
-use diesel::{ExpressionMethods, IntoSql, OptionalExtension, QueryDsl};
+use crate::util::to_interval;
+use diesel::dsl::{exists, not, now};
+use diesel::sql_types::{Int2, Interval, Jsonb, Nullable, Text, Timestamptz};
+use diesel::{ExpressionMethods, IntoSql, NullableExpressionMethods, OptionalExtension, QueryDsl};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::future::Future;
+use std::time::Duration;
 use tracing::instrument;
 
 pub const DEFAULT_QUEUE: &str = "default";
@@ -30,6 +33,16 @@ pub trait BackgroundJob: Serialize + DeserializeOwned + Send + Sync + 'static {
     /// Job queue where this job will be executed.
     const QUEUE: &'static str = DEFAULT_QUEUE;
 
+    /// Maximum number of times the job is retried after it failed.
+    ///
+    /// Once a job has failed and exhausted its retries, it is moved to the
+    /// `background_jobs_dead` table and no longer attempted. If `None`, the
+    /// job is retried indefinitely.
+    const MAX_RETRIES: Option<u32> = None;
+
+    /// How long to wait before retrying the job after it failed.
+    const BACKOFF: Backoff = Backoff::DEFAULT;
+
     /// The application data provided to this job at runtime.
     type Context: Clone + Send + 'static;
 
@@ -41,27 +54,50 @@ pub trait BackgroundJob: Serialize + DeserializeOwned + Send + Sync + 'static {
         &'a self,
         conn: &'a AsyncPgConnection,
     ) -> impl Future<Output = Result<Option<i64>, EnqueueError>> + Send + 'a {
-        async move {
-            let data = serde_json::to_value(self).map_err(EnqueueError::SerializationError)?;
-            let priority = Self::PRIORITY;
-
-            if Self::DEDUPLICATED {
-                enqueue_deduplicated(conn, Self::JOB_NAME, data, priority).await
-            } else {
-                Ok(Some(
-                    enqueue_simple(conn, Self::JOB_NAME, data, priority).await?,
-                ))
-            }
-        }
+        enqueue_job(self, conn, None)
+    }
+
+    /// Enqueues the job to be run once the given `delay` has passed.
+    ///
+    /// Deduplicated jobs are deduplicated against all unstarted jobs with
+    /// the same data, regardless of when they are scheduled to run.
+    #[instrument(name = "swirl.enqueue", skip(self, conn), fields(message = Self::JOB_NAME))]
+    fn enqueue_with_delay<'a>(
+        &'a self,
+        conn: &'a AsyncPgConnection,
+        delay: Duration,
+    ) -> impl Future<Output = Result<Option<i64>, EnqueueError>> + Send + 'a {
+        enqueue_job(self, conn, Some(delay))
     }
 }
 
-async fn enqueue_deduplicated(
+async fn enqueue_job<J: BackgroundJob>(
+    job: &J,
+    conn: &AsyncPgConnection,
+    delay: Option<Duration>,
+) -> Result<Option<i64>, EnqueueError> {
+    let data = serde_json::to_value(job).map_err(EnqueueError::SerializationError)?;
+    let priority = J::PRIORITY;
+
+    if J::DEDUPLICATED {
+        enqueue_deduplicated(conn, J::JOB_NAME, data, priority, delay).await
+    } else {
+        Ok(Some(
+            enqueue_simple(conn, J::JOB_NAME, data, priority, delay).await?,
+        ))
+    }
+}
+
+pub(crate) async fn enqueue_deduplicated(
     mut conn: &AsyncPgConnection,
     job_type: &str,
     data: Value,
     priority: i16,
+    delay: Option<Duration>,
 ) -> Result<Option<i64>, EnqueueError> {
+    let run_at = now.into_sql::<Timestamptz>().nullable()
+        + delay.map(to_interval).into_sql::<Nullable<Interval>>();
+
     let similar_jobs = background_jobs::table
         .select(background_jobs::id)
         .filter(background_jobs::job_type.eq(job_type))
@@ -74,6 +110,7 @@ async fn enqueue_deduplicated(
         job_type.into_sql::<Text>(),
         data.into_sql::<Jsonb>(),
         priority.into_sql::<Int2>(),
+        run_at,
     ))
     .filter(not(exists(similar_jobs)));
 
@@ -83,6 +120,7 @@ async fn enqueue_deduplicated(
             background_jobs::job_type,
             background_jobs::data,
             background_jobs::priority,
+            background_jobs::run_at,
         ))
         .returning(background_jobs::id)
         .get_result::<i64>(&mut conn)
@@ -92,17 +130,22 @@ async fn enqueue_deduplicated(
     Ok(id)
 }
 
-async fn enqueue_simple(
+pub(crate) async fn enqueue_simple(
     mut conn: &AsyncPgConnection,
     job_type: &str,
     data: Value,
     priority: i16,
+    delay: Option<Duration>,
 ) -> Result<i64, EnqueueError> {
+    let run_at = now.into_sql::<Timestamptz>().nullable()
+        + delay.map(to_interval).into_sql::<Nullable<Interval>>();
+
     let id = diesel::insert_into(background_jobs::table)
         .values((
             background_jobs::job_type.eq(job_type),
             background_jobs::data.eq(data),
             background_jobs::priority.eq(priority),
+            background_jobs::run_at.eq(run_at),
         ))
         .returning(background_jobs::id)
         .get_result(&mut conn)
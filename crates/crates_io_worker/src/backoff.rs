@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+/// How long a failed job has to wait before it is retried.
+///
+/// The delay is calculated from the number of times the job has already been
+/// retried, and is measured from the time of the last failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backoff {
+    /// Waits the same amount of time before every retry.
+    Fixed(Duration),
+    /// Waits `delay * retries` before the next retry.
+    Linear(Duration),
+    /// Waits `delay * 2^retries` before the next retry.
+    Exponential(Duration),
+}
+
+impl Backoff {
+    /// The default backoff, which waits one minute before the first retry and
+    /// doubles the delay after every failed attempt.
+    pub const DEFAULT: Self = Self::Exponential(Duration::from_secs(60));
+}
@@ -0,0 +1,47 @@
+//! Management of background jobs that have exhausted their retries and were
+//! moved to the `background_jobs_dead` table.
+
+use crate::schema::{background_jobs, background_jobs_dead};
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+/// Moves the given dead jobs back to the `background_jobs` table with a
+/// reset retry counter, so that they are attempted again.
+///
+/// Returns the number of jobs that were requeued.
+pub async fn requeue(conn: &mut AsyncPgConnection, job_ids: &[i64]) -> QueryResult<usize> {
+    conn.transaction(async |conn| {
+        let dead_jobs = background_jobs_dead::table
+            .filter(background_jobs_dead::id.eq_any(job_ids))
+            .select((
+                background_jobs_dead::job_type,
+                background_jobs_dead::data,
+                background_jobs_dead::priority,
+            ));
+
+        let requeued = diesel::insert_into(background_jobs::table)
+            .values(dead_jobs)
+            .into_columns((
+                background_jobs::job_type,
+                background_jobs::data,
+                background_jobs::priority,
+            ))
+            .execute(conn)
+            .await?;
+
+        discard(conn, job_ids).await?;
+
+        Ok(requeued)
+    })
+    .await
+}
+
+/// Deletes the given dead jobs without attempting them again.
+///
+/// Returns the number of jobs that were deleted.
+pub async fn discard(conn: &mut AsyncPgConnection, job_ids: &[i64]) -> QueryResult<usize> {
+    diesel::delete(background_jobs_dead::table)
+        .filter(background_jobs_dead::id.eq_any(job_ids))
+        .execute(conn)
+        .await
+}
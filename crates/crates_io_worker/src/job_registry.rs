@@ -1,4 +1,4 @@
-use crate::BackgroundJob;
+use crate::{BackgroundJob, Backoff};
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
@@ -9,7 +9,23 @@ type RunTaskFn<Context> = dyn Fn(Context, serde_json::Value) -> RunTaskFnReturn
 
 #[derive(Clone)]
 pub struct JobRegistry<Context> {
-    entries: HashMap<String, Arc<RunTaskFn<Context>>>,
+    entries: HashMap<String, Entry<Context>>,
+}
+
+struct Entry<Context> {
+    run_task_fn: Arc<RunTaskFn<Context>>,
+    max_retries: Option<u32>,
+    backoff: Backoff,
+}
+
+impl<Context> Clone for Entry<Context> {
+    fn clone(&self) -> Self {
+        Self {
+            run_task_fn: self.run_task_fn.clone(),
+            max_retries: self.max_retries,
+            backoff: self.backoff,
+        }
+    }
 }
 
 impl<Context> Default for JobRegistry<Context> {
@@ -22,17 +38,36 @@ impl<Context> Default for JobRegistry<Context> {
 
 impl<Context: Clone + Send + Sync + 'static> JobRegistry<Context> {
     pub fn register<J: BackgroundJob<Context = Context>>(&mut self) {
-        self.entries
-            .insert(J::JOB_NAME.to_string(), Arc::new(runnable::<J>));
+        let entry = Entry {
+            run_task_fn: Arc::new(runnable::<J>),
+            max_retries: J::MAX_RETRIES,
+            backoff: J::BACKOFF,
+        };
+
+        self.entries.insert(J::JOB_NAME.to_string(), entry);
     }
 
     pub fn get(&self, key: &str) -> Option<&Arc<RunTaskFn<Context>>> {
-        self.entries.get(key)
+        self.entries.get(key).map(|entry| &entry.run_task_fn)
     }
 
-    /// Returns a list of all registered job types.
-    pub fn job_types(&self) -> Vec<String> {
-        self.entries.keys().cloned().collect()
+    /// Returns the maximum number of retries of a job type, or `None` if
+    /// the job type is retried indefinitely (or unknown).
+    pub fn max_retries(&self, key: &str) -> Option<u32> {
+        self.entries.get(key).and_then(|entry| entry.max_retries)
+    }
+
+    /// Returns the registered job types, grouped by their [`Backoff`].
+    pub fn job_types_by_backoff(&self) -> Vec<(Backoff, Vec<String>)> {
+        let mut groups: HashMap<Backoff, Vec<String>> = HashMap::new();
+        for (job_type, entry) in &self.entries {
+            groups
+                .entry(entry.backoff)
+                .or_default()
+                .push(job_type.clone());
+        }
+
+        groups.into_iter().collect()
     }
 }
 
@@ -48,6 +83,7 @@ mod tests {
     use super::*;
     use crate::BackgroundJob;
     use serde::{Deserialize, Serialize};
+    use std::time::Duration;
 
     #[test]
     fn test_job_types() {
@@ -64,6 +100,57 @@ mod tests {
 
         let mut registry = JobRegistry::default();
         registry.register::<TestJob>();
-        assert_eq!(registry.job_types(), vec!["test"]);
+        assert_eq!(
+            registry.job_types_by_backoff(),
+            vec![(Backoff::DEFAULT, vec!["test".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        #[derive(Serialize, Deserialize)]
+        struct DefaultJob;
+
+        impl BackgroundJob for DefaultJob {
+            const JOB_NAME: &'static str = "default";
+            type Context = ();
+            async fn run(&self, _: Self::Context) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        #[derive(Serialize, Deserialize)]
+        struct LimitedJob;
+
+        impl BackgroundJob for LimitedJob {
+            const JOB_NAME: &'static str = "limited";
+            const MAX_RETRIES: Option<u32> = Some(3);
+            const BACKOFF: Backoff = Backoff::Fixed(Duration::from_secs(10));
+            type Context = ();
+            async fn run(&self, _: Self::Context) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut registry = JobRegistry::default();
+        registry.register::<DefaultJob>();
+        registry.register::<LimitedJob>();
+
+        assert_eq!(registry.max_retries("default"), None);
+        assert_eq!(registry.max_retries("limited"), Some(3));
+        assert_eq!(registry.max_retries("unknown"), None);
+
+        let mut groups = registry.job_types_by_backoff();
+        groups.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            groups,
+            vec![
+                (Backoff::DEFAULT, vec!["default".to_string()]),
+                (
+                    Backoff::Fixed(Duration::from_secs(10)),
+                    vec!["limited".to_string()]
+                ),
+            ]
+        );
     }
 }
@@ -1,15 +1,20 @@
 #![doc = include_str!("../README.md")]
 
 mod background_job;
+mod backoff;
+pub mod dead_jobs;
 mod errors;
 mod job_registry;
 mod listener;
 mod runner;
+mod scheduler;
 pub mod schema;
 mod storage;
 mod util;
 mod worker;
 
 pub use self::background_job::BackgroundJob;
+pub use self::backoff::Backoff;
 pub use self::errors::EnqueueError;
 pub use self::runner::Runner;
+pub use cron::Schedule;
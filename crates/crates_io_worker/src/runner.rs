@@ -1,8 +1,10 @@
 use crate::background_job::DEFAULT_QUEUE;
 use crate::job_registry::JobRegistry;
+use crate::scheduler::{self, ScheduledJob};
 use crate::worker::Worker;
 use crate::{BackgroundJob, listener, storage};
 use anyhow::anyhow;
+use cron::Schedule;
 use diesel_async::AsyncPgConnection;
 use diesel_async::pooled_connection::deadpool::Pool;
 use futures_util::FutureExt;
@@ -17,10 +19,14 @@ use tracing::{Instrument, error, info, info_span, warn};
 
 const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
+/// How often the runner checks whether any scheduled jobs are due.
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+
 /// The core runner responsible for locking and running jobs
 pub struct Runner<Context> {
     connection_pool: Pool<AsyncPgConnection>,
     queues: HashMap<String, Queue<Context>>,
+    scheduled_jobs: Vec<ScheduledJob>,
     context: Context,
     shutdown_when_queue_empty: bool,
 }
@@ -30,6 +36,7 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
         Self {
             connection_pool,
             queues: HashMap::new(),
+            scheduled_jobs: Vec::new(),
             context,
             shutdown_when_queue_empty: false,
         }
@@ -42,6 +49,25 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
         self
     }
 
+    /// Enqueues the given job whenever an occurrence of the cron `schedule`
+    /// has passed.
+    ///
+    /// The job type still needs to be registered via
+    /// [`register_job_type()`](Self::register_job_type) for the job to be
+    /// run by this runner.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job can not be serialized.
+    pub fn schedule_job<J: BackgroundJob<Context = Context>>(
+        mut self,
+        schedule: Schedule,
+        job: J,
+    ) -> Self {
+        self.scheduled_jobs.push(ScheduledJob::new(schedule, &job));
+        self
+    }
+
     /// Adjusts the configuration of the [`DEFAULT_QUEUE`] queue.
     pub fn configure_default_queue<F>(self, f: F) -> Self
     where
@@ -95,6 +121,25 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
             });
         }
 
+        // Periodically enqueue the scheduled jobs that are due. Like the
+        // listener, this is skipped when shutting down on an empty queue.
+        if !self.shutdown_when_queue_empty && !self.scheduled_jobs.is_empty() {
+            let pool = self.connection_pool.clone();
+            let scheduled_jobs = self.scheduled_jobs.clone();
+            tokio::spawn(async move {
+                loop {
+                    match pool.get().await {
+                        Ok(mut conn) => {
+                            scheduler::enqueue_scheduled_jobs(&mut conn, &scheduled_jobs).await;
+                        }
+                        Err(error) => error!("Failed to check scheduled jobs: {error}"),
+                    }
+
+                    tokio::time::sleep(SCHEDULER_INTERVAL).await;
+                }
+            });
+        }
+
         let mut handles = Vec::new();
         for (queue_name, queue) in &self.queues {
             for i in 1..=queue.num_workers {
@@ -120,6 +165,17 @@ impl<Context: Clone + Send + Sync + 'static> Runner<Context> {
         RunHandle { handles }
     }
 
+    /// Enqueues all scheduled jobs that are due.
+    ///
+    /// This is done periodically by the runner after [`start()`](Self::start)
+    /// is called, unless [`shutdown_when_queue_empty()`](Self::shutdown_when_queue_empty)
+    /// is set.
+    pub async fn enqueue_scheduled_jobs(&self) -> anyhow::Result<()> {
+        let mut conn = self.connection_pool.get().await?;
+        scheduler::enqueue_scheduled_jobs(&mut conn, &self.scheduled_jobs).await;
+        Ok(())
+    }
+
     /// Checks if any jobs in the queue have failed.
     ///
     /// This function is intended for use in tests and will return an error if
@@ -0,0 +1,126 @@
+use crate::background_job::{enqueue_deduplicated, enqueue_simple};
+use crate::schema::background_job_schedules;
+use crate::{BackgroundJob, EnqueueError};
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use serde_json::Value;
+use tracing::{info, warn};
+
+/// A recurring job that is enqueued by the [`Runner`](crate::Runner)
+/// according to a cron [`Schedule`].
+#[derive(Clone)]
+pub(crate) struct ScheduledJob {
+    job_type: &'static str,
+    schedule: Schedule,
+    data: Value,
+    priority: i16,
+    deduplicated: bool,
+}
+
+impl ScheduledJob {
+    /// # Panics
+    ///
+    /// Panics if the job can not be serialized.
+    pub(crate) fn new<J: BackgroundJob>(schedule: Schedule, job: &J) -> Self {
+        let data = serde_json::to_value(job).expect("Failed to serialize scheduled job");
+
+        Self {
+            job_type: J::JOB_NAME,
+            schedule,
+            data,
+            priority: J::PRIORITY,
+            deduplicated: J::DEDUPLICATED,
+        }
+    }
+}
+
+/// Enqueues all scheduled jobs that are due.
+///
+/// Every scheduled job is enqueued at most once per occurrence of its
+/// schedule, even if multiple runners are checking the schedules
+/// concurrently. If several occurrences were missed (e.g. because no runner
+/// was active), the job is only enqueued once.
+pub(crate) async fn enqueue_scheduled_jobs(
+    conn: &mut AsyncPgConnection,
+    scheduled_jobs: &[ScheduledJob],
+) {
+    for scheduled_job in scheduled_jobs {
+        let job_type = scheduled_job.job_type;
+        match enqueue_if_due(conn, scheduled_job).await {
+            Ok(Some(job_id)) => {
+                info!(job.id = %job_id, job.typ = %job_type, "Enqueued scheduled job")
+            }
+            Ok(None) => {}
+            Err(error) => warn!(job.typ = %job_type, "Failed to enqueue scheduled job: {error}"),
+        }
+    }
+}
+
+/// Enqueues the scheduled job if an occurrence of its schedule has passed
+/// since it was last enqueued.
+///
+/// Returns the id of the enqueued job, or `None` if the job is not due, is
+/// currently being handled by another runner, or was deduplicated.
+async fn enqueue_if_due(
+    conn: &mut AsyncPgConnection,
+    scheduled_job: &ScheduledJob,
+) -> Result<Option<i64>, EnqueueError> {
+    let now = Utc::now();
+
+    conn.transaction(async |conn| {
+        // Jobs that are scheduled for the first time are not enqueued
+        // immediately, but only once the next occurrence has passed.
+        diesel::insert_into(background_job_schedules::table)
+            .values((
+                background_job_schedules::job_type.eq(scheduled_job.job_type),
+                background_job_schedules::last_run_at.eq(now),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await?;
+
+        let last_run_at = background_job_schedules::table
+            .find(scheduled_job.job_type)
+            .select(background_job_schedules::last_run_at)
+            .for_update()
+            .skip_locked()
+            .get_result::<DateTime<Utc>>(conn)
+            .await
+            .optional()?;
+
+        // Another runner is currently handling this schedule.
+        let Some(last_run_at) = last_run_at else {
+            return Ok(None);
+        };
+
+        let is_due = scheduled_job
+            .schedule
+            .after(&last_run_at)
+            .next()
+            .is_some_and(|next_run_at| next_run_at <= now);
+
+        if !is_due {
+            return Ok(None);
+        }
+
+        diesel::update(background_job_schedules::table.find(scheduled_job.job_type))
+            .set(background_job_schedules::last_run_at.eq(now))
+            .execute(conn)
+            .await?;
+
+        let job_type = scheduled_job.job_type;
+        let data = scheduled_job.data.clone();
+        let priority = scheduled_job.priority;
+
+        if scheduled_job.deduplicated {
+            enqueue_deduplicated(conn, job_type, data, priority, None).await
+        } else {
+            Ok(Some(
+                enqueue_simple(conn, job_type, data, priority, None).await?,
+            ))
+        }
+    })
+    .await
+}
@@ -1,3 +1,10 @@
+diesel::table! {
+    background_job_schedules (job_type) {
+        job_type -> Text,
+        last_run_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     background_jobs (id) {
         id -> Int8,
@@ -5,7 +12,21 @@ diesel::table! {
         data -> Jsonb,
         retries -> Int4,
         last_retry -> Timestamp,
-        created_at -> Timestamp,
+        created_at -> Timestamptz,
+        priority -> Int2,
+        run_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    background_jobs_dead (id) {
+        id -> Int8,
+        job_type -> Text,
+        data -> Jsonb,
+        retries -> Int4,
         priority -> Int2,
+        created_at -> Timestamptz,
+        failed_at -> Timestamptz,
+        last_error -> Nullable<Text>,
     }
 }
@@ -1,8 +1,10 @@
-use crate::schema::background_jobs;
+use crate::Backoff;
+use crate::schema::{background_jobs, background_jobs_dead};
+use crate::util::to_interval;
 use diesel::dsl::now;
 use diesel::pg::Pg;
 use diesel::prelude::*;
-use diesel::sql_types::{Bool, Integer, Interval};
+use diesel::sql_types::{Bool, Integer, Interval, Nullable, Text};
 use diesel::{delete, update};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 
@@ -11,28 +13,56 @@ pub(super) struct BackgroundJob {
     pub(super) id: i64,
     pub(super) job_type: String,
     pub(super) data: serde_json::Value,
+    pub(super) retries: i32,
 }
 
-fn retriable() -> Box<dyn BoxableExpression<background_jobs::table, Pg, SqlType = Bool>> {
-    use diesel::dsl::*;
+type BoxedCondition = Box<dyn BoxableExpression<background_jobs::table, Pg, SqlType = Bool>>;
 
+/// Whether enough time has passed since the last failed attempt of a job
+/// to retry it according to the given [`Backoff`].
+fn backoff_elapsed(backoff: Backoff) -> BoxedCondition {
     define_sql_function!(fn power(x: Integer, y: Integer) -> Integer);
 
-    Box::new(
-        background_jobs::last_retry
-            .lt(now - 1.minute().into_sql::<Interval>() * power(2, background_jobs::retries)),
-    )
+    let interval = |delay| to_interval(delay).into_sql::<Interval>();
+
+    let last_retry = background_jobs::last_retry;
+    let retries = background_jobs::retries;
+
+    match backoff {
+        Backoff::Fixed(delay) => Box::new(last_retry.lt(now - interval(delay))),
+        Backoff::Linear(delay) => Box::new(last_retry.lt(now - interval(delay) * retries)),
+        Backoff::Exponential(delay) => {
+            Box::new(last_retry.lt(now - interval(delay) * power(2, retries)))
+        }
+    }
+}
+
+/// Whether a job is of one of the given job types and ready to be retried
+/// according to the [`Backoff`] of its job type.
+fn retriable(job_types_by_backoff: &[(Backoff, Vec<String>)]) -> BoxedCondition {
+    job_types_by_backoff
+        .iter()
+        .map(|(backoff, job_types)| -> BoxedCondition {
+            let job_types = background_jobs::job_type.eq_any(job_types.clone());
+            Box::new(job_types.and(backoff_elapsed(*backoff)))
+        })
+        .reduce(|a, b| Box::new(a.or(b)))
+        .unwrap_or_else(|| Box::new(false.into_sql::<Bool>()))
 }
 
 /// Finds the next job that is unlocked, and ready to be retried. If a row is
 /// found, it will be locked.
 pub(super) async fn find_next_unlocked_job(
     conn: &mut AsyncPgConnection,
-    job_types: &[String],
+    job_types_by_backoff: &[(Backoff, Vec<String>)],
 ) -> QueryResult<BackgroundJob> {
     BackgroundJob::query()
-        .filter(background_jobs::job_type.eq_any(job_types))
-        .filter(retriable())
+        .filter(retriable(job_types_by_backoff))
+        .filter(
+            background_jobs::run_at
+                .is_null()
+                .or(background_jobs::run_at.le(now)),
+        )
         .order((background_jobs::priority.desc(), background_jobs::id))
         .for_update()
         .skip_locked()
@@ -40,13 +70,18 @@ pub(super) async fn find_next_unlocked_job(
         .await
 }
 
-/// The number of jobs that have failed at least once.
+/// The number of jobs that have failed at least once, including the jobs
+/// that have exhausted their retries.
 pub(super) async fn failed_job_count(conn: &mut AsyncPgConnection) -> QueryResult<i64> {
-    background_jobs::table
+    let failed_jobs: i64 = background_jobs::table
         .count()
         .filter(background_jobs::retries.gt(0))
         .get_result(conn)
-        .await
+        .await?;
+
+    let dead_jobs: i64 = background_jobs_dead::table.count().get_result(conn).await?;
+
+    Ok(failed_jobs + dead_jobs)
 }
 
 /// Deletes a job that has successfully completed running.
@@ -73,3 +108,41 @@ pub(super) async fn update_failed_job(conn: &mut AsyncPgConnection, job_id: i64)
         .execute(conn)
         .await;
 }
+
+/// Moves a job that has exhausted its retries to the `background_jobs_dead`
+/// table, so that it is no longer attempted.
+pub(super) async fn move_dead_job(
+    conn: &mut AsyncPgConnection,
+    job_id: i64,
+    error: &str,
+) -> QueryResult<()> {
+    let dead_job = background_jobs::table.find(job_id).select((
+        background_jobs::id,
+        background_jobs::job_type,
+        background_jobs::data,
+        background_jobs::retries,
+        background_jobs::priority,
+        background_jobs::created_at,
+        error.into_sql::<Nullable<Text>>(),
+    ));
+
+    diesel::insert_into(background_jobs_dead::table)
+        .values(dead_job)
+        .into_columns((
+            background_jobs_dead::id,
+            background_jobs_dead::job_type,
+            background_jobs_dead::data,
+            background_jobs_dead::retries,
+            background_jobs_dead::priority,
+            background_jobs_dead::created_at,
+            background_jobs_dead::last_error,
+        ))
+        .execute(conn)
+        .await?;
+
+    delete(background_jobs::table.find(job_id))
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
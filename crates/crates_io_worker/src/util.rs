@@ -1,8 +1,10 @@
 use anyhow::anyhow;
+use diesel::pg::data_types::PgInterval;
 use sentry_core::{Hub, SentryFutureExt};
 use std::any::Any;
 use std::future::Future;
 use std::panic::PanicHookInfo;
+use std::time::Duration;
 
 pub async fn with_sentry_transaction<F, R, E, Fut>(
     transaction_name: &str,
@@ -48,3 +50,10 @@ pub fn try_to_extract_panic_info(info: &(dyn Any + Send + 'static)) -> anyhow::E
         anyhow!("job panicked")
     }
 }
+
+/// Converts a [`Duration`] into a [`PgInterval`], saturating at the maximum
+/// number of microseconds that fit into the interval.
+pub fn to_interval(duration: Duration) -> PgInterval {
+    let microseconds = i64::try_from(duration.as_micros()).unwrap_or(i64::MAX);
+    PgInterval::from_microseconds(microseconds)
+}
@@ -72,10 +72,10 @@ impl<Context: Clone + Send + Sync + 'static> Worker<Context> {
         let job_registry = self.job_registry.clone();
         let mut conn = self.connection_pool.get().await?;
 
-        let job_types = job_registry.job_types();
+        let job_types_by_backoff = job_registry.job_types_by_backoff();
         conn.transaction(async |conn| {
             debug!("Looking for next background worker job…");
-            let Some(job) = storage::find_next_unlocked_job(conn, &job_types)
+            let Some(job) = storage::find_next_unlocked_job(conn, &job_types_by_backoff)
                 .await
                 .optional()?
             else {
@@ -109,7 +109,15 @@ impl<Context: Clone + Send + Sync + 'static> Worker<Context> {
                 }
                 Err(error) => {
                     warn!("Failed to run job: {error}");
-                    storage::update_failed_job(conn, job_id).await;
+
+                    let max_retries = job_registry.max_retries(&job.job_type);
+                    let retries = u32::try_from(job.retries).unwrap_or_default();
+                    if max_retries.is_some_and(|max_retries| retries >= max_retries) {
+                        warn!("Job has exhausted its retries. Moving it to the dead jobs…");
+                        storage::move_dead_job(conn, job_id, &error.to_string()).await?;
+                    } else {
+                        storage::update_failed_job(conn, job_id).await;
+                    }
                 }
             }
 
@@ -1,7 +1,8 @@
+use chrono::{TimeDelta, Utc};
 use claims::{assert_none, assert_some};
 use crates_io_test_db::TestDatabase;
-use crates_io_worker::schema::background_jobs;
-use crates_io_worker::{BackgroundJob, Runner};
+use crates_io_worker::schema::{background_job_schedules, background_jobs, background_jobs_dead};
+use crates_io_worker::{BackgroundJob, Backoff, Runner, dead_jobs};
 use diesel::prelude::*;
 use diesel_async::pooled_connection::AsyncDieselConnectionManager;
 use diesel_async::pooled_connection::deadpool::Pool;
@@ -16,6 +17,8 @@ use std::time::Duration;
 use tokio::sync::{Barrier, Notify};
 use tokio::time::{sleep, timeout};
 
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
 async fn all_jobs(conn: &mut AsyncPgConnection) -> QueryResult<Vec<(String, Value)>> {
     background_jobs::table
         .select((background_jobs::job_type, background_jobs::data))
@@ -233,6 +236,127 @@ async fn panicking_in_jobs_updates_retry_counter() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn jobs_are_moved_to_dead_jobs_after_exhausting_retries() -> anyhow::Result<()> {
+    #[derive(Serialize, Deserialize)]
+    struct TestJob;
+
+    impl BackgroundJob for TestJob {
+        const JOB_NAME: &'static str = "test";
+        const MAX_RETRIES: Option<u32> = Some(2);
+        const BACKOFF: Backoff = Backoff::Fixed(Duration::ZERO);
+        type Context = ();
+
+        async fn run(&self, _ctx: Self::Context) -> anyhow::Result<()> {
+            anyhow::bail!("something went wrong")
+        }
+    }
+
+    async fn dead_jobs(conn: &mut AsyncPgConnection) -> QueryResult<Vec<(i64, i32, String)>> {
+        background_jobs_dead::table
+            .select((
+                background_jobs_dead::id,
+                background_jobs_dead::retries,
+                background_jobs_dead::last_error.assume_not_null(),
+            ))
+            .get_results(conn)
+            .await
+    }
+
+    let test_database = TestDatabase::new();
+
+    let pool = pool(test_database.url())?;
+    let mut conn = pool.get().await?;
+
+    let runner = runner(pool, ()).register_job_type::<TestJob>();
+
+    let job_id = assert_some!(TestJob.enqueue(&conn).await?);
+
+    let handle = runner.start();
+    handle.wait_for_shutdown().await;
+
+    // The job was attempted once and retried twice before it was given up
+    assert!(!job_exists(job_id, &mut conn).await?);
+    assert_eq!(
+        dead_jobs(&mut conn).await?,
+        vec![(job_id, 2, "something went wrong".to_string())]
+    );
+
+    // Dead jobs are reported as failed jobs
+    let error = runner.check_for_failed_jobs().await.unwrap_err();
+    assert_eq!(error.to_string(), "1 jobs failed");
+
+    // Requeued jobs are moved back to the queue with a reset retry counter
+    assert_eq!(dead_jobs::requeue(&mut conn, &[job_id]).await?, 1);
+    assert_eq!(dead_jobs(&mut conn).await?, vec![]);
+
+    let retries = background_jobs::table
+        .select(background_jobs::retries)
+        .get_results::<i32>(&mut conn)
+        .await?;
+    assert_eq!(retries, vec![0]);
+
+    // Once they fail again, they can be discarded
+    let handle = runner.start();
+    handle.wait_for_shutdown().await;
+
+    let dead_job_ids = background_jobs_dead::table
+        .select(background_jobs_dead::id)
+        .get_results::<i64>(&mut conn)
+        .await?;
+    assert_eq!(dead_job_ids.len(), 1);
+
+    assert_eq!(dead_jobs::discard(&mut conn, &dead_job_ids).await?, 1);
+    assert_eq!(dead_jobs(&mut conn).await?, vec![]);
+    assert_eq!(all_jobs(&mut conn).await?, vec![]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn jobs_without_max_retries_are_not_moved_to_dead_jobs() -> anyhow::Result<()> {
+    #[derive(Serialize, Deserialize)]
+    struct TestJob;
+
+    impl BackgroundJob for TestJob {
+        const JOB_NAME: &'static str = "test";
+        const BACKOFF: Backoff = Backoff::Linear(Duration::from_secs(3600));
+        type Context = ();
+
+        async fn run(&self, _ctx: Self::Context) -> anyhow::Result<()> {
+            anyhow::bail!("something went wrong")
+        }
+    }
+
+    let test_database = TestDatabase::new();
+
+    let pool = pool(test_database.url())?;
+    let mut conn = pool.get().await?;
+
+    let runner = runner(pool, ()).register_job_type::<TestJob>();
+
+    let job_id = assert_some!(TestJob.enqueue(&conn).await?);
+
+    let handle = runner.start();
+    handle.wait_for_shutdown().await;
+
+    // The job was attempted once, and the backoff prevents an immediate retry
+    let retries = background_jobs::table
+        .find(job_id)
+        .select(background_jobs::retries)
+        .get_result::<i32>(&mut conn)
+        .await?;
+    assert_eq!(retries, 1);
+
+    let dead_jobs = background_jobs_dead::table
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await?;
+    assert_eq!(dead_jobs, 0);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn jobs_can_be_deduplicated() -> anyhow::Result<()> {
     #[derive(Clone)]
@@ -317,6 +441,111 @@ async fn jobs_can_be_deduplicated() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn delayed_jobs_are_not_run_before_their_delay() -> anyhow::Result<()> {
+    #[derive(Serialize, Deserialize)]
+    struct TestJob {
+        value: String,
+    }
+
+    impl BackgroundJob for TestJob {
+        const JOB_NAME: &'static str = "test";
+        type Context = ();
+
+        async fn run(&self, _ctx: Self::Context) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    let test_database = TestDatabase::new();
+
+    let pool = pool(test_database.url())?;
+    let mut conn = pool.get().await?;
+
+    let runner = runner(pool, ()).register_job_type::<TestJob>();
+
+    let job = TestJob {
+        value: "delayed".into(),
+    };
+    let delayed_job_id = assert_some!(job.enqueue_with_delay(&conn, HOUR).await?);
+
+    let job = TestJob {
+        value: "immediate".into(),
+    };
+    let immediate_job_id = assert_some!(job.enqueue(&conn).await?);
+
+    let handle = runner.start();
+    handle.wait_for_shutdown().await;
+
+    assert!(job_exists(delayed_job_id, &mut conn).await?);
+    assert!(!job_exists(immediate_job_id, &mut conn).await?);
+
+    // Once the delay has passed, the job is run
+    diesel::update(background_jobs::table.find(delayed_job_id))
+        .set(background_jobs::run_at.eq(diesel::dsl::now))
+        .execute(&mut conn)
+        .await?;
+
+    let handle = runner.start();
+    handle.wait_for_shutdown().await;
+
+    assert!(!job_exists(delayed_job_id, &mut conn).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn scheduled_jobs_are_enqueued_when_due() -> anyhow::Result<()> {
+    #[derive(Serialize, Deserialize)]
+    struct TestJob {
+        value: String,
+    }
+
+    impl BackgroundJob for TestJob {
+        const JOB_NAME: &'static str = "test";
+        type Context = ();
+
+        async fn run(&self, _ctx: Self::Context) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn set_last_run_at(days_ago: i64, conn: &mut AsyncPgConnection) -> QueryResult<()> {
+        let last_run_at = Utc::now() - TimeDelta::days(days_ago);
+        diesel::update(background_job_schedules::table)
+            .set(background_job_schedules::last_run_at.eq(last_run_at))
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    let test_database = TestDatabase::new();
+
+    let pool = pool(test_database.url())?;
+    let mut conn = pool.get().await?;
+
+    // Runs at midnight on the first day of every year
+    let job = TestJob {
+        value: "yearly".into(),
+    };
+    let runner = runner(pool, ()).schedule_job("0 0 0 1 1 *".parse()?, job);
+
+    // Jobs are not enqueued immediately when they are scheduled for the first time
+    runner.enqueue_scheduled_jobs().await?;
+    assert_compact_json_snapshot!(all_jobs(&mut conn).await?, @"[]");
+
+    // Once an occurrence of the schedule has passed, the job is enqueued…
+    set_last_run_at(400, &mut conn).await?;
+    runner.enqueue_scheduled_jobs().await?;
+    assert_compact_json_snapshot!(all_jobs(&mut conn).await?, @r#"[["test", {"value": "yearly"}]]"#);
+
+    // …but only once, even if multiple occurrences were missed
+    runner.enqueue_scheduled_jobs().await?;
+    assert_compact_json_snapshot!(all_jobs(&mut conn).await?, @r#"[["test", {"value": "yearly"}]]"#);
+
+    Ok(())
+}
+
 /// A database trigger should emit a `NOTIFY` on the `background_jobs` channel
 /// whenever a job is enqueued, so that listening workers can wake up
 /// immediately instead of waiting for the next poll.
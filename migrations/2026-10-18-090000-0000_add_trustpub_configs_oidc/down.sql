@@ -0,0 +1 @@
+DROP TABLE trustpub_configs_oidc;
@@ -0,0 +1,26 @@
+CREATE TABLE trustpub_configs_oidc (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    crate_id INTEGER NOT NULL REFERENCES crates ON DELETE CASCADE,
+    provider VARCHAR NOT NULL,
+    repository VARCHAR NOT NULL,
+    repository_owner_id VARCHAR,
+    claims JSONB NOT NULL DEFAULT '{}'
+);
+
+comment on table trustpub_configs_oidc is 'Trusted Publisher configuration for generic OIDC providers (e.g. Buildkite)';
+comment on column trustpub_configs_oidc.id is 'Unique identifier of the `trustpub_configs_oidc` row';
+comment on column trustpub_configs_oidc.created_at is 'Date and time when the configuration was created';
+comment on column trustpub_configs_oidc.crate_id is 'Unique identifier of the crate that this configuration is for';
+comment on column trustpub_configs_oidc.provider is 'Identifier of the OIDC provider (e.g. `buildkite`)';
+comment on column trustpub_configs_oidc.repository is 'Provider-specific identifier of the repository or pipeline that is allowed to publish the crate (e.g. `acme-inc/my-pipeline`)';
+comment on column trustpub_configs_oidc.repository_owner_id is 'Immutable ID of the repository owner, populated on first token exchange for resurrection attack protection (if supported by the provider)';
+comment on column trustpub_configs_oidc.claims is 'Additional JWT claims that must match for a token exchange to succeed (e.g. `{"step_key": "publish"}`)';
+
+-- safety-assured:start
+-- Suppresses the "ADD INDEX without CONCURRENTLY" diesel-guard check.
+-- The table is brand new and empty, so the index builds instantly with
+-- no meaningful SHARE lock contention; CONCURRENTLY is unnecessary.
+CREATE INDEX IF NOT EXISTS idx_trustpub_configs_oidc_repository
+ON trustpub_configs_oidc (provider, LOWER(repository));
+-- safety-assured:end
@@ -0,0 +1,12 @@
+CREATE TABLE trustpub_provenance (
+    version_id INTEGER PRIMARY KEY REFERENCES versions ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    key_id VARCHAR NOT NULL,
+    envelope JSONB NOT NULL
+);
+
+comment on table trustpub_provenance is 'Signed provenance attestations for versions published via Trusted Publishing';
+comment on column trustpub_provenance.version_id is 'Unique identifier of the version that this attestation is for';
+comment on column trustpub_provenance.created_at is 'Date and time when the attestation was issued';
+comment on column trustpub_provenance.key_id is 'Identifier of the key that was used to sign the attestation (hex-encoded SHA256 hash of the Ed25519 public key)';
+comment on column trustpub_provenance.envelope is 'DSSE envelope containing the signed in-toto statement with a SLSA provenance predicate';
@@ -0,0 +1,2 @@
+DROP TABLE crate_rate_overrides;
+DROP TABLE crate_limit_buckets;
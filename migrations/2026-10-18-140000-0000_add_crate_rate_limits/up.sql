@@ -0,0 +1,27 @@
+CREATE TABLE crate_limit_buckets (
+    crate_id INTEGER NOT NULL REFERENCES crates ON DELETE CASCADE,
+    action INTEGER NOT NULL,
+    tokens INTEGER NOT NULL,
+    last_refill TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (crate_id, action)
+);
+
+comment on table crate_limit_buckets is 'Rate limiting buckets for actions that are performed on behalf of a crate instead of a user (e.g. Trusted Publishing token exchanges)';
+comment on column crate_limit_buckets.crate_id is 'Unique identifier of the crate that the bucket belongs to';
+comment on column crate_limit_buckets.action is 'The rate-limited action (see `LimitedAction`)';
+comment on column crate_limit_buckets.tokens is 'Number of remaining tokens in the bucket';
+comment on column crate_limit_buckets.last_refill is 'Date and time when the bucket was last refilled';
+
+CREATE TABLE crate_rate_overrides (
+    crate_id INTEGER NOT NULL REFERENCES crates ON DELETE CASCADE,
+    action INTEGER NOT NULL,
+    burst INTEGER NOT NULL,
+    expires_at TIMESTAMPTZ,
+    PRIMARY KEY (crate_id, action)
+);
+
+comment on table crate_rate_overrides is 'Per-crate overrides of the burst size of crate rate limiting buckets';
+comment on column crate_rate_overrides.crate_id is 'Unique identifier of the crate that the override applies to';
+comment on column crate_rate_overrides.action is 'The rate-limited action (see `LimitedAction`)';
+comment on column crate_rate_overrides.burst is 'Burst size that is used instead of the default';
+comment on column crate_rate_overrides.expires_at is 'Date and time when the override expires, or `NULL` if it does not expire';
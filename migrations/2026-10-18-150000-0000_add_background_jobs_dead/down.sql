@@ -0,0 +1 @@
+DROP TABLE background_jobs_dead;
@@ -0,0 +1,27 @@
+CREATE TABLE background_jobs_dead (
+    id BIGINT PRIMARY KEY,
+    job_type TEXT NOT NULL,
+    data JSONB NOT NULL,
+    retries INTEGER NOT NULL,
+    priority SMALLINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    failed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error TEXT
+);
+
+comment on table background_jobs_dead is 'Background jobs that have exhausted their retry policy and are no longer attempted by the background workers';
+comment on column background_jobs_dead.id is 'Identifier of the job in the `background_jobs` table';
+comment on column background_jobs_dead.job_type is 'Type of the background job';
+comment on column background_jobs_dead.data is 'Serialized data of the background job';
+comment on column background_jobs_dead.retries is 'Number of times the job was retried before it was moved to this table';
+comment on column background_jobs_dead.priority is 'Priority of the background job';
+comment on column background_jobs_dead.created_at is 'Date and time when the job was originally enqueued';
+comment on column background_jobs_dead.failed_at is 'Date and time when the job failed for the last time';
+comment on column background_jobs_dead.last_error is 'Error message of the last failed attempt';
+
+-- safety-assured:start
+-- The table is created empty in this migration, so building the index
+-- without CONCURRENTLY does not block any readers or writers.
+CREATE INDEX IF NOT EXISTS background_jobs_dead_job_type_index
+ON background_jobs_dead (job_type);
+-- safety-assured:end
@@ -0,0 +1,3 @@
+DROP TABLE background_job_schedules;
+
+ALTER TABLE background_jobs DROP COLUMN run_at;
@@ -0,0 +1,12 @@
+ALTER TABLE background_jobs ADD COLUMN run_at TIMESTAMPTZ;
+
+comment on column background_jobs.run_at is 'Date and time before which the job must not be run, or `NULL` if it can be run immediately';
+
+CREATE TABLE background_job_schedules (
+    job_type TEXT PRIMARY KEY,
+    last_run_at TIMESTAMPTZ NOT NULL
+);
+
+comment on table background_job_schedules is 'State of the recurring background jobs that are scheduled by the background worker itself';
+comment on column background_job_schedules.job_type is 'Type of the scheduled background job';
+comment on column background_job_schedules.last_run_at is 'Date and time when the job was last enqueued by the scheduler';
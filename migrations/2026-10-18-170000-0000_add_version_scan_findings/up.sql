@@ -0,0 +1,23 @@
+CREATE TABLE version_scan_findings (
+    id BIGINT PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    version_id INTEGER NOT NULL REFERENCES versions ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    path TEXT NOT NULL,
+    line INTEGER,
+    description TEXT NOT NULL
+);
+
+comment on table version_scan_findings is 'Findings of the content scanner for the files of published versions, visible to the crate owners and admins';
+comment on column version_scan_findings.id is 'Unique identifier of the finding';
+comment on column version_scan_findings.version_id is 'Unique identifier of the version that the finding belongs to';
+comment on column version_scan_findings.kind is 'Category of the finding (`secret`, `binary` or `build_script`)';
+comment on column version_scan_findings.path is 'Path of the file, relative to the root of the crate';
+comment on column version_scan_findings.line is 'Line number of the finding, if it is specific to a line';
+comment on column version_scan_findings.description is 'Human-readable description of the finding';
+
+-- safety-assured:start
+-- `version_scan_findings` is a brand new table without any rows yet, so a
+-- non-concurrent index build completes instantly.
+CREATE INDEX IF NOT EXISTS version_scan_findings_version_id_index
+ON version_scan_findings (version_id);
+-- safety-assured:end
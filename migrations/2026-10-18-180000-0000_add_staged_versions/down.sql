@@ -0,0 +1,2 @@
+ALTER TABLE crates DROP COLUMN require_promotion_review;
+ALTER TABLE versions DROP COLUMN staged_at;
@@ -0,0 +1,5 @@
+ALTER TABLE versions ADD COLUMN staged_at TIMESTAMPTZ;
+COMMENT ON COLUMN versions.staged_at IS 'Date and time when the version was uploaded as a staged version, or NULL if the version is visible. Staged versions are hidden from the index and the API listings until they are promoted.';
+
+ALTER TABLE crates ADD COLUMN require_promotion_review BOOLEAN NOT NULL DEFAULT FALSE;
+COMMENT ON COLUMN crates.require_promotion_review IS 'When true, new versions of this crate are always staged, and have to be promoted by an owner other than the one who published them';
@@ -0,0 +1 @@
+DROP INDEX CONCURRENTLY IF EXISTS index_versions_staged_at
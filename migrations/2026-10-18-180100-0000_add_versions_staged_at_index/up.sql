@@ -0,0 +1,3 @@
+CREATE INDEX CONCURRENTLY IF NOT EXISTS index_versions_staged_at
+ON versions (staged_at)
+WHERE staged_at IS NOT NULL
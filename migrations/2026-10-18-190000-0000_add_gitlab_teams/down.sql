@@ -0,0 +1,13 @@
+DROP TABLE oauth_gitlab;
+
+DELETE FROM crate_owners
+WHERE owner_kind = 1
+  AND owner_id IN (SELECT id FROM teams WHERE gitlab_id IS NOT NULL);
+
+DELETE FROM teams WHERE gitlab_id IS NOT NULL;
+
+ALTER TABLE teams DROP CONSTRAINT teams_provider_id_ck;
+ALTER TABLE teams DROP COLUMN gitlab_id;
+COMMENT ON COLUMN teams.login IS 'Example: `github:foo:bar` means the `bar` team of the `foo` GitHub organization.';
+ALTER TABLE teams ALTER COLUMN org_id SET NOT NULL;
+ALTER TABLE teams ALTER COLUMN github_id SET NOT NULL;
@@ -0,0 +1,31 @@
+ALTER TABLE teams ALTER COLUMN github_id DROP NOT NULL;
+ALTER TABLE teams ALTER COLUMN org_id DROP NOT NULL;
+ALTER TABLE teams ADD COLUMN gitlab_id BIGINT;
+COMMENT ON COLUMN teams.gitlab_id IS 'Unique group ID on the GitLab API. When groups are recreated with the same path then they will still get a different ID, so this allows us to avoid potential name reuse attacks.';
+COMMENT ON COLUMN teams.login IS 'Example: `github:foo:bar` means the `bar` team of the `foo` GitHub organization, and `gitlab:foo/bar` means the `bar` subgroup of the `foo` GitLab group.';
+
+-- safety-assured:start
+-- `teams` only has a few thousand rows, so validating the constraint while
+-- holding the lock is fast.
+ALTER TABLE teams ADD CONSTRAINT teams_provider_id_ck CHECK (
+    (github_id IS NOT NULL AND org_id IS NOT NULL AND gitlab_id IS NULL)
+    OR (github_id IS NULL AND org_id IS NULL AND gitlab_id IS NOT NULL)
+);
+-- safety-assured:end
+
+CREATE TABLE oauth_gitlab (
+    account_id BIGINT NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL UNIQUE REFERENCES users (id) ON DELETE CASCADE,
+    encrypted_token BYTEA NOT NULL,
+    login VARCHAR NOT NULL,
+    avatar VARCHAR,
+    last_sync TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+comment on table oauth_gitlab is 'GitLab account information linked to a crates.io account, used for GitLab group membership checks';
+comment on column oauth_gitlab.account_id is 'GitLab ID returned from the OAuth response';
+comment on column oauth_gitlab.user_id is 'Crates.io user ID foreign key';
+comment on column oauth_gitlab.encrypted_token is 'Encrypted GitLab access token';
+comment on column oauth_gitlab.login is 'GitLab username';
+comment on column oauth_gitlab.avatar is 'GitLab avatar URL';
+comment on column oauth_gitlab.last_sync is 'The last time the account details were fetched from GitLab';
@@ -0,0 +1 @@
+DROP INDEX CONCURRENTLY IF EXISTS teams_gitlab_id_key
@@ -0,0 +1,2 @@
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS teams_gitlab_id_key
+ON teams (gitlab_id)
@@ -0,0 +1,23 @@
+CREATE TABLE oauth_gitlab (
+    account_id BIGINT NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL UNIQUE REFERENCES users (id) ON DELETE CASCADE,
+    encrypted_token BYTEA NOT NULL,
+    login VARCHAR NOT NULL,
+    avatar VARCHAR,
+    last_sync TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+comment on table oauth_gitlab is 'GitLab account information linked to a crates.io account, used for GitLab group membership checks';
+comment on column oauth_gitlab.account_id is 'GitLab ID returned from the OAuth response';
+comment on column oauth_gitlab.user_id is 'Crates.io user ID foreign key';
+comment on column oauth_gitlab.encrypted_token is 'Encrypted GitLab access token';
+comment on column oauth_gitlab.login is 'GitLab username';
+comment on column oauth_gitlab.avatar is 'GitLab avatar URL';
+comment on column oauth_gitlab.last_sync is 'The last time the account details were fetched from GitLab';
+
+INSERT INTO oauth_gitlab (account_id, user_id, encrypted_token, login, avatar, last_sync)
+SELECT account_id::BIGINT, user_id, encrypted_token, login, avatar, last_sync
+FROM linked_identities
+WHERE provider = 'gitlab' AND encrypted_token IS NOT NULL AND login IS NOT NULL;
+
+DROP TABLE linked_identities;
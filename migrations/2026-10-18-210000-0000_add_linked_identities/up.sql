@@ -0,0 +1,34 @@
+CREATE TABLE linked_identities (
+    provider VARCHAR NOT NULL,
+    account_id VARCHAR NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    login VARCHAR,
+    email VARCHAR,
+    avatar VARCHAR,
+    encrypted_token BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_sync TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (provider, account_id),
+    UNIQUE (user_id, provider)
+);
+
+comment on table linked_identities is 'Accounts of external identity providers other than GitHub (e.g. GitLab or generic OpenID Connect providers) that are linked to a crates.io account and can be used to sign in';
+comment on column linked_identities.provider is 'ID of the identity provider, e.g. `gitlab` or the ID of a configured OpenID Connect provider';
+comment on column linked_identities.account_id is 'ID of the account at the identity provider (the `sub` claim for OpenID Connect providers)';
+comment on column linked_identities.user_id is 'Crates.io user ID foreign key';
+comment on column linked_identities.login is 'Username of the account at the identity provider, if available';
+comment on column linked_identities.email is 'Email address of the account at the identity provider, if available';
+comment on column linked_identities.avatar is 'Avatar URL of the account at the identity provider, if available';
+comment on column linked_identities.encrypted_token is 'Encrypted access token, only stored for providers whose API is used after signing in (e.g. for GitLab group membership checks)';
+comment on column linked_identities.created_at is 'The time when the account was linked';
+comment on column linked_identities.last_sync is 'The last time the account details were fetched from the identity provider';
+
+INSERT INTO linked_identities (provider, account_id, user_id, login, avatar, encrypted_token, created_at, last_sync)
+SELECT 'gitlab', account_id::VARCHAR, user_id, login, avatar, encrypted_token, last_sync, last_sync
+FROM oauth_gitlab;
+
+-- safety-assured:start
+-- The linked GitLab accounts have been copied into `linked_identities` above,
+-- and the application does not use `oauth_gitlab` anymore.
+DROP TABLE oauth_gitlab;
+-- safety-assured:end
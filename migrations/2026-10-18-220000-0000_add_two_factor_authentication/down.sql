@@ -0,0 +1,5 @@
+ALTER TABLE crates DROP COLUMN require_owner_2fa;
+
+DROP TABLE recovery_codes;
+DROP TABLE webauthn_credentials;
+DROP TABLE totp_credentials;
@@ -0,0 +1,57 @@
+CREATE TABLE totp_credentials (
+    user_id INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    encrypted_secret BYTEA NOT NULL,
+    confirmed_at TIMESTAMPTZ,
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+comment on table totp_credentials is 'Authenticator apps that are used as a second factor, using time-based one-time passwords (TOTP)';
+comment on column totp_credentials.user_id is 'Crates.io user ID foreign key';
+comment on column totp_credentials.encrypted_secret is 'Encrypted shared secret of the authenticator app';
+comment on column totp_credentials.confirmed_at is 'The time when the user confirmed the setup with a valid code, or NULL if the setup is still pending';
+comment on column totp_credentials.last_used_step is 'Time step of the last accepted code, to make sure that every code is only accepted once';
+comment on column totp_credentials.created_at is 'The time when the setup was started';
+
+CREATE TABLE webauthn_credentials (
+    id INTEGER PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    credential_id BYTEA NOT NULL UNIQUE,
+    public_key BYTEA NOT NULL,
+    sign_count BIGINT NOT NULL DEFAULT 0,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ
+);
+
+comment on table webauthn_credentials is 'Security keys and passkeys that are used as a second factor via WebAuthn';
+comment on column webauthn_credentials.id is 'Unique identifier of the credential';
+comment on column webauthn_credentials.user_id is 'Crates.io user ID foreign key';
+comment on column webauthn_credentials.credential_id is 'ID that the authenticator assigned to the credential';
+comment on column webauthn_credentials.public_key is 'ES256 public key of the credential, in uncompressed SEC1 encoding';
+comment on column webauthn_credentials.sign_count is 'Last known value of the signature counter of the authenticator, used to detect cloned authenticators';
+comment on column webauthn_credentials.name is 'Name of the credential chosen by the user, e.g. the name of the security key';
+comment on column webauthn_credentials.created_at is 'The time when the credential was registered';
+comment on column webauthn_credentials.last_used_at is 'The last time the credential was used for a verification';
+
+-- safety-assured:start
+-- `webauthn_credentials` is a brand new table without any rows yet, so a
+-- non-concurrent index build completes instantly.
+CREATE INDEX IF NOT EXISTS webauthn_credentials_user_id_index
+ON webauthn_credentials (user_id);
+-- safety-assured:end
+
+CREATE TABLE recovery_codes (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code_hash BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, code_hash)
+);
+
+comment on table recovery_codes is 'Unused single-use recovery codes, which can be used instead of a second factor. Codes are deleted once they are used.';
+comment on column recovery_codes.user_id is 'Crates.io user ID foreign key';
+comment on column recovery_codes.code_hash is 'SHA-256 hash of the normalized recovery code';
+comment on column recovery_codes.created_at is 'The time when the recovery code was generated';
+
+ALTER TABLE crates ADD COLUMN require_owner_2fa BOOLEAN NOT NULL DEFAULT FALSE;
+COMMENT ON COLUMN crates.require_owner_2fa IS 'When true, all owners of this crate must have two-factor authentication enabled';
@@ -0,0 +1,2 @@
+DROP TABLE client_downloads;
+DROP TABLE cargo_version_downloads;
@@ -0,0 +1,31 @@
+CREATE TABLE cargo_version_downloads (
+    crate_id INTEGER NOT NULL REFERENCES crates (id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    cargo_version VARCHAR NOT NULL,
+    downloads INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (crate_id, date, cargo_version)
+);
+
+comment on table cargo_version_downloads is 'Number of downloads per crate and day, broken down by the `major.minor` version of cargo that downloaded the crate files';
+comment on column cargo_version_downloads.crate_id is 'Crate ID foreign key';
+comment on column cargo_version_downloads.date is 'The day on which the crate files were downloaded';
+comment on column cargo_version_downloads.cargo_version is 'The `major.minor` version of cargo, as reported in the user agent';
+comment on column cargo_version_downloads.downloads is 'The number of downloads';
+
+CREATE INDEX cargo_version_downloads_date_idx ON cargo_version_downloads (date);
+
+CREATE TABLE client_downloads (
+    crate_id INTEGER NOT NULL REFERENCES crates (id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    client INTEGER NOT NULL,
+    downloads INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (crate_id, date, client)
+);
+
+comment on table client_downloads is 'Number of downloads per crate and day, broken down by the class of client that downloaded the crate files';
+comment on column client_downloads.crate_id is 'Crate ID foreign key';
+comment on column client_downloads.date is 'The day on which the crate files were downloaded';
+comment on column client_downloads.client is 'The class of client: 0 = cargo, 1 = cargo on a CI service, 2 = mirroring tool, 3 = other';
+comment on column client_downloads.downloads is 'The number of downloads. Downloads by mirroring tools are not included in the regular download counts.';
+
+CREATE INDEX client_downloads_date_idx ON client_downloads (date);
@@ -0,0 +1,44 @@
+CREATE TABLE advisories (
+    id TEXT PRIMARY KEY,
+    crate_name TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    date DATE NOT NULL,
+    url TEXT,
+    aliases TEXT[] NOT NULL DEFAULT '{}',
+    informational TEXT,
+    withdrawn DATE,
+    patched TEXT[] NOT NULL DEFAULT '{}',
+    unaffected TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+comment on table advisories is 'Security advisories imported from a RustSec-format advisory database';
+comment on column advisories.id is 'The ID of the advisory, e.g. `RUSTSEC-2019-0001`';
+comment on column advisories.crate_name is 'Name of the affected crate. The crate does not necessarily exist on this registry.';
+comment on column advisories.title is 'One-line summary of the advisory';
+comment on column advisories.description is 'Markdown description of the advisory';
+comment on column advisories.date is 'The date on which the advisory was reported';
+comment on column advisories.url is 'URL with more information about the advisory';
+comment on column advisories.aliases is 'Other identifiers of the vulnerability, e.g. CVE IDs';
+comment on column advisories.informational is 'The kind of informational advisory (e.g. `unmaintained`), or NULL for vulnerabilities';
+comment on column advisories.withdrawn is 'The date on which the advisory was withdrawn, if it was. Withdrawn advisories are not linked to any versions.';
+comment on column advisories.patched is 'Version requirements matching the versions that contain a fix';
+comment on column advisories.unaffected is 'Version requirements matching the versions that were never affected';
+comment on column advisories.created_at is 'Date and time when the advisory was first imported';
+comment on column advisories.updated_at is 'Date and time when the advisory was last imported';
+
+CREATE INDEX advisories_crate_name_index ON advisories (crate_name);
+
+CREATE TABLE version_advisories (
+    version_id INTEGER NOT NULL REFERENCES versions ON DELETE CASCADE,
+    advisory_id TEXT NOT NULL REFERENCES advisories ON DELETE CASCADE,
+    PRIMARY KEY (version_id, advisory_id)
+);
+
+comment on table version_advisories is 'Links between versions and the security advisories that affect them';
+comment on column version_advisories.version_id is 'Unique identifier of the affected version';
+comment on column version_advisories.advisory_id is 'ID of the advisory';
+
+CREATE INDEX version_advisories_advisory_id_index ON version_advisories (advisory_id);
@@ -0,0 +1,8 @@
+ALTER TABLE crates
+    ADD COLUMN deprecated_at TIMESTAMPTZ,
+    ADD COLUMN deprecation_message VARCHAR,
+    ADD COLUMN successor VARCHAR;
+
+COMMENT ON COLUMN crates.deprecated_at IS 'The time when the owners marked this crate as deprecated, or NULL if it is not deprecated';
+COMMENT ON COLUMN crates.deprecation_message IS 'Optional message from the owners explaining the deprecation';
+COMMENT ON COLUMN crates.successor IS 'Optional name of the crate that the owners recommend using instead';
@@ -0,0 +1 @@
+DROP TABLE version_compatibility_reports;
@@ -0,0 +1,10 @@
+CREATE TABLE version_compatibility_reports (
+    version_id INTEGER NOT NULL PRIMARY KEY REFERENCES versions (id) ON DELETE CASCADE,
+    report JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE version_compatibility_reports IS 'Comparisons of the features, dependencies and `rust-version` of versions with their previous semver-compatible version';
+COMMENT ON COLUMN version_compatibility_reports.version_id IS 'The version that was checked';
+COMMENT ON COLUMN version_compatibility_reports.report IS 'The changes compared to the previous compatible version, and warnings about changes that are likely to break users of the crate';
+COMMENT ON COLUMN version_compatibility_reports.created_at IS 'The time when the version was checked';
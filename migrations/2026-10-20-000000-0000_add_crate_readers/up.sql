@@ -0,0 +1,13 @@
+CREATE TABLE crate_readers (
+    crate_id INTEGER NOT NULL REFERENCES crates (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (crate_id, user_id)
+);
+
+CREATE INDEX crate_readers_user_id_idx ON crate_readers (user_id);
+
+COMMENT ON TABLE crate_readers IS 'Users that are allowed to read access-controlled crates in private registry mode. Crates without any readers can be read by all authenticated users.';
+COMMENT ON COLUMN crate_readers.crate_id IS 'The access-controlled crate';
+COMMENT ON COLUMN crate_readers.user_id IS 'The user that is allowed to read the crate';
+COMMENT ON COLUMN crate_readers.created_at IS 'The time when read access was granted';
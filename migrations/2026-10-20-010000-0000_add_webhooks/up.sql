@@ -0,0 +1,48 @@
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    crate_id INTEGER REFERENCES crates (id) ON DELETE CASCADE,
+    url VARCHAR NOT NULL,
+    encrypted_secret BYTEA NOT NULL,
+    events TEXT[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX webhooks_user_id_idx ON webhooks (user_id);
+CREATE INDEX webhooks_crate_id_idx ON webhooks (crate_id);
+
+COMMENT ON TABLE webhooks IS 'Webhook subscriptions of users for events of the crates they own';
+COMMENT ON COLUMN webhooks.id IS 'Unique identifier of the webhook';
+COMMENT ON COLUMN webhooks.user_id IS 'The user that created the webhook';
+COMMENT ON COLUMN webhooks.crate_id IS 'The crate whose events are delivered, or NULL for the events of all crates owned by the user';
+COMMENT ON COLUMN webhooks.url IS 'The URL that the events are sent to';
+COMMENT ON COLUMN webhooks.encrypted_secret IS 'Encrypted secret that is used to sign the payloads';
+COMMENT ON COLUMN webhooks.events IS 'The types of events that are delivered, e.g. `publish` or `yank`';
+COMMENT ON COLUMN webhooks.created_at IS 'The time when the webhook was created';
+
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE,
+    event VARCHAR NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    error VARCHAR,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_attempt_at TIMESTAMPTZ,
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX webhook_deliveries_webhook_id_idx ON webhook_deliveries (webhook_id);
+
+COMMENT ON TABLE webhook_deliveries IS 'Log of the events that were sent, or are about to be sent, to webhooks';
+COMMENT ON COLUMN webhook_deliveries.id IS 'Unique identifier of the delivery, also sent in the `X-Crates-Io-Delivery` header';
+COMMENT ON COLUMN webhook_deliveries.webhook_id IS 'The webhook that the event is sent to';
+COMMENT ON COLUMN webhook_deliveries.event IS 'The type of the event, e.g. `publish` or `yank`';
+COMMENT ON COLUMN webhook_deliveries.payload IS 'The JSON payload that is sent to the webhook';
+COMMENT ON COLUMN webhook_deliveries.attempts IS 'The number of delivery attempts so far';
+COMMENT ON COLUMN webhook_deliveries.response_status IS 'The HTTP status code of the response to the last attempt, if a response was received';
+COMMENT ON COLUMN webhook_deliveries.error IS 'The error of the last attempt, if it failed';
+COMMENT ON COLUMN webhook_deliveries.created_at IS 'The time when the event happened';
+COMMENT ON COLUMN webhook_deliveries.last_attempt_at IS 'The time of the last delivery attempt';
+COMMENT ON COLUMN webhook_deliveries.delivered_at IS 'The time when the event was delivered successfully';
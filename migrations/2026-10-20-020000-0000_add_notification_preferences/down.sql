@@ -0,0 +1,3 @@
+DROP TABLE pending_publish_notifications;
+DROP TABLE notification_settings;
+DROP TABLE notification_preferences;
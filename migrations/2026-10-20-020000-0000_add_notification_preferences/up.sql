@@ -0,0 +1,45 @@
+CREATE TABLE notification_preferences (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    category VARCHAR NOT NULL,
+    crate_id INTEGER REFERENCES crates (id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX notification_preferences_user_id_category_crate_id_idx
+    ON notification_preferences (user_id, category, crate_id) NULLS NOT DISTINCT;
+CREATE INDEX notification_preferences_crate_id_idx ON notification_preferences (crate_id);
+
+COMMENT ON TABLE notification_preferences IS 'Opt-ins and opt-outs of users for the categories of emails that are not security-relevant';
+COMMENT ON COLUMN notification_preferences.id IS 'Unique identifier of the preference';
+COMMENT ON COLUMN notification_preferences.user_id IS 'The user that the preference belongs to';
+COMMENT ON COLUMN notification_preferences.category IS 'The category of emails, e.g. `publish` or `owner_invite`';
+COMMENT ON COLUMN notification_preferences.crate_id IS 'The crate that the preference is restricted to, or NULL for the preference of the whole category. Crate-specific preferences take precedence.';
+COMMENT ON COLUMN notification_preferences.enabled IS 'Whether emails of the category are sent to the user';
+COMMENT ON COLUMN notification_preferences.updated_at IS 'The time when the preference was last changed';
+
+CREATE TABLE notification_settings (
+    user_id INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    publish_delivery VARCHAR NOT NULL DEFAULT 'immediate',
+    unsubscribe_token VARCHAR NOT NULL UNIQUE
+);
+
+COMMENT ON TABLE notification_settings IS 'Email notification settings of users. Users without a row use the defaults.';
+COMMENT ON COLUMN notification_settings.user_id IS 'The user that the settings belong to';
+COMMENT ON COLUMN notification_settings.publish_delivery IS 'How publish notifications are delivered: `immediate`, or batched into a `daily` or `weekly` digest';
+COMMENT ON COLUMN notification_settings.unsubscribe_token IS 'Secret token of the one-click unsubscribe links in the emails sent to the user';
+
+CREATE TABLE pending_publish_notifications (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    version_id INTEGER NOT NULL REFERENCES versions (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, version_id)
+);
+
+CREATE INDEX pending_publish_notifications_version_id_idx ON pending_publish_notifications (version_id);
+
+COMMENT ON TABLE pending_publish_notifications IS 'Publish notifications that are waiting to be sent as part of a digest email';
+COMMENT ON COLUMN pending_publish_notifications.user_id IS 'The user that the notification is sent to';
+COMMENT ON COLUMN pending_publish_notifications.version_id IS 'The version that was published';
+COMMENT ON COLUMN pending_publish_notifications.created_at IS 'The time when the notification was queued';
@@ -0,0 +1,30 @@
+CREATE OR REPLACE FUNCTION update_num_versions_from_versions() RETURNS TRIGGER AS $$
+BEGIN
+    IF (TG_OP = 'INSERT') THEN
+        INSERT INTO default_versions (crate_id, version_id, num_versions)
+        VALUES (NEW.crate_id, NEW.id, 1)
+        ON CONFLICT (crate_id) DO UPDATE
+        SET num_versions = default_versions.num_versions + 1;
+        RETURN NEW;
+    ELSIF (TG_OP = 'DELETE') THEN
+        UPDATE default_versions
+        SET num_versions = num_versions - 1
+        WHERE crate_id = OLD.crate_id;
+        RETURN OLD;
+    END IF;
+END
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_num_versions_from_versions ON versions;
+CREATE TRIGGER trigger_update_num_versions_from_versions
+     AFTER INSERT OR DELETE ON versions
+     FOR EACH ROW
+     EXECUTE PROCEDURE update_num_versions_from_versions();
+
+UPDATE default_versions
+SET num_versions = (
+    SELECT count(*)
+    FROM versions
+    WHERE versions.crate_id = default_versions.crate_id
+)
+WHERE crate_id IN (SELECT crate_id FROM versions WHERE staged_at IS NOT NULL);
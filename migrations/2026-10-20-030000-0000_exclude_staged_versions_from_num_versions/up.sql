@@ -0,0 +1,46 @@
+CREATE OR REPLACE FUNCTION update_num_versions_from_versions() RETURNS TRIGGER AS $$
+BEGIN
+    IF (TG_OP = 'INSERT') THEN
+        -- Staged versions are only counted once they are promoted
+        INSERT INTO default_versions (crate_id, version_id, num_versions)
+        VALUES (NEW.crate_id, NEW.id, CASE WHEN NEW.staged_at IS NULL THEN 1 ELSE 0 END)
+        ON CONFLICT (crate_id) DO UPDATE
+        SET num_versions = default_versions.num_versions + EXCLUDED.num_versions;
+        RETURN NEW;
+    ELSIF (TG_OP = 'UPDATE') THEN
+        IF (OLD.staged_at IS NOT NULL AND NEW.staged_at IS NULL) THEN
+            UPDATE default_versions
+            SET num_versions = num_versions + 1
+            WHERE crate_id = NEW.crate_id;
+        ELSIF (OLD.staged_at IS NULL AND NEW.staged_at IS NOT NULL) THEN
+            UPDATE default_versions
+            SET num_versions = num_versions - 1
+            WHERE crate_id = NEW.crate_id;
+        END IF;
+        RETURN NEW;
+    ELSIF (TG_OP = 'DELETE') THEN
+        IF (OLD.staged_at IS NULL) THEN
+            UPDATE default_versions
+            SET num_versions = num_versions - 1
+            WHERE crate_id = OLD.crate_id;
+        END IF;
+        RETURN OLD;
+    END IF;
+END
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_num_versions_from_versions ON versions;
+CREATE TRIGGER trigger_update_num_versions_from_versions
+     AFTER INSERT OR DELETE OR UPDATE OF staged_at ON versions
+     FOR EACH ROW
+     EXECUTE PROCEDURE update_num_versions_from_versions();
+
+-- Fix the counts of crates that currently have staged versions
+UPDATE default_versions
+SET num_versions = (
+    SELECT count(*)
+    FROM versions
+    WHERE versions.crate_id = default_versions.crate_id
+      AND versions.staged_at IS NULL
+)
+WHERE crate_id IN (SELECT crate_id FROM versions WHERE staged_at IS NOT NULL);
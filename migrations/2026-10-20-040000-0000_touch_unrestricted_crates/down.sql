@@ -0,0 +1,2 @@
+DROP TRIGGER trigger_touch_unrestricted_crate ON crate_readers;
+DROP FUNCTION touch_unrestricted_crate();
@@ -0,0 +1,22 @@
+-- Once the last reader of a crate is removed, the rows of the crate become
+-- visible in the database dumps. Bumping their `updated_at` makes sure that
+-- they are exported in the next delta dump. The dependencies and provenance
+-- of the versions use the `updated_at` column of the versions as their cursor.
+CREATE FUNCTION touch_unrestricted_crate() RETURNS TRIGGER AS $$
+BEGIN
+    -- Skip crates that are being deleted
+    IF EXISTS (SELECT 1 FROM crates WHERE id = OLD.crate_id)
+        AND NOT EXISTS (SELECT 1 FROM crate_readers WHERE crate_id = OLD.crate_id)
+    THEN
+        UPDATE crates SET updated_at = CURRENT_TIMESTAMP WHERE id = OLD.crate_id;
+        UPDATE versions SET updated_at = CURRENT_TIMESTAMP WHERE crate_id = OLD.crate_id;
+        UPDATE crate_owners SET updated_at = CURRENT_TIMESTAMP WHERE crate_id = OLD.crate_id;
+    END IF;
+    RETURN OLD;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_touch_unrestricted_crate
+    AFTER DELETE ON crate_readers
+    FOR EACH ROW
+    EXECUTE PROCEDURE touch_unrestricted_crate();
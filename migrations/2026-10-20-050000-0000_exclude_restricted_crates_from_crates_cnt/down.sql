@@ -0,0 +1,48 @@
+DROP TRIGGER trigger_update_crates_cnt_from_crate_readers ON crate_readers;
+DROP FUNCTION update_crates_cnt_from_crate_readers();
+
+CREATE OR REPLACE FUNCTION update_keywords_crates_cnt() RETURNS TRIGGER AS $$
+BEGIN
+    IF (TG_OP = 'INSERT') THEN
+        UPDATE keywords SET crates_cnt = crates_cnt + 1 WHERE id = NEW.keyword_id;
+        RETURN NEW;
+    ELSIF (TG_OP = 'DELETE') THEN
+        UPDATE keywords SET crates_cnt = crates_cnt - 1 WHERE id = OLD.keyword_id;
+        RETURN OLD;
+    END IF;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION update_categories_crates_cnt() RETURNS TRIGGER AS $$
+BEGIN
+    IF (TG_OP = 'INSERT') THEN
+        UPDATE categories SET crates_cnt = crates_cnt + 1 WHERE id = NEW.category_id;
+        RETURN NEW;
+    ELSIF (TG_OP = 'DELETE') THEN
+        UPDATE categories SET crates_cnt = crates_cnt - 1 WHERE id = OLD.category_id;
+        RETURN OLD;
+    END IF;
+END
+$$ LANGUAGE plpgsql;
+
+UPDATE keywords
+SET crates_cnt = (
+    SELECT count(*)
+    FROM crates_keywords
+    WHERE crates_keywords.keyword_id = keywords.id
+)
+WHERE id IN (
+    SELECT keyword_id FROM crates_keywords
+    WHERE crate_id IN (SELECT crate_id FROM crate_readers)
+);
+
+UPDATE categories
+SET crates_cnt = (
+    SELECT count(*)
+    FROM crates_categories
+    WHERE crates_categories.category_id = categories.id
+)
+WHERE id IN (
+    SELECT category_id FROM crates_categories
+    WHERE crate_id IN (SELECT crate_id FROM crate_readers)
+);
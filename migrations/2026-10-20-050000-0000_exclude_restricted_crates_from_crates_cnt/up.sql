@@ -0,0 +1,90 @@
+-- Crates with restricted read access are not counted in the `crates_cnt`
+-- columns of their keywords and categories.
+CREATE OR REPLACE FUNCTION update_keywords_crates_cnt() RETURNS TRIGGER AS $$
+BEGIN
+    IF (TG_OP = 'INSERT') THEN
+        IF NOT EXISTS (SELECT 1 FROM crate_readers WHERE crate_id = NEW.crate_id) THEN
+            UPDATE keywords SET crates_cnt = crates_cnt + 1 WHERE id = NEW.keyword_id;
+        END IF;
+        RETURN NEW;
+    ELSIF (TG_OP = 'DELETE') THEN
+        IF NOT EXISTS (SELECT 1 FROM crate_readers WHERE crate_id = OLD.crate_id) THEN
+            UPDATE keywords SET crates_cnt = crates_cnt - 1 WHERE id = OLD.keyword_id;
+        END IF;
+        RETURN OLD;
+    END IF;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION update_categories_crates_cnt() RETURNS TRIGGER AS $$
+BEGIN
+    IF (TG_OP = 'INSERT') THEN
+        IF NOT EXISTS (SELECT 1 FROM crate_readers WHERE crate_id = NEW.crate_id) THEN
+            UPDATE categories SET crates_cnt = crates_cnt + 1 WHERE id = NEW.category_id;
+        END IF;
+        RETURN NEW;
+    ELSIF (TG_OP = 'DELETE') THEN
+        IF NOT EXISTS (SELECT 1 FROM crate_readers WHERE crate_id = OLD.crate_id) THEN
+            UPDATE categories SET crates_cnt = crates_cnt - 1 WHERE id = OLD.category_id;
+        END IF;
+        RETURN OLD;
+    END IF;
+END
+$$ LANGUAGE plpgsql;
+
+-- Adding the first reader to a crate removes it from the counts, and removing
+-- the last reader adds it back.
+CREATE FUNCTION update_crates_cnt_from_crate_readers() RETURNS TRIGGER AS $$
+BEGIN
+    IF (TG_OP = 'INSERT') THEN
+        IF NOT EXISTS (SELECT 1 FROM crate_readers WHERE crate_id = NEW.crate_id) THEN
+            UPDATE keywords SET crates_cnt = crates_cnt - 1
+            WHERE id IN (SELECT keyword_id FROM crates_keywords WHERE crate_id = NEW.crate_id);
+            UPDATE categories SET crates_cnt = crates_cnt - 1
+            WHERE id IN (SELECT category_id FROM crates_categories WHERE crate_id = NEW.crate_id);
+        END IF;
+        RETURN NEW;
+    ELSIF (TG_OP = 'DELETE') THEN
+        IF NOT EXISTS (
+            SELECT 1 FROM crate_readers
+            WHERE crate_id = OLD.crate_id AND user_id <> OLD.user_id
+        ) THEN
+            UPDATE keywords SET crates_cnt = crates_cnt + 1
+            WHERE id IN (SELECT keyword_id FROM crates_keywords WHERE crate_id = OLD.crate_id);
+            UPDATE categories SET crates_cnt = crates_cnt + 1
+            WHERE id IN (SELECT category_id FROM crates_categories WHERE crate_id = OLD.crate_id);
+        END IF;
+        RETURN OLD;
+    END IF;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_update_crates_cnt_from_crate_readers
+    BEFORE INSERT OR DELETE ON crate_readers
+    FOR EACH ROW
+    EXECUTE PROCEDURE update_crates_cnt_from_crate_readers();
+
+-- Fix the counts of keywords and categories of currently restricted crates
+UPDATE keywords
+SET crates_cnt = (
+    SELECT count(*)
+    FROM crates_keywords
+    WHERE crates_keywords.keyword_id = keywords.id
+      AND crates_keywords.crate_id NOT IN (SELECT crate_id FROM crate_readers)
+)
+WHERE id IN (
+    SELECT keyword_id FROM crates_keywords
+    WHERE crate_id IN (SELECT crate_id FROM crate_readers)
+);
+
+UPDATE categories
+SET crates_cnt = (
+    SELECT count(*)
+    FROM crates_categories
+    WHERE crates_categories.category_id = categories.id
+      AND crates_categories.crate_id NOT IN (SELECT crate_id FROM crate_readers)
+)
+WHERE id IN (
+    SELECT category_id FROM crates_categories
+    WHERE crate_id IN (SELECT crate_id FROM crate_readers)
+);
@@ -0,0 +1 @@
+ALTER TABLE linked_identities DROP COLUMN encrypted_refresh_token;
@@ -0,0 +1,3 @@
+ALTER TABLE linked_identities ADD COLUMN encrypted_refresh_token BYTEA;
+
+comment on column linked_identities.encrypted_refresh_token is 'Encrypted refresh token, used to replace the access token once it has expired';
@@ -1413,7 +1413,7 @@ export interface components {
             version_id: number;
         };
         /** @enum {string} */
-        EndpointScope: "publish-new" | "publish-update" | "trusted-publishing" | "yank" | "change-owners";
+        EndpointScope: "publish-new" | "publish-update" | "trusted-publishing" | "yank" | "change-owners" | "update-crate" | "update-version" | "rebuild-docs";
         GitHubConfig: {
             /** @example regex */
             crate: string;
@@ -5512,7 +5512,7 @@ export const pathsApiPrivateSessionAuthorizePostResponses200ContentApplicationJs
 export const pathsApiPrivateSessionAuthorizePostResponses200ContentApplicationJsonOneOf1StatusValues: ReadonlyArray<Extract<FlattenedDeepRequired<paths>["/api/private/session/authorize"]["post"]["responses"]["200"]["content"]["application/json"], {
     status: unknown;
 }>["status"]> = ["signup_required"];
-export const endpointScopeValues: ReadonlyArray<FlattenedDeepRequired<components>["schemas"]["EndpointScope"]> = ["publish-new", "publish-update", "trusted-publishing", "yank", "change-owners"];
+export const endpointScopeValues: ReadonlyArray<FlattenedDeepRequired<components>["schemas"]["EndpointScope"]> = ["publish-new", "publish-update", "trusted-publishing", "yank", "change-owners", "update-crate", "update-version", "rebuild-docs"];
 export const trustpubDataOneOf0ProviderValues: ReadonlyArray<Extract<FlattenedDeepRequired<components>["schemas"]["TrustpubData"], {
     provider: unknown;
 }>["provider"]> = ["github"];
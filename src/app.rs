@@ -9,12 +9,15 @@ use crate::email::Emails;
 use crate::metrics::{InstanceMetrics, ServiceMetrics};
 use crate::rate_limiter::{LimitedAction, RateLimiter, RateLimiterConfig};
 use crate::storage::{Storage, StorageConfig};
+use crate::util::oauth::OidcLoginClient;
 use axum::extract::{FromRef, FromRequestParts, State};
 use bon::Builder;
 use crates_io_github::GitHubClient;
+use crates_io_gitlab::GitLabClient;
 use crates_io_trustpub::github::GITHUB_ISSUER_URL;
 use crates_io_trustpub::gitlab::GITLAB_ISSUER_URL;
 use crates_io_trustpub::keystore::{OidcKeyStore, RealOidcKeyStore};
+use crates_io_trustpub::provider::provider_by_id;
 use deadpool_runtime::Runtime;
 use derive_more::Deref;
 use diesel_async::AsyncPgConnection;
@@ -46,6 +49,19 @@ pub struct App {
     pub github_oauth:
         BasicClient<EndpointSet, EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointSet>,
 
+    /// GitLab API client
+    pub gitlab: Arc<dyn GitLabClient>,
+
+    /// The GitLab OAuth2 configuration, used for linking GitLab accounts and
+    /// signing in with them
+    pub gitlab_oauth: Option<
+        BasicClient<EndpointSet, EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointSet>,
+    >,
+
+    /// Generic OpenID Connect providers that can be used to sign in
+    #[builder(default)]
+    pub oidc_login: Vec<OidcLoginClient>,
+
     /// OIDC key stores for "Trusted Publishing"
     ///
     /// This is a map of OIDC key stores, where the key is the issuer URL and
@@ -97,12 +113,64 @@ impl<S: app_builder::State> AppBuilder<S> {
         self.github_oauth(github_oauth)
     }
 
+    pub fn gitlab_oauth_from_config(
+        self,
+        config: &config::Server,
+    ) -> AppBuilder<app_builder::SetGitlabOauth<S>>
+    where
+        S::GitlabOauth: app_builder::IsUnset,
+    {
+        use oauth2::{AuthUrl, TokenUrl};
+
+        let gitlab_oauth = config.gitlab_oauth.as_ref().map(|gitlab_oauth| {
+            let auth_url = "https://gitlab.com/oauth/authorize";
+            let auth_url = AuthUrl::new(auth_url.into()).unwrap();
+            let token_url = "https://gitlab.com/oauth/token";
+            let token_url = TokenUrl::new(token_url.into()).unwrap();
+
+            BasicClient::new(gitlab_oauth.client_id.clone())
+                .set_client_secret(gitlab_oauth.client_secret.clone())
+                .set_auth_uri(auth_url)
+                .set_token_uri(token_url)
+                .set_redirect_uri(gitlab_oauth.redirect_url.clone())
+        });
+
+        self.maybe_gitlab_oauth(gitlab_oauth)
+    }
+
+    pub fn oidc_login_from_config(
+        self,
+        config: &config::Server,
+    ) -> AppBuilder<app_builder::SetOidcLogin<S>>
+    where
+        S::OidcLogin: app_builder::IsUnset,
+    {
+        let oidc_login = config
+            .oidc_login
+            .iter()
+            .map(|provider| OidcLoginClient {
+                id: provider.id.clone(),
+                name: provider.name.clone(),
+                oauth: BasicClient::new(provider.client_id.clone())
+                    .set_client_secret(provider.client_secret.clone())
+                    .set_auth_uri(provider.auth_url.clone())
+                    .set_token_uri(provider.token_url.clone())
+                    .set_redirect_uri(provider.redirect_url.clone()),
+                userinfo_url: provider.userinfo_url.clone(),
+            })
+            .collect();
+
+        self.oidc_login(oidc_login)
+    }
+
     /// Set the "Trusted Publishing" providers supported by the application.
     ///
     /// This method configures the OIDC key stores for the specified providers
     /// and expects a list of provider names as input.
     ///
-    /// Currently, "github" and "gitlab" are supported as providers.
+    /// Currently, "github" and "gitlab" are supported as providers, in
+    /// addition to the generic providers in [`crates_io_trustpub::provider::PROVIDERS`]
+    /// (e.g. "buildkite").
     pub fn trustpub_providers(
         self,
         providers: &[String],
@@ -122,9 +190,16 @@ impl<S: app_builder::State> AppBuilder<S> {
                     let key_store = RealOidcKeyStore::new(GITLAB_ISSUER_URL.into());
                     key_stores.insert(GITLAB_ISSUER_URL.into(), Box::new(key_store));
                 }
-                provider => {
-                    warn!("Unknown Trusted Publishing provider: {provider}");
-                }
+                provider => match provider_by_id(provider) {
+                    Some(provider) => {
+                        let issuer_url = provider.issuer_url();
+                        let key_store = RealOidcKeyStore::new(issuer_url.into());
+                        key_stores.insert(issuer_url.into(), Box::new(key_store));
+                    }
+                    None => {
+                        warn!("Unknown Trusted Publishing provider: {provider}");
+                    }
+                },
             }
         }
 
@@ -2,6 +2,7 @@ use crate::controllers;
 use crate::controllers::util::RequestPartsExt;
 use crate::middleware::log_request::RequestLogExt;
 use crate::models::token::{CrateScope, EndpointScope};
+use crate::models::two_factor::has_two_factor;
 use crate::models::{ApiToken, User};
 use crate::util::errors::{
     AppResult, BoxedAppError, InsecurelyGeneratedTokenRevoked, account_locked, custom, forbidden,
@@ -9,7 +10,7 @@ use crate::util::errors::{
 };
 use crate::util::token::HashedToken;
 use axum::extract::FromRequestParts;
-use chrono::Utc;
+use chrono::{DateTime, TimeDelta, Utc};
 use crates_io_session::SessionExtension;
 use diesel_async::AsyncPgConnection;
 use http::request::Parts;
@@ -17,6 +18,14 @@ use http::{StatusCode, header};
 use secrecy::{ExposeSecret, SecretString};
 use tracing::instrument;
 
+/// The session key that stores the user ID and the time of the last
+/// successful verification with a second factor, as `<user_id>:<timestamp>`.
+const SECOND_FACTOR_VERIFIED_AT: &str = "second_factor_verified_at";
+
+/// How long a verification with a second factor allows sensitive actions
+/// without verifying again.
+const SECOND_FACTOR_VERIFICATION_TTL: TimeDelta = TimeDelta::minutes(15);
+
 pub struct AuthHeader(SecretString);
 
 impl AuthHeader {
@@ -66,9 +75,10 @@ impl<S: Send + Sync> FromRequestParts<S> for AuthHeader {
 #[derive(Debug, Clone)]
 pub struct AuthCheck {
     allow_token: bool,
-    endpoint_scope: Option<EndpointScope>,
+    endpoint_scopes: Vec<EndpointScope>,
     crate_name: Option<String>,
     allow_any_crate_scope: bool,
+    require_second_factor: bool,
 }
 
 impl AuthCheck {
@@ -78,9 +88,10 @@ impl AuthCheck {
     pub fn default() -> Self {
         Self {
             allow_token: true,
-            endpoint_scope: None,
+            endpoint_scopes: Vec::new(),
             crate_name: None,
             allow_any_crate_scope: false,
+            require_second_factor: false,
         }
     }
 
@@ -88,27 +99,47 @@ impl AuthCheck {
     pub fn only_cookie() -> Self {
         Self {
             allow_token: false,
-            endpoint_scope: None,
+            endpoint_scopes: Vec::new(),
             crate_name: None,
             allow_any_crate_scope: false,
+            require_second_factor: false,
         }
     }
 
     pub fn with_endpoint_scope(&self, endpoint_scope: EndpointScope) -> Self {
         Self {
             allow_token: self.allow_token,
-            endpoint_scope: Some(endpoint_scope),
+            endpoint_scopes: vec![endpoint_scope],
             crate_name: self.crate_name.clone(),
             allow_any_crate_scope: self.allow_any_crate_scope,
+            require_second_factor: self.require_second_factor,
+        }
+    }
+
+    /// Additionally allows tokens with the given endpoint scope.
+    ///
+    /// Use this when an endpoint gained a dedicated endpoint scope, but tokens
+    /// with the endpoint scope that previously covered it should keep working.
+    pub fn or_endpoint_scope(&self, endpoint_scope: EndpointScope) -> Self {
+        let mut endpoint_scopes = self.endpoint_scopes.clone();
+        endpoint_scopes.push(endpoint_scope);
+
+        Self {
+            allow_token: self.allow_token,
+            endpoint_scopes,
+            crate_name: self.crate_name.clone(),
+            allow_any_crate_scope: self.allow_any_crate_scope,
+            require_second_factor: self.require_second_factor,
         }
     }
 
     pub fn for_crate(&self, crate_name: &str) -> Self {
         Self {
             allow_token: self.allow_token,
-            endpoint_scope: self.endpoint_scope,
+            endpoint_scopes: self.endpoint_scopes.clone(),
             crate_name: Some(crate_name.to_string()),
             allow_any_crate_scope: self.allow_any_crate_scope,
+            require_second_factor: self.require_second_factor,
         }
     }
 
@@ -119,9 +150,26 @@ impl AuthCheck {
     pub fn allow_any_crate_scope(&self) -> Self {
         Self {
             allow_token: self.allow_token,
-            endpoint_scope: self.endpoint_scope,
+            endpoint_scopes: self.endpoint_scopes.clone(),
             crate_name: self.crate_name.clone(),
             allow_any_crate_scope: true,
+            require_second_factor: self.require_second_factor,
+        }
+    }
+
+    /// Requires users that enabled two-factor authentication to have
+    /// recently verified their second factor in the current session.
+    ///
+    /// Use this for sensitive actions that a stolen session cookie should
+    /// not be enough for. Requests authenticated with an API token are not
+    /// affected.
+    pub fn require_second_factor(&self) -> Self {
+        Self {
+            allow_token: self.allow_token,
+            endpoint_scopes: self.endpoint_scopes.clone(),
+            crate_name: self.crate_name.clone(),
+            allow_any_crate_scope: self.allow_any_crate_scope,
+            require_second_factor: true,
         }
     }
 
@@ -163,19 +211,40 @@ impl AuthCheck {
             }
         }
 
+        if self.require_second_factor
+            && let Authentication::Cookie(cookie) = &auth
+            && has_two_factor(conn, cookie.user.id).await?
+        {
+            let session = parts
+                .extensions()
+                .get::<SessionExtension>()
+                .expect("missing cookie session");
+
+            if second_factor_verified_until(session, cookie.user.id).is_none() {
+                let error_message = "No recent second factor verification";
+                parts.request_log().add("cause", error_message);
+
+                return Err(forbidden(
+                    "this action requires a recent verification with your second factor",
+                ));
+            }
+        }
+
         Ok(auth)
     }
 
     fn endpoint_scope_matches(&self, token_scopes: Option<&Vec<EndpointScope>>) -> bool {
-        match (&token_scopes, &self.endpoint_scope) {
+        match (&token_scopes, self.endpoint_scopes.as_slice()) {
             // The token is a legacy token.
             (None, _) => true,
 
             // The token is NOT a legacy token, and the endpoint only allows legacy tokens.
-            (Some(_), None) => false,
+            (Some(_), []) => false,
 
-            // The token is NOT a legacy token, and the endpoint allows a certain endpoint scope or a legacy token.
-            (Some(token_scopes), Some(endpoint_scope)) => token_scopes.contains(endpoint_scope),
+            // The token is NOT a legacy token, and the endpoint allows certain endpoint scopes or a legacy token.
+            (Some(token_scopes), endpoint_scopes) => endpoint_scopes
+                .iter()
+                .any(|endpoint_scope| token_scopes.contains(endpoint_scope)),
         }
     }
 
@@ -316,8 +385,15 @@ async fn authenticate_via_token(
     Ok(Some(TokenAuthentication { user, token }))
 }
 
+/// Authenticates the request via session cookie or API token.
+///
+/// In contrast to [`AuthCheck::check`], this does not check the endpoint and
+/// crate scopes of API tokens.
 #[instrument(skip_all)]
-async fn authenticate(parts: &Parts, conn: &mut AsyncPgConnection) -> AppResult<Authentication> {
+pub async fn authenticate(
+    parts: &Parts,
+    conn: &mut AsyncPgConnection,
+) -> AppResult<Authentication> {
     controllers::util::verify_origin(parts)?;
 
     match authenticate_via_cookie(parts, conn).await {
@@ -345,6 +421,41 @@ async fn authenticate(parts: &Parts, conn: &mut AsyncPgConnection) -> AppResult<
     return Err(forbidden("this action requires authentication"));
 }
 
+/// Records a successful verification with a second factor of the user in the
+/// session.
+///
+/// Returns the time until which the verification is considered recent.
+pub fn mark_second_factor_verified(session: &SessionExtension, user_id: i32) -> DateTime<Utc> {
+    let now = Utc::now();
+    let value = format!("{user_id}:{}", now.timestamp());
+    session.insert(SECOND_FACTOR_VERIFIED_AT.into(), value);
+    now + SECOND_FACTOR_VERIFICATION_TTL
+}
+
+/// Returns the time until which the last verification with a second factor
+/// of the user in this session is considered recent, or `None` if there was
+/// no such verification or it has expired.
+pub fn second_factor_verified_until(
+    session: &SessionExtension,
+    user_id: i32,
+) -> Option<DateTime<Utc>> {
+    let value = session.get(SECOND_FACTOR_VERIFIED_AT)?;
+    let (verified_user_id, verified_at) = value.split_once(':')?;
+    if verified_user_id.parse::<i32>().ok()? != user_id {
+        return None;
+    }
+
+    let verified_at = DateTime::from_timestamp(verified_at.parse().ok()?, 0)?;
+    let verified_until = verified_at + SECOND_FACTOR_VERIFICATION_TTL;
+    (verified_until > Utc::now()).then_some(verified_until)
+}
+
+/// Removes the verification with a second factor from the session, so that it
+/// does not carry over to the next user that signs in.
+pub fn clear_second_factor_verification(session: &SessionExtension) {
+    session.remove(SECOND_FACTOR_VERIFIED_AT);
+}
+
 fn ensure_not_locked(user: &User) -> AppResult<()> {
     if let Some(reason) = &user.account_lock_reason {
         let still_locked = user
@@ -458,4 +569,46 @@ mod tests {
         assert!(!auth_check.crate_scope_matches(Some(&vec![cs("anyhow")])));
         assert!(!auth_check.crate_scope_matches(Some(&vec![cs("actix-*")])));
     }
+
+    #[test]
+    fn update_version_endpoint() {
+        let auth_check = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::UpdateVersion)
+            .or_endpoint_scope(EndpointScope::Yank)
+            .for_crate("tokio-console");
+
+        assert!(auth_check.endpoint_scope_matches(None));
+        assert!(!auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::PublishNew])));
+        assert!(!auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::PublishUpdate])));
+        assert!(auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::Yank])));
+        assert!(auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::UpdateVersion])));
+        assert!(!auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::UpdateCrate])));
+        assert!(!auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::ChangeOwners])));
+
+        assert!(auth_check.crate_scope_matches(None));
+        assert!(auth_check.crate_scope_matches(Some(&vec![cs("tokio-console")])));
+        assert!(auth_check.crate_scope_matches(Some(&vec![cs("tokio-*")])));
+        assert!(!auth_check.crate_scope_matches(Some(&vec![cs("anyhow")])));
+        assert!(!auth_check.crate_scope_matches(Some(&vec![cs("actix-*")])));
+    }
+
+    #[test]
+    fn rebuild_docs_endpoint() {
+        let auth_check = AuthCheck::default()
+            .with_endpoint_scope(EndpointScope::RebuildDocs)
+            .for_crate("tokio-console");
+
+        assert!(auth_check.endpoint_scope_matches(None));
+        assert!(!auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::PublishNew])));
+        assert!(!auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::PublishUpdate])));
+        assert!(!auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::Yank])));
+        assert!(auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::RebuildDocs])));
+        assert!(!auth_check.endpoint_scope_matches(Some(&vec![EndpointScope::UpdateVersion])));
+
+        assert!(auth_check.crate_scope_matches(None));
+        assert!(auth_check.crate_scope_matches(Some(&vec![cs("tokio-console")])));
+        assert!(auth_check.crate_scope_matches(Some(&vec![cs("tokio-*")])));
+        assert!(!auth_check.crate_scope_matches(Some(&vec![cs("anyhow")])));
+        assert!(!auth_check.crate_scope_matches(Some(&vec![cs("actix-*")])));
+    }
 }
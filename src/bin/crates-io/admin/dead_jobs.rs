@@ -0,0 +1,176 @@
+use super::dialoguer;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use crates_io::db;
+use crates_io::schema::background_jobs_dead;
+use crates_io_worker::dead_jobs;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "dead-jobs",
+    about = "Inspect, requeue or discard background jobs that have exhausted their retries."
+)]
+pub enum Command {
+    /// List the dead jobs, most recently failed first.
+    List {
+        #[command(flatten)]
+        filter: Filter,
+
+        /// Also print the serialized data of the jobs.
+        #[arg(long)]
+        data: bool,
+    },
+    /// Move dead jobs back into the queue, with a reset retry counter.
+    Requeue(Opts),
+    /// Permanently delete dead jobs without attempting them again.
+    Discard(Opts),
+}
+
+/// Options for the `requeue` and `discard` subcommands.
+#[derive(clap::Args, Debug)]
+pub struct Opts {
+    #[command(flatten)]
+    filter: Filter,
+
+    /// Ids of the dead jobs. Required unless `--all` or `--job-type` is given.
+    #[arg(
+        value_name = "ID",
+        required_unless_present_any = ["all", "job_type"],
+        conflicts_with = "all"
+    )]
+    ids: Vec<i64>,
+
+    /// Process all dead jobs (of the given `--job-type`, if any).
+    #[arg(long)]
+    all: bool,
+
+    /// Don't ask for confirmation: yes, we are sure. Best for scripting.
+    #[arg(short, long)]
+    yes: bool,
+}
+
+/// Restricts the dead jobs to a specific job type.
+#[derive(clap::Args, Debug)]
+pub struct Filter {
+    /// Only consider dead jobs of this job type.
+    #[arg(long)]
+    job_type: Option<String>,
+}
+
+#[derive(Debug, HasQuery)]
+#[diesel(table_name = background_jobs_dead)]
+struct DeadJob {
+    id: i64,
+    job_type: String,
+    data: serde_json::Value,
+    retries: i32,
+    created_at: DateTime<Utc>,
+    failed_at: DateTime<Utc>,
+    last_error: Option<String>,
+}
+
+pub async fn run(command: Command) -> anyhow::Result<()> {
+    let mut conn = db::oneoff_connection()
+        .await
+        .context("Failed to connect to the database")?;
+
+    match command {
+        Command::List { filter, data } => {
+            let dead_jobs = load_dead_jobs(&filter, &[], &mut conn).await?;
+            if dead_jobs.is_empty() {
+                println!("No dead jobs found.");
+            }
+
+            for job in dead_jobs {
+                print_dead_job(&job, data);
+            }
+        }
+        Command::Requeue(opts) => {
+            let ids = confirm(&opts, "requeue", &mut conn).await?;
+            if !ids.is_empty() {
+                let requeued = dead_jobs::requeue(&mut conn, &ids).await?;
+                println!("Requeued {requeued} dead jobs.");
+            }
+        }
+        Command::Discard(opts) => {
+            let ids = confirm(&opts, "permanently discard", &mut conn).await?;
+            if !ids.is_empty() {
+                let discarded = dead_jobs::discard(&mut conn, &ids).await?;
+                println!("Discarded {discarded} dead jobs.");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the dead jobs matching the options, prints them and asks for
+/// confirmation. Returns the ids of the jobs to process, which is empty if
+/// there are no matching jobs or the operation was cancelled.
+async fn confirm(
+    opts: &Opts,
+    action: &str,
+    conn: &mut AsyncPgConnection,
+) -> anyhow::Result<Vec<i64>> {
+    let dead_jobs = load_dead_jobs(&opts.filter, &opts.ids, conn).await?;
+    if dead_jobs.is_empty() {
+        println!("No matching dead jobs found.");
+        return Ok(vec![]);
+    }
+
+    for job in &dead_jobs {
+        print_dead_job(job, false);
+    }
+
+    let prompt = format!("Do you want to {action} these {} jobs?", dead_jobs.len());
+    if !opts.yes && !dialoguer::confirm(prompt).await? {
+        return Ok(vec![]);
+    }
+
+    Ok(dead_jobs.into_iter().map(|job| job.id).collect())
+}
+
+async fn load_dead_jobs(
+    filter: &Filter,
+    ids: &[i64],
+    conn: &mut AsyncPgConnection,
+) -> anyhow::Result<Vec<DeadJob>> {
+    let mut query = DeadJob::query()
+        .order(background_jobs_dead::failed_at.desc())
+        .into_boxed();
+
+    if let Some(job_type) = &filter.job_type {
+        query = query.filter(background_jobs_dead::job_type.eq(job_type));
+    }
+
+    if !ids.is_empty() {
+        query = query.filter(background_jobs_dead::id.eq_any(ids));
+    }
+
+    query
+        .load(conn)
+        .await
+        .context("Failed to load dead jobs from the database")
+}
+
+fn print_dead_job(job: &DeadJob, with_data: bool) {
+    println!(
+        "{} {} (retried {} times, enqueued at {}, failed at {})",
+        job.id.to_string().bold(),
+        job.job_type,
+        job.retries,
+        job.created_at,
+        job.failed_at
+    );
+
+    if let Some(last_error) = &job.last_error {
+        println!("    {last_error}");
+    }
+
+    if with_data {
+        println!("    {}", job.data);
+    }
+}
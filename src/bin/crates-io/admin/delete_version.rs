@@ -154,6 +154,18 @@ pub async fn run(opts: Opts) -> anyhow::Result<()> {
                 paths.push(readme_key.path());
             }
         }
+
+        debug!(%crate_name, %version, "Deleting readme table of contents from S3");
+        let toc_key = StorageKey::for_readme_toc(crate_name, version);
+        match store.delete(&toc_key).await {
+            Err(object_store::Error::NotFound { .. }) => {}
+            Err(error) => {
+                warn!(%crate_name, %version, "Failed to delete readme table of contents from S3: {error}")
+            }
+            Ok(()) => {
+                paths.push(toc_key.path());
+            }
+        }
     }
 
     let job = if features.cache_tag_invalidations_enabled {
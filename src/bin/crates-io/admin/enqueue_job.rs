@@ -8,6 +8,7 @@ use crates_io_worker::BackgroundJob;
 use diesel::dsl::exists;
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
+use std::path::PathBuf;
 
 #[derive(clap::Parser, Debug)]
 #[command(
@@ -34,18 +35,31 @@ pub enum Command {
     CleanProcessedLogFiles,
     DailyDbMaintenance,
     DumpDb,
+    /// Create an incremental database dump with the changes since the
+    /// previous one
+    DumpDbDelta,
+    /// Delete staged versions that have not been promoted in time
+    ExpireStagedVersions,
     /// Generate OpenGraph images for the specified crates
     GenerateOgImage {
         /// Crate names to generate OpenGraph images for
         #[arg(required = true)]
         names: Vec<String>,
     },
+    /// Import security advisories from a local checkout of a RustSec-format
+    /// advisory database
+    ImportAdvisories {
+        /// Path to the checkout of the advisory database
+        path: PathBuf,
+    },
     IndexVersionDownloadsArchive,
     NormalizeIndex {
         #[arg(long = "dry-run")]
         dry_run: bool,
     },
     ProcessCdnLogQueue(jobs::ProcessCdnLogQueue),
+    /// Send the publish notification digests that are due
+    SendPublishNotificationDigests,
     SendTokenExpiryNotifications,
     SquashIndex,
     SyncAdmins {
@@ -107,11 +121,20 @@ pub async fn run(command: Command) -> Result<()> {
         Command::DumpDb => {
             jobs::DumpDb::default().enqueue(&conn).await?;
         }
+        Command::DumpDbDelta => {
+            jobs::DumpDbDelta.enqueue(&conn).await?;
+        }
+        Command::ExpireStagedVersions => {
+            jobs::ExpireStagedVersions.enqueue(&conn).await?;
+        }
         Command::GenerateOgImage { names } => {
             for name in names {
                 jobs::GenerateOgImage::new(name).enqueue(&conn).await?;
             }
         }
+        Command::ImportAdvisories { path } => {
+            jobs::ImportAdvisories::new(path).enqueue(&conn).await?;
+        }
         Command::IndexVersionDownloadsArchive => {
             jobs::IndexVersionDownloadsArchive.enqueue(&conn).await?;
         }
@@ -121,6 +144,9 @@ pub async fn run(command: Command) -> Result<()> {
         Command::ProcessCdnLogQueue(job) => {
             job.enqueue(&conn).await?;
         }
+        Command::SendPublishNotificationDigests => {
+            jobs::SendPublishNotificationDigests.enqueue(&conn).await?;
+        }
         Command::SendTokenExpiryNotifications => {
             jobs::SendTokenExpiryNotifications.enqueue(&conn).await?;
         }
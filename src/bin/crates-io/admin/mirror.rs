@@ -0,0 +1,132 @@
+use anyhow::Context;
+use crates_io::db;
+use crates_io::worker::jobs::MirrorCrates;
+use crates_io_worker::BackgroundJob;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+#[derive(clap::Parser, Debug)]
+#[command(
+    name = "mirror",
+    about = "Mirror crates from an upstream registry into this instance",
+    long_about = "Mirror crates from an upstream registry into this instance.\n\n\
+        Downloads all versions of the crates that are not available locally yet, \
+        and updates the yanked state of the previously mirrored versions."
+)]
+pub struct Opts {
+    /// Names of the crates to mirror
+    #[arg(required_unless_present = "lockfile")]
+    names: Vec<String>,
+
+    /// Mirror all registry packages of a `Cargo.lock` file
+    #[arg(long)]
+    lockfile: Option<PathBuf>,
+
+    /// URL of the sparse index of the upstream registry
+    #[arg(long, default_value = "https://index.crates.io/")]
+    index_url: String,
+}
+
+pub async fn run(opts: Opts) -> anyhow::Result<()> {
+    let mut names = opts.names.into_iter().collect::<BTreeSet<_>>();
+    if let Some(path) = &opts.lockfile {
+        names.extend(read_lockfile(path)?);
+    }
+
+    if names.is_empty() {
+        println!("No crates to mirror");
+        return Ok(());
+    }
+
+    let conn = db::oneoff_connection().await;
+    let conn = conn.context("Failed to connect to the database")?;
+
+    println!("Enqueueing mirror job for {} crates", names.len());
+    let names = names.into_iter().collect();
+    MirrorCrates::new(opts.index_url, names)
+        .enqueue(&conn)
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockfilePackage>,
+}
+
+#[derive(Deserialize)]
+struct LockfilePackage {
+    name: String,
+    source: Option<String>,
+}
+
+/// Returns the names of all packages in a `Cargo.lock` file that come from a
+/// registry, ignoring workspace members and git dependencies.
+fn read_lockfile(path: &PathBuf) -> anyhow::Result<BTreeSet<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    parse_lockfile(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn parse_lockfile(content: &str) -> anyhow::Result<BTreeSet<String>> {
+    let lockfile: Lockfile = toml::from_str(content)?;
+
+    let names = lockfile
+        .package
+        .into_iter()
+        .filter(|package| {
+            package.source.as_deref().is_some_and(|source| {
+                source.starts_with("registry+") || source.starts_with("sparse+")
+            })
+        })
+        .map(|package| package.name)
+        .collect();
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lockfile() {
+        let content = r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["serde", "tokio"]
+
+[[package]]
+name = "serde"
+version = "1.0.219"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "5f0e2c6ed6606019b4e29e69dbaba95b11854410e5347d525002456dbbb786b6"
+
+[[package]]
+name = "serde"
+version = "0.9.15"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "34b623917345a631dc9608d5194cc206b3fe6c3554cd1c75b937e55e285254af"
+
+[[package]]
+name = "tokio"
+version = "1.45.0"
+source = "sparse+https://index.crates.io/"
+
+[[package]]
+name = "patched"
+version = "0.1.0"
+source = "git+https://github.com/rust-lang/patched#abcdef"
+"#;
+
+        let names = parse_lockfile(content).unwrap();
+        assert_eq!(names.into_iter().collect::<Vec<_>>(), ["serde", "tokio"]);
+    }
+}
@@ -1,11 +1,13 @@
 mod analyze_crates;
 mod build_crate_zips;
+mod dead_jobs;
 mod default_versions;
 mod delete_crate;
 mod delete_version;
 mod dialoguer;
 mod enqueue_job;
 mod migrate;
+mod mirror;
 mod populate;
 mod render_og_images;
 mod render_readmes;
@@ -29,9 +31,12 @@ pub enum Command {
     TestEmail(test_email::Opts),
     VerifyToken(verify_token::Opts),
     Migrate(migrate::Opts),
+    Mirror(mirror::Opts),
     UploadIndex(upload_index::Opts),
     YankVersion(yank_version::Opts),
     #[clap(subcommand)]
+    DeadJobs(dead_jobs::Command),
+    #[clap(subcommand)]
     EnqueueJob(enqueue_job::Command),
     #[clap(subcommand)]
     DefaultVersions(default_versions::Command),
@@ -61,8 +66,10 @@ pub async fn run(command: Command) -> anyhow::Result<()> {
         Command::TestEmail(opts) => test_email::run(opts).await,
         Command::VerifyToken(opts) => verify_token::run(opts).await,
         Command::Migrate(opts) => migrate::run(opts).await,
+        Command::Mirror(opts) => mirror::run(opts).await,
         Command::UploadIndex(opts) => upload_index::run(opts).await,
         Command::YankVersion(opts) => yank_version::run(opts).await,
+        Command::DeadJobs(command) => dead_jobs::run(command).await,
         Command::EnqueueJob(command) => enqueue_job::run(command).await,
         Command::DefaultVersions(command) => default_versions::run(command).await,
         Command::ReverseDependencies(command) => reverse_dependencies::run(command).await,
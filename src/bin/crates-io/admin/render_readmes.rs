@@ -10,9 +10,10 @@ use tokio_util::io::StreamReader;
 
 use async_compression::tokio::bufread::GzipDecoder;
 use chrono::{NaiveDateTime, Utc};
+use crates_io::config::FeaturesConfig;
 use crates_io::storage::{Storage, StorageKey};
 use crates_io::tasks::spawn_blocking;
-use crates_io_markdown::text_to_html;
+use crates_io_markdown::{RenderOptions, RenderedReadme, render_readme};
 use crates_io_tarball::{Manifest, StringOrBool};
 use diesel::prelude::*;
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
@@ -53,6 +54,11 @@ pub async fn run(opts: Opts) -> anyhow::Result<()> {
 
     let storage = Arc::new(Storage::from_environment());
 
+    let features = FeaturesConfig::from_env().context("Failed to load features config")?;
+    let options = RenderOptions {
+        syntax_highlighting: features.readme_syntax_highlighting_enabled,
+    };
+
     let start_time = Utc::now();
 
     let older_than = if let Some(ref time) = opts.older_than {
@@ -119,13 +125,20 @@ pub async fn run(opts: Opts) -> anyhow::Result<()> {
             let storage = storage.clone();
             let handle = tokio::spawn(async move {
                 println!("[{}-{}] Rendering README...", krate_name, version.num);
-                let readme = get_readme(&storage, &client, &version, &krate_name).await?;
-                if !readme.is_empty() {
+                let readme = get_readme(&storage, &client, &version, &krate_name, options).await?;
+                if !readme.html.is_empty() {
                     let key = StorageKey::for_readme(&krate_name, &version.num);
                     storage
-                        .upload(&key, readme.into())
+                        .upload(&key, readme.html.into())
                         .await
                         .context("Failed to upload rendered README file to S3")?;
+
+                    let toc = serde_json::to_vec(&readme.table_of_contents)?;
+                    let key = StorageKey::for_readme_toc(&krate_name, &version.num);
+                    storage
+                        .upload(&key, toc.into())
+                        .await
+                        .context("Failed to upload README table of contents to S3")?;
                 }
 
                 Ok::<_, anyhow::Error>(())
@@ -150,7 +163,8 @@ async fn get_readme(
     client: &Client,
     version: &Version,
     krate_name: &str,
-) -> anyhow::Result<String> {
+    options: RenderOptions,
+) -> anyhow::Result<RenderedReadme> {
     let pkg_name = format!("{}-{}", krate_name, version.num);
 
     let key = StorageKey::for_crate_file(krate_name, &version.num);
@@ -175,13 +189,14 @@ async fn get_readme(
     let reader = StreamReader::new(reader);
     let reader = GzipDecoder::new(reader);
     let archive = Archive::new(reader);
-    render_pkg_readme(archive, &pkg_name).await
+    render_pkg_readme(archive, &pkg_name, options).await
 }
 
 async fn render_pkg_readme<R: AsyncRead + Unpin>(
     mut archive: Archive<R>,
     pkg_name: &str,
-) -> anyhow::Result<String> {
+    options: RenderOptions,
+) -> anyhow::Result<RenderedReadme> {
     let mut entries = archive.entries().context("Invalid tar archive entries")?;
 
     let manifest: Manifest = {
@@ -204,7 +219,7 @@ async fn render_pkg_readme<R: AsyncRead + Unpin>(
             .and_then(|r| r.as_ref().as_local());
 
         let readme_path = match readme {
-            Some(StringOrBool::Bool(bool)) if !(*bool) => return Ok("".to_string()),
+            Some(StringOrBool::Bool(bool)) if !(*bool) => return Ok(RenderedReadme::default()),
             Some(StringOrBool::String(path)) => PathBuf::from(path),
             _ => PathBuf::from("README.md"),
         };
@@ -225,7 +240,13 @@ async fn render_pkg_readme<R: AsyncRead + Unpin>(
                 .and_then(|p| p.repository.as_ref())
                 .and_then(|r| r.as_ref().as_local())
                 .map(|s| s.as_str());
-            text_to_html(&contents, &readme_path, repository, pkg_path_in_vcs)
+            render_readme(
+                &contents,
+                &readme_path,
+                repository,
+                pkg_path_in_vcs,
+                options,
+            )
         })
         .await?
     };
@@ -258,6 +279,7 @@ pub mod tests {
     use crates_io_tarball::TarballBuilder;
 
     use super::render_pkg_readme;
+    use crates_io_markdown::RenderOptions;
 
     #[tokio::test]
     async fn test_render_pkg_readme() {
@@ -274,10 +296,14 @@ readme = "README.md"
             .add_file("foo-0.0.1/README.md", b"readme")
             .build_unzipped();
 
-        let result = render_pkg_readme(tokio_tar::Archive::new(&*serialized_archive), "foo-0.0.1")
-            .await
-            .unwrap();
-        assert!(result.contains("readme"))
+        let result = render_pkg_readme(
+            tokio_tar::Archive::new(&*serialized_archive),
+            "foo-0.0.1",
+            RenderOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.html.contains("readme"))
     }
 
     #[tokio::test]
@@ -292,7 +318,12 @@ readme = "README.md"
             .build_unzipped();
 
         assert_err!(
-            render_pkg_readme(tokio_tar::Archive::new(&*serialized_archive), "foo-0.0.1").await
+            render_pkg_readme(
+                tokio_tar::Archive::new(&*serialized_archive),
+                "foo-0.0.1",
+                RenderOptions::default(),
+            )
+            .await
         );
     }
 
@@ -310,10 +341,14 @@ version = "0.0.1"
             .add_file("foo-0.0.1/README.md", b"readme")
             .build_unzipped();
 
-        let result = render_pkg_readme(tokio_tar::Archive::new(&*serialized_archive), "foo-0.0.1")
-            .await
-            .unwrap();
-        assert!(result.contains("readme"))
+        let result = render_pkg_readme(
+            tokio_tar::Archive::new(&*serialized_archive),
+            "foo-0.0.1",
+            RenderOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.html.contains("readme"))
     }
 
     #[tokio::test]
@@ -332,10 +367,18 @@ repository = "https://github.com/foo/foo"
             .add_file("foo-0.0.1/README.md", b"readme [link](./Other.md)")
             .build_unzipped();
 
-        let result = render_pkg_readme(tokio_tar::Archive::new(&*serialized_archive), "foo-0.0.1")
-            .await
-            .unwrap();
-        assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/./Other.md\""))
+        let result = render_pkg_readme(
+            tokio_tar::Archive::new(&*serialized_archive),
+            "foo-0.0.1",
+            RenderOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(
+            result
+                .html
+                .contains("\"https://github.com/foo/foo/blob/HEAD/./Other.md\"")
+        )
     }
 
     #[tokio::test]
@@ -357,10 +400,53 @@ repository = "https://github.com/foo/foo"
             )
             .build_unzipped();
 
-        let result = render_pkg_readme(tokio_tar::Archive::new(&*serialized_archive), "foo-0.0.1")
-            .await
-            .unwrap();
-        assert!(result.contains("docs/readme"));
-        assert!(result.contains("\"https://github.com/foo/foo/blob/HEAD/docs/./Other.md\""))
+        let result = render_pkg_readme(
+            tokio_tar::Archive::new(&*serialized_archive),
+            "foo-0.0.1",
+            RenderOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.html.contains("docs/readme"));
+        assert!(
+            result
+                .html
+                .contains("\"https://github.com/foo/foo/blob/HEAD/docs/./Other.md\"")
+        )
+    }
+
+    #[tokio::test]
+    async fn test_render_pkg_rst_readme() {
+        let serialized_archive = TarballBuilder::new()
+            .add_file(
+                "foo-0.0.1/Cargo.toml",
+                br#"
+[package]
+name = "foo"
+version = "0.0.1"
+readme = "README.rst"
+repository = "https://github.com/foo/foo"
+"#,
+            )
+            .add_file(
+                "foo-0.0.1/README.rst",
+                b"Foo\n===\n\n*readme* `link <./Other.rst>`_",
+            )
+            .build_unzipped();
+
+        let result = render_pkg_readme(
+            tokio_tar::Archive::new(&*serialized_archive),
+            "foo-0.0.1",
+            RenderOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.html.contains("<em>readme</em>"));
+        assert_eq!(result.table_of_contents[0].id, "user-content-foo");
+        assert!(
+            result
+                .html
+                .contains("\"https://github.com/foo/foo/blob/HEAD/./Other.rst\"")
+        )
     }
 }
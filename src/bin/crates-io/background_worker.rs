@@ -8,6 +8,9 @@
 //! the worker thread), we will rebuild the runner and try again up to 5 times.
 //! After the 5th occurrence, we will panic.
 //!
+//! With `JOB_SCHEDULES_ENABLED=true`, the runner also enqueues the recurring
+//! jobs (e.g. `daily_db_maintenance` and `dump_db`) on its own schedule.
+//!
 //! Usage:
 //!      cargo run -- background-worker
 
@@ -138,6 +141,14 @@ pub fn run() -> anyhow::Result<()> {
         .configure_queue("cloudfront", |queue| queue.num_workers(1))
         .register_crates_io_job_types();
 
+    // Recurring jobs are enqueued by an external scheduler, unless the worker
+    // is configured to schedule them itself.
+    let runner = if var_parsed("JOB_SCHEDULES_ENABLED")?.unwrap_or(false) {
+        runner.schedule_crates_io_jobs()
+    } else {
+        runner
+    };
+
     runtime.block_on(async {
         let handle = runner.start();
         crates_io::metrics::datadog::spawn(
@@ -120,10 +120,17 @@ async fn check_failing_background_jobs(conn: &mut AsyncPgConnection) -> Result<C
     // Max job execution time in minutes
     let max_job_time = var_parsed("MAX_JOB_TIME")?.unwrap_or(15);
 
+    let threshold = now.into_sql::<Timestamptz>() - max_job_time.minutes();
+
+    // Delayed jobs are only considered stalled once they were due to run for
+    // longer than the max job execution time.
     let stalled_jobs: Vec<i32> = background_jobs::table
         .select(1.into_sql::<Integer>())
         .filter(
-            background_jobs::created_at.lt(now.into_sql::<Timestamptz>() - max_job_time.minutes()),
+            background_jobs::run_at
+                .is_null()
+                .and(background_jobs::created_at.lt(threshold))
+                .or(background_jobs::run_at.lt(threshold.nullable())),
         )
         .filter(background_jobs::priority.ge(0))
         .for_update()
@@ -5,6 +5,7 @@ use std::{sync::Arc, time::Duration};
 use axum::ServiceExt;
 use crates_io_env_vars::list;
 use crates_io_github::RealGitHubClient;
+use crates_io_gitlab::{GitLabOAuthApp, RealGitLabClient};
 use prometheus::Encoder;
 use reqwest::Client;
 use std::io::Write;
@@ -30,13 +31,27 @@ pub fn run() -> anyhow::Result<()> {
     let user_agent = crates_io_version::user_agent();
     let client = Client::builder().user_agent(user_agent).build()?;
 
-    let github = RealGitHubClient::new(client);
+    let github = RealGitHubClient::new(client.clone());
     let github = Arc::new(github);
 
+    let mut gitlab = RealGitLabClient::new(client);
+    if let Some(gitlab_oauth) = &config.gitlab_oauth {
+        // Needed to refresh the expired access tokens of linked GitLab accounts
+        gitlab = gitlab.with_oauth_app(GitLabOAuthApp {
+            client_id: gitlab_oauth.client_id.to_string(),
+            client_secret: gitlab_oauth.client_secret.secret().clone().into(),
+            redirect_uri: gitlab_oauth.redirect_url.to_string(),
+        });
+    }
+    let gitlab = Arc::new(gitlab);
+
     let app = App::builder()
         .databases_from_config(&config.db)
         .github(github)
         .github_oauth_from_config(&config)
+        .gitlab(gitlab)
+        .gitlab_oauth_from_config(&config)
+        .oidc_login_from_config(&config)
         .trustpub_providers(&list("TRUSTPUB_PROVIDERS")?)
         .emails(emails)
         .storage_from_config(&config.storage)
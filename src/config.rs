@@ -9,7 +9,9 @@ mod fastly;
 mod features;
 mod frontend;
 mod github;
+mod gitlab;
 mod metrics;
+mod oidc_login;
 mod publish_limits;
 mod rate_limits;
 mod sentry;
@@ -26,7 +28,9 @@ pub use self::fastly::FastlyConfig;
 pub use self::features::FeaturesConfig;
 pub use self::frontend::FrontendConfig;
 pub use self::github::GitHubOAuthConfig;
+pub use self::gitlab::GitLabOAuthConfig;
 pub use self::metrics::MetricsConfig;
+pub use self::oidc_login::OidcLoginConfig;
 pub use self::publish_limits::PublishLimitsConfig;
 pub use self::rate_limits::RateLimitsConfig;
 pub use self::sentry::SentryConfig;
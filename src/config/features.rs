@@ -26,6 +26,11 @@ pub struct FeaturesConfig {
     ///
     /// Read from the `CACHE_TAG_INVALIDATIONS_ENABLED` environment variable.
     pub cache_tag_invalidations_enabled: bool,
+
+    /// Highlight Rust, TOML and shell code blocks when rendering READMEs.
+    ///
+    /// Read from the `README_SYNTAX_HIGHLIGHTING_ENABLED` environment variable.
+    pub readme_syntax_highlighting_enabled: bool,
 }
 
 impl FeaturesConfig {
@@ -36,6 +41,8 @@ impl FeaturesConfig {
         let cache_tags_enabled = var_parsed("CACHE_TAGS_ENABLED")?.unwrap_or(false);
         let cache_tag_invalidations_enabled =
             var_parsed("CACHE_TAG_INVALIDATIONS_ENABLED")?.unwrap_or(false);
+        let readme_syntax_highlighting_enabled =
+            var_parsed("README_SYNTAX_HIGHLIGHTING_ENABLED")?.unwrap_or(false);
 
         if cache_tag_invalidations_enabled && !cache_tags_enabled {
             anyhow::bail!("CACHE_TAG_INVALIDATIONS_ENABLED requires CACHE_TAGS_ENABLED");
@@ -47,6 +54,7 @@ impl FeaturesConfig {
             zip_archives_enabled,
             cache_tags_enabled,
             cache_tag_invalidations_enabled,
+            readme_syntax_highlighting_enabled,
         })
     }
 }
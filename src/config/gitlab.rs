@@ -0,0 +1,42 @@
+use oauth2::{ClientId, ClientSecret, RedirectUrl};
+
+use crates_io_env_vars::{required_var, var};
+
+#[derive(Debug)]
+pub struct GitLabOAuthConfig {
+    /// The client ID of the associated GitLab application.
+    ///
+    /// Read from the `GITLAB_CLIENT_ID` environment variable.
+    pub client_id: ClientId,
+
+    /// The client secret of the associated GitLab application.
+    ///
+    /// Read from the `GITLAB_CLIENT_SECRET` environment variable.
+    pub client_secret: ClientSecret,
+
+    /// The URL that GitLab redirects to after the user has authorized the
+    /// application. It has to match the URL configured in the application.
+    ///
+    /// Read from the `GITLAB_REDIRECT_URL` environment variable.
+    pub redirect_url: RedirectUrl,
+}
+
+impl GitLabOAuthConfig {
+    /// Reads the configuration from the environment, or returns `None` if
+    /// linking GitLab accounts is not configured.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let Some(client_id) = var("GITLAB_CLIENT_ID")? else {
+            return Ok(None);
+        };
+
+        let client_id = ClientId::new(client_id);
+        let client_secret = ClientSecret::new(required_var("GITLAB_CLIENT_SECRET")?);
+        let redirect_url = RedirectUrl::new(required_var("GITLAB_REDIRECT_URL")?)?;
+
+        Ok(Some(Self {
+            client_id,
+            client_secret,
+            redirect_url,
+        }))
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context, bail};
+use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl};
+use url::Url;
+
+use crates_io_env_vars::{list, required_var, var};
+
+/// Configuration of a generic OpenID Connect provider that can be used to
+/// sign in to crates.io.
+///
+/// The providers are read from the `OIDC_LOGIN_PROVIDERS` environment
+/// variable, which contains a comma-separated list of provider IDs. The
+/// settings of each provider are read from `OIDC_LOGIN_<ID>_*` environment
+/// variables, where `<ID>` is the uppercased provider ID with `-` replaced
+/// by `_`.
+#[derive(Debug)]
+pub struct OidcLoginConfig {
+    /// The ID of the provider, which is used in the API routes and stored
+    /// with the linked accounts (e.g. `"google"`). Changing the ID of a
+    /// provider unlinks all of its accounts.
+    pub id: String,
+
+    /// The name of the provider that is displayed to users.
+    ///
+    /// Read from the `OIDC_LOGIN_<ID>_NAME` environment variable, and
+    /// defaults to the provider ID.
+    pub name: String,
+
+    /// Read from the `OIDC_LOGIN_<ID>_CLIENT_ID` environment variable.
+    pub client_id: ClientId,
+
+    /// Read from the `OIDC_LOGIN_<ID>_CLIENT_SECRET` environment variable.
+    pub client_secret: ClientSecret,
+
+    /// Read from the `OIDC_LOGIN_<ID>_REDIRECT_URL` environment variable.
+    pub redirect_url: RedirectUrl,
+
+    /// The `authorization_endpoint` of the provider's discovery document.
+    ///
+    /// Read from the `OIDC_LOGIN_<ID>_AUTH_URL` environment variable.
+    pub auth_url: AuthUrl,
+
+    /// The `token_endpoint` of the provider's discovery document.
+    ///
+    /// Read from the `OIDC_LOGIN_<ID>_TOKEN_URL` environment variable.
+    pub token_url: TokenUrl,
+
+    /// The `userinfo_endpoint` of the provider's discovery document.
+    ///
+    /// Read from the `OIDC_LOGIN_<ID>_USERINFO_URL` environment variable.
+    pub userinfo_url: Url,
+}
+
+impl OidcLoginConfig {
+    /// Provider IDs that are reserved for the built-in providers.
+    const RESERVED_IDS: &[&str] = &["github", "gitlab"];
+
+    pub fn from_env() -> anyhow::Result<Vec<Self>> {
+        list("OIDC_LOGIN_PROVIDERS")?
+            .into_iter()
+            .map(|id| {
+                Self::from_env_for(&id)
+                    .with_context(|| format!("Invalid configuration of OIDC login provider `{id}`"))
+            })
+            .collect()
+    }
+
+    fn from_env_for(id: &str) -> anyhow::Result<Self> {
+        let is_valid_char = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+        if id.is_empty() || !id.chars().all(is_valid_char) {
+            bail!("provider IDs may only contain lowercase letters, digits and `-`");
+        }
+        if Self::RESERVED_IDS.contains(&id) {
+            bail!("`{id}` is reserved for a built-in provider");
+        }
+
+        let prefix = format!("OIDC_LOGIN_{}", id.to_uppercase().replace('-', "_"));
+        let var_name = |suffix: &str| format!("{prefix}_{suffix}");
+
+        let name = var(&var_name("NAME"))?.unwrap_or_else(|| id.to_string());
+        let client_id = ClientId::new(required_var(&var_name("CLIENT_ID"))?);
+        let client_secret = ClientSecret::new(required_var(&var_name("CLIENT_SECRET"))?);
+        let redirect_url = RedirectUrl::new(required_var(&var_name("REDIRECT_URL"))?)?;
+        let auth_url = AuthUrl::new(required_var(&var_name("AUTH_URL"))?)?;
+        let token_url = TokenUrl::new(required_var(&var_name("TOKEN_URL"))?)?;
+        let userinfo_url = required_var(&var_name("USERINFO_URL"))?.parse()?;
+
+        Ok(Self {
+            id: id.to_string(),
+            name,
+            client_id,
+            client_secret,
+            redirect_url,
+            auth_url,
+            token_url,
+            userinfo_url,
+        })
+    }
+}
@@ -13,13 +13,16 @@ use crate::config::fastly::FastlyConfig;
 use crate::config::features::FeaturesConfig;
 use crate::config::frontend::FrontendConfig;
 use crate::config::github::GitHubOAuthConfig;
+use crate::config::gitlab::GitLabOAuthConfig;
 use crate::config::metrics::MetricsConfig;
+use crate::config::oidc_login::OidcLoginConfig;
 use crate::config::publish_limits::PublishLimitsConfig;
 use crate::config::rate_limits::RateLimitsConfig;
 use crate::middleware::cargo_compat::StatusCodeConfig;
 use crate::storage::StorageConfig;
 use crates_io_encryption::TokenEncryption;
 use crates_io_env_vars::{list, required_var, var, var_parsed};
+use crates_io_trustpub::provenance::ProvenanceSigner;
 use http::HeaderValue;
 use std::convert::Infallible;
 use std::path::PathBuf;
@@ -35,6 +38,8 @@ pub struct Server {
     pub cdn_log_queue: CdnLogQueueConfig,
     pub session_key: cookie::Key,
     pub github_oauth: GitHubOAuthConfig,
+    pub gitlab_oauth: Option<GitLabOAuthConfig>,
+    pub oidc_login: Vec<OidcLoginConfig>,
     pub token_encryption: TokenEncryption,
     pub publish_limits: PublishLimitsConfig,
     pub rate_limits: RateLimitsConfig,
@@ -58,6 +63,10 @@ pub struct Server {
     /// token exchange.
     pub trustpub_audience: String,
 
+    /// Signs the provenance attestations of versions that are published
+    /// via Trusted Publishing. Attestations are not issued if unset.
+    pub provenance_signer: Option<ProvenanceSigner>,
+
     /// Disables API token creation when set to any non-empty value.
     /// The value is used as the error message returned to users.
     pub disable_token_creation: Option<String>,
@@ -84,6 +93,15 @@ pub struct Server {
     /// for database dump generation. When unset, the binaries are resolved via
     /// `PATH`.
     pub postgres_bin_dir: Option<PathBuf>,
+
+    /// Runs the application as a private registry, where reading crates,
+    /// the API and the sparse index requires authentication, and where
+    /// owners can restrict read access to their crates.
+    pub private_registry: bool,
+
+    /// Allows webhooks to use `http` URLs, and to send requests to loopback
+    /// and private network addresses. Only meant for local development.
+    pub insecure_webhooks: bool,
 }
 
 impl Server {
@@ -108,6 +126,10 @@ impl Server {
     ///   are not supported. If unset the job is a no-op.
     /// - `POSTGRES_BIN_DIR`: Optional directory containing `pg_dump` and `psql` binaries to use
     ///   for database dump generation. If unset, the binaries are looked up via `PATH`.
+    /// - `PRIVATE_REGISTRY`: If set to `true`, all reads require authentication and the sparse
+    ///   index is served by the application itself, instead of the CDN. Defaults to `false`.
+    /// - `INSECURE_WEBHOOKS`: If set to `true`, webhooks can use `http` URLs and send requests to
+    ///   loopback and private network addresses. Defaults to `false`.
     ///
     /// # Panics
     ///
@@ -140,6 +162,8 @@ impl Server {
             max_blocking_threads,
             session_key: cookie::Key::derive_from(required_var("SESSION_KEY")?.as_bytes()),
             github_oauth: GitHubOAuthConfig::from_env()?,
+            gitlab_oauth: GitLabOAuthConfig::from_env()?,
+            oidc_login: OidcLoginConfig::from_env()?,
             token_encryption: TokenEncryption::from_environment()?,
             publish_limits: PublishLimitsConfig::from_env()?,
             rate_limits: RateLimitsConfig::from_env()?,
@@ -157,6 +181,7 @@ impl Server {
                 .unwrap_or(StatusCodeConfig::AdjustAll),
             frontend: FrontendConfig::from_env()?,
             trustpub_audience,
+            provenance_signer: ProvenanceSigner::from_environment()?,
             disable_token_creation,
             banner_message,
             features,
@@ -164,6 +189,8 @@ impl Server {
             sync_git_index: true,
             index_archive_url: var_parsed("GIT_ARCHIVE_REPO_URL")?,
             postgres_bin_dir: var_parsed("POSTGRES_BIN_DIR")?,
+            private_registry: var_parsed("PRIVATE_REGISTRY")?.unwrap_or(false),
+            insecure_webhooks: var_parsed("INSECURE_WEBHOOKS")?.unwrap_or(false),
         })
     }
 }
@@ -189,6 +216,10 @@ impl AllowedOrigins {
     pub fn contains(&self, value: &HeaderValue) -> bool {
         self.0.iter().any(|it| it == value)
     }
+
+    pub fn as_slice(&self) -> &[String] {
+        &self.0
+    }
 }
 
 impl FromStr for AllowedOrigins {
@@ -11,6 +11,7 @@ pub mod krate;
 pub mod metrics;
 pub mod session;
 pub mod site_metadata;
+pub mod sparse_index;
 pub mod summary;
 pub mod team;
 pub mod token;
@@ -4,7 +4,7 @@ use crate::auth::Authentication;
 use crate::controllers::helpers::authorization::Rights;
 use crate::controllers::helpers::pagination::{Page, PaginationOptions, PaginationQueryParams};
 use crate::models::crate_owner_invitation::AcceptError;
-use crate::models::{Crate, CrateOwnerInvitation, PublicUser};
+use crate::models::{Crate, CrateOwnerInvitation, PublicUser, WebhookEvent};
 use crate::schema::{crate_owner_invitations, crates, users};
 use crate::util::RequestUtils;
 use crate::util::errors::{AppResult, BoxedAppError, bad_request, custom, forbidden, internal};
@@ -13,11 +13,13 @@ use crate::views::{
     EncodableCrateOwnerInvitation, EncodableCrateOwnerInvitationV1, EncodablePublicUser,
     InvitationResponse,
 };
+use crate::worker::jobs::DispatchWebhookEvent;
 use axum::Json;
 use axum::extract::{FromRequestParts, Path, Query};
 use axum_extra::TypedHeader;
 use axum_extra::headers::CacheControl;
 use chrono::Utc;
+use crates_io_worker::BackgroundJob;
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::sql_types::Bool;
@@ -181,8 +183,7 @@ async fn prepare_list(
                 // Only allow crate owners to query pending invitations for their crate.
                 let krate: Crate = Crate::by_name(&crate_name).first(&mut conn).await?;
                 let owners = krate.owners(conn).await?;
-                let encryption = &state.config.token_encryption;
-                if Rights::get(user, &*state.github, &owners, encryption).await? != Rights::Full {
+                if Rights::get(user, state, &owners).await? != Rights::Full {
                     let detail = "only crate owners can query pending invitations for their crate";
                     return Err(forbidden(detail));
                 }
@@ -388,6 +389,7 @@ pub async fn handle_crate_owner_invitation(
 
     if crate_invite.accepted {
         invitation.accept(&mut conn).await?;
+        enqueue_owner_add_event(&conn, crate_invite.crate_id, user_id).await?;
     } else {
         invitation.decline(&conn).await?;
     }
@@ -419,7 +421,9 @@ pub async fn accept_crate_owner_invitation_with_token(
     let invitation = CrateOwnerInvitation::find_by_token(&token, &conn).await?;
 
     let crate_id = invitation.crate_id;
+    let user_id = invitation.invited_user_id;
     invitation.accept(&mut conn).await?;
+    enqueue_owner_add_event(&conn, crate_id, user_id).await?;
 
     let crate_owner_invitation = InvitationResponse {
         crate_id,
@@ -431,6 +435,31 @@ pub async fn accept_crate_owner_invitation_with_token(
     }))
 }
 
+/// Notifies the webhooks of a crate that a user accepted the invitation to
+/// become an owner.
+async fn enqueue_owner_add_event(
+    mut conn: &AsyncPgConnection,
+    crate_id: i32,
+    user_id: i32,
+) -> AppResult<()> {
+    let login = users::table
+        .find(user_id)
+        .select(users::gh_login)
+        .first::<String>(&mut conn)
+        .await?;
+
+    DispatchWebhookEvent::builder()
+        .crate_id(crate_id)
+        .event(WebhookEvent::OwnerAdd)
+        .owner(&login)
+        .actor(&login)
+        .build()
+        .enqueue(conn)
+        .await?;
+
+    Ok(())
+}
+
 impl From<AcceptError> for BoxedAppError {
     fn from(error: AcceptError) -> Self {
         match error {
@@ -449,6 +478,14 @@ impl From<AcceptError> for BoxedAppError {
                     to become an owner of the {crate_name} crate.",
                 );
 
+                custom(StatusCode::FORBIDDEN, detail)
+            }
+            AcceptError::TwoFactorRequired { crate_name } => {
+                let detail = format!(
+                    "You need to enable two-factor authentication before you can accept the \
+                    invitation to become an owner of the {crate_name} crate.",
+                );
+
                 custom(StatusCode::FORBIDDEN, detail)
             }
         }
@@ -3,6 +3,7 @@ use axum::response::{IntoResponse, Response};
 use serde::Serialize;
 
 pub mod authorization;
+pub mod identity_provider;
 pub(crate) mod pagination;
 
 pub(crate) use self::pagination::Paginate;
@@ -1,8 +1,14 @@
-use crate::models::{Owner, User};
+use crate::app::App;
+use crate::models::{LinkedIdentity, Owner, User};
+use crate::schema::linked_identities;
 use crate::util::errors::{BoxedAppError, custom};
-use crates_io_encryption::TokenEncryption;
-use crates_io_github::{GitHubAuth, GitHubClient, GitHubError};
+use crates_io_github::{GitHubAuth, GitHubError};
+use crates_io_gitlab::{GitLabAuth, GitLabError};
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, RunQueryDsl};
 use http::StatusCode;
+use secrecy::ExposeSecret;
+use tracing::warn;
 
 /// Access rights to the crate (publishing and ownership management)
 /// NOTE: The order of these variants matters!
@@ -22,12 +28,11 @@ impl Rights {
     /// `Publish` as well, but this is a non-obvious invariant so we don't bother.
     /// Sweet free optimization if teams are proving burdensome to check.
     /// More than one team isn't really expected, though.
-    pub async fn get(
-        user: &User,
-        gh_client: &dyn GitHubClient,
-        owners: &[Owner],
-        encryption: &TokenEncryption,
-    ) -> Result<Self, BoxedAppError> {
+    pub async fn get(user: &User, app: &App, owners: &[Owner]) -> Result<Self, BoxedAppError> {
+        let gh_client = &*app.github;
+        let gl_client = &*app.gitlab;
+        let encryption = &app.config.token_encryption;
+
         // Compute GitHub token once rather than every loop iteration.
         // If the user doesn't have an associated `oauth_github`, then they can't be associated
         // with any GitHub teams.
@@ -39,6 +44,15 @@ impl Rights {
             .map_err(GitHubError::Other)?;
         let auth = token.map(GitHubAuth::bearer);
 
+        // Same for GitLab groups, which require a linked GitLab account.
+        let gl_token = user
+            .gl_encrypted_token
+            .as_ref()
+            .map(|gl_encrypted_token| encryption.decrypt(gl_encrypted_token))
+            .transpose()
+            .map_err(GitLabError::Other)?;
+        let mut gl_auth = gl_token.map(GitLabAuth::bearer);
+
         let mut best = Self::None;
         for owner in owners {
             match *owner {
@@ -47,14 +61,44 @@ impl Rights {
                         return Ok(Self::Full);
                     }
                 }
+                Owner::Team(ref team) if team.gitlab_id.is_some() => {
+                    let (Some(group_id), Some(account_id), Some(auth)) =
+                        (team.gitlab_id, user.gitlab_user_id(), &gl_auth)
+                    else {
+                        continue;
+                    };
+
+                    // Same caveat as for GitHub teams below: this must only
+                    // be asked on behalf of the user themselves.
+                    let membership =
+                        match gl_client.group_membership(group_id, account_id, auth).await {
+                            Err(error @ GitLabError::Unauthorized(_)) => {
+                                let auth = refresh_gitlab_auth(app, user, error).await?;
+                                let membership = gl_client
+                                    .group_membership(group_id, account_id, &auth)
+                                    .await?;
+                                gl_auth = Some(auth);
+                                membership
+                            }
+                            result => result?,
+                        };
+
+                    if membership.is_some_and(|m| m.is_active_developer()) {
+                        best = Self::Publish;
+                    }
+                }
                 Owner::Team(ref team) => {
+                    let (Some(org_id), Some(github_id)) = (team.org_id, team.github_id) else {
+                        continue;
+                    };
+
                     if let Some(ref auth) = auth {
                         // Phones home to GitHub to ask if this User is a member of the given team.
                         // Note that we're assuming that the given user is the one interested in
                         // the answer. If this is not the case, then we could accidentally leak
                         // private membership information here.
                         let is_team_member = match gh_client
-                            .team_membership(team.org_id, team.github_id, &user.gh_login, auth)
+                            .team_membership(org_id, github_id, &user.gh_login, auth)
                             .await
                         {
                             Ok(membership) => membership.is_some_and(|m| m.is_active()),
@@ -87,3 +131,80 @@ impl Rights {
         Ok(best)
     }
 }
+
+/// Replaces the expired access token of the linked GitLab account of the user
+/// by exchanging the stored refresh token for a new pair of tokens.
+///
+/// GitLab only accepts each refresh token once, so the new tokens are stored
+/// using a separate database connection. Otherwise they would be lost if the
+/// transaction of the current request is rolled back.
+///
+/// Returns the original `error` if the account has no refresh token, or if
+/// GitLab rejects it.
+pub async fn refresh_gitlab_auth(
+    app: &App,
+    user: &User,
+    error: GitLabError,
+) -> Result<GitLabAuth, BoxedAppError> {
+    let encryption = &app.config.token_encryption;
+    let mut conn = app.db_write().await?;
+
+    conn.transaction(async |conn| {
+        // Lock the linked account, so that concurrent requests don't use the
+        // same refresh token
+        let identity = linked_identities::table
+            .filter(linked_identities::user_id.eq(user.id))
+            .filter(linked_identities::provider.eq(LinkedIdentity::GITLAB))
+            .select(LinkedIdentity::as_select())
+            .for_update()
+            .first(conn)
+            .await?;
+
+        // Another request might have refreshed the tokens in the meantime
+        if identity.encrypted_token != user.gl_encrypted_token
+            && let Some(encrypted_token) = &identity.encrypted_token
+        {
+            let token = encryption
+                .decrypt(encrypted_token)
+                .map_err(GitLabError::Other)?;
+            return Ok(GitLabAuth::bearer(token));
+        }
+
+        let Some(encrypted_refresh_token) = &identity.encrypted_refresh_token else {
+            return Err(error.into());
+        };
+
+        let refresh_token = encryption
+            .decrypt(encrypted_refresh_token)
+            .map_err(GitLabError::Other)?;
+
+        let tokens = match app.gitlab.refresh_token(&refresh_token).await {
+            Ok(tokens) => tokens,
+            Err(refresh_error) => {
+                warn!(
+                    "Failed to refresh GitLab token of user {}: {refresh_error}",
+                    user.id
+                );
+                return Err(error.into());
+            }
+        };
+
+        let encrypted_token = encryption
+            .encrypt(tokens.access_token.expose_secret())
+            .map_err(GitLabError::Other)?;
+        let encrypted_refresh_token = encryption
+            .encrypt(tokens.refresh_token.expose_secret())
+            .map_err(GitLabError::Other)?;
+
+        diesel::update(linked_identities::table.find((&identity.provider, &identity.account_id)))
+            .set((
+                linked_identities::encrypted_token.eq(encrypted_token),
+                linked_identities::encrypted_refresh_token.eq(encrypted_refresh_token),
+            ))
+            .execute(conn)
+            .await?;
+
+        Ok(GitLabAuth::bearer(tokens.access_token))
+    })
+    .await
+}
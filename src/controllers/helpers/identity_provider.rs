@@ -0,0 +1,309 @@
+//! Identity providers other than GitHub, which can be linked to crates.io
+//! accounts and used to sign in.
+//!
+//! GitHub is handled separately, since every crates.io account is created
+//! from a GitHub account and the `users` table is still tied to it.
+
+use crate::app::App;
+use crate::controllers::session::BeginResponse;
+use crate::middleware::log_request::RequestLogExt;
+use crate::models::{LinkedIdentity, NewLinkedIdentity};
+use crate::util::errors::{AppResult, bad_request, not_found, server_error};
+use crate::util::oauth::{OAuthClient, OidcLoginClient, ReqwestClient};
+use crates_io_gitlab::GitLabAuth;
+use crates_io_session::SessionExtension;
+use http::request::Parts;
+use oauth2::{AccessToken, AuthorizationCode, CsrfToken, Scope, TokenResponse};
+use serde::Deserialize;
+use tracing::error;
+use url::Url;
+
+/// An identity provider that is configured for this instance.
+#[derive(Clone, Copy)]
+pub enum IdentityProvider<'a> {
+    GitLab(&'a OAuthClient),
+    Oidc(&'a OidcLoginClient),
+}
+
+impl<'a> IdentityProvider<'a> {
+    /// Looks up a configured identity provider by its ID.
+    ///
+    /// Returns a "not found" error if the provider does not exist or is not
+    /// configured.
+    pub fn find(app: &'a App, id: &str) -> AppResult<Self> {
+        Self::all(app)
+            .into_iter()
+            .find(|provider| provider.id() == id)
+            .ok_or_else(not_found)
+    }
+
+    /// Returns all identity providers that are configured for this instance.
+    pub fn all(app: &'a App) -> Vec<Self> {
+        let gitlab = app.gitlab_oauth.as_ref().map(Self::GitLab);
+        let oidc = app.oidc_login.iter().map(Self::Oidc);
+        gitlab.into_iter().chain(oidc).collect()
+    }
+
+    pub fn id(&self) -> &'a str {
+        match self {
+            Self::GitLab(_) => LinkedIdentity::GITLAB,
+            Self::Oidc(client) => &client.id,
+        }
+    }
+
+    pub fn name(&self) -> &'a str {
+        match self {
+            Self::GitLab(_) => "GitLab",
+            Self::Oidc(client) => &client.name,
+        }
+    }
+
+    fn oauth(&self) -> &'a OAuthClient {
+        match self {
+            Self::GitLab(client) => client,
+            Self::Oidc(client) => &client.oauth,
+        }
+    }
+
+    fn scopes(&self) -> &'static [&'static str] {
+        match self {
+            // The API scope is needed for the GitLab group membership checks
+            Self::GitLab(_) => &["read_api"],
+            Self::Oidc(_) => &["openid", "profile", "email"],
+        }
+    }
+
+    /// Session key containing the state of a pending OAuth flow.
+    fn state_session_key(&self) -> String {
+        format!("{}_oauth_state", self.id())
+    }
+
+    /// Begins the OAuth flow by generating an authorization URL, and saves
+    /// the randomly generated `state` secret in the session.
+    pub fn begin(&self, session: &SessionExtension) -> BeginResponse {
+        let scopes = self
+            .scopes()
+            .iter()
+            .map(|scope| Scope::new(scope.to_string()));
+
+        let (url, state) = self
+            .oauth()
+            .authorize_url(CsrfToken::new_random)
+            .add_scopes(scopes)
+            .url();
+
+        let state = state.secret().to_string();
+        session.insert(self.state_session_key(), state.clone());
+
+        let url = url.to_string();
+        BeginResponse { url, state }
+    }
+
+    /// Completes the OAuth flow.
+    ///
+    /// This checks the `state` parameter against the session, exchanges the
+    /// temporary `code` for an access token and then fetches the details of
+    /// the account that the token belongs to.
+    pub async fn authorize(
+        &self,
+        app: &App,
+        session: &SessionExtension,
+        req: &Parts,
+        code: AuthorizationCode,
+        state: &CsrfToken,
+    ) -> AppResult<ExternalIdentity> {
+        // Make sure that the state we just got matches the session state that we
+        // should have issued earlier.
+        let session_state = session
+            .remove(&self.state_session_key())
+            .map(CsrfToken::new);
+        if session_state.is_none_or(|session_state| state.secret() != session_state.secret()) {
+            return Err(bad_request("invalid state parameter"));
+        }
+
+        let client = ReqwestClient(
+            reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()?,
+        );
+
+        let token = self
+            .oauth()
+            .exchange_code(code)
+            .request_async(&client)
+            .await
+            .map_err(|err| {
+                req.request_log().add("cause", err);
+                server_error("Error obtaining token")
+            })?;
+
+        let refresh_token = token.refresh_token();
+        let token = token.access_token();
+
+        match self {
+            Self::GitLab(_) => {
+                let encryption = &app.config.token_encryption;
+                let encrypted_token = encryption.encrypt(token.secret()).map_err(|error| {
+                    error!("Failed to encrypt GitLab token: {error}");
+                    server_error("Internal server error")
+                })?;
+
+                // GitLab access tokens expire after two hours, so the refresh
+                // token is needed to keep using the API for group membership checks
+                let encrypted_refresh_token = refresh_token
+                    .map(|refresh_token| encryption.encrypt(refresh_token.secret()))
+                    .transpose()
+                    .map_err(|error| {
+                        error!("Failed to encrypt GitLab refresh token: {error}");
+                        server_error("Internal server error")
+                    })?;
+
+                let auth = GitLabAuth::bearer(token.secret().clone());
+                let gl_user = app.gitlab.current_user(&auth).await?;
+
+                Ok(ExternalIdentity {
+                    account_id: gl_user.id.to_string(),
+                    login: Some(gl_user.username),
+                    email: None,
+                    avatar: gl_user.avatar_url,
+                    encrypted_token: Some(encrypted_token),
+                    encrypted_refresh_token,
+                })
+            }
+            Self::Oidc(oidc) => {
+                let user_info = fetch_user_info(&client.0, &oidc.userinfo_url, token)
+                    .await
+                    .map_err(|err| {
+                        req.request_log().add("cause", err);
+                        server_error("Error obtaining user information")
+                    })?;
+
+                Ok(ExternalIdentity {
+                    login: user_info.preferred_username,
+                    email: user_info.email,
+                    avatar: user_info.picture,
+                    account_id: user_info.sub,
+                    encrypted_token: None,
+                    encrypted_refresh_token: None,
+                })
+            }
+        }
+    }
+}
+
+/// Details of an account at an identity provider.
+#[derive(Debug)]
+pub struct ExternalIdentity {
+    pub account_id: String,
+    pub login: Option<String>,
+    pub email: Option<String>,
+    pub avatar: Option<String>,
+    /// The encrypted access token, for providers whose API is used after
+    /// signing in.
+    pub encrypted_token: Option<Vec<u8>>,
+    /// The encrypted refresh token, for providers with expiring access
+    /// tokens.
+    pub encrypted_refresh_token: Option<Vec<u8>>,
+}
+
+impl ExternalIdentity {
+    /// Creates a record linking this account to the given crates.io user.
+    pub fn link_to<'a>(&'a self, provider: &'a str, user_id: i32) -> NewLinkedIdentity<'a> {
+        NewLinkedIdentity::builder()
+            .provider(provider)
+            .account_id(&self.account_id)
+            .user_id(user_id)
+            .maybe_login(self.login.as_deref())
+            .maybe_email(self.email.as_deref())
+            .maybe_avatar(self.avatar.as_deref())
+            .maybe_encrypted_token(self.encrypted_token.as_deref())
+            .maybe_encrypted_refresh_token(self.encrypted_refresh_token.as_deref())
+            .build()
+    }
+}
+
+/// The standard claims of the OpenID Connect `userinfo` endpoint that are
+/// used by crates.io.
+///
+/// See <https://openid.net/specs/openid-connect-core-1_0.html#StandardClaims>.
+#[derive(Debug, Deserialize)]
+struct UserInfo {
+    sub: String,
+    preferred_username: Option<String>,
+    email: Option<String>,
+    picture: Option<String>,
+}
+
+async fn fetch_user_info(
+    client: &reqwest::Client,
+    url: &Url,
+    token: &AccessToken,
+) -> reqwest::Result<UserInfo> {
+    client
+        .get(url.clone())
+        .bearer_auth(token.secret())
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+
+    #[tokio::test]
+    async fn fetch_user_info_parses_standard_claims() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/userinfo")
+            .match_header("authorization", "Bearer secret-token")
+            .with_status(200)
+            .with_body(
+                r#"{
+                    "sub": "248289761001",
+                    "name": "Jane Doe",
+                    "preferred_username": "j.doe",
+                    "email": "janedoe@example.com",
+                    "email_verified": true,
+                    "picture": "https://example.com/janedoe/me.jpg"
+                }"#,
+            )
+            .create_async()
+            .await;
+
+        let url = format!("{}/userinfo", server.url()).parse().unwrap();
+        let token = AccessToken::new("secret-token".into());
+        let user_info = fetch_user_info(&reqwest::Client::new(), &url, &token)
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(user_info.sub, "248289761001");
+        assert_eq!(user_info.preferred_username.as_deref(), Some("j.doe"));
+        assert_eq!(user_info.email.as_deref(), Some("janedoe@example.com"));
+        assert_eq!(
+            user_info.picture.as_deref(),
+            Some("https://example.com/janedoe/me.jpg")
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_user_info_fails_for_invalid_tokens() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/userinfo")
+            .with_status(401)
+            .create_async()
+            .await;
+
+        let url = format!("{}/userinfo", server.url()).parse().unwrap();
+        let token = AccessToken::new("invalid-token".into());
+        let result = fetch_user_info(&reqwest::Client::new(), &url, &token).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::app::AppState;
+use crate::middleware::private_registry::check_crate_access;
 use crate::models::Crate;
 use crate::util::errors::{AppResult, BoxedAppError, crate_not_found, custom};
 use axum::extract::{FromRequestParts, Path};
@@ -15,6 +17,7 @@ pub mod follow;
 pub mod metadata;
 pub mod owners;
 pub mod publish;
+pub mod readers;
 pub mod rev_deps;
 pub mod search;
 pub mod update;
@@ -27,10 +30,13 @@ pub struct CratePath {
     pub name: String,
 }
 
-impl<S: Send + Sync> FromRequestParts<S> for CratePath {
+impl FromRequestParts<AppState> for CratePath {
     type Rejection = BoxedAppError;
 
-    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
         let Path(path) = Path::<CratePath>::from_request_parts(parts, state)
             .await
             .map_err(|err| custom(err.status(), err.body_text()))?;
@@ -45,6 +51,8 @@ impl<S: Send + Sync> FromRequestParts<S> for CratePath {
             return Err(crate_not_found(&path.name));
         }
 
+        check_crate_access(state, parts, &path.name).await?;
+
         Ok(path)
     }
 }
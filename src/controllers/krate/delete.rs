@@ -68,7 +68,10 @@ pub async fn delete_crate(
     let mut conn = app.db_write().await?;
 
     // Check that the user is authenticated
-    let auth = AuthCheck::only_cookie().check(&parts, &mut conn).await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&parts, &mut conn)
+        .await?;
 
     // Check that the crate exists
     let krate = path.load_crate(&conn).await?;
@@ -76,7 +79,7 @@ pub async fn delete_crate(
     // Check that the user is an owner of the crate (team owners are not allowed to delete crates)
     let user = auth.user();
     let owners = krate.owners(&conn).await?;
-    match Rights::get(user, &*app.github, &owners, &app.config.token_encryption).await? {
+    match Rights::get(user, &app, &owners).await? {
         Rights::Full => {}
         Rights::Publish => {
             let msg = "team members don't have permission to delete crates";
@@ -5,11 +5,17 @@
 
 use crate::app::AppState;
 use crate::controllers::krate::CratePath;
-use crate::models::download::Version;
+use crate::models::download::{CargoVersionDownload, ClientDownload, Version};
 use crate::models::{PublicUser, Version as FullVersion, VersionDownload, VersionOwnerAction};
-use crate::schema::{oauth_github, users, version_downloads, version_owner_actions, versions};
+use crate::schema::{
+    cargo_version_downloads, client_downloads, oauth_github, users, version_downloads,
+    version_owner_actions, versions,
+};
 use crate::util::errors::{AppResult, BoxedAppError, bad_request};
-use crate::views::{EncodableVersion, EncodableVersionDownload};
+use crate::views::{
+    EncodableCargoVersionDownload, EncodableClientDownload, EncodableVersion,
+    EncodableVersionDownload,
+};
 use axum::Json;
 use axum::extract::FromRequestParts;
 use axum_extra::extract::Query;
@@ -27,7 +33,7 @@ use std::str::FromStr;
 pub struct DownloadsQueryParams {
     /// Additional data to include in the response.
     ///
-    /// Valid values: `versions`.
+    /// Valid values: `versions`, `cargo_versions`, and `clients`.
     ///
     /// Defaults to no additional data.
     ///
@@ -45,6 +51,16 @@ pub struct DownloadsResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub versions: Option<Vec<EncodableVersion>>,
 
+    /// The per-day download counts by `major.minor` cargo version for the
+    /// last 90 days, if `?include=cargo_versions` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cargo_version_downloads: Option<Vec<EncodableCargoVersionDownload>>,
+
+    /// The per-day download counts by class of client for the last 90 days,
+    /// if `?include=clients` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_downloads: Option<Vec<EncodableClientDownload>>,
+
     #[schema(inline)]
     pub meta: DownloadsMeta,
 }
@@ -70,6 +86,10 @@ pub struct ExtraDownload {
 ///
 /// This includes the per-day downloads for the last 90 days and for the
 /// latest 5 versions plus the sum of the rest.
+///
+/// The downloads can additionally be broken down by cargo version and by
+/// class of client, e.g. to find out which toolchains are still in use
+/// before raising the minimum supported Rust version.
 #[utoipa::path(
     get,
     path = "/api/v1/crates/{name}/downloads",
@@ -109,7 +129,14 @@ pub async fn get_crate_downloads(
         .unwrap_or_default();
 
     let sum_downloads = sql::<BigInt>("SUM(version_downloads.downloads)");
-    let (downloads, extra_downloads, versions_and_publishers, actions) = tokio::try_join!(
+    let (
+        downloads,
+        extra_downloads,
+        versions_and_publishers,
+        actions,
+        cargo_version_downloads,
+        client_downloads,
+    ) = tokio::try_join!(
         VersionDownload::belonging_to(latest_five)
             .filter(version_downloads::date.gt(date(now - 90.days())))
             .select(VersionDownload::as_select())
@@ -131,6 +158,8 @@ pub async fn get_crate_downloads(
             .boxed(),
         load_versions_and_publishers(&conn, latest_five, include.versions),
         load_actions(&conn, latest_five, include.versions),
+        load_cargo_version_downloads(&conn, crate_id, include.cargo_versions),
+        load_client_downloads(&conn, crate_id, include.clients),
     )?;
 
     let version_downloads = downloads
@@ -156,9 +185,25 @@ pub async fn get_crate_downloads(
         None
     };
 
+    let cargo_version_downloads = include.cargo_versions.then(|| {
+        cargo_version_downloads
+            .into_iter()
+            .map(EncodableCargoVersionDownload::from)
+            .collect()
+    });
+
+    let client_downloads = include.clients.then(|| {
+        client_downloads
+            .into_iter()
+            .map(EncodableClientDownload::from)
+            .collect()
+    });
+
     Ok(Json(DownloadsResponse {
         version_downloads,
         versions,
+        cargo_version_downloads,
+        client_downloads,
         meta: DownloadsMeta { extra_downloads },
     }))
 }
@@ -196,24 +241,68 @@ async fn load_actions(
         .await
 }
 
+async fn load_cargo_version_downloads(
+    mut conn: &AsyncPgConnection,
+    crate_id: i32,
+    includes: bool,
+) -> QueryResult<Vec<CargoVersionDownload>> {
+    use diesel::dsl::*;
+
+    if !includes {
+        return Ok(vec![]);
+    }
+    CargoVersionDownload::query()
+        .filter(cargo_version_downloads::crate_id.eq(crate_id))
+        .filter(cargo_version_downloads::date.gt(date(now - 90.days())))
+        .order((
+            cargo_version_downloads::date.asc(),
+            cargo_version_downloads::cargo_version.asc(),
+        ))
+        .load(&mut conn)
+        .await
+}
+
+async fn load_client_downloads(
+    mut conn: &AsyncPgConnection,
+    crate_id: i32,
+    includes: bool,
+) -> QueryResult<Vec<ClientDownload>> {
+    use diesel::dsl::*;
+
+    if !includes {
+        return Ok(vec![]);
+    }
+    ClientDownload::query()
+        .filter(client_downloads::crate_id.eq(crate_id))
+        .filter(client_downloads::date.gt(date(now - 90.days())))
+        .order((client_downloads::date.asc(), client_downloads::client.asc()))
+        .load(&mut conn)
+        .await
+}
+
 #[derive(Debug, Default)]
 struct ShowIncludeMode {
     versions: bool,
+    cargo_versions: bool,
+    clients: bool,
 }
 
 impl ShowIncludeMode {
-    const INVALID_COMPONENT: &'static str = "invalid component for ?include= (expected 'versions')";
+    const INVALID_COMPONENT: &'static str =
+        "invalid component for ?include= (expected 'versions', 'cargo_versions', or 'clients')";
 }
 
 impl FromStr for ShowIncludeMode {
     type Err = BoxedAppError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut mode = Self { versions: false };
+        let mut mode = Self::default();
         for component in s.split(',') {
             match component {
                 "" => {}
                 "versions" => mode.versions = true,
+                "cargo_versions" => mode.cargo_versions = true,
+                "clients" => mode.clients = true,
                 _ => return Err(bad_request(Self::INVALID_COMPONENT)),
             }
         }
@@ -316,6 +316,7 @@ async fn _load_versions_and_publishers(
     version_num: Option<&str>,
 ) -> AppResult<Option<Vec<VersionsAndPublishers>>> {
     let mut query = Version::belonging_to(krate)
+        .filter(versions::staged_at.is_null())
         .left_outer_join(users::table.left_join(oauth_github::table))
         .select(<(Version, Option<PublicUser>)>::as_select())
         .order_by(versions::id.desc())
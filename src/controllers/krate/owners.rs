@@ -1,21 +1,30 @@
 //! All routes related to managing owners of a crate
 
-use crate::controllers::helpers::authorization::Rights;
+use crate::auth::AuthCheck;
+use crate::controllers::helpers::authorization::{Rights, refresh_gitlab_auth};
 use crate::controllers::krate::CratePath;
+use crate::email::{EmailMessage, Unsubscribe};
 use crate::models::krate::OwnerRemoveError;
-use crate::models::{Crate, Owner, PublicUser, Team, User};
+use crate::models::two_factor::has_two_factor;
+use crate::models::{
+    Crate, NotificationCategory, NotificationPreference, NotificationSettings, Owner, PublicUser,
+    Team, User, WebhookEvent,
+};
 use crate::models::{
     CrateOwner, NewCrateOwnerInvitation, NewCrateOwnerInvitationOutcome, NewTeam,
     krate::NewOwnerInvite, token::EndpointScope,
 };
+use crate::rate_limiter::LimitedAction;
 use crate::util::errors::{AppResult, BoxedAppError, bad_request, crate_not_found, custom};
 use crate::views::EncodableOwner;
+use crate::worker::jobs::DispatchWebhookEvent;
 use crate::{App, app::AppState};
-use crate::{auth::AuthCheck, email::EmailMessage};
 use axum::Json;
 use chrono::Utc;
 use crates_io_encryption::TokenEncryption;
 use crates_io_github::{GitHubAuth, GitHubClient, GitHubError};
+use crates_io_gitlab::{GitLabAuth, GitLabError};
+use crates_io_worker::BackgroundJob;
 use diesel::prelude::*;
 use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 use http::StatusCode;
@@ -192,6 +201,7 @@ pub struct ChangeOwnersRequest {
     ///
     /// For users, use just the username (e.g., `"octocat"`).
     /// For GitHub teams, use the format `github:org:team` (e.g., `"github:rust-lang:owners"`).
+    /// For GitLab groups, use the format `gitlab:group/subgroup` (e.g., `"gitlab:rust-lang/owners"`).
     #[schema(example = json!(["octocat", "github:rust-lang:owners"]))]
     #[serde(alias = "users")]
     owners: Vec<String>,
@@ -218,6 +228,7 @@ async fn modify_owners(
     let auth = AuthCheck::default()
         .with_endpoint_scope(EndpointScope::ChangeOwners)
         .for_crate(&crate_name)
+        .require_second_factor()
         .check(&parts, &mut conn)
         .await?;
 
@@ -233,7 +244,7 @@ async fn modify_owners(
 
             let owners = krate.owners(conn).await?;
 
-            match Rights::get(user, &*app.github, &owners, &app.config.token_encryption).await? {
+            match Rights::get(user, &app, &owners).await? {
                 Rights::Full => {}
                 // Yes!
                 Rights::Publish => {
@@ -263,6 +274,10 @@ async fn modify_owners(
                         return Err(bad_request(format_args!("`{login}` is already an owner")));
                     }
 
+                    app.rate_limiter
+                        .check_rate_limit(user.id, LimitedAction::OwnerInvite, conn)
+                        .await?;
+
                     match add_owner(&app, conn, user, &krate, login).await {
                         // A user was successfully invited, and they must accept
                         // the invite, and a best-effort attempt should be made
@@ -274,10 +289,29 @@ async fn modify_owners(
                                 invitee.gh_login, krate.name,
                             ));
 
-                            if let Some(recipient) =
-                                invitee.verified_email(conn).await.ok().flatten()
+                            let category = NotificationCategory::OwnerInvite;
+                            let is_enabled = NotificationPreference::is_enabled(
+                                conn,
+                                invitee.id,
+                                category,
+                                Some(krate.id),
+                            )
+                            .await?;
+
+                            if is_enabled
+                                && let Some(recipient) =
+                                    invitee.verified_email(conn).await.ok().flatten()
                             {
-                                let email = EmailMessage::from_template(
+                                let unsubscribe_token =
+                                    NotificationSettings::unsubscribe_token(conn, invitee.id)
+                                        .await?;
+                                let unsubscribe = Unsubscribe::new(
+                                    &app.emails.domain,
+                                    &unsubscribe_token,
+                                    category,
+                                );
+
+                                let email = EmailMessage::from_template_with_unsubscribe(
                                     "owner_invite",
                                     context! {
                                         inviter => user.gh_login,
@@ -285,6 +319,7 @@ async fn modify_owners(
                                         crate_name => krate.name,
                                         token => token.expose_secret()
                                     },
+                                    unsubscribe,
                                 );
 
                                 match email {
@@ -298,10 +333,21 @@ async fn modify_owners(
 
                         // A team was successfully invited. They are immediately
                         // added, and do not have an invite token.
-                        Ok(NewOwnerInvite::Team(team)) => msgs.push(format!(
-                            "team {} has been added as an owner of crate {}",
-                            team.login, krate.name
-                        )),
+                        Ok(NewOwnerInvite::Team(team)) => {
+                            DispatchWebhookEvent::builder()
+                                .crate_id(krate.id)
+                                .event(WebhookEvent::OwnerAdd)
+                                .owner(&team.login)
+                                .actor(&user.gh_login)
+                                .build()
+                                .enqueue(conn)
+                                .await?;
+
+                            msgs.push(format!(
+                                "team {} has been added as an owner of crate {}",
+                                team.login, krate.name
+                            ))
+                        }
 
                         // This user has a pending invite.
                         Err(OwnerAddError::AlreadyInvited(user)) => msgs.push(format!(
@@ -318,6 +364,15 @@ async fn modify_owners(
             } else {
                 for login in &logins {
                     krate.owner_remove(conn, login).await?;
+
+                    DispatchWebhookEvent::builder()
+                        .crate_id(krate.id)
+                        .event(WebhookEvent::OwnerRemove)
+                        .owner(login)
+                        .actor(&user.gh_login)
+                        .build()
+                        .enqueue(conn)
+                        .await?;
                 }
                 if User::owning(&krate, conn).await?.is_empty() {
                     return Err(bad_request(
@@ -354,8 +409,7 @@ async fn add_owner(
     login: &str,
 ) -> Result<NewOwnerInvite, OwnerAddError> {
     if login.contains(':') {
-        let encryption = &app.config.token_encryption;
-        add_team_owner(&*app.github, conn, req_user, krate, login, encryption).await
+        add_team_owner(app, conn, req_user, krate, login).await
     } else {
         invite_user_owner(app, conn, req_user, krate, login).await
     }
@@ -373,6 +427,14 @@ async fn invite_user_owner(
         .optional()?
         .ok_or_else(|| bad_request(format_args!("could not find user with login `{login}`")))?;
 
+    if krate.require_owner_2fa && !has_two_factor(conn, user.id).await? {
+        let crate_name = &krate.name;
+        return Err(bad_request(format_args!(
+            "user `{login}` needs to enable two-factor authentication first, because crate `{crate_name}` requires it for all owners"
+        ))
+        .into());
+    }
+
     // Users are invited and must accept before being added
     let expires_at = Utc::now() + app.config.ownership_invitations_expiration;
     let invite = NewCrateOwnerInvitation {
@@ -393,40 +455,52 @@ async fn invite_user_owner(
 }
 
 async fn add_team_owner(
-    gh_client: &dyn GitHubClient,
+    app: &App,
     conn: &mut AsyncPgConnection,
     req_user: &User,
     krate: &Crate,
     login: &str,
-    encryption: &TokenEncryption,
 ) -> Result<NewOwnerInvite, OwnerAddError> {
+    let encryption = &app.config.token_encryption;
+
     // github:rust-lang:owners
     let mut chunks = login.split(':');
 
     let team_system = chunks.next().unwrap();
-    if team_system != "github" {
-        let error = "unknown organization handler, only 'github:org:team' is supported";
-        return Err(bad_request(error).into());
-    }
-
-    // unwrap is documented above as part of the calling contract
-    let org = chunks.next().unwrap();
-    let team = chunks.next().ok_or_else(|| {
-        let error = "missing github team argument; format is github:org:team";
-        bad_request(error)
-    })?;
+    let team = match team_system {
+        "github" => {
+            // unwrap is documented above as part of the calling contract
+            let org = chunks.next().unwrap();
+            let team = chunks.next().ok_or_else(|| {
+                let error = "missing github team argument; format is github:org:team";
+                bad_request(error)
+            })?;
+
+            // Always recreate teams to get the most up-to-date GitHub ID
+            create_or_update_github_team(
+                &*app.github,
+                conn,
+                &login.to_lowercase(),
+                org,
+                team,
+                req_user,
+                encryption,
+            )
+            .await?
+        }
+        "gitlab" => {
+            // gitlab:rust-lang/owners
+            let path = &login["gitlab:".len()..];
 
-    // Always recreate teams to get the most up-to-date GitHub ID
-    let team = create_or_update_github_team(
-        gh_client,
-        conn,
-        &login.to_lowercase(),
-        org,
-        team,
-        req_user,
-        encryption,
-    )
-    .await?;
+            // Always recreate groups to get the most up-to-date GitLab ID
+            create_or_update_gitlab_team(app, conn, path, req_user).await?
+        }
+        _ => {
+            let error = "unknown organization handler, \
+                only 'github:org:team' and 'gitlab:group/subgroup' are supported";
+            return Err(bad_request(error).into());
+        }
+    };
 
     // Teams are added as owners immediately, since the above call ensures
     // the user is a team member.
@@ -523,6 +597,93 @@ pub async fn create_or_update_github_team(
         .map_err(Into::into)
 }
 
+/// Tries to create or update a team for a GitLab group. `path` is the full
+/// path of the group (e.g. `rust-lang/owners`).
+pub async fn create_or_update_gitlab_team(
+    app: &App,
+    conn: &mut AsyncPgConnection,
+    path: &str,
+    req_user: &User,
+) -> AppResult<Team> {
+    fn is_allowed_char(c: char) -> bool {
+        matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' | '/')
+    }
+
+    if path.is_empty() || path.starts_with('/') || path.ends_with('/') {
+        return Err(bad_request(
+            "missing gitlab group argument; format is gitlab:group/subgroup",
+        ));
+    }
+
+    if let Some(c) = path.chars().find(|c| !is_allowed_char(*c)) {
+        return Err(bad_request(format_args!(
+            "group path cannot contain special characters like {c}"
+        )));
+    }
+
+    if path
+        .split('/')
+        .any(|segment| segment.is_empty() || segment == "..")
+    {
+        return Err(bad_request(format_args!(
+            "invalid gitlab group path: {path}"
+        )));
+    }
+
+    let (Some(account_id), Some(token)) = (req_user.gitlab_user_id(), &req_user.gl_encrypted_token)
+    else {
+        return Err(bad_request(
+            "Cannot add a GitLab group as an owner without a linked GitLab account",
+        ));
+    };
+
+    let gl_client = &*app.gitlab;
+    let token = app.config.token_encryption.decrypt(token).map_err(|err| {
+        custom(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to decrypt GitLab token: {err}"),
+        )
+    })?;
+
+    let mut auth = GitLabAuth::bearer(token);
+    let mut result = gl_client.group_by_path(path, &auth).await;
+    if let Err(error @ GitLabError::Unauthorized(_)) = result {
+        auth = refresh_gitlab_auth(app, req_user, error).await?;
+        result = gl_client.group_by_path(path, &auth).await;
+    }
+
+    let group = result.map_err(|_| {
+        bad_request(format_args!(
+            "could not find the gitlab group {path}. \
+                    Make sure that you have the right permissions in GitLab."
+        ))
+    })?;
+
+    let can_add_group = gl_client
+        .group_membership(group.id, account_id, &auth)
+        .await?
+        .is_some_and(|m| m.is_active_maintainer());
+
+    if !can_add_group {
+        return Err(custom(
+            StatusCode::FORBIDDEN,
+            "only maintainers and owners of a group can add it as an owner",
+        ));
+    }
+
+    let login = format!("gitlab:{}", group.full_path.to_lowercase());
+
+    NewTeam::builder()
+        .login(&login)
+        .gitlab_id(group.id)
+        .maybe_name(group.full_name.as_deref())
+        .maybe_avatar(group.avatar_url.as_deref())
+        .build()
+        .create_or_update(conn)
+        .await
+        .map_err(Into::into)
+}
+
 async fn is_gh_org_owner(
     gh_client: &dyn GitHubClient,
     org_id: i32,
@@ -3,11 +3,12 @@
 use crate::app::AppState;
 use crate::auth::{AuthCheck, AuthHeader, Authentication};
 use crate::worker::jobs::{
-    self, AnalyzeCrateFile, BuildCrateZip, CheckTyposquat, GenerateOgImage,
-    SendPublishNotificationsJob, UpdateDefaultVersion,
+    self, AnalyzeCrateFile, BuildCrateZip, CheckCompatibility, CheckTyposquat, GenerateOgImage,
+    UpdateDefaultVersion,
 };
 use axum::Json;
 use axum::body::{Body, Bytes};
+use axum::extract::{FromRequestParts, Query};
 use chrono::{DateTime, SecondsFormat, Utc};
 use crates_io_cargo_toml::{Dependency, DepsSet, TargetDepsSet};
 use crates_io_tarball::{TarballError, TarballLimits, process_tarball};
@@ -24,6 +25,7 @@ use futures_util::TryStreamExt;
 use http::StatusCode;
 use http::request::Parts;
 use secrecy::ExposeSecret;
+use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use tokio::io::{AsyncRead, AsyncReadExt};
@@ -32,14 +34,16 @@ use tracing::{error, instrument};
 use url::Url;
 
 use crate::models::{
-    Category, Crate, DependencyKind, Keyword, NewCrate, NewVersion, NewVersionOwnerAction,
-    VersionAction, default_versions::Version as DefaultVersion,
+    Category, Crate, DependencyKind, Keyword, NewCrate, NewVersion, NewVersionOwnerAction, Version,
+    VersionAction, WebhookEvent, default_versions::Version as DefaultVersion,
 };
 
 use crate::controllers::helpers::authorization::Rights;
+use crate::controllers::version::provenance;
 use crate::licenses::parse_license_expr;
 use crate::middleware::log_request::RequestLogExt;
 use crate::models::token::EndpointScope;
+use crate::models::two_factor::has_two_factor;
 use crate::rate_limiter::LimitedAction;
 use crate::schema::*;
 use crate::storage::StorageKey;
@@ -48,6 +52,7 @@ use crate::views::{
     EncodableCrate, EncodableCrateDependency, GoodCrate, PublishMetadata, PublishWarnings,
 };
 use crates_io_database::fns::canon_crate_name;
+use crates_io_database::models::trustpub::NewProvenance;
 use crates_io_database::models::{TrustpubData, User, versions_published_by};
 use crates_io_trustpub::access_token::AccessToken;
 
@@ -58,6 +63,19 @@ const MISSING_RIGHTS_ERROR_MESSAGE: &str = "this crate exists but you don't seem
 
 const MAX_DESCRIPTION_LENGTH: usize = 1000;
 
+#[derive(Debug, Deserialize, FromRequestParts, utoipa::IntoParams)]
+#[from_request(via(Query))]
+#[into_params(parameter_in = Query)]
+pub struct PublishQueryParams {
+    /// Upload the version as a staged version.
+    ///
+    /// Staged versions are hidden from the index and the version listings
+    /// until an owner promotes them. Crates that require promotion reviews
+    /// always use staged publishing.
+    #[serde(default)]
+    staged: bool,
+}
+
 enum AuthType {
     Regular(Box<Authentication>),
     TrustPub(Option<TrustpubData>),
@@ -90,6 +108,7 @@ impl AuthType {
 #[utoipa::path(
     put,
     path = "/api/v1/crates/new",
+    params(PublishQueryParams),
     security(
         ("api_token" = []),
         ("trustpub_token" = []),
@@ -102,7 +121,12 @@ impl AuthType {
         (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
     ),
 )]
-pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<GoodCrate>> {
+pub async fn publish(
+    app: AppState,
+    params: PublishQueryParams,
+    req: Parts,
+    body: Body,
+) -> AppResult<Json<GoodCrate>> {
     let stream = body.into_data_stream();
     let stream = stream.map_err(std::io::Error::other);
     let mut reader = StreamReader::new(stream);
@@ -237,6 +261,30 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
         ));
     }
 
+    // Check if crate requires owners with two-factor authentication
+    if let Some(existing_crate) = &existing_crate
+        && existing_crate.require_owner_2fa
+        && let Some(user_id) = auth.user_id()
+        && !has_two_factor(&conn, user_id).await?
+    {
+        return Err(forbidden(
+            "New versions of this crate can only be published by users with two-factor authentication enabled.",
+        ));
+    }
+
+    let staged = params.staged
+        || existing_crate
+            .as_ref()
+            .is_some_and(|krate| krate.require_promotion_review);
+
+    // A new crate would be visible in the API right away, even if its only
+    // version is hidden, so staging is limited to existing crates.
+    if staged && existing_crate.is_none() {
+        return Err(bad_request(
+            "Staged publishing is only supported for new versions of existing crates",
+        ));
+    }
+
     let verified_email_address = if let Some(user) = auth.user() {
         let verified_email_address = user.verified_email(&conn).await?;
         Some(verified_email_address.ok_or_else(|| verified_email_error(&app.config.domain_name))?)
@@ -482,7 +530,7 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
             };
 
             let owners = krate.owners(conn).await?;
-            if Rights::get(user, &*app.github, &owners, &app.config.token_encryption).await? < Rights::Publish {
+            if Rights::get(user, &app, &owners).await? < Rights::Publish {
                 return Err(custom(StatusCode::FORBIDDEN, MISSING_RIGHTS_ERROR_MESSAGE));
             }
 
@@ -527,6 +575,8 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
 
         let tar_sha256 = Sha256::digest(&tarball_bytes);
 
+        let staged_at = staged.then(Utc::now);
+
         // Persist the new version of this crate
         let new_version = NewVersion::builder(krate.id, &version_string)
             .features(serde_json::to_value(&features)?)
@@ -549,6 +599,7 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
             .categories(&categories)
             .keywords(&keywords)
             .maybe_trustpub_data(auth.trustpub_data())
+            .maybe_staged_at(staged_at.as_ref())
             .build();
 
         let version = new_version.save(conn).await.map_err(|error| {
@@ -564,6 +615,29 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
             versions_published_by::insert(version.id, &email_address, conn).await?;
         }
 
+        if let Some(signer) = &app.config.provenance_signer
+            && let Some(trustpub_data) = auth.trustpub_data()
+        {
+            let statement = provenance::build_statement(
+                &krate.name,
+                &version_string,
+                &tar_sha256,
+                trustpub_data,
+            );
+
+            let envelope = signer
+                .sign(&statement)
+                .map_err(|e| internal(format!("failed to sign provenance: {e}")))?;
+
+            let new_provenance = NewProvenance {
+                version_id: version.id,
+                key_id: signer.key_id(),
+                envelope: serde_json::to_value(&envelope)?,
+            };
+
+            new_provenance.insert(conn).await?;
+        }
+
         if let AuthType::Regular(auth) = &auth {
             NewVersionOwnerAction::builder()
                 .version_id(version.id)
@@ -603,7 +677,7 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
                 yanked: false,
             };
 
-            if existing_default_version < &published_default_version {
+            if !staged && existing_default_version < &published_default_version {
                 diesel::update(default_versions::table)
                     .filter(default_versions::crate_id.eq(krate.id))
                     .set(default_versions::version_id.eq(version.id))
@@ -657,18 +731,14 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
             .await
             .map_err(|e| internal(format!("failed to upload crate: {e}")))?;
 
-        let sync_git_index = async {
-            if app.config.sync_git_index {
-                let git_index_job = jobs::SyncToGitIndex::new(&krate.name);
-                git_index_job.enqueue(&*conn).await?;
+        let release = async {
+            if !staged {
+                let actor = auth.user().map(|user| user.gh_login.as_str());
+                enqueue_release_jobs(&app, &krate, &version, actor, &*conn).await?;
             }
             Ok(())
         };
 
-        let sparse_index_job = jobs::SyncToSparseIndex::new(&krate.name);
-        let publish_notifications_job = SendPublishNotificationsJob::new(version.id);
-        let crate_feed_job = jobs::rss::SyncCrateFeed::new(krate.name.clone());
-        let updates_feed_job = jobs::rss::SyncUpdatesFeed;
         let analyze_crate_file_job = AnalyzeCrateFile::new(version.id);
         let build_crate_zip_job = BuildCrateZip::new(version.id);
 
@@ -680,12 +750,8 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
         };
 
         tokio::try_join!(
-            sync_git_index,
-            sparse_index_job.enqueue(&*conn),
-            publish_notifications_job.enqueue(&*conn),
+            release,
             build_crate_zip,
-            enqueue_or_log(&crate_feed_job, &*conn),
-            enqueue_or_log(&updates_feed_job, &*conn),
             enqueue_or_log(&analyze_crate_file_job, &*conn),
         )?;
 
@@ -708,13 +774,20 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
             )?;
         }
 
-        // The `other` field on `PublishWarnings` was introduced to handle a temporary warning
-        // that is no longer needed. As such, crates.io currently does not return any `other`
-        // warnings at this time, but if we need to, the field is available.
+        // The `other` warnings are displayed by cargo, so they are used to
+        // tell the user that a staged version still has to be promoted.
+        let mut other = vec![];
+        if staged {
+            other.push(format!(
+                "{name}@{version_string} was uploaded as a staged version, and will not be \
+                available until an owner of the crate promotes it"
+            ));
+        }
+
         let warnings = PublishWarnings {
             invalid_categories: vec![],
             invalid_badges: vec![],
-            other: vec![],
+            other,
         };
 
         Ok(Json(GoodCrate {
@@ -733,6 +806,55 @@ pub async fn publish(app: AppState, req: Parts, body: Body) -> AppResult<Json<Go
     }).await
 }
 
+/// Enqueues the background jobs that make a new version visible, i.e. the
+/// index updates, the RSS feed updates, the publish notifications and the
+/// `publish` webhook event.
+///
+/// For staged versions, these jobs are only enqueued once the version is
+/// promoted.
+pub async fn enqueue_release_jobs(
+    app: &AppState,
+    krate: &Crate,
+    version: &Version,
+    actor: Option<&str>,
+    conn: &AsyncPgConnection,
+) -> Result<(), EnqueueError> {
+    let crate_name = krate.name.as_str();
+    let version_id = version.id;
+
+    let sync_git_index = async {
+        if app.config.sync_git_index {
+            let git_index_job = jobs::SyncToGitIndex::new(crate_name);
+            git_index_job.enqueue(conn).await?;
+        }
+        Ok(())
+    };
+
+    let sparse_index_job = jobs::SyncToSparseIndex::new(crate_name);
+    // The publish notifications are sent by this job once the compatibility
+    // report is available, so that they can include its warnings.
+    let check_compatibility_job = CheckCompatibility::for_release(version_id);
+    let crate_feed_job = jobs::rss::SyncCrateFeed::new(crate_name.to_string());
+    let updates_feed_job = jobs::rss::SyncUpdatesFeed;
+    let webhook_job = jobs::DispatchWebhookEvent::builder()
+        .crate_id(krate.id)
+        .event(WebhookEvent::Publish)
+        .version(&version.num)
+        .maybe_actor(actor)
+        .build();
+
+    tokio::try_join!(
+        sync_git_index,
+        sparse_index_job.enqueue(conn),
+        check_compatibility_job.enqueue(conn),
+        enqueue_or_log(&crate_feed_job, conn),
+        enqueue_or_log(&updates_feed_job, conn),
+        enqueue_or_log(&webhook_job, conn),
+    )?;
+
+    Ok(())
+}
+
 /// Enqueues a background job, logging any error instead of propagating it.
 ///
 /// Used for jobs whose failure should not abort the publish flow.
@@ -0,0 +1,242 @@
+//! Endpoints for managing the read access to crates in private registry mode.
+//!
+//! Crates without readers can be read by all authenticated users. Once a
+//! crate has at least one reader, only its readers and the users that own it
+//! can download it, find it via search and fetch its sparse index file.
+
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::controllers::helpers::authorization::Rights;
+use crate::controllers::krate::CratePath;
+use crate::models::token::EndpointScope;
+use crate::models::{Crate, CrateReader, PublicUser, User};
+use crate::schema::{crate_readers, users};
+use crate::util::errors::{AppResult, bad_request, custom};
+use crate::views::EncodablePublicUser;
+use crate::worker::jobs;
+use axum::Json;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use http::StatusCode;
+use http::request::Parts;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ReadersResponse {
+    /// The users that are allowed to read the crate, in addition to its
+    /// owners.
+    pub users: Vec<EncodablePublicUser>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ChangeReadersRequest {
+    /// Login names of the users to grant or revoke read access.
+    #[schema(example = json!(["octocat"]))]
+    users: Vec<String>,
+}
+
+/// List the readers of a crate.
+///
+/// Only available in private registry mode. Only owners of the crate can
+/// see its readers.
+#[utoipa::path(
+    get,
+    path = "/api/v1/crates/{name}/readers",
+    params(CratePath),
+    security(
+        ("api_token" = []),
+        ("cookie" = []),
+    ),
+    tag = "owners",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(ReadersResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn list_readers(
+    app: AppState,
+    path: CratePath,
+    req: Parts,
+) -> AppResult<Json<ReadersResponse>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let auth = AuthCheck::default().for_crate(&path.name);
+    let krate = load_crate_as_owner(&app, &path, &req, auth, Rights::Publish, &mut conn).await?;
+
+    let users = load_readers(&conn, &krate).await?;
+    Ok(Json(ReadersResponse { users }))
+}
+
+/// Grant users read access to a crate.
+///
+/// Only available in private registry mode. Adding the first reader
+/// restricts the read access to the crate to its owners and readers.
+#[utoipa::path(
+    put,
+    path = "/api/v1/crates/{name}/readers",
+    params(CratePath),
+    request_body = inline(ChangeReadersRequest),
+    security(
+        ("api_token" = []),
+        ("cookie" = []),
+    ),
+    tag = "owners",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(ReadersResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn add_readers(
+    app: AppState,
+    path: CratePath,
+    req: Parts,
+    Json(body): Json<ChangeReadersRequest>,
+) -> AppResult<Json<ReadersResponse>> {
+    modify_readers(app, path, req, body, true).await
+}
+
+/// Revoke the read access of users to a crate.
+///
+/// Only available in private registry mode. Removing the last reader makes
+/// the crate readable by all authenticated users again.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/crates/{name}/readers",
+    params(CratePath),
+    request_body = inline(ChangeReadersRequest),
+    security(
+        ("api_token" = []),
+        ("cookie" = []),
+    ),
+    tag = "owners",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(ReadersResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn remove_readers(
+    app: AppState,
+    path: CratePath,
+    req: Parts,
+    Json(body): Json<ChangeReadersRequest>,
+) -> AppResult<Json<ReadersResponse>> {
+    modify_readers(app, path, req, body, false).await
+}
+
+async fn modify_readers(
+    app: AppState,
+    path: CratePath,
+    req: Parts,
+    body: ChangeReadersRequest,
+    add: bool,
+) -> AppResult<Json<ReadersResponse>> {
+    if body.users.len() > 10 {
+        return Err(bad_request("too many users for this request - maximum 10"));
+    }
+
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::default()
+        .with_endpoint_scope(EndpointScope::ChangeOwners)
+        .for_crate(&path.name);
+    let krate = load_crate_as_owner(&app, &path, &req, auth, Rights::Full, &mut conn).await?;
+
+    let mut user_ids = Vec::with_capacity(body.users.len());
+    for login in &body.users {
+        let user = User::find_by_login(&conn, login)
+            .await
+            .optional()?
+            .ok_or_else(|| bad_request(format_args!("could not find user with login `{login}`")))?;
+
+        user_ids.push(user.id);
+    }
+
+    if add {
+        let readers = user_ids
+            .iter()
+            .map(|&user_id| CrateReader {
+                crate_id: krate.id,
+                user_id,
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(crate_readers::table)
+            .values(&readers)
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .await?;
+    } else {
+        diesel::delete(crate_readers::table)
+            .filter(crate_readers::crate_id.eq(krate.id))
+            .filter(crate_readers::user_id.eq_any(&user_ids))
+            .execute(&mut conn)
+            .await?;
+    }
+
+    // Crates with restricted read access are left out of the git index
+    if app.config.sync_git_index {
+        jobs::SyncToGitIndex::new(&krate.name)
+            .enqueue(&conn)
+            .await?;
+    }
+
+    // The public RSS feed of the crate is removed while its read access is
+    // restricted
+    jobs::rss::SyncCrateFeed::new(krate.name.clone())
+        .enqueue(&conn)
+        .await?;
+
+    let users = load_readers(&conn, &krate).await?;
+    Ok(Json(ReadersResponse { users }))
+}
+
+/// Loads the crate, and checks that the authenticated user has at least the
+/// given rights for it.
+async fn load_crate_as_owner(
+    app: &AppState,
+    path: &CratePath,
+    req: &Parts,
+    auth: AuthCheck,
+    required_rights: Rights,
+    conn: &mut AsyncPgConnection,
+) -> AppResult<Crate> {
+    if !app.config.private_registry {
+        return Err(bad_request(
+            "read access can only be restricted in private registry mode",
+        ));
+    }
+
+    let auth = auth.check(req, conn).await?;
+    let krate = path.load_crate(conn).await?;
+
+    let user = auth.user();
+    let owners = krate.owners(conn).await?;
+    let rights = Rights::get(user, app, &owners).await?;
+    if rights < required_rights {
+        return Err(custom(
+            StatusCode::FORBIDDEN,
+            "only owners have permission to manage the readers of a crate",
+        ));
+    }
+
+    Ok(krate)
+}
+
+async fn load_readers(
+    mut conn: &AsyncPgConnection,
+    krate: &Crate,
+) -> QueryResult<Vec<EncodablePublicUser>> {
+    let reader_ids = crate_readers::table
+        .filter(crate_readers::crate_id.eq(krate.id))
+        .select(crate_readers::user_id);
+
+    let users = PublicUser::query()
+        .filter(users::id.eq_any(reader_ids))
+        .order(users::id)
+        .load(&mut conn)
+        .await?;
+
+    Ok(users.into_iter().map(EncodablePublicUser::from).collect())
+}
@@ -1,6 +1,8 @@
 use crate::app::AppState;
 use crate::controllers::helpers::pagination::{PaginationOptions, PaginationQueryParams};
 use crate::controllers::krate::CratePath;
+use crate::controllers::version::add_advisories;
+use crate::middleware::private_registry::RegistryReader;
 use crate::models::{CrateName, PublicUser, ReverseDependency, Version, VersionOwnerAction};
 use crate::util::errors::AppResult;
 use crate::views::{EncodableDependency, EncodableVersion};
@@ -54,11 +56,16 @@ pub async fn list_reverse_dependencies(
 
     let krate = path.load_crate(&conn).await?;
 
+    let reader_id = req
+        .extensions
+        .get::<RegistryReader>()
+        .map(|reader| reader.user_id);
+
     let offset = pagination_options.offset().unwrap_or_default();
     let limit = pagination_options.per_page;
     let (rev_deps, total) = tokio::try_join!(
-        ReverseDependency::page_for_crate(krate.id, &conn, offset, limit),
-        ReverseDependency::count_for_crate(krate.id, &conn),
+        ReverseDependency::page_for_crate(krate.id, reader_id, &conn, offset, limit),
+        ReverseDependency::count_for_crate(krate.id, reader_id, &conn),
     )?;
 
     let rev_deps: Vec<_> = rev_deps
@@ -84,7 +91,7 @@ pub async fn list_reverse_dependencies(
 
     let actions = VersionOwnerAction::for_versions(&conn, &versions).await?;
 
-    let versions = versions_and_publishers
+    let mut versions = versions_and_publishers
         .into_iter()
         .zip(actions)
         .map(|((version, krate_name, published_by), actions)| {
@@ -92,6 +99,8 @@ pub async fn list_reverse_dependencies(
         })
         .collect::<Vec<_>>();
 
+    add_advisories(&conn, &mut versions).await?;
+
     Ok(Json(RevDepsResponse {
         dependencies: rev_deps,
         versions,
@@ -8,9 +8,9 @@ use axum_extra::extract::Query;
 use axum_extra::headers::CacheControl;
 use derive_more::Deref;
 use diesel::alias;
-use diesel::dsl::{InnerJoinQuerySource, LeftJoinQuerySource, exists};
+use diesel::dsl::{AsExprOf, InnerJoinQuerySource, LeftJoinQuerySource, case_when, exists};
 use diesel::prelude::*;
-use diesel::sql_types::Bool;
+use diesel::sql_types::{Bool, Float};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use diesel_full_text_search::{configuration::TsConfigurationByName, *};
 use http::request::Parts;
@@ -20,7 +20,8 @@ use utoipa::IntoParams;
 
 use crate::app::AppState;
 use crate::controllers::helpers::Paginate;
-use crate::models::{Crate, CrateOwner, OwnerKind, TopVersions, Version};
+use crate::middleware::private_registry::RegistryReader;
+use crate::models::{Crate, CrateOwner, CrateReader, OwnerKind, TopVersions, Version};
 use crate::schema::*;
 use crate::util::errors::{AppResult, bad_request};
 use crate::views::EncodableCrate;
@@ -36,6 +37,9 @@ use crates_io_database::fns::{array_agg, canon_crate_name, lower};
 /// query (see [`FilterParams::relevance_candidate_ids`]).
 const RELEVANCE_CANDIDATE_LIMIT: i64 = 1000;
 
+/// The factor by which the relevance rank of deprecated crates is reduced.
+const DEPRECATED_RANK_FACTOR: f32 = 0.1;
+
 /// Response returned when listing crates.
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct CrateListResponse {
@@ -111,11 +115,18 @@ pub async fn list_crates(
     use diesel::sql_types::Float;
     use seek::*;
 
-    let filter_params = FilterParams::from(params, &req, &mut conn).await?;
+    let reader = match app.config.private_registry {
+        true => Some(RegistryReader::from_parts(&req)?),
+        false => None,
+    };
+
+    let filter_params = FilterParams::from(params, reader, &req, &mut conn).await?;
 
-    // When the results are filtered by the followed crates of the authenticated
-    // user, the response depends on the user's identity and must not be cached.
-    let cache_control = filter_params.auth_user_id.map(|_| no_store());
+    // When the results are filtered by the followed crates or the read access
+    // of the authenticated user, the response depends on the user's identity
+    // and must not be cached.
+    let is_personalized = filter_params.auth_user_id.is_some() || filter_params.reader.is_some();
+    let cache_control = is_personalized.then(no_store);
 
     let sort = filter_params.sort.as_deref();
 
@@ -159,8 +170,7 @@ pub async fn list_crates(
                 .await?;
             query = query.filter(crates::id.eq_any(candidate_ids));
 
-            let q = plainto_tsquery_with_search_config(TsConfigurationByName("english"), q_string);
-            let rank = ts_rank_cd(crates::textsearchable_index_col, q);
+            let rank = relevance_rank(q_string);
             query = query.select((
                 ALL_COLUMNS,
                 Crate::with_name(q_string),
@@ -404,11 +414,15 @@ struct FilterParams {
     search_params: CrateListQueryParams,
     letter: Option<char>,
     auth_user_id: Option<i32>,
+    /// The authenticated user in private registry mode, whose read access
+    /// the results are restricted to.
+    reader: Option<RegistryReader>,
 }
 
 impl FilterParams {
     async fn from(
         search_params: CrateListQueryParams,
+        reader: Option<RegistryReader>,
         parts: &Parts,
         conn: &mut AsyncPgConnection,
     ) -> AppResult<Self> {
@@ -427,6 +441,7 @@ impl FilterParams {
             search_params,
             letter,
             auth_user_id,
+            reader,
         })
     }
 }
@@ -517,6 +532,10 @@ impl FilterParams {
             query = query.filter(crates::name.eq_any(self.ids.iter().map(|s| s.as_str())));
         }
 
+        if let Some(reader) = self.reader {
+            query = query.filter(crates::id.ne_all(CrateReader::hidden_crate_ids(reader.user_id)));
+        }
+
         if !self.include_yanked() {
             query = query.filter(exists(
                 versions::table
@@ -708,11 +727,7 @@ impl FilterParams {
                 // ORDER BY exact_match DESC, rank DESC, recent_downloads DESC NULLS LAST, name ASC
                 // ```
                 let q_string = self.q_string.as_ref().expect("q_string should not be None");
-                let q = plainto_tsquery_with_search_config(
-                    TsConfigurationByName("english"),
-                    q_string.as_str(),
-                );
-                let rank = ts_rank_cd(crates::textsearchable_index_col, q);
+                let rank = relevance_rank(q_string.as_str());
                 let name_exact_match = Crate::with_name(q_string.as_str());
                 let downloads = recent_crate_downloads::downloads;
 
@@ -770,6 +785,20 @@ impl FilterParams {
     }
 }
 
+/// The full-text search rank of a crate for the given query.
+///
+/// The rank of deprecated crates is reduced by [`DEPRECATED_RANK_FACTOR`], so
+/// that they are listed below their maintained alternatives. Exact name
+/// matches are still listed first though.
+#[diesel::dsl::auto_type]
+fn relevance_rank<'a>(q_string: &'a str) -> _ {
+    let config: TsConfigurationByName = TsConfigurationByName("english");
+    let q = plainto_tsquery_with_search_config(config, q_string);
+    let factor: AsExprOf<f32, Float> = DEPRECATED_RANK_FACTOR.into_sql::<Float>();
+    let rank = ts_rank_cd(crates::textsearchable_index_col, q);
+    case_when(crates::deprecated_at.is_null(), rank).otherwise(rank * factor)
+}
+
 mod seek {
     use super::Record;
     use crate::controllers::helpers::pagination::seek;
@@ -4,18 +4,25 @@ use crate::controllers::krate::CratePath;
 use crate::email::EmailMessage;
 use crate::middleware::real_ip::RealIp;
 use crate::models::token::EndpointScope;
-use crate::models::{Crate, User};
+use crate::models::two_factor::has_two_factor;
+use crate::models::{Crate, CrateOwner, OwnerKind, User};
 use crate::schema::*;
-use crate::util::errors::{AppResult, crate_not_found, custom};
+use crate::util::errors::{AppResult, bad_request, crate_not_found, custom};
 use crate::views::EncodableCrate;
+use crate::worker::jobs;
 use anyhow::Context;
 use axum::{Extension, Json};
+use chrono::Utc;
+use crates_io_worker::BackgroundJob;
 use diesel::prelude::*;
 use diesel_async::{AsyncConnection, RunQueryDsl};
 use http::{StatusCode, request::Parts};
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
+/// The maximum number of characters in a deprecation message.
+const MAX_DEPRECATION_MESSAGE_LENGTH: usize = 1000;
+
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct PatchRequest {
     /// The crate settings to update.
@@ -29,6 +36,35 @@ pub struct PatchRequestCrate {
     /// Whether this crate can only be published via Trusted Publishing.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub trustpub_only: Option<bool>,
+
+    /// Whether new versions of this crate are always staged, and have to be
+    /// promoted by an owner other than the one who published them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_promotion_review: Option<bool>,
+
+    /// Whether all owners of this crate are required to have two-factor
+    /// authentication enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_owner_2fa: Option<bool>,
+
+    /// Whether this crate is deprecated.
+    ///
+    /// Setting this to `false` also removes the deprecation message and the
+    /// successor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+
+    /// A message explaining why the crate is deprecated.
+    ///
+    /// Can only be set together with `deprecated: true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation_message: Option<String>,
+
+    /// The name of a crate that should be used instead of this one.
+    ///
+    /// Can only be set together with `deprecated: true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub successor: Option<String>,
 }
 
 #[derive(Debug, Serialize, utoipa::ToSchema)]
@@ -69,8 +105,10 @@ pub async fn update_crate(
 
     // Check that the user is authenticated with appropriate permissions
     let auth = AuthCheck::default()
-        .with_endpoint_scope(EndpointScope::TrustedPublishing)
+        .with_endpoint_scope(EndpointScope::UpdateCrate)
+        .or_endpoint_scope(EndpointScope::TrustedPublishing)
         .for_crate(&krate.name)
+        .require_second_factor()
         .check(&req, &mut conn)
         .await?;
 
@@ -108,8 +146,45 @@ async fn update_inner(
         return Err(custom(StatusCode::FORBIDDEN, msg));
     }
 
+    let PatchRequestCrate {
+        trustpub_only,
+        require_promotion_review,
+        require_owner_2fa,
+        deprecated,
+        deprecation_message,
+        successor,
+    } = body.krate;
+
+    // Validate the whole request before anything is written, so that no
+    // notification emails are sent for changes that are rolled back.
+    if require_owner_2fa == Some(true) && !krate.require_owner_2fa {
+        ensure_owners_have_two_factor(conn, krate).await?;
+    }
+
+    if deprecated != Some(true) && (deprecation_message.is_some() || successor.is_some()) {
+        let msg = "`deprecation_message` and `successor` can only be set together with `deprecated: true`";
+        return Err(bad_request(msg));
+    }
+
+    let deprecation_message = deprecation_message
+        .map(|message| message.trim().to_string())
+        .filter(|message| !message.is_empty());
+
+    if let Some(message) = &deprecation_message
+        && message.chars().count() > MAX_DEPRECATION_MESSAGE_LENGTH
+    {
+        return Err(bad_request(format!(
+            "The deprecation message must not be longer than {MAX_DEPRECATION_MESSAGE_LENGTH} characters"
+        )));
+    }
+
+    let successor = match successor {
+        Some(successor) => Some(find_successor(conn, krate, &successor).await?),
+        None => None,
+    };
+
     // Update trustpub_only if provided
-    if let Some(trustpub_only) = body.krate.trustpub_only
+    if let Some(trustpub_only) = trustpub_only
         && trustpub_only != krate.trustpub_only
     {
         diesel::update(crates::table)
@@ -148,6 +223,136 @@ async fn update_inner(
         }
     }
 
+    // Update require_promotion_review if provided
+    if let Some(require_promotion_review) = require_promotion_review
+        && require_promotion_review != krate.require_promotion_review
+    {
+        diesel::update(crates::table)
+            .filter(crates::id.eq(krate.id))
+            .set(crates::require_promotion_review.eq(require_promotion_review))
+            .execute(conn)
+            .await?;
+
+        // Audit log the setting change
+        info!(
+            target: "audit",
+            action = "require_promotion_review_change",
+            krate.name = %krate.name,
+            network.client.ip = %**real_ip,
+            usr.id = user.id,
+            usr.name = %user.gh_login,
+            "User {} set require_promotion_review={require_promotion_review} for crate {}",
+            user.gh_login,
+            krate.name
+        );
+
+        // Disabling the review allows a single owner to publish new versions
+        // again, so all owners are notified about the change.
+        for (_, gh_login, email_address, email_verified) in &user_owners {
+            if *email_verified {
+                let email = PromotionReviewChangedEmail {
+                    recipient: gh_login,
+                    auth_user: user,
+                    krate,
+                    require_promotion_review,
+                };
+
+                if let Err(err) = email.send(app, email_address).await {
+                    warn!(
+                        "Failed to send require_promotion_review notification to {email_address}: {err}"
+                    );
+                }
+            }
+        }
+    }
+
+    // Update require_owner_2fa if provided
+    if let Some(require_owner_2fa) = require_owner_2fa
+        && require_owner_2fa != krate.require_owner_2fa
+    {
+        diesel::update(crates::table)
+            .filter(crates::id.eq(krate.id))
+            .set(crates::require_owner_2fa.eq(require_owner_2fa))
+            .execute(conn)
+            .await?;
+
+        // Audit log the setting change
+        info!(
+            target: "audit",
+            action = "require_owner_2fa_change",
+            krate.name = %krate.name,
+            network.client.ip = %**real_ip,
+            usr.id = user.id,
+            usr.name = %user.gh_login,
+            "User {} set require_owner_2fa={require_owner_2fa} for crate {}",
+            user.gh_login,
+            krate.name
+        );
+
+        for (_, gh_login, email_address, email_verified) in &user_owners {
+            if *email_verified {
+                let email = Owner2faChangedEmail {
+                    recipient: gh_login,
+                    auth_user: user,
+                    krate,
+                    require_owner_2fa,
+                };
+
+                if let Err(err) = email.send(app, email_address).await {
+                    warn!(
+                        "Failed to send require_owner_2fa notification to {email_address}: {err}"
+                    );
+                }
+            }
+        }
+    }
+
+    // Update the deprecation if provided
+    if let Some(deprecated) = deprecated {
+        let (deprecated_at, deprecation_message, successor) = if deprecated {
+            let deprecated_at = krate.deprecated_at.unwrap_or_else(Utc::now);
+            (Some(deprecated_at), deprecation_message, successor)
+        } else {
+            (None, None, None)
+        };
+
+        diesel::update(crates::table)
+            .filter(crates::id.eq(krate.id))
+            .set((
+                crates::deprecated_at.eq(deprecated_at),
+                crates::deprecation_message.eq(&deprecation_message),
+                crates::successor.eq(&successor),
+            ))
+            .execute(conn)
+            .await?;
+
+        // Audit log the setting change
+        info!(
+            target: "audit",
+            action = "deprecation_change",
+            krate.name = %krate.name,
+            network.client.ip = %**real_ip,
+            usr.id = user.id,
+            usr.name = %user.gh_login,
+            "User {} set deprecated={deprecated} for crate {}",
+            user.gh_login,
+            krate.name
+        );
+
+        // The deprecation is included in the index files
+        let sync_git_index = async {
+            if app.config.sync_git_index {
+                let git_index_job = jobs::SyncToGitIndex::new(&krate.name);
+                git_index_job.enqueue(&*conn).await?;
+            }
+            Ok(())
+        };
+
+        let sparse_index_job = jobs::SyncToSparseIndex::new(&krate.name);
+
+        tokio::try_join!(sync_git_index, sparse_index_job.enqueue(&*conn))?;
+    }
+
     // Reload the crate to get updated data
     let (krate, downloads, recent_downloads, default_version, yanked, num_versions): (
         Crate,
@@ -193,6 +398,60 @@ async fn update_inner(
     }))
 }
 
+/// Looks up the successor of a deprecated crate, and returns its name as it
+/// is spelled in the database.
+async fn find_successor(
+    conn: &mut diesel_async::AsyncPgConnection,
+    krate: &Crate,
+    successor: &str,
+) -> AppResult<String> {
+    let successor = Crate::by_name(successor)
+        .select(crates::name)
+        .first::<String>(conn)
+        .await
+        .optional()?
+        .ok_or_else(|| bad_request(format!("The successor crate `{successor}` does not exist")))?;
+
+    if successor == krate.name {
+        return Err(bad_request("A crate cannot be its own successor"));
+    }
+
+    Ok(successor)
+}
+
+/// Returns an error listing the user owners of the crate that don't have
+/// two-factor authentication enabled, if there are any.
+async fn ensure_owners_have_two_factor(
+    conn: &mut diesel_async::AsyncPgConnection,
+    krate: &Crate,
+) -> AppResult<()> {
+    // Unlike the owner query above, this includes owners without an email
+    // address.
+    let owners = CrateOwner::by_owner_kind(OwnerKind::User)
+        .inner_join(users::table)
+        .filter(crate_owners::crate_id.eq(krate.id))
+        .select((users::id, users::gh_login))
+        .order(users::gh_login)
+        .load::<(i32, String)>(conn)
+        .await?;
+
+    let mut owners_without_2fa = Vec::new();
+    for (user_id, gh_login) in owners {
+        if !has_two_factor(conn, user_id).await? {
+            owners_without_2fa.push(gh_login);
+        }
+    }
+
+    if !owners_without_2fa.is_empty() {
+        let logins = owners_without_2fa.join("`, `");
+        return Err(bad_request(format!(
+            "All owners need to enable two-factor authentication first. Missing for: `{logins}`"
+        )));
+    }
+
+    Ok(())
+}
+
 #[derive(Serialize)]
 struct TrustpubOnlyChangedEmail<'a> {
     /// The GitHub login of the email recipient.
@@ -217,3 +476,53 @@ impl TrustpubOnlyChangedEmail<'_> {
             .context("Failed to send email")
     }
 }
+
+#[derive(Serialize)]
+struct PromotionReviewChangedEmail<'a> {
+    /// The GitHub login of the email recipient.
+    recipient: &'a str,
+    /// The user who changed the setting.
+    auth_user: &'a User,
+    /// The crate for which the setting was changed.
+    krate: &'a Crate,
+    /// The new value of the `require_promotion_review` flag.
+    require_promotion_review: bool,
+}
+
+impl PromotionReviewChangedEmail<'_> {
+    async fn send(&self, state: &AppState, email_address: &str) -> anyhow::Result<()> {
+        let email = EmailMessage::from_template("promotion_review_changed", self);
+        let email = email.context("Failed to render email template")?;
+
+        state
+            .emails
+            .send(email_address, email)
+            .await
+            .context("Failed to send email")
+    }
+}
+
+#[derive(Serialize)]
+struct Owner2faChangedEmail<'a> {
+    /// The GitHub login of the email recipient.
+    recipient: &'a str,
+    /// The user who changed the setting.
+    auth_user: &'a User,
+    /// The crate for which the setting was changed.
+    krate: &'a Crate,
+    /// The new value of the `require_owner_2fa` flag.
+    require_owner_2fa: bool,
+}
+
+impl Owner2faChangedEmail<'_> {
+    async fn send(&self, state: &AppState, email_address: &str) -> anyhow::Result<()> {
+        let email = EmailMessage::from_template("owner_2fa_changed", self);
+        let email = email.context("Failed to render email template")?;
+
+        state
+            .emails
+            .send(email_address, email)
+            .await
+            .context("Failed to send email")
+    }
+}
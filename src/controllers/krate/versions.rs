@@ -5,6 +5,7 @@ use crate::controllers::helpers::pagination::{
     Page, PaginationOptions, PaginationQueryParams, encode_seek,
 };
 use crate::controllers::krate::CratePath;
+use crate::controllers::version::add_advisories;
 use crate::models::{PublicUser, Version, VersionOwnerAction};
 use crate::schema::{oauth_github, users, versions};
 use crate::util::RequestUtils;
@@ -115,13 +116,15 @@ pub async fn list_versions(
         .map(|(v, _)| v)
         .collect::<Vec<_>>();
     let actions = VersionOwnerAction::for_versions(&conn, &versions).await?;
-    let versions = versions_and_publishers
+    let mut versions = versions_and_publishers
         .data
         .into_iter()
         .zip(actions)
         .map(|((v, pb), aas)| EncodableVersion::from(v, &path.name, pb, aas))
         .collect::<Vec<_>>();
 
+    add_advisories(&conn, &mut versions).await?;
+
     Ok(Json(VersionListResponse {
         versions,
         meta: versions_and_publishers.meta,
@@ -150,6 +153,7 @@ async fn list(
     let make_base_query = || {
         let mut query = versions::table
             .filter(versions::crate_id.eq(crate_id))
+            .filter(versions::staged_at.is_null())
             .left_outer_join(users::table.left_join(oauth_github::table))
             .select(<(Version, Option<PublicUser>)>::as_select())
             .into_boxed();
@@ -211,6 +215,7 @@ async fn list(
             versions::table
                 .filter(versions::crate_id.eq(crate_id))
                 .filter(not(versions::yanked))
+                .filter(versions::staged_at.is_null())
                 .select(versions::num)
                 .order(versions::semver_ord_v2.desc())
                 .load_stream::<String>(&mut conn)
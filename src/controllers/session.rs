@@ -1,4 +1,5 @@
 use crate::app::AppState;
+use crate::auth::clear_second_factor_verification;
 use crate::controllers::helpers::OkResponse;
 use crate::email::EmailMessage;
 use crate::email::Emails;
@@ -25,6 +26,8 @@ use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
 use tracing::{error, warn};
 
+pub mod identity;
+
 /// Session key containing serialized pending-signup state.
 pub const PENDING_SIGNUP_KEY: &str = "pending_signup";
 const PENDING_SIGNUP_LIFETIME: chrono::TimeDelta = chrono::TimeDelta::minutes(30);
@@ -77,14 +80,20 @@ pub async fn begin_session(app: AppState, session: SessionExtension) -> Json<Beg
 
 #[derive(Clone, Debug, Deserialize, utoipa::ToSchema)]
 pub struct AuthorizeBody {
-    /// Temporary code received from the GitHub API.
+    /// Temporary code received from the OAuth provider (e.g. GitHub).
     #[schema(value_type = String, example = "901dd10e07c7e9fa1cd5")]
     code: AuthorizationCode,
-    /// State parameter received from the GitHub API.
+    /// State parameter received from the OAuth provider (e.g. GitHub).
     #[schema(value_type = String, example = "fYcUY3FMdUUz00FC7vLT7A")]
     state: CsrfToken,
 }
 
+impl AuthorizeBody {
+    pub fn into_parts(self) -> (AuthorizationCode, CsrfToken) {
+        (self.code, self.state)
+    }
+}
+
 /// The result of completing the GitHub OAuth flow.
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 #[serde(tag = "status", rename_all = "snake_case")]
@@ -255,8 +264,7 @@ pub async fn complete_pending_signup(
         })
         .await?;
 
-    session.remove(PENDING_SIGNUP_KEY);
-    session.insert("user_id".to_string(), user_id.to_string());
+    sign_in(&session, user_id);
     Ok(user)
 }
 
@@ -353,8 +361,7 @@ pub async fn authorize_session(
 
     match user_id {
         Some(user_id) => {
-            session.remove(PENDING_SIGNUP_KEY);
-            session.insert("user_id".to_string(), user_id.to_string());
+            sign_in(&session, user_id);
 
             let Json(user) = super::user::me::authenticated_user(&mut conn, user_id).await?;
             Ok(Json(AuthorizeResponse::SignedIn(user)))
@@ -363,6 +370,7 @@ pub async fn authorize_session(
             let pending_signup = PendingSignup::new(ghuser, encrypted_token);
             let pending_signup = serde_json::to_string(&pending_signup)?;
             session.remove("user_id");
+            clear_second_factor_verification(&session);
             session.insert(PENDING_SIGNUP_KEY.to_string(), pending_signup);
             Ok(Json(AuthorizeResponse::SignupRequired))
         }
@@ -569,9 +577,20 @@ async fn find_user_by_gh_id(mut conn: &AsyncPgConnection, gh_id: i32) -> QueryRe
 pub async fn end_session(session: SessionExtension) -> OkResponse {
     session.remove("user_id");
     session.remove(PENDING_SIGNUP_KEY);
+    clear_second_factor_verification(&session);
     OkResponse::new()
 }
 
+/// Signs the session in as the given user.
+///
+/// A verification with a second factor from a previous sign-in in the same
+/// browser does not carry over to the new one.
+fn sign_in(session: &SessionExtension, user_id: i32) {
+    session.remove(PENDING_SIGNUP_KEY);
+    clear_second_factor_verification(session);
+    session.insert("user_id".to_string(), user_id.to_string());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,215 @@
+//! Sign-in with linked accounts of identity providers other than GitHub.
+
+use super::{AuthorizeBody, AuthorizeResponse, BeginResponse, PENDING_SIGNUP_KEY, sign_in};
+use crate::app::AppState;
+use crate::controllers::helpers::identity_provider::{ExternalIdentity, IdentityProvider};
+use crate::schema::linked_identities;
+use crate::util::diesel::is_read_only_error;
+use crate::util::errors::{AppResult, bad_request};
+use axum::Json;
+use axum::extract::Path;
+use chrono::Utc;
+use crates_io_session::SessionExtension;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use http::request::Parts;
+
+/// Begin authentication flow with a linked account.
+///
+/// This route will return an authorization URL for the OAuth flow of the
+/// identity provider including the crates.io `client_id` and a randomly
+/// generated `state` secret. Only accounts that have been linked to a
+/// crates.io account before can be used to sign in.
+#[utoipa::path(
+    post,
+    path = "/api/private/session/{provider}/begin",
+    params(
+        ("provider" = String, Path, description = "ID of the identity provider, e.g. `gitlab`"),
+    ),
+    tag = "session",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(BeginResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn begin_identity_session(
+    app: AppState,
+    Path(provider): Path<String>,
+    session: SessionExtension,
+) -> AppResult<Json<BeginResponse>> {
+    let provider = IdentityProvider::find(&app, &provider)?;
+
+    session.remove(PENDING_SIGNUP_KEY);
+
+    Ok(Json(provider.begin(&session)))
+}
+
+/// Complete authentication flow with a linked account.
+///
+/// This route is called after the user accepted or rejected the data access
+/// permissions at the identity provider. It will check the `state` parameter,
+/// exchange the temporary `code` for an access token and sign in the
+/// crates.io user that the account is linked to.
+#[utoipa::path(
+    post,
+    path = "/api/private/session/{provider}/authorize",
+    params(
+        ("provider" = String, Path, description = "ID of the identity provider, e.g. `gitlab`"),
+    ),
+    tag = "session",
+    request_body = inline(AuthorizeBody),
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(AuthorizeResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn authorize_identity_session(
+    app: AppState,
+    Path(provider): Path<String>,
+    session: SessionExtension,
+    req: Parts,
+    Json(body): Json<AuthorizeBody>,
+) -> AppResult<Json<AuthorizeResponse>> {
+    let provider = IdentityProvider::find(&app, &provider)?;
+
+    let (code, state) = body.into_parts();
+    let identity = provider
+        .authorize(&app, &session, &req, code, &state)
+        .await?;
+
+    let mut conn = app.db_write().await?;
+    let Some(user_id) = sign_in_with_identity(provider.id(), &identity, &mut conn).await? else {
+        let name = provider.name();
+        return Err(bad_request(format!(
+            "This {name} account is not linked to a crates.io account. \
+            Sign in with GitHub and link the {name} account in your account settings first."
+        )));
+    };
+
+    sign_in(&session, user_id);
+
+    let Json(user) = crate::controllers::user::me::authenticated_user(&mut conn, user_id).await?;
+    Ok(Json(AuthorizeResponse::SignedIn(user)))
+}
+
+/// Updates the details of a linked account and returns the ID of the
+/// crates.io user that it is linked to.
+///
+/// Returns `None` if the account is not linked to any crates.io user.
+async fn sign_in_with_identity(
+    provider: &str,
+    identity: &ExternalIdentity,
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<Option<i32>> {
+    let linked_identity = linked_identities::table.find((provider, &identity.account_id));
+
+    let result = diesel::update(linked_identity)
+        .set((
+            linked_identities::login.eq(identity.login.as_deref()),
+            linked_identities::email.eq(identity.email.as_deref()),
+            linked_identities::avatar.eq(identity.avatar.as_deref()),
+            linked_identities::encrypted_token.eq(identity.encrypted_token.as_deref()),
+            linked_identities::encrypted_refresh_token
+                .eq(identity.encrypted_refresh_token.as_deref()),
+            linked_identities::last_sync.eq(Utc::now()),
+        ))
+        .returning(linked_identities::user_id)
+        .get_result(conn)
+        .await
+        .optional();
+
+    match result {
+        // In read-only mode, the account details can't be updated, but the
+        // user can still be signed in.
+        Err(error) if is_read_only_error(&error) => linked_identity
+            .select(linked_identities::user_id)
+            .first(conn)
+            .await
+            .optional(),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NewUser;
+    use claims::{assert_none, assert_ok, assert_some_eq};
+    use crates_io_test_db::TestDatabase;
+
+    fn identity() -> ExternalIdentity {
+        ExternalIdentity {
+            account_id: "248289761001".into(),
+            login: Some("j.doe".into()),
+            email: Some("janedoe@example.com".into()),
+            avatar: None,
+            encrypted_token: None,
+            encrypted_refresh_token: None,
+        }
+    }
+
+    async fn insert_linked_user(conn: &mut AsyncPgConnection) -> i32 {
+        let new_user = NewUser::builder()
+            .gh_id(42)
+            .gh_login("ghost")
+            .username("ghost")
+            .build();
+        let user_id = assert_ok!(new_user.insert(conn).await);
+
+        let identity = ExternalIdentity {
+            login: Some("old-login".into()),
+            ..identity()
+        };
+        assert_ok!(identity.link_to("example", user_id).insert(conn).await);
+
+        user_id
+    }
+
+    #[tokio::test]
+    async fn signs_in_linked_user() {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.async_connect().await;
+        let user_id = insert_linked_user(&mut conn).await;
+
+        let result = sign_in_with_identity("example", &identity(), &mut conn).await;
+        assert_some_eq!(assert_ok!(result), user_id);
+
+        let login: Option<String> = assert_ok!(
+            linked_identities::table
+                .select(linked_identities::login)
+                .first(&mut conn)
+                .await
+        );
+        assert_some_eq!(login, "j.doe");
+    }
+
+    #[tokio::test]
+    async fn does_not_sign_in_unlinked_accounts() {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.async_connect().await;
+        insert_linked_user(&mut conn).await;
+
+        // Same account ID, but a different provider
+        let result = sign_in_with_identity("other", &identity(), &mut conn).await;
+        assert_none!(assert_ok!(result));
+    }
+
+    #[tokio::test]
+    async fn signs_in_linked_user_during_read_only_mode() {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.async_connect().await;
+        let user_id = insert_linked_user(&mut conn).await;
+        assert_ok!(
+            diesel::sql_query("SET default_transaction_read_only = 't'")
+                .execute(&mut conn)
+                .await
+        );
+
+        let result = sign_in_with_identity("example", &identity(), &mut conn).await;
+        assert_some_eq!(assert_ok!(result), user_id);
+    }
+}
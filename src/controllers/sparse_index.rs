@@ -0,0 +1,79 @@
+//! Serves the sparse index in private registry mode.
+//!
+//! Public registries serve the index files straight from the index bucket
+//! via a CDN. In private registry mode every index request is authenticated
+//! and checked against the read access of the crate, so the files are served
+//! by the application instead.
+//!
+//! See <https://doc.rust-lang.org/cargo/reference/registry-index.html#sparse-protocol>.
+
+use crate::app::AppState;
+use crate::middleware::private_registry::RegistryReader;
+use crate::models::Crate;
+use crate::util::errors::{AppResult, internal, not_found};
+use crate::util::no_store;
+use axum::Json;
+use axum::extract::Path;
+use axum::response::{IntoResponse, Response};
+use crates_io_index::Repository;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use http::header;
+use http::request::Parts;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct IndexConfig {
+    dl: String,
+    api: String,
+    #[serde(rename = "auth-required")]
+    auth_required: bool,
+}
+
+/// Returns the `config.json` file of the index, which tells cargo to send
+/// the registry token with all requests.
+pub async fn get_index_config(app: AppState) -> Json<IndexConfig> {
+    let domain_name = &app.config.domain_name;
+
+    Json(IndexConfig {
+        dl: format!("https://{domain_name}/api/v1/crates"),
+        api: format!("https://{domain_name}"),
+        auth_required: true,
+    })
+}
+
+/// Returns the index file of a crate, if the user is allowed to read it.
+pub async fn get_index_file(
+    app: AppState,
+    Path(path): Path<String>,
+    req: Parts,
+) -> AppResult<Response> {
+    let reader = RegistryReader::from_parts(&req)?;
+
+    let name = path.rsplit('/').next().unwrap_or_default();
+
+    let mut conn = app.db_read().await?;
+    let krate: Crate = Crate::by_name(name)
+        .first(&mut conn)
+        .await
+        .optional()?
+        .ok_or_else(not_found)?;
+
+    // Only the canonical location of the index file is served, since cargo
+    // caches the index files by their path.
+    if Repository::relative_index_file_for_url(&krate.name) != path {
+        return Err(not_found());
+    }
+
+    if !reader.can_read(&conn, krate.id).await? {
+        return Err(not_found());
+    }
+
+    let content = match app.storage.download_index(&krate.name).await {
+        Ok(content) => content,
+        Err(object_store::Error::NotFound { .. }) => return Err(not_found()),
+        Err(error) => return Err(internal(format!("failed to download index file: {error}"))),
+    };
+
+    Ok((no_store(), [(header::CONTENT_TYPE, "text/plain")], content).into_response())
+}
@@ -1,5 +1,5 @@
 use crate::app::AppState;
-use crate::models::{Category, Crate, Keyword, TopVersions, Version};
+use crate::models::{Category, Crate, CrateReader, Keyword, TopVersions, Version};
 use crate::schema::{
     crate_downloads, crates, default_versions, keywords, metadata, recent_crate_downloads, versions,
 };
@@ -64,6 +64,8 @@ pub async fn get_summary(
 
     let config = &state.config;
 
+    // The response is cached publicly, so crates with restricted read access
+    // are left out regardless of who is asking.
     let (
         num_crates,
         num_downloads,
@@ -74,29 +76,37 @@ pub async fn get_summary(
         popular_categories,
         popular_keywords,
     ) = tokio::try_join!(
-        crates::table.count().get_result(&mut &*conn).boxed(),
+        crates::table
+            .filter(crates::id.ne_all(CrateReader::restricted_crate_ids()))
+            .count()
+            .get_result(&mut &*conn)
+            .boxed(),
         metadata::table
             .select(metadata::total_downloads)
             .get_result(&mut &*conn)
             .boxed(),
         Record::query()
+            .filter(crates::id.ne_all(CrateReader::restricted_crate_ids()))
             .order(crates::created_at.desc())
             .limit(10)
             .load(&mut &*conn)
             .boxed(),
         Record::query()
+            .filter(crates::id.ne_all(CrateReader::restricted_crate_ids()))
             .filter(crates::updated_at.ne(crates::created_at))
             .order(crates::updated_at.desc())
             .limit(10)
             .load(&mut &*conn)
             .boxed(),
         Record::query()
+            .filter(crates::id.ne_all(CrateReader::restricted_crate_ids()))
             .filter(crates::name.ne_all(&config.excluded_crate_names))
             .then_order_by(crate_downloads::downloads.desc())
             .limit(10)
             .load(&mut &*conn)
             .boxed(),
         Record::query()
+            .filter(crates::id.ne_all(CrateReader::restricted_crate_ids()))
             .filter(crates::name.ne_all(&config.excluded_crate_names))
             .filter(recent_crate_downloads::downloads.is_not_null())
             .then_order_by(recent_crate_downloads::downloads.desc())
@@ -175,6 +185,7 @@ async fn encode_crates(
     let versions: Vec<Version> = Version::query()
         .filter(versions::crate_id.eq_any(crate_ids))
         .filter(versions::yanked.eq(false))
+        .filter(versions::staged_at.is_null())
         .load(&mut conn)
         .await?;
 
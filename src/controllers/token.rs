@@ -8,6 +8,7 @@ use crate::app::AppState;
 use crate::auth::AuthCheck;
 use crate::middleware::real_ip::RealIp;
 use crate::models::token::{CrateScope, EndpointScope};
+use crate::rate_limiter::LimitedAction;
 use crate::util::errors::{AppResult, bad_request, custom};
 use crate::util::no_store;
 use crate::util::token::PlainToken;
@@ -139,7 +140,10 @@ pub async fn create_api_token(
     }
 
     let mut conn = app.db_write().await?;
-    let auth = AuthCheck::default().check(&parts, &mut conn).await?;
+    let auth = AuthCheck::default()
+        .require_second_factor()
+        .check(&parts, &mut conn)
+        .await?;
 
     if auth.api_token_id().is_some() {
         return Err(bad_request(
@@ -204,6 +208,10 @@ pub async fn create_api_token(
         .transpose()
         .map_err(|_err| bad_request("invalid endpoint scope"))?;
 
+    app.rate_limiter
+        .check_rate_limit(user.id, LimitedAction::CreateToken, &mut conn)
+        .await?;
+
     let recipient = user.email(&conn).await?;
 
     let plaintext = PlainToken::generate();
@@ -1,12 +1,32 @@
 use crate::email::EmailMessage;
-use crates_io_database::models::trustpub::{GitHubConfig, GitLabConfig};
+use crates_io_database::models::trustpub::{GitHubConfig, GitLabConfig, OidcConfig};
 use crates_io_database::models::{Crate, User};
+use crates_io_trustpub::provider::provider_by_id;
 
 #[derive(Debug, Clone, Copy, serde::Serialize)]
 #[serde(tag = "type")]
 pub enum ConfigType<'a> {
     GitHub(&'a GitHubConfig),
     GitLab(&'a GitLabConfig),
+    Oidc {
+        /// Human-readable name of the OIDC provider (e.g. "Buildkite").
+        provider_name: &'a str,
+        #[serde(flatten)]
+        config: &'a OidcConfig,
+    },
+}
+
+impl<'a> ConfigType<'a> {
+    pub fn oidc(config: &'a OidcConfig) -> Self {
+        let provider_name = provider_by_id(&config.provider)
+            .map(|provider| provider.display_name())
+            .unwrap_or(&config.provider);
+
+        Self::Oidc {
+            provider_name,
+            config,
+        }
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -70,6 +90,11 @@ mod tests {
             max_upload_size: None,
             max_features: None,
             trustpub_only: false,
+            require_promotion_review: false,
+            require_owner_2fa: false,
+            deprecated_at: None,
+            deprecation_message: None,
+            successor: None,
         }
     }
 
@@ -99,6 +124,18 @@ mod tests {
         }
     }
 
+    fn test_oidc_config(claims: serde_json::Value) -> OidcConfig {
+        OidcConfig {
+            id: 1,
+            created_at: Utc::now(),
+            crate_id: 1,
+            provider: "buildkite".into(),
+            repository: "rust-lang/my-crate".into(),
+            repository_owner_id: None,
+            claims,
+        }
+    }
+
     #[test]
     fn test_config_created_email() {
         let email = ConfigCreatedEmail {
@@ -169,6 +206,37 @@ mod tests {
         assert_snapshot!(rendered.body_text);
     }
 
+    #[test]
+    fn test_config_created_email_oidc() {
+        let config = test_oidc_config(serde_json::json!({}));
+        let email = ConfigCreatedEmail {
+            recipient: "octocat",
+            auth_user: &test_user(),
+            krate: &test_crate(),
+            saved_config: ConfigType::oidc(&config),
+        };
+
+        let rendered = assert_ok!(email.render());
+        assert_snapshot!(rendered.subject, @"crates.io: Trusted Publishing configuration added to my-crate");
+        assert_snapshot!(rendered.body_text);
+    }
+
+    #[test]
+    fn test_config_created_email_oidc_with_claims() {
+        let claims = serde_json::json!({ "build_branch": "main", "step_key": "publish" });
+        let config = test_oidc_config(claims);
+        let email = ConfigCreatedEmail {
+            recipient: "octocat",
+            auth_user: &test_user(),
+            krate: &test_crate(),
+            saved_config: ConfigType::oidc(&config),
+        };
+
+        let rendered = assert_ok!(email.render());
+        assert_snapshot!(rendered.subject, @"crates.io: Trusted Publishing configuration added to my-crate");
+        assert_snapshot!(rendered.body_text);
+    }
+
     #[test]
     fn test_config_deleted_email() {
         let email = ConfigDeletedEmail {
@@ -238,4 +306,19 @@ mod tests {
         assert_snapshot!(rendered.subject, @"crates.io: Trusted Publishing configuration removed from my-crate");
         assert_snapshot!(rendered.body_text);
     }
+
+    #[test]
+    fn test_config_deleted_email_oidc() {
+        let config = test_oidc_config(serde_json::json!({ "step_key": "publish" }));
+        let email = ConfigDeletedEmail {
+            recipient: "octocat",
+            auth_user: &test_user(),
+            krate: &test_crate(),
+            config: ConfigType::oidc(&config),
+        };
+
+        let rendered = assert_ok!(email.render());
+        assert_snapshot!(rendered.subject, @"crates.io: Trusted Publishing configuration removed from my-crate");
+        assert_snapshot!(rendered.body_text);
+    }
 }
@@ -2,13 +2,14 @@ use crate::app::AppState;
 use crate::auth::AuthCheck;
 use crate::controllers::krate::load_crate;
 use crate::controllers::trustpub::emails::{ConfigCreatedEmail, ConfigType};
+use crate::controllers::trustpub::enqueue_config_webhook_event;
 use crate::controllers::trustpub::github_configs::json;
 use crate::util::errors::{AppResult, bad_request, custom, forbidden, server_error};
 use anyhow::Context;
 use axum::Json;
-use crates_io_database::models::OwnerKind;
 use crates_io_database::models::token::EndpointScope;
 use crates_io_database::models::trustpub::{GitHubConfig, NewGitHubConfig};
+use crates_io_database::models::{OwnerKind, WebhookEvent};
 use crates_io_database::schema::{crate_owners, emails, users};
 use crates_io_github::{GitHubAuth, GitHubError};
 use crates_io_trustpub::github::validation::{
@@ -53,6 +54,7 @@ pub async fn create_trustpub_github_config(
     let auth = AuthCheck::default()
         .with_endpoint_scope(EndpointScope::TrustedPublishing)
         .for_crate(&json_config.krate)
+        .require_second_factor()
         .check(&parts, &mut conn)
         .await?;
     let auth_user = auth.user();
@@ -126,6 +128,11 @@ pub async fn create_trustpub_github_config(
 
     let saved_config = new_config.insert(&conn).await?;
 
+    let event = WebhookEvent::TrustpubConfigCreate;
+    let provider = "github";
+    enqueue_config_webhook_event(&conn, event, &krate, auth_user, provider, saved_config.id)
+        .await?;
+
     // Send notification emails to crate owners
 
     let recipients = user_owners
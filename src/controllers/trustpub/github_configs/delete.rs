@@ -1,12 +1,13 @@
 use crate::app::AppState;
 use crate::auth::AuthCheck;
 use crate::controllers::trustpub::emails::{ConfigDeletedEmail, ConfigType};
+use crate::controllers::trustpub::enqueue_config_webhook_event;
 use crate::util::errors::{AppResult, bad_request, not_found};
 use anyhow::Context;
 use axum::extract::Path;
 use crates_io_database::models::token::EndpointScope;
 use crates_io_database::models::trustpub::GitHubConfig;
-use crates_io_database::models::{Crate, OwnerKind};
+use crates_io_database::models::{Crate, OwnerKind, WebhookEvent};
 use crates_io_database::schema::{crate_owners, crates, emails, trustpub_configs_github, users};
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
@@ -49,6 +50,7 @@ pub async fn delete_trustpub_github_config(
     let auth = AuthCheck::default()
         .with_endpoint_scope(EndpointScope::TrustedPublishing)
         .for_crate(&krate.name)
+        .require_second_factor()
         .check(&parts, &mut conn)
         .await?;
     let auth_user = auth.user();
@@ -74,6 +76,10 @@ pub async fn delete_trustpub_github_config(
         .execute(&mut conn)
         .await?;
 
+    let event = WebhookEvent::TrustpubConfigDelete;
+    let provider = "github";
+    enqueue_config_webhook_event(&conn, event, &krate, auth_user, provider, config.id).await?;
+
     // Send notification emails to crate owners
 
     let recipients = user_owners
@@ -2,13 +2,14 @@ use crate::app::AppState;
 use crate::auth::AuthCheck;
 use crate::controllers::krate::load_crate;
 use crate::controllers::trustpub::emails::{ConfigCreatedEmail, ConfigType};
+use crate::controllers::trustpub::enqueue_config_webhook_event;
 use crate::controllers::trustpub::gitlab_configs::json;
 use crate::util::errors::{AppResult, bad_request, custom, forbidden};
 use anyhow::Context;
 use axum::Json;
-use crates_io_database::models::OwnerKind;
 use crates_io_database::models::token::EndpointScope;
 use crates_io_database::models::trustpub::{GitLabConfig, NewGitLabConfig};
+use crates_io_database::models::{OwnerKind, WebhookEvent};
 use crates_io_database::schema::{crate_owners, emails, users};
 use crates_io_trustpub::gitlab::validation::{
     validate_environment, validate_namespace, validate_project, validate_workflow_filepath,
@@ -51,6 +52,7 @@ pub async fn create_trustpub_gitlab_config(
     let auth = AuthCheck::default()
         .with_endpoint_scope(EndpointScope::TrustedPublishing)
         .for_crate(&json_config.krate)
+        .require_second_factor()
         .check(&parts, &mut conn)
         .await?;
     let auth_user = auth.user();
@@ -99,6 +101,11 @@ pub async fn create_trustpub_gitlab_config(
 
     let saved_config = new_config.insert(&conn).await?;
 
+    let event = WebhookEvent::TrustpubConfigCreate;
+    let provider = "gitlab";
+    enqueue_config_webhook_event(&conn, event, &krate, auth_user, provider, saved_config.id)
+        .await?;
+
     // Send notification emails to crate owners
 
     let recipients = user_owners
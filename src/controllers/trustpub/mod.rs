@@ -1,4 +1,33 @@
+use crate::worker::jobs::DispatchWebhookEvent;
+use crates_io_database::models::{Crate, User, WebhookEvent};
+use crates_io_worker::{BackgroundJob, EnqueueError};
+use diesel_async::AsyncPgConnection;
+
 pub mod emails;
 pub mod github_configs;
 pub mod gitlab_configs;
+pub mod oidc_configs;
 pub mod tokens;
+
+/// Notifies the webhooks of a crate that a Trusted Publishing configuration
+/// was created or deleted.
+async fn enqueue_config_webhook_event(
+    conn: &AsyncPgConnection,
+    event: WebhookEvent,
+    krate: &Crate,
+    auth_user: &User,
+    provider: &str,
+    config_id: i32,
+) -> Result<(), EnqueueError> {
+    DispatchWebhookEvent::builder()
+        .crate_id(krate.id)
+        .event(event)
+        .actor(&auth_user.gh_login)
+        .trustpub_provider(provider)
+        .trustpub_config_id(config_id)
+        .build()
+        .enqueue(conn)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::controllers::krate::load_crate;
+use crate::controllers::trustpub::emails::{ConfigCreatedEmail, ConfigType};
+use crate::controllers::trustpub::enqueue_config_webhook_event;
+use crate::controllers::trustpub::oidc_configs::json;
+use crate::util::errors::{AppResult, BoxedAppError, bad_request, custom, forbidden};
+use anyhow::Context;
+use axum::Json;
+use crates_io_database::models::token::EndpointScope;
+use crates_io_database::models::trustpub::{NewOidcConfig, OidcConfig};
+use crates_io_database::models::{OwnerKind, WebhookEvent};
+use crates_io_database::schema::{crate_owners, emails, users};
+use crates_io_trustpub::provider::{Provider, provider_by_id, validate_claims};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use http::request::Parts;
+use tracing::warn;
+
+const MAX_CONFIGS_PER_CRATE: usize = 5;
+
+/// Create a new Trusted Publishing configuration for a generic OIDC provider.
+#[utoipa::path(
+    post,
+    path = "/api/v1/trusted_publishing/oidc_configs",
+    security(("cookie" = []), ("api_token" = [])),
+    request_body = inline(json::CreateRequest),
+    tag = "trusted_publishing",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(json::CreateResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn create_trustpub_oidc_config(
+    state: AppState,
+    parts: Parts,
+    json: json::CreateRequest,
+) -> AppResult<Json<json::CreateResponse>> {
+    let json_config = json.oidc_config;
+
+    let provider = enabled_provider(&state, &json_config.provider)?;
+    provider.validate_repository(&json_config.repository)?;
+    validate_claims(provider, &json_config.claims)?;
+
+    let mut conn = state.db_write().await?;
+
+    let auth = AuthCheck::default()
+        .with_endpoint_scope(EndpointScope::TrustedPublishing)
+        .for_crate(&json_config.krate)
+        .require_second_factor()
+        .check(&parts, &mut conn)
+        .await?;
+    let auth_user = auth.user();
+
+    let krate = load_crate(&conn, &json_config.krate).await?;
+
+    // Check if the crate has reached the maximum number of configs
+    let config_count = OidcConfig::count_for_crate(&conn, krate.id).await?;
+    if config_count >= MAX_CONFIGS_PER_CRATE as i64 {
+        let message = format!(
+            "This crate already has the maximum number of generic OIDC Trusted Publishing configurations ({})",
+            MAX_CONFIGS_PER_CRATE
+        );
+        return Err(custom(http::StatusCode::CONFLICT, message));
+    }
+
+    let user_owners = crate_owners::table
+        .filter(crate_owners::crate_id.eq(krate.id))
+        .filter(crate_owners::deleted.eq(false))
+        .filter(crate_owners::owner_kind.eq(OwnerKind::User))
+        .inner_join(users::table)
+        .inner_join(emails::table.on(users::id.eq(emails::user_id)))
+        .select((users::id, users::gh_login, emails::email, emails::verified))
+        .load::<(i32, String, String, bool)>(&mut conn)
+        .await?;
+
+    let (_, _, _, email_verified) = user_owners
+        .iter()
+        .find(|(id, _, _, _)| *id == auth_user.id)
+        .ok_or_else(|| bad_request("You are not an owner of this crate"))?;
+
+    if !email_verified {
+        let message = "You must verify your email address to create a Trusted Publishing config";
+        return Err(forbidden(message));
+    }
+
+    // Save the new OIDC config to the database
+
+    let claims = serde_json::to_value(&json_config.claims)?;
+
+    let new_config = NewOidcConfig {
+        crate_id: krate.id,
+        provider: provider.id(),
+        repository: &json_config.repository,
+        claims,
+    };
+
+    let saved_config = new_config.insert(&conn).await?;
+
+    let event = WebhookEvent::TrustpubConfigCreate;
+    let provider = &saved_config.provider;
+    enqueue_config_webhook_event(&conn, event, &krate, auth_user, provider, saved_config.id)
+        .await?;
+
+    // Send notification emails to crate owners
+
+    let recipients = user_owners
+        .into_iter()
+        .filter(|(_, _, _, verified)| *verified)
+        .map(|(_, login, email, _)| (login, email))
+        .collect::<Vec<_>>();
+
+    for (recipient, email_address) in &recipients {
+        let saved_config = ConfigType::oidc(&saved_config);
+
+        let context = ConfigCreatedEmail {
+            recipient,
+            auth_user,
+            krate: &krate,
+            saved_config,
+        };
+
+        if let Err(err) = send_notification_email(&state, email_address, context).await {
+            warn!("Failed to send trusted publishing notification to {email_address}: {err}");
+        }
+    }
+
+    let oidc_config = json::OidcConfig {
+        id: saved_config.id,
+        krate: krate.name,
+        claims: saved_config.claim_restrictions()?,
+        provider: saved_config.provider,
+        repository: saved_config.repository,
+        repository_owner_id: saved_config.repository_owner_id,
+        created_at: saved_config.created_at,
+    };
+
+    Ok(Json(json::CreateResponse { oidc_config }))
+}
+
+/// Looks up a generic OIDC provider by its ID and checks that its issuer
+/// has been enabled via the `TRUSTPUB_PROVIDERS` environment variable.
+fn enabled_provider(state: &AppState, id: &str) -> Result<&'static dyn Provider, BoxedAppError> {
+    provider_by_id(id)
+        .filter(|provider| state.oidc_key_stores.contains_key(provider.issuer_url()))
+        .ok_or_else(|| bad_request(format!("Unsupported Trusted Publishing provider: {id}")))
+}
+
+async fn send_notification_email(
+    state: &AppState,
+    email_address: &str,
+    context: ConfigCreatedEmail<'_>,
+) -> anyhow::Result<()> {
+    let email = context.render();
+    let email = email.context("Failed to render email template")?;
+
+    state
+        .emails
+        .send(email_address, email)
+        .await
+        .context("Failed to send email")
+}
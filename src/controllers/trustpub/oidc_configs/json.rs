@@ -0,0 +1,38 @@
+use axum::Json;
+use axum::extract::FromRequest;
+use serde::{Deserialize, Serialize};
+
+pub use crate::views::trustpub::{NewOidcConfig, OidcConfig};
+
+#[derive(Debug, Deserialize, FromRequest, utoipa::ToSchema)]
+#[from_request(via(Json))]
+pub struct CreateRequest {
+    #[schema(inline)]
+    pub oidc_config: NewOidcConfig,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CreateResponse {
+    pub oidc_config: OidcConfig,
+}
+
+/// Response returned when listing generic OIDC trusted publishing configurations.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct OidcConfigListResponse {
+    pub oidc_configs: Vec<OidcConfig>,
+
+    #[schema(inline)]
+    pub meta: OidcConfigListMeta,
+}
+
+/// Pagination metadata for a generic OIDC configuration list response.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct OidcConfigListMeta {
+    /// The total number of generic OIDC configs belonging to the crate.
+    #[schema(example = 42)]
+    pub total: i64,
+
+    /// Query string to the next page of results, if any.
+    #[schema(example = "?seek=abc123")]
+    pub next_page: Option<String>,
+}
@@ -1,18 +1,22 @@
 use super::json;
 use crate::app::AppState;
+use crate::rate_limiter::{LimitedAction, RateLimiter};
 use crate::util::errors::{AppResult, BoxedAppError, bad_request, server_error};
 use axum::Json;
 use chrono::{DateTime, Utc};
 use crates_io_database::fns::lower;
 use crates_io_database::models::trustpub::{
-    GitHubConfig, GitLabConfig, NewToken, NewUsedJti, TrustpubData,
+    GitHubConfig, GitLabConfig, NewToken, NewUsedJti, OidcConfig, TrustpubData,
+};
+use crates_io_database::schema::{
+    trustpub_configs_github, trustpub_configs_gitlab, trustpub_configs_oidc,
 };
-use crates_io_database::schema::{trustpub_configs_github, trustpub_configs_gitlab};
 use crates_io_trustpub::JWT_LEEWAY;
 use crates_io_trustpub::access_token::AccessToken;
 use crates_io_trustpub::github::{GITHUB_ISSUER_URL, GitHubClaims};
 use crates_io_trustpub::gitlab::{GITLAB_ISSUER_URL, GitLabClaims};
 use crates_io_trustpub::keystore::DecodingKey;
+use crates_io_trustpub::provider::{OidcClaims, Provider, provider_by_issuer};
 use crates_io_trustpub::unverified::UnverifiedClaims;
 use diesel::prelude::*;
 use diesel::result::DatabaseErrorKind::UniqueViolation;
@@ -66,7 +70,10 @@ pub async fn exchange_trustpub_token(
     match unverified_issuer.as_str() {
         GITHUB_ISSUER_URL => handle_github_token(&state, &unverified_jwt, &key).await,
         GITLAB_ISSUER_URL => handle_gitlab_token(&state, &unverified_jwt, &key).await,
-        _ => Err(unsupported_issuer(&unverified_issuer)),
+        issuer => match provider_by_issuer(issuer) {
+            Some(provider) => handle_oidc_token(&state, provider, &unverified_jwt, &key).await,
+            None => Err(unsupported_issuer(issuer)),
+        },
     }
 }
 
@@ -91,6 +98,26 @@ async fn insert_jti(conn: &mut AsyncPgConnection, jti: &str, exp: DateTime<Utc>)
     }
 }
 
+/// Takes a token from the rate limiting bucket of each of the crates that
+/// the temporary access token would be issued for.
+async fn check_rate_limits(
+    conn: &mut AsyncPgConnection,
+    rate_limiter: &RateLimiter,
+    crate_ids: &[i32],
+) -> AppResult<()> {
+    let mut crate_ids = crate_ids.to_vec();
+    crate_ids.sort_unstable();
+    crate_ids.dedup();
+
+    for crate_id in crate_ids {
+        rate_limiter
+            .check_crate_rate_limit(crate_id, LimitedAction::TrustpubExchange, conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
 async fn handle_github_token(
     state: &AppState,
     unverified_jwt: &str,
@@ -104,12 +131,15 @@ async fn handle_github_token(
 
     let mut conn = state.db_write().await?;
 
-    conn.transaction(async |conn| handle_github_token_inner(conn, signed_claims).await)
-        .await
+    conn.transaction(async |conn| {
+        handle_github_token_inner(conn, &state.rate_limiter, signed_claims).await
+    })
+    .await
 }
 
 async fn handle_github_token_inner(
     conn: &mut AsyncPgConnection,
+    rate_limiter: &RateLimiter,
     signed_claims: GitHubClaims,
 ) -> AppResult<Json<json::ExchangeResponse>> {
     insert_jti(conn, &signed_claims.jti, signed_claims.exp).await?;
@@ -221,12 +251,15 @@ async fn handle_github_token_inner(
         .map(|config| config.crate_id)
         .collect::<Vec<_>>();
 
+    check_rate_limits(conn, rate_limiter, &crate_ids).await?;
+
     let new_token = AccessToken::generate();
 
     let trustpub_data = TrustpubData::GitHub {
         repository: signed_claims.repository,
         run_id: signed_claims.run_id,
         sha: signed_claims.sha,
+        workflow_ref: Some(signed_claims.workflow_ref),
     };
 
     let new_token_model = NewToken {
@@ -255,12 +288,15 @@ async fn handle_gitlab_token(
 
     let mut conn = state.db_write().await?;
 
-    conn.transaction(async |conn| handle_gitlab_token_inner(conn, signed_claims).await)
-        .await
+    conn.transaction(async |conn| {
+        handle_gitlab_token_inner(conn, &state.rate_limiter, signed_claims).await
+    })
+    .await
 }
 
 async fn handle_gitlab_token_inner(
     conn: &mut AsyncPgConnection,
+    rate_limiter: &RateLimiter,
     signed_claims: GitLabClaims,
 ) -> AppResult<Json<json::ExchangeResponse>> {
     insert_jti(conn, &signed_claims.jti, signed_claims.exp).await?;
@@ -384,12 +420,165 @@ async fn handle_gitlab_token_inner(
         .map(|config| config.crate_id)
         .collect::<Vec<_>>();
 
+    check_rate_limits(conn, rate_limiter, &crate_ids).await?;
+
     let new_token = AccessToken::generate();
 
     let trustpub_data = TrustpubData::GitLab {
         project_path: signed_claims.project_path,
         job_id: signed_claims.job_id,
         sha: signed_claims.sha,
+        ci_config_ref_uri: Some(signed_claims.ci_config_ref_uri),
+    };
+
+    let new_token_model = NewToken {
+        expires_at: chrono::Utc::now() + chrono::Duration::minutes(30),
+        hashed_token: &new_token.sha256(),
+        crate_ids: &crate_ids,
+        trustpub_data: Some(&trustpub_data),
+    };
+
+    new_token_model.insert(conn).await?;
+
+    let token = new_token.finalize().expose_secret().into();
+    Ok(Json(json::ExchangeResponse { token }))
+}
+
+async fn handle_oidc_token(
+    state: &AppState,
+    provider: &dyn Provider,
+    unverified_jwt: &str,
+    key: &DecodingKey,
+) -> AppResult<Json<json::ExchangeResponse>> {
+    let audience = &state.config.trustpub_audience;
+    let signed_claims = provider
+        .decode(unverified_jwt, audience, key)
+        .map_err(|err| {
+            warn!("Failed to decode JWT: {err}");
+            bad_request("Failed to decode JWT")
+        })?;
+
+    let mut conn = state.db_write().await?;
+
+    conn.transaction(async |conn| {
+        handle_oidc_token_inner(conn, &state.rate_limiter, provider, signed_claims).await
+    })
+    .await
+}
+
+async fn handle_oidc_token_inner(
+    conn: &mut AsyncPgConnection,
+    rate_limiter: &RateLimiter,
+    provider: &dyn Provider,
+    signed_claims: OidcClaims,
+) -> AppResult<Json<json::ExchangeResponse>> {
+    insert_jti(conn, &signed_claims.jti, signed_claims.exp).await?;
+
+    let repo = &signed_claims.repository;
+
+    let mut repo_configs = OidcConfig::query()
+        .filter(trustpub_configs_oidc::provider.eq(provider.id()))
+        .filter(lower(trustpub_configs_oidc::repository).eq(lower(repo)))
+        .load(conn)
+        .await?;
+
+    if repo_configs.is_empty() {
+        let message = format!("No Trusted Publishing config found for repository `{repo}`.");
+        return Err(bad_request(message));
+    }
+
+    // Handle resurrection protection by lazily storing the repository owner ID
+    // and verifying it on subsequent exchanges, if the provider supplies one.
+    if let Some(owner_id) = &signed_claims.repository_owner_id {
+        let configs_to_update: Vec<i32> = repo_configs
+            .iter()
+            .filter(|config| config.repository_owner_id.is_none())
+            .map(|config| config.id)
+            .collect();
+
+        if !configs_to_update.is_empty() {
+            diesel::update(trustpub_configs_oidc::table)
+                .filter(trustpub_configs_oidc::id.eq_any(&configs_to_update))
+                .filter(trustpub_configs_oidc::repository_owner_id.is_null())
+                .set(trustpub_configs_oidc::repository_owner_id.eq(owner_id))
+                .execute(conn)
+                .await?;
+        }
+    }
+
+    // Remove configs that have a stored owner ID which doesn't match. Tokens
+    // without an owner ID can't be matched against configs that have one.
+    let mismatched_owner_ids: Vec<String> = repo_configs
+        .extract_if(.., |config| {
+            config
+                .repository_owner_id
+                .as_ref()
+                .is_some_and(|stored| signed_claims.repository_owner_id.as_ref() != Some(stored))
+        })
+        .filter_map(|config| config.repository_owner_id)
+        .collect();
+
+    if repo_configs.is_empty() {
+        let signed_owner_id = signed_claims.repository_owner_id.as_deref();
+        let message = format!(
+            "The Trusted Publishing config for repository `{repo}` does not match the repository owner ID ({}) in the JWT. Expected owner IDs: {}. Please recreate the Trusted Publishing config to update the repository owner ID.",
+            signed_owner_id.unwrap_or("none"),
+            mismatched_owner_ids.join(", ")
+        );
+        return Err(bad_request(message));
+    }
+
+    // Filter by the additional claim restrictions of the configs. Configs
+    // with restrictions that can't be parsed never match.
+    let mismatched_claims: Vec<String> = repo_configs
+        .extract_if(.., |config| match config.claim_restrictions() {
+            Ok(expected) => !signed_claims.mismatched_claims(&expected).is_empty(),
+            Err(err) => {
+                warn!(
+                    "Failed to parse claim restrictions of OIDC config {}: {err}",
+                    config.id
+                );
+                true
+            }
+        })
+        .filter_map(|config| config.claim_restrictions().ok())
+        .flat_map(|expected| {
+            signed_claims
+                .mismatched_claims(&expected)
+                .into_iter()
+                .map(|(name, value)| format!("`{name}` = `{value}`"))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    if repo_configs.is_empty() {
+        let message = if mismatched_claims.is_empty() {
+            format!(
+                "The Trusted Publishing config for repository `{repo}` has invalid claim restrictions. Please recreate the Trusted Publishing config."
+            )
+        } else {
+            format!(
+                "The Trusted Publishing config for repository `{repo}` does not match the claims in the JWT. Expected claims: {}",
+                mismatched_claims.join(", ")
+            )
+        };
+        return Err(bad_request(message));
+    }
+
+    let crate_ids = repo_configs
+        .iter()
+        .map(|config| config.crate_id)
+        .collect::<Vec<_>>();
+
+    check_rate_limits(conn, rate_limiter, &crate_ids).await?;
+
+    let new_token = AccessToken::generate();
+
+    let trustpub_data = TrustpubData::Oidc {
+        issuer: provider.issuer_url().into(),
+        repository: signed_claims.repository,
+        run_id: signed_claims.run_id,
+        sha: signed_claims.sha,
     };
 
     let new_token_model = NewToken {
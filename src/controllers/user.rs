@@ -1,8 +1,12 @@
 pub mod email_notifications;
 pub mod email_verification;
+pub mod identities;
 pub mod me;
+pub mod notification_preferences;
 pub mod other;
+pub mod two_factor;
 pub mod update;
+pub mod webhooks;
 
 pub use email_verification::resend_email_verification;
 pub use update::update_user;
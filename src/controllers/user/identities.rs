@@ -0,0 +1,312 @@
+//! Endpoints for managing the accounts of identity providers that are linked
+//! to a crates.io account.
+//!
+//! Linked accounts can be used to sign in to crates.io, in addition to the
+//! GitHub account that the crates.io account was created with. A linked
+//! GitLab account is also required for using GitLab groups as crate owners.
+//!
+//! Since a linked account is another way to sign in, linking and unlinking
+//! accounts requires a recent verification with a second factor, if the user
+//! has enabled two-factor authentication.
+
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::controllers::helpers::OkResponse;
+use crate::controllers::helpers::identity_provider::IdentityProvider;
+use crate::controllers::session::{AuthorizeBody, BeginResponse};
+use crate::models::LinkedIdentity;
+use crate::schema::linked_identities;
+use crate::util::errors::{AppResult, BoxedAppError, bad_request};
+use axum::Json;
+use axum::extract::Path;
+use chrono::{DateTime, Utc};
+use crates_io_session::SessionExtension;
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use http::request::Parts;
+use serde::Serialize;
+use tracing::info;
+
+/// The provider ID of the GitHub account that every crates.io account is
+/// created with.
+const GITHUB_PROVIDER: &str = "github";
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct EncodableLinkedIdentity {
+    /// The ID of the identity provider.
+    #[schema(example = "gitlab")]
+    provider: String,
+
+    /// The username of the account at the identity provider, if available.
+    #[schema(example = "ghost")]
+    login: Option<String>,
+
+    /// The email address of the account at the identity provider, if
+    /// available.
+    #[schema(example = "ghost@example.com")]
+    email: Option<String>,
+
+    /// The avatar URL of the account at the identity provider, if available.
+    #[schema(example = "https://gitlab.com/uploads/-/system/user/avatar/1/avatar.png")]
+    avatar: Option<String>,
+
+    /// The time when the account was linked, or `null` for the GitHub
+    /// account that the crates.io account was created with.
+    #[schema(example = "2026-10-18T12:00:00Z")]
+    linked_at: Option<DateTime<Utc>>,
+}
+
+impl From<LinkedIdentity> for EncodableLinkedIdentity {
+    fn from(identity: LinkedIdentity) -> Self {
+        Self {
+            provider: identity.provider,
+            login: identity.login,
+            email: identity.email,
+            avatar: identity.avatar,
+            linked_at: Some(identity.created_at),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct EncodableIdentityProvider {
+    /// The ID of the identity provider.
+    #[schema(example = "gitlab")]
+    id: String,
+
+    /// The name of the identity provider.
+    #[schema(example = "GitLab")]
+    name: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ListIdentitiesResponse {
+    /// The accounts that are linked to the crates.io account.
+    identities: Vec<EncodableLinkedIdentity>,
+
+    /// The identity providers whose accounts can be linked.
+    providers: Vec<EncodableIdentityProvider>,
+}
+
+/// List the linked accounts of the authenticated user.
+#[utoipa::path(
+    get,
+    path = "/api/private/me/identities",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(ListIdentitiesResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn list_identities(app: AppState, req: Parts) -> AppResult<Json<ListIdentitiesResponse>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+    let user = auth.user();
+
+    let github = EncodableLinkedIdentity {
+        provider: GITHUB_PROVIDER.to_string(),
+        login: Some(user.gh_login.clone()),
+        email: None,
+        avatar: user.gh_avatar.clone(),
+        linked_at: None,
+    };
+
+    let linked = LinkedIdentity::for_user(&conn, user.id).await?;
+    let identities = std::iter::once(github)
+        .chain(linked.into_iter().map(EncodableLinkedIdentity::from))
+        .collect();
+
+    let providers = IdentityProvider::all(&app)
+        .into_iter()
+        .map(|provider| EncodableIdentityProvider {
+            id: provider.id().to_string(),
+            name: provider.name().to_string(),
+        })
+        .collect();
+
+    Ok(Json(ListIdentitiesResponse {
+        identities,
+        providers,
+    }))
+}
+
+/// Begin linking an account of an identity provider.
+///
+/// This route will return an authorization URL for the OAuth flow of the
+/// identity provider including the crates.io `client_id` and a randomly
+/// generated `state` secret.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/identities/{provider}/begin",
+    params(
+        ("provider" = String, Path, description = "ID of the identity provider, e.g. `gitlab`"),
+    ),
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(BeginResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn begin_identity_link(
+    app: AppState,
+    Path(provider): Path<String>,
+    session: SessionExtension,
+    req: Parts,
+) -> AppResult<Json<BeginResponse>> {
+    let provider = IdentityProvider::find(&app, &provider)?;
+
+    let mut conn = app.db_read().await?;
+    AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+
+    Ok(Json(provider.begin(&session)))
+}
+
+/// Complete linking an account of an identity provider.
+///
+/// This route is called after the user accepted or rejected the data access
+/// permissions at the identity provider. It will check the `state` parameter,
+/// exchange the temporary `code` for an access token and link the account of
+/// the token to the authenticated user. A previously linked account of the
+/// same identity provider is replaced.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/identities/{provider}/authorize",
+    params(
+        ("provider" = String, Path, description = "ID of the identity provider, e.g. `gitlab`"),
+    ),
+    security(("cookie" = [])),
+    tag = "users",
+    request_body = inline(AuthorizeBody),
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(OkResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn authorize_identity_link(
+    app: AppState,
+    Path(provider): Path<String>,
+    session: SessionExtension,
+    req: Parts,
+    Json(body): Json<AuthorizeBody>,
+) -> AppResult<OkResponse> {
+    let provider = IdentityProvider::find(&app, &provider)?;
+
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+    let user = auth.user();
+
+    let (code, state) = body.into_parts();
+    let identity = provider
+        .authorize(&app, &session, &req, code, &state)
+        .await?;
+
+    conn.transaction(async |conn| {
+        let linked_user_id = linked_identities::table
+            .find((provider.id(), &identity.account_id))
+            .select(linked_identities::user_id)
+            .first::<i32>(conn)
+            .await
+            .optional()?;
+
+        if linked_user_id.is_some_and(|linked_user_id| linked_user_id != user.id) {
+            return Err(bad_request(format!(
+                "This {} account is already linked to another crates.io account",
+                provider.name()
+            )));
+        }
+
+        diesel::delete(linked_identities::table)
+            .filter(linked_identities::user_id.eq(user.id))
+            .filter(linked_identities::provider.eq(provider.id()))
+            .execute(conn)
+            .await?;
+
+        identity
+            .link_to(provider.id(), user.id)
+            .insert(conn)
+            .await?;
+
+        Ok::<_, BoxedAppError>(())
+    })
+    .await?;
+
+    info!(
+        "User {} linked {} account {}",
+        user.gh_login,
+        provider.name(),
+        identity.account_id
+    );
+
+    Ok(OkResponse::new())
+}
+
+/// Unlink the account of an identity provider from the authenticated user.
+///
+/// The GitHub account that the crates.io account was created with can not be
+/// unlinked. If a GitLab account is unlinked, the GitLab groups that own
+/// crates are kept as owners, but the user can no longer use their
+/// membership in these groups.
+#[utoipa::path(
+    delete,
+    path = "/api/private/me/identities/{provider}",
+    params(
+        ("provider" = String, Path, description = "ID of the identity provider, e.g. `gitlab`"),
+    ),
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(OkResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn unlink_identity(
+    app: AppState,
+    Path(provider): Path<String>,
+    req: Parts,
+) -> AppResult<OkResponse> {
+    let mut conn = app.db_write().await?;
+    let user_id = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?
+        .user_id();
+
+    if provider == GITHUB_PROVIDER {
+        return Err(bad_request("The GitHub account can not be unlinked"));
+    }
+
+    // Providers that have been removed from the configuration can still be
+    // unlinked, so the provider is not looked up here.
+    let deleted = diesel::delete(linked_identities::table)
+        .filter(linked_identities::user_id.eq(user_id))
+        .filter(linked_identities::provider.eq(&provider))
+        .execute(&mut conn)
+        .await?;
+
+    if deleted == 0 {
+        return Err(bad_request(format!(
+            "No `{provider}` account is linked to this account"
+        )));
+    }
+
+    Ok(OkResponse::new())
+}
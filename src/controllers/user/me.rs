@@ -3,7 +3,9 @@ use crate::auth::AuthCheck;
 use crate::controllers::helpers::Paginate;
 use crate::controllers::helpers::pagination::{Paginated, PaginationOptions};
 use crate::models::krate::CrateName;
-use crate::models::{CrateOwner, Follow, OwnerKind, PublicUser, User, Version, VersionOwnerAction};
+use crate::models::{
+    CrateOwner, CrateReader, Follow, OwnerKind, PublicUser, User, Version, VersionOwnerAction,
+};
 use crate::schema::{crate_owners, crates, emails, follows, oauth_github, users, versions};
 use crate::util::errors::AppResult;
 use crate::util::no_store;
@@ -129,6 +131,8 @@ pub async fn get_authenticated_user_updates(
         .inner_join(crates::table)
         .left_outer_join(users::table.left_join(oauth_github::table))
         .filter(crates::id.eq_any(followed_crates))
+        .filter(crates::id.ne_all(CrateReader::hidden_crate_ids(user.id)))
+        .filter(versions::staged_at.is_null())
         .order(versions::created_at.desc())
         .select(<(Version, CrateName, Option<PublicUser>)>::as_select())
         .pages_pagination(PaginationOptions::builder().gather(&req)?);
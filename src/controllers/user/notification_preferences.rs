@@ -0,0 +1,319 @@
+//! Endpoints for managing the email notification preferences of users.
+//!
+//! Users can opt out of the categories of emails in
+//! [`NotificationCategory`], either completely or for single crates, and
+//! can receive publish notifications as a daily or weekly digest. Emails
+//! of these categories contain a one-click unsubscribe link, which is
+//! handled by [`unsubscribe`]. Security-relevant emails are always sent.
+
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::controllers::helpers::OkResponse;
+use crate::controllers::user::update::set_publish_notifications;
+use crate::controllers::user::webhooks::load_owned_crate;
+use crate::models::{
+    NewNotificationPreference, NotificationCategory, NotificationSettings, PublishDelivery, User,
+};
+use crate::schema::{crates, notification_preferences};
+use crate::util::errors::{AppResult, bad_request, not_found};
+use axum::Json;
+use axum::extract::Path;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use http::request::Parts;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CategoryPreference {
+    /// The category of emails.
+    category: NotificationCategory,
+
+    /// Whether emails of the category are sent.
+    #[schema(example = true)]
+    enabled: bool,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CratePreference {
+    /// The name of the crate that the preference is restricted to.
+    #[serde(rename = "crate")]
+    #[schema(example = "serde")]
+    krate: String,
+
+    /// The category of emails.
+    category: NotificationCategory,
+
+    /// Whether emails of the category about the crate are sent. Takes
+    /// precedence over the preference for the whole category.
+    #[schema(example = false)]
+    enabled: bool,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct NotificationPreferencesResponse {
+    /// How publish notifications are delivered.
+    publish_delivery: PublishDelivery,
+
+    /// The preferences for each category of emails.
+    categories: Vec<CategoryPreference>,
+
+    /// The preferences that are restricted to single crates.
+    crates: Vec<CratePreference>,
+}
+
+impl NotificationPreferencesResponse {
+    async fn load(conn: &mut AsyncPgConnection, user: &User) -> QueryResult<Self> {
+        let publish_delivery = NotificationSettings::publish_delivery(conn, user.id).await?;
+
+        let preferences = notification_preferences::table
+            .left_join(crates::table)
+            .filter(notification_preferences::user_id.eq(user.id))
+            .select((
+                notification_preferences::category,
+                crates::name.nullable(),
+                notification_preferences::enabled,
+            ))
+            .order((crates::name.nullable(), notification_preferences::category))
+            .load::<(String, Option<String>, bool)>(conn)
+            .await?
+            .into_iter()
+            .filter_map(|(category, krate, enabled)| {
+                let category = category.parse::<NotificationCategory>().ok()?;
+                Some((category, krate, enabled))
+            })
+            .collect::<Vec<_>>();
+
+        // Publish notifications of all crates are configured by the
+        // `publish_notifications` flag of the user.
+        let categories = NotificationCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let enabled = match category {
+                    NotificationCategory::Publish => user.publish_notifications,
+                    _ => preferences
+                        .iter()
+                        .find(|(c, krate, _)| *c == category && krate.is_none())
+                        .is_none_or(|(_, _, enabled)| *enabled),
+                };
+
+                CategoryPreference { category, enabled }
+            })
+            .collect();
+
+        let crates = preferences
+            .into_iter()
+            .filter_map(|(category, krate, enabled)| {
+                let krate = krate?;
+                Some(CratePreference {
+                    krate,
+                    category,
+                    enabled,
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            publish_delivery,
+            categories,
+            crates,
+        })
+    }
+}
+
+/// List the email notification preferences of the authenticated user.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/notification_preferences",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(NotificationPreferencesResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn get_notification_preferences(
+    app: AppState,
+    req: Parts,
+) -> AppResult<Json<NotificationPreferencesResponse>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let auth = AuthCheck::only_cookie().check(&req, &mut conn).await?;
+    let user = auth.user();
+
+    let response = NotificationPreferencesResponse::load(&mut conn, user).await?;
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct CratePreferenceUpdate {
+    /// The name of a crate that the user owns.
+    #[serde(rename = "crate")]
+    #[schema(example = "serde")]
+    krate: String,
+
+    /// The category of emails.
+    category: NotificationCategory,
+
+    /// Whether emails of the category about the crate are sent, or `null`
+    /// to remove the preference, so that the preference for the whole
+    /// category applies again.
+    #[schema(example = false)]
+    enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct NotificationPreferencesUpdate {
+    /// How publish notifications should be delivered.
+    publish_delivery: Option<PublishDelivery>,
+
+    /// The preferences for categories of emails that should be changed.
+    #[serde(default)]
+    categories: Vec<CategoryPreference>,
+
+    /// The preferences for single crates that should be changed.
+    #[serde(default)]
+    crates: Vec<CratePreferenceUpdate>,
+}
+
+/// Update the email notification preferences of the authenticated user.
+///
+/// Only the given preferences are changed. The response contains all
+/// preferences of the user.
+#[utoipa::path(
+    put,
+    path = "/api/v1/me/notification_preferences",
+    security(("cookie" = [])),
+    tag = "users",
+    request_body = inline(NotificationPreferencesUpdate),
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(NotificationPreferencesResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn update_notification_preferences(
+    app: AppState,
+    req: Parts,
+    Json(body): Json<NotificationPreferencesUpdate>,
+) -> AppResult<Json<NotificationPreferencesResponse>> {
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie().check(&req, &mut conn).await?;
+    let user = auth.user();
+
+    let mut crate_updates = Vec::with_capacity(body.crates.len());
+    for update in &body.crates {
+        if update.category == NotificationCategory::TokenExpiry {
+            let message = format!("`{}` can not be configured per crate", update.category);
+            return Err(bad_request(message));
+        }
+
+        let krate = load_owned_crate(&mut conn, &update.krate, user.id).await?;
+        crate_updates.push((krate.id, update));
+    }
+
+    if let Some(publish_delivery) = body.publish_delivery {
+        NotificationSettings::set_publish_delivery(&conn, user.id, publish_delivery).await?;
+    }
+
+    for preference in &body.categories {
+        set_category_enabled(
+            &app,
+            &mut conn,
+            user,
+            preference.category,
+            preference.enabled,
+        )
+        .await?;
+    }
+
+    for (crate_id, update) in crate_updates {
+        let category = update.category.as_str();
+        match update.enabled {
+            Some(enabled) => {
+                NewNotificationPreference::builder()
+                    .user_id(user.id)
+                    .category(category)
+                    .crate_id(crate_id)
+                    .enabled(enabled)
+                    .build()
+                    .upsert(&conn)
+                    .await?;
+            }
+            None => {
+                diesel::delete(notification_preferences::table)
+                    .filter(notification_preferences::user_id.eq(user.id))
+                    .filter(notification_preferences::category.eq(category))
+                    .filter(notification_preferences::crate_id.eq(crate_id))
+                    .execute(&mut conn)
+                    .await?;
+            }
+        }
+    }
+
+    // Reload the user, since `publish_notifications` might have changed
+    let user = User::find(&conn, user.id).await?;
+    let response = NotificationPreferencesResponse::load(&mut conn, &user).await?;
+    Ok(Json(response))
+}
+
+/// Unsubscribe from a category of emails.
+///
+/// The token is part of the unsubscribe links in the emails, so this
+/// endpoint does not require authentication. Mail clients use it for
+/// one-click unsubscribes (RFC 8058).
+#[utoipa::path(
+    post,
+    path = "/api/v1/unsubscribe/{token}/{category}",
+    params(
+        ("token" = String, Path, description = "Unsubscribe token from the email"),
+        ("category" = NotificationCategory, Path, description = "Category of emails to unsubscribe from"),
+    ),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(OkResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn unsubscribe(
+    app: AppState,
+    Path((token, category)): Path<(String, String)>,
+) -> AppResult<OkResponse> {
+    let category = category
+        .parse::<NotificationCategory>()
+        .map_err(bad_request)?;
+
+    let mut conn = app.db_write().await?;
+
+    let user_id = NotificationSettings::find_user_by_unsubscribe_token(&conn, &token)
+        .await?
+        .ok_or_else(not_found)?;
+
+    let user = User::find(&conn, user_id).await?;
+    set_category_enabled(&app, &mut conn, &user, category, false).await?;
+
+    Ok(OkResponse::new())
+}
+
+async fn set_category_enabled(
+    app: &AppState,
+    conn: &mut AsyncPgConnection,
+    user: &User,
+    category: NotificationCategory,
+    enabled: bool,
+) -> QueryResult<()> {
+    if category == NotificationCategory::Publish {
+        return set_publish_notifications(app, conn, user, enabled).await;
+    }
+
+    NewNotificationPreference::builder()
+        .user_id(user.id)
+        .category(category.as_str())
+        .enabled(enabled)
+        .build()
+        .upsert(conn)
+        .await
+}
@@ -1,5 +1,6 @@
 use crate::app::AppState;
-use crate::models::{CrateOwner, OwnerKind, PublicUser};
+use crate::middleware::private_registry::RegistryReader;
+use crate::models::{CrateOwner, CrateReader, OwnerKind, PublicUser};
 use crate::schema::{crate_downloads, crate_owners, crates};
 use crate::util::errors::AppResult;
 use crate::views::EncodablePublicUser;
@@ -9,6 +10,7 @@ use bigdecimal::{BigDecimal, ToPrimitive};
 use crates_io_database::fns::canon_username;
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
+use http::request::Parts;
 use serde::Serialize;
 
 /// Response returned when getting a user by login.
@@ -76,17 +78,25 @@ pub struct StatsResponse {
 pub async fn get_user_stats(
     state: AppState,
     Path(user_id): Path<i32>,
+    req: Parts,
 ) -> AppResult<Json<StatsResponse>> {
     let mut conn = state.db_read_prefer_primary().await?;
 
     use diesel::dsl::sum;
     use diesel_async::RunQueryDsl;
 
-    let total_downloads = CrateOwner::by_owner_kind(OwnerKind::User)
+    let mut query = CrateOwner::by_owner_kind(OwnerKind::User)
         .inner_join(crates::table)
         .inner_join(crate_downloads::table.on(crates::id.eq(crate_downloads::crate_id)))
         .filter(crate_owners::owner_id.eq(user_id))
-        .select(sum(crate_downloads::downloads))
+        .select(sum(crate_downloads::downloads));
+
+    // Leave out the downloads of crates that the reader is not allowed to read
+    if let Some(reader) = req.extensions.get::<RegistryReader>() {
+        query = query.filter(crates::id.ne_all(CrateReader::hidden_crate_ids(reader.user_id)));
+    }
+
+    let total_downloads = query
         .first::<Option<BigDecimal>>(&mut conn)
         .await?
         .map(|d| d.to_u64().unwrap_or(u64::MAX))
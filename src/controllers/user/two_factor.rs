@@ -0,0 +1,399 @@
+//! Endpoints for managing and verifying the second factors of the
+//! authenticated user.
+//!
+//! Two-factor authentication is optional. Users can enable it by setting up
+//! an authenticator app (TOTP) or by registering security keys and passkeys
+//! (WebAuthn). Once enabled, sensitive actions like creating API tokens or
+//! changing crate owners require a recent verification with one of these
+//! factors, see [`AuthCheck::require_second_factor()`].
+
+use crate::app::AppState;
+use crate::auth::{AuthCheck, mark_second_factor_verified, second_factor_verified_until};
+use crate::config::Server;
+use crate::models::two_factor::{has_two_factor, replace_recovery_codes, use_recovery_code};
+use crate::models::{CrateOwner, OwnerKind, TotpCredential, WebauthnCredential};
+use crate::rate_limiter::LimitedAction;
+use crate::schema::{crate_owners, crates, recovery_codes};
+use crate::util::errors::{AppResult, bad_request};
+use axum::Json;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use crates_io_session::SessionExtension;
+use crates_io_two_factor::recovery_codes as codes;
+use crates_io_two_factor::webauthn::RelyingParty;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use http::request::Parts;
+use serde::{Deserialize, Serialize};
+
+pub mod totp;
+pub mod webauthn;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct EncodableWebauthnCredential {
+    /// An opaque identifier for the security key or passkey.
+    #[schema(example = 42)]
+    id: i32,
+
+    /// The name that the user gave the security key or passkey.
+    #[schema(example = "YubiKey")]
+    name: String,
+
+    /// The time when the security key or passkey was registered.
+    #[schema(example = "2026-10-18T12:00:00Z")]
+    created_at: DateTime<Utc>,
+
+    /// The time when the security key or passkey was last used for a
+    /// verification.
+    #[schema(example = "2026-10-18T12:00:00Z")]
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebauthnCredential> for EncodableWebauthnCredential {
+    fn from(credential: WebauthnCredential) -> Self {
+        Self {
+            id: credential.id,
+            name: credential.name,
+            created_at: credential.created_at,
+            last_used_at: credential.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct TwoFactorStatusResponse {
+    /// Whether two-factor authentication is enabled for the account.
+    #[schema(example = true)]
+    enabled: bool,
+
+    /// Whether an authenticator app has been set up.
+    #[schema(example = false)]
+    totp_enabled: bool,
+
+    /// The registered security keys and passkeys.
+    webauthn_credentials: Vec<EncodableWebauthnCredential>,
+
+    /// The number of unused recovery codes.
+    #[schema(example = 10)]
+    recovery_codes_remaining: i64,
+
+    /// The time until which sensitive actions can be performed in the
+    /// current session without verifying a second factor again, or `null`
+    /// if there is no recent verification.
+    #[schema(example = "2026-10-18T12:15:00Z")]
+    verified_until: Option<DateTime<Utc>>,
+}
+
+/// Get the two-factor authentication status of the authenticated user.
+#[utoipa::path(
+    get,
+    path = "/api/private/me/two_factor",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(TwoFactorStatusResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn get_two_factor_status(
+    app: AppState,
+    session: SessionExtension,
+    req: Parts,
+) -> AppResult<Json<TwoFactorStatusResponse>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let user_id = AuthCheck::only_cookie()
+        .check(&req, &mut conn)
+        .await?
+        .user_id();
+
+    let totp = TotpCredential::find(&conn, user_id).await?;
+    let totp_enabled = totp.is_some_and(|totp| totp.is_confirmed());
+
+    let webauthn_credentials = WebauthnCredential::for_user(&conn, user_id).await?;
+
+    let recovery_codes_remaining = recovery_codes::table
+        .filter(recovery_codes::user_id.eq(user_id))
+        .count()
+        .get_result(&mut conn)
+        .await?;
+
+    let enabled = totp_enabled || !webauthn_credentials.is_empty();
+    let verified_until = enabled
+        .then(|| second_factor_verified_until(&session, user_id))
+        .flatten();
+
+    Ok(Json(TwoFactorStatusResponse {
+        enabled,
+        totp_enabled,
+        webauthn_credentials: webauthn_credentials.into_iter().map(Into::into).collect(),
+        recovery_codes_remaining,
+        verified_until,
+    }))
+}
+
+/// Begin a verification with a security key or passkey.
+///
+/// Returns the options for `navigator.credentials.get()` in the JSON format
+/// of `PublicKeyCredential.parseRequestOptionsFromJSON()`. The challenge is
+/// stored in the session and can only be used once.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/two_factor/verify/begin",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(webauthn::RequestOptions)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn begin_two_factor_verification(
+    app: AppState,
+    session: SessionExtension,
+    req: Parts,
+) -> AppResult<Json<webauthn::RequestOptions>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let user_id = AuthCheck::only_cookie()
+        .check(&req, &mut conn)
+        .await?
+        .user_id();
+
+    let credentials = WebauthnCredential::for_user(&conn, user_id).await?;
+    if credentials.is_empty() {
+        return Err(bad_request(
+            "No security keys or passkeys are registered for this account",
+        ));
+    }
+
+    let options = webauthn::RequestOptions::new(&app.config, &session, &credentials);
+    Ok(Json(options))
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum VerifyRequest {
+    /// A code of the authenticator app.
+    Totp {
+        #[schema(example = "123456")]
+        code: String,
+    },
+    /// One of the recovery codes. Every recovery code can only be used once.
+    RecoveryCode {
+        #[schema(example = "abcde-fghij")]
+        code: String,
+    },
+    /// The response of a security key or passkey to the challenge of
+    /// `POST /api/private/me/two_factor/verify/begin`.
+    Webauthn(webauthn::AuthenticationResponse),
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct VerifyResponse {
+    /// The time until which sensitive actions can be performed in the
+    /// current session without verifying a second factor again.
+    #[schema(example = "2026-10-18T12:15:00Z")]
+    verified_until: DateTime<Utc>,
+}
+
+/// Verify a second factor of the authenticated user.
+///
+/// A successful verification allows sensitive actions in the current session
+/// for a limited time.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/two_factor/verify",
+    security(("cookie" = [])),
+    tag = "users",
+    request_body = inline(VerifyRequest),
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(VerifyResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn verify_two_factor(
+    app: AppState,
+    session: SessionExtension,
+    req: Parts,
+    Json(body): Json<VerifyRequest>,
+) -> AppResult<Json<VerifyResponse>> {
+    let mut conn = app.db_write().await?;
+    let user_id = AuthCheck::only_cookie()
+        .check(&req, &mut conn)
+        .await?
+        .user_id();
+
+    if !has_two_factor(&conn, user_id).await? {
+        return Err(bad_request(
+            "Two-factor authentication is not enabled for this account",
+        ));
+    }
+
+    app.rate_limiter
+        .check_rate_limit(user_id, LimitedAction::TwoFactorVerify, &mut conn)
+        .await?;
+
+    let verified = match body {
+        VerifyRequest::Totp { code } => totp::verify_code(&app, &conn, user_id, &code).await?,
+        VerifyRequest::RecoveryCode { code } => {
+            use_recovery_code(&conn, user_id, &codes::hash(&code)).await?
+        }
+        VerifyRequest::Webauthn(response) => {
+            webauthn::verify_response(&app, &conn, &session, user_id, &response).await?
+        }
+    };
+
+    if !verified {
+        return Err(bad_request("The verification code is invalid"));
+    }
+
+    let verified_until = mark_second_factor_verified(&session, user_id);
+    Ok(Json(VerifyResponse { verified_until }))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct RecoveryCodesResponse {
+    /// The new recovery codes. They are only shown once and replace all
+    /// previous recovery codes.
+    #[schema(example = json!(["abcde-fghij"]))]
+    recovery_codes: Vec<String>,
+}
+
+/// Regenerate the recovery codes of the authenticated user.
+///
+/// All previous recovery codes become invalid.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/two_factor/recovery_codes",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(RecoveryCodesResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn regenerate_recovery_codes(
+    app: AppState,
+    req: Parts,
+) -> AppResult<Json<RecoveryCodesResponse>> {
+    let mut conn = app.db_write().await?;
+    let user_id = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?
+        .user_id();
+
+    if !has_two_factor(&conn, user_id).await? {
+        return Err(bad_request(
+            "Two-factor authentication is not enabled for this account",
+        ));
+    }
+
+    let recovery_codes = generate_recovery_codes(&conn, user_id).await?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct FactorAddedResponse {
+    /// The recovery codes of the account, if this was the first second
+    /// factor. Otherwise the existing recovery codes stay valid and this is
+    /// `null`.
+    #[schema(example = json!(["abcde-fghij"]))]
+    recovery_codes: Option<Vec<String>>,
+}
+
+impl FactorAddedResponse {
+    /// Builds the response for a newly added second factor, and marks the
+    /// current session as verified since the user just proved possession of
+    /// the factor.
+    async fn new(
+        conn: &AsyncPgConnection,
+        session: &SessionExtension,
+        user_id: i32,
+        is_first_factor: bool,
+    ) -> AppResult<Self> {
+        let recovery_codes = match is_first_factor {
+            true => Some(generate_recovery_codes(conn, user_id).await?),
+            false => None,
+        };
+
+        mark_second_factor_verified(session, user_id);
+
+        Ok(Self { recovery_codes })
+    }
+}
+
+/// Generates and stores a new set of recovery codes for the user, and
+/// returns them in plain text.
+async fn generate_recovery_codes(conn: &AsyncPgConnection, user_id: i32) -> AppResult<Vec<String>> {
+    let codes = codes::generate();
+    let hashes = codes
+        .iter()
+        .map(|code| codes::hash(code))
+        .collect::<Vec<_>>();
+
+    replace_recovery_codes(conn, user_id, &hashes).await?;
+    Ok(codes)
+}
+
+/// Returns an error if the user owns crates that require all owners to have
+/// two-factor authentication enabled.
+///
+/// This is checked before the last second factor of a user is removed.
+async fn ensure_not_required_by_crates(conn: &AsyncPgConnection, user_id: i32) -> AppResult<()> {
+    let crate_names: Vec<String> = CrateOwner::by_owner_kind(OwnerKind::User)
+        .inner_join(crates::table)
+        .filter(crate_owners::owner_id.eq(user_id))
+        .filter(crates::require_owner_2fa)
+        .select(crates::name)
+        .order(crates::name.asc())
+        .load(&mut &*conn)
+        .await?;
+
+    if !crate_names.is_empty() {
+        let crate_names = crate_names.join("`, `");
+        return Err(bad_request(format!(
+            "Two-factor authentication can not be disabled because it is required for the following crates: `{crate_names}`"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Removes the recovery codes of a user, if they no longer have a second
+/// factor that the codes could replace.
+async fn remove_unused_recovery_codes(conn: &AsyncPgConnection, user_id: i32) -> AppResult<()> {
+    if !has_two_factor(conn, user_id).await? {
+        diesel::delete(recovery_codes::table)
+            .filter(recovery_codes::user_id.eq(user_id))
+            .execute(&mut &*conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the relying party that WebAuthn credentials are registered for.
+fn relying_party(config: &Server) -> RelyingParty<'_> {
+    RelyingParty {
+        id: &config.domain_name,
+        origins: config.allowed_origins.as_slice(),
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn decode_base64(field: &str, value: &str) -> AppResult<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|_| bad_request(format!("`{field}` is not valid base64url")))
+}
@@ -0,0 +1,259 @@
+//! Endpoints for setting up and removing an authenticator app, which
+//! generates time-based one-time passwords (TOTP).
+
+use super::{FactorAddedResponse, ensure_not_required_by_crates, remove_unused_recovery_codes};
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::controllers::helpers::OkResponse;
+use crate::models::two_factor::has_two_factor;
+use crate::models::{NewTotpCredential, TotpCredential};
+use crate::schema::{totp_credentials, webauthn_credentials};
+use crate::util::errors::{AppResult, bad_request, server_error};
+use axum::Json;
+use chrono::Utc;
+use crates_io_session::SessionExtension;
+use crates_io_two_factor::totp::Secret;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use http::request::Parts;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BeginTotpResponse {
+    /// The shared secret in base32 encoding, for entering it manually into
+    /// the authenticator app.
+    #[schema(example = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ")]
+    secret: String,
+
+    /// The `otpauth://` URI of the secret, usually displayed as a QR code.
+    #[schema(
+        example = "otpauth://totp/crates.io:ghost?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=crates.io"
+    )]
+    provisioning_uri: String,
+}
+
+/// Begin setting up an authenticator app.
+///
+/// Generates a new shared secret, which has to be confirmed with a code of
+/// the authenticator app before it is enabled. A previous setup that was not
+/// confirmed is replaced.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/two_factor/totp/begin",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(BeginTotpResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn begin_totp_setup(app: AppState, req: Parts) -> AppResult<Json<BeginTotpResponse>> {
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+    let user = auth.user();
+
+    let secret = Secret::generate();
+
+    let encryption = &app.config.token_encryption;
+    let encrypted_secret = encryption
+        .encrypt(&hex::encode(secret.as_bytes()))
+        .map_err(|error| {
+            error!("Failed to encrypt TOTP secret: {error}");
+            server_error("Internal server error")
+        })?;
+
+    let inserted = NewTotpCredential::builder()
+        .user_id(user.id)
+        .encrypted_secret(&encrypted_secret)
+        .build()
+        .insert_pending(&conn)
+        .await?;
+
+    if !inserted {
+        return Err(bad_request(
+            "An authenticator app is already set up for this account",
+        ));
+    }
+
+    let issuer = &app.config.domain_name;
+    Ok(Json(BeginTotpResponse {
+        secret: secret.to_base32(),
+        provisioning_uri: secret.provisioning_uri(issuer, &user.gh_login),
+    }))
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct ConfirmTotpRequest {
+    /// A code of the authenticator app.
+    #[schema(example = "123456")]
+    code: String,
+}
+
+/// Confirm the setup of an authenticator app.
+///
+/// If this is the first second factor of the account, two-factor
+/// authentication is enabled and a set of recovery codes is returned.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/two_factor/totp/confirm",
+    security(("cookie" = [])),
+    tag = "users",
+    request_body = inline(ConfirmTotpRequest),
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(FactorAddedResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn confirm_totp_setup(
+    app: AppState,
+    session: SessionExtension,
+    req: Parts,
+    Json(body): Json<ConfirmTotpRequest>,
+) -> AppResult<Json<FactorAddedResponse>> {
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+    let user = auth.user();
+
+    let credential = TotpCredential::find(&conn, user.id)
+        .await?
+        .filter(|credential| !credential.is_confirmed())
+        .ok_or_else(|| bad_request("There is no pending authenticator app setup"))?;
+
+    let secret = decrypt_secret(&app, &credential)?;
+    let Some(step) = secret.verify(&body.code, Utc::now(), None) else {
+        return Err(bad_request("The verification code is invalid"));
+    };
+
+    let is_first_factor = !has_two_factor(&conn, user.id).await?;
+
+    let updated = diesel::update(totp_credentials::table.find(user.id))
+        .filter(totp_credentials::confirmed_at.is_null())
+        .set((
+            totp_credentials::confirmed_at.eq(now),
+            totp_credentials::last_used_step.eq(step),
+        ))
+        .execute(&mut conn)
+        .await?;
+
+    if updated == 0 {
+        return Err(bad_request("There is no pending authenticator app setup"));
+    }
+
+    info!("User {} enabled an authenticator app", user.gh_login);
+
+    let response = FactorAddedResponse::new(&conn, &session, user.id, is_first_factor).await?;
+    Ok(Json(response))
+}
+
+/// Remove the authenticator app of the authenticated user.
+#[utoipa::path(
+    delete,
+    path = "/api/private/me/two_factor/totp",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(OkResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn delete_totp(app: AppState, req: Parts) -> AppResult<OkResponse> {
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+    let user = auth.user();
+
+    let credential = TotpCredential::find(&conn, user.id).await?;
+    let Some(credential) = credential.filter(TotpCredential::is_confirmed) else {
+        return Err(bad_request(
+            "No authenticator app is set up for this account",
+        ));
+    };
+
+    let webauthn_count = webauthn_credentials::table
+        .filter(webauthn_credentials::user_id.eq(user.id))
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await?;
+
+    if webauthn_count == 0 {
+        ensure_not_required_by_crates(&conn, user.id).await?;
+    }
+
+    diesel::delete(totp_credentials::table.find(credential.user_id))
+        .execute(&mut conn)
+        .await?;
+
+    remove_unused_recovery_codes(&conn, user.id).await?;
+
+    info!("User {} removed their authenticator app", user.gh_login);
+
+    Ok(OkResponse::new())
+}
+
+/// Verifies a code of the confirmed authenticator app of a user.
+///
+/// Every code can only be used once. Returns `false` if the user has no
+/// confirmed authenticator app or the code is invalid.
+pub(super) async fn verify_code(
+    app: &AppState,
+    mut conn: &AsyncPgConnection,
+    user_id: i32,
+    code: &str,
+) -> AppResult<bool> {
+    let credential = TotpCredential::find(conn, user_id).await?;
+    let Some(credential) = credential.filter(TotpCredential::is_confirmed) else {
+        return Ok(false);
+    };
+
+    let secret = decrypt_secret(app, &credential)?;
+    let Some(step) = secret.verify(code, Utc::now(), credential.last_used_step) else {
+        return Ok(false);
+    };
+
+    // The filter prevents concurrent requests from using the same code twice.
+    let updated = diesel::update(totp_credentials::table.find(user_id))
+        .filter(
+            totp_credentials::last_used_step
+                .is_null()
+                .or(totp_credentials::last_used_step.lt(step)),
+        )
+        .set(totp_credentials::last_used_step.eq(step))
+        .execute(&mut conn)
+        .await?;
+
+    Ok(updated > 0)
+}
+
+fn decrypt_secret(app: &AppState, credential: &TotpCredential) -> AppResult<Secret> {
+    let encryption = &app.config.token_encryption;
+    let secret = encryption
+        .decrypt(&credential.encrypted_secret)
+        .map_err(|error| {
+            error!("Failed to decrypt TOTP secret: {error}");
+            server_error("Internal server error")
+        })?;
+
+    let secret = hex::decode(secret.expose_secret()).map_err(|error| {
+        error!("Failed to decode TOTP secret: {error}");
+        server_error("Internal server error")
+    })?;
+
+    Ok(Secret::from_bytes(secret))
+}
@@ -0,0 +1,424 @@
+//! Endpoints for registering and removing security keys and passkeys
+//! (WebAuthn credentials).
+//!
+//! The request and response types use the JSON serialization of the
+//! WebAuthn Level 3 specification, so that the frontend can pass them to
+//! `PublicKeyCredential.parseCreationOptionsFromJSON()` and
+//! `PublicKeyCredential.parseRequestOptionsFromJSON()` directly.
+
+use super::{
+    FactorAddedResponse, decode_base64, encode_base64, ensure_not_required_by_crates,
+    relying_party, remove_unused_recovery_codes,
+};
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::config::Server;
+use crate::controllers::helpers::OkResponse;
+use crate::models::two_factor::has_two_factor;
+use crate::models::{NewWebauthnCredential, TotpCredential, WebauthnCredential};
+use crate::schema::webauthn_credentials;
+use crate::util::errors::{AppResult, bad_request};
+use axum::Json;
+use axum::extract::Path;
+use crates_io_session::SessionExtension;
+use crates_io_two_factor::webauthn;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use http::request::Parts;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// The session key that stores the challenge of a pending registration.
+const REGISTRATION_CHALLENGE: &str = "webauthn_registration_challenge";
+
+/// The session key that stores the challenge of a pending verification.
+const AUTHENTICATION_CHALLENGE: &str = "webauthn_authentication_challenge";
+
+/// How long the browser should wait for the user to interact with their
+/// authenticator, in milliseconds.
+const TIMEOUT_MS: u32 = 5 * 60 * 1000;
+
+/// The maximum number of security keys and passkeys per user.
+const MAX_CREDENTIALS: usize = 10;
+
+/// The maximum length of the name of a security key or passkey.
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct RelyingPartyEntity {
+    #[schema(example = "crates.io")]
+    id: String,
+    #[schema(example = "crates.io")]
+    name: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntity {
+    /// The base64url encoded user handle.
+    #[schema(example = "AAAAKg")]
+    id: String,
+    #[schema(example = "ghost")]
+    name: String,
+    #[schema(example = "Ghost")]
+    display_name: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CredentialParameters {
+    #[serde(rename = "type")]
+    #[schema(example = "public-key")]
+    ty: &'static str,
+    #[schema(example = -7)]
+    alg: i64,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    #[schema(example = "public-key")]
+    ty: &'static str,
+    /// The base64url encoded credential ID.
+    #[schema(example = "Y3JlZGVudGlhbA")]
+    id: String,
+}
+
+impl CredentialDescriptor {
+    fn new(credential: &WebauthnCredential) -> Self {
+        Self {
+            ty: "public-key",
+            id: encode_base64(&credential.credential_id),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreationOptions {
+    /// The base64url encoded challenge.
+    #[schema(example = "Y2hhbGxlbmdl")]
+    challenge: String,
+    rp: RelyingPartyEntity,
+    user: UserEntity,
+    pub_key_cred_params: Vec<CredentialParameters>,
+    #[schema(example = 300000)]
+    timeout: u32,
+    exclude_credentials: Vec<CredentialDescriptor>,
+    #[schema(example = "none")]
+    attestation: &'static str,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions {
+    /// The base64url encoded challenge.
+    #[schema(example = "Y2hhbGxlbmdl")]
+    challenge: String,
+    #[schema(example = "crates.io")]
+    rp_id: String,
+    allow_credentials: Vec<CredentialDescriptor>,
+    #[schema(example = 300000)]
+    timeout: u32,
+    #[schema(example = "discouraged")]
+    user_verification: &'static str,
+}
+
+impl RequestOptions {
+    /// Creates the options for a verification with one of the given
+    /// credentials, and stores the new challenge in the session.
+    pub(super) fn new(
+        config: &Server,
+        session: &SessionExtension,
+        credentials: &[WebauthnCredential],
+    ) -> Self {
+        let challenge = encode_base64(&webauthn::generate_challenge());
+        session.insert(AUTHENTICATION_CHALLENGE.into(), challenge.clone());
+
+        Self {
+            challenge,
+            rp_id: config.domain_name.clone(),
+            allow_credentials: credentials.iter().map(CredentialDescriptor::new).collect(),
+            timeout: TIMEOUT_MS,
+            user_verification: "discouraged",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct AuthenticationResponse {
+    /// The base64url encoded ID of the credential that was used.
+    #[schema(example = "Y3JlZGVudGlhbA")]
+    credential_id: String,
+    /// The base64url encoded `clientDataJSON` of the response.
+    client_data_json: String,
+    /// The base64url encoded `authenticatorData` of the response.
+    authenticator_data: String,
+    /// The base64url encoded `signature` of the response.
+    signature: String,
+}
+
+/// Begin registering a security key or passkey.
+///
+/// Returns the options for `navigator.credentials.create()`. The challenge
+/// is stored in the session and can only be used once.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/two_factor/webauthn/begin",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(CreationOptions)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn begin_webauthn_registration(
+    app: AppState,
+    session: SessionExtension,
+    req: Parts,
+) -> AppResult<Json<CreationOptions>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+    let user = auth.user();
+
+    let credentials = WebauthnCredential::for_user(&conn, user.id).await?;
+    if credentials.len() >= MAX_CREDENTIALS {
+        return Err(bad_request(format!(
+            "An account can not have more than {MAX_CREDENTIALS} security keys or passkeys"
+        )));
+    }
+
+    let challenge = encode_base64(&webauthn::generate_challenge());
+    session.insert(REGISTRATION_CHALLENGE.into(), challenge.clone());
+
+    let domain_name = &app.config.domain_name;
+    Ok(Json(CreationOptions {
+        challenge,
+        rp: RelyingPartyEntity {
+            id: domain_name.clone(),
+            name: domain_name.clone(),
+        },
+        user: UserEntity {
+            id: encode_base64(&user.id.to_be_bytes()),
+            name: user.gh_login.clone(),
+            display_name: user.name.clone().unwrap_or_else(|| user.gh_login.clone()),
+        },
+        pub_key_cred_params: vec![CredentialParameters {
+            ty: "public-key",
+            alg: webauthn::ES256,
+        }],
+        timeout: TIMEOUT_MS,
+        exclude_credentials: credentials.iter().map(CredentialDescriptor::new).collect(),
+        attestation: "none",
+    }))
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct RegisterWebauthnRequest {
+    /// A name for the security key or passkey.
+    #[schema(example = "YubiKey")]
+    name: String,
+    /// The base64url encoded `clientDataJSON` of the response.
+    client_data_json: String,
+    /// The base64url encoded `attestationObject` of the response.
+    attestation_object: String,
+}
+
+/// Complete the registration of a security key or passkey.
+///
+/// If this is the first second factor of the account, two-factor
+/// authentication is enabled and a set of recovery codes is returned.
+#[utoipa::path(
+    post,
+    path = "/api/private/me/two_factor/webauthn",
+    security(("cookie" = [])),
+    tag = "users",
+    request_body = inline(RegisterWebauthnRequest),
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(FactorAddedResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn register_webauthn_credential(
+    app: AppState,
+    session: SessionExtension,
+    req: Parts,
+    Json(body): Json<RegisterWebauthnRequest>,
+) -> AppResult<Json<FactorAddedResponse>> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(bad_request("name must have a value"));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(bad_request(format!(
+            "name must not be longer than {MAX_NAME_LENGTH} characters"
+        )));
+    }
+
+    let client_data_json = decode_base64("client_data_json", &body.client_data_json)?;
+    let attestation_object = decode_base64("attestation_object", &body.attestation_object)?;
+
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+    let user = auth.user();
+
+    let challenge = take_challenge(&session, REGISTRATION_CHALLENGE)?;
+
+    let rp = relying_party(&app.config);
+    let credential =
+        webauthn::verify_registration(rp, &challenge, &client_data_json, &attestation_object)
+            .map_err(|error| {
+                warn!(
+                    "Failed to verify WebAuthn registration of {}: {error}",
+                    user.gh_login
+                );
+                bad_request(format!("Invalid security key response: {error}"))
+            })?;
+
+    let is_first_factor = !has_two_factor(&conn, user.id).await?;
+
+    let inserted = NewWebauthnCredential::builder()
+        .user_id(user.id)
+        .credential_id(&credential.id)
+        .public_key(&credential.public_key)
+        .sign_count(credential.sign_count.into())
+        .name(name)
+        .build()
+        .insert(&conn)
+        .await?;
+
+    if !inserted {
+        return Err(bad_request("This security key is already registered"));
+    }
+
+    info!("User {} registered security key {name:?}", user.gh_login);
+
+    let response = FactorAddedResponse::new(&conn, &session, user.id, is_first_factor).await?;
+    Ok(Json(response))
+}
+
+/// Remove a security key or passkey of the authenticated user.
+#[utoipa::path(
+    delete,
+    path = "/api/private/me/two_factor/webauthn/{id}",
+    params(
+        ("id" = i32, Path, description = "ID of the security key or passkey"),
+    ),
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(OkResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn delete_webauthn_credential(
+    app: AppState,
+    Path(id): Path<i32>,
+    req: Parts,
+) -> AppResult<OkResponse> {
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie()
+        .require_second_factor()
+        .check(&req, &mut conn)
+        .await?;
+    let user = auth.user();
+
+    let credentials = WebauthnCredential::for_user(&conn, user.id).await?;
+    let Some(credential) = credentials.iter().find(|credential| credential.id == id) else {
+        return Err(bad_request("No security key with this ID is registered"));
+    };
+
+    let totp = TotpCredential::find(&conn, user.id).await?;
+    let totp_enabled = totp.is_some_and(|totp| totp.is_confirmed());
+    if credentials.len() == 1 && !totp_enabled {
+        ensure_not_required_by_crates(&conn, user.id).await?;
+    }
+
+    diesel::delete(webauthn_credentials::table.find(credential.id))
+        .execute(&mut conn)
+        .await?;
+
+    remove_unused_recovery_codes(&conn, user.id).await?;
+
+    info!(
+        "User {} removed security key {:?}",
+        user.gh_login, credential.name
+    );
+
+    Ok(OkResponse::new())
+}
+
+/// Verifies the response of a security key or passkey of a user to the
+/// challenge in the session.
+///
+/// Returns `false` if the credential is not registered for the user.
+pub(super) async fn verify_response(
+    app: &AppState,
+    mut conn: &AsyncPgConnection,
+    session: &SessionExtension,
+    user_id: i32,
+    response: &AuthenticationResponse,
+) -> AppResult<bool> {
+    let credential_id = decode_base64("credential_id", &response.credential_id)?;
+    let client_data_json = decode_base64("client_data_json", &response.client_data_json)?;
+    let authenticator_data = decode_base64("authenticator_data", &response.authenticator_data)?;
+    let signature = decode_base64("signature", &response.signature)?;
+
+    let challenge = take_challenge(session, AUTHENTICATION_CHALLENGE)?;
+
+    let credential = webauthn_credentials::table
+        .filter(webauthn_credentials::user_id.eq(user_id))
+        .filter(webauthn_credentials::credential_id.eq(&credential_id))
+        .select(WebauthnCredential::as_select())
+        .first(&mut conn)
+        .await
+        .optional()?;
+
+    let Some(credential) = credential else {
+        return Ok(false);
+    };
+
+    let sign_count = webauthn::verify_authentication(
+        relying_party(&app.config),
+        &challenge,
+        &credential.public_key,
+        u32::try_from(credential.sign_count).unwrap_or(u32::MAX),
+        &client_data_json,
+        &authenticator_data,
+        &signature,
+    )
+    .map_err(|error| {
+        warn!("Failed to verify WebAuthn response of user {user_id}: {error}");
+        bad_request(format!("Invalid security key response: {error}"))
+    })?;
+
+    diesel::update(webauthn_credentials::table.find(credential.id))
+        .set((
+            webauthn_credentials::sign_count.eq(i64::from(sign_count)),
+            webauthn_credentials::last_used_at.eq(now),
+        ))
+        .execute(&mut conn)
+        .await?;
+
+    Ok(true)
+}
+
+/// Removes the challenge with the given key from the session and returns it.
+fn take_challenge(session: &SessionExtension, key: &str) -> AppResult<Vec<u8>> {
+    session
+        .remove(key)
+        .and_then(|challenge| decode_base64(key, &challenge).ok())
+        .ok_or_else(|| bad_request("The security key challenge is missing or has expired"))
+}
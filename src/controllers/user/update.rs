@@ -2,13 +2,13 @@ use crate::app::AppState;
 use crate::auth::AuthCheck;
 use crate::controllers::helpers::OkResponse;
 use crate::email::EmailMessage;
-use crate::models::NewEmail;
+use crate::models::{self, NewEmail};
 use crate::schema::users;
 use crate::util::errors::{AppResult, bad_request, server_error};
 use axum::Json;
 use axum::extract::Path;
 use diesel::prelude::*;
-use diesel_async::RunQueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use http::request::Parts;
 use lettre::Address;
 use minijinja::context;
@@ -67,38 +67,8 @@ pub async fn update_user(
         return Err(bad_request("current user does not match requested user"));
     }
 
-    if let Some(publish_notifications) = &user_update.user.publish_notifications
-        && user.publish_notifications != *publish_notifications
-    {
-        diesel::update(user)
-            .set(users::publish_notifications.eq(*publish_notifications))
-            .execute(&mut conn)
-            .await?;
-
-        if !publish_notifications {
-            let email_address = user.verified_email(&conn).await?;
-
-            if let Some(email_address) = email_address {
-                let email = EmailMessage::from_template(
-                    "unsubscribe_notifications",
-                    context! {
-                        user_name => user.gh_login,
-                        domain => state.emails.domain
-                    },
-                );
-
-                match email {
-                    Ok(email) => {
-                        if let Err(error) = state.emails.send(&email_address, email).await {
-                            warn!(
-                                "Failed to send publish notifications unsubscribe email to {email_address}: {error}"
-                            );
-                        }
-                    }
-                    Err(error) => warn!("Failed to render unsubscribe email template: {error}"),
-                }
-            }
-        }
+    if let Some(publish_notifications) = user_update.user.publish_notifications {
+        set_publish_notifications(&state, &mut conn, user, publish_notifications).await?;
     }
 
     if let Some(user_email) = &user_update.user.email {
@@ -145,3 +115,50 @@ pub async fn update_user(
 
     Ok(OkResponse::new())
 }
+
+/// Enables or disables the publish notifications of all crates of the user.
+///
+/// Disabling them sends a confirmation email, so that the user notices if
+/// someone else has disabled them.
+pub(crate) async fn set_publish_notifications(
+    state: &AppState,
+    conn: &mut AsyncPgConnection,
+    user: &models::User,
+    publish_notifications: bool,
+) -> QueryResult<()> {
+    if user.publish_notifications == publish_notifications {
+        return Ok(());
+    }
+
+    diesel::update(user)
+        .set(users::publish_notifications.eq(publish_notifications))
+        .execute(conn)
+        .await?;
+
+    if !publish_notifications {
+        let email_address = user.verified_email(conn).await?;
+
+        if let Some(email_address) = email_address {
+            let email = EmailMessage::from_template(
+                "unsubscribe_notifications",
+                context! {
+                    user_name => user.gh_login,
+                    domain => state.emails.domain
+                },
+            );
+
+            match email {
+                Ok(email) => {
+                    if let Err(error) = state.emails.send(&email_address, email).await {
+                        warn!(
+                            "Failed to send publish notifications unsubscribe email to {email_address}: {error}"
+                        );
+                    }
+                }
+                Err(error) => warn!("Failed to render unsubscribe email template: {error}"),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,422 @@
+//! Endpoints for managing the webhooks of the authenticated user.
+//!
+//! Webhooks receive the events of the crates that the user owns, e.g. new
+//! releases or owner changes. Webhooks can be restricted to a single crate,
+//! and stop receiving its events once the user is no longer an owner.
+//!
+//! Every payload is signed with the secret that is returned when the webhook
+//! is created, see [`crate::worker::jobs::DeliverWebhook`].
+
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::models::{Crate, NewWebhook, OwnerKind, Webhook, WebhookDelivery, WebhookEvent};
+use crate::schema::{crate_owners, crates, webhook_deliveries, webhooks};
+use crate::util::errors::{AppResult, bad_request, custom, not_found, server_error};
+use crate::worker::jobs::webhooks::is_public_ip;
+use axum::Json;
+use axum::extract::Path;
+use chrono::{DateTime, Utc};
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use http::StatusCode;
+use http::request::Parts;
+use rand::distr::{Alphanumeric, SampleString};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use url::{Host, Url};
+
+/// The maximum number of webhooks per user.
+const MAX_WEBHOOKS_PER_USER: i64 = 20;
+
+/// The number of deliveries that are returned by the delivery log.
+const MAX_DELIVERIES: i64 = 100;
+
+const SECRET_LENGTH: usize = 32;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct EncodableWebhook {
+    /// An opaque identifier for the webhook.
+    #[schema(example = 42)]
+    id: i32,
+
+    /// The name of the crate whose events are delivered, or `null` for the
+    /// events of all crates owned by the user.
+    #[serde(rename = "crate")]
+    #[schema(example = "serde")]
+    krate: Option<String>,
+
+    /// The URL that the events are sent to.
+    #[schema(example = "https://example.com/webhooks/crates-io")]
+    url: String,
+
+    /// The types of events that are delivered.
+    events: Vec<String>,
+
+    /// The time when the webhook was created.
+    #[schema(example = "2026-10-18T12:00:00Z")]
+    created_at: DateTime<Utc>,
+}
+
+impl EncodableWebhook {
+    fn new(webhook: Webhook, krate: Option<String>) -> Self {
+        Self {
+            id: webhook.id,
+            krate,
+            url: webhook.url,
+            events: webhook.events,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ListWebhooksResponse {
+    webhooks: Vec<EncodableWebhook>,
+}
+
+/// List the webhooks of the authenticated user.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/webhooks",
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(ListWebhooksResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn list_webhooks(app: AppState, req: Parts) -> AppResult<Json<ListWebhooksResponse>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let auth = AuthCheck::only_cookie().check(&req, &mut conn).await?;
+    let user = auth.user();
+
+    let webhooks = webhooks::table
+        .left_join(crates::table)
+        .select((Webhook::as_select(), crates::name.nullable()))
+        .filter(webhooks::user_id.eq(user.id))
+        .order(webhooks::id)
+        .load::<(Webhook, Option<String>)>(&mut conn)
+        .await?
+        .into_iter()
+        .map(|(webhook, krate)| EncodableWebhook::new(webhook, krate))
+        .collect();
+
+    Ok(Json(ListWebhooksResponse { webhooks }))
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct NewWebhookRequest {
+    /// The name of the crate whose events should be delivered. If omitted,
+    /// the events of all crates owned by the user are delivered.
+    #[serde(rename = "crate")]
+    #[schema(example = "serde")]
+    krate: Option<String>,
+
+    /// The `https` URL that the events are sent to. It must not point to a
+    /// local or private network address.
+    #[schema(example = "https://example.com/webhooks/crates-io")]
+    url: String,
+
+    /// The types of events that should be delivered.
+    #[schema(example = json!(["publish", "yank", "unyank"]))]
+    events: Vec<WebhookEvent>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CreateWebhookResponse {
+    webhook: EncodableWebhook,
+
+    /// The secret that is used to sign the payloads. It is only returned
+    /// once, when the webhook is created.
+    secret: String,
+}
+
+/// Create a webhook for the authenticated user.
+///
+/// The response includes the secret that is used to sign the payloads. It
+/// can not be retrieved again later.
+#[utoipa::path(
+    post,
+    path = "/api/v1/me/webhooks",
+    security(("cookie" = [])),
+    tag = "users",
+    request_body = inline(NewWebhookRequest),
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(CreateWebhookResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn create_webhook(
+    app: AppState,
+    req: Parts,
+    Json(body): Json<NewWebhookRequest>,
+) -> AppResult<Json<CreateWebhookResponse>> {
+    validate_url(&body.url, app.config.insecure_webhooks)?;
+
+    if body.events.is_empty() {
+        return Err(bad_request("at least one event must be selected"));
+    }
+
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie().check(&req, &mut conn).await?;
+    let user = auth.user();
+
+    if Webhook::count_for_user(&conn, user.id).await? >= MAX_WEBHOOKS_PER_USER {
+        let message = format!("You can not have more than {MAX_WEBHOOKS_PER_USER} webhooks");
+        return Err(custom(StatusCode::CONFLICT, message));
+    }
+
+    let krate = match &body.krate {
+        Some(name) => Some(load_owned_crate(&mut conn, name, user.id).await?),
+        None => None,
+    };
+
+    let secret = Alphanumeric.sample_string(&mut rand::rng(), SECRET_LENGTH);
+    let encrypted_secret = app
+        .config
+        .token_encryption
+        .encrypt(&secret)
+        .map_err(|error| {
+            error!("Failed to encrypt webhook secret: {error}");
+            server_error("Internal server error")
+        })?;
+
+    let mut events = body
+        .events
+        .iter()
+        .map(WebhookEvent::as_str)
+        .collect::<Vec<_>>();
+    events.sort_unstable();
+    events.dedup();
+
+    let webhook = NewWebhook::builder()
+        .user_id(user.id)
+        .maybe_crate_id(krate.as_ref().map(|krate| krate.id))
+        .url(&body.url)
+        .encrypted_secret(&encrypted_secret)
+        .events(&events)
+        .build()
+        .insert(&conn)
+        .await?;
+
+    let webhook = EncodableWebhook::new(webhook, krate.map(|krate| krate.name));
+    Ok(Json(CreateWebhookResponse { webhook, secret }))
+}
+
+/// Delete a webhook of the authenticated user.
+///
+/// The delivery log of the webhook is deleted too.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/me/webhooks/{id}",
+    params(
+        ("id" = i32, Path, description = "ID of the webhook"),
+    ),
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 204, description = "Successful Response"),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn delete_webhook(
+    app: AppState,
+    Path(id): Path<i32>,
+    req: Parts,
+) -> AppResult<StatusCode> {
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::only_cookie().check(&req, &mut conn).await?;
+    let user = auth.user();
+
+    let deleted = diesel::delete(webhooks::table)
+        .filter(webhooks::id.eq(id))
+        .filter(webhooks::user_id.eq(user.id))
+        .execute(&mut conn)
+        .await?;
+
+    if deleted == 0 {
+        return Err(not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct EncodableWebhookDelivery {
+    /// The ID of the delivery, which is also sent in the
+    /// `X-Crates-Io-Delivery` header.
+    #[schema(example = 42)]
+    id: i64,
+
+    /// The type of the event.
+    #[schema(example = "publish")]
+    event: String,
+
+    /// The JSON payload that is sent to the webhook.
+    payload: serde_json::Value,
+
+    /// The number of delivery attempts so far.
+    #[schema(example = 1)]
+    attempts: i32,
+
+    /// The HTTP status code of the response to the last attempt, if a
+    /// response was received.
+    #[schema(example = 200)]
+    response_status: Option<i32>,
+
+    /// The error of the last attempt, if it failed.
+    error: Option<String>,
+
+    /// The time when the event happened.
+    #[schema(example = "2026-10-18T12:00:00Z")]
+    created_at: DateTime<Utc>,
+
+    /// The time of the last delivery attempt.
+    #[schema(example = "2026-10-18T12:00:01Z")]
+    last_attempt_at: Option<DateTime<Utc>>,
+
+    /// The time when the event was delivered successfully.
+    #[schema(example = "2026-10-18T12:00:01Z")]
+    delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDelivery> for EncodableWebhookDelivery {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            event: delivery.event,
+            payload: delivery.payload,
+            attempts: delivery.attempts,
+            response_status: delivery.response_status,
+            error: delivery.error,
+            created_at: delivery.created_at,
+            last_attempt_at: delivery.last_attempt_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ListWebhookDeliveriesResponse {
+    /// The most recent deliveries of the webhook, newest first.
+    deliveries: Vec<EncodableWebhookDelivery>,
+}
+
+/// List the most recent deliveries of a webhook of the authenticated user.
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/webhooks/{id}/deliveries",
+    params(
+        ("id" = i32, Path, description = "ID of the webhook"),
+    ),
+    security(("cookie" = [])),
+    tag = "users",
+    extensions(("x-internal" = json!(true))),
+    responses(
+        (status = 200, description = "Successful Response", body = inline(ListWebhookDeliveriesResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn list_webhook_deliveries(
+    app: AppState,
+    Path(id): Path<i32>,
+    req: Parts,
+) -> AppResult<Json<ListWebhookDeliveriesResponse>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let auth = AuthCheck::only_cookie().check(&req, &mut conn).await?;
+    let user = auth.user();
+
+    let is_owner = diesel::select(exists(
+        webhooks::table
+            .filter(webhooks::id.eq(id))
+            .filter(webhooks::user_id.eq(user.id)),
+    ))
+    .get_result::<bool>(&mut conn)
+    .await?;
+
+    if !is_owner {
+        return Err(not_found());
+    }
+
+    let deliveries = WebhookDelivery::query()
+        .filter(webhook_deliveries::webhook_id.eq(id))
+        .order(webhook_deliveries::id.desc())
+        .limit(MAX_DELIVERIES)
+        .load(&mut conn)
+        .await?
+        .into_iter()
+        .map(EncodableWebhookDelivery::from)
+        .collect();
+
+    Ok(Json(ListWebhookDeliveriesResponse { deliveries }))
+}
+
+/// Checks that the URL can be used as a webhook target. The addresses that
+/// a domain resolves to are checked again before every delivery.
+fn validate_url(url: &str, allow_insecure: bool) -> AppResult<()> {
+    let Ok(parsed) = Url::parse(url) else {
+        return Err(bad_request("invalid webhook URL"));
+    };
+
+    if allow_insecure {
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(bad_request("webhook URLs must use `http` or `https`"));
+        }
+        return Ok(());
+    }
+
+    if parsed.scheme() != "https" {
+        return Err(bad_request("webhook URLs must use `https`"));
+    }
+
+    let is_public = match parsed.host() {
+        Some(Host::Domain(domain)) => domain != "localhost" && !domain.ends_with(".localhost"),
+        Some(Host::Ipv4(ip)) => is_public_ip(ip.into()),
+        Some(Host::Ipv6(ip)) => is_public_ip(ip.into()),
+        None => false,
+    };
+    if !is_public {
+        return Err(bad_request(
+            "webhook URLs must not point to local or private network addresses",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Loads a crate, and checks that the user is one of its individual owners.
+pub(super) async fn load_owned_crate(
+    conn: &mut AsyncPgConnection,
+    name: &str,
+    user_id: i32,
+) -> AppResult<Crate> {
+    let krate = Crate::by_name(name)
+        .first::<Crate>(conn)
+        .await
+        .optional()?
+        .ok_or_else(|| bad_request(format!("crate `{name}` does not exist")))?;
+
+    let is_owner = diesel::select(exists(
+        crate_owners::table
+            .filter(crate_owners::crate_id.eq(krate.id))
+            .filter(crate_owners::owner_id.eq(user_id))
+            .filter(crate_owners::owner_kind.eq(OwnerKind::User))
+            .filter(crate_owners::deleted.eq(false)),
+    ))
+    .get_result::<bool>(conn)
+    .await?;
+
+    if !is_owner {
+        return Err(bad_request(format!("you are not an owner of `{name}`")));
+    }
+
+    Ok(krate)
+}
@@ -1,9 +1,14 @@
 pub mod authors;
 pub mod dependencies;
+pub mod diff;
 pub mod docs;
 pub mod downloads;
+pub mod files;
 pub mod metadata;
+pub mod promote;
+pub mod provenance;
 pub mod readme;
+pub mod scan_findings;
 pub mod update;
 pub mod yank;
 
@@ -15,11 +20,15 @@ use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use http::request::Parts;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
 use utoipa::IntoParams;
 
-use crate::models::{Crate, Version};
+use crate::app::AppState;
+use crate::middleware::private_registry::check_crate_access;
+use crate::models::{Advisory, Crate, Version};
 use crate::schema::{crates, versions};
 use crate::util::errors::{AppResult, BoxedAppError, crate_not_found, custom, version_not_found};
+use crate::views::{EncodableAdvisory, EncodableVersion};
 
 #[derive(Deserialize, IntoParams)]
 #[into_params(parameter_in = Path)]
@@ -32,10 +41,13 @@ pub struct CrateVersionPath {
     pub version: String,
 }
 
-impl<S: Send + Sync> FromRequestParts<S> for CrateVersionPath {
+impl FromRequestParts<AppState> for CrateVersionPath {
     type Rejection = BoxedAppError;
 
-    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
         let Path(path) = Path::<CrateVersionPath>::from_request_parts(parts, state)
             .await
             .map_err(|err| custom(err.status(), err.body_text()))?;
@@ -51,6 +63,8 @@ impl<S: Send + Sync> FromRequestParts<S> for CrateVersionPath {
             return Err(crate_not_found(&path.name));
         }
 
+        check_crate_access(state, parts, &path.name).await?;
+
         Ok(path)
     }
 }
@@ -97,6 +111,28 @@ impl CrateVersionPath {
     }
 }
 
+/// Fills in the `advisories` field of the given versions.
+pub async fn add_advisories(
+    conn: &AsyncPgConnection,
+    versions: &mut [EncodableVersion],
+) -> QueryResult<()> {
+    let version_ids = versions.iter().map(|v| v.id).collect::<Vec<_>>();
+
+    let mut advisories = HashMap::<i32, Vec<EncodableAdvisory>>::new();
+    for (version_id, advisory) in Advisory::for_versions(conn, &version_ids).await? {
+        advisories
+            .entry(version_id)
+            .or_default()
+            .push(advisory.into());
+    }
+
+    for version in versions {
+        version.advisories = advisories.remove(&version.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
 fn deserialize_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
     let s = String::deserialize(deserializer)?;
     let _ = semver::Version::parse(&s).map_err(Error::custom)?;
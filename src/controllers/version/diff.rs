@@ -0,0 +1,236 @@
+//! Comparison of the source files of two versions of a crate.
+//!
+//! The comparison is based on the manifests of the zip archives of both
+//! versions, so the list of changed files can be built from the recorded
+//! sha256 checksums alone. File contents are only fetched for the textual
+//! diff of a single file, and only if the checksums differ.
+
+use crate::app::AppState;
+use crate::controllers::version::CrateVersionPath;
+use crate::controllers::version::files::{load_manifest, read_file};
+use crate::tasks::spawn_blocking;
+use crate::util::errors::{AppResult, custom};
+use axum::Json;
+use axum::extract::{FromRequestParts, Query};
+use crates_io_crate_zip::FileEntry;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Upper bound for the time spent on computing the diff of a single file.
+/// If it is exceeded, a less minimal diff is returned instead.
+const DIFF_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Deserialize, FromRequestParts, utoipa::IntoParams)]
+#[from_request(via(Query))]
+#[into_params(parameter_in = Query)]
+pub struct DiffQueryParams {
+    /// The version to compare against.
+    #[param(example = "0.9.0")]
+    #[serde(deserialize_with = "super::deserialize_version")]
+    base: String,
+
+    /// Path of a file to include a unified diff for.
+    #[param(example = "src/lib.rs")]
+    path: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct DiffResponse {
+    /// The files that differ between the two versions, sorted by path.
+    pub files: Vec<FileChange>,
+
+    /// Unified diff of the file that was selected with the `path` query
+    /// parameter, or `null` if no file was selected.
+    ///
+    /// The diff is empty if the file is identical in both versions.
+    #[schema(
+        example = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-fn foo() {}\n+fn bar() {}\n"
+    )]
+    pub diff: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct FileChange {
+    /// Path of the file, relative to the root of the crate.
+    #[schema(example = "src/lib.rs")]
+    pub path: String,
+
+    /// How the file has changed between the two versions.
+    pub status: ChangeStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeStatus {
+    /// The file only exists in the newer version.
+    Added,
+    /// The file only exists in the base version.
+    Removed,
+    /// The file exists in both versions, but with different contents.
+    Modified,
+}
+
+/// Compare the source files of a crate version against another version.
+///
+/// Both versions must have been published after source archives were
+/// introduced, and the archives must have been processed, which happens
+/// shortly after publishing.
+#[utoipa::path(
+    get,
+    path = "/api/v1/crates/{name}/{version}/diff",
+    params(CrateVersionPath, DiffQueryParams),
+    tag = "versions",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(DiffResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn get_version_diff(
+    app: AppState,
+    path: CrateVersionPath,
+    params: DiffQueryParams,
+) -> AppResult<Json<DiffResponse>> {
+    let conn = app.db_read().await?;
+    let (version, krate) = path.load_version_and_crate(&conn).await?;
+
+    let base_path = CrateVersionPath {
+        name: krate.name.clone(),
+        version: params.base,
+    };
+    let base_version = base_path.load_version(&conn).await?;
+
+    let base_manifest = load_manifest(&app, &krate.name, &base_version).await?;
+    let manifest = load_manifest(&app, &krate.name, &version).await?;
+
+    let mut entries: BTreeMap<String, (Option<FileEntry>, Option<FileEntry>)> = BTreeMap::new();
+    for entry in base_manifest.files {
+        let path = entry.path.clone();
+        entries.entry(path).or_default().0 = Some(entry);
+    }
+    for entry in manifest.files {
+        let path = entry.path.clone();
+        entries.entry(path).or_default().1 = Some(entry);
+    }
+
+    let files = entries
+        .iter()
+        .filter_map(|(path, entries)| {
+            let status = match entries {
+                (None, Some(_)) => ChangeStatus::Added,
+                (Some(_), None) => ChangeStatus::Removed,
+                (Some(old), Some(new)) if old.sha256 != new.sha256 => ChangeStatus::Modified,
+                _ => return None,
+            };
+
+            let path = path.clone();
+            Some(FileChange { path, status })
+        })
+        .collect();
+
+    let Some(file_path) = params.path else {
+        return Ok(Json(DiffResponse { files, diff: None }));
+    };
+
+    let (old, new) = entries.remove(&file_path).ok_or_else(|| {
+        let detail = format!(
+            "neither version `{}` nor `{}` of crate `{}` contains a file `{file_path}`",
+            base_version.num, version.num, krate.name
+        );
+        custom(StatusCode::NOT_FOUND, detail)
+    })?;
+
+    if let (Some(old), Some(new)) = (&old, &new)
+        && old.sha256 == new.sha256
+    {
+        let diff = Some(String::new());
+        return Ok(Json(DiffResponse { files, diff }));
+    }
+
+    let old = match old {
+        Some(entry) => Some(read_file(&app, &krate.name, &base_version.num, entry).await?),
+        None => None,
+    };
+    let new = match new {
+        Some(entry) => Some(read_file(&app, &krate.name, &version.num, entry).await?),
+        None => None,
+    };
+
+    let diff = spawn_blocking(move || unified_diff(&file_path, old, new)).await?;
+
+    Ok(Json(DiffResponse {
+        files,
+        diff: Some(diff),
+    }))
+}
+
+/// Builds a unified diff in the format of `git diff`, where a missing file is
+/// represented as `/dev/null`.
+fn unified_diff(path: &str, old: Option<Vec<u8>>, new: Option<Vec<u8>>) -> String {
+    let old_header = match old {
+        Some(_) => format!("a/{path}"),
+        None => "/dev/null".to_string(),
+    };
+    let new_header = match new {
+        Some(_) => format!("b/{path}"),
+        None => "/dev/null".to_string(),
+    };
+
+    let old = old.unwrap_or_default();
+    let new = new.unwrap_or_default();
+
+    let (Ok(old), Ok(new)) = (str::from_utf8(&old), str::from_utf8(&new)) else {
+        return format!("Binary files {old_header} and {new_header} differ\n");
+    };
+
+    TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(old, new)
+        .unified_diff()
+        .header(&old_header, &new_header)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = b"fn foo() {}\nfn bar() {}\n".to_vec();
+        let new = b"fn foo() {}\nfn baz() {}\n".to_vec();
+        assert_snapshot!(unified_diff("src/lib.rs", Some(old.clone()), Some(new)), @r#"
+        --- a/src/lib.rs
+        +++ b/src/lib.rs
+        @@ -1,2 +1,2 @@
+         fn foo() {}
+        -fn bar() {}
+        +fn baz() {}
+        "#);
+        assert_snapshot!(unified_diff("src/lib.rs", None, Some(old.clone())), @r#"
+        --- /dev/null
+        +++ b/src/lib.rs
+        @@ -0,0 +1,2 @@
+        +fn foo() {}
+        +fn bar() {}
+        "#);
+        assert_snapshot!(unified_diff("src/lib.rs", Some(old), None), @r#"
+        --- a/src/lib.rs
+        +++ /dev/null
+        @@ -1,2 +0,0 @@
+        -fn foo() {}
+        -fn bar() {}
+        "#);
+    }
+
+    #[test]
+    fn test_unified_diff_binary() {
+        let old = b"\xff\xfe".to_vec();
+        let new = b"fn foo() {}\n".to_vec();
+        assert_snapshot!(unified_diff("data.bin", Some(old), Some(new)), @"Binary files a/data.bin and b/data.bin differ");
+    }
+}
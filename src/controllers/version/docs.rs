@@ -4,6 +4,7 @@ use super::CrateVersionPath;
 use crate::app::AppState;
 use crate::auth::AuthCheck;
 use crate::controllers::helpers::authorization::Rights;
+use crate::models::token::EndpointScope;
 use crate::util::errors::{AppResult, custom, server_error};
 use crate::worker::jobs;
 use crates_io_worker::BackgroundJob as _;
@@ -17,6 +18,7 @@ use tracing::error;
     path = "/api/v1/crates/{name}/{version}/rebuild_docs",
     params(CrateVersionPath),
     security(
+        ("api_token" = []),
         ("cookie" = []),
     ),
     tag = "versions",
@@ -33,7 +35,15 @@ pub async fn rebuild_version_docs(
     req: Parts,
 ) -> AppResult<StatusCode> {
     let mut conn = app.db_write().await?;
-    let auth = AuthCheck::only_cookie().check(&req, &mut conn).await?;
+    let auth = AuthCheck::default()
+        .with_endpoint_scope(EndpointScope::RebuildDocs)
+        .for_crate(&path.name)
+        .check(&req, &mut conn)
+        .await?;
+
+    // This endpoint used to be cookie-only, so legacy tokens without any
+    // scopes must not gain access to it.
+    auth.reject_legacy_tokens()?;
 
     // validate if version & crate exist
     let (_, krate) = path.load_version_and_crate(&conn).await?;
@@ -41,8 +51,7 @@ pub async fn rebuild_version_docs(
     // Check that the user is an owner of the crate, or a team member (= publish rights)
     let user = auth.user();
     let owners = krate.owners(&conn).await?;
-    let encryption = &app.config.token_encryption;
-    if Rights::get(user, &*app.github, &owners, encryption).await? < Rights::Publish {
+    if Rights::get(user, &app, &owners).await? < Rights::Publish {
         return Err(custom(
             StatusCode::FORBIDDEN,
             "user doesn't have permission to trigger a docs rebuild",
@@ -7,10 +7,11 @@ use crate::app::AppState;
 use crate::models::VersionDownload;
 use crate::schema::*;
 use crate::storage::StorageKey;
-use crate::util::errors::AppResult;
+use crate::util::errors::{AppResult, internal};
 use crate::util::{RequestUtils, redirect};
 use crate::views::EncodableVersionDownload;
 use axum::Json;
+use axum::body::Body;
 use axum::extract::{FromRequestParts, Query};
 use axum::response::{IntoResponse, Response};
 use axum_extra::json;
@@ -30,7 +31,9 @@ pub struct UrlResponse {
 
 /// Download a crate version.
 ///
-/// This returns a URL to the location where the crate is stored.
+/// This returns a URL to the location where the crate is stored. In private
+/// registry mode, the crate file is returned directly instead, if the user is
+/// allowed to read the crate.
 #[utoipa::path(
     get,
     path = "/api/v1/crates/{name}/{version}/download",
@@ -38,7 +41,7 @@ pub struct UrlResponse {
     tag = "versions",
     responses(
         (status = 302, description = "Successful Response (default)", headers(("location" = String, description = "The URL to the crate file."))),
-        (status = 200, description = "Successful Response (for `content-type: application/json`)", body = inline(UrlResponse)),
+        (status = 200, description = "Successful Response (for `content-type: application/json`, or the crate file in private registry mode)", body = inline(UrlResponse)),
         (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
         (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
     ),
@@ -48,6 +51,10 @@ pub async fn download_version(
     path: CrateVersionPath,
     req: Parts,
 ) -> AppResult<Response> {
+    if app.config.private_registry {
+        return serve_crate_file(&app, &path).await;
+    }
+
     let wants_json = req.wants_json();
     let key = StorageKey::for_crate_file(&path.name, &path.version);
     let redirect_url = app.storage.location(&key);
@@ -65,6 +72,27 @@ pub async fn download_version(
         .into_response())
 }
 
+/// Streams the crate file through the application, since the CDN would serve
+/// it to anyone who knows the URL.
+async fn serve_crate_file(app: &AppState, path: &CrateVersionPath) -> AppResult<Response> {
+    let conn = app.db_read().await?;
+    let (version, krate) = path.load_version_and_crate(&conn).await?;
+
+    let key = StorageKey::for_crate_file(&krate.name, &version.num);
+    let content_type = key.content_type().unwrap_or("application/octet-stream");
+    let stream = app
+        .storage
+        .download_stream(&key)
+        .await
+        .map_err(|e| internal(format!("failed to download crate: {e}")))?;
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type)],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
 #[derive(Debug, Deserialize, FromRequestParts, utoipa::IntoParams)]
 #[from_request(via(Query))]
 #[into_params(parameter_in = Query)]
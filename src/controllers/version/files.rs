@@ -0,0 +1,183 @@
+//! Access to the individual source files of a published crate version.
+//!
+//! The files are served from the zip archive that the [`BuildCrateZip`]
+//! background job creates after a version is published. Instead of
+//! downloading the whole archive, only the compressed payload of the
+//! requested file is fetched from storage, using the offsets recorded in the
+//! archive manifest, and its contents are verified against the sha256 from
+//! the manifest before they are returned.
+//!
+//! [`BuildCrateZip`]: crate::worker::jobs::BuildCrateZip
+
+use crate::app::AppState;
+use crate::controllers::version::CrateVersionPath;
+use crate::models::Version;
+use crate::storage::StorageKey;
+use crate::tasks::spawn_blocking;
+use crate::util::errors::{AppResult, custom, internal};
+use axum::Json;
+use axum::extract::Path;
+use axum::response::{IntoResponse, Response};
+use crates_io_crate_zip::{FileEntry, Manifest};
+use http::{StatusCode, header};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::IntoParams;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ListResponse {
+    /// The files of the crate version, sorted alphabetically by path.
+    pub files: Vec<EncodableFile>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct EncodableFile {
+    /// Path of the file, relative to the root of the crate.
+    #[schema(example = "src/lib.rs")]
+    pub path: String,
+
+    /// Size of the file in bytes.
+    #[schema(example = 1024)]
+    pub size: u64,
+
+    /// Lowercase hex SHA256 checksum of the file contents.
+    #[schema(example = "a2c8b4e5f9d1e0c3b7a6f4d2e8c1b9a0f3e5d7c9b1a3f5e7d9c1b3a5f7e9d1c3")]
+    pub sha256: String,
+}
+
+/// List the files of a crate version.
+///
+/// The file list is only available once the source archive of the version
+/// has been processed, which happens shortly after it was published.
+#[utoipa::path(
+    get,
+    path = "/api/v1/crates/{name}/{version}/files",
+    params(CrateVersionPath),
+    tag = "versions",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(ListResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn list_version_files(
+    app: AppState,
+    path: CrateVersionPath,
+) -> AppResult<Json<ListResponse>> {
+    let conn = app.db_read().await?;
+    let (version, krate) = path.load_version_and_crate(&conn).await?;
+    let manifest = load_manifest(&app, &krate.name, &version).await?;
+
+    let files = manifest
+        .files
+        .into_iter()
+        .map(|file| EncodableFile {
+            path: file.path,
+            size: file.uncompressed_size,
+            sha256: file.sha256,
+        })
+        .collect();
+
+    Ok(Json(ListResponse { files }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct FilePath {
+    /// Path of the file, relative to the root of the crate
+    #[param(example = "src/lib.rs")]
+    pub path: String,
+}
+
+/// Get the contents of a file of a crate version.
+///
+/// The contents are returned as `application/octet-stream`, and are
+/// guaranteed to match the SHA256 checksum returned by the file list.
+#[utoipa::path(
+    get,
+    path = "/api/v1/crates/{name}/{version}/files/{*path}",
+    params(CrateVersionPath, FilePath),
+    tag = "versions",
+    responses(
+        (status = 200, description = "Successful Response", content_type = "application/octet-stream", body = [u8]),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn get_version_file(
+    app: AppState,
+    path: CrateVersionPath,
+    Path(FilePath { path: file_path }): Path<FilePath>,
+) -> AppResult<Response> {
+    let conn = app.db_read().await?;
+    let (version, krate) = path.load_version_and_crate(&conn).await?;
+    let manifest = load_manifest(&app, &krate.name, &version).await?;
+
+    let entry = manifest
+        .files
+        .into_iter()
+        .find(|file| file.path == file_path)
+        .ok_or_else(|| {
+            let detail = format!(
+                "crate `{}` version `{}` does not contain a file `{file_path}`",
+                krate.name, version.num
+            );
+            custom(StatusCode::NOT_FOUND, detail)
+        })?;
+
+    let contents = read_file(&app, &krate.name, &version.num, entry).await?;
+
+    let headers = [(header::CONTENT_TYPE, "application/octet-stream")];
+    Ok((headers, contents).into_response())
+}
+
+/// Loads the manifest of the zip archive of a crate version from storage,
+/// and verifies it against the checksum that is stored in the database.
+pub(super) async fn load_manifest(
+    app: &AppState,
+    name: &str,
+    version: &Version,
+) -> AppResult<Manifest> {
+    let Some(expected_sha256) = &version.zip_json_sha256 else {
+        let detail = format!(
+            "the files of crate `{name}` version `{}` are not available yet",
+            version.num
+        );
+        return Err(custom(StatusCode::NOT_FOUND, detail));
+    };
+
+    let key = StorageKey::for_crate_zip_manifest(name, &version.num);
+    let bytes = app
+        .storage
+        .download(&key)
+        .await
+        .map_err(|error| internal(format!("Failed to download zip manifest: {error}")))?;
+
+    if Sha256::digest(&bytes).as_slice() != expected_sha256.as_slice() {
+        return Err(internal("Checksum mismatch for zip manifest"));
+    }
+
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Fetches the compressed payload of a single file from the zip archive of a
+/// crate version, and returns its verified contents.
+pub(super) async fn read_file(
+    app: &AppState,
+    name: &str,
+    version: &str,
+    entry: FileEntry,
+) -> AppResult<Vec<u8>> {
+    let key = StorageKey::for_crate_zip(name, version);
+    let payload = app
+        .storage
+        .download_range(&key, entry.range())
+        .await
+        .map_err(|error| internal(format!("Failed to download `{}`: {error}", entry.path)))?;
+
+    let contents = spawn_blocking(move || entry.decode(&payload))
+        .await?
+        .map_err(internal)?;
+
+    Ok(contents)
+}
@@ -6,12 +6,15 @@
 
 use crate::app::AppState;
 use crate::models::VersionOwnerAction;
+use crate::schema::version_compatibility_reports;
 use crate::util::errors::AppResult;
 use crate::views::EncodableVersion;
 use axum::Json;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
 use serde::Serialize;
 
-use super::CrateVersionPath;
+use super::{CrateVersionPath, add_advisories};
 
 /// Response returned when getting crate version metadata.
 #[derive(Debug, Serialize, utoipa::ToSchema)]
@@ -35,13 +38,23 @@ pub async fn find_version(
     state: AppState,
     path: CrateVersionPath,
 ) -> AppResult<Json<VersionGetResponse>> {
-    let conn = state.db_read().await?;
+    let mut conn = state.db_read().await?;
     let (version, krate) = path.load_version_and_crate(&conn).await?;
     let (actions, published_by) = tokio::try_join!(
         VersionOwnerAction::by_version(&conn, &version),
         version.published_by(&conn),
     )?;
 
-    let version = EncodableVersion::from(version, &krate.name, published_by, actions);
+    let compatibility_report = version_compatibility_reports::table
+        .find(version.id)
+        .select(version_compatibility_reports::report)
+        .first(&mut conn)
+        .await
+        .optional()?;
+
+    let mut version = EncodableVersion::from(version, &krate.name, published_by, actions);
+    version.compatibility_report = compatibility_report;
+    add_advisories(&conn, std::slice::from_mut(&mut version)).await?;
+
     Ok(Json(VersionGetResponse { version }))
 }
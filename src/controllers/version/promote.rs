@@ -0,0 +1,194 @@
+//! Endpoint for promoting staged versions of crates
+
+use super::CrateVersionPath;
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::controllers::helpers::OkResponse;
+use crate::controllers::helpers::authorization::Rights;
+use crate::controllers::krate::publish::enqueue_release_jobs;
+use crate::models::token::EndpointScope;
+use crate::models::{NewVersionOwnerAction, User, VersionAction};
+use crate::schema::{users, version_owner_actions, versions};
+use crate::util::errors::{AppResult, BoxedAppError, bad_request, custom};
+use crate::worker::jobs::UpdateDefaultVersion;
+use chrono::{DateTime, Utc};
+use crates_io_worker::BackgroundJob;
+use diesel::dsl::{exists, now};
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use http::StatusCode;
+use http::request::Parts;
+use tracing::info;
+
+/// Promote a staged crate version.
+///
+/// Staged versions are hidden from the index, the version listings and the
+/// RSS feeds until they are promoted. If the crate requires promotion
+/// reviews, the version has to be promoted by an owner other than the one
+/// who published it. Versions without a publisher, e.g. from Trusted
+/// Publishing, have to be approved by two different owners instead: the
+/// first request records the approval and responds with `202 Accepted`,
+/// the request of a second owner promotes the version.
+#[utoipa::path(
+    put,
+    path = "/api/v1/crates/{name}/{version}/promote",
+    params(CrateVersionPath),
+    security(
+        ("api_token" = []),
+        ("cookie" = []),
+    ),
+    tag = "versions",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(OkResponse)),
+        (status = 202, description = "The promotion was approved, and needs the approval of a second owner", body = inline(OkResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn promote_version(
+    app: AppState,
+    path: CrateVersionPath,
+    req: Parts,
+) -> AppResult<(StatusCode, OkResponse)> {
+    let mut conn = app.db_write().await?;
+    let auth = AuthCheck::default()
+        .with_endpoint_scope(EndpointScope::PublishUpdate)
+        .for_crate(&path.name)
+        .check(&req, &mut conn)
+        .await?;
+
+    let (version, krate) = path.load_version_and_crate(&conn).await?;
+
+    let user = auth.user();
+    let owners = krate.owners(&conn).await?;
+    if Rights::get(user, &app, &owners).await? < Rights::Publish {
+        return Err(custom(
+            StatusCode::FORBIDDEN,
+            "must already be an owner to promote a staged version",
+        ));
+    }
+
+    if version.staged_at.is_none() {
+        return Err(bad_request(format!(
+            "version `{}` of crate `{}` is not staged",
+            version.num, krate.name
+        )));
+    }
+
+    if krate.require_promotion_review
+        && let Some(publisher) = version.published_by
+        && publisher == user.id
+    {
+        return Err(custom(
+            StatusCode::FORBIDDEN,
+            "this crate requires staged versions to be promoted by a different owner than the one who published them",
+        ));
+    }
+
+    conn.transaction(async |conn| {
+        // Lock the version, so that concurrent approvals and promotions are
+        // handled one after another
+        let staged_at = versions::table
+            .find(version.id)
+            .select(versions::staged_at)
+            .for_update()
+            .first::<Option<DateTime<Utc>>>(conn)
+            .await?;
+
+        // The version has been promoted by a concurrent request in the meantime
+        if staged_at.is_none() {
+            return Ok(StatusCode::OK);
+        }
+
+        if krate.require_promotion_review && version.published_by.is_none() {
+            let approver_ids = version_owner_actions::table
+                .filter(version_owner_actions::version_id.eq(version.id))
+                .filter(version_owner_actions::action.eq(VersionAction::ApprovePromotion))
+                .filter(version_owner_actions::user_id.ne(user.id))
+                .select(version_owner_actions::user_id)
+                .distinct()
+                .load::<i32>(conn)
+                .await?;
+
+            // Approvals of users who are no longer owners of the crate don't
+            // count towards the promotion
+            let mut approved_by_other_owner = false;
+            let approvers = User::query()
+                .filter(users::id.eq_any(approver_ids))
+                .load(conn)
+                .await?;
+
+            for approver in &approvers {
+                let rights = Rights::get(approver, &app, &owners).await?;
+
+                if rights >= Rights::Publish {
+                    approved_by_other_owner = true;
+                    break;
+                }
+            }
+
+            if !approved_by_other_owner {
+                let already_approved = version_owner_actions::table
+                    .filter(version_owner_actions::version_id.eq(version.id))
+                    .filter(version_owner_actions::action.eq(VersionAction::ApprovePromotion))
+                    .filter(version_owner_actions::user_id.eq(user.id));
+
+                let already_approved = diesel::select(exists(already_approved))
+                    .get_result::<bool>(conn)
+                    .await?;
+
+                if !already_approved {
+                    info!(
+                        "User {} approved the promotion of staged version {}@{}",
+                        user.gh_login, krate.name, version.num
+                    );
+
+                    NewVersionOwnerAction::builder()
+                        .version_id(version.id)
+                        .user_id(user.id)
+                        .maybe_api_token_id(auth.api_token_id())
+                        .action(VersionAction::ApprovePromotion)
+                        .build()
+                        .insert(conn)
+                        .await?;
+                }
+
+                return Ok(StatusCode::ACCEPTED);
+            }
+        }
+
+        // Bump `updated_at` so that delta database dumps pick up the version
+        diesel::update(versions::table.find(version.id))
+            .set((
+                versions::staged_at.eq(None::<DateTime<Utc>>),
+                versions::updated_at.eq(now),
+            ))
+            .execute(conn)
+            .await?;
+
+        info!(
+            "User {} promoted staged version {}@{}",
+            user.gh_login, krate.name, version.num
+        );
+
+        NewVersionOwnerAction::builder()
+            .version_id(version.id)
+            .user_id(user.id)
+            .maybe_api_token_id(auth.api_token_id())
+            .action(VersionAction::Promote)
+            .build()
+            .insert(conn)
+            .await?;
+
+        let update_default_version_job = UpdateDefaultVersion::new(krate.id);
+
+        tokio::try_join!(
+            enqueue_release_jobs(&app, &krate, &version, Some(&user.gh_login), conn),
+            update_default_version_job.enqueue(conn),
+        )?;
+
+        Ok::<_, BoxedAppError>(StatusCode::OK)
+    })
+    .await
+    .map(|status| (status, OkResponse::new()))
+}
@@ -0,0 +1,376 @@
+//! Signed provenance attestations of versions published via "Trusted
+//! Publishing".
+//!
+//! See [`crates_io_trustpub::provenance`] for the format of the attestations.
+
+use crate::app::AppState;
+use crate::controllers::version::CrateVersionPath;
+use crate::models::TrustpubData;
+use crate::schema::trustpub_provenance;
+use crate::util::errors::{AppResult, not_found};
+use crate::views::trustpub::{Provenance as EncodableProvenance, ProvenanceKey};
+use axum::Json;
+use base64::{Engine, engine::general_purpose};
+use crates_io_database::models::trustpub::Provenance;
+use crates_io_trustpub::github::GITHUB_ISSUER_URL;
+use crates_io_trustpub::gitlab::GITLAB_ISSUER_URL;
+use crates_io_trustpub::provenance::{
+    BUILD_TYPE, BuildDefinition, BuildMetadata, Builder, ExternalParameters, ResourceDescriptor,
+    RunDetails, Statement,
+};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct GetResponse {
+    pub provenance: EncodableProvenance,
+}
+
+/// Get the signed provenance attestation of a crate version.
+///
+/// Attestations are only available for versions that were published via
+/// "Trusted Publishing". They bind the SHA256 checksum of the crate file to
+/// the CI workflow that published it, and can be verified offline using the
+/// key returned by `/api/v1/trusted_publishing/provenance_key`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/crates/{name}/{version}/provenance",
+    params(CrateVersionPath),
+    tag = "versions",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(GetResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn get_version_provenance(
+    app: AppState,
+    path: CrateVersionPath,
+) -> AppResult<Json<GetResponse>> {
+    let mut conn = app.db_read().await?;
+    let version = path.load_version(&conn).await?;
+
+    let provenance = Provenance::query()
+        .filter(trustpub_provenance::version_id.eq(version.id))
+        .first(&mut conn)
+        .await
+        .optional()?
+        .ok_or_else(not_found)?;
+
+    let provenance = EncodableProvenance {
+        key_id: provenance.key_id,
+        created_at: provenance.created_at,
+        envelope: provenance.envelope,
+    };
+
+    Ok(Json(GetResponse { provenance }))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct GetKeyResponse {
+    pub key: ProvenanceKey,
+}
+
+/// Get the public key that is used to sign provenance attestations.
+#[utoipa::path(
+    get,
+    path = "/api/v1/trusted_publishing/provenance_key",
+    tag = "trusted_publishing",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(GetKeyResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn get_provenance_key(app: AppState) -> AppResult<Json<GetKeyResponse>> {
+    let signer = app
+        .config
+        .provenance_signer
+        .as_ref()
+        .ok_or_else(not_found)?;
+
+    let key = ProvenanceKey {
+        key_id: signer.key_id().to_string(),
+        algorithm: "ed25519",
+        public_key: general_purpose::STANDARD.encode(signer.public_key()),
+    };
+
+    Ok(Json(GetKeyResponse { key }))
+}
+
+/// Builds the provenance [`Statement`] for a crate file that was published
+/// with a Trusted Publishing token containing the given `trustpub_data`.
+pub fn build_statement(
+    krate: &str,
+    version: &str,
+    tar_sha256: &[u8],
+    trustpub_data: &TrustpubData,
+) -> Statement {
+    let (external_parameters, source_uri, sha, invocation_id) = match trustpub_data {
+        TrustpubData::GitHub {
+            repository,
+            run_id,
+            sha,
+            workflow_ref,
+        } => {
+            // e.g. `octo-org/octo-repo/.github/workflows/release.yml@refs/heads/main`
+            let (workflow, git_ref) = workflow_ref
+                .as_deref()
+                .and_then(|workflow_ref| workflow_ref.split_once('@'))
+                .map(|(path, git_ref)| {
+                    let prefix = format!("{repository}/");
+                    let path = path.strip_prefix(&prefix).unwrap_or(path);
+                    (Some(path.to_string()), Some(git_ref.to_string()))
+                })
+                .unwrap_or_default();
+
+            let external_parameters = ExternalParameters {
+                issuer: GITHUB_ISSUER_URL.into(),
+                repository: repository.clone(),
+                workflow,
+                git_ref,
+            };
+
+            let source_uri = format!("git+https://github.com/{repository}");
+            let invocation_id = format!("https://github.com/{repository}/actions/runs/{run_id}");
+            (external_parameters, Some(source_uri), sha, invocation_id)
+        }
+        TrustpubData::GitLab {
+            project_path,
+            job_id,
+            sha,
+            ci_config_ref_uri,
+        } => {
+            // e.g. `gitlab.com/rust-lang/cargo//.gitlab-ci.yml@refs/heads/main`
+            let (workflow, git_ref) = ci_config_ref_uri
+                .as_deref()
+                .and_then(|ref_uri| ref_uri.split_once('@'))
+                .and_then(|(uri, git_ref)| Some((uri.split_once("//")?.1, git_ref)))
+                .map(|(path, git_ref)| (Some(path.to_string()), Some(git_ref.to_string())))
+                .unwrap_or_default();
+
+            let external_parameters = ExternalParameters {
+                issuer: GITLAB_ISSUER_URL.into(),
+                repository: project_path.clone(),
+                workflow,
+                git_ref,
+            };
+
+            let source_uri = format!("git+https://gitlab.com/{project_path}");
+            let invocation_id = format!("https://gitlab.com/{project_path}/-/jobs/{job_id}");
+            (external_parameters, Some(source_uri), sha, invocation_id)
+        }
+        TrustpubData::Oidc {
+            issuer,
+            repository,
+            run_id,
+            sha,
+        } => {
+            let external_parameters = ExternalParameters {
+                issuer: issuer.clone(),
+                repository: repository.clone(),
+                workflow: None,
+                git_ref: None,
+            };
+
+            (external_parameters, None, sha, run_id.clone())
+        }
+    };
+
+    let git_ref = external_parameters.git_ref.as_deref();
+    let uri = source_uri.map(|uri| match git_ref {
+        Some(git_ref) => format!("{uri}@{git_ref}"),
+        None => uri,
+    });
+
+    // Without a source URI the repository name is used to identify the source
+    let name = uri
+        .is_none()
+        .then(|| external_parameters.repository.clone());
+
+    let source = ResourceDescriptor {
+        uri,
+        name,
+        digest: BTreeMap::from([("gitCommit".into(), sha.clone())]),
+    };
+
+    let builder = Builder {
+        id: external_parameters.issuer.clone(),
+    };
+
+    let predicate = crates_io_trustpub::provenance::Provenance {
+        build_definition: BuildDefinition {
+            build_type: BUILD_TYPE.into(),
+            external_parameters,
+            resolved_dependencies: vec![source],
+        },
+        run_details: RunDetails {
+            builder,
+            metadata: BuildMetadata { invocation_id },
+        },
+    };
+
+    Statement::new(&format!("{krate}-{version}.crate"), tar_sha256, predicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn test_build_statement_github() {
+        let trustpub_data = TrustpubData::GitHub {
+            repository: "rust-lang/foo-rs".into(),
+            run_id: "42".into(),
+            sha: "9f3182061f1e2cca4702c368cbc039b7dc9d4485".into(),
+            workflow_ref: Some(
+                "rust-lang/foo-rs/.github/workflows/publish.yml@refs/tags/v1.0.0".into(),
+            ),
+        };
+
+        let statement = build_statement("foo", "1.0.0", &[0xab; 32], &trustpub_data);
+        assert_json_snapshot!(statement.predicate, @r#"
+        {
+          "buildDefinition": {
+            "buildType": "https://crates.io/trusted-publishing/provenance/v1",
+            "externalParameters": {
+              "issuer": "https://token.actions.githubusercontent.com",
+              "repository": "rust-lang/foo-rs",
+              "workflow": ".github/workflows/publish.yml",
+              "ref": "refs/tags/v1.0.0"
+            },
+            "resolvedDependencies": [
+              {
+                "uri": "git+https://github.com/rust-lang/foo-rs@refs/tags/v1.0.0",
+                "digest": {
+                  "gitCommit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485"
+                }
+              }
+            ]
+          },
+          "runDetails": {
+            "builder": {
+              "id": "https://token.actions.githubusercontent.com"
+            },
+            "metadata": {
+              "invocationId": "https://github.com/rust-lang/foo-rs/actions/runs/42"
+            }
+          }
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_build_statement_gitlab() {
+        let trustpub_data = TrustpubData::GitLab {
+            project_path: "rust-lang/foo-rs".into(),
+            job_id: "42".into(),
+            sha: "9f3182061f1e2cca4702c368cbc039b7dc9d4485".into(),
+            ci_config_ref_uri: Some(
+                "gitlab.com/rust-lang/foo-rs//.gitlab-ci.yml@refs/heads/main".into(),
+            ),
+        };
+
+        let statement = build_statement("foo", "1.0.0", &[0xab; 32], &trustpub_data);
+        assert_json_snapshot!(statement.predicate, @r#"
+        {
+          "buildDefinition": {
+            "buildType": "https://crates.io/trusted-publishing/provenance/v1",
+            "externalParameters": {
+              "issuer": "https://gitlab.com",
+              "repository": "rust-lang/foo-rs",
+              "workflow": ".gitlab-ci.yml",
+              "ref": "refs/heads/main"
+            },
+            "resolvedDependencies": [
+              {
+                "uri": "git+https://gitlab.com/rust-lang/foo-rs@refs/heads/main",
+                "digest": {
+                  "gitCommit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485"
+                }
+              }
+            ]
+          },
+          "runDetails": {
+            "builder": {
+              "id": "https://gitlab.com"
+            },
+            "metadata": {
+              "invocationId": "https://gitlab.com/rust-lang/foo-rs/-/jobs/42"
+            }
+          }
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_build_statement_oidc() {
+        let trustpub_data = TrustpubData::Oidc {
+            issuer: "https://agent.buildkite.com".into(),
+            repository: "acme-inc/my-pipeline".into(),
+            run_id: "0184990a-477b-4fa8-9968-496074483cee".into(),
+            sha: "9f3182061f1e2cca4702c368cbc039b7dc9d4485".into(),
+        };
+
+        let statement = build_statement("foo", "1.0.0", &[0xab; 32], &trustpub_data);
+        assert_json_snapshot!(statement.predicate, @r#"
+        {
+          "buildDefinition": {
+            "buildType": "https://crates.io/trusted-publishing/provenance/v1",
+            "externalParameters": {
+              "issuer": "https://agent.buildkite.com",
+              "repository": "acme-inc/my-pipeline"
+            },
+            "resolvedDependencies": [
+              {
+                "name": "acme-inc/my-pipeline",
+                "digest": {
+                  "gitCommit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485"
+                }
+              }
+            ]
+          },
+          "runDetails": {
+            "builder": {
+              "id": "https://agent.buildkite.com"
+            },
+            "metadata": {
+              "invocationId": "0184990a-477b-4fa8-9968-496074483cee"
+            }
+          }
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_build_statement_without_workflow_ref() {
+        let trustpub_data = TrustpubData::GitHub {
+            repository: "rust-lang/foo-rs".into(),
+            run_id: "42".into(),
+            sha: "9f3182061f1e2cca4702c368cbc039b7dc9d4485".into(),
+            workflow_ref: None,
+        };
+
+        let statement = build_statement("foo", "1.0.0", &[0xab; 32], &trustpub_data);
+        assert_json_snapshot!(statement.predicate.build_definition, @r#"
+        {
+          "buildType": "https://crates.io/trusted-publishing/provenance/v1",
+          "externalParameters": {
+            "issuer": "https://token.actions.githubusercontent.com",
+            "repository": "rust-lang/foo-rs"
+          },
+          "resolvedDependencies": [
+            {
+              "uri": "git+https://github.com/rust-lang/foo-rs",
+              "digest": {
+                "gitCommit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485"
+              }
+            }
+          ]
+        }
+        "#);
+    }
+}
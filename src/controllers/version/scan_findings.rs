@@ -0,0 +1,111 @@
+//! Endpoint for the findings of the content scanner.
+//!
+//! The findings are produced by the [`AnalyzeCrateFile`] background job, and
+//! may point at leaked credentials, so they are only visible to the owners of
+//! the crate and to admins.
+//!
+//! [`AnalyzeCrateFile`]: crate::worker::jobs::AnalyzeCrateFile
+
+use super::CrateVersionPath;
+use crate::app::AppState;
+use crate::auth::AuthCheck;
+use crate::controllers::helpers::authorization::Rights;
+use crate::models::ScanFinding;
+use crate::schema::version_scan_findings;
+use crate::util::errors::{AppResult, custom};
+use axum::Json;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use http::StatusCode;
+use http::request::Parts;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ScanFindingsResponse {
+    /// The findings for the files of the crate version.
+    pub findings: Vec<EncodableScanFinding>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct EncodableScanFinding {
+    /// The kind of the finding: `secret`, `binary` or `build_script`.
+    #[schema(example = "secret")]
+    pub kind: String,
+
+    /// Path of the file, relative to the root of the crate.
+    #[schema(example = "src/lib.rs")]
+    pub path: String,
+
+    /// Line number of the finding, if it is specific to a single line.
+    #[schema(example = 42)]
+    pub line: Option<i32>,
+
+    /// Human-readable description of the finding.
+    #[schema(example = "possible crates.io API token")]
+    pub description: String,
+}
+
+impl From<ScanFinding> for EncodableScanFinding {
+    fn from(finding: ScanFinding) -> Self {
+        Self {
+            kind: finding.kind,
+            path: finding.path,
+            line: finding.line,
+            description: finding.description,
+        }
+    }
+}
+
+/// List the content scanner findings of a crate version.
+///
+/// Published files are scanned for embedded secrets, unexpected binaries
+/// and build scripts that access the network. Only owners of the crate can
+/// see the findings.
+#[utoipa::path(
+    get,
+    path = "/api/v1/crates/{name}/{version}/scan_findings",
+    params(CrateVersionPath),
+    security(
+        ("api_token" = []),
+        ("cookie" = []),
+    ),
+    tag = "versions",
+    responses(
+        (status = 200, description = "Successful Response", body = inline(ScanFindingsResponse)),
+        (status = "4XX", description = "Client Error", body = crate::util::errors::ApiErrorResponse<'_>),
+        (status = "5XX", description = "Server Error", body = crate::util::errors::ApiErrorResponse<'_>),
+    ),
+)]
+pub async fn list_version_scan_findings(
+    app: AppState,
+    path: CrateVersionPath,
+    req: Parts,
+) -> AppResult<Json<ScanFindingsResponse>> {
+    let mut conn = app.db_read_prefer_primary().await?;
+    let auth = AuthCheck::default()
+        .for_crate(&path.name)
+        .check(&req, &mut conn)
+        .await?;
+
+    let (version, krate) = path.load_version_and_crate(&conn).await?;
+
+    let user = auth.user();
+    let owners = krate.owners(&conn).await?;
+    if !user.is_admin && Rights::get(user, &app, &owners).await? < Rights::Publish {
+        return Err(custom(
+            StatusCode::FORBIDDEN,
+            "must be an owner to see the scan findings",
+        ));
+    }
+
+    let findings = ScanFinding::query()
+        .filter(version_scan_findings::version_id.eq(version.id))
+        .order(version_scan_findings::id)
+        .load(&mut conn)
+        .await?
+        .into_iter()
+        .map(EncodableScanFinding::from)
+        .collect();
+
+    Ok(Json(ScanFindingsResponse { findings }))
+}
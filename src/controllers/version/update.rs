@@ -3,12 +3,16 @@ use crate::app::AppState;
 use crate::auth::{AuthCheck, Authentication};
 use crate::controllers::helpers::authorization::Rights;
 use crate::models::token::EndpointScope;
-use crate::models::{Crate, NewVersionOwnerAction, Version, VersionAction, VersionOwnerAction};
+use crate::models::{
+    Crate, NewVersionOwnerAction, Version, VersionAction, VersionOwnerAction, WebhookEvent,
+};
 use crate::rate_limiter::LimitedAction;
 use crate::schema::versions;
 use crate::util::errors::{AppResult, bad_request, custom};
 use crate::views::EncodableVersion;
-use crate::worker::jobs::{SyncToGitIndex, SyncToSparseIndex, UpdateDefaultVersion};
+use crate::worker::jobs::{
+    DispatchWebhookEvent, SyncToGitIndex, SyncToSparseIndex, UpdateDefaultVersion,
+};
 use axum::Json;
 use crates_io_worker::BackgroundJob;
 use diesel::prelude::*;
@@ -61,7 +65,12 @@ pub async fn update_version(
     let mut conn = state.db_write().await?;
     let (mut version, krate) = path.load_version_and_crate(&conn).await?;
     validate_yank_update(&update_request.version, &version)?;
-    let auth = authenticate(&req, &mut conn, &krate.name).await?;
+    let auth = AuthCheck::default()
+        .with_endpoint_scope(EndpointScope::UpdateVersion)
+        .or_endpoint_scope(EndpointScope::Yank)
+        .for_crate(&krate.name)
+        .check(&req, &mut conn)
+        .await?;
 
     state
         .rate_limiter
@@ -130,8 +139,7 @@ pub async fn perform_version_yank_update(
 
     let yanked = yanked.unwrap_or(version.yanked);
 
-    let encryption = &state.config.token_encryption;
-    if Rights::get(user, &*state.github, &owners, encryption).await? < Rights::Publish {
+    if Rights::get(user, state, &owners).await? < Rights::Publish {
         if user.is_admin {
             let action = if yanked { "yanking" } else { "unyanking" };
             warn!(
@@ -194,11 +202,23 @@ pub async fn perform_version_yank_update(
 
     let sparse_index_job = SyncToSparseIndex::new(&krate.name);
     let update_default_version_job = UpdateDefaultVersion::new(krate.id);
+    let event = if yanked {
+        WebhookEvent::Yank
+    } else {
+        WebhookEvent::Unyank
+    };
+    let webhook_job = DispatchWebhookEvent::builder()
+        .crate_id(krate.id)
+        .event(event)
+        .version(&version.num)
+        .actor(&user.gh_login)
+        .build();
 
     tokio::try_join!(
         sync_git_index,
         sparse_index_job.enqueue(&*conn),
         update_default_version_job.enqueue(&*conn),
+        webhook_job.enqueue(&*conn),
     )?;
 
     Ok(())
@@ -1,9 +1,10 @@
 use crate::Env;
 use crate::config;
+use crate::models::NotificationCategory;
 use lettre::address::Envelope;
 use lettre::message::Mailbox;
 use lettre::message::MultiPart;
-use lettre::message::header::ContentType;
+use lettre::message::header::{ContentType, Header, HeaderName, HeaderValue};
 use lettre::transport::file::AsyncFileTransport;
 use lettre::transport::smtp::AsyncSmtpTransport;
 use lettre::transport::smtp::authentication::{Credentials, Mechanism};
@@ -13,6 +14,7 @@ use minijinja::Environment;
 use rand::distr::{Alphanumeric, SampleString};
 use serde::Serialize;
 use sha2::{Digest, Sha256};
+use std::error::Error;
 use std::sync::LazyLock;
 
 static EMAIL_ENV: LazyLock<Environment<'static>> = LazyLock::new(|| {
@@ -94,6 +96,9 @@ pub struct EmailMessage {
     pub subject: String,
     pub body_text: String,
     pub body_html: String,
+    /// The URL of the `List-Unsubscribe` header, if the recipient can
+    /// unsubscribe from this kind of email.
+    pub list_unsubscribe: Option<String>,
 }
 
 impl EmailMessage {
@@ -110,8 +115,93 @@ impl EmailMessage {
             subject,
             body_text,
             body_html,
+            list_unsubscribe: None,
         })
     }
+
+    /// Renders an email of a [`NotificationCategory`] that the recipient can
+    /// unsubscribe from.
+    ///
+    /// The footer of the email links to the unsubscribe page, and the
+    /// `List-Unsubscribe` headers allow mail clients to unsubscribe in one
+    /// click (RFC 8058).
+    pub fn from_template_with_unsubscribe(
+        template_name: &'static str,
+        context: impl Serialize,
+        unsubscribe: Unsubscribe,
+    ) -> Result<Self, minijinja::Error> {
+        let context = minijinja::context! {
+            unsubscribe_url => unsubscribe.page_url,
+            ..minijinja::Value::from_serialize(context)
+        };
+
+        let mut message = Self::from_template(template_name, context)?;
+        message.list_unsubscribe = Some(unsubscribe.one_click_url);
+        Ok(message)
+    }
+}
+
+/// The unsubscribe links of an email of a [`NotificationCategory`].
+#[derive(Debug, Clone)]
+pub struct Unsubscribe {
+    /// The page that is linked from the footer of the email.
+    page_url: String,
+    /// The endpoint that mail clients send one-click unsubscribe requests to.
+    one_click_url: String,
+}
+
+impl Unsubscribe {
+    pub fn new(domain: &str, token: &str, category: NotificationCategory) -> Self {
+        Self {
+            page_url: format!("https://{domain}/unsubscribe/{token}/{category}"),
+            one_click_url: format!("https://{domain}/api/v1/unsubscribe/{token}/{category}"),
+        }
+    }
+}
+
+/// The `List-Unsubscribe` header (RFC 2369).
+#[derive(Debug, Clone)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let url = s.trim().trim_start_matches('<').trim_end_matches('>');
+        Ok(Self(url.to_string()))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), format!("<{}>", self.0))
+    }
+}
+
+/// The `List-Unsubscribe-Post` header (RFC 8058), which signals that the
+/// `List-Unsubscribe` URL supports one-click unsubscribes.
+#[derive(Debug, Clone)]
+struct ListUnsubscribePost;
+
+impl ListUnsubscribePost {
+    const VALUE: &str = "List-Unsubscribe=One-Click";
+}
+
+impl Header for ListUnsubscribePost {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe-Post")
+    }
+
+    fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match s.trim() {
+            Self::VALUE => Ok(Self),
+            _ => Err(format!("unexpected `List-Unsubscribe-Post` value `{s}`").into()),
+        }
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), Self::VALUE.to_string())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -213,15 +303,22 @@ impl Emails {
         subject: String,
         body_text: String,
         body_html: String,
+        list_unsubscribe: Option<String>,
     ) -> Result<Message, EmailError> {
         let from = Mailbox::new(Some(self.domain.clone()), self.from.clone());
 
-        let builder = Message::builder()
+        let mut builder = Message::builder()
             .message_id(Some(message_id))
             .to(recipient.parse()?)
             .from(from)
             .subject(subject);
 
+        if let Some(url) = list_unsubscribe {
+            builder = builder
+                .header(ListUnsubscribe(url))
+                .header(ListUnsubscribePost);
+        }
+
         let message = if self.html_emails_enabled {
             builder.multipart(MultiPart::alternative_plain_html(body_text, body_html))?
         } else {
@@ -254,6 +351,7 @@ impl Emails {
             email.subject,
             email.body_text,
             email.body_html,
+            email.list_unsubscribe,
         )?;
 
         match self.backend.send(message).await {
@@ -469,6 +567,7 @@ mod tests {
             subject: "test".into(),
             body_text: "test".into(),
             body_html: "<p>test</p>".into(),
+            list_unsubscribe: None,
         };
         assert_err!(emails.send(address, email).await);
     }
@@ -483,6 +582,7 @@ mod tests {
             subject: "test".into(),
             body_text: "test".into(),
             body_html: "<p>test</p>".into(),
+            list_unsubscribe: None,
         };
         assert_ok!(emails.send(address, email).await);
     }
@@ -3,7 +3,7 @@
 //! index files.
 
 use crate::models::{Crate, Dependency, Version};
-use crate::schema::crates;
+use crate::schema::{crates, versions};
 use anyhow::Context;
 use crates_io_index::features::split_features;
 use diesel::prelude::*;
@@ -62,6 +62,7 @@ pub async fn index_metadata(
     include_pubtime: bool,
 ) -> QueryResult<Vec<crates_io_index::Crate>> {
     let mut versions: Vec<Version> = Version::belonging_to(krate)
+        .filter(versions::staged_at.is_null())
         .select(Version::as_select())
         .load(conn)
         .await?;
@@ -79,6 +80,11 @@ pub async fn index_metadata(
 
     let deps = deps.grouped_by(&versions);
 
+    let deprecated = krate.deprecated_at.map(|_| crates_io_index::Deprecation {
+        message: krate.deprecation_message.clone(),
+        successor: krate.successor.clone(),
+    });
+
     versions
         .into_iter()
         .zip(deps)
@@ -132,6 +138,7 @@ pub async fn index_metadata(
                 pubtime: include_pubtime.then_some(version.created_at),
                 features2,
                 v,
+                deprecated: deprecated.clone(),
             };
 
             Ok(krate)
@@ -217,4 +224,35 @@ mod tests {
         let metadata = index_metadata(&bar, &mut conn, true).await.unwrap();
         assert_json_snapshot!(metadata);
     }
+
+    #[tokio::test]
+    async fn test_index_metadata_with_deprecation() {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.async_connect().await;
+
+        let user_id = UserBuilder::new()
+            .with_username("user1")
+            .new_user()
+            .insert(&conn)
+            .await
+            .unwrap();
+
+        let mut foo = CrateBuilder::new("foo", user_id)
+            .version(VersionBuilder::new("1.0.0"))
+            .expect_build(&mut conn)
+            .await;
+
+        foo.deprecated_at = Some(Utc::now());
+        foo.successor = Some("bar".to_string());
+
+        let metadata = index_metadata(&foo, &mut conn, false).await.unwrap();
+        let deprecated = metadata.into_iter().map(|krate| krate.deprecated);
+        assert_json_snapshot!(deprecated.collect::<Vec<_>>(), @r#"
+        [
+          {
+            "successor": "bar"
+          }
+        ]
+        "#);
+    }
 }
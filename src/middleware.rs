@@ -6,6 +6,7 @@ mod debug;
 mod frontend_html;
 pub mod log_request;
 pub mod normalize_path;
+pub mod private_registry;
 pub mod real_ip;
 mod require_user_agent;
 mod static_or_continue;
@@ -82,7 +83,10 @@ pub fn apply_axum_middleware(state: AppState, router: Router<()>) -> Router {
         .layer(conditional_layer(config.frontend.serve_html, || {
             from_fn_with_state(state.clone(), frontend_html::serve)
         }))
-        .layer(AddExtensionLayer::new(state.clone()));
+        .layer(AddExtensionLayer::new(state.clone()))
+        .layer(conditional_layer(config.private_registry, || {
+            from_fn_with_state(state.clone(), private_registry::require_authentication)
+        }));
 
     router
         .layer(middlewares_2)
@@ -0,0 +1,158 @@
+//! Middleware that requires authentication for all reads in private registry
+//! mode.
+//!
+//! Requests to the API and the sparse index without a session cookie or API
+//! token are rejected with `401 Unauthorized` and a `www-authenticate`
+//! header, which makes cargo retry the request with the token of the
+//! registry. API tokens grant read access regardless of their scopes.
+//!
+//! Crates with restricted read access are hidden from users that are not
+//! allowed to read them, see [`check_crate_access`].
+//!
+//! See <https://doc.rust-lang.org/cargo/reference/registry-authentication.html>.
+
+use crate::app::AppState;
+use crate::auth::authenticate;
+use crate::middleware::log_request::RequestLogExt;
+use crate::models::{Crate, CrateReader};
+use crate::schema::crates;
+use crate::util::errors::{AppResult, BoxedAppError, crate_not_found, custom};
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use crates_io_session::SessionExtension;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use http::request::Parts;
+use http::{HeaderValue, Method, StatusCode, header};
+
+/// Paths that can be read without authentication, so that users are able to
+/// sign in.
+const PUBLIC_PATHS: &[&str] = &[
+    "/api/openapi.json",
+    "/api/private/metrics/",
+    "/api/private/session/",
+    "/api/v1/site_metadata",
+];
+
+/// The user that was authenticated for read access by the middleware.
+#[derive(Clone, Copy, Debug)]
+pub struct RegistryReader {
+    pub user_id: i32,
+}
+
+impl RegistryReader {
+    /// Returns the reader that was authenticated by the middleware.
+    pub fn from_parts(parts: &Parts) -> AppResult<Self> {
+        parts.extensions.get::<Self>().copied().ok_or_else(|| {
+            custom(
+                StatusCode::UNAUTHORIZED,
+                "this registry requires authentication",
+            )
+        })
+    }
+
+    /// Returns whether the reader is allowed to read the crate.
+    pub async fn can_read(&self, conn: &AsyncPgConnection, crate_id: i32) -> QueryResult<bool> {
+        CrateReader::can_read(conn, crate_id, self.user_id).await
+    }
+}
+
+/// Returns a `404 Not Found` error if the request was authenticated by the
+/// middleware and the reader is not allowed to read the crate.
+///
+/// This is called by the [`CratePath`] and [`CrateVersionPath`] extractors,
+/// which makes it apply to all crate-scoped API endpoints. Crates that don't
+/// exist are left to the endpoints to report.
+///
+/// [`CratePath`]: crate::controllers::krate::CratePath
+/// [`CrateVersionPath`]: crate::controllers::version::CrateVersionPath
+pub async fn check_crate_access(state: &AppState, parts: &Parts, name: &str) -> AppResult<()> {
+    let Some(reader) = parts.extensions.get::<RegistryReader>() else {
+        return Ok(());
+    };
+
+    let mut conn = state.db_read().await?;
+    let crate_id = Crate::by_name(name)
+        .select(crates::id)
+        .first(&mut conn)
+        .await
+        .optional()?;
+
+    match crate_id {
+        Some(crate_id) if !reader.can_read(&conn, crate_id).await? => Err(crate_not_found(name)),
+        _ => Ok(()),
+    }
+}
+
+pub async fn require_authentication(state: AppState, req: Request, next: Next) -> Response {
+    if !requires_authentication(&req) {
+        return next.run(req).await;
+    }
+
+    let (mut parts, body) = req.into_parts();
+    match authenticate_reader(&state, &parts).await {
+        Ok(reader) => parts.extensions.insert(reader),
+        Err(error) => return error.into_response(),
+    };
+
+    next.run(Request::from_parts(parts, body)).await
+}
+
+fn requires_authentication(req: &Request) -> bool {
+    let is_read = matches!(*req.method(), Method::GET | Method::HEAD);
+    let path = req.uri().path();
+    let is_protected = path.starts_with("/api/") || path.starts_with("/index/");
+    let is_public = PUBLIC_PATHS.iter().any(|public| path.starts_with(public));
+
+    is_read && is_protected && !is_public
+}
+
+async fn authenticate_reader(state: &AppState, parts: &Parts) -> Result<RegistryReader, Response> {
+    let has_session = parts
+        .extensions
+        .get::<SessionExtension>()
+        .is_some_and(|session| session.get("user_id").is_some());
+
+    let has_token = parts.headers.contains_key(header::AUTHORIZATION);
+
+    if !has_session && !has_token {
+        parts
+            .request_log()
+            .add("cause", "private registry requires authentication");
+        return Err(unauthorized(state));
+    }
+
+    let mut conn = state
+        .db_read_prefer_primary()
+        .await
+        .map_err(|error| BoxedAppError::from(error).into_response())?;
+
+    let auth = authenticate(parts, &mut conn)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let user_id = auth.user_id();
+    Ok(RegistryReader { user_id })
+}
+
+fn unauthorized(state: &AppState) -> Response {
+    let login_url = format!(
+        "Cargo login_url=\"https://{}/settings/tokens\"",
+        state.config.domain_name
+    );
+
+    let mut response = custom(
+        StatusCode::UNAUTHORIZED,
+        "this registry requires authentication",
+    )
+    .into_response();
+
+    if let Ok(value) = HeaderValue::from_str(&login_url) {
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, value);
+    }
+
+    response
+}
@@ -1,4 +1,6 @@
-use crate::schema::{publish_limit_buckets, publish_rate_overrides};
+use crate::schema::{
+    crate_limit_buckets, crate_rate_overrides, publish_limit_buckets, publish_rate_overrides,
+};
 use crate::util::errors::{AppResult, TooManyRequests};
 use chrono::{DateTime, Utc};
 use crates_io_database::fns::{date_part, floor, greatest, interval_part, least};
@@ -16,15 +18,23 @@ pg_enum! {
         PublishNew = 0,
         PublishUpdate = 1,
         YankUnyank = 2,
+        OwnerInvite = 3,
+        CreateToken = 4,
+        TrustpubExchange = 5,
+        TwoFactorVerify = 6,
     }
 }
 
 impl LimitedAction {
     pub fn default_rate_seconds(&self) -> u64 {
         match self {
-            LimitedAction::PublishNew => 10 * 60, // 10 minutes
-            LimitedAction::PublishUpdate => 60,   // 1 minute
-            LimitedAction::YankUnyank => 60,      // 1 minute
+            LimitedAction::PublishNew => 10 * 60,  // 10 minutes
+            LimitedAction::PublishUpdate => 60,    // 1 minute
+            LimitedAction::YankUnyank => 60,       // 1 minute
+            LimitedAction::OwnerInvite => 60,      // 1 minute
+            LimitedAction::CreateToken => 60,      // 1 minute
+            LimitedAction::TrustpubExchange => 60, // 1 minute
+            LimitedAction::TwoFactorVerify => 60,  // 1 minute
         }
     }
 
@@ -33,6 +43,10 @@ impl LimitedAction {
             LimitedAction::PublishNew => 5,
             LimitedAction::PublishUpdate => 30,
             LimitedAction::YankUnyank => 100,
+            LimitedAction::OwnerInvite => 30,
+            LimitedAction::CreateToken => 20,
+            LimitedAction::TrustpubExchange => 30,
+            LimitedAction::TwoFactorVerify => 10,
         }
     }
 
@@ -41,6 +55,10 @@ impl LimitedAction {
             LimitedAction::PublishNew => "PUBLISH_NEW",
             LimitedAction::PublishUpdate => "PUBLISH_UPDATE",
             LimitedAction::YankUnyank => "YANK_UNYANK",
+            LimitedAction::OwnerInvite => "OWNER_INVITE",
+            LimitedAction::CreateToken => "CREATE_TOKEN",
+            LimitedAction::TrustpubExchange => "TRUSTPUB_EXCHANGE",
+            LimitedAction::TwoFactorVerify => "TWO_FACTOR_VERIFY",
         }
     }
 
@@ -55,6 +73,18 @@ impl LimitedAction {
             LimitedAction::YankUnyank => {
                 "You have yanked or unyanked too many versions in a short period of time"
             }
+            LimitedAction::OwnerInvite => {
+                "You have invited too many crate owners in a short period of time"
+            }
+            LimitedAction::CreateToken => {
+                "You have created too many API tokens in a short period of time"
+            }
+            LimitedAction::TrustpubExchange => {
+                "Too many Trusted Publishing tokens have been requested for this crate in a short period of time"
+            }
+            LimitedAction::TwoFactorVerify => {
+                "You have attempted too many two-factor verifications in a short period of time"
+            }
         }
     }
 }
@@ -84,12 +114,38 @@ impl RateLimiter {
         let bucket = self
             .take_token(uploader, performed_action, Utc::now(), conn)
             .await?;
-        if bucket.tokens >= 1 {
+
+        self.check_bucket(performed_action, bucket.tokens, bucket.last_refill)
+    }
+
+    /// Same as [`RateLimiter::check_rate_limit`], but for actions that are
+    /// performed on behalf of a crate instead of a user, like the exchange of
+    /// Trusted Publishing tokens.
+    pub async fn check_crate_rate_limit(
+        &self,
+        crate_id: i32,
+        performed_action: LimitedAction,
+        conn: &mut AsyncPgConnection,
+    ) -> AppResult<()> {
+        let bucket = self
+            .take_crate_token(crate_id, performed_action, Utc::now(), conn)
+            .await?;
+
+        self.check_bucket(performed_action, bucket.tokens, bucket.last_refill)
+    }
+
+    fn check_bucket(
+        &self,
+        performed_action: LimitedAction,
+        tokens: i32,
+        last_refill: DateTime<Utc>,
+    ) -> AppResult<()> {
+        if tokens >= 1 {
             Ok(())
         } else {
             Err(Box::new(TooManyRequests {
                 action: performed_action,
-                retry_after: bucket.last_refill
+                retry_after: last_refill
                     + chrono::Duration::from_std(self.config_for_action(performed_action).rate)
                         .unwrap(),
             }))
@@ -98,66 +154,132 @@ impl RateLimiter {
 
     /// Refills a user's bucket as needed, takes a token from it,
     /// and returns the result.
+    async fn take_token(
+        &self,
+        uploader: i32,
+        performed_action: LimitedAction,
+        now: DateTime<Utc>,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Bucket> {
+        let key = BucketKey::User(uploader);
+        let (tokens, last_refill) = self
+            .take_token_from(key, performed_action, now, conn)
+            .await?;
+
+        Ok(Bucket {
+            user_id: uploader,
+            tokens,
+            last_refill,
+            action: performed_action,
+        })
+    }
+
+    /// Same as [`RateLimiter::take_token`], but uses the crate buckets and
+    /// overrides.
+    async fn take_crate_token(
+        &self,
+        crate_id: i32,
+        performed_action: LimitedAction,
+        now: DateTime<Utc>,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<CrateBucket> {
+        let key = BucketKey::Crate(crate_id);
+        let (tokens, last_refill) = self
+            .take_token_from(key, performed_action, now, conn)
+            .await?;
+
+        Ok(CrateBucket {
+            crate_id,
+            tokens,
+            last_refill,
+            action: performed_action,
+        })
+    }
+
+    /// Refills the bucket with the given key as needed, takes a token from
+    /// it, and returns the remaining tokens and the time of the last refill.
     ///
     /// The number of tokens remaining will always be between 0 and self.burst.
     /// If the number is 0, the request should be rejected, as the user doesn't
     /// have a token to take. Technically a "full" bucket would have
     /// `self.burst + 1` tokens in it, but that value would never be returned
     /// since we only refill buckets when trying to take a token from it.
-    async fn take_token(
+    async fn take_token_from(
         &self,
-        uploader: i32,
+        key: BucketKey,
         performed_action: LimitedAction,
         now: DateTime<Utc>,
         conn: &mut AsyncPgConnection,
-    ) -> QueryResult<Bucket> {
+    ) -> QueryResult<(i32, DateTime<Utc>)> {
         let config = self.config_for_action(performed_action);
         let refill_rate = (config.rate.as_millis() as i64).milliseconds();
 
-        let burst: i32 = publish_rate_overrides::table
-            .find((uploader, performed_action))
-            .filter(
-                publish_rate_overrides::expires_at
-                    .is_null()
-                    .or(publish_rate_overrides::expires_at.gt(now)),
-            )
-            .select(publish_rate_overrides::burst)
-            .first(conn)
-            .await
-            .optional()?
-            .unwrap_or(config.burst);
-
-        // Interval division is poorly defined in general (what is 1 month / 30 days?)
-        // However, for the intervals we're dealing with, it is always well
-        // defined, so we convert to an f64 of seconds to represent this.
-        let tokens_to_add = floor(
-            (date_part("epoch", now) - date_part("epoch", publish_limit_buckets::last_refill))
-                / interval_part("epoch", refill_rate),
-        );
+        // The user and crate buckets only differ in their tables and the
+        // column of the bucket owner, so the query is generated for both.
+        macro_rules! take_token {
+            ($buckets:ident, $overrides:ident, $owner:ident, $owner_id:expr) => {{
+                let burst: i32 = $overrides::table
+                    .find(($owner_id, performed_action))
+                    .filter(
+                        $overrides::expires_at
+                            .is_null()
+                            .or($overrides::expires_at.gt(now)),
+                    )
+                    .select($overrides::burst)
+                    .first(conn)
+                    .await
+                    .optional()?
+                    .unwrap_or(config.burst);
+
+                // Interval division is poorly defined in general (what is 1 month / 30 days?)
+                // However, for the intervals we're dealing with, it is always well
+                // defined, so we convert to an f64 of seconds to represent this.
+                let tokens_to_add = floor(
+                    (date_part("epoch", now) - date_part("epoch", $buckets::last_refill))
+                        / interval_part("epoch", refill_rate),
+                );
+
+                diesel::insert_into($buckets::table)
+                    .values((
+                        $buckets::$owner.eq($owner_id),
+                        $buckets::action.eq(performed_action),
+                        $buckets::tokens.eq(burst),
+                        $buckets::last_refill.eq(now),
+                    ))
+                    .on_conflict(($buckets::$owner, $buckets::action))
+                    .do_update()
+                    .set((
+                        $buckets::tokens.eq(least(
+                            burst,
+                            greatest(0, $buckets::tokens - 1) + tokens_to_add,
+                        )),
+                        $buckets::last_refill.eq($buckets::last_refill
+                            + refill_rate.into_sql::<Interval>() * tokens_to_add),
+                    ))
+                    .returning(($buckets::tokens, $buckets::last_refill))
+                    .get_result(conn)
+                    .await
+            }};
+        }
 
-        diesel::insert_into(publish_limit_buckets::table)
-            .values((
-                publish_limit_buckets::user_id.eq(uploader),
-                publish_limit_buckets::action.eq(performed_action),
-                publish_limit_buckets::tokens.eq(burst),
-                publish_limit_buckets::last_refill.eq(now),
-            ))
-            .on_conflict((
-                publish_limit_buckets::user_id,
-                publish_limit_buckets::action,
-            ))
-            .do_update()
-            .set((
-                publish_limit_buckets::tokens.eq(least(
-                    burst,
-                    greatest(0, publish_limit_buckets::tokens - 1) + tokens_to_add,
-                )),
-                publish_limit_buckets::last_refill.eq(publish_limit_buckets::last_refill
-                    + refill_rate.into_sql::<Interval>() * tokens_to_add),
-            ))
-            .returning(Bucket::as_returning())
-            .get_result(conn)
-            .await
+        match key {
+            BucketKey::User(user_id) => {
+                take_token!(
+                    publish_limit_buckets,
+                    publish_rate_overrides,
+                    user_id,
+                    user_id
+                )
+            }
+            BucketKey::Crate(crate_id) => {
+                take_token!(
+                    crate_limit_buckets,
+                    crate_rate_overrides,
+                    crate_id,
+                    crate_id
+                )
+            }
+        }
     }
 
     fn config_for_action(&self, action: LimitedAction) -> Cow<'_, RateLimiterConfig> {
@@ -172,6 +294,13 @@ impl RateLimiter {
     }
 }
 
+/// Identifies the bucket that a token is taken from.
+#[derive(Debug, Clone, Copy)]
+enum BucketKey {
+    User(i32),
+    Crate(i32),
+}
+
 #[derive(HasQuery, Insertable, Debug, PartialEq, Clone, Copy)]
 #[diesel(table_name = publish_limit_buckets)]
 #[allow(dead_code)] // Most fields only read in tests
@@ -182,12 +311,22 @@ struct Bucket {
     action: LimitedAction,
 }
 
+#[derive(HasQuery, Insertable, Debug, PartialEq, Clone, Copy)]
+#[diesel(table_name = crate_limit_buckets)]
+#[allow(dead_code)] // Most fields only read in tests
+struct CrateBucket {
+    crate_id: i32,
+    tokens: i32,
+    last_refill: DateTime<Utc>,
+    action: LimitedAction,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::NaiveDateTime;
     use crates_io_test_db::TestDatabase;
-    use crates_io_test_utils::builders::UserBuilder;
+    use crates_io_test_utils::builders::{CrateBuilder, UserBuilder};
 
     #[tokio::test]
     async fn default_rate_limits() -> anyhow::Result<()> {
@@ -703,6 +842,133 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn crate_buckets_are_separate_from_user_buckets() -> anyhow::Result<()> {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.async_connect().await;
+        let now = now();
+
+        let rate = SampleRateLimiter {
+            rate: Duration::from_secs(1),
+            burst: 10,
+            action: LimitedAction::TrustpubExchange,
+        }
+        .create();
+        let user_id = new_user(&mut conn, "user").await?;
+        let crate_id = new_crate(&mut conn, "foo", user_id).await?;
+        let other_crate_id = new_crate(&mut conn, "bar", user_id).await?;
+
+        let bucket = rate
+            .take_crate_token(crate_id, LimitedAction::TrustpubExchange, now, &mut conn)
+            .await?;
+        let expected = CrateBucket {
+            crate_id,
+            tokens: 10,
+            last_refill: now,
+            action: LimitedAction::TrustpubExchange,
+        };
+        assert_eq!(expected, bucket);
+
+        let bucket = rate
+            .take_crate_token(crate_id, LimitedAction::TrustpubExchange, now, &mut conn)
+            .await?;
+        assert_eq!(bucket.tokens, 9);
+
+        let other_bucket = rate
+            .take_crate_token(
+                other_crate_id,
+                LimitedAction::TrustpubExchange,
+                now,
+                &mut conn,
+            )
+            .await?;
+        assert_eq!(other_bucket.tokens, 10);
+
+        let user_bucket = rate
+            .take_token(user_id, LimitedAction::TrustpubExchange, now, &mut conn)
+            .await?;
+        assert_eq!(user_bucket.tokens, 10);
+
+        let refill_time = now + chrono::Duration::seconds(2);
+        let bucket = rate
+            .take_crate_token(
+                crate_id,
+                LimitedAction::TrustpubExchange,
+                refill_time,
+                &mut conn,
+            )
+            .await?;
+        let expected = CrateBucket {
+            crate_id,
+            tokens: 10,
+            last_refill: refill_time,
+            action: LimitedAction::TrustpubExchange,
+        };
+        assert_eq!(expected, bucket);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn crate_override_is_used_instead_of_global_burst_if_present() -> anyhow::Result<()> {
+        let test_db = TestDatabase::new();
+        let mut conn = test_db.async_connect().await;
+        let now = now();
+
+        let rate = SampleRateLimiter {
+            rate: Duration::from_secs(1),
+            burst: 10,
+            action: LimitedAction::TrustpubExchange,
+        }
+        .create();
+        let user_id = new_user(&mut conn, "user").await?;
+        let crate_id = new_crate(&mut conn, "foo", user_id).await?;
+        let other_crate_id = new_crate(&mut conn, "bar", user_id).await?;
+        let expired_crate_id = new_crate(&mut conn, "baz", user_id).await?;
+
+        diesel::insert_into(crate_rate_overrides::table)
+            .values(&vec![
+                (
+                    crate_rate_overrides::crate_id.eq(crate_id),
+                    crate_rate_overrides::action.eq(LimitedAction::TrustpubExchange),
+                    crate_rate_overrides::burst.eq(20),
+                    crate_rate_overrides::expires_at.eq(None),
+                ),
+                (
+                    crate_rate_overrides::crate_id.eq(expired_crate_id),
+                    crate_rate_overrides::action.eq(LimitedAction::TrustpubExchange),
+                    crate_rate_overrides::burst.eq(20),
+                    crate_rate_overrides::expires_at.eq(Some(now - chrono::Duration::days(1))),
+                ),
+            ])
+            .execute(&mut conn)
+            .await?;
+
+        let action = LimitedAction::TrustpubExchange;
+        let bucket = rate
+            .take_crate_token(crate_id, action, now, &mut conn)
+            .await?;
+        let other_bucket = rate
+            .take_crate_token(other_crate_id, action, now, &mut conn)
+            .await?;
+        let expired_bucket = rate
+            .take_crate_token(expired_crate_id, action, now, &mut conn)
+            .await?;
+
+        assert_eq!(bucket.tokens, 20);
+        assert_eq!(other_bucket.tokens, 10);
+        assert_eq!(expired_bucket.tokens, 10);
+        Ok(())
+    }
+
+    async fn new_crate(
+        conn: &mut AsyncPgConnection,
+        name: &str,
+        owner_id: i32,
+    ) -> anyhow::Result<i32> {
+        Ok(CrateBuilder::new(name, owner_id).build(conn).await?.id)
+    }
+
     async fn new_user(conn: &mut AsyncPgConnection, gh_login: &str) -> QueryResult<i32> {
         UserBuilder::new()
             .with_username(gh_login)
@@ -26,6 +26,11 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
             krate::owners::add_owners,
             krate::owners::remove_owners
         ))
+        .routes(routes!(
+            krate::readers::list_readers,
+            krate::readers::add_readers,
+            krate::readers::remove_readers
+        ))
         .routes(routes!(version::yank::yank_version))
         .routes(routes!(version::yank::unyank_version))
         .routes(routes!(version::downloads::download_version))
@@ -43,7 +48,13 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
         .routes(routes!(version::dependencies::get_version_dependencies))
         .routes(routes!(version::downloads::get_version_downloads))
         .routes(routes!(version::docs::rebuild_version_docs))
+        .routes(routes!(version::promote::promote_version))
         .routes(routes!(version::authors::get_version_authors))
+        .routes(routes!(version::provenance::get_version_provenance))
+        .routes(routes!(version::files::list_version_files))
+        .routes(routes!(version::files::get_version_file))
+        .routes(routes!(version::diff::get_version_diff))
+        .routes(routes!(version::scan_findings::list_version_scan_findings))
         .routes(routes!(krate::downloads::get_crate_downloads))
         .routes(routes!(krate::versions::list_versions))
         .routes(routes!(
@@ -85,10 +96,43 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
         .routes(routes!(summary::get_summary))
         .routes(routes!(user::email_verification::confirm_user_email))
         .routes(routes!(user::email_verification::resend_email_verification))
+        .routes(routes!(user::identities::list_identities))
+        .routes(routes!(user::identities::begin_identity_link))
+        .routes(routes!(user::identities::authorize_identity_link))
+        .routes(routes!(user::identities::unlink_identity))
+        .routes(routes!(user::two_factor::get_two_factor_status))
+        .routes(routes!(user::two_factor::begin_two_factor_verification))
+        .routes(routes!(user::two_factor::verify_two_factor))
+        .routes(routes!(user::two_factor::regenerate_recovery_codes))
+        .routes(routes!(user::two_factor::totp::begin_totp_setup))
+        .routes(routes!(user::two_factor::totp::confirm_totp_setup))
+        .routes(routes!(user::two_factor::totp::delete_totp))
+        .routes(routes!(
+            user::two_factor::webauthn::begin_webauthn_registration
+        ))
+        .routes(routes!(
+            user::two_factor::webauthn::register_webauthn_credential
+        ))
+        .routes(routes!(
+            user::two_factor::webauthn::delete_webauthn_credential
+        ))
+        .routes(routes!(
+            user::webhooks::list_webhooks,
+            user::webhooks::create_webhook
+        ))
+        .routes(routes!(user::webhooks::delete_webhook))
+        .routes(routes!(user::webhooks::list_webhook_deliveries))
+        .routes(routes!(
+            user::notification_preferences::get_notification_preferences,
+            user::notification_preferences::update_notification_preferences
+        ))
+        .routes(routes!(user::notification_preferences::unsubscribe))
         .routes(routes!(site_metadata::get_site_metadata))
         // Session management
         .routes(routes!(session::begin_session))
         .routes(routes!(session::authorize_session))
+        .routes(routes!(session::identity::begin_identity_session))
+        .routes(routes!(session::identity::authorize_identity_session))
         .routes(routes!(
             session::get_pending_signup,
             session::complete_pending_signup,
@@ -110,6 +154,12 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
             trustpub::gitlab_configs::delete::delete_trustpub_gitlab_config,
             trustpub::gitlab_configs::list::list_trustpub_gitlab_configs,
         ))
+        .routes(routes!(
+            trustpub::oidc_configs::create::create_trustpub_oidc_config,
+            trustpub::oidc_configs::delete::delete_trustpub_oidc_config,
+            trustpub::oidc_configs::list::list_trustpub_oidc_configs,
+        ))
+        .routes(routes!(version::provenance::get_provenance_key))
         .split_for_parts();
 
     let mut router = router
@@ -134,6 +184,14 @@ pub fn build_axum_router(state: AppState) -> Router<()> {
         );
     }
 
+    // In private registry mode, the sparse index needs to be served with
+    // authentication instead of via the CDN.
+    if state.config.private_registry {
+        router = router
+            .route("/index/config.json", get(sparse_index::get_index_config))
+            .route("/index/{*path}", get(sparse_index::get_index_file));
+    }
+
     router
         .route(
             "/api/openapi.json",
@@ -13,6 +13,7 @@ use object_store::{
 };
 use secrecy::{ExposeSecret, SecretString};
 use std::fs;
+use std::ops::Range;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncRead, AsyncWriteExt};
@@ -21,6 +22,7 @@ use tracing::{instrument, warn};
 const PREFIX_CRATES: &str = "crates";
 const PREFIX_READMES: &str = "readmes";
 const PREFIX_OG_IMAGES: &str = "og-images";
+const PREFIX_DB_DUMP_DELTAS: &str = "db-dump-deltas";
 const DEFAULT_REGION: &str = "us-west-1";
 const CACHE_CONTROL_IMMUTABLE: &str = "public,max-age=31536000,immutable";
 const CACHE_CONTROL_INDEX: &str = "public,max-age=600";
@@ -299,11 +301,23 @@ impl Storage {
     pub async fn download_stream(
         &self,
         key: &StorageKey<'_>,
-    ) -> Result<BoxStream<'_, Result<Bytes>>> {
+    ) -> Result<BoxStream<'static, Result<Bytes>>> {
         let result = self.store.get(&key.path()).await?;
         Ok(result.into_stream())
     }
 
+    /// Downloads the object identified by `key` into memory.
+    #[instrument(skip(self))]
+    pub async fn download(&self, key: &StorageKey<'_>) -> Result<Bytes> {
+        self.store.get(&key.path()).await?.bytes().await
+    }
+
+    /// Downloads the given byte `range` of the object identified by `key`.
+    #[instrument(skip(self))]
+    pub async fn download_range(&self, key: &StorageKey<'_>, range: Range<u64>) -> Result<Bytes> {
+        self.store.get_range(&key.path(), range).await
+    }
+
     #[instrument(skip(self, content))]
     pub async fn sync_index(&self, name: &str, content: Option<String>) -> Result<()> {
         let path = crates_io_index::Repository::relative_index_file_for_url(name).into();
@@ -322,6 +336,16 @@ impl Storage {
         Ok(())
     }
 
+    /// Downloads the sparse index file of the given crate.
+    ///
+    /// This is used to serve the index in private registry mode, where it is
+    /// not available via the CDN.
+    #[instrument(skip(self))]
+    pub async fn download_index(&self, name: &str) -> Result<Bytes> {
+        let path = crates_io_index::Repository::relative_index_file_for_url(name).into();
+        self.index_store.get(&path).await?.bytes().await
+    }
+
     /// This should only be used for assertions in the test suite!
     pub fn as_inner(&self) -> Arc<dyn ObjectStore> {
         self.store.clone()
@@ -367,12 +391,19 @@ pub enum StorageKey<'a> {
     CrateZip { name: &'a str, version: &'a str },
     CrateZipManifest { name: &'a str, version: &'a str },
     Readme { name: &'a str, version: &'a str },
+    ReadmeToc { name: &'a str, version: &'a str },
     OgImage { name: &'a str },
     CrateFeed { name: &'a str },
     CratesFeed,
     UpdatesFeed,
     DbDumpTar,
     DbDumpZip,
+    DbDumpParquet,
+    DbDumpSqlite,
+    DbDumpDeltaTar { name: &'a str },
+    DbDumpDeltaZip { name: &'a str },
+    DbDumpDeltaManifest,
+    DbDumpDeltaState,
 }
 
 impl<'a> StorageKey<'a> {
@@ -396,6 +427,11 @@ impl<'a> StorageKey<'a> {
         StorageKey::Readme { name, version }
     }
 
+    /// Builds a [`StorageKey::ReadmeToc`] key for the given crate version.
+    pub fn for_readme_toc(name: &'a str, version: &'a str) -> Self {
+        StorageKey::ReadmeToc { name, version }
+    }
+
     /// Builds a [`StorageKey::OgImage`] key for the given crate.
     pub fn for_og_image(name: &'a str) -> Self {
         StorageKey::OgImage { name }
@@ -416,12 +452,27 @@ impl<'a> StorageKey<'a> {
             StorageKey::Readme { name, version } => {
                 format!("{PREFIX_READMES}/{name}/{name}-{version}.html").into()
             }
+            StorageKey::ReadmeToc { name, version } => {
+                format!("{PREFIX_READMES}/{name}/{name}-{version}.toc.json").into()
+            }
             StorageKey::OgImage { name } => format!("{PREFIX_OG_IMAGES}/{name}.png").into(),
             StorageKey::CrateFeed { name } => format!("rss/crates/{name}.xml").into(),
             StorageKey::CratesFeed => "rss/crates.xml".into(),
             StorageKey::UpdatesFeed => "rss/updates.xml".into(),
             StorageKey::DbDumpTar => "db-dump.tar.gz".into(),
             StorageKey::DbDumpZip => "db-dump.zip".into(),
+            StorageKey::DbDumpParquet => "db-dump-parquet.tar".into(),
+            StorageKey::DbDumpSqlite => "db-dump.sqlite.gz".into(),
+            StorageKey::DbDumpDeltaTar { name } => {
+                format!("{PREFIX_DB_DUMP_DELTAS}/{name}.tar.gz").into()
+            }
+            StorageKey::DbDumpDeltaZip { name } => {
+                format!("{PREFIX_DB_DUMP_DELTAS}/{name}.zip").into()
+            }
+            StorageKey::DbDumpDeltaManifest => {
+                format!("{PREFIX_DB_DUMP_DELTAS}/manifest.json").into()
+            }
+            StorageKey::DbDumpDeltaState => format!("{PREFIX_DB_DUMP_DELTAS}/state.tar.gz").into(),
         }
     }
 
@@ -437,14 +488,21 @@ impl<'a> StorageKey<'a> {
         match self {
             StorageKey::CrateFile { .. } => Some("application/gzip"),
             StorageKey::CrateZip { .. } => Some("application/zip"),
-            StorageKey::CrateZipManifest { .. } => Some("application/json"),
+            StorageKey::CrateZipManifest { .. } | StorageKey::ReadmeToc { .. } => {
+                Some("application/json")
+            }
             StorageKey::Readme { .. } => Some("text/html"),
             StorageKey::OgImage { .. } => Some("image/png"),
             StorageKey::CrateFeed { .. } | StorageKey::CratesFeed | StorageKey::UpdatesFeed => {
                 Some("text/xml; charset=UTF-8")
             }
-            StorageKey::DbDumpTar => Some("application/gzip"),
-            StorageKey::DbDumpZip => Some("application/zip"),
+            StorageKey::DbDumpTar
+            | StorageKey::DbDumpSqlite
+            | StorageKey::DbDumpDeltaTar { .. }
+            | StorageKey::DbDumpDeltaState => Some("application/gzip"),
+            StorageKey::DbDumpParquet => Some("application/x-tar"),
+            StorageKey::DbDumpZip | StorageKey::DbDumpDeltaZip { .. } => Some("application/zip"),
+            StorageKey::DbDumpDeltaManifest => Some("application/json"),
         }
     }
 
@@ -454,14 +512,20 @@ impl<'a> StorageKey<'a> {
         match self {
             StorageKey::CrateFile { .. }
             | StorageKey::CrateZip { .. }
-            | StorageKey::CrateZipManifest { .. } => Some(CACHE_CONTROL_IMMUTABLE),
-            StorageKey::Readme { .. } => Some(CACHE_CONTROL_README),
+            | StorageKey::CrateZipManifest { .. }
+            | StorageKey::DbDumpDeltaTar { .. }
+            | StorageKey::DbDumpDeltaZip { .. } => Some(CACHE_CONTROL_IMMUTABLE),
+            StorageKey::Readme { .. } | StorageKey::ReadmeToc { .. } => Some(CACHE_CONTROL_README),
             StorageKey::OgImage { .. } => Some(CACHE_CONTROL_OG_IMAGE),
             StorageKey::CrateFeed { .. }
             | StorageKey::CratesFeed
             | StorageKey::UpdatesFeed
             | StorageKey::DbDumpTar
-            | StorageKey::DbDumpZip => None,
+            | StorageKey::DbDumpZip
+            | StorageKey::DbDumpParquet
+            | StorageKey::DbDumpSqlite
+            | StorageKey::DbDumpDeltaManifest
+            | StorageKey::DbDumpDeltaState => None,
         }
     }
 
@@ -477,7 +541,8 @@ impl<'a> StorageKey<'a> {
             StorageKey::CrateFile { name, version }
             | StorageKey::CrateZip { name, version }
             | StorageKey::CrateZipManifest { name, version }
-            | StorageKey::Readme { name, version } => Some(format!(
+            | StorageKey::Readme { name, version }
+            | StorageKey::ReadmeToc { name, version } => Some(format!(
                 "{},{}",
                 crate_cache_tag(name),
                 release_cache_tag(name, version)
@@ -488,7 +553,13 @@ impl<'a> StorageKey<'a> {
             StorageKey::CratesFeed
             | StorageKey::UpdatesFeed
             | StorageKey::DbDumpTar
-            | StorageKey::DbDumpZip => None,
+            | StorageKey::DbDumpZip
+            | StorageKey::DbDumpParquet
+            | StorageKey::DbDumpSqlite
+            | StorageKey::DbDumpDeltaTar { .. }
+            | StorageKey::DbDumpDeltaZip { .. }
+            | StorageKey::DbDumpDeltaManifest
+            | StorageKey::DbDumpDeltaState => None,
         }
     }
 
@@ -613,6 +684,16 @@ mod tests {
             assert_eq!(storage.location(&key), expected);
         }
 
+        let readme_toc_tests = vec![(
+            "foo",
+            "1.2.3",
+            "https://static.crates.io/readmes/foo/foo-1.2.3.toc.json",
+        )];
+        for (name, version, expected) in readme_toc_tests {
+            let key = StorageKey::for_readme_toc(name, version);
+            assert_eq!(storage.location(&key), expected);
+        }
+
         let og_image_tests = vec![
             ("foo", "https://static.crates.io/og-images/foo.png"),
             (
@@ -663,6 +744,7 @@ mod tests {
             StorageKey::for_crate_zip(name, version),
             StorageKey::for_crate_zip_manifest(name, version),
             StorageKey::for_readme(name, version),
+            StorageKey::for_readme_toc(name, version),
         ];
         for key in version_scoped {
             assert_some_eq!(
@@ -684,6 +766,12 @@ mod tests {
             StorageKey::UpdatesFeed,
             StorageKey::DbDumpTar,
             StorageKey::DbDumpZip,
+            StorageKey::DbDumpParquet,
+            StorageKey::DbDumpSqlite,
+            StorageKey::DbDumpDeltaTar { name },
+            StorageKey::DbDumpDeltaZip { name },
+            StorageKey::DbDumpDeltaManifest,
+            StorageKey::DbDumpDeltaState,
         ];
         for key in untagged {
             assert_none!(key.cache_tags());
@@ -868,6 +956,17 @@ mod tests {
         assert_eq!(stored_files(&s.store).await, expected_files);
     }
 
+    #[tokio::test]
+    async fn download_range() {
+        let s = Storage::from_config(&StorageConfig::in_memory());
+
+        let key = StorageKey::for_crate_zip("foo", "1.2.3");
+        s.upload_stream(&key, &b"fake zip data"[..]).await.unwrap();
+
+        assert_eq!(s.download(&key).await.unwrap(), "fake zip data");
+        assert_eq!(s.download_range(&key, 5..8).await.unwrap(), "zip");
+    }
+
     #[tokio::test]
     async fn upload_crate_zip_manifest() {
         let s = Storage::from_config(&StorageConfig::in_memory());
@@ -927,6 +1026,18 @@ mod tests {
         assert!(stored_files(&s.store).await.is_empty());
     }
 
+    #[tokio::test]
+    async fn download_index() {
+        let s = Storage::from_config(&StorageConfig::in_memory());
+
+        let result = s.download_index("foo").await;
+        assert!(matches!(result, Err(object_store::Error::NotFound { .. })));
+
+        let content = "foo".to_string();
+        s.sync_index("foo", Some(content)).await.unwrap();
+        assert_eq!(s.download_index("foo").await.unwrap(), "foo");
+    }
+
     #[tokio::test]
     async fn upload_db_dump() {
         let s = Storage::from_config(&StorageConfig::in_memory());
@@ -1,12 +1,17 @@
 use crate::builders::CrateBuilder;
 use crate::util::TestApp;
 use bytes::Buf;
-use crates_io::worker::jobs::DumpDb;
+use chrono::{TimeDelta, Utc};
+use crates_io::schema::{crate_owners, crate_readers, crates, versions};
+use crates_io::worker::jobs::{DumpDb, DumpDbDelta};
 use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
 use flate2::read::GzDecoder;
 use insta::{assert_debug_snapshot, assert_snapshot};
 use object_store::ObjectStoreExt;
 use regex::regex;
+use std::collections::HashMap;
 use std::io::{Cursor, Read};
 use tar::Archive;
 
@@ -24,6 +29,8 @@ async fn test_dump_db_job() -> anyhow::Result<()> {
     app.run_pending_background_jobs().await;
 
     assert_snapshot!(app.stored_files().await.join("\n"), @r"
+    db-dump-parquet.tar
+    db-dump.sqlite.gz
     db-dump.tar.gz
     db-dump.zip
     ");
@@ -45,6 +52,7 @@ async fn test_dump_db_job() -> anyhow::Result<()> {
         "YYYY-MM-DD-HHMMSS/metadata.json",
         "YYYY-MM-DD-HHMMSS/schema.sql",
         "YYYY-MM-DD-HHMMSS/data",
+        "YYYY-MM-DD-HHMMSS/data/advisories.csv",
         "YYYY-MM-DD-HHMMSS/data/categories.csv",
         "YYYY-MM-DD-HHMMSS/data/crate_downloads.csv",
         "YYYY-MM-DD-HHMMSS/data/crates.csv",
@@ -55,6 +63,8 @@ async fn test_dump_db_job() -> anyhow::Result<()> {
         "YYYY-MM-DD-HHMMSS/data/reserved_usernames.csv",
         "YYYY-MM-DD-HHMMSS/data/teams.csv",
         "YYYY-MM-DD-HHMMSS/data/users.csv",
+        "YYYY-MM-DD-HHMMSS/data/cargo_version_downloads.csv",
+        "YYYY-MM-DD-HHMMSS/data/client_downloads.csv",
         "YYYY-MM-DD-HHMMSS/data/crates_categories.csv",
         "YYYY-MM-DD-HHMMSS/data/crates_keywords.csv",
         "YYYY-MM-DD-HHMMSS/data/crate_owners.csv",
@@ -62,6 +72,8 @@ async fn test_dump_db_job() -> anyhow::Result<()> {
         "YYYY-MM-DD-HHMMSS/data/versions.csv",
         "YYYY-MM-DD-HHMMSS/data/default_versions.csv",
         "YYYY-MM-DD-HHMMSS/data/dependencies.csv",
+        "YYYY-MM-DD-HHMMSS/data/trustpub_provenance.csv",
+        "YYYY-MM-DD-HHMMSS/data/version_advisories.csv",
         "YYYY-MM-DD-HHMMSS/data/version_downloads.csv",
     ]
     "#);
@@ -80,6 +92,7 @@ async fn test_dump_db_job() -> anyhow::Result<()> {
         "metadata.json",
         "schema.sql",
         "data/",
+        "data/advisories.csv",
         "data/categories.csv",
         "data/crate_downloads.csv",
         "data/crates.csv",
@@ -90,6 +103,8 @@ async fn test_dump_db_job() -> anyhow::Result<()> {
         "data/reserved_usernames.csv",
         "data/teams.csv",
         "data/users.csv",
+        "data/cargo_version_downloads.csv",
+        "data/client_downloads.csv",
         "data/crates_categories.csv",
         "data/crates_keywords.csv",
         "data/crate_owners.csv",
@@ -97,6 +112,8 @@ async fn test_dump_db_job() -> anyhow::Result<()> {
         "data/versions.csv",
         "data/default_versions.csv",
         "data/dependencies.csv",
+        "data/trustpub_provenance.csv",
+        "data/version_advisories.csv",
         "data/version_downloads.csv",
     ]
     "#);
@@ -104,6 +121,189 @@ async fn test_dump_db_job() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dump_db_delta_job() -> anyhow::Result<()> {
+    let (app, _, _, token) = TestApp::full().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", token.as_model().user_id)
+        .expect_build(&mut conn)
+        .await;
+    CrateBuilder::new("bar", token.as_model().user_id)
+        .expect_build(&mut conn)
+        .await;
+
+    // The first run only records the state of the database
+    DumpDbDelta.enqueue(&conn).await?;
+    app.run_pending_background_jobs().await;
+
+    assert_snapshot!(app.stored_files().await.join("\n"), @"db-dump-deltas/state.tar.gz");
+
+    diesel::delete(crates::table.filter(crates::name.eq("bar")))
+        .execute(&mut conn)
+        .await?;
+
+    DumpDbDelta.enqueue(&conn).await?;
+    app.run_pending_background_jobs().await;
+
+    let stored_files = app.stored_files().await;
+    let stored_files = stored_files
+        .iter()
+        .map(|path| regex!(r"\d{4}-\d{2}-\d{2}-\d{6}").replace(path, "YYYY-MM-DD-HHMMSS"))
+        .collect::<Vec<_>>();
+    assert_snapshot!(stored_files.join("\n"), @r"
+    db-dump-deltas/YYYY-MM-DD-HHMMSS.tar.gz
+    db-dump-deltas/YYYY-MM-DD-HHMMSS.zip
+    db-dump-deltas/manifest.json
+    db-dump-deltas/state.tar.gz
+    ");
+
+    let path = object_store::path::Path::parse("db-dump-deltas/manifest.json")?;
+    let result = app.as_inner().storage.as_inner().get(&path).await?;
+    let index: serde_json::Value = serde_json::from_slice(&result.bytes().await?)?;
+    let deltas = index["deltas"].as_array().unwrap();
+    assert_eq!(deltas.len(), 1);
+
+    let path = object_store::path::Path::parse(deltas[0]["tar"].as_str().unwrap())?;
+    let result = app.as_inner().storage.as_inner().get(&path).await?;
+    let bytes = result.bytes().await?;
+
+    let mut tar = Archive::new(GzDecoder::new(bytes.reader()));
+    let paths = tar_paths(&mut tar);
+    assert_debug_snapshot!(paths, @r#"
+    [
+        "YYYY-MM-DD-HHMMSS",
+        "YYYY-MM-DD-HHMMSS/README.md",
+        "YYYY-MM-DD-HHMMSS/export.sql",
+        "YYYY-MM-DD-HHMMSS/import.sql",
+        "YYYY-MM-DD-HHMMSS/manifest.json",
+        "YYYY-MM-DD-HHMMSS/data",
+        "YYYY-MM-DD-HHMMSS/data/advisories.csv",
+        "YYYY-MM-DD-HHMMSS/data/categories.csv",
+        "YYYY-MM-DD-HHMMSS/data/crate_downloads.csv",
+        "YYYY-MM-DD-HHMMSS/data/crates.csv",
+        "YYYY-MM-DD-HHMMSS/data/keywords.csv",
+        "YYYY-MM-DD-HHMMSS/data/metadata.csv",
+        "YYYY-MM-DD-HHMMSS/data/oauth_github.csv",
+        "YYYY-MM-DD-HHMMSS/data/reserved_crate_names.csv",
+        "YYYY-MM-DD-HHMMSS/data/reserved_usernames.csv",
+        "YYYY-MM-DD-HHMMSS/data/teams.csv",
+        "YYYY-MM-DD-HHMMSS/data/users.csv",
+        "YYYY-MM-DD-HHMMSS/data/cargo_version_downloads.csv",
+        "YYYY-MM-DD-HHMMSS/data/client_downloads.csv",
+        "YYYY-MM-DD-HHMMSS/data/crates_categories.csv",
+        "YYYY-MM-DD-HHMMSS/data/crates_keywords.csv",
+        "YYYY-MM-DD-HHMMSS/data/crate_owners.csv",
+        "YYYY-MM-DD-HHMMSS/data/deleted_crates.csv",
+        "YYYY-MM-DD-HHMMSS/data/versions.csv",
+        "YYYY-MM-DD-HHMMSS/data/default_versions.csv",
+        "YYYY-MM-DD-HHMMSS/data/dependencies.csv",
+        "YYYY-MM-DD-HHMMSS/data/trustpub_provenance.csv",
+        "YYYY-MM-DD-HHMMSS/data/version_advisories.csv",
+        "YYYY-MM-DD-HHMMSS/data/version_downloads.csv",
+        "YYYY-MM-DD-HHMMSS/deleted",
+        "YYYY-MM-DD-HHMMSS/deleted/advisories.csv",
+        "YYYY-MM-DD-HHMMSS/deleted/crates.csv",
+        "YYYY-MM-DD-HHMMSS/deleted/crate_owners.csv",
+        "YYYY-MM-DD-HHMMSS/deleted/versions.csv",
+    ]
+    "#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dump_db_delta_job_with_unrestricted_crate() -> anyhow::Result<()> {
+    let (app, _, user) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+    let user_id = user.as_model().id;
+
+    let krate = CrateBuilder::new("foo", user_id)
+        .version("1.0.0")
+        .expect_build(&mut conn)
+        .await;
+
+    diesel::insert_into(crate_readers::table)
+        .values((
+            crate_readers::crate_id.eq(krate.id),
+            crate_readers::user_id.eq(user_id),
+        ))
+        .execute(&mut conn)
+        .await?;
+
+    // Make sure that the rows of the crate are older than the cursors of the
+    // delta dump, so that they are only exported because of the unrestriction
+    let two_hours_ago = Utc::now() - TimeDelta::hours(2);
+    diesel::update(crates::table.find(krate.id))
+        .set(crates::updated_at.eq(two_hours_ago))
+        .execute(&mut conn)
+        .await?;
+    diesel::update(versions::table.filter(versions::crate_id.eq(krate.id)))
+        .set(versions::updated_at.eq(two_hours_ago))
+        .execute(&mut conn)
+        .await?;
+    diesel::update(crate_owners::table.filter(crate_owners::crate_id.eq(krate.id)))
+        .set(crate_owners::updated_at.eq(two_hours_ago))
+        .execute(&mut conn)
+        .await?;
+
+    DumpDbDelta.enqueue(&conn).await?;
+    app.run_pending_background_jobs().await;
+
+    diesel::delete(crate_readers::table)
+        .execute(&mut conn)
+        .await?;
+
+    DumpDbDelta.enqueue(&conn).await?;
+    app.run_pending_background_jobs().await;
+
+    let path = object_store::path::Path::parse("db-dump-deltas/manifest.json")?;
+    let result = app.as_inner().storage.as_inner().get(&path).await?;
+    let index: serde_json::Value = serde_json::from_slice(&result.bytes().await?)?;
+    let deltas = index["deltas"].as_array().unwrap();
+    assert_eq!(deltas.len(), 1);
+
+    let path = object_store::path::Path::parse(deltas[0]["tar"].as_str().unwrap())?;
+    let result = app.as_inner().storage.as_inner().get(&path).await?;
+    let bytes = result.bytes().await?;
+
+    let mut tar = Archive::new(GzDecoder::new(bytes.reader()));
+    let files = tar_files(&mut tar);
+
+    let crates = &files["YYYY-MM-DD-HHMMSS/data/crates.csv"];
+    assert_eq!(crates.lines().count(), 2);
+    assert!(crates.contains(",foo,"));
+
+    let versions = &files["YYYY-MM-DD-HHMMSS/data/versions.csv"];
+    assert_eq!(versions.lines().count(), 2);
+    assert!(versions.contains(",1.0.0,"));
+
+    let crate_owners = &files["YYYY-MM-DD-HHMMSS/data/crate_owners.csv"];
+    assert_eq!(crate_owners.lines().count(), 2);
+
+    Ok(())
+}
+
+/// Returns the contents of the files in the archive by their path.
+fn tar_files<R: Read>(archive: &mut Archive<R>) -> HashMap<String, String> {
+    let path_date_re = regex!(r"^\d{4}-\d{2}-\d{2}-\d{6}");
+
+    archive
+        .entries()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.header().entry_type().is_file())
+        .map(|mut entry| {
+            let path = entry.path().unwrap().display().to_string();
+            let path = path_date_re.replace(&path, "YYYY-MM-DD-HHMMSS").to_string();
+
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+            (path, contents)
+        })
+        .collect()
+}
+
 fn tar_paths<R: Read>(archive: &mut Archive<R>) -> Vec<String> {
     let path_date_re = regex!(r"^\d{4}-\d{2}-\d{2}-\d{6}");
 
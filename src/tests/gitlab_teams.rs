@@ -0,0 +1,340 @@
+use crate::OwnerTeamsResponse;
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{MockAnonymousUser, MockCookieUser, RequestHelper, TestApp};
+use anyhow::anyhow;
+use crates_io::models::{LinkedIdentity, NewLinkedIdentity};
+use crates_io::schema::linked_identities;
+use crates_io_gitlab::{
+    GitLabAuth, GitLabError, GitLabGroup, GitLabGroupMembership, GitLabTokens, MockGitLabClient,
+};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use insta::assert_snapshot;
+use secrecy::ExposeSecret;
+
+const GROUP_ID: i64 = 3000;
+const GROUP_PATH: &str = "test-group/owners";
+
+const MAINTAINER_ACCOUNT_ID: i64 = 1;
+const DEVELOPER_ACCOUNT_ID: i64 = 2;
+const REPORTER_ACCOUNT_ID: i64 = 3;
+
+const EXPIRED_TOKEN: &str = "expired-gitlab-token";
+
+fn gitlab_mock() -> MockGitLabClient {
+    let mut mock = MockGitLabClient::new();
+
+    mock.expect_group_by_path().returning(|path, _auth| {
+        if !path.eq_ignore_ascii_case(GROUP_PATH) {
+            return Err(GitLabError::NotFound(anyhow!("group not found")));
+        }
+
+        Ok(GitLabGroup {
+            id: GROUP_ID,
+            full_name: Some("Test Group / Owners".to_string()),
+            full_path: GROUP_PATH.to_string(),
+            avatar_url: None,
+        })
+    });
+
+    mock.expect_group_membership()
+        .returning(|group_id, user_id, auth| {
+            let GitLabAuth::Bearer { token } = auth;
+            if token.expose_secret() == EXPIRED_TOKEN {
+                return Err(GitLabError::Unauthorized(anyhow!("token expired")));
+            }
+
+            let access_level = match (group_id, user_id) {
+                (GROUP_ID, MAINTAINER_ACCOUNT_ID) => 40,
+                (GROUP_ID, DEVELOPER_ACCOUNT_ID) => 30,
+                (GROUP_ID, REPORTER_ACCOUNT_ID) => 20,
+                _ => return Ok(None),
+            };
+
+            Ok(Some(GitLabGroupMembership {
+                access_level,
+                state: "active".to_string(),
+            }))
+        });
+
+    mock
+}
+
+/// Links a GitLab account with the given ID to the user.
+async fn link_gitlab_account(app: &TestApp, user: &MockCookieUser, account_id: i64) {
+    let conn = app.db_conn().await;
+
+    let encryption = &app.as_inner().config.token_encryption;
+    let encrypted_token = encryption.encrypt("gitlab-token").unwrap();
+
+    NewLinkedIdentity::builder()
+        .provider(LinkedIdentity::GITLAB)
+        .account_id(&account_id.to_string())
+        .user_id(user.as_model().id)
+        .encrypted_token(&encrypted_token)
+        .login(&user.as_model().gh_login)
+        .build()
+        .insert(&conn)
+        .await
+        .unwrap();
+}
+
+async fn crate_owner_teams(anon: &MockAnonymousUser, krate_name: &str) -> OwnerTeamsResponse {
+    let url = format!("/api/v1/crates/{krate_name}/owner_team");
+    anon.get(&url).await.good()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn add_gitlab_group_as_maintainer() {
+    let (app, anon) = TestApp::full().with_gitlab(gitlab_mock()).empty().await;
+    let mut conn = app.db_conn().await;
+
+    let user = app.db_new_user("maintainer").await;
+    link_gitlab_account(&app, &user, MAINTAINER_ACCOUNT_ID).await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let response = user
+        .add_named_owner("foo", "gitlab:Test-Group/Owners")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.text(), @r#"{"msg":"team gitlab:test-group/owners has been added as an owner of crate foo","ok":true}"#);
+
+    let json = crate_owner_teams(&anon, "foo").await;
+    assert_eq!(json.teams.len(), 1);
+    assert_eq!(json.teams[0].login, "gitlab:test-group/owners");
+    assert_eq!(json.teams[0].name.as_deref(), Some("Test Group / Owners"));
+    assert_eq!(
+        json.teams[0].url.as_deref(),
+        Some("https://gitlab.com/test-group/owners")
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn add_gitlab_group_without_linked_account() {
+    let (app, _, user) = TestApp::init().with_gitlab(gitlab_mock()).with_user().await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let response = user
+        .add_named_owner("foo", "gitlab:test-group/owners")
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Cannot add a GitLab group as an owner without a linked GitLab account"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn add_gitlab_group_as_developer() {
+    let (app, _) = TestApp::init().with_gitlab(gitlab_mock()).empty().await;
+    let mut conn = app.db_conn().await;
+
+    let user = app.db_new_user("developer").await;
+    link_gitlab_account(&app, &user, DEVELOPER_ACCOUNT_ID).await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let response = user
+        .add_named_owner("foo", "gitlab:test-group/owners")
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"only maintainers and owners of a group can add it as an owner"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn add_nonexistent_gitlab_group() {
+    let (app, _) = TestApp::init().with_gitlab(gitlab_mock()).empty().await;
+    let mut conn = app.db_conn().await;
+
+    let user = app.db_new_user("maintainer").await;
+    link_gitlab_account(&app, &user, MAINTAINER_ACCOUNT_ID).await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let response = user.add_named_owner("foo", "gitlab:does-not-exist").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"could not find the gitlab group does-not-exist. Make sure that you have the right permissions in GitLab."}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn add_invalid_gitlab_group_paths() {
+    let (app, _, user) = TestApp::init().with_gitlab(gitlab_mock()).with_user().await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let response = user.add_named_owner("foo", "gitlab:").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"missing gitlab group argument; format is gitlab:group/subgroup"}]}"#);
+
+    let response = user.add_named_owner("foo", "gitlab:group/").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"missing gitlab group argument; format is gitlab:group/subgroup"}]}"#);
+
+    let response = user.add_named_owner("foo", "gitlab:group/../other").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"invalid gitlab group path: group/../other"}]}"#);
+
+    let response = user.add_named_owner("foo", "gitlab:group:team").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"group path cannot contain special characters like :"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_owned_by_gitlab_group() {
+    let (app, _) = TestApp::full().with_gitlab(gitlab_mock()).empty().await;
+    let mut conn = app.db_conn().await;
+
+    let maintainer = app.db_new_user("maintainer").await;
+    link_gitlab_account(&app, &maintainer, MAINTAINER_ACCOUNT_ID).await;
+
+    CrateBuilder::new("foo", maintainer.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    maintainer
+        .add_named_owner("foo", "gitlab:test-group/owners")
+        .await
+        .good();
+
+    let developer = app.db_new_user("developer").await;
+    link_gitlab_account(&app, &developer, DEVELOPER_ACCOUNT_ID).await;
+
+    let response = developer
+        .publish_crate(PublishBuilder::new("foo", "2.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let reporter = app.db_new_user("reporter").await;
+    link_gitlab_account(&app, &reporter, REPORTER_ACCOUNT_ID).await;
+
+    let response = reporter
+        .publish_crate(PublishBuilder::new("foo", "3.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+}
+
+/// Links a GitLab account with the given ID to the user, with an access token
+/// that has already expired.
+async fn link_expired_gitlab_account(
+    app: &TestApp,
+    user: &MockCookieUser,
+    account_id: i64,
+    refresh_token: Option<&str>,
+) {
+    let conn = app.db_conn().await;
+
+    let encryption = &app.as_inner().config.token_encryption;
+    let encrypted_token = encryption.encrypt(EXPIRED_TOKEN).unwrap();
+    let encrypted_refresh_token = refresh_token.map(|token| encryption.encrypt(token).unwrap());
+
+    NewLinkedIdentity::builder()
+        .provider(LinkedIdentity::GITLAB)
+        .account_id(&account_id.to_string())
+        .user_id(user.as_model().id)
+        .encrypted_token(&encrypted_token)
+        .maybe_encrypted_refresh_token(encrypted_refresh_token.as_deref())
+        .login(&user.as_model().gh_login)
+        .build()
+        .insert(&conn)
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_with_expired_gitlab_token() {
+    let mut gitlab = gitlab_mock();
+    gitlab
+        .expect_refresh_token()
+        .withf(|refresh_token| refresh_token.expose_secret() == "gitlab-refresh-token")
+        .times(1)
+        .returning(|_| {
+            Ok(GitLabTokens {
+                access_token: "new-gitlab-token".into(),
+                refresh_token: "new-gitlab-refresh-token".into(),
+            })
+        });
+
+    let (app, _) = TestApp::full().with_gitlab(gitlab).empty().await;
+    let mut conn = app.db_conn().await;
+
+    let maintainer = app.db_new_user("maintainer").await;
+    link_gitlab_account(&app, &maintainer, MAINTAINER_ACCOUNT_ID).await;
+
+    CrateBuilder::new("foo", maintainer.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    maintainer
+        .add_named_owner("foo", "gitlab:test-group/owners")
+        .await
+        .good();
+
+    let developer = app.db_new_user("developer").await;
+    let refresh_token = Some("gitlab-refresh-token");
+    link_expired_gitlab_account(&app, &developer, DEVELOPER_ACCOUNT_ID, refresh_token).await;
+
+    let response = developer
+        .publish_crate(PublishBuilder::new("foo", "2.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    // The new tokens are stored, so the next request doesn't refresh them again
+    let (encrypted_token, encrypted_refresh_token) = linked_identities::table
+        .filter(linked_identities::user_id.eq(developer.as_model().id))
+        .select((
+            linked_identities::encrypted_token.assume_not_null(),
+            linked_identities::encrypted_refresh_token.assume_not_null(),
+        ))
+        .first::<(Vec<u8>, Vec<u8>)>(&mut conn)
+        .await
+        .unwrap();
+
+    let encryption = &app.as_inner().config.token_encryption;
+    let token = encryption.decrypt(&encrypted_token).unwrap();
+    assert_eq!(token.expose_secret(), "new-gitlab-token");
+    let refresh_token = encryption.decrypt(&encrypted_refresh_token).unwrap();
+    assert_eq!(refresh_token.expose_secret(), "new-gitlab-refresh-token");
+
+    let response = developer
+        .publish_crate(PublishBuilder::new("foo", "3.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_with_expired_gitlab_token_without_refresh_token() {
+    let (app, _) = TestApp::full().with_gitlab(gitlab_mock()).empty().await;
+    let mut conn = app.db_conn().await;
+
+    let maintainer = app.db_new_user("maintainer").await;
+    link_gitlab_account(&app, &maintainer, MAINTAINER_ACCOUNT_ID).await;
+
+    CrateBuilder::new("foo", maintainer.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    maintainer
+        .add_named_owner("foo", "gitlab:test-group/owners")
+        .await
+        .good();
+
+    let developer = app.db_new_user("developer").await;
+    link_expired_gitlab_account(&app, &developer, DEVELOPER_ACCOUNT_ID, None).await;
+
+    let response = developer
+        .publish_crate(PublishBuilder::new("foo", "2.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"401 Unauthorized");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"It looks like the access token of your linked GitLab account has expired or was revoked. Please link your GitLab account again."}]}"#);
+}
@@ -19,8 +19,11 @@ mod max_size;
 mod rate_limit;
 mod readme;
 mod similar_names;
+mod staged;
 mod tarball;
 mod timestamps;
 mod trustpub_github;
 mod trustpub_gitlab;
+mod trustpub_provenance;
+mod two_factor;
 mod validation;
@@ -1,6 +1,7 @@
 use crate::builders::{CrateBuilder, PublishBuilder};
 use crate::util::{RequestHelper, TestApp};
 use insta::{assert_json_snapshot, assert_snapshot};
+use object_store::ObjectStoreExt;
 
 #[tokio::test(flavor = "multi_thread")]
 async fn new_krate_with_readme() {
@@ -20,12 +21,56 @@ async fn new_krate_with_readme() {
     crates/foo_readme/foo_readme-1.0.0.zip.json
     index/fo/o_/foo_readme
     readmes/foo_readme/foo_readme-1.0.0.html
+    readmes/foo_readme/foo_readme-1.0.0.toc.json
     rss/crates.xml
     rss/crates/foo_readme.xml
     rss/updates.xml
     ");
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn new_krate_with_readme_table_of_contents() {
+    let (app, _, _, token) = TestApp::full().with_token().await;
+
+    let readme = "# Usage\n\n```toml\n[dependencies]\nfoo = \"1\"\n```\n\n## Usage";
+    let crate_to_publish = PublishBuilder::new("foo_readme", "1.0.0").readme(readme);
+    token.publish_crate(crate_to_publish).await.good();
+
+    let store = app.as_inner().storage.as_inner();
+    let result = store
+        .get(&"readmes/foo_readme/foo_readme-1.0.0.html".into())
+        .await
+        .unwrap();
+    let html = String::from_utf8(result.bytes().await.unwrap().to_vec()).unwrap();
+    assert_snapshot!(html, @r##"
+    <h1 id="user-content-usage">Usage<a href="#user-content-usage" aria-label="Link to heading 'Usage'" rel="nofollow noopener noreferrer"></a></h1>
+    <pre><code class="language-toml"><span class="hl-table">[dependencies]</span>
+    <span class="hl-property">foo</span> = <span class="hl-string">"1"</span>
+    </code></pre>
+    <h2 id="user-content-usage-1">Usage<a href="#user-content-usage-1" aria-label="Link to heading 'Usage'" rel="nofollow noopener noreferrer"></a></h2>
+    "##);
+
+    let result = store
+        .get(&"readmes/foo_readme/foo_readme-1.0.0.toc.json".into())
+        .await
+        .unwrap();
+    let toc: serde_json::Value = serde_json::from_slice(&result.bytes().await.unwrap()).unwrap();
+    assert_json_snapshot!(toc, @r#"
+    [
+      {
+        "id": "user-content-usage",
+        "level": 1,
+        "title": "Usage"
+      },
+      {
+        "id": "user-content-usage-1",
+        "level": 2,
+        "title": "Usage"
+      }
+    ]
+    "#);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn new_krate_with_empty_readme() {
     let (app, _, _, token) = TestApp::full().with_token().await;
@@ -67,6 +112,7 @@ async fn new_krate_with_readme_and_plus_version() {
     crates/foo_readme/foo_readme-1.0.0+foo.zip.json
     index/fo/o_/foo_readme
     readmes/foo_readme/foo_readme-1.0.0+foo.html
+    readmes/foo_readme/foo_readme-1.0.0+foo.toc.json
     rss/crates.xml
     rss/crates/foo_readme.xml
     rss/updates.xml
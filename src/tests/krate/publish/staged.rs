@@ -0,0 +1,301 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{MockTokenUser, RequestHelper, TestApp};
+use claims::{assert_none, assert_some};
+use crates_io::models::{Crate, CrateOwner};
+use crates_io::schema::{crate_owners, crates, versions};
+use crates_io::views::GoodCrate;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use googletest::prelude::*;
+use insta::{assert_json_snapshot, assert_snapshot};
+use serde_json::{Value, json};
+
+const PROMOTE_URL: &str = "/api/v1/crates/foo/1.1.0/promote";
+
+async fn publish_staged(app: &TestApp, token: &MockTokenUser, version: &str) {
+    let pb = PublishBuilder::new("foo", version);
+    let response = token
+        .put::<GoodCrate>("/api/v1/crates/new?staged=true", pb)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    app.run_pending_background_jobs().await;
+}
+
+fn index_versions(app: &TestApp) -> Vec<String> {
+    let crates = app.crates_from_index_head("foo");
+    crates.into_iter().map(|krate| krate.vers).collect()
+}
+
+async fn listed_versions(anon: &impl RequestHelper) -> Vec<String> {
+    let json = anon
+        .get::<Value>("/api/v1/crates/foo/versions")
+        .await
+        .good();
+    let versions = json["versions"].as_array().unwrap();
+    versions
+        .iter()
+        .map(|version| version["num"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn staged_version_is_hidden_until_promoted() {
+    let (app, anon, _, token) = TestApp::full().with_git_index().with_token().await;
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    let pb = PublishBuilder::new("foo", "1.1.0");
+    let response = token
+        .put::<GoodCrate>("/api/v1/crates/new?staged=true", pb)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json()["warnings"], @r#"
+    {
+      "invalid_badges": [],
+      "invalid_categories": [],
+      "other": [
+        "foo@1.1.0 was uploaded as a staged version, and will not be available until an owner of the crate promotes it"
+      ]
+    }
+    "#);
+    assert_eq!(response.json()["crate"]["default_version"], "1.0.0");
+    app.run_pending_background_jobs().await;
+
+    assert_eq!(index_versions(&app), ["1.0.0"]);
+    assert_eq!(listed_versions(&anon).await, ["1.0.0"]);
+
+    let json = anon.show_crate("foo").await;
+    assert_eq!(json.krate.default_version.as_deref(), Some("1.0.0"));
+    assert_eq!(json.krate.max_version, "1.0.0");
+
+    // The staged version can still be inspected directly
+    let json = anon.show_version("foo", "1.1.0").await;
+    assert_some!(json.version.staged_at);
+
+    let response = token.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.text(), @r#"{"ok":true}"#);
+    app.run_pending_background_jobs().await;
+
+    assert_eq!(index_versions(&app), ["1.0.0", "1.1.0"]);
+    assert_eq!(listed_versions(&anon).await, ["1.1.0", "1.0.0"]);
+
+    let json = anon.show_crate("foo").await;
+    assert_eq!(json.krate.default_version.as_deref(), Some("1.1.0"));
+
+    let json = anon.show_version("foo", "1.1.0").await;
+    assert_none!(json.version.staged_at);
+
+    let actions = json.version.audit_actions;
+    let actions = actions
+        .iter()
+        .map(|a| a.action.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(actions, ["publish", "promote"]);
+
+    // Promoting the version a second time fails
+    let response = token.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"version `1.1.0` of crate `foo` is not staged"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn staged_publish_of_new_crate_fails() {
+    let (app, _, _, token) = TestApp::full().with_token().await;
+
+    let pb = PublishBuilder::new("foo", "1.0.0");
+    let response = token
+        .put::<GoodCrate>("/api/v1/crates/new?staged=true", pb)
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Staged publishing is only supported for new versions of existing crates"}]}"#);
+    assert_that!(app.stored_files().await, is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn non_owner_cannot_promote() {
+    let (app, anon, user, token) = TestApp::full().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .version("1.0.0")
+        .expect_build(&mut conn)
+        .await;
+
+    publish_staged(&app, &token, "1.1.0").await;
+
+    let response = anon.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    let other = app.db_new_user("other").await;
+    let response = other.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"must already be an owner to promote a staged version"}]}"#);
+
+    assert_eq!(listed_versions(&anon).await, ["1.0.0"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn promotion_review_requires_second_owner() {
+    let (app, anon, user, token) = TestApp::full().with_git_index().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    let body = json!({ "crate": { "require_promotion_review": true } });
+    let response = user
+        .patch::<()>("/api/v1/crates/foo", body.to_string())
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(app.emails_snapshot().await);
+
+    // Versions of crates with promotion reviews are staged automatically
+    let response = token
+        .publish_crate(PublishBuilder::new("foo", "1.1.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(index_versions(&app), ["1.0.0"]);
+
+    let response = token.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"this crate requires staged versions to be promoted by a different owner than the one who published them"}]}"#);
+
+    let reviewer = app.db_new_user("reviewer").await;
+    let krate: Crate = Crate::by_name("foo").first(&mut conn).await.unwrap();
+    CrateOwner::builder()
+        .crate_id(krate.id)
+        .user_id(reviewer.as_model().id)
+        .created_by(user.as_model().id)
+        .build()
+        .insert(&conn)
+        .await
+        .unwrap();
+
+    let response = reviewer.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    app.run_pending_background_jobs().await;
+
+    assert_eq!(index_versions(&app), ["1.0.0", "1.1.0"]);
+    assert_eq!(listed_versions(&anon).await, ["1.1.0", "1.0.0"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn promotion_review_without_publisher_requires_two_owners() {
+    let (app, anon, user, token) = TestApp::full().with_git_index().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    diesel::update(crates::table)
+        .set(crates::require_promotion_review.eq(true))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    // Versions of crates with promotion reviews are staged automatically
+    let response = token
+        .publish_crate(PublishBuilder::new("foo", "1.1.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    // Versions published via Trusted Publishing have no publisher
+    diesel::update(versions::table)
+        .filter(versions::num.eq("1.1.0"))
+        .set(versions::published_by.eq(None::<i32>))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let response = user.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"202 Accepted");
+
+    // Approving the promotion twice does not count as a second owner
+    let response = token.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"202 Accepted");
+    app.run_pending_background_jobs().await;
+    assert_eq!(index_versions(&app), ["1.0.0"]);
+    assert_eq!(listed_versions(&anon).await, ["1.0.0"]);
+
+    let reviewer = app.db_new_user("reviewer").await;
+    let krate: Crate = Crate::by_name("foo").first(&mut conn).await.unwrap();
+    CrateOwner::builder()
+        .crate_id(krate.id)
+        .user_id(reviewer.as_model().id)
+        .created_by(user.as_model().id)
+        .build()
+        .insert(&conn)
+        .await
+        .unwrap();
+
+    let response = reviewer.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    app.run_pending_background_jobs().await;
+
+    assert_eq!(index_versions(&app), ["1.0.0", "1.1.0"]);
+    assert_eq!(listed_versions(&anon).await, ["1.1.0", "1.0.0"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn promotion_review_ignores_approvals_of_former_owners() {
+    let (app, _, user, token) = TestApp::full().with_git_index().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    diesel::update(crates::table)
+        .set(crates::require_promotion_review.eq(true))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let response = token
+        .publish_crate(PublishBuilder::new("foo", "1.1.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    diesel::update(versions::table)
+        .filter(versions::num.eq("1.1.0"))
+        .set(versions::published_by.eq(None::<i32>))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let reviewer = app.db_new_user("reviewer").await;
+    let krate: Crate = Crate::by_name("foo").first(&mut conn).await.unwrap();
+    CrateOwner::builder()
+        .crate_id(krate.id)
+        .user_id(reviewer.as_model().id)
+        .created_by(user.as_model().id)
+        .build()
+        .insert(&conn)
+        .await
+        .unwrap();
+
+    let response = reviewer.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"202 Accepted");
+
+    diesel::update(crate_owners::table)
+        .filter(crate_owners::owner_id.eq(reviewer.as_model().id))
+        .set(crate_owners::deleted.eq(true))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    // The approval of the removed owner does not count anymore
+    let response = user.put::<()>(PROMOTE_URL, "").await;
+    assert_snapshot!(response.status(), @"202 Accepted");
+    app.run_pending_background_jobs().await;
+    assert_eq!(index_versions(&app), ["1.0.0"]);
+}
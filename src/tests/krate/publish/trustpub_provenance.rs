@@ -0,0 +1,212 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{MockTokenUser, RequestHelper, TestApp};
+use chrono::{TimeDelta, Utc};
+use claims::assert_ok;
+use crates_io::models::TrustpubData;
+use crates_io_database::models::trustpub::NewToken;
+use crates_io_trustpub::access_token::AccessToken;
+use crates_io_trustpub::provenance::{Envelope, ProvenanceSigner};
+use diesel_async::AsyncPgConnection;
+use insta::{assert_json_snapshot, assert_snapshot};
+use secrecy::ExposeSecret;
+
+async fn new_token(
+    conn: &mut AsyncPgConnection,
+    crate_id: i32,
+    trustpub_data: Option<&TrustpubData>,
+) -> anyhow::Result<String> {
+    let token = AccessToken::generate();
+    let hashed_token = token.sha256();
+
+    let new_token = NewToken {
+        expires_at: Utc::now() + TimeDelta::minutes(30),
+        hashed_token: hashed_token.as_slice(),
+        crate_ids: &[crate_id],
+        trustpub_data,
+    };
+
+    new_token.insert(conn).await?;
+
+    Ok(token.finalize().expose_secret().to_string())
+}
+
+fn github_data() -> TrustpubData {
+    TrustpubData::GitHub {
+        repository: "rust-lang/foo-rs".into(),
+        run_id: "42".into(),
+        sha: "9f3182061f1e2cca4702c368cbc039b7dc9d4485".into(),
+        workflow_ref: Some(
+            "rust-lang/foo-rs/.github/workflows/publish.yml@refs/tags/v1.1.0".into(),
+        ),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_provenance_is_issued() -> anyhow::Result<()> {
+    let (app, client, cookie_client) = TestApp::full().with_user().await;
+
+    let mut conn = app.db_conn().await;
+
+    let owner_id = cookie_client.as_model().id;
+    let krate = CrateBuilder::new("foo", owner_id).build(&mut conn).await?;
+
+    let token = new_token(&mut conn, krate.id, Some(&github_data())).await?;
+    let oidc_token_client = MockTokenUser::with_auth_header(token, app);
+
+    let pb = PublishBuilder::new(&krate.name, "1.1.0");
+    let response = oidc_token_client.publish_crate(pb).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = client.get::<()>("/api/v1/crates/foo/1.1.0").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let checksum = response.json()["version"]["checksum"].clone();
+
+    let response = client
+        .get::<()>("/api/v1/crates/foo/1.1.0/provenance")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let json = response.json();
+    assert_json_snapshot!(json, {
+        ".provenance.created_at" => "[datetime]",
+        ".provenance.envelope.payload" => "[payload]",
+        ".provenance.envelope.signatures[].sig" => "[signature]",
+    }, @r#"
+    {
+      "provenance": {
+        "created_at": "[datetime]",
+        "envelope": {
+          "payload": "[payload]",
+          "payloadType": "application/vnd.in-toto+json",
+          "signatures": [
+            {
+              "keyid": "b7ee70b69a694170739dc4d5140246892f0d30d2d03522cadd0f6f932a31dbe3",
+              "sig": "[signature]"
+            }
+          ]
+        },
+        "key_id": "b7ee70b69a694170739dc4d5140246892f0d30d2d03522cadd0f6f932a31dbe3"
+      }
+    }
+    "#);
+
+    // The attestation can be verified with the public key of crates.io
+    let signer = ProvenanceSigner::for_testing();
+    let envelope: Envelope = serde_json::from_value(json["provenance"]["envelope"].clone())?;
+    let statement = assert_ok!(envelope.verify(signer.public_key()));
+
+    assert_eq!(
+        statement.subject[0].name.as_deref(),
+        Some("foo-1.1.0.crate")
+    );
+    assert_eq!(statement.subject[0].digest["sha256"], checksum);
+    assert_json_snapshot!(statement.predicate, @r#"
+    {
+      "buildDefinition": {
+        "buildType": "https://crates.io/trusted-publishing/provenance/v1",
+        "externalParameters": {
+          "issuer": "https://token.actions.githubusercontent.com",
+          "repository": "rust-lang/foo-rs",
+          "workflow": ".github/workflows/publish.yml",
+          "ref": "refs/tags/v1.1.0"
+        },
+        "resolvedDependencies": [
+          {
+            "uri": "git+https://github.com/rust-lang/foo-rs@refs/tags/v1.1.0",
+            "digest": {
+              "gitCommit": "9f3182061f1e2cca4702c368cbc039b7dc9d4485"
+            }
+          }
+        ]
+      },
+      "runDetails": {
+        "builder": {
+          "id": "https://token.actions.githubusercontent.com"
+        },
+        "metadata": {
+          "invocationId": "https://github.com/rust-lang/foo-rs/actions/runs/42"
+        }
+      }
+    }
+    "#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_no_provenance_without_trustpub_data() -> anyhow::Result<()> {
+    let (app, client, cookie_client) = TestApp::full().with_user().await;
+
+    let mut conn = app.db_conn().await;
+
+    let owner_id = cookie_client.as_model().id;
+    let krate = CrateBuilder::new("foo", owner_id).build(&mut conn).await?;
+
+    let token = new_token(&mut conn, krate.id, None).await?;
+    let oidc_token_client = MockTokenUser::with_auth_header(token, app);
+
+    let pb = PublishBuilder::new(&krate.name, "1.1.0");
+    let response = oidc_token_client.publish_crate(pb).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = client
+        .get::<()>("/api/v1/crates/foo/1.1.0/provenance")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_no_provenance_for_api_token_publish() -> anyhow::Result<()> {
+    let (_app, client, _cookie_client, token_client) = TestApp::full().with_token().await;
+
+    let pb = PublishBuilder::new("foo", "1.0.0");
+    let response = token_client.publish_crate(pb).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = client
+        .get::<()>("/api/v1/crates/foo/1.0.0/provenance")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"Not Found"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unknown_version() -> anyhow::Result<()> {
+    let (_app, client) = TestApp::full().empty().await;
+
+    let response = client
+        .get::<()>("/api/v1/crates/foo/1.0.0/provenance")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"crate `foo` does not exist"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_provenance_key() -> anyhow::Result<()> {
+    let (_app, client) = TestApp::full().empty().await;
+
+    let response = client
+        .get::<()>("/api/v1/trusted_publishing/provenance_key")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "key": {
+        "algorithm": "ed25519",
+        "key_id": "b7ee70b69a694170739dc4d5140246892f0d30d2d03522cadd0f6f932a31dbe3",
+        "public_key": "7p2xyCP3pAhyOVQd9cirx5BncxMLlGx8zBPYCCtrAuE="
+      }
+    }
+    "#);
+
+    let signer = ProvenanceSigner::for_testing();
+    assert_eq!(response.json()["key"]["key_id"], signer.key_id());
+
+    Ok(())
+}
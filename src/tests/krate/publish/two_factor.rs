@@ -0,0 +1,36 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::routes::me::two_factor::enable_totp;
+use crate::util::{RequestHelper, TestApp};
+use crates_io::schema::crates;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use insta::assert_snapshot;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_requires_two_factor() {
+    let (app, _, user, token) = TestApp::full().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    let krate = CrateBuilder::new("foo", user.as_model().id)
+        .version("1.0.0")
+        .expect_build(&mut conn)
+        .await;
+
+    diesel::update(crates::table.find(krate.id))
+        .set(crates::require_owner_2fa.eq(true))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let crate_to_publish = PublishBuilder::new("foo", "1.1.0");
+    let response = token.publish_crate(crate_to_publish).await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"New versions of this crate can only be published by users with two-factor authentication enabled."}]}"#);
+    assert_eq!(app.stored_files().await.len(), 0);
+
+    enable_totp(&app, &user).await;
+
+    let crate_to_publish = PublishBuilder::new("foo", "1.1.0");
+    let response = token.publish_crate(crate_to_publish).await;
+    assert_snapshot!(response.status(), @"200 OK");
+}
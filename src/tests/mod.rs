@@ -16,6 +16,7 @@ mod categories;
 mod cors;
 mod dump_db;
 mod github_secret_scanning;
+mod gitlab_teams;
 mod issues;
 mod krate;
 mod middleware;
@@ -23,6 +24,7 @@ mod not_found_error;
 mod openapi;
 mod owners;
 mod pagination;
+mod private_registry;
 mod read_only_mode;
 mod routes;
 mod server;
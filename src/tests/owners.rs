@@ -1,8 +1,9 @@
 use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::routes::me::two_factor::enable_totp;
 use crate::util::{MockAnonymousUser, MockCookieUser, MockTokenUser, RequestHelper, Response};
 use crate::{TestApp, add_team_to_crate, new_team};
 use crates_io::models::{Crate, CrateOwner};
-use crates_io::schema::emails;
+use crates_io::schema::{crates, emails};
 use crates_io::views::{
     EncodableCrateOwnerInvitationV1, EncodableOwner, EncodablePublicUser, InvitationResponse,
 };
@@ -173,7 +174,7 @@ async fn create_and_add_owner(
 /// a user can still remove their own login as an owner.
 #[tokio::test(flavor = "multi_thread")]
 async fn owners_can_remove_self() {
-    let (app, _, user, token) = TestApp::init().with_token().await;
+    let (app, _, user, token) = TestApp::full().with_token().await;
     let mut conn = app.db_conn().await;
     let username = &user.as_model().gh_login;
 
@@ -208,7 +209,7 @@ async fn owners_can_remove_self() {
 /// Verifies consistency when adding or removing multiple owners in a single request.
 #[tokio::test(flavor = "multi_thread")]
 async fn modify_multiple_owners() -> anyhow::Result<()> {
-    let (app, _, user, token) = TestApp::init().with_token().await;
+    let (app, _, user, token) = TestApp::full().with_token().await;
     let mut conn = app.db_conn().await;
     let username = &user.as_model().gh_login;
 
@@ -504,7 +505,7 @@ async fn invitations_list_does_not_include_expired_invites_v1() {
 /// inserted into the table for the given crate.
 #[tokio::test(flavor = "multi_thread")]
 async fn test_accept_invitation() {
-    let (app, anon, owner, owner_token) = TestApp::init().with_token().await;
+    let (app, anon, owner, owner_token) = TestApp::full().with_token().await;
     let mut conn = app.db_conn().await;
     let owner = owner.as_model();
     let invited_user = app.db_new_user("user_bar").await;
@@ -570,7 +571,7 @@ async fn test_decline_invitation() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_accept_invitation_by_mail() {
-    let (app, anon, owner, owner_token) = TestApp::init().with_token().await;
+    let (app, anon, owner, owner_token) = TestApp::full().with_token().await;
     let mut conn = app.db_conn().await;
 
     let owner = owner.as_model();
@@ -745,6 +746,50 @@ async fn test_accept_invitation_without_verified_email() {
     assert_eq!(json.users.len(), 1);
 }
 
+/// Given a crate that requires two-factor authentication for all owners,
+/// check that an invited user cannot accept their invitation before they
+/// have enabled it.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_accept_invitation_without_two_factor() {
+    let (app, anon, owner, owner_token) = TestApp::full().with_token().await;
+    let mut conn = app.db_conn().await;
+    let owner = owner.as_model();
+
+    let invited_user = app.db_new_user("user_no_2fa").await;
+    let krate = CrateBuilder::new("foo", owner.id)
+        .expect_build(&mut conn)
+        .await;
+
+    owner_token
+        .add_named_owner("foo", "user_no_2fa")
+        .await
+        .good();
+
+    // The policy is enabled after the invitation was sent
+    diesel::update(crates::table.find(krate.id))
+        .set(crates::require_owner_2fa.eq(true))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let response = invited_user
+        .try_accept_ownership_invitation::<()>(&krate.name, krate.id)
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"You need to enable two-factor authentication before you can accept the invitation to become an owner of the foo crate."}]}"#);
+
+    let json = anon.show_crate_owners("foo").await;
+    assert_eq!(json.users.len(), 1);
+
+    enable_totp(&app, &invited_user).await;
+    invited_user
+        .accept_ownership_invitation(&krate.name, krate.id)
+        .await;
+
+    let json = anon.show_crate_owners("foo").await;
+    assert_eq!(json.users.len(), 2);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_accept_expired_invitation_by_mail() {
     let (app, anon, owner, owner_token) = TestApp::init().with_token().await;
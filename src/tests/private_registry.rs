@@ -0,0 +1,386 @@
+use crate::builders::{DependencyBuilder, PublishBuilder};
+use crate::new_category;
+use crate::util::{MockAnonymousUser, MockTokenUser, RequestHelper, TestApp};
+use crates_io::schema::{categories, crate_downloads};
+use crates_io::worker::jobs;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel::{insert_into, update};
+use diesel_async::RunQueryDsl;
+use http::StatusCode;
+use insta::{assert_json_snapshot, assert_snapshot};
+use object_store::ObjectStoreExt;
+use serde_json::{Value, json};
+
+async fn private_app() -> (TestApp, MockAnonymousUser, MockTokenUser) {
+    let (app, anon, _, token) = TestApp::full()
+        .with_config(|config| config.private_registry = true)
+        .with_token()
+        .await;
+
+    let pb = PublishBuilder::new("foo", "1.0.0");
+    token.publish_crate(pb).await.good();
+    app.run_pending_background_jobs().await;
+
+    (app, anon, token)
+}
+
+async fn search(token: &MockTokenUser) -> Vec<String> {
+    let json = token.get::<()>("/api/v1/crates").await.json();
+    json["crates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|krate| krate["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn crates_cnt(token: &MockTokenUser) -> (Value, Value) {
+    let keyword = token.get::<()>("/api/v1/keywords/kw1").await.json();
+    let category = token.get::<()>("/api/v1/categories/cat1").await.json();
+    (
+        keyword["keyword"]["crates_cnt"].clone(),
+        category["category"]["crates_cnt"].clone(),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reads_require_authentication() {
+    let (_app, anon, token) = private_app().await;
+
+    let response = anon.get::<()>("/api/v1/crates").await;
+    assert_snapshot!(response.status(), @"401 Unauthorized");
+    assert_snapshot!(response.headers()["www-authenticate"].to_str().unwrap(), @r#"Cargo login_url="https://crates.io/settings/tokens""#);
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"this registry requires authentication"}]}"#);
+
+    let response = anon.get::<()>("/api/v1/crates/foo/1.0.0/download").await;
+    assert_snapshot!(response.status(), @"401 Unauthorized");
+
+    let response = anon.get::<()>("/index/config.json").await;
+    assert_snapshot!(response.status(), @"401 Unauthorized");
+
+    // Signing in must still be possible
+    let response = anon.get::<()>("/api/v1/site_metadata").await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    assert_eq!(search(&token).await, ["foo"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sparse_index() {
+    let (_app, _anon, token) = private_app().await;
+
+    let response = token.get::<()>("/index/config.json").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "api": "https://crates.io",
+      "auth-required": true,
+      "dl": "https://crates.io/api/v1/crates"
+    }
+    "#);
+
+    let response = token.get::<()>("/index/3/f/foo").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert!(response.text().contains(r#""name":"foo","vers":"1.0.0""#));
+
+    let response = token.get::<()>("/index/fo/o/foo").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+
+    let response = token.get::<()>("/index/3/b/bar").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn download_serves_crate_file() {
+    let (_app, _anon, token) = private_app().await;
+
+    let response = token.get::<()>("/api/v1/crates/foo/1.0.0/download").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.headers()["content-type"].to_str().unwrap(), @"application/gzip");
+    assert!(!response.body().is_empty());
+
+    let response = token.get::<()>("/api/v1/crates/foo/2.0.0/download").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restricted_crates() {
+    let (app, _anon, owner) = private_app().await;
+
+    let reader = app.db_new_user("reader").await;
+    let reader = reader.db_new_token("reader").await;
+
+    let other = app.db_new_user("other").await;
+    let other = other.db_new_token("other").await;
+
+    let body = json!({ "users": ["reader"] }).to_string();
+    let response = other
+        .put::<()>("/api/v1/crates/foo/readers", body.clone())
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"only owners have permission to manage the readers of a crate"}]}"#);
+
+    let response = owner.put::<()>("/api/v1/crates/foo/readers", body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".users[].created_at" => "[datetime]",
+    }, @r#"
+    {
+      "users": [
+        {
+          "avatar": null,
+          "created_at": "[datetime]",
+          "github_username_matches": true,
+          "id": 2,
+          "login": "reader",
+          "name": null,
+          "url": "https://github.com/reader"
+        }
+      ]
+    }
+    "#);
+
+    // Owners and readers can still read the crate
+    for user in [&owner, &reader] {
+        assert_eq!(search(user).await, ["foo"]);
+
+        let response = user.get::<()>("/api/v1/crates/foo/1.0.0/download").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = user.get::<()>("/index/3/f/foo").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Other users can not
+    assert_eq!(search(&other).await, Vec::<String>::new());
+
+    let response = other.get::<()>("/api/v1/crates/foo/1.0.0/download").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+
+    let response = other.get::<()>("/index/3/f/foo").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+
+    // Removing the last reader lifts the restriction
+    let body = json!({ "users": ["reader"] }).to_string();
+    let response = owner
+        .delete_with_body::<()>("/api/v1/crates/foo/readers", body)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "users": []
+    }
+    "#);
+
+    assert_eq!(search(&other).await, ["foo"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restricted_crates_are_hidden_from_other_users() {
+    let (app, _anon, owner) = private_app().await;
+
+    let pb = PublishBuilder::new("bar", "1.0.0").dependency(DependencyBuilder::new("foo"));
+    owner.publish_crate(pb).await.good();
+
+    let reader = app.db_new_user("reader").await;
+    let reader = reader.db_new_token("reader").await;
+
+    let other = app.db_new_user("other").await;
+    let other = other.db_new_token("other").await;
+
+    let body = json!({ "users": ["reader"] }).to_string();
+    let response = owner.put::<()>("/api/v1/crates/bar/readers", body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    app.run_pending_background_jobs().await;
+
+    let urls = [
+        "/api/v1/crates/bar",
+        "/api/v1/crates/bar/versions",
+        "/api/v1/crates/bar/downloads",
+        "/api/v1/crates/bar/owners",
+        "/api/v1/crates/bar/owner_user",
+        "/api/v1/crates/bar/owner_team",
+        "/api/v1/crates/bar/reverse_dependencies",
+        "/api/v1/crates/bar/1.0.0",
+        "/api/v1/crates/bar/1.0.0/readme",
+        "/api/v1/crates/bar/1.0.0/dependencies",
+        "/api/v1/crates/bar/1.0.0/files",
+        "/api/v1/crates/bar/1.0.0/files/src/lib.rs",
+        "/api/v1/crates/bar/1.0.0/diff?base=1.0.0",
+        "/api/v1/crates/bar/1.0.0/download",
+    ];
+
+    for url in urls {
+        let response = other.get::<()>(url).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{url}");
+        assert_eq!(
+            response.text(),
+            r#"{"errors":[{"detail":"crate `bar` does not exist"}]}"#,
+            "{url}"
+        );
+    }
+
+    for user in [&owner, &reader] {
+        let response = user.get::<()>("/api/v1/crates/bar").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = user.get::<()>("/api/v1/crates/bar/versions").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Restricted crates are left out of the reverse dependencies of other crates
+    let url = "/api/v1/crates/foo/reverse_dependencies";
+    let json = reader.get::<()>(url).await.json();
+    assert_eq!(json["meta"]["total"], 1);
+    assert_eq!(json["versions"][0]["crate"], "bar");
+
+    let json = other.get::<()>(url).await.json();
+    assert_eq!(json["meta"]["total"], 0);
+    assert_eq!(json["dependencies"], json!([]));
+
+    // The summary is cached publicly, so it leaves them out for everyone
+    let json = reader.get::<()>("/api/v1/summary").await.json();
+    assert_eq!(json["num_crates"], 1);
+    let names = |list: &Value| {
+        list.as_array()
+            .unwrap()
+            .iter()
+            .map(|krate| krate["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&json["new_crates"]), ["foo"]);
+    assert_eq!(names(&json["most_downloaded"]), ["foo"]);
+    assert_eq!(names(&json["just_updated"]), Vec::<String>::new());
+
+    // ... and so are the RSS feeds
+    let conn = app.db_conn().await;
+    jobs::rss::SyncCratesFeed.enqueue(&conn).await.unwrap();
+    jobs::rss::SyncUpdatesFeed.enqueue(&conn).await.unwrap();
+    app.run_pending_background_jobs().await;
+
+    let files = app.stored_files().await;
+    assert!(files.contains(&"rss/crates/foo.xml".to_string()));
+    assert!(!files.contains(&"rss/crates/bar.xml".to_string()));
+
+    let store = app.as_inner().storage.as_inner();
+    for path in ["rss/crates.xml", "rss/updates.xml"] {
+        let bytes = store
+            .get(&path.into())
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let content = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(content.contains("<crates:name>foo</crates:name>"), "{path}");
+        assert!(
+            !content.contains("<crates:name>bar</crates:name>"),
+            "{path}"
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restricted_crates_are_left_out_of_updates_stats_and_counts() {
+    let (app, _anon, owner) = private_app().await;
+    let mut conn = app.db_conn().await;
+
+    insert_into(categories::table)
+        .values(new_category("Category 1", "cat1", "Category 1 crates"))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let pb = PublishBuilder::new("bar", "1.0.0")
+        .keyword("kw1")
+        .category("cat1");
+    owner.publish_crate(pb).await.good();
+
+    update(crate_downloads::table)
+        .set(crate_downloads::downloads.eq(10))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let reader = app.db_new_user("reader").await;
+    let other = app.db_new_user("other").await;
+    for user in [&reader, &other] {
+        let response = user
+            .put::<()>("/api/v1/crates/bar/follow", b"" as &[u8])
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    assert_eq!(crates_cnt(&owner).await, (json!(1), json!(1)));
+
+    let body = json!({ "users": ["reader"] }).to_string();
+    let response = owner
+        .put::<()>("/api/v1/crates/bar/readers", body.clone())
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    // Only the readers of the crate see its versions in their followed updates
+    let json = reader.get::<()>("/api/v1/me/updates").await.json();
+    assert_eq!(json["versions"][0]["crate"], "bar");
+
+    let json = other.get::<()>("/api/v1/me/updates").await.json();
+    assert_eq!(json["versions"], json!([]));
+
+    // ... and the downloads of the crate in the stats of its owners
+    let url = format!("/api/v1/users/{}/stats", owner.as_model().user_id);
+    let json = reader.get::<()>(&url).await.json();
+    assert_eq!(json["total_downloads"], 20);
+
+    let json = other.get::<()>(&url).await.json();
+    assert_eq!(json["total_downloads"], 10);
+
+    // Keywords and categories don't count restricted crates
+    assert_eq!(crates_cnt(&owner).await, (json!(0), json!(0)));
+
+    let response = owner
+        .delete_with_body::<()>("/api/v1/crates/bar/readers", body)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    assert_eq!(crates_cnt(&owner).await, (json!(1), json!(1)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn readers_require_private_registry_mode() {
+    let (app, _anon, _user, token) = TestApp::full().with_token().await;
+
+    let pb = PublishBuilder::new("foo", "1.0.0");
+    token.publish_crate(pb).await.good();
+    app.run_pending_background_jobs().await;
+
+    let response = token.get::<()>("/api/v1/crates/foo/readers").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"read access can only be restricted in private registry mode"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restricted_crates_are_left_out_of_git_index() {
+    let (app, _anon, _user, token) = TestApp::full()
+        .with_config(|config| config.private_registry = true)
+        .with_git_index()
+        .with_token()
+        .await;
+
+    let pb = PublishBuilder::new("foo", "1.0.0");
+    token.publish_crate(pb).await.good();
+    app.run_pending_background_jobs().await;
+    assert!(app.upstream_index().crate_exists("foo").unwrap());
+
+    app.db_new_user("reader").await;
+
+    let body = json!({ "users": ["reader"] }).to_string();
+    let response = token.put::<()>("/api/v1/crates/foo/readers", body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    app.run_pending_background_jobs().await;
+    assert!(!app.upstream_index().crate_exists("foo").unwrap());
+
+    // The crate is still available via the sparse index
+    let response = token.get::<()>("/index/3/f/foo").await;
+    assert_snapshot!(response.status(), @"200 OK");
+}
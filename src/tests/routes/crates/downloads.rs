@@ -1,7 +1,10 @@
 use crate::builders::{CrateBuilder, VersionBuilder};
 use crate::util::{MockAnonymousUser, RequestHelper, TestApp};
 use chrono::{Duration, Utc};
-use crates_io::schema::{crates, version_downloads, versions};
+use crates_io::models::download::DownloadClient;
+use crates_io::schema::{
+    cargo_version_downloads, client_downloads, crates, version_downloads, versions,
+};
 use crates_io::views::EncodableVersionDownload;
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
@@ -158,6 +161,92 @@ async fn test_crate_downloads() {
     assert_eq!(response.json(), json);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_crate_downloads_by_client() {
+    let (app, anon, cookie) = TestApp::init().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let user_id = cookie.as_model().id;
+    let krate = CrateBuilder::new("foo", user_id)
+        .version("1.0.0")
+        .expect_build(&mut conn)
+        .await;
+
+    let today = Utc::now().date_naive();
+    let long_ago = today - Duration::days(100);
+
+    diesel::insert_into(cargo_version_downloads::table)
+        .values(vec![
+            (
+                cargo_version_downloads::crate_id.eq(krate.id),
+                cargo_version_downloads::date.eq(today),
+                cargo_version_downloads::cargo_version.eq("1.85"),
+                cargo_version_downloads::downloads.eq(7),
+            ),
+            (
+                cargo_version_downloads::crate_id.eq(krate.id),
+                cargo_version_downloads::date.eq(today),
+                cargo_version_downloads::cargo_version.eq("1.70"),
+                cargo_version_downloads::downloads.eq(2),
+            ),
+            (
+                cargo_version_downloads::crate_id.eq(krate.id),
+                cargo_version_downloads::date.eq(long_ago),
+                cargo_version_downloads::cargo_version.eq("1.60"),
+                cargo_version_downloads::downloads.eq(5),
+            ),
+        ])
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    diesel::insert_into(client_downloads::table)
+        .values(vec![
+            (
+                client_downloads::crate_id.eq(krate.id),
+                client_downloads::date.eq(today),
+                client_downloads::client.eq(DownloadClient::Cargo),
+                client_downloads::downloads.eq(6),
+            ),
+            (
+                client_downloads::crate_id.eq(krate.id),
+                client_downloads::date.eq(today),
+                client_downloads::client.eq(DownloadClient::Ci),
+                client_downloads::downloads.eq(3),
+            ),
+            (
+                client_downloads::crate_id.eq(krate.id),
+                client_downloads::date.eq(today),
+                client_downloads::client.eq(DownloadClient::Mirror),
+                client_downloads::downloads.eq(1),
+            ),
+        ])
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    // The breakdowns are only included when requested
+    let response = anon.get::<()>("/api/v1/crates/foo/downloads").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let json = response.json();
+    assert!(json.get("cargo_version_downloads").is_none());
+    assert!(json.get("client_downloads").is_none());
+
+    let url = "/api/v1/crates/foo/downloads?include=cargo_versions,clients";
+    let response = anon.get::<()>(url).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".cargo_version_downloads[].date" => "[date]",
+        ".client_downloads[].date" => "[date]",
+    });
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/downloads?include=foo")
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"invalid component for ?include= (expected 'versions', 'cargo_versions', or 'clients')"}]}"#);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_version_downloads() {
     let (app, anon, cookie) = TestApp::init().with_user().await;
@@ -897,6 +897,45 @@ async fn loose_search_order() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn deprecated_crates_rank_lower() -> anyhow::Result<()> {
+    let (app, anon, user) = TestApp::init().with_user().await;
+    let mut conn = app.db_conn().await;
+    let user = user.as_model();
+
+    CrateBuilder::new("alpha", user.id)
+        .readme("widget widget widget")
+        .description("widget")
+        .expect_build(&mut conn)
+        .await;
+    CrateBuilder::new("beta", user.id)
+        .description("widget")
+        .expect_build(&mut conn)
+        .await;
+
+    for json in search_both(&anon, "q=widget").await {
+        let names = json.crates.iter().map(|c| &c.name).collect::<Vec<_>>();
+        assert_eq!(names, ["alpha", "beta"]);
+    }
+
+    update(crates::table.filter(crates::name.eq("alpha")))
+        .set(crates::deprecated_at.eq(now))
+        .execute(&mut conn)
+        .await?;
+
+    for json in search_both(&anon, "q=widget").await {
+        let names = json.crates.iter().map(|c| &c.name).collect::<Vec<_>>();
+        assert_eq!(names, ["beta", "alpha"]);
+    }
+
+    // Exact name matches are still listed first
+    for json in search_both(&anon, "q=alpha").await {
+        assert_eq!(json.crates[0].name, "alpha");
+    }
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn index_include_yanked() -> anyhow::Result<()> {
     let (app, anon, user) = TestApp::init().with_user().await;
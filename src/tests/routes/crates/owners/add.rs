@@ -1,8 +1,14 @@
 use crate::builders::CrateBuilder;
 use crate::owners::expire_invitation;
+use crate::routes::me::two_factor::enable_totp;
 use crate::util::{RequestHelper, TestApp};
 use crates_io::models::token::{CrateScope, EndpointScope};
+use crates_io::rate_limiter::LimitedAction;
+use crates_io::schema::crates;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
 use insta::assert_snapshot;
+use std::time::Duration;
 
 // This is testing Cargo functionality! ! !
 // specifically functions modify_owners and add_owners
@@ -283,6 +289,34 @@ async fn invite_with_existing_expired_invite() {
     assert_eq!(app.emails().await.len(), 2);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn invite_without_two_factor() {
+    let (app, _, _, owner) = TestApp::init().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    let invited_user = app.db_new_user("invited_user").await;
+    let krate = CrateBuilder::new("crate_name", owner.as_model().user_id)
+        .expect_build(&mut conn)
+        .await;
+
+    diesel::update(crates::table.find(krate.id))
+        .set(crates::require_owner_2fa.eq(true))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let response = owner.add_named_owner("crate_name", "invited_user").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"user `invited_user` needs to enable two-factor authentication first, because crate `crate_name` requires it for all owners"}]}"#);
+    assert_eq!(app.emails().await.len(), 0);
+
+    enable_totp(&app, &invited_user).await;
+
+    let response = owner.add_named_owner("crate_name", "invited_user").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(app.emails().await.len(), 1);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_unknown_crate() {
     let (app, _, user) = TestApp::full().with_user().await;
@@ -383,3 +417,36 @@ async fn no_invite_emails_for_txn_rollback() {
     // 9 emails to the good invitees should have been sent.
     assert_eq!(app.emails().await.len(), 9);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn invite_ratelimit_hit() {
+    let (app, _, _, token) = TestApp::full()
+        .with_rate_limit(LimitedAction::OwnerInvite, Duration::from_secs(60), 1)
+        .with_token()
+        .await;
+    let mut conn = app.db_conn().await;
+
+    let owner = token.as_model().user_id;
+    CrateBuilder::new("crate_name", owner)
+        .expect_build(&mut conn)
+        .await;
+
+    app.db_new_user("user_1").await;
+    app.db_new_user("user_2").await;
+
+    // Each invitation takes a token from the bucket, so the second one of
+    // this request is rejected, and none of the invitations are created.
+    let response = token
+        .add_named_owners("crate_name", &["user_1", "user_2"])
+        .await;
+    response.assert_rate_limited(LimitedAction::OwnerInvite);
+    assert_eq!(app.emails().await.len(), 0);
+
+    let response = token.add_named_owner("crate_name", "user_1").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(app.emails().await.len(), 1);
+
+    let response = token.add_named_owner("crate_name", "user_2").await;
+    response.assert_rate_limited(LimitedAction::OwnerInvite);
+    assert_eq!(app.emails().await.len(), 1);
+}
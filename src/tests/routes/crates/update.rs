@@ -1,7 +1,12 @@
 use crate::builders::CrateBuilder;
+use crate::routes::me::two_factor::enable_totp;
 use crate::util::{RequestHelper, TestApp};
+use crates_io::models::CrateOwner;
 use crates_io::models::token::{CrateScope, EndpointScope};
-use insta::{assert_json_snapshot, assert_snapshot};
+use crates_io::schema::crates;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use insta::{assert_debug_snapshot, assert_json_snapshot, assert_snapshot};
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_enable_trustpub_only() {
@@ -213,6 +218,26 @@ mod auth {
         assert!(!app.emails().await.is_empty());
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn token_user_with_update_crate_endpoint_scope() {
+        let (app, user) = prepare().await;
+        let token = user
+            .db_new_scoped_token(
+                "test-token",
+                None,
+                Some(vec![EndpointScope::UpdateCrate]),
+                None,
+            )
+            .await;
+
+        let url = format!("/api/v1/crates/{}", CRATE_NAME);
+        let body = serde_json::json!({ "crate": { "trustpub_only": true } });
+        let response = token.patch::<()>(&url, body.to_string()).await;
+        assert_snapshot!(response.status(), @"200 OK");
+
+        assert!(!app.emails().await.is_empty());
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn token_user_with_incorrect_endpoint_scope() {
         let (app, user) = prepare().await;
@@ -296,3 +321,188 @@ mod auth {
         assert!(!app.emails().await.is_empty());
     }
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_require_owner_2fa() {
+    let (app, _, user) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let krate = CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let other_owner = app.db_new_user("other").await;
+    CrateOwner::builder()
+        .crate_id(krate.id)
+        .user_id(other_owner.as_model().id)
+        .created_by(user.as_model().id)
+        .build()
+        .insert(&conn)
+        .await
+        .unwrap();
+
+    // API tokens don't require a recent verification of the second factor
+    enable_totp(&app, &user).await;
+    let token = user
+        .db_new_scoped_token(
+            "test-token",
+            None,
+            Some(vec![EndpointScope::UpdateCrate]),
+            None,
+        )
+        .await;
+
+    let url = "/api/v1/crates/foo";
+    let body = serde_json::json!({ "crate": { "require_owner_2fa": true } });
+    let response = token.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"All owners need to enable two-factor authentication first. Missing for: `other`"}]}"#);
+    assert_eq!(app.emails().await.len(), 0);
+
+    enable_totp(&app, &other_owner).await;
+
+    let response = token.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let require_owner_2fa: bool = crates::table
+        .find(krate.id)
+        .select(crates::require_owner_2fa)
+        .first(&mut conn)
+        .await
+        .unwrap();
+    assert!(require_owner_2fa);
+
+    assert_snapshot!(app.emails_snapshot().await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_invalid_request_sends_no_emails() {
+    let (app, _, user) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let owner_id = user.as_model().id;
+    CrateBuilder::new("foo", owner_id)
+        .expect_build(&mut conn)
+        .await;
+
+    let url = "/api/v1/crates/foo";
+
+    let body = serde_json::json!({ "crate": {
+        "trustpub_only": true,
+        "require_promotion_review": true,
+        "deprecation_message": "Unmaintained",
+    } });
+    let response = user.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+
+    let body = serde_json::json!({ "crate": {
+        "trustpub_only": true,
+        "deprecated": true,
+        "deprecation_message": "x".repeat(1001),
+    } });
+    let response = user.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+
+    assert!(app.emails().await.is_empty());
+
+    let (trustpub_only, require_promotion_review): (bool, bool) = crates::table
+        .select((crates::trustpub_only, crates::require_promotion_review))
+        .first(&mut conn)
+        .await
+        .unwrap();
+    assert!(!trustpub_only);
+    assert!(!require_promotion_review);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_deprecation() {
+    let (app, _, user) = TestApp::full().with_git_index().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let owner_id = user.as_model().id;
+    CrateBuilder::new("foo", owner_id)
+        .version("1.0.0")
+        .expect_build(&mut conn)
+        .await;
+    CrateBuilder::new("foo_next", owner_id)
+        .expect_build(&mut conn)
+        .await;
+
+    let url = "/api/v1/crates/foo";
+
+    let body = serde_json::json!({ "crate": { "deprecation_message": "Unmaintained" } });
+    let response = user.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"`deprecation_message` and `successor` can only be set together with `deprecated: true`"}]}"#);
+
+    let body = serde_json::json!({ "crate": { "deprecated": true, "successor": "missing" } });
+    let response = user.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"The successor crate `missing` does not exist"}]}"#);
+
+    let body = serde_json::json!({ "crate": { "deprecated": true, "successor": "FOO" } });
+    let response = user.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"A crate cannot be its own successor"}]}"#);
+
+    let body = serde_json::json!({ "crate": {
+        "deprecated": true,
+        "deprecation_message": "Use foo_next instead.",
+        "successor": "foo-next",
+    } });
+    let response = user.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json()["crate"]["deprecation"], {
+        ".deprecated_at" => "[datetime]",
+    }, @r#"
+    {
+      "deprecated_at": "[datetime]",
+      "message": "Use foo_next instead.",
+      "successor": "foo_next"
+    }
+    "#);
+
+    // The deprecation is shown on the crate page and in the index
+    let response = user.get::<()>(url).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json()["crate"]["deprecation"], {
+        ".deprecated_at" => "[datetime]",
+    }, @r#"
+    {
+      "deprecated_at": "[datetime]",
+      "message": "Use foo_next instead.",
+      "successor": "foo_next"
+    }
+    "#);
+
+    app.run_pending_background_jobs().await;
+    let index = app.crates_from_index_head("foo");
+    assert_debug_snapshot!(index[0].deprecated, @r#"
+    Some(
+        Deprecation {
+            message: Some(
+                "Use foo_next instead.",
+            ),
+            successor: Some(
+                "foo_next",
+            ),
+        },
+    )
+    "#);
+
+    // Removing the deprecation also removes the message and successor
+    let body = serde_json::json!({ "crate": { "deprecated": false } });
+    let response = user.patch::<()>(url, body.to_string()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json()["crate"]["deprecation"], @"null");
+
+    let deprecation: (Option<String>, Option<String>) = crates::table
+        .filter(crates::name.eq("foo"))
+        .select((crates::deprecation_message, crates::successor))
+        .first(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(deprecation, (None, None));
+
+    app.run_pending_background_jobs().await;
+}
@@ -0,0 +1,112 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use insta::{assert_json_snapshot, assert_snapshot};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn diff_versions() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+
+    let pb = PublishBuilder::new("foo", "1.0.0")
+        .add_file("foo-1.0.0/src/lib.rs", "pub fn foo() {}\n")
+        .add_file("foo-1.0.0/src/old.rs", "fn old() {}\n")
+        .add_file("foo-1.0.0/LICENSE", "MIT\n");
+    token.publish_crate(pb).await.good();
+
+    let pb = PublishBuilder::new("foo", "1.1.0")
+        .add_file("foo-1.1.0/src/lib.rs", "pub fn foo() {}\npub fn bar() {}\n")
+        .add_file("foo-1.1.0/src/new.rs", "fn new() {}\n")
+        .add_file("foo-1.1.0/LICENSE", "MIT\n");
+    token.publish_crate(pb).await.good();
+
+    app.run_pending_background_jobs().await;
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/1.1.0/diff?base=1.0.0")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "diff": null,
+      "files": [
+        {
+          "path": "Cargo.toml",
+          "status": "modified"
+        },
+        {
+          "path": "src/lib.rs",
+          "status": "modified"
+        },
+        {
+          "path": "src/new.rs",
+          "status": "added"
+        },
+        {
+          "path": "src/old.rs",
+          "status": "removed"
+        }
+      ]
+    }
+    "#);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/1.1.0/diff?base=1.0.0&path=src/lib.rs")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.json()["diff"].as_str().unwrap(), @r#"
+    --- a/src/lib.rs
+    +++ b/src/lib.rs
+    @@ -1 +1,2 @@
+     pub fn foo() {}
+    +pub fn bar() {}
+    "#);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/1.1.0/diff?base=1.0.0&path=src/old.rs")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.json()["diff"].as_str().unwrap(), @r#"
+    --- a/src/old.rs
+    +++ /dev/null
+    @@ -1 +0,0 @@
+    -fn old() {}
+    "#);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/1.1.0/diff?base=1.0.0&path=LICENSE")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.json()["diff"], @r#""""#);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/1.1.0/diff?base=1.0.0&path=src/main.rs")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"neither version `1.0.0` nor `1.1.0` of crate `foo` contains a file `src/main.rs`"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn diff_unknown_base_version() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+    app.run_pending_background_jobs().await;
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/1.0.0/diff?base=0.1.0")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"crate `foo` does not have a version `0.1.0`"}]}"#);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/1.0.0/diff?base=foo")
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Failed to deserialize query string: base: unexpected character 'f' while parsing major version number"}]}"#);
+
+    let response = anon.get::<()>("/api/v1/crates/foo/1.0.0/diff").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Failed to deserialize query string: missing field `base`"}]}"#);
+}
@@ -1,5 +1,6 @@
 use crate::builders::{CrateBuilder, VersionBuilder};
 use crate::util::{RequestHelper as _, TestApp};
+use crates_io::models::token::EndpointScope;
 use crates_io_docs_rs::MockDocsRsClient;
 use crates_io_test_utils::builders::UserBuilder;
 use insta::assert_snapshot;
@@ -87,3 +88,95 @@ async fn test_trigger_rebuild_unknown_crate_doesnt_queue_job() -> anyhow::Result
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_trigger_rebuild_with_token() -> anyhow::Result<()> {
+    let mut docs_rs_mock = MockDocsRsClient::new();
+    docs_rs_mock
+        .expect_rebuild_docs()
+        .returning(|_, _| Ok(()))
+        .times(1);
+
+    let (app, _client, _cookie_client, token_client) = TestApp::full()
+        .with_docs_rs(docs_rs_mock)
+        .with_scoped_token(None, Some(vec![EndpointScope::RebuildDocs]))
+        .await;
+
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("krate", token_client.as_model().user_id)
+        .version(VersionBuilder::new("0.1.0"))
+        .build(&mut conn)
+        .await?;
+
+    let response = token_client
+        .post::<()>("/api/v1/crates/krate/0.1.0/rebuild_docs", "")
+        .await;
+    assert_snapshot!(response.status(), @"201 Created");
+
+    app.run_pending_background_jobs().await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_trigger_rebuild_with_incorrect_endpoint_scope() -> anyhow::Result<()> {
+    let mut docs_rs_mock = MockDocsRsClient::new();
+    docs_rs_mock
+        .expect_rebuild_docs()
+        .returning(|_, _| Ok(()))
+        .never();
+
+    let (app, _client, _cookie_client, token_client) = TestApp::full()
+        .with_docs_rs(docs_rs_mock)
+        .with_scoped_token(None, Some(vec![EndpointScope::PublishUpdate]))
+        .await;
+
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("krate", token_client.as_model().user_id)
+        .version(VersionBuilder::new("0.1.0"))
+        .build(&mut conn)
+        .await?;
+
+    let response = token_client
+        .post::<()>("/api/v1/crates/krate/0.1.0/rebuild_docs", "")
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"this token does not have the required permissions to perform this action"}]}"#);
+
+    app.run_pending_background_jobs().await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_trigger_rebuild_with_legacy_token() -> anyhow::Result<()> {
+    let mut docs_rs_mock = MockDocsRsClient::new();
+    docs_rs_mock
+        .expect_rebuild_docs()
+        .returning(|_, _| Ok(()))
+        .never();
+
+    let (app, _client, _cookie_client, token_client) = TestApp::full()
+        .with_docs_rs(docs_rs_mock)
+        .with_token()
+        .await;
+
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("krate", token_client.as_model().user_id)
+        .version(VersionBuilder::new("0.1.0"))
+        .build(&mut conn)
+        .await?;
+
+    let response = token_client
+        .post::<()>("/api/v1/crates/krate/0.1.0/rebuild_docs", "")
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"This endpoint cannot be used with legacy API tokens. Use a scoped API token instead."}]}"#);
+
+    app.run_pending_background_jobs().await;
+
+    Ok(())
+}
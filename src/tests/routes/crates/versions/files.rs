@@ -0,0 +1,107 @@
+use crate::builders::{CrateBuilder, PublishBuilder};
+use crate::util::{RequestHelper, TestApp};
+use bytes::Bytes;
+use http::StatusCode;
+use insta::{assert_json_snapshot, assert_snapshot};
+use object_store::ObjectStoreExt;
+
+async fn publish_zip_test(app: &TestApp, token: &impl RequestHelper) {
+    let pb = PublishBuilder::new("zip_test", "1.0.0")
+        .add_file("zip_test-1.0.0/src/lib.rs", "pub fn foo() {}\n")
+        .add_file("zip_test-1.0.0/src/empty.rs", "");
+
+    let response = token.publish_crate(pb).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.run_pending_background_jobs().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn list_files() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+    publish_zip_test(&app, &token).await;
+
+    let response = anon.get::<()>("/api/v1/crates/zip_test/1.0.0/files").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_file() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+    publish_zip_test(&app, &token).await;
+
+    let response = anon
+        .get::<()>("/api/v1/crates/zip_test/1.0.0/files/src/lib.rs")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.headers()["content-type"].to_str().unwrap(), @"application/octet-stream");
+    assert_snapshot!(response.text(), @"pub fn foo() {}");
+
+    let response = anon
+        .get::<()>("/api/v1/crates/zip_test/1.0.0/files/src/empty.rs")
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(response.text(), "");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_unknown_file() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+    publish_zip_test(&app, &token).await;
+
+    let response = anon
+        .get::<()>("/api/v1/crates/zip_test/1.0.0/files/src/main.rs")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"crate `zip_test` version `1.0.0` does not contain a file `src/main.rs`"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_file_with_corrupted_zip() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+    publish_zip_test(&app, &token).await;
+
+    // Overwrite the zip archive with garbage of the same length, so that the
+    // ranged fetch still succeeds but the contents no longer match.
+    let storage = app.as_inner().storage.as_inner();
+    let path = "crates/zip_test/zip_test-1.0.0.zip".into();
+    let zip = storage.get(&path).await.unwrap().bytes().await.unwrap();
+    let garbage = Bytes::from(vec![0; zip.len()]);
+    storage.put(&path, garbage.into()).await.unwrap();
+
+    let response = anon
+        .get::<()>("/api/v1/crates/zip_test/1.0.0/files/src/lib.rs")
+        .await;
+    assert_snapshot!(response.status(), @"500 Internal Server Error");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn files_of_unprocessed_version() {
+    let (app, anon, user) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .version("1.0.0")
+        .expect_build(&mut conn)
+        .await;
+
+    let response = anon.get::<()>("/api/v1/crates/foo/1.0.0/files").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"the files of crate `foo` version `1.0.0` are not available yet"}]}"#);
+
+    let response = anon
+        .get::<()>("/api/v1/crates/foo/1.0.0/files/src/lib.rs")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"the files of crate `foo` version `1.0.0` are not available yet"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn files_of_unknown_version() {
+    let (_app, anon) = TestApp::full().empty().await;
+
+    let response = anon.get::<()>("/api/v1/crates/foo/1.0.0/files").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"crate `foo` does not exist"}]}"#);
+}
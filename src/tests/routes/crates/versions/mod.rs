@@ -1,7 +1,10 @@
 mod authors;
 pub mod dependencies;
+mod diff;
 mod docs;
 pub mod download;
+mod files;
 mod list;
 mod read;
+mod scan_findings;
 pub mod yank_unyank;
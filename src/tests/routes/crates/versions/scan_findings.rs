@@ -0,0 +1,113 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use crates_io::schema::users;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use http::StatusCode;
+use insta::{assert_json_snapshot, assert_snapshot};
+
+const URL: &str = "/api/v1/crates/scan_test/1.0.0/scan_findings";
+
+async fn publish_scan_test(app: &TestApp, token: &impl RequestHelper) {
+    let build_rs =
+        "fn main() {\n    let _ = std::net::TcpStream::connect(\"example.com:80\");\n}\n";
+
+    let pb = PublishBuilder::new("scan_test", "1.0.0")
+        .add_file(
+            "scan_test-1.0.0/src/lib.rs",
+            "const TOKEN: &str = \"cio0123456789abcdef0123456789ABCDEF\";\n",
+        )
+        .add_file("scan_test-1.0.0/build.rs", build_rs);
+
+    let response = token.publish_crate(pb).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.run_pending_background_jobs().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn owner_can_list_findings() {
+    let (app, _, user, token) = TestApp::full().with_token().await;
+    publish_scan_test(&app, &token).await;
+
+    let response = user.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn clean_crate_has_no_findings() {
+    let (app, _, user, token) = TestApp::full().with_token().await;
+
+    let pb = PublishBuilder::new("scan_test", "1.0.0")
+        .add_file("scan_test-1.0.0/src/lib.rs", "pub fn foo() {}\n");
+    token.publish_crate(pb).await.good();
+    app.run_pending_background_jobs().await;
+
+    let response = user.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.text(), @r#"{"findings":[]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn non_owner_cannot_list_findings() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+    publish_scan_test(&app, &token).await;
+
+    let response = anon.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    let other = app.db_new_user("other").await;
+    let response = other.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"must be an owner to see the scan findings"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn admin_can_list_findings() {
+    let (app, _, _, token) = TestApp::full().with_token().await;
+    publish_scan_test(&app, &token).await;
+
+    let mut conn = app.db_conn().await;
+
+    let admin = app.db_new_user("admin").await;
+    diesel::update(admin.as_model())
+        .set(users::is_admin.eq(true))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let response = admin.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"200 OK");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn custom_build_script_path() {
+    let (app, _, user, token) = TestApp::full().with_token().await;
+
+    let manifest = "[package]\nname = \"scan_test\"\nversion = \"1.0.0\"\ndescription = \"description\"\nlicense = \"MIT\"\nbuild = \"custom.rs\"\n";
+    let build_rs =
+        "fn main() {\n    let _ = std::net::TcpStream::connect(\"example.com:80\");\n}\n";
+
+    let pb = PublishBuilder::new("scan_test", "1.0.0")
+        .custom_manifest(manifest)
+        .add_file("scan_test-1.0.0/custom.rs", build_rs)
+        .add_file("scan_test-1.0.0/build.rs", build_rs);
+    token.publish_crate(pb).await.good();
+    app.run_pending_background_jobs().await;
+
+    let response = user.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "findings": [
+        {
+          "description": "build script opens a network connection",
+          "kind": "build_script",
+          "line": 2,
+          "path": "custom.rs"
+        }
+      ]
+    }
+    "#);
+}
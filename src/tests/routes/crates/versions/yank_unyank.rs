@@ -284,6 +284,50 @@ mod auth {
         assert!(!is_yanked(&app).await);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn token_user_with_update_version_endpoint_scope() {
+        let (app, _, client) = prepare().await;
+        let client = client
+            .db_new_scoped_token(
+                "test-token",
+                None,
+                Some(vec![EndpointScope::UpdateVersion]),
+                None,
+            )
+            .await;
+
+        let response = client
+            .update_yank_status(CRATE_NAME, CRATE_VERSION, Some(true), None)
+            .await;
+        assert_snapshot!(response.status(), @"200 OK");
+        assert!(is_yanked(&app).await);
+
+        let response = client
+            .update_yank_status(CRATE_NAME, CRATE_VERSION, Some(false), None)
+            .await;
+        assert_snapshot!(response.status(), @"200 OK");
+        assert!(!is_yanked(&app).await);
+
+        // The `update-version` scope does not cover the dedicated yank endpoints
+        let response = client.yank(CRATE_NAME, CRATE_VERSION).await;
+        assert_snapshot!(response.status(), @"403 Forbidden");
+        assert!(!is_yanked(&app).await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn token_user_with_yank_endpoint_scope_can_update_version() {
+        let (app, _, client) = prepare().await;
+        let client = client
+            .db_new_scoped_token("test-token", None, Some(vec![EndpointScope::Yank]), None)
+            .await;
+
+        let response = client
+            .update_yank_status(CRATE_NAME, CRATE_VERSION, Some(true), None)
+            .await;
+        assert_snapshot!(response.status(), @"200 OK");
+        assert!(is_yanked(&app).await);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn token_user_with_correct_crate_scope() {
         let (app, _, client) = prepare().await;
@@ -0,0 +1,216 @@
+use crate::routes::me::two_factor::{Session, enable_totp};
+use crate::util::{MockCookieUser, RequestHelper, TestApp};
+use crates_io::config::{GitLabOAuthConfig, OidcLoginConfig, Server};
+use crates_io::models::{LinkedIdentity, NewLinkedIdentity};
+use http::StatusCode;
+use insta::{assert_json_snapshot, assert_snapshot};
+use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl};
+use serde_json::Value;
+
+pub fn configure_gitlab(config: &mut Server) {
+    config.gitlab_oauth = Some(GitLabOAuthConfig {
+        client_id: ClientId::new("gitlab-client-id".into()),
+        client_secret: ClientSecret::new("gitlab-client-secret".into()),
+        redirect_url: RedirectUrl::new("https://crates.io/gitlab-redirect.html".into()).unwrap(),
+    });
+}
+
+pub fn configure_oidc(config: &mut Server) {
+    config.oidc_login = vec![OidcLoginConfig {
+        id: "example".into(),
+        name: "Example".into(),
+        client_id: ClientId::new("example-client-id".into()),
+        client_secret: ClientSecret::new("example-client-secret".into()),
+        redirect_url: RedirectUrl::new("https://crates.io/oidc-redirect.html".into()).unwrap(),
+        auth_url: AuthUrl::new("https://login.example.com/authorize".into()).unwrap(),
+        token_url: TokenUrl::new("https://login.example.com/token".into()).unwrap(),
+        userinfo_url: "https://login.example.com/userinfo".parse().unwrap(),
+    }];
+}
+
+pub async fn link_identity(app: &TestApp, user: &MockCookieUser, provider: &str, account_id: &str) {
+    let conn = app.db_conn().await;
+
+    NewLinkedIdentity::builder()
+        .provider(provider)
+        .account_id(account_id)
+        .user_id(user.as_model().id)
+        .login("linked-login")
+        .build()
+        .insert(&conn)
+        .await
+        .unwrap();
+}
+
+/// Replaces the random `state` parameter of an authorization URL.
+pub fn redact_state(json: &Value) -> String {
+    let state = json["state"].as_str().unwrap();
+    json["url"].as_str().unwrap().replace(state, "[STATE]")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn list_identities() {
+    let (app, anon, user) = TestApp::init()
+        .with_config(|config| {
+            configure_gitlab(config);
+            configure_oidc(config);
+        })
+        .with_user()
+        .await;
+
+    link_identity(&app, &user, LinkedIdentity::GITLAB, "42").await;
+
+    let response = user.get::<Value>("/api/private/me/identities").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".identities[1].linked_at" => "[datetime]",
+    }, @r#"
+    {
+      "identities": [
+        {
+          "avatar": null,
+          "email": null,
+          "linked_at": null,
+          "login": "foo",
+          "provider": "github"
+        },
+        {
+          "avatar": null,
+          "email": null,
+          "linked_at": "[datetime]",
+          "login": "linked-login",
+          "provider": "gitlab"
+        }
+      ],
+      "providers": [
+        {
+          "id": "gitlab",
+          "name": "GitLab"
+        },
+        {
+          "id": "example",
+          "name": "Example"
+        }
+      ]
+    }
+    "#);
+
+    let response = anon.get::<()>("/api/private/me/identities").await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn begin_identity_link() {
+    let (_, anon, user) = TestApp::init()
+        .with_config(|config| {
+            configure_gitlab(config);
+            configure_oidc(config);
+        })
+        .with_user()
+        .await;
+
+    let url = "/api/private/me/identities/gitlab/begin";
+    let response = user.post::<Value>(url, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(redact_state(&response.json()), @"https://gitlab.com/oauth/authorize?response_type=code&client_id=gitlab-client-id&state=[STATE]&redirect_uri=https%3A%2F%2Fcrates.io%2Fgitlab-redirect.html&scope=read_api");
+
+    let url = "/api/private/me/identities/example/begin";
+    let response = user.post::<Value>(url, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(redact_state(&response.json()), @"https://login.example.com/authorize?response_type=code&client_id=example-client-id&state=[STATE]&redirect_uri=https%3A%2F%2Fcrates.io%2Foidc-redirect.html&scope=openid+profile+email");
+
+    let response = anon.post::<()>(url, "").await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    let url = "/api/private/me/identities/unknown/begin";
+    let response = user.post::<()>(url, "").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn begin_identity_link_without_configuration() {
+    let (_, _, user) = TestApp::init().with_user().await;
+
+    let url = "/api/private/me/identities/gitlab/begin";
+    let response = user.post::<()>(url, "").await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn authorize_identity_link_rejects_invalid_state() {
+    let (_, _, user) = TestApp::init()
+        .with_config(configure_gitlab)
+        .with_user()
+        .await;
+
+    let url = "/api/private/me/identities/gitlab/authorize";
+    let body = r#"{"code":"901dd10e07c7e9fa1cd5","state":"fYcUY3FMdUUz00FC7vLT7A"}"#;
+    let response = user.post::<()>(url, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"invalid state parameter"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unlink_identity() {
+    let (app, _, user) = TestApp::init().with_user().await;
+
+    // Accounts of providers that are not configured anymore can be unlinked
+    link_identity(&app, &user, "example", "248289761001").await;
+
+    let url = "/api/private/me/identities/example";
+    let response = user.delete::<()>(url).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.text(), @r#"{"ok":true}"#);
+
+    let response = user.delete::<()>(url).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"No `example` account is linked to this account"}]}"#);
+
+    let response = user.delete::<()>("/api/private/me/identities/github").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"The GitHub account can not be unlinked"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn linking_requires_recent_verification() {
+    let (app, anon, user) = TestApp::init()
+        .with_config(configure_gitlab)
+        .with_user()
+        .await;
+
+    link_identity(&app, &user, "example", "248289761001").await;
+    let secret = enable_totp(&app, &user).await;
+    let mut session = Session::new(&app, &anon, &user);
+
+    let requests = [
+        ("POST", "/api/private/me/identities/gitlab/begin", ""),
+        (
+            "POST",
+            "/api/private/me/identities/gitlab/authorize",
+            r#"{"code":"901dd10e07c7e9fa1cd5","state":"fYcUY3FMdUUz00FC7vLT7A"}"#,
+        ),
+        ("DELETE", "/api/private/me/identities/example", ""),
+    ];
+
+    for (method, url, body) in requests {
+        let body = match body {
+            "" => Value::Null,
+            body => serde_json::from_str(body).unwrap(),
+        };
+
+        let response = session.request(method.parse().unwrap(), url, body).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "{method} {url}");
+        let expected = r#"{"errors":[{"detail":"this action requires a recent verification with your second factor"}]}"#;
+        assert_eq!(response.text(), expected, "{method} {url}");
+    }
+
+    session.verify_totp(&secret).await;
+
+    let response = session
+        .post("/api/private/me/identities/gitlab/begin", Value::Null)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = session.delete("/api/private/me/identities/example").await;
+    assert_snapshot!(response.status(), @"200 OK");
+}
@@ -1,4 +1,8 @@
 mod email_notifications;
 pub mod get;
+pub mod identities;
+mod notification_preferences;
 pub mod tokens;
+pub mod two_factor;
 mod updates;
+mod webhooks;
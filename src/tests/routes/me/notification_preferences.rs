@@ -0,0 +1,199 @@
+use crate::builders::CrateBuilder;
+use crate::util::{RequestHelper, TestApp};
+use crates_io::models::{NotificationCategory, NotificationPreference, NotificationSettings};
+use insta::{assert_json_snapshot, assert_snapshot};
+use serde_json::{Value, json};
+
+const URL: &str = "/api/v1/me/notification_preferences";
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_and_update_preferences() {
+    let (app, _, user) = TestApp::init().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let krate = CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let response = user.get::<Value>(URL).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "categories": [
+        {
+          "category": "publish",
+          "enabled": true
+        },
+        {
+          "category": "owner_invite",
+          "enabled": true
+        },
+        {
+          "category": "token_expiry",
+          "enabled": true
+        }
+      ],
+      "crates": [],
+      "publish_delivery": "immediate"
+    }
+    "#);
+
+    let body = json!({
+        "publish_delivery": "weekly",
+        "categories": [{ "category": "token_expiry", "enabled": false }],
+        "crates": [{ "crate": "foo", "category": "publish", "enabled": false }],
+    });
+    let response = user.put::<Value>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "categories": [
+        {
+          "category": "publish",
+          "enabled": true
+        },
+        {
+          "category": "owner_invite",
+          "enabled": true
+        },
+        {
+          "category": "token_expiry",
+          "enabled": false
+        }
+      ],
+      "crates": [
+        {
+          "category": "publish",
+          "crate": "foo",
+          "enabled": false
+        }
+      ],
+      "publish_delivery": "weekly"
+    }
+    "#);
+
+    let user_id = user.as_model().id;
+    let category = NotificationCategory::Publish;
+    let crate_id = Some(krate.id);
+    assert!(
+        !NotificationPreference::is_enabled(&conn, user_id, category, crate_id)
+            .await
+            .unwrap()
+    );
+    let category = NotificationCategory::TokenExpiry;
+    assert!(
+        !NotificationPreference::is_enabled(&conn, user_id, category, None)
+            .await
+            .unwrap()
+    );
+
+    // Removing the crate preference falls back to the category preference
+    let body = json!({
+        "crates": [{ "crate": "foo", "category": "publish", "enabled": null }],
+    });
+    let response = user.put::<Value>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json()["crates"], @"[]");
+
+    // Disabling publish notifications of all crates is equivalent to the
+    // `publish_notifications` flag of the user
+    let body = json!({ "categories": [{ "category": "publish", "enabled": false }] });
+    let response = user.put::<Value>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json()["categories"][0], @r#"
+    {
+      "category": "publish",
+      "enabled": false
+    }
+    "#);
+
+    let response = user.get::<Value>("/api/v1/me").await;
+    assert_eq!(
+        response.json()["user"]["publish_notifications"],
+        json!(false)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_preferences_validation() {
+    let (app, _, user) = TestApp::init().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let another_user = app.db_new_user("bar").await;
+    CrateBuilder::new("foo", another_user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let body = json!({
+        "crates": [{ "crate": "foo", "category": "publish", "enabled": false }],
+    });
+    let response = user.put::<Value>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"you are not an owner of `foo`"}]}"#);
+
+    let body = json!({
+        "crates": [{ "crate": "foo", "category": "token_expiry", "enabled": false }],
+    });
+    let response = user.put::<Value>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"`token_expiry` can not be configured per crate"}]}"#);
+
+    let body = json!({ "publish_delivery": "hourly" });
+    let response = user.put::<Value>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"422 Unprocessable Entity");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn preferences_require_cookie_auth() {
+    let (_, anon, _, token) = TestApp::init().with_token().await;
+
+    let response = anon.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    let response = token.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn one_click_unsubscribe() {
+    let (app, anon, user) = TestApp::init().with_user().await;
+    let conn = app.db_conn().await;
+
+    let user_id = user.as_model().id;
+    let token = NotificationSettings::unsubscribe_token(&conn, user_id)
+        .await
+        .unwrap();
+
+    let url = format!("/api/v1/unsubscribe/{token}/owner_invite");
+    let response = anon.post::<()>(&url, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(response.text(), @r#"{"ok":true}"#);
+
+    let category = NotificationCategory::OwnerInvite;
+    assert!(
+        !NotificationPreference::is_enabled(&conn, user_id, category, None)
+            .await
+            .unwrap()
+    );
+
+    // Unsubscribing again is a no-op
+    let response = anon.post::<()>(&url, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let url = format!("/api/v1/unsubscribe/{token}/publish");
+    let response = anon.post::<()>(&url, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    // Publish notifications send a confirmation email when they are disabled
+    assert_snapshot!(app.emails_snapshot().await);
+
+    let url = format!("/api/v1/unsubscribe/{token}/api_token_exposed");
+    let response = anon.post::<()>(&url, "").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"unknown notification category `api_token_exposed`"}]}"#);
+
+    let response = anon
+        .post::<()>("/api/v1/unsubscribe/invalid/owner_invite", "")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+}
@@ -3,11 +3,13 @@ use crate::util::{RequestHelper, TestApp};
 use claims::assert_ok;
 use crates_io::models::ApiToken;
 use crates_io::models::token::{CrateScope, EndpointScope, NewApiToken};
+use crates_io::rate_limiter::LimitedAction;
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 use googletest::prelude::*;
 use insta::assert_snapshot;
 use serde_json::{Value, json};
+use std::time::Duration;
 
 static NEW_BAR: &[u8] = br#"{ "api_token": { "name": "bar" } }"#;
 
@@ -347,3 +349,69 @@ async fn create_token_with_trusted_publishing_scope() {
 
     assert_snapshot!(app.emails_snapshot().await);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_token_with_update_and_rebuild_docs_scopes() {
+    let (app, _, user) = TestApp::init().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let json = json!({
+        "api_token": {
+            "name": "automation-token",
+            "crate_scopes": ["my-crate"],
+            "endpoint_scopes": ["update-crate", "update-version", "rebuild-docs"],
+        }
+    });
+
+    let response = user
+        .put::<()>("/api/v1/me/tokens", serde_json::to_vec(&json).unwrap())
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json()["api_token"]["endpoint_scopes"], @r#"
+    [
+      "update-crate",
+      "update-version",
+      "rebuild-docs"
+    ]
+    "#);
+
+    let tokens: Vec<ApiToken> = assert_ok!(
+        ApiToken::belonging_to(user.as_model())
+            .select(ApiToken::as_select())
+            .load(&mut conn)
+            .await
+    );
+
+    assert_that!(tokens, len(eq(1)));
+    assert_eq!(
+        tokens[0].endpoint_scopes,
+        Some(vec![
+            EndpointScope::UpdateCrate,
+            EndpointScope::UpdateVersion,
+            EndpointScope::RebuildDocs,
+        ])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_token_ratelimit_hit() {
+    let (app, _, user) = TestApp::init()
+        .with_rate_limit(LimitedAction::CreateToken, Duration::from_secs(60), 1)
+        .with_user()
+        .await;
+
+    let response = user.put::<()>("/api/v1/me/tokens", NEW_BAR).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = user.put::<()>("/api/v1/me/tokens", NEW_BAR).await;
+    response.assert_rate_limited(LimitedAction::CreateToken);
+
+    let mut conn = app.db_conn().await;
+    let tokens: Vec<ApiToken> = assert_ok!(
+        ApiToken::belonging_to(user.as_model())
+            .select(ApiToken::as_select())
+            .load(&mut conn)
+            .await
+    );
+    assert_that!(tokens, len(eq(1)));
+}
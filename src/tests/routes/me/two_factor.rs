@@ -0,0 +1,616 @@
+use crate::builders::CrateBuilder;
+use crate::util::{
+    MockAnonymousUser, MockCookieUser, MockRequestExt, RequestHelper, Response, TestApp,
+    encode_session_header,
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use cookie::{Cookie, CookieJar};
+use crates_io::models::{NewTotpCredential, NewWebauthnCredential, TotpCredential};
+use crates_io::rate_limiter::LimitedAction;
+use crates_io::schema::{crates, totp_credentials};
+use crates_io_two_factor::totp::{self, Secret};
+use crates_io_two_factor::webauthn::test_helpers::SoftwareAuthenticator;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use http::{Method, StatusCode, header};
+use insta::{assert_json_snapshot, assert_snapshot};
+use secrecy::ExposeSecret;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+const ORIGIN: &str = "https://crates.io";
+const RP_ID: &str = "crates.io";
+
+/// A cookie session that keeps the values that the server stores in it, like
+/// WebAuthn challenges and the time of the last second factor verification.
+pub struct Session<'a> {
+    anon: &'a MockAnonymousUser,
+    cookie: String,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(app: &TestApp, anon: &'a MockAnonymousUser, user: &MockCookieUser) -> Self {
+        let cookie = encode_session_header(app.as_inner().session_key(), user.as_model().id);
+        Self { anon, cookie }
+    }
+
+    pub async fn request(&mut self, method: Method, path: &str, body: Value) -> Response<()> {
+        let mut request = self.anon.request_builder(method, path);
+        request.header(header::COOKIE, &self.cookie);
+        if !body.is_null() {
+            request = request.with_body(body.to_string().into());
+        }
+
+        let response = self.anon.run::<()>(request).await;
+        if let Some(set_cookie) = response.headers().get(header::SET_COOKIE) {
+            let set_cookie = set_cookie.to_str().unwrap();
+            self.cookie = set_cookie.split(';').next().unwrap().to_string();
+        }
+
+        response
+    }
+
+    pub async fn get(&mut self, path: &str) -> Response<()> {
+        self.request(Method::GET, path, Value::Null).await
+    }
+
+    pub async fn post(&mut self, path: &str, body: Value) -> Response<()> {
+        self.request(Method::POST, path, body).await
+    }
+
+    pub async fn delete(&mut self, path: &str) -> Response<()> {
+        self.request(Method::DELETE, path, Value::Null).await
+    }
+
+    /// Changes the signed-in user of the session, while keeping all other
+    /// values that are stored in the cookie of the browser.
+    pub fn switch_user(&mut self, app: &TestApp, user: &MockCookieUser) {
+        let key = app.as_inner().session_key();
+
+        let mut jar = CookieJar::new();
+        jar.add_original(Cookie::parse_encoded(self.cookie.clone()).unwrap());
+        let cookie = jar.signed(key).get("cargo_session").unwrap();
+
+        let mut data = crates_io_session::decode(cookie);
+        data.insert("user_id".into(), user.as_model().id.to_string());
+
+        let cookie = Cookie::new("cargo_session", crates_io_session::encode(&data));
+        jar.signed_mut(key).add(cookie);
+        self.cookie = jar.get("cargo_session").unwrap().encoded().to_string();
+    }
+
+    /// Verifies the second factor with a code of the given authenticator app.
+    pub async fn verify_totp(&mut self, secret: &Secret) {
+        let code = secret.code_at(totp::time_step(Utc::now()));
+        let body = json!({ "method": "totp", "code": code });
+        let response = self.post("/api/private/me/two_factor/verify", body).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+
+/// Sets up a confirmed authenticator app for the user, bypassing the API.
+pub async fn enable_totp(app: &TestApp, user: &MockCookieUser) -> Secret {
+    let mut conn = app.db_conn().await;
+    let user_id = user.as_model().id;
+
+    let secret = Secret::generate();
+    let encryption = &app.as_inner().config.token_encryption;
+    let encrypted_secret = encryption.encrypt(&hex::encode(secret.as_bytes())).unwrap();
+
+    NewTotpCredential::builder()
+        .user_id(user_id)
+        .encrypted_secret(&encrypted_secret)
+        .build()
+        .insert_pending(&conn)
+        .await
+        .unwrap();
+
+    diesel::update(totp_credentials::table.find(user_id))
+        .set(totp_credentials::confirmed_at.eq(diesel::dsl::now))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    secret
+}
+
+/// Loads and decrypts the TOTP secret of a user.
+async fn totp_secret(app: &TestApp, user: &MockCookieUser) -> Secret {
+    let conn = app.db_conn().await;
+    let credential = TotpCredential::find(&conn, user.as_model().id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let encryption = &app.as_inner().config.token_encryption;
+    let secret = encryption.decrypt(&credential.encrypted_secret).unwrap();
+    Secret::from_bytes(hex::decode(secret.expose_secret()).unwrap())
+}
+
+fn b64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn challenge(response: &Response<()>) -> Vec<u8> {
+    let challenge = response.json()["challenge"].as_str().unwrap().to_string();
+    URL_SAFE_NO_PAD.decode(challenge).unwrap()
+}
+
+async fn init() -> (TestApp, MockAnonymousUser, MockCookieUser) {
+    TestApp::init()
+        .with_config(|config| config.allowed_origins = ORIGIN.parse().unwrap())
+        .with_user()
+        .await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn status_without_two_factor() {
+    let (_, _, user) = init().await;
+
+    let response = user.get::<()>("/api/private/me/two_factor").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "enabled": false,
+      "recovery_codes_remaining": 0,
+      "totp_enabled": false,
+      "verified_until": null,
+      "webauthn_credentials": []
+    }
+    "#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requires_cookie_authentication() {
+    let (_, anon, _, token) = TestApp::init().with_token().await;
+
+    let response = anon.get::<()>("/api/private/me/two_factor").await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    let response = token.get::<()>("/api/private/me/two_factor").await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"this action can only be performed on the crates.io website"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn totp_setup() {
+    let (app, anon, user) = init().await;
+    let mut session = Session::new(&app, &anon, &user);
+
+    let response = session
+        .post("/api/private/me/two_factor/totp/begin", Value::Null)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let json = response.json();
+    let secret = totp_secret(&app, &user).await;
+    assert_eq!(json["secret"], secret.to_base32());
+    assert_eq!(
+        json["provisioning_uri"],
+        secret.provisioning_uri("crates.io", "foo")
+    );
+
+    // The setup is not enabled before it is confirmed
+    let response = session.get("/api/private/me/two_factor").await;
+    assert_eq!(response.json()["enabled"], false);
+
+    let body = json!({ "code": "000000" });
+    let response = session
+        .post("/api/private/me/two_factor/totp/confirm", body)
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"The verification code is invalid"}]}"#);
+
+    let code = secret.code_at(totp::time_step(Utc::now()));
+    let body = json!({ "code": code });
+    let response = session
+        .post("/api/private/me/two_factor/totp/confirm", body)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let recovery_codes = response.json()["recovery_codes"].as_array().unwrap().len();
+    assert_eq!(recovery_codes, 10);
+
+    let response = session.get("/api/private/me/two_factor").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".verified_until" => "[datetime]",
+    }, @r#"
+    {
+      "enabled": true,
+      "recovery_codes_remaining": 10,
+      "totp_enabled": true,
+      "verified_until": "[datetime]",
+      "webauthn_credentials": []
+    }
+    "#);
+
+    // A second authenticator app can not be set up
+    let response = session
+        .post("/api/private/me/two_factor/totp/begin", Value::Null)
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"An authenticator app is already set up for this account"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sensitive_actions_require_recent_verification() {
+    let (app, anon, user) = init().await;
+    let secret = enable_totp(&app, &user).await;
+    let mut session = Session::new(&app, &anon, &user);
+
+    let body = json!({ "api_token": { "name": "bar" } });
+    let response = session
+        .request(Method::PUT, "/api/v1/me/tokens", body.clone())
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"this action requires a recent verification with your second factor"}]}"#);
+
+    // Non-sensitive actions are not affected
+    let response = session.get("/api/v1/me/tokens").await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    session.verify_totp(&secret).await;
+
+    let response = session
+        .request(Method::PUT, "/api/v1/me/tokens", body)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verification_does_not_carry_over_to_other_users() {
+    let (app, anon, user) = init().await;
+    let secret = enable_totp(&app, &user).await;
+
+    let other = app.db_new_user("other").await;
+    enable_totp(&app, &other).await;
+
+    let mut session = Session::new(&app, &anon, &user);
+    session.verify_totp(&secret).await;
+
+    // The verification is tied to the user, even if it is still stored in
+    // the session
+    session.switch_user(&app, &other);
+
+    let body = json!({ "api_token": { "name": "bar" } });
+    let response = session
+        .request(Method::PUT, "/api/v1/me/tokens", body.clone())
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    let response = session.get("/api/private/me/two_factor").await;
+    assert_eq!(response.json()["verified_until"], Value::Null);
+
+    session.switch_user(&app, &user);
+
+    let response = session
+        .request(Method::PUT, "/api/v1/me/tokens", body.clone())
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    // Signing out discards the verification
+    let response = session.delete("/api/private/session").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    session.switch_user(&app, &user);
+
+    let response = session
+        .request(Method::PUT, "/api/v1/me/tokens", body)
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn crate_deletion_requires_recent_verification() {
+    let (app, anon, user) = TestApp::full().with_git_index().with_user().await;
+    let mut conn = app.db_conn().await;
+    CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let secret = enable_totp(&app, &user).await;
+    let mut session = Session::new(&app, &anon, &user);
+
+    let response = session.delete("/api/v1/crates/foo").await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    session.verify_totp(&secret).await;
+
+    let response = session.delete("/api/v1/crates/foo").await;
+    assert_snapshot!(response.status(), @"204 No Content");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn totp_codes_can_only_be_used_once() {
+    let (app, anon, user) = init().await;
+    let secret = enable_totp(&app, &user).await;
+    let mut session = Session::new(&app, &anon, &user);
+
+    let code = secret.code_at(totp::time_step(Utc::now()));
+    let body = json!({ "method": "totp", "code": code });
+    let response = session
+        .post("/api/private/me/two_factor/verify", body.clone())
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = session
+        .post("/api/private/me/two_factor/verify", body)
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"The verification code is invalid"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verify_without_two_factor() {
+    let (app, anon, user) = init().await;
+    let mut session = Session::new(&app, &anon, &user);
+
+    let body = json!({ "method": "totp", "code": "123456" });
+    let response = session
+        .post("/api/private/me/two_factor/verify", body)
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Two-factor authentication is not enabled for this account"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn recovery_codes() {
+    let (app, anon, user) = init().await;
+    let secret = enable_totp(&app, &user).await;
+    let mut session = Session::new(&app, &anon, &user);
+
+    // Regenerating the recovery codes is a sensitive action itself
+    let response = session
+        .post("/api/private/me/two_factor/recovery_codes", Value::Null)
+        .await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    session.verify_totp(&secret).await;
+
+    let response = session
+        .post("/api/private/me/two_factor/recovery_codes", Value::Null)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let json = response.json();
+    let code = json["recovery_codes"][0].as_str().unwrap().to_uppercase();
+
+    // A fresh session has to verify again, which works with a recovery code
+    let mut session = Session::new(&app, &anon, &user);
+    let body = json!({ "method": "recovery_code", "code": code });
+    let response = session
+        .post("/api/private/me/two_factor/verify", body.clone())
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = session.get("/api/private/me/two_factor").await;
+    assert_eq!(response.json()["recovery_codes_remaining"], 9);
+
+    let response = session
+        .post("/api/private/me/two_factor/verify", body)
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webauthn_registration_and_verification() {
+    let (app, anon, user) = init().await;
+    let mut authenticator = SoftwareAuthenticator::new(b"yubikey");
+    let mut session = Session::new(&app, &anon, &user);
+
+    let response = session
+        .post("/api/private/me/two_factor/webauthn/begin", Value::Null)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".challenge" => "[challenge]",
+        ".user.id" => "[user_id]",
+    }, @r#"
+    {
+      "attestation": "none",
+      "challenge": "[challenge]",
+      "excludeCredentials": [],
+      "pubKeyCredParams": [
+        {
+          "alg": -7,
+          "type": "public-key"
+        }
+      ],
+      "rp": {
+        "id": "crates.io",
+        "name": "crates.io"
+      },
+      "timeout": 300000,
+      "user": {
+        "displayName": "foo",
+        "id": "[user_id]",
+        "name": "foo"
+      }
+    }
+    "#);
+
+    let registration = authenticator.register(RP_ID, ORIGIN, &challenge(&response));
+    let body = json!({
+        "name": "YubiKey",
+        "client_data_json": b64(&registration.client_data_json),
+        "attestation_object": b64(&registration.attestation_object),
+    });
+    let response = session
+        .post("/api/private/me/two_factor/webauthn", body.clone())
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(
+        response.json()["recovery_codes"].as_array().unwrap().len(),
+        10
+    );
+
+    // The challenge can only be used once
+    let response = session
+        .post("/api/private/me/two_factor/webauthn", body)
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"The security key challenge is missing or has expired"}]}"#);
+
+    let response = session.get("/api/private/me/two_factor").await;
+    assert_json_snapshot!(response.json(), {
+        ".verified_until" => "[datetime]",
+        ".webauthn_credentials[].id" => "[id]",
+        ".webauthn_credentials[].created_at" => "[datetime]",
+    }, @r#"
+    {
+      "enabled": true,
+      "recovery_codes_remaining": 10,
+      "totp_enabled": false,
+      "verified_until": "[datetime]",
+      "webauthn_credentials": [
+        {
+          "created_at": "[datetime]",
+          "id": "[id]",
+          "last_used_at": null,
+          "name": "YubiKey"
+        }
+      ]
+    }
+    "#);
+
+    // A fresh session has to verify with the security key
+    let mut session = Session::new(&app, &anon, &user);
+
+    let response = session
+        .post("/api/private/me/two_factor/verify/begin", Value::Null)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(
+        response.json()["allowCredentials"][0]["id"],
+        b64(authenticator.credential_id())
+    );
+
+    let assertion = authenticator.authenticate(RP_ID, ORIGIN, &challenge(&response));
+    let body = json!({
+        "method": "webauthn",
+        "credential_id": b64(authenticator.credential_id()),
+        "client_data_json": b64(&assertion.client_data_json),
+        "authenticator_data": b64(&assertion.authenticator_data),
+        "signature": b64(&assertion.signature),
+    });
+    let response = session
+        .post("/api/private/me/two_factor/verify", body)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = session.get("/api/private/me/two_factor").await;
+    assert!(response.json()["verified_until"].is_string());
+    assert!(response.json()["webauthn_credentials"][0]["last_used_at"].is_string());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webauthn_verification_with_wrong_origin() {
+    let (app, anon, user) = init().await;
+    let mut authenticator = SoftwareAuthenticator::new(b"yubikey");
+
+    let conn = app.db_conn().await;
+    NewWebauthnCredential::builder()
+        .user_id(user.as_model().id)
+        .credential_id(authenticator.credential_id())
+        .public_key(&authenticator.public_key())
+        .sign_count(0)
+        .name("YubiKey")
+        .build()
+        .insert(&conn)
+        .await
+        .unwrap();
+
+    let mut session = Session::new(&app, &anon, &user);
+    let response = session
+        .post("/api/private/me/two_factor/verify/begin", Value::Null)
+        .await;
+
+    let origin = "https://evil.example.com";
+    let assertion = authenticator.authenticate(RP_ID, origin, &challenge(&response));
+    let body = json!({
+        "method": "webauthn",
+        "credential_id": b64(authenticator.credential_id()),
+        "client_data_json": b64(&assertion.client_data_json),
+        "authenticator_data": b64(&assertion.authenticator_data),
+        "signature": b64(&assertion.signature),
+    });
+    let response = session
+        .post("/api/private/me/two_factor/verify", body)
+        .await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Invalid security key response: origin `https://evil.example.com` is not allowed"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remove_last_factor() {
+    let (app, anon, user) = init().await;
+    let secret = enable_totp(&app, &user).await;
+    let mut session = Session::new(&app, &anon, &user);
+    session.verify_totp(&secret).await;
+
+    let response = session
+        .post("/api/private/me/two_factor/recovery_codes", Value::Null)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = session.delete("/api/private/me/two_factor/totp").await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    // The recovery codes are removed together with the last factor
+    let response = session.get("/api/private/me/two_factor").await;
+    assert_json_snapshot!(response.json(), @r#"
+    {
+      "enabled": false,
+      "recovery_codes_remaining": 0,
+      "totp_enabled": false,
+      "verified_until": null,
+      "webauthn_credentials": []
+    }
+    "#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remove_last_factor_required_by_crate() {
+    let (app, anon, user) = init().await;
+    let mut conn = app.db_conn().await;
+    let krate = CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    diesel::update(crates::table.find(krate.id))
+        .set(crates::require_owner_2fa.eq(true))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let secret = enable_totp(&app, &user).await;
+    let mut session = Session::new(&app, &anon, &user);
+    session.verify_totp(&secret).await;
+
+    let response = session.delete("/api/private/me/two_factor/totp").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Two-factor authentication can not be disabled because it is required for the following crates: `foo`"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verification_is_rate_limited() {
+    let (app, anon, user) = TestApp::init()
+        .with_rate_limit(LimitedAction::TwoFactorVerify, Duration::from_secs(60), 1)
+        .with_user()
+        .await;
+
+    let secret = enable_totp(&app, &user).await;
+    let mut session = Session::new(&app, &anon, &user);
+
+    let body = json!({ "method": "totp", "code": "000000" });
+    let response = session
+        .post("/api/private/me/two_factor/verify", body.clone())
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let code = secret.code_at(totp::time_step(Utc::now()));
+    let body = json!({ "method": "totp", "code": code });
+    let response = session
+        .post("/api/private/me/two_factor/verify", body)
+        .await;
+    response.assert_rate_limited(LimitedAction::TwoFactorVerify);
+}
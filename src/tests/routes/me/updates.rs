@@ -107,3 +107,38 @@ async fn following() {
     assert_snapshot!(response.status(), @"400 Bad Request");
     assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Failed to deserialize query string: page: invalid value: integer `0`, expected a nonzero u32"}]}"#);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn staged_versions_are_hidden() {
+    let (app, _, user) = TestApp::init().with_user().await;
+    let mut conn = app.db_conn().await;
+    let user_id = user.as_model().id;
+
+    CrateBuilder::new("foo_fighters", user_id)
+        .version(VersionBuilder::new("1.0.0"))
+        .version(VersionBuilder::new("1.1.0"))
+        .expect_build(&mut conn)
+        .await;
+
+    update(versions::table)
+        .filter(versions::num.eq("1.1.0"))
+        .set(versions::staged_at.eq(diesel::dsl::now))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    user.put::<OkBool>("/api/v1/crates/foo_fighters/follow", b"" as &[u8])
+        .await
+        .good();
+
+    let json = user
+        .get::<serde_json::Value>("/api/v1/me/updates")
+        .await
+        .good();
+    let versions = json["versions"].as_array().unwrap();
+    let nums = versions
+        .iter()
+        .map(|v| v["num"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(nums, ["1.0.0"]);
+}
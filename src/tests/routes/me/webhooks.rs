@@ -0,0 +1,184 @@
+use crate::builders::CrateBuilder;
+use crate::util::{RequestHelper, TestApp};
+use http::StatusCode;
+use insta::{assert_json_snapshot, assert_snapshot};
+use serde_json::{Value, json};
+
+const URL: &str = "/api/v1/me/webhooks";
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_list_and_delete_webhooks() {
+    let (app, _, user) = TestApp::init().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let body = json!({
+        "url": "https://example.com/all",
+        "events": ["yank", "publish", "yank"],
+    });
+    let response = user.post::<Value>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let json = response.json();
+    assert_eq!(json["secret"].as_str().unwrap().len(), 32);
+    assert_json_snapshot!(json, {
+        ".webhook.id" => "[id]",
+        ".webhook.created_at" => "[datetime]",
+        ".secret" => "[secret]",
+    }, @r#"
+    {
+      "secret": "[secret]",
+      "webhook": {
+        "crate": null,
+        "created_at": "[datetime]",
+        "events": [
+          "publish",
+          "yank"
+        ],
+        "id": "[id]",
+        "url": "https://example.com/all"
+      }
+    }
+    "#);
+    let all_id = json["webhook"]["id"].as_i64().unwrap();
+
+    let body = json!({
+        "crate": "foo",
+        "url": "https://example.com/foo",
+        "events": ["owner_add", "owner_remove"],
+    });
+    let response = user.post::<Value>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = user.get::<Value>(URL).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".webhooks[].id" => "[id]",
+        ".webhooks[].created_at" => "[datetime]",
+    }, @r#"
+    {
+      "webhooks": [
+        {
+          "crate": null,
+          "created_at": "[datetime]",
+          "events": [
+            "publish",
+            "yank"
+          ],
+          "id": "[id]",
+          "url": "https://example.com/all"
+        },
+        {
+          "crate": "foo",
+          "created_at": "[datetime]",
+          "events": [
+            "owner_add",
+            "owner_remove"
+          ],
+          "id": "[id]",
+          "url": "https://example.com/foo"
+        }
+      ]
+    }
+    "#);
+
+    let response = user.delete::<()>(&format!("{URL}/{all_id}")).await;
+    assert_snapshot!(response.status(), @"204 No Content");
+
+    let response = user.delete::<()>(&format!("{URL}/{all_id}")).await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+
+    let response = user.get::<Value>(URL).await;
+    assert_eq!(response.json()["webhooks"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_webhook_validation() {
+    let (app, _, user) = TestApp::init().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let other_user = app.db_new_user("other").await;
+    CrateBuilder::new("foo", other_user.as_model().id)
+        .expect_build(&mut conn)
+        .await;
+
+    let body = json!({ "url": "not a url", "events": ["publish"] });
+    let response = user.post::<()>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"invalid webhook URL"}]}"#);
+
+    let body = json!({ "url": "ftp://example.com/", "events": ["publish"] });
+    let response = user.post::<()>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"webhook URLs must use `https`"}]}"#);
+
+    let body = json!({ "url": "http://example.com/", "events": ["publish"] });
+    let response = user.post::<()>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"webhook URLs must use `https`"}]}"#);
+
+    for url in [
+        "https://localhost:8080/",
+        "https://127.0.0.1/",
+        "https://10.0.0.1/",
+        "https://169.254.169.254/latest/meta-data/",
+        "https://[::1]/",
+        "https://[fd00::1]/",
+    ] {
+        let body = json!({ "url": url, "events": ["publish"] });
+        let response = user.post::<()>(URL, body.to_string()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.json()["errors"][0]["detail"],
+            "webhook URLs must not point to local or private network addresses"
+        );
+    }
+
+    let body = json!({ "url": "https://example.com/", "events": [] });
+    let response = user.post::<()>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"at least one event must be selected"}]}"#);
+
+    let body = json!({ "url": "https://example.com/", "events": ["download"] });
+    let response = user.post::<()>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"422 Unprocessable Entity");
+
+    let body = json!({ "crate": "foo", "url": "https://example.com/", "events": ["publish"] });
+    let response = user.post::<()>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"you are not an owner of `foo`"}]}"#);
+
+    let body = json!({ "crate": "bar", "url": "https://example.com/", "events": ["publish"] });
+    let response = user.post::<()>(URL, body.to_string()).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"crate `bar` does not exist"}]}"#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webhooks_of_other_users_are_hidden() {
+    let (app, anon, user) = TestApp::init().with_user().await;
+
+    let body = json!({ "url": "https://example.com/", "events": ["publish"] });
+    let response = user.post::<Value>(URL, body.to_string()).await;
+    let id = response.json()["webhook"]["id"].as_i64().unwrap();
+
+    let response = anon.get::<()>(URL).await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+
+    let other_user = app.db_new_user("other").await;
+    let response = other_user.get::<Value>(URL).await;
+    assert_eq!(response.json(), json!({ "webhooks": [] }));
+
+    let response = other_user
+        .get::<()>(&format!("{URL}/{id}/deliveries"))
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+
+    let response = other_user.delete::<()>(&format!("{URL}/{id}")).await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+
+    let response = user.get::<Value>(&format!("{URL}/{id}/deliveries")).await;
+    assert_eq!(response.json(), json!({ "deliveries": [] }));
+}
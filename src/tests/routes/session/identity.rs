@@ -0,0 +1,53 @@
+use crate::routes::me::identities::{configure_gitlab, configure_oidc, redact_state};
+use crate::util::{RequestHelper, TestApp};
+use insta::assert_snapshot;
+use serde_json::Value;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn begin_identity_session() {
+    let (_, anon) = TestApp::init()
+        .with_config(|config| {
+            configure_gitlab(config);
+            configure_oidc(config);
+        })
+        .empty()
+        .await;
+
+    let url = "/api/private/session/gitlab/begin";
+    let response = anon.post::<Value>(url, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(redact_state(&response.json()), @"https://gitlab.com/oauth/authorize?response_type=code&client_id=gitlab-client-id&state=[STATE]&redirect_uri=https%3A%2F%2Fcrates.io%2Fgitlab-redirect.html&scope=read_api");
+
+    let url = "/api/private/session/example/begin";
+    let response = anon.post::<Value>(url, "").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_snapshot!(redact_state(&response.json()), @"https://login.example.com/authorize?response_type=code&client_id=example-client-id&state=[STATE]&redirect_uri=https%3A%2F%2Fcrates.io%2Foidc-redirect.html&scope=openid+profile+email");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn begin_identity_session_with_unknown_provider() {
+    let (_, anon) = TestApp::init().empty().await;
+
+    // GitLab is not configured for this app
+    let response = anon
+        .post::<()>("/api/private/session/gitlab/begin", "")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+
+    // GitHub uses the `/api/private/session/begin` route instead
+    let response = anon
+        .post::<()>("/api/private/session/github/begin", "")
+        .await;
+    assert_snapshot!(response.status(), @"404 Not Found");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn authorize_identity_session_rejects_invalid_state() {
+    let (_, anon) = TestApp::init().with_config(configure_oidc).empty().await;
+
+    let url = "/api/private/session/example/authorize";
+    let body = r#"{"code":"901dd10e07c7e9fa1cd5","state":"fYcUY3FMdUUz00FC7vLT7A"}"#;
+    let response = anon.post::<()>(url, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"invalid state parameter"}]}"#);
+}
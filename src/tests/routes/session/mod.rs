@@ -1,4 +1,5 @@
 mod authorize;
 mod begin;
 mod end;
+mod identity;
 mod signup;
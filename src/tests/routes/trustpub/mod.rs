@@ -1,3 +1,4 @@
 mod github_configs;
 mod gitlab_configs;
+mod oidc_configs;
 mod tokens;
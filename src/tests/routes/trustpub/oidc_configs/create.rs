@@ -0,0 +1,268 @@
+use crate::builders::CrateBuilder;
+use crate::util::{MockCookieUser, RequestHelper, Response, TestApp};
+use bytes::Bytes;
+use crates_io_database::schema::trustpub_configs_oidc;
+use crates_io_trustpub::buildkite::BUILDKITE_ISSUER_URL;
+use crates_io_trustpub::keystore::MockOidcKeyStore;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use insta::{assert_json_snapshot, assert_snapshot};
+use serde_json::json;
+
+const URL: &str = "/api/v1/trusted_publishing/oidc_configs";
+
+const CRATE_NAME: &str = "foo";
+
+async fn prepare() -> (TestApp, MockCookieUser) {
+    let (app, _client, cookie_client) = TestApp::full()
+        .with_oidc_keystore(BUILDKITE_ISSUER_URL, MockOidcKeyStore::default())
+        .with_user()
+        .await;
+
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new(CRATE_NAME, cookie_client.as_model().id)
+        .build(&mut conn)
+        .await
+        .unwrap();
+
+    (app, cookie_client)
+}
+
+async fn run_test(payload: impl Into<Bytes>) -> (TestApp, Response<()>) {
+    let (app, cookie_client) = prepare().await;
+    let response = cookie_client.post::<()>(URL, payload.into()).await;
+    (app, response)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_happy_path() -> anyhow::Result<()> {
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "buildkite",
+            "repository": "rust-lang/foo-rs",
+        }
+    }))?;
+
+    let (app, response) = run_test(body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), { ".oidc_config.created_at" => "[datetime]" }, @r#"
+    {
+      "oidc_config": {
+        "claims": {},
+        "crate": "foo",
+        "created_at": "[datetime]",
+        "id": 1,
+        "provider": "buildkite",
+        "repository": "rust-lang/foo-rs",
+        "repository_owner_id": null
+      }
+    }
+    "#);
+
+    assert_snapshot!(app.emails_snapshot().await);
+
+    let mut conn = app.db_conn().await;
+    let config_ids = trustpub_configs_oidc::table
+        .select(trustpub_configs_oidc::id)
+        .get_results::<i32>(&mut conn)
+        .await?;
+
+    assert_eq!(config_ids, vec![1]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_happy_path_with_claims() -> anyhow::Result<()> {
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "buildkite",
+            "repository": "rust-lang/foo-rs",
+            "claims": { "build_branch": "main", "step_key": "publish" },
+        }
+    }))?;
+
+    let (_app, response) = run_test(body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), { ".oidc_config.created_at" => "[datetime]" }, @r#"
+    {
+      "oidc_config": {
+        "claims": {
+          "build_branch": "main",
+          "step_key": "publish"
+        },
+        "crate": "foo",
+        "created_at": "[datetime]",
+        "id": 1,
+        "provider": "buildkite",
+        "repository": "rust-lang/foo-rs",
+        "repository_owner_id": null
+      }
+    }
+    "#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unknown_provider() -> anyhow::Result<()> {
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "jenkins",
+            "repository": "rust-lang/foo-rs",
+        }
+    }))?;
+
+    let (_app, response) = run_test(body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Unsupported Trusted Publishing provider: jenkins"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_disabled_provider() -> anyhow::Result<()> {
+    let (app, _client, cookie_client) = TestApp::full().with_user().await;
+
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new(CRATE_NAME, cookie_client.as_model().id)
+        .build(&mut conn)
+        .await?;
+
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "buildkite",
+            "repository": "rust-lang/foo-rs",
+        }
+    }))?;
+
+    let response = cookie_client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Unsupported Trusted Publishing provider: buildkite"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_invalid_repository() -> anyhow::Result<()> {
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "buildkite",
+            "repository": "foo-rs",
+        }
+    }))?;
+
+    let (_app, response) = run_test(body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Invalid repository: expected `{organization_slug}/{pipeline_slug}`"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unsupported_claim() -> anyhow::Result<()> {
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "buildkite",
+            "repository": "rust-lang/foo-rs",
+            "claims": { "agent_id": "foo" },
+        }
+    }))?;
+
+    let (_app, response) = run_test(body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"Unsupported claim `agent_id`"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unauthenticated() -> anyhow::Result<()> {
+    let (app, client, cookie_client) = TestApp::full()
+        .with_oidc_keystore(BUILDKITE_ISSUER_URL, MockOidcKeyStore::default())
+        .with_user()
+        .await;
+
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new(CRATE_NAME, cookie_client.as_model().id)
+        .build(&mut conn)
+        .await?;
+
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "buildkite",
+            "repository": "rust-lang/foo-rs",
+        }
+    }))?;
+
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"403 Forbidden");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"this action requires authentication"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_non_owner() -> anyhow::Result<()> {
+    let (app, _cookie_client) = prepare().await;
+
+    let other_client = app.db_new_user("other_user").await;
+
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "buildkite",
+            "repository": "rust-lang/foo-rs",
+        }
+    }))?;
+
+    let response = other_client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"You are not an owner of this crate"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_too_many_configs() -> anyhow::Result<()> {
+    let (_app, cookie_client) = prepare().await;
+
+    // Create 5 configurations (the maximum)
+    for i in 0..5 {
+        let body = serde_json::to_vec(&json!({
+            "oidc_config": {
+                "crate": CRATE_NAME,
+                "provider": "buildkite",
+                "repository": format!("rust-lang/foo-rs-{i}"),
+            }
+        }))?;
+
+        let response = cookie_client.post::<()>(URL, body).await;
+        assert_eq!(response.status(), 200);
+    }
+
+    // Try to create a 6th configuration
+    let body = serde_json::to_vec(&json!({
+        "oidc_config": {
+            "crate": CRATE_NAME,
+            "provider": "buildkite",
+            "repository": "rust-lang/foo-rs-6",
+        }
+    }))?;
+
+    let response = cookie_client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"409 Conflict");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"This crate already has the maximum number of generic OIDC Trusted Publishing configurations (5)"}]}"#);
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::builders::CrateBuilder;
+use crate::util::{RequestHelper, TestApp};
+use crates_io_database::models::trustpub::{NewOidcConfig, OidcConfig};
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use insta::{assert_json_snapshot, assert_snapshot};
+use serde_json::json;
+
+const URL: &str = "/api/v1/trusted_publishing/oidc_configs";
+
+async fn create_config(
+    conn: &mut AsyncPgConnection,
+    crate_id: i32,
+    repository: &str,
+) -> QueryResult<OidcConfig> {
+    let config = NewOidcConfig {
+        crate_id,
+        provider: "buildkite",
+        repository,
+        claims: json!({}),
+    };
+
+    config.insert(conn).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_by_crate() -> anyhow::Result<()> {
+    let (app, _client, cookie_client) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let owner_id = cookie_client.as_model().id;
+    let foo = CrateBuilder::new("foo", owner_id).build(&mut conn).await?;
+    let bar = CrateBuilder::new("bar", owner_id).build(&mut conn).await?;
+
+    create_config(&mut conn, foo.id, "rust-lang/foo-rs").await?;
+    create_config(&mut conn, bar.id, "rust-lang/bar-rs").await?;
+
+    let response = cookie_client.get_with_query::<()>(URL, "crate=foo").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".oidc_configs[].created_at" => "[datetime]",
+    }, @r#"
+    {
+      "meta": {
+        "next_page": null,
+        "total": 1
+      },
+      "oidc_configs": [
+        {
+          "claims": {},
+          "crate": "foo",
+          "created_at": "[datetime]",
+          "id": 1,
+          "provider": "buildkite",
+          "repository": "rust-lang/foo-rs",
+          "repository_owner_id": null
+        }
+      ]
+    }
+    "#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_by_user() -> anyhow::Result<()> {
+    let (app, _client, cookie_client) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let owner_id = cookie_client.as_model().id;
+    let foo = CrateBuilder::new("foo", owner_id).build(&mut conn).await?;
+    let bar = CrateBuilder::new("bar", owner_id).build(&mut conn).await?;
+
+    let other_user = app.db_new_user("other").await;
+    let other_id = other_user.as_model().id;
+    let baz = CrateBuilder::new("baz", other_id).build(&mut conn).await?;
+
+    create_config(&mut conn, foo.id, "rust-lang/foo-rs").await?;
+    create_config(&mut conn, bar.id, "rust-lang/bar-rs").await?;
+    create_config(&mut conn, baz.id, "rust-lang/baz-rs").await?;
+
+    let query = format!("user_id={owner_id}");
+    let response = cookie_client.get_with_query::<()>(URL, &query).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".oidc_configs[].created_at" => "[datetime]",
+    }, @r#"
+    {
+      "meta": {
+        "next_page": null,
+        "total": 2
+      },
+      "oidc_configs": [
+        {
+          "claims": {},
+          "crate": "foo",
+          "created_at": "[datetime]",
+          "id": 1,
+          "provider": "buildkite",
+          "repository": "rust-lang/foo-rs",
+          "repository_owner_id": null
+        },
+        {
+          "claims": {},
+          "crate": "bar",
+          "created_at": "[datetime]",
+          "id": 2,
+          "provider": "buildkite",
+          "repository": "rust-lang/bar-rs",
+          "repository_owner_id": null
+        }
+      ]
+    }
+    "#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_not_owner() -> anyhow::Result<()> {
+    let (app, _, cookie_client) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+
+    let owner_id = cookie_client.as_model().id;
+    let krate = CrateBuilder::new("foo", owner_id).build(&mut conn).await?;
+    create_config(&mut conn, krate.id, "rust-lang/foo-rs").await?;
+
+    let other_client = app.db_new_user("other_user").await;
+
+    let response = other_client.get_with_query::<()>(URL, "crate=foo").await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"You are not an owner of this crate"}]}"#);
+
+    Ok(())
+}
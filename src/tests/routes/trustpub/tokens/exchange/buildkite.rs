@@ -0,0 +1,291 @@
+use crate::builders::CrateBuilder;
+use crate::util::{MockAnonymousUser, RequestHelper, TestApp};
+use claims::{assert_ok, assert_some_eq};
+use crates_io_database::models::trustpub::{NewOidcConfig, OidcConfig};
+use crates_io_database::schema::{trustpub_configs_oidc, trustpub_tokens};
+use crates_io_trustpub::access_token::AccessToken;
+use crates_io_trustpub::buildkite::BUILDKITE_ISSUER_URL;
+use crates_io_trustpub::buildkite::test_helpers::FullBuildkiteClaims;
+use crates_io_trustpub::keystore::MockOidcKeyStore;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use insta::{assert_compact_debug_snapshot, assert_json_snapshot, assert_snapshot};
+use serde_json::json;
+
+const URL: &str = "/api/v1/trusted_publishing/tokens";
+
+const CRATE_NAME: &str = "foo";
+const ORGANIZATION: &str = "rust-lang";
+const ORGANIZATION_ID: &str = "0184990a-4782-42b5-afc1-16715b10b8ff";
+const PIPELINE: &str = "foo-rs";
+const REPOSITORY: &str = "rust-lang/foo-rs";
+
+async fn prepare() -> anyhow::Result<MockAnonymousUser> {
+    prepare_with_claims(json!({})).await
+}
+
+async fn prepare_with_claims(claims: serde_json::Value) -> anyhow::Result<MockAnonymousUser> {
+    let (app, client, cookie) = TestApp::full()
+        .with_oidc_keystore(BUILDKITE_ISSUER_URL, MockOidcKeyStore::with_test_key())
+        .with_user()
+        .await;
+
+    let mut conn = app.db_conn().await;
+
+    let owner_id = cookie.as_model().id;
+    let krate = CrateBuilder::new(CRATE_NAME, owner_id)
+        .build(&mut conn)
+        .await?;
+
+    let new_oidc_config = NewOidcConfig {
+        crate_id: krate.id,
+        provider: "buildkite",
+        repository: REPOSITORY,
+        claims,
+    };
+
+    new_oidc_config.insert(&conn).await?;
+
+    Ok(client)
+}
+
+fn default_claims() -> FullBuildkiteClaims {
+    FullBuildkiteClaims::builder()
+        .organization(ORGANIZATION)
+        .organization_id(ORGANIZATION_ID)
+        .pipeline(PIPELINE)
+        .build()
+}
+
+// ============================================================================
+// Success cases
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_happy_path() -> anyhow::Result<()> {
+    let client = prepare().await?;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let json = response.json();
+    assert_json_snapshot!(json, { ".token" => "[token]" }, @r#"
+    {
+      "token": "[token]"
+    }
+    "#);
+
+    let token = json["token"].as_str().unwrap();
+    let token = assert_ok!(token.parse::<AccessToken>());
+    let hashed_token = token.sha256();
+
+    let mut conn = client.app().db_conn().await;
+
+    let tokens = trustpub_tokens::table
+        .filter(trustpub_tokens::hashed_token.eq(hashed_token.as_slice()))
+        .select((trustpub_tokens::id, trustpub_tokens::crate_ids))
+        .get_results::<(i64, Vec<Option<i32>>)>(&mut conn)
+        .await?;
+
+    assert_eq!(tokens.len(), 1);
+    assert_compact_debug_snapshot!(tokens, @"[(1, [Some(1)])]");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_happy_path_with_claims() -> anyhow::Result<()> {
+    let client = prepare_with_claims(json!({ "step_key": "publish" })).await?;
+
+    let claims = FullBuildkiteClaims::builder()
+        .organization(ORGANIZATION)
+        .pipeline(PIPELINE)
+        .step_key("Publish")
+        .build();
+
+    let body = claims.as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_case_insensitive() -> anyhow::Result<()> {
+    let client = prepare().await?;
+
+    let claims = FullBuildkiteClaims::builder()
+        .organization("RUST-lanG")
+        .pipeline("foo-RS")
+        .build();
+
+    let body = claims.as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    Ok(())
+}
+
+// ============================================================================
+// Provider and JWT validation tests
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_provider_not_enabled() -> anyhow::Result<()> {
+    let (_app, client, _cookie) = TestApp::full().with_user().await;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"Unsupported JWT issuer: https://agent.buildkite.com"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_broken_jwt() -> anyhow::Result<()> {
+    let client = prepare().await?;
+
+    let body = serde_json::to_vec(&json!({ "jwt": "broken" }))?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"Failed to decode JWT"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_invalid_audience() -> anyhow::Result<()> {
+    let client = prepare().await?;
+
+    let mut claims = default_claims();
+    claims.aud = "invalid-audience".into();
+
+    let body = claims.as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"Failed to decode JWT"}]}"#);
+
+    Ok(())
+}
+
+/// Buildkite tokens do not contain a `jti` claim, so replay protection is
+/// based on the hash of the token instead.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_token_reuse() -> anyhow::Result<()> {
+    let client = prepare().await?;
+
+    let body = default_claims().as_exchange_body()?;
+
+    // The first exchange should succeed
+    let response = client.post::<()>(URL, body.clone()).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    // The second exchange should fail
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"JWT has already been used"}]}"#);
+
+    Ok(())
+}
+
+// ============================================================================
+// Config lookup tests
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_missing_config() -> anyhow::Result<()> {
+    let (_app, client, _cookie) = TestApp::full()
+        .with_oidc_keystore(BUILDKITE_ISSUER_URL, MockOidcKeyStore::with_test_key())
+        .with_user()
+        .await;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"No Trusted Publishing config found for repository `rust-lang/foo-rs`."}]}"#);
+
+    Ok(())
+}
+
+// ============================================================================
+// Repository owner ID lazy population and resurrection protection tests
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_lazy_repository_owner_id_population() -> anyhow::Result<()> {
+    let client = prepare().await?;
+    let mut conn = client.app().db_conn().await;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let config: OidcConfig = OidcConfig::query()
+        .filter(trustpub_configs_oidc::repository.eq(REPOSITORY))
+        .first(&mut conn)
+        .await?;
+
+    assert_some_eq!(config.repository_owner_id, ORGANIZATION_ID);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_repository_owner_id_mismatch_resurrection_attack() -> anyhow::Result<()> {
+    let client = prepare().await?;
+    let mut conn = client.app().db_conn().await;
+
+    diesel::update(trustpub_configs_oidc::table)
+        .set(trustpub_configs_oidc::repository_owner_id.eq("999"))
+        .execute(&mut conn)
+        .await?;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"The Trusted Publishing config for repository `rust-lang/foo-rs` does not match the repository owner ID (0184990a-4782-42b5-afc1-16715b10b8ff) in the JWT. Expected owner IDs: 999. Please recreate the Trusted Publishing config to update the repository owner ID."}]}"#);
+
+    Ok(())
+}
+
+// ============================================================================
+// Claim matching tests
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_missing_claim() -> anyhow::Result<()> {
+    let client = prepare_with_claims(json!({ "step_key": "publish" })).await?;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"The Trusted Publishing config for repository `rust-lang/foo-rs` does not match the claims in the JWT. Expected claims: `step_key` = `publish`"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wrong_claim() -> anyhow::Result<()> {
+    let client = prepare_with_claims(json!({ "build_branch": "release" })).await?;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"The Trusted Publishing config for repository `rust-lang/foo-rs` does not match the claims in the JWT. Expected claims: `build_branch` = `release`"}]}"#);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_malformed_claim_restrictions() -> anyhow::Result<()> {
+    let client = prepare_with_claims(json!({ "step_key": ["publish"] })).await?;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"400 Bad Request");
+    assert_snapshot!(response.json(), @r#"{"errors":[{"detail":"The Trusted Publishing config for repository `rust-lang/foo-rs` has invalid claim restrictions. Please recreate the Trusted Publishing config."}]}"#);
+
+    Ok(())
+}
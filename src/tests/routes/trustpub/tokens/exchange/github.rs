@@ -2,8 +2,11 @@ use crate::builders::CrateBuilder;
 use crate::util::{MockAnonymousUser, RequestHelper, TestApp};
 use chrono::{DateTime, Utc};
 use claims::assert_ok;
+use crates_io::rate_limiter::LimitedAction;
 use crates_io_database::models::trustpub::NewGitHubConfig;
-use crates_io_database::schema::{trustpub_tokens, trustpub_used_jtis};
+use crates_io_database::schema::{
+    crate_limit_buckets, crate_rate_overrides, crates, trustpub_tokens, trustpub_used_jtis,
+};
 use crates_io_trustpub::JWT_LEEWAY;
 use crates_io_trustpub::access_token::AccessToken;
 use crates_io_trustpub::github::GITHUB_ISSUER_URL;
@@ -15,6 +18,7 @@ use insta::{assert_compact_debug_snapshot, assert_json_snapshot, assert_snapshot
 use jsonwebtoken::{EncodingKey, Header};
 use mockall::predicate::*;
 use serde_json::json;
+use std::time::Duration;
 
 const URL: &str = "/api/v1/trusted_publishing/tokens";
 
@@ -446,6 +450,88 @@ async fn test_workflow_filename_mismatch() -> anyhow::Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// Rate limiting tests
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rate_limited() -> anyhow::Result<()> {
+    let client = prepare().await?;
+
+    let mut conn = client.app().db_conn().await;
+
+    // Set up the database so it'll think the crate has massively rate-limited itself.
+    // The bucket should next refill in about a year.
+    let crate_id: i32 = crates::table
+        .filter(crates::name.eq(CRATE_NAME))
+        .select(crates::id)
+        .get_result(&mut conn)
+        .await?;
+
+    let far_future = Utc::now() + Duration::from_secs(60 * 60 * 24 * 365);
+    diesel::insert_into(crate_limit_buckets::table)
+        .values((
+            crate_limit_buckets::crate_id.eq(crate_id),
+            crate_limit_buckets::action.eq(LimitedAction::TrustpubExchange),
+            crate_limit_buckets::tokens.eq(0),
+            crate_limit_buckets::last_refill.eq(far_future),
+        ))
+        .execute(&mut conn)
+        .await?;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    response.assert_rate_limited(LimitedAction::TrustpubExchange);
+
+    // No token should have been issued, and the JWT should not have been
+    // marked as used, so that it can be retried later.
+    let tokens: i64 = trustpub_tokens::table.count().get_result(&mut conn).await?;
+    assert_eq!(tokens, 0);
+
+    let used_jtis: i64 = trustpub_used_jtis::table
+        .count()
+        .get_result(&mut conn)
+        .await?;
+    assert_eq!(used_jtis, 0);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rate_limit_override() -> anyhow::Result<()> {
+    let client = prepare().await?;
+
+    let mut conn = client.app().db_conn().await;
+
+    let crate_id: i32 = crates::table
+        .filter(crates::name.eq(CRATE_NAME))
+        .select(crates::id)
+        .get_result(&mut conn)
+        .await?;
+
+    diesel::insert_into(crate_rate_overrides::table)
+        .values((
+            crate_rate_overrides::crate_id.eq(crate_id),
+            crate_rate_overrides::action.eq(LimitedAction::TrustpubExchange),
+            crate_rate_overrides::burst.eq(1),
+        ))
+        .execute(&mut conn)
+        .await?;
+
+    let body = default_claims().as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let mut claims = default_claims();
+    claims.jti = "another-id".into();
+
+    let body = claims.as_exchange_body()?;
+    let response = client.post::<()>(URL, body).await;
+    response.assert_rate_limited(LimitedAction::TrustpubExchange);
+
+    Ok(())
+}
+
 // ============================================================================
 // Dangerous event trigger tests
 // ============================================================================
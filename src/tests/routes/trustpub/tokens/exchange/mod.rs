@@ -1,2 +1,3 @@
+mod buildkite;
 mod github;
 mod gitlab;
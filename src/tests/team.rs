@@ -32,7 +32,7 @@ async fn not_github() {
         .add_named_owner("foo_not_github", "dropbox:foo:foo")
         .await;
     assert_snapshot!(response.status(), @"400 Bad Request");
-    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"unknown organization handler, only 'github:org:team' is supported"}]}"#);
+    assert_snapshot!(response.text(), @r#"{"errors":[{"detail":"unknown organization handler, only 'github:org:team' and 'gitlab:group/subgroup' are supported"}]}"#);
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -85,7 +85,7 @@ async fn add_nonexistent_team() {
 /// Tests adding a renamed team
 #[tokio::test(flavor = "multi_thread")]
 async fn add_renamed_team() -> anyhow::Result<()> {
-    let (app, anon) = TestApp::init().empty().await;
+    let (app, anon) = TestApp::full().empty().await;
     let mut conn = app.db_conn().await;
     let user = app.db_new_user("user-all-teams").await;
     let token = user.db_new_token("arbitrary token name").await;
@@ -127,7 +127,7 @@ async fn add_renamed_team() -> anyhow::Result<()> {
 /// Tests adding team names with mixed case, when on the team
 #[tokio::test(flavor = "multi_thread")]
 async fn add_team_mixed_case() -> anyhow::Result<()> {
-    let (app, anon) = TestApp::init().empty().await;
+    let (app, anon) = TestApp::full().empty().await;
     let mut conn = app.db_conn().await;
     let user = app.db_new_user("user-all-teams").await;
     let token = user.db_new_token("arbitrary token name").await;
@@ -156,7 +156,7 @@ async fn add_team_mixed_case() -> anyhow::Result<()> {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn add_team_as_org_owner() -> anyhow::Result<()> {
-    let (app, anon) = TestApp::init().empty().await;
+    let (app, anon) = TestApp::full().empty().await;
     let mut conn = app.db_conn().await;
     let user = app.db_new_user("user-org-owner").await;
     let token = user.db_new_token("arbitrary token name").await;
@@ -243,7 +243,7 @@ async fn remove_team_as_named_owner() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn remove_team_as_team_owner() {
-    let (app, _) = TestApp::init().empty().await;
+    let (app, _) = TestApp::full().empty().await;
     let mut conn = app.db_conn().await;
     let user_on_both_teams = app.db_new_user("user-all-teams").await;
     let token_on_both_teams = user_on_both_teams
@@ -279,7 +279,7 @@ async fn remove_team_as_team_owner() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn remove_nonexistent_team() {
-    let (app, _, user, token) = TestApp::init().with_token().await;
+    let (app, _, user, token) = TestApp::full().with_token().await;
     let mut conn = app.db_conn().await;
 
     let krate = CrateBuilder::new("foo_remove_nonexistent", user.as_model().id)
@@ -400,7 +400,7 @@ async fn publish_owned() {
 /// Tests trying to change owners (when only on an owning team)
 #[tokio::test(flavor = "multi_thread")]
 async fn add_owners_as_org_owner() {
-    let (app, _) = TestApp::init().empty().await;
+    let (app, _) = TestApp::full().empty().await;
     let mut conn = app.db_conn().await;
     let user_on_both_teams = app.db_new_user("user-all-teams").await;
     let token_on_both_teams = user_on_both_teams
@@ -428,7 +428,7 @@ async fn add_owners_as_org_owner() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn add_owners_as_team_owner() {
-    let (app, _) = TestApp::init().empty().await;
+    let (app, _) = TestApp::full().empty().await;
     let mut conn = app.db_conn().await;
     let user_on_both_teams = app.db_new_user("user-all-teams").await;
     let token_on_both_teams = user_on_both_teams
@@ -18,6 +18,7 @@ use crates_io_docs_rs::MockDocsRsClient;
 use crates_io_encryption::TokenEncryption;
 use crates_io_github::{GitHubClient, MockGitHubClient};
 use crates_io_github_app::MockGitHubApp;
+use crates_io_gitlab::{GitLabClient, MockGitLabClient};
 use crates_io_index::testing::UpstreamIndex;
 use crates_io_index::{Credentials, RepositoryConfig};
 use crates_io_og_image::OgImageGenerator;
@@ -26,6 +27,7 @@ use crates_io_test_db::TestDatabase;
 use crates_io_test_utils::builders::OauthGithubBuilder;
 use crates_io_trustpub::github::test_helpers::AUDIENCE;
 use crates_io_trustpub::keystore::{MockOidcKeyStore, OidcKeyStore};
+use crates_io_trustpub::provenance::ProvenanceSigner;
 use crates_io_worker::Runner;
 use diesel_async::AsyncPgConnection;
 use futures_util::TryStreamExt;
@@ -130,6 +132,7 @@ impl TestApp {
             index_sync_github_app: Some(index_sync_github_app),
             sync_github_app: Some(sync_github_app),
             github: None,
+            gitlab: None,
             docs_rs: None,
             oidc_key_stores: Default::default(),
             og_image_generator: None,
@@ -208,6 +211,7 @@ impl TestApp {
         let date_time_re = regex!(r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z");
         let email_confirm_re = regex!(r"/confirm/\w+");
         let invite_token_re = regex!(r"/accept-invite/\w+");
+        let unsubscribe_token_re = regex!(r"/unsubscribe/\w+/");
 
         // MIME boundary strings are randomly generated alphanumeric strings
         let mime_boundary_re = regex!(r"[A-Za-z0-9]{32,}");
@@ -227,6 +231,8 @@ impl TestApp {
                 let email = date_time_re.replace_all(&email, "[0000-00-00T00:00:00Z]");
                 let email = email_confirm_re.replace_all(&email, "/confirm/[confirm-token]");
                 let email = invite_token_re.replace_all(&email, "/accept-invite/[invite-token]");
+                let email =
+                    unsubscribe_token_re.replace_all(&email, "/unsubscribe/[unsubscribe-token]/");
                 let email = mime_boundary_re.replace_all(&email, "[boundary]");
                 email.to_string()
             })
@@ -295,6 +301,7 @@ pub struct TestAppBuilder {
     index_sync_github_app: Option<MockGitHubApp>,
     sync_github_app: Option<MockGitHubApp>,
     github: Option<MockGitHubClient>,
+    gitlab: Option<MockGitLabClient>,
     docs_rs: Option<MockDocsRsClient>,
     oidc_key_stores: HashMap<String, Box<dyn OidcKeyStore>>,
     og_image_generator: Option<OgImageGenerator>,
@@ -341,7 +348,14 @@ impl TestAppBuilder {
             None => Arc::new(MOCK_GITHUB_DATA.as_mock_client()),
         };
 
-        let (app, router) = build_app(self.config, Arc::clone(&github), self.oidc_key_stores);
+        let gitlab: Arc<dyn GitLabClient> = Arc::new(self.gitlab.unwrap_or_default());
+
+        let (app, router) = build_app(
+            self.config,
+            Arc::clone(&github),
+            gitlab,
+            self.oidc_key_stores,
+        );
 
         let runner = if self.build_job_runner {
             let index_location = self
@@ -371,7 +385,8 @@ impl TestAppBuilder {
 
             let runner = Runner::new(app.primary_database.clone(), Arc::new(environment))
                 .shutdown_when_queue_empty()
-                .register_crates_io_job_types();
+                .register_crates_io_job_types()
+                .schedule_crates_io_jobs();
 
             Some(runner)
         } else {
@@ -471,6 +486,11 @@ impl TestAppBuilder {
         self
     }
 
+    pub fn with_gitlab(mut self, gitlab: MockGitLabClient) -> Self {
+        self.gitlab = Some(gitlab);
+        self
+    }
+
     /// Adds a new OIDC keystore to the application
     pub fn with_oidc_keystore(
         mut self,
@@ -569,6 +589,8 @@ fn simple_config() -> config::Server {
             client_id: ClientId::new(dotenvy::var("GH_CLIENT_ID").unwrap_or_default()),
             client_secret: ClientSecret::new(dotenvy::var("GH_CLIENT_SECRET").unwrap_or_default()),
         },
+        gitlab_oauth: None,
+        oidc_login: vec![],
         token_encryption: TokenEncryption::for_testing(),
         publish_limits: PublishLimitsConfig::for_testing(),
         rate_limits: RateLimitsConfig {
@@ -597,6 +619,7 @@ fn simple_config() -> config::Server {
             html_render_cache_max_capacity: 1024,
         },
         trustpub_audience: AUDIENCE.to_string(),
+        provenance_signer: Some(ProvenanceSigner::for_testing()),
         disable_token_creation: None,
         banner_message: None,
         features: FeaturesConfig {
@@ -605,17 +628,21 @@ fn simple_config() -> config::Server {
             zip_archives_enabled: true,
             cache_tags_enabled: true,
             cache_tag_invalidations_enabled: true,
+            readme_syntax_highlighting_enabled: true,
         },
         fastly: None,
         sync_git_index: false,
         index_archive_url: None,
         postgres_bin_dir: None,
+        private_registry: false,
+        insecure_webhooks: false,
     }
 }
 
 fn build_app(
     config: config::Server,
     github: Arc<dyn GitHubClient>,
+    gitlab: Arc<dyn GitLabClient>,
     oidc_key_stores: HashMap<String, Box<dyn OidcKeyStore>>,
 ) -> (Arc<App>, axum::Router) {
     // Use the in-memory email backend for all tests, allowing tests to analyze the emails sent by
@@ -626,6 +653,9 @@ fn build_app(
         .databases_from_config(&config.db)
         .github(github)
         .github_oauth_from_config(&config)
+        .gitlab(gitlab)
+        .gitlab_oauth_from_config(&config)
+        .oidc_login_from_config(&config)
         .oidc_key_stores(oidc_key_stores)
         .emails(emails)
         .storage_from_config(&config.storage)
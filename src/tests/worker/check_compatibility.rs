@@ -0,0 +1,112 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use crates_io::schema::versions;
+use crates_io::worker::jobs::CheckCompatibility;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use insta::{assert_json_snapshot, assert_snapshot};
+use serde_json::Value;
+
+const MANIFEST_1_0_0: &str = r#"
+[package]
+name = "foo"
+version = "1.0.0"
+description = "description"
+license = "MIT"
+rust-version = "1.60"
+
+[features]
+default = ["std"]
+std = []
+"#;
+
+const MANIFEST_1_0_1: &str = r#"
+[package]
+name = "foo"
+version = "1.0.1"
+description = "description"
+license = "MIT"
+rust-version = "1.70"
+
+[features]
+default = []
+"#;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_check_compatibility() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+
+    let pb = PublishBuilder::new("foo", "1.0.0").custom_manifest(MANIFEST_1_0_0);
+    token.publish_crate(pb).await.good();
+    app.run_pending_background_jobs().await;
+
+    // The first version has nothing to be compared to
+    let json = anon.get::<()>("/api/v1/crates/foo/1.0.0").await.json();
+    assert_eq!(json["version"]["compatibility_report"], Value::Null);
+
+    let pb = PublishBuilder::new("foo", "1.0.1").custom_manifest(MANIFEST_1_0_1);
+    token.publish_crate(pb).await.good();
+    app.run_pending_background_jobs().await;
+
+    let json = anon.get::<()>("/api/v1/crates/foo/1.0.1").await.json();
+    assert_json_snapshot!(json["version"]["compatibility_report"], @r#"
+    {
+      "dependencies": [],
+      "features": {
+        "added": [],
+        "changed": [
+          "default"
+        ],
+        "removed": [
+          "std"
+        ]
+      },
+      "previous_version": "1.0.0",
+      "rust_version": {
+        "current": "1.70",
+        "previous": "1.60"
+      },
+      "warnings": [
+        "The `default` feature no longer enables `std`",
+        "The `std` feature was removed",
+        "The minimum supported Rust version was raised to 1.70 in a patch release"
+      ]
+    }
+    "#);
+
+    // The publish notification includes the warnings
+    assert_snapshot!(app.emails_snapshot().await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_check_compatibility_skips_incompatible_versions() {
+    let (app, anon, _, token) = TestApp::full().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    let pb = PublishBuilder::new("foo", "1.0.0").custom_manifest(MANIFEST_1_0_0);
+    token.publish_crate(pb).await.good();
+
+    let manifest = MANIFEST_1_0_1.replace("1.0.1", "2.0.0");
+    let pb = PublishBuilder::new("foo", "2.0.0").custom_manifest(manifest);
+    token.publish_crate(pb).await.good();
+    app.run_pending_background_jobs().await;
+
+    let json = anon.get::<()>("/api/v1/crates/foo/2.0.0").await.json();
+    assert_eq!(json["version"]["compatibility_report"], Value::Null);
+
+    // Running the check again without a release does not send notifications
+    let num_emails = app.emails().await.len();
+    let version_id: i32 = versions::table
+        .filter(versions::num.eq("2.0.0"))
+        .select(versions::id)
+        .first(&mut conn)
+        .await
+        .unwrap();
+    CheckCompatibility::new(version_id)
+        .enqueue(&conn)
+        .await
+        .unwrap();
+    app.run_pending_background_jobs().await;
+    assert_eq!(app.emails().await.len(), num_emails);
+}
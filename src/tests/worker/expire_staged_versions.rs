@@ -0,0 +1,72 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use chrono::{TimeDelta, Utc};
+use crates_io::schema::{default_versions, versions};
+use crates_io::views::GoodCrate;
+use crates_io::worker::jobs::ExpireStagedVersions;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use http::StatusCode;
+use insta::assert_snapshot;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_expire_staged_versions() {
+    let (app, _, _, token) = TestApp::full().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await
+        .good();
+
+    for version in ["1.1.0", "1.2.0"] {
+        let pb = PublishBuilder::new("foo", version);
+        let response = token
+            .put::<GoodCrate>("/api/v1/crates/new?staged=true", pb)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    app.run_pending_background_jobs().await;
+
+    // Only the first staged version is older than the expiry threshold
+    diesel::update(versions::table)
+        .filter(versions::num.eq("1.1.0"))
+        .set(versions::staged_at.eq(Utc::now() - TimeDelta::days(8)))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    ExpireStagedVersions.enqueue(&conn).await.unwrap();
+    app.run_pending_background_jobs().await;
+
+    let versions: Vec<String> = versions::table
+        .select(versions::num)
+        .order(versions::num)
+        .load(&mut conn)
+        .await
+        .unwrap();
+
+    assert_eq!(versions, ["1.0.0", "1.2.0"]);
+
+    // Staged versions are not counted
+    let num_versions: Option<i32> = default_versions::table
+        .select(default_versions::num_versions)
+        .get_result(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(num_versions, Some(1));
+
+    assert_snapshot!(app.stored_files().await.join("\n"), @r#"
+    crates/foo/foo-1.0.0.crate
+    crates/foo/foo-1.0.0.zip
+    crates/foo/foo-1.0.0.zip.json
+    crates/foo/foo-1.2.0.crate
+    crates/foo/foo-1.2.0.zip
+    crates/foo/foo-1.2.0.zip.json
+    index/3/f/foo
+    rss/crates.xml
+    rss/crates/foo.xml
+    rss/updates.xml
+    "#);
+}
@@ -0,0 +1,251 @@
+use crate::builders::{CrateBuilder, VersionBuilder};
+use crate::util::{RequestHelper, TestApp};
+use crates_io::schema::{advisories, background_jobs, version_advisories, versions};
+use crates_io::worker::jobs::ImportAdvisories;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use insta::{assert_debug_snapshot, assert_json_snapshot};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+const AMMONIA_ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2019-0001"
+package = "ammonia"
+date = "2019-04-27"
+url = "https://github.com/rust-ammonia/ammonia/blob/master/CHANGELOG.md#210"
+aliases = ["CVE-2019-15542"]
+
+[versions]
+patched = [">= 2.1.0"]
+unaffected = ["< 1.0.0"]
+```
+
+# Uncontrolled recursion leads to abort in HTML serialization
+
+Affected versions of this crate did use recursion for serialization of HTML
+DOM trees.
+"#;
+
+const FOO_ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2020-0001"
+package = "foo"
+date = "2020-01-01"
+informational = "unmaintained"
+
+[versions]
+patched = []
+```
+
+# foo is unmaintained
+
+The author has archived the repository.
+"#;
+
+fn write_advisory(dir: &Path, krate: &str, id: &str, content: &str) {
+    let dir = dir.join("crates").join(krate);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(format!("{id}.md")), content).unwrap();
+}
+
+async fn affected_versions(conn: &mut AsyncPgConnection) -> Vec<(String, String)> {
+    version_advisories::table
+        .inner_join(versions::table)
+        .select((version_advisories::advisory_id, versions::num))
+        .order((version_advisories::advisory_id, versions::num))
+        .load(conn)
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_import_advisories() {
+    let (app, _, user) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+    let user = user.as_model();
+
+    CrateBuilder::new("ammonia", user.id)
+        .version("0.7.0")
+        .version("1.2.0")
+        .version("2.0.0")
+        .version("2.1.0")
+        .expect_build(&mut conn)
+        .await;
+
+    CrateBuilder::new("foo", user.id)
+        .version("1.0.0")
+        .expect_build(&mut conn)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    write_advisory(dir.path(), "ammonia", "RUSTSEC-2019-0001", AMMONIA_ADVISORY);
+    write_advisory(dir.path(), "foo", "RUSTSEC-2020-0001", FOO_ADVISORY);
+
+    ImportAdvisories::new(dir.path())
+        .enqueue(&conn)
+        .await
+        .unwrap();
+    app.run_pending_background_jobs().await;
+
+    assert_debug_snapshot!(affected_versions(&mut conn).await, @r#"
+    [
+        (
+            "RUSTSEC-2019-0001",
+            "1.2.0",
+        ),
+        (
+            "RUSTSEC-2019-0001",
+            "2.0.0",
+        ),
+        (
+            "RUSTSEC-2020-0001",
+            "1.0.0",
+        ),
+    ]
+    "#);
+
+    // Withdrawn advisories are kept, but not linked to any versions, and
+    // advisories that were removed from the database are deleted.
+    let withdrawn =
+        AMMONIA_ADVISORY.replace("[versions]", "withdrawn = \"2019-05-01\"\n\n[versions]");
+    write_advisory(dir.path(), "ammonia", "RUSTSEC-2019-0001", &withdrawn);
+    fs::remove_dir_all(dir.path().join("crates/foo")).unwrap();
+
+    ImportAdvisories::new(dir.path())
+        .enqueue(&conn)
+        .await
+        .unwrap();
+    app.run_pending_background_jobs().await;
+
+    assert_eq!(affected_versions(&mut conn).await, vec![]);
+
+    let advisories: Vec<(String, bool)> = advisories::table
+        .select((advisories::id, advisories::withdrawn.is_not_null()))
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(advisories, [("RUSTSEC-2019-0001".to_string(), true)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_import_advisories_from_empty_directory() {
+    let (app, _) = TestApp::full().empty().await;
+    let mut conn = app.db_conn().await;
+
+    let dir = tempfile::tempdir().unwrap();
+    write_advisory(dir.path(), "foo", "RUSTSEC-2020-0001", FOO_ADVISORY);
+
+    ImportAdvisories::new(dir.path())
+        .enqueue(&conn)
+        .await
+        .unwrap();
+    app.run_pending_background_jobs().await;
+
+    // A wrong path must not delete all previously imported advisories
+    let empty_dir = tempfile::tempdir().unwrap();
+    ImportAdvisories::new(empty_dir.path())
+        .enqueue(&conn)
+        .await
+        .unwrap();
+
+    let err = app.try_run_pending_background_jobs().await.unwrap_err();
+    assert_eq!(err.to_string(), "1 jobs failed");
+
+    let count: i64 = advisories::table
+        .count()
+        .get_result(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    // Drain the failed job so the `TestAppInner::drop` empty-queue
+    // post-condition is satisfied.
+    diesel::delete(background_jobs::table)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_advisories_in_api() {
+    let (app, anon, user) = TestApp::full().with_user().await;
+    let mut conn = app.db_conn().await;
+    let user = user.as_model();
+
+    let ammonia = CrateBuilder::new("ammonia", user.id)
+        .version("1.2.0")
+        .version("2.1.0")
+        .expect_build(&mut conn)
+        .await;
+
+    CrateBuilder::new("foo", user.id)
+        .version(VersionBuilder::new("1.0.0").dependency(&ammonia, None))
+        .expect_build(&mut conn)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    write_advisory(dir.path(), "ammonia", "RUSTSEC-2019-0001", AMMONIA_ADVISORY);
+    write_advisory(dir.path(), "foo", "RUSTSEC-2020-0001", FOO_ADVISORY);
+
+    ImportAdvisories::new(dir.path())
+        .enqueue(&conn)
+        .await
+        .unwrap();
+    app.run_pending_background_jobs().await;
+
+    let json = anon.get::<()>("/api/v1/crates/ammonia/1.2.0").await.json();
+    assert_json_snapshot!(json["version"]["advisories"], @r#"
+    [
+      {
+        "aliases": [
+          "CVE-2019-15542"
+        ],
+        "date": "2019-04-27",
+        "id": "RUSTSEC-2019-0001",
+        "informational": null,
+        "title": "Uncontrolled recursion leads to abort in HTML serialization",
+        "url": "https://github.com/rust-ammonia/ammonia/blob/master/CHANGELOG.md#210"
+      }
+    ]
+    "#);
+
+    // Versions without advisories don't include the field
+    let json = anon
+        .get::<()>("/api/v1/crates/ammonia/versions")
+        .await
+        .json();
+    let advisories = json["versions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| (v["num"].clone(), v["advisories"].clone()))
+        .map(|(num, advisories)| (num, advisories.as_array().map(Vec::len)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        advisories,
+        [
+            (Value::from("2.1.0"), None),
+            (Value::from("1.2.0"), Some(1))
+        ]
+    );
+
+    let json = anon
+        .get::<()>("/api/v1/crates/ammonia/reverse_dependencies")
+        .await
+        .json();
+    assert_json_snapshot!(json["versions"][0]["advisories"], @r#"
+    [
+      {
+        "aliases": [],
+        "date": "2020-01-01",
+        "id": "RUSTSEC-2020-0001",
+        "informational": "unmaintained",
+        "title": "foo is unmaintained",
+        "url": null
+      }
+    ]
+    "#);
+}
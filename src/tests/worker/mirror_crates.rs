@@ -0,0 +1,269 @@
+use crate::util::TestApp;
+use chrono::{DateTime, TimeZone, Utc};
+use crates_io::schema::{background_jobs, crates, dependencies, versions};
+use crates_io::worker::jobs::MirrorCrates;
+use crates_io_tarball::TarballBuilder;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use insta::assert_snapshot;
+use mockito::{Mock, Server, ServerGuard};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+/// A fake upstream registry with a sparse index.
+struct Upstream {
+    server: ServerGuard,
+    _config: Mock,
+}
+
+impl Upstream {
+    async fn new() -> Self {
+        let mut server = Server::new_async().await;
+
+        let config = json!({ "dl": format!("{}/dl", server.url()) });
+        let config = server
+            .mock("GET", "/config.json")
+            .with_body(config.to_string())
+            .create_async()
+            .await;
+
+        Self {
+            server,
+            _config: config,
+        }
+    }
+
+    fn index_url(&self) -> String {
+        format!("{}/", self.server.url())
+    }
+
+    /// Serves the index file of a crate with the given entries.
+    async fn index_file(&mut self, path: &str, entries: &[Value]) -> Mock {
+        let content = entries
+            .iter()
+            .map(|entry| format!("{entry}\n"))
+            .collect::<String>();
+
+        self.server
+            .mock("GET", format!("/{path}").as_str())
+            .with_body(content)
+            .create_async()
+            .await
+    }
+
+    /// Serves the `.crate` file of a version, and returns the index entry
+    /// for it.
+    async fn publish(&mut self, name: &str, version: &str, deps: Value) -> (Value, Mock) {
+        let manifest = format!(
+            "[package]\nname = \"{name}\"\nversion = \"{version}\"\n\
+            description = \"description of {name} {version}\"\nlicense = \"MIT\"\n\
+            keywords = [\"mirror\"]\n"
+        );
+
+        let tarball = TarballBuilder::new()
+            .add_file(&format!("{name}-{version}/Cargo.toml"), manifest.as_bytes())
+            .build();
+
+        let cksum = hex::encode(Sha256::digest(&tarball));
+
+        let mock = self
+            .server
+            .mock("GET", format!("/dl/{name}/{version}/download").as_str())
+            .with_body(tarball)
+            .create_async()
+            .await;
+
+        let entry = json!({
+            "name": name,
+            "vers": version,
+            "deps": deps,
+            "cksum": cksum,
+            "features": {},
+            "yanked": false,
+            "pubtime": "2020-01-01T00:00:00Z",
+        });
+
+        (entry, mock)
+    }
+}
+
+fn dependency(name: &str, kind: &str, optional: bool) -> Value {
+    json!({
+        "name": name,
+        "req": "^1.0",
+        "features": [],
+        "optional": optional,
+        "default_features": true,
+        "target": null,
+        "kind": kind,
+    })
+}
+
+async fn mirrored_versions(conn: &mut AsyncPgConnection) -> Vec<(String, String, bool)> {
+    versions::table
+        .inner_join(crates::table)
+        .select((crates::name, versions::num, versions::yanked))
+        .order((crates::name, versions::id))
+        .load(conn)
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mirror_crates() {
+    let (app, _) = TestApp::full().empty().await;
+    let mut conn = app.db_conn().await;
+
+    let mut upstream = Upstream::new().await;
+
+    let (bar_1, _bar_1_mock) = upstream.publish("bar", "1.0.0", json!([])).await;
+    let _bar = upstream.index_file("3/b/bar", &[bar_1]).await;
+
+    let (foo_1, foo_1_mock) = upstream.publish("foo", "1.0.0", json!([])).await;
+    let deps = json!([
+        dependency("bar", "normal", false),
+        dependency("missing-optional", "normal", true),
+        dependency("missing-dev", "dev", false),
+    ]);
+    let (mut foo_2, _foo_2_mock) = upstream.publish("foo", "2.0.0", deps).await;
+    foo_2["features"] = json!({ "extra": ["dep:missing-optional", "bar/std"] });
+    let deps = json!([dependency("missing", "normal", false)]);
+    let (foo_3, _foo_3_mock) = upstream.publish("foo", "3.0.0", deps).await;
+    let foo_entries = [foo_1.clone(), foo_2.clone(), foo_3.clone()];
+    let foo = upstream.index_file("3/f/foo", &foo_entries).await;
+
+    let _unknown = upstream
+        .server
+        .mock("GET", "/un/kn/unknown")
+        .with_status(404)
+        .create_async()
+        .await;
+
+    let names = vec!["foo".to_string(), "bar".to_string(), "unknown".to_string()];
+    MirrorCrates::new(upstream.index_url(), names.clone())
+        .enqueue(&conn)
+        .await
+        .unwrap();
+    app.run_pending_background_jobs().await;
+
+    // `foo@3.0.0` is skipped, since its dependency has not been mirrored
+    assert_eq!(
+        mirrored_versions(&mut conn).await,
+        [
+            ("bar".to_string(), "1.0.0".to_string(), false),
+            ("foo".to_string(), "1.0.0".to_string(), false),
+            ("foo".to_string(), "2.0.0".to_string(), false),
+        ]
+    );
+
+    let description: Option<String> = crates::table
+        .filter(crates::name.eq("foo"))
+        .select(crates::description)
+        .get_result(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(description.as_deref(), Some("description of foo 2.0.0"));
+
+    let created_at: Vec<DateTime<Utc>> = versions::table
+        .select(versions::created_at)
+        .distinct()
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        created_at,
+        [Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap()]
+    );
+
+    let num_dependencies: i64 = dependencies::table
+        .count()
+        .get_result(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(num_dependencies, 1);
+
+    let mut stored_files = app.stored_files().await;
+    stored_files.sort();
+    assert_eq!(
+        stored_files,
+        [
+            "crates/bar/bar-1.0.0.crate",
+            "crates/foo/foo-1.0.0.crate",
+            "crates/foo/foo-2.0.0.crate",
+            "index/3/b/bar",
+            "index/3/f/foo",
+        ]
+    );
+
+    let index = app.as_inner().storage.download_index("foo").await.unwrap();
+    let index = String::from_utf8(index.to_vec()).unwrap();
+    let entries = index
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["cksum"], foo_1["cksum"]);
+    assert_eq!(entries[1]["deps"].as_array().unwrap().len(), 1);
+    assert_snapshot!(entries[1]["features"], @r#"{"extra":["bar/std"]}"#);
+
+    // Later runs only download the new versions, and update the yanked
+    // state of the existing ones
+    foo.remove_async().await;
+
+    let (foo_4, _foo_4_mock) = upstream.publish("foo", "4.0.0", json!([])).await;
+    let mut foo_1 = foo_1;
+    foo_1["yanked"] = json!(true);
+    let foo_entries = [foo_1, foo_2, foo_3, foo_4];
+    let _foo = upstream.index_file("3/f/foo", &foo_entries).await;
+
+    MirrorCrates::new(upstream.index_url(), names)
+        .enqueue(&conn)
+        .await
+        .unwrap();
+    app.run_pending_background_jobs().await;
+
+    assert_eq!(
+        mirrored_versions(&mut conn).await,
+        [
+            ("bar".to_string(), "1.0.0".to_string(), false),
+            ("foo".to_string(), "1.0.0".to_string(), true),
+            ("foo".to_string(), "2.0.0".to_string(), false),
+            ("foo".to_string(), "4.0.0".to_string(), false),
+        ]
+    );
+
+    foo_1_mock.assert_async().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mirror_crates_with_checksum_mismatch() {
+    let (app, _) = TestApp::full().empty().await;
+    let mut conn = app.db_conn().await;
+
+    let mut upstream = Upstream::new().await;
+
+    let (mut foo_1, _foo_1_mock) = upstream.publish("foo", "1.0.0", json!([])).await;
+    foo_1["cksum"] = json!("0".repeat(64));
+    let _foo = upstream.index_file("3/f/foo", &[foo_1]).await;
+
+    MirrorCrates::new(upstream.index_url(), vec!["foo".to_string()])
+        .enqueue(&conn)
+        .await
+        .unwrap();
+
+    let error = app.try_run_pending_background_jobs().await.unwrap_err();
+    assert_eq!(error.to_string(), "1 jobs failed");
+
+    // The crate is removed again, since none of its versions were mirrored
+    assert_eq!(mirrored_versions(&mut conn).await, []);
+    let num_crates: i64 = crates::table.count().get_result(&mut conn).await.unwrap();
+    assert_eq!(num_crates, 0);
+
+    // Drain the failed job so the `TestAppInner::drop` empty-queue
+    // post-condition is satisfied.
+    diesel::delete(background_jobs::table)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+}
@@ -1,13 +1,19 @@
 mod archive_index_branch;
 mod build_crate_zip;
+mod check_compatibility;
+mod expire_staged_versions;
 mod generate_og_image;
 mod git;
+mod import_advisories;
+mod mirror_crates;
 mod normalize_index;
 mod readmes;
 mod rss;
+mod send_publish_notification_digests;
 mod send_publish_notifications;
 mod squash_index;
 mod sync_admins;
 mod trustpub;
 mod update_default_version;
 mod update_user_from_github;
+mod webhooks;
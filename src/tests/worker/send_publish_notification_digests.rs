@@ -0,0 +1,58 @@
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
+use chrono::{TimeDelta, Utc};
+use crates_io::models::{NotificationSettings, PublishDelivery};
+use crates_io::schema::pending_publish_notifications;
+use crates_io::worker::jobs;
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use insta::assert_snapshot;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sends_daily_digest() -> anyhow::Result<()> {
+    let (app, _, user, token) = TestApp::full().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    let user_id = user.as_model().id;
+    NotificationSettings::set_publish_delivery(&conn, user_id, PublishDelivery::Daily).await?;
+
+    let response = token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let response = token
+        .publish_crate(PublishBuilder::new("bar", "1.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    // The notifications are queued instead of being sent immediately
+    assert_eq!(app.emails().await.len(), 0);
+    let num_pending = pending_publish_notifications::table
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await?;
+    assert_eq!(num_pending, 2);
+
+    // The digest is not due yet
+    jobs::SendPublishNotificationDigests.enqueue(&conn).await?;
+    app.run_pending_background_jobs().await;
+    assert_eq!(app.emails().await.len(), 0);
+
+    diesel::update(pending_publish_notifications::table)
+        .set(pending_publish_notifications::created_at.eq(Utc::now() - TimeDelta::days(1)))
+        .execute(&mut conn)
+        .await?;
+
+    jobs::SendPublishNotificationDigests.enqueue(&conn).await?;
+    app.run_pending_background_jobs().await;
+    assert_snapshot!(app.emails_snapshot().await);
+
+    let num_pending = pending_publish_notifications::table
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await?;
+    assert_eq!(num_pending, 0);
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
-use crate::util::TestApp;
+use crate::builders::PublishBuilder;
+use crate::util::{RequestHelper, TestApp};
 use crates_io::worker::jobs;
 use crates_io_worker::BackgroundJob;
+use insta::assert_snapshot;
 
 #[tokio::test(flavor = "multi_thread")]
 async fn skips_when_crate_deleted() -> anyhow::Result<()> {
@@ -14,3 +16,35 @@ async fn skips_when_crate_deleted() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn skips_crates_with_disabled_publish_notifications() -> anyhow::Result<()> {
+    let (app, _, user, token) = TestApp::full().with_token().await;
+
+    let response = token
+        .publish_crate(PublishBuilder::new("foo", "1.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(app.emails().await.len(), 1);
+
+    let body = r#"{"crates":[{"crate":"foo","category":"publish","enabled":false}]}"#;
+    let response = user
+        .put::<()>("/api/v1/me/notification_preferences", body)
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+
+    let response = token
+        .publish_crate(PublishBuilder::new("foo", "1.1.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(app.emails().await.len(), 1);
+
+    // Other crates are not affected by the preference
+    let response = token
+        .publish_crate(PublishBuilder::new("bar", "1.0.0"))
+        .await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_eq!(app.emails().await.len(), 2);
+
+    Ok(())
+}
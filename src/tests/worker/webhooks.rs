@@ -0,0 +1,267 @@
+use crate::builders::{CrateBuilder, VersionBuilder};
+use crate::routes::crates::versions::yank_unyank::YankRequestHelper;
+use crate::util::{MockCookieUser, RequestHelper, TestApp};
+use claims::assert_err;
+use crates_io::schema::{background_jobs, webhooks};
+use crates_io::worker::jobs::webhooks::{
+    DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER, sign,
+};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use http::StatusCode;
+use insta::{assert_json_snapshot, assert_snapshot};
+use mockito::{Matcher, Server};
+use serde_json::{Value, json};
+
+/// Creates a webhook for all crates of the user and returns its ID and secret.
+async fn create_webhook(user: &MockCookieUser, url: &str, events: &[&str]) -> (i64, String) {
+    let body = json!({ "url": url, "events": events });
+    let response = user
+        .post::<Value>("/api/v1/me/webhooks", body.to_string())
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json = response.json();
+    let id = json["webhook"]["id"].as_i64().unwrap();
+    let secret = json["secret"].as_str().unwrap().to_string();
+    (id, secret)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_deliver_webhook() {
+    let (app, _, user, token) = TestApp::full()
+        .with_config(|config| config.insecure_webhooks = true)
+        .with_token()
+        .await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .version(VersionBuilder::new("1.0.0"))
+        .expect_build(&mut conn)
+        .await;
+
+    let mut server = Server::new_async().await;
+    let url = format!("{}/hook", server.url());
+    let (id, secret) = create_webhook(&user, &url, &["yank"]).await;
+
+    let mock = server
+        .mock("POST", "/hook")
+        .match_header(EVENT_HEADER, "yank")
+        .match_header(DELIVERY_HEADER, Matcher::Regex(r"^\d+$".into()))
+        .match_body(Matcher::PartialJson(json!({
+            "event": "yank",
+            "crate": "foo",
+            "version": "1.0.0",
+            "actor": "foo",
+        })))
+        .match_request(move |request| {
+            let header = |name| request.header(name)[0].to_str().unwrap().to_string();
+            let timestamp = header(TIMESTAMP_HEADER).parse().unwrap();
+            let body = request.body().unwrap();
+            let expected = sign(secret.as_bytes(), timestamp, body);
+            header(SIGNATURE_HEADER) == format!("sha256={expected}")
+        })
+        .expect(1)
+        .create_async()
+        .await;
+
+    // Unyank events are not delivered to this webhook
+    token.yank("foo", "1.0.0").await.good();
+    token.unyank("foo", "1.0.0").await.good();
+
+    mock.assert_async().await;
+
+    let url = format!("/api/v1/me/webhooks/{id}/deliveries");
+    let response = user.get::<Value>(&url).await;
+    assert_snapshot!(response.status(), @"200 OK");
+    assert_json_snapshot!(response.json(), {
+        ".deliveries[].id" => "[id]",
+        ".deliveries[].created_at" => "[datetime]",
+        ".deliveries[].last_attempt_at" => "[datetime]",
+        ".deliveries[].delivered_at" => "[datetime]",
+        ".deliveries[].payload.time" => "[datetime]",
+    }, @r#"
+    {
+      "deliveries": [
+        {
+          "attempts": 1,
+          "created_at": "[datetime]",
+          "delivered_at": "[datetime]",
+          "error": null,
+          "event": "yank",
+          "id": "[id]",
+          "last_attempt_at": "[datetime]",
+          "payload": {
+            "actor": "foo",
+            "crate": "foo",
+            "event": "yank",
+            "time": "[datetime]",
+            "version": "1.0.0"
+          },
+          "response_status": 200
+        }
+      ]
+    }
+    "#);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_deliver_webhook_failure() {
+    let (app, _, user, token) = TestApp::full()
+        .with_config(|config| config.insecure_webhooks = true)
+        .with_token()
+        .await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .version(VersionBuilder::new("1.0.0"))
+        .expect_build(&mut conn)
+        .await;
+
+    let mut server = Server::new_async().await;
+    let url = format!("{}/hook", server.url());
+    let (id, _) = create_webhook(&user, &url, &["yank"]).await;
+
+    let mock = server
+        .mock("POST", "/hook")
+        .with_status(500)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let response = token.delete::<()>("/api/v1/crates/foo/1.0.0/yank").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let result = app.try_run_pending_background_jobs().await;
+    assert_err!(result);
+
+    mock.assert_async().await;
+
+    let url = format!("/api/v1/me/webhooks/{id}/deliveries");
+    let response = user.get::<Value>(&url).await;
+    let delivery = &response.json()["deliveries"][0];
+    assert_eq!(delivery["attempts"], 1);
+    assert_eq!(delivery["response_status"], 500);
+    assert_eq!(
+        delivery["error"],
+        "Unexpected status 500 Internal Server Error"
+    );
+    assert_eq!(delivery["delivered_at"], Value::Null);
+
+    // The failed job stays in the queue to be retried
+    let deleted = diesel::delete(background_jobs::table)
+        .filter(background_jobs::job_type.eq("deliver_webhook"))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(deleted, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_webhooks_of_non_owners_are_skipped() {
+    let (app, _, user, token) = TestApp::full()
+        .with_config(|config| config.insecure_webhooks = true)
+        .with_token()
+        .await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .version(VersionBuilder::new("1.0.0"))
+        .expect_build(&mut conn)
+        .await;
+
+    let other_user = app.db_new_user("other").await;
+    let mut server = Server::new_async().await;
+    let url = format!("{}/hook", server.url());
+    create_webhook(&other_user, &url, &["yank"]).await;
+
+    let mock = server.mock("POST", "/hook").expect(0).create_async().await;
+
+    token.yank("foo", "1.0.0").await.good();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_deliver_webhook_connection_error() {
+    let (app, _, user, token) = TestApp::full()
+        .with_config(|config| config.insecure_webhooks = true)
+        .with_token()
+        .await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .version(VersionBuilder::new("1.0.0"))
+        .expect_build(&mut conn)
+        .await;
+
+    // Nothing is listening on this port
+    let (id, _) = create_webhook(&user, "http://127.0.0.1:1/hook", &["yank"]).await;
+
+    let response = token.delete::<()>("/api/v1/crates/foo/1.0.0/yank").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let result = app.try_run_pending_background_jobs().await;
+    assert_err!(result);
+
+    // The error of the HTTP client is not exposed to the user
+    let url = format!("/api/v1/me/webhooks/{id}/deliveries");
+    let response = user.get::<Value>(&url).await;
+    let delivery = &response.json()["deliveries"][0];
+    assert_eq!(delivery["response_status"], Value::Null);
+    assert_eq!(delivery["error"], "Failed to send the request");
+
+    // The failed job stays in the queue to be retried
+    let deleted = diesel::delete(background_jobs::table)
+        .filter(background_jobs::job_type.eq("deliver_webhook"))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(deleted, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_private_addresses_are_rejected_on_delivery() {
+    let (app, _, user, token) = TestApp::full().with_token().await;
+    let mut conn = app.db_conn().await;
+
+    CrateBuilder::new("foo", user.as_model().id)
+        .version(VersionBuilder::new("1.0.0"))
+        .expect_build(&mut conn)
+        .await;
+
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/hook").expect(0).create_async().await;
+
+    // Simulates a host whose DNS records changed after the webhook was
+    // created, by replacing the URL in the database.
+    let (id, _) = create_webhook(&user, "https://example.com/hook", &["yank"]).await;
+    let private_url = format!("https://{}/hook", server.host_with_port());
+    diesel::update(webhooks::table.find(id as i32))
+        .set(webhooks::url.eq(&private_url))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let response = token.delete::<()>("/api/v1/crates/foo/1.0.0/yank").await;
+    assert_snapshot!(response.status(), @"200 OK");
+    let result = app.try_run_pending_background_jobs().await;
+    assert_err!(result);
+
+    mock.assert_async().await;
+
+    let url = format!("/api/v1/me/webhooks/{id}/deliveries");
+    let response = user.get::<Value>(&url).await;
+    let delivery = &response.json()["deliveries"][0];
+    assert_eq!(delivery["response_status"], Value::Null);
+    assert_eq!(
+        delivery["error"],
+        "The webhook host does not resolve to a public IP address"
+    );
+
+    // The failed job stays in the queue to be retried
+    let deleted = diesel::delete(background_jobs::table)
+        .filter(background_jobs::job_type.eq("deliver_webhook"))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(deleted, 1);
+}
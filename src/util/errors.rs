@@ -32,6 +32,7 @@ mod json;
 use crate::email::EmailError;
 use crate::util::diesel::is_read_only_error;
 use crates_io_github::GitHubError;
+use crates_io_gitlab::GitLabError;
 pub use json::ApiErrorResponse;
 pub use json::TOKEN_FORMAT_ERROR;
 pub(crate) use json::{InsecurelyGeneratedTokenRevoked, TooManyRequests, custom};
@@ -238,6 +239,25 @@ impl From<GitHubError> for BoxedAppError {
     }
 }
 
+impl From<GitLabError> for BoxedAppError {
+    fn from(error: GitLabError) -> Self {
+        match error {
+            GitLabError::Unauthorized(_) => custom(
+                StatusCode::UNAUTHORIZED,
+                "It looks like the access token of your linked GitLab account has expired \
+                 or was revoked. Please link your GitLab account again.",
+            ),
+            GitLabError::Forbidden(_) => custom(
+                StatusCode::FORBIDDEN,
+                "It looks like you don't have permission to query a necessary property \
+                 from GitLab to complete this request.",
+            ),
+            GitLabError::NotFound(_) => not_found(),
+            _ => internal(format!("didn't get a 200 result from gitlab: {error}")),
+        }
+    }
+}
+
 impl From<crates_io_trustpub::github::validation::ValidationError> for BoxedAppError {
     fn from(error: crates_io_trustpub::github::validation::ValidationError) -> Self {
         bad_request(error)
@@ -250,6 +270,12 @@ impl From<crates_io_trustpub::gitlab::validation::ValidationError> for BoxedAppE
     }
 }
 
+impl From<crates_io_trustpub::provider::ValidationError> for BoxedAppError {
+    fn from(error: crates_io_trustpub::provider::ValidationError) -> Self {
+        bad_request(error)
+    }
+}
+
 // =============================================================================
 // Internal error for use with `chain_error`
 
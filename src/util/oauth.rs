@@ -1,5 +1,23 @@
+use oauth2::basic::BasicClient;
+use oauth2::{EndpointNotSet, EndpointSet};
 use std::future::Future;
 use std::pin::Pin;
+use url::Url;
+
+/// An OAuth2 client for the authorization code flow.
+pub type OAuthClient =
+    BasicClient<EndpointSet, EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointSet>;
+
+/// A generic OpenID Connect provider that can be used to sign in to crates.io.
+pub struct OidcLoginClient {
+    /// The ID of the provider (see [`crate::config::OidcLoginConfig::id`]).
+    pub id: String,
+    /// The name of the provider that is displayed to users.
+    pub name: String,
+    pub oauth: OAuthClient,
+    /// The endpoint returning the claims about the authenticated user.
+    pub userinfo_url: Url,
+}
 
 /// Bridges `reqwest::Client` with `oauth2::AsyncHttpClient` so that
 /// oauth2 can be used without pulling in its default reqwest feature.
@@ -4,20 +4,29 @@ use crate::worker::Environment;
 use crate::worker::jobs::GenerateOgImage;
 use anyhow::Context;
 use async_compression::tokio::bufread::GzipDecoder;
+use crates_io_database::models::{NewScanFinding, scan_finding};
 use crates_io_database::schema::default_versions;
 use crates_io_linecount::{LinecountStats, PathDetails};
+use crates_io_tarball::Manifest;
+use crates_io_tarball::scan::{ContentScanner, Finding};
 use crates_io_worker::BackgroundJob;
 use diesel::dsl::exists;
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::io::{AsyncReadExt, BufReader};
 use tokio_util::io::StreamReader;
 use tracing::{info, instrument, warn};
 
+/// Upper limit for the number of content scanner findings that are stored
+/// for a single version, to keep crates with e.g. many vendored binaries from
+/// flooding the database.
+const MAX_SCAN_FINDINGS: usize = 100;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct AnalyzeCrateFile {
     version_id: i32,
@@ -51,9 +60,10 @@ impl BackgroundJob for AnalyzeCrateFile {
         };
 
         info!("Loading and analyzing crate file for {krate}@{version}… (version_id={version_id})");
-        let linecount_stats = analyze_crate_tarball(&krate, &version, &env.storage).await?;
+        let analysis = analyze_crate_tarball(&krate, &version, &env.storage).await?;
 
-        update_version_linecount_stats(version_id, &linecount_stats, &mut conn).await?;
+        update_version_linecount_stats(version_id, &analysis.linecount_stats, &mut conn).await?;
+        update_version_scan_findings(version_id, &analysis.findings, &mut conn).await?;
 
         info!(
             duration = start.elapsed().as_nanos(),
@@ -86,13 +96,20 @@ async fn get_crate_version_info(
         .context("Failed to query crate and version info")
 }
 
-/// Downloads and analyzes a crate tarball to generate linecount statistics.
+/// Results of the analysis of a crate tarball.
+struct Analysis {
+    linecount_stats: LinecountStats,
+    findings: Vec<Finding>,
+}
+
+/// Downloads and analyzes a crate tarball to generate linecount statistics
+/// and to scan the contents of the files.
 #[instrument(skip(storage))]
 async fn analyze_crate_tarball(
     krate: &str,
     version: &str,
     storage: &Storage,
-) -> anyhow::Result<LinecountStats> {
+) -> anyhow::Result<Analysis> {
     let key = StorageKey::for_crate_file(krate, version);
     let result = storage.download_stream(&key).await;
     let stream = result.context("Failed to download crate file")?;
@@ -105,7 +122,16 @@ async fn analyze_crate_tarball(
     let entries = archive.entries();
     let mut entries = entries.context("Failed to read tarball entries")?;
 
+    let prefix = format!("{krate}-{version}");
+
+    // The content scanner depends on the build script path in the manifest,
+    // so files that appear before the manifest in the tarball are buffered
+    // until it has been read.
+    let mut scanner = None;
+    let mut pending_files: Vec<(String, Vec<u8>)> = Vec::new();
+
     let mut linecount_stats = LinecountStats::new();
+    let mut findings = Vec::new();
     while let Some(entry) = entries.next().await {
         let mut entry = entry.context("Failed to read tarball entry")?;
         if !entry.header().entry_type().is_file() {
@@ -113,23 +139,69 @@ async fn analyze_crate_tarball(
             continue;
         }
 
-        let path = entry.path().context("Failed to get entry path")?;
+        let path = entry
+            .path()
+            .context("Failed to get entry path")?
+            .into_owned();
         let path_details = PathDetails::from_path(&path);
 
+        let mut contents = Vec::new();
+        let result = entry.read_to_end(&mut contents).await;
+        result.context("Failed to read entry contents")?;
+
         // Check if this file should be counted for line statistics
         if !path_details.should_ignore()
             && let Some(language_type) = path_details.language_type()
         {
-            // If this is a file that we want to count, read it and update the linecount stats.
-            let mut contents = Vec::new();
-            let result = entry.read_to_end(&mut contents).await;
-            result.context("Failed to read entry contents")?;
-
             linecount_stats.add_file(language_type, &contents);
         }
+
+        let relative_path = path.strip_prefix(&prefix).unwrap_or(&path);
+        let relative_path = relative_path.to_string_lossy().into_owned();
+
+        if scanner.is_none() && relative_path == "Cargo.toml" {
+            let manifest = std::str::from_utf8(&contents)
+                .ok()
+                .and_then(|contents| Manifest::from_str(contents).ok());
+
+            let content_scanner = match manifest {
+                Some(manifest) => ContentScanner::for_manifest(&manifest),
+                None => ContentScanner::default(),
+            };
+
+            for (path, contents) in pending_files.drain(..) {
+                findings.extend(content_scanner.scan(&path, &contents));
+            }
+
+            scanner = Some(content_scanner);
+        }
+
+        match &scanner {
+            Some(scanner) => findings.extend(scanner.scan(&relative_path, &contents)),
+            None => pending_files.push((relative_path, contents)),
+        }
     }
 
-    Ok(linecount_stats)
+    if !pending_files.is_empty() {
+        warn!("No Cargo.toml found in the crate file for {krate}@{version}");
+        let scanner = ContentScanner::default();
+        for (path, contents) in pending_files {
+            findings.extend(scanner.scan(&path, &contents));
+        }
+    }
+
+    if findings.len() > MAX_SCAN_FINDINGS {
+        warn!(
+            "Found {} scan findings for {krate}@{version}, only keeping the first {MAX_SCAN_FINDINGS}",
+            findings.len()
+        );
+        findings.truncate(MAX_SCAN_FINDINGS);
+    }
+
+    Ok(Analysis {
+        linecount_stats,
+        findings,
+    })
 }
 
 /// Updates the linecount statistics for a version in the database.
@@ -151,6 +223,31 @@ async fn update_version_linecount_stats(
     Ok(())
 }
 
+/// Replaces the content scanner findings for a version in the database.
+#[instrument(skip(conn, findings))]
+async fn update_version_scan_findings(
+    version_id: i32,
+    findings: &[Finding],
+    conn: &mut AsyncPgConnection,
+) -> anyhow::Result<()> {
+    let findings = findings
+        .iter()
+        .map(|finding| NewScanFinding {
+            version_id,
+            kind: finding.kind.as_str(),
+            path: &finding.path,
+            line: finding.line.and_then(|line| i32::try_from(line).ok()),
+            description: &finding.description,
+        })
+        .collect::<Vec<_>>();
+
+    scan_finding::replace(version_id, &findings, conn)
+        .await
+        .context("Failed to save scan findings to the database")?;
+
+    Ok(())
+}
+
 /// Checks whether the `version_id` is a default version of any crate and
 /// schedules an OpenGraph image rerender background job if that is the case.
 #[instrument(skip(conn))]
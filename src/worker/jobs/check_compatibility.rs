@@ -0,0 +1,228 @@
+use crate::models::Version;
+use crate::schema::{crates, version_compatibility_reports, versions};
+use crate::storage::{Storage, StorageKey};
+use crate::worker::Environment;
+use crate::worker::jobs::SendPublishNotificationsJob;
+use anyhow::{Context, anyhow};
+use async_compression::tokio::bufread::GzipDecoder;
+use crates_io_cargo_toml::Manifest;
+use crates_io_compat_check::{compare, is_compatible};
+use crates_io_worker::BackgroundJob;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, BufReader};
+use tokio_util::io::StreamReader;
+use tracing::{info, instrument, warn};
+
+/// Compares the `Cargo.toml` of a version with the one of the previous
+/// semver-compatible version of the crate, and saves the resulting
+/// compatibility report.
+///
+/// When the job is enqueued as part of a release, it also sends the publish
+/// notifications afterwards, so that they can include the warnings from the
+/// report. The notifications are sent even if the check fails.
+#[derive(Serialize, Deserialize)]
+pub struct CheckCompatibility {
+    version_id: i32,
+    #[serde(default)]
+    send_notifications: bool,
+}
+
+impl CheckCompatibility {
+    pub fn new(version_id: i32) -> Self {
+        let send_notifications = false;
+        Self {
+            version_id,
+            send_notifications,
+        }
+    }
+
+    /// Creates a job that sends the publish notifications for the version
+    /// once the check is done.
+    pub fn for_release(version_id: i32) -> Self {
+        let send_notifications = true;
+        Self {
+            version_id,
+            send_notifications,
+        }
+    }
+}
+
+impl BackgroundJob for CheckCompatibility {
+    const JOB_NAME: &'static str = "check_compatibility";
+    const DEDUPLICATED: bool = true;
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip_all, fields(version_id = ?self.version_id))]
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let version_id = self.version_id;
+
+        let mut conn = env.deadpool.get().await?;
+
+        let Some((krate, version)) = versions::table
+            .find(version_id)
+            .inner_join(crates::table)
+            .select((crates::name, Version::as_select()))
+            .first::<(String, Version)>(&mut conn)
+            .await
+            .optional()?
+        else {
+            warn!("version_id={version_id} not found in database, skipping compatibility check");
+            return Ok(());
+        };
+
+        info!("Checking compatibility of {krate}@{}…", version.num);
+
+        match build_report(&krate, &version, &env.storage, &conn).await {
+            Ok(Some(report)) => {
+                diesel::insert_into(version_compatibility_reports::table)
+                    .values((
+                        version_compatibility_reports::version_id.eq(version_id),
+                        version_compatibility_reports::report.eq(&report),
+                    ))
+                    .on_conflict(version_compatibility_reports::version_id)
+                    .do_update()
+                    .set((
+                        version_compatibility_reports::report.eq(&report),
+                        version_compatibility_reports::created_at.eq(now),
+                    ))
+                    .execute(&mut conn)
+                    .await
+                    .context("Failed to save compatibility report to the database")?;
+            }
+            Ok(None) => {
+                info!("No previous compatible version of {krate}@{}", version.num);
+            }
+            Err(error) => {
+                warn!(
+                    "Failed to check compatibility of {krate}@{}: {error:#}",
+                    version.num
+                );
+            }
+        }
+
+        if self.send_notifications {
+            SendPublishNotificationsJob::new(version_id)
+                .enqueue(&conn)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares the version with the highest previous version of the crate that
+/// is semver-compatible with it, ignoring yanked and staged versions.
+///
+/// Returns `None` if there is no such version.
+async fn build_report(
+    krate: &str,
+    version: &Version,
+    storage: &Storage,
+    mut conn: &AsyncPgConnection,
+) -> anyhow::Result<Option<serde_json::Value>> {
+    let semver: semver::Version = version.num.parse()?;
+
+    let candidates: Vec<Version> = versions::table
+        .filter(versions::crate_id.eq(version.crate_id))
+        .filter(versions::id.ne(version.id))
+        .filter(versions::yanked.eq(false))
+        .filter(versions::staged_at.is_null())
+        .select(Version::as_select())
+        .load(&mut conn)
+        .await?;
+
+    let previous = candidates
+        .into_iter()
+        .filter_map(|version| Some((version.num.parse::<semver::Version>().ok()?, version)))
+        .filter(|(num, _)| *num < semver && is_compatible(num, &semver))
+        .max_by(|(a, _), (b, _)| a.cmp(b));
+
+    let Some((previous_semver, previous)) = previous else {
+        return Ok(None);
+    };
+
+    let previous_manifest = load_cargo_toml(krate, &previous, storage)
+        .await
+        .with_context(|| format!("Failed to load `Cargo.toml` of {krate}@{}", previous.num))?;
+
+    let manifest = load_cargo_toml(krate, version, storage)
+        .await
+        .with_context(|| format!("Failed to load `Cargo.toml` of {krate}@{}", version.num))?;
+
+    let report = compare(&previous_semver, &previous_manifest, &semver, &manifest);
+    Ok(Some(serde_json::to_value(report)?))
+}
+
+/// Loads the `Cargo.toml` of a version from its zip archive, or from the
+/// `.crate` file if the zip archive has not been built yet.
+#[instrument(skip(storage, version), fields(version = %version.num))]
+async fn load_cargo_toml(
+    krate: &str,
+    version: &Version,
+    storage: &Storage,
+) -> anyhow::Result<Manifest> {
+    let contents = match &version.zip_json_sha256 {
+        Some(sha256) => read_from_zip(krate, &version.num, sha256, storage).await?,
+        None => read_from_tarball(krate, &version.num, storage).await?,
+    };
+
+    Ok(Manifest::from_slice(&contents)?)
+}
+
+/// Reads the `Cargo.toml` from the zip archive, using the offsets from the
+/// archive manifest to only download the compressed file.
+async fn read_from_zip(
+    krate: &str,
+    version: &str,
+    expected_sha256: &[u8],
+    storage: &Storage,
+) -> anyhow::Result<Vec<u8>> {
+    let key = StorageKey::for_crate_zip_manifest(krate, version);
+    let bytes = storage.download(&key).await?;
+    if Sha256::digest(&bytes).as_slice() != expected_sha256 {
+        return Err(anyhow!("Checksum mismatch for zip manifest"));
+    }
+
+    let manifest: crates_io_crate_zip::Manifest = serde_json::from_slice(&bytes)?;
+    let entry = manifest
+        .files
+        .into_iter()
+        .find(|file| file.path == "Cargo.toml")
+        .ok_or_else(|| anyhow!("Zip archive does not contain a `Cargo.toml` file"))?;
+
+    let key = StorageKey::for_crate_zip(krate, version);
+    let payload = storage.download_range(&key, entry.range()).await?;
+    let contents = tokio::task::spawn_blocking(move || entry.decode(&payload)).await??;
+    Ok(contents)
+}
+
+async fn read_from_tarball(
+    krate: &str,
+    version: &str,
+    storage: &Storage,
+) -> anyhow::Result<Vec<u8>> {
+    let key = StorageKey::for_crate_file(krate, version);
+    let stream = storage.download_stream(&key).await?;
+    let reader = BufReader::new(StreamReader::new(stream));
+    let mut archive = tokio_tar::Archive::new(GzipDecoder::new(reader));
+
+    let path = format!("{krate}-{version}/Cargo.toml");
+    let mut entries = archive.entries()?;
+    while let Some(entry) = entries.next().await {
+        let mut entry = entry?;
+        if entry.path()?.to_str() == Some(&path) {
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents).await?;
+            return Ok(contents);
+        }
+    }
+
+    Err(anyhow!("Crate file does not contain a `Cargo.toml` file"))
+}
@@ -1,5 +1,8 @@
+use crate::schema::{cargo_version_downloads, client_downloads};
 use crate::worker::Environment;
 use crates_io_worker::BackgroundJob;
+use diesel::dsl::{IntervalDsl, date, now};
+use diesel::prelude::*;
 use diesel::sql_query;
 use diesel_async::RunQueryDsl;
 use serde::{Deserialize, Serialize};
@@ -24,6 +27,9 @@ impl BackgroundJob for DailyDbMaintenance {
     /// We only need to keep 90 days of entries in `version_downloads`. Once we have a mechanism to
     /// archive daily download counts and drop historical data, we can drop this task and rely on
     /// auto-vacuum again.
+    ///
+    /// The download counts per cargo version and per client are not archived,
+    /// so entries older than 90 days are deleted.
     async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
         let mut conn = env.deadpool.get().await?;
 
@@ -32,6 +38,21 @@ impl BackgroundJob for DailyDbMaintenance {
             .execute(&mut conn)
             .await?;
         info!("Finished running VACUUM on version_downloads table");
+
+        let cutoff = date(now - 90.days());
+
+        let deleted = diesel::delete(cargo_version_downloads::table)
+            .filter(cargo_version_downloads::date.lt(cutoff))
+            .execute(&mut conn)
+            .await?;
+        info!("Deleted {deleted} rows from cargo_version_downloads table");
+
+        let deleted = diesel::delete(client_downloads::table)
+            .filter(client_downloads::date.lt(cutoff))
+            .execute(&mut conn)
+            .await?;
+        info!("Deleted {deleted} rows from client_downloads table");
+
         Ok(())
     }
 }
@@ -1,7 +1,9 @@
 use crate::config::CdnLogStorageConfig;
+use crate::models::download::DownloadClient;
 use crate::worker::Environment;
 use anyhow::Context;
 use chrono::NaiveDate;
+use crates_io_cdn_logs::user_agent::ClientKind;
 use crates_io_cdn_logs::{Decompressor, DownloadsMap, count_downloads};
 use crates_io_worker::BackgroundJob;
 use diesel::dsl::exists;
@@ -24,6 +26,10 @@ use tracing::{debug, info, instrument, warn};
 /// A background job that loads a CDN log file from an object store (aka. S3),
 /// counts the number of downloads for each crate and version, and then inserts
 /// the results into the database.
+///
+/// The downloads are additionally counted per crate, cargo version and client
+/// class, and saved to the `cargo_version_downloads` and `client_downloads`
+/// tables.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProcessCdnLog {
     pub region: String,
@@ -218,6 +224,11 @@ pub async fn save_downloads(
     downloads: DownloadsMap,
     conn: &mut AsyncPgConnection,
 ) -> anyhow::Result<()> {
+    debug!("Saving per-client download counts");
+    save_client_downloads(&downloads, conn)
+        .await
+        .context("Failed to save per-client download counts")?;
+
     debug!("Creating temp_downloads table");
     create_temp_downloads_table(conn)
         .await
@@ -358,6 +369,145 @@ impl Debug for NameAndVersion {
     }
 }
 
+table! {
+    /// Diesel table definition for the temporary `temp_cargo_version_downloads`
+    /// table that is created by the [`save_client_downloads`] function.
+    temp_cargo_version_downloads (name, date, cargo_version) {
+        name -> Text,
+        date -> Date,
+        cargo_version -> Text,
+        downloads -> BigInt,
+    }
+}
+
+table! {
+    /// Diesel table definition for the temporary `temp_client_downloads`
+    /// table that is created by the [`save_client_downloads`] function.
+    temp_client_downloads (name, date, client) {
+        name -> Text,
+        date -> Date,
+        client -> Integer,
+        downloads -> BigInt,
+    }
+}
+
+/// Saves the per-cargo-version and per-client download counts from the given
+/// [`DownloadsMap`] to the `cargo_version_downloads` and `client_downloads`
+/// tables.
+///
+/// Similar to [`save_downloads`], the counts are first inserted into
+/// temporary tables, so that the crate IDs can be looked up with a join.
+/// Downloads of unknown crates are ignored.
+async fn save_client_downloads(
+    downloads: &DownloadsMap,
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<()> {
+    const MAX_BATCH_SIZE: usize = 5_000;
+
+    diesel::sql_query(
+        r#"
+            CREATE TEMPORARY TABLE temp_cargo_version_downloads (
+                name VARCHAR NOT NULL,
+                date DATE NOT NULL,
+                cargo_version VARCHAR NOT NULL,
+                downloads INTEGER NOT NULL
+            ) ON COMMIT DROP;
+        "#,
+    )
+    .execute(conn)
+    .await?;
+
+    diesel::sql_query(
+        r#"
+            CREATE TEMPORARY TABLE temp_client_downloads (
+                name VARCHAR NOT NULL,
+                date DATE NOT NULL,
+                client INTEGER NOT NULL,
+                downloads INTEGER NOT NULL
+            ) ON COMMIT DROP;
+        "#,
+    )
+    .execute(conn)
+    .await?;
+
+    let cargo_versions = downloads
+        .cargo_versions()
+        .map(|(name, cargo_version, date, downloads)| {
+            (
+                temp_cargo_version_downloads::name.eq(name),
+                temp_cargo_version_downloads::date.eq(date),
+                temp_cargo_version_downloads::cargo_version.eq(cargo_version.to_string()),
+                temp_cargo_version_downloads::downloads.eq(downloads as i64),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for chunk in cargo_versions.chunks(MAX_BATCH_SIZE) {
+        diesel::insert_into(temp_cargo_version_downloads::table)
+            .values(chunk)
+            .execute(conn)
+            .await?;
+    }
+
+    let clients = downloads
+        .clients()
+        .map(|(name, client, date, downloads)| {
+            (
+                temp_client_downloads::name.eq(name),
+                temp_client_downloads::date.eq(date),
+                temp_client_downloads::client.eq(download_client(client) as i32),
+                temp_client_downloads::downloads.eq(downloads as i64),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for chunk in clients.chunks(MAX_BATCH_SIZE) {
+        diesel::insert_into(temp_client_downloads::table)
+            .values(chunk)
+            .execute(conn)
+            .await?;
+    }
+
+    diesel::sql_query(
+        r#"
+            INSERT INTO cargo_version_downloads (crate_id, date, cargo_version, downloads)
+            SELECT crates.id, temp.date, temp.cargo_version, temp.downloads
+            FROM temp_cargo_version_downloads temp
+            INNER JOIN crates ON crates.name = temp.name
+            ORDER BY crates.id, temp.date, temp.cargo_version
+            ON CONFLICT (crate_id, date, cargo_version)
+            DO UPDATE SET downloads = cargo_version_downloads.downloads + EXCLUDED.downloads;
+        "#,
+    )
+    .execute(conn)
+    .await?;
+
+    diesel::sql_query(
+        r#"
+            INSERT INTO client_downloads (crate_id, date, client, downloads)
+            SELECT crates.id, temp.date, temp.client, temp.downloads
+            FROM temp_client_downloads temp
+            INNER JOIN crates ON crates.name = temp.name
+            ORDER BY crates.id, temp.date, temp.client
+            ON CONFLICT (crate_id, date, client)
+            DO UPDATE SET downloads = client_downloads.downloads + EXCLUDED.downloads;
+        "#,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+fn download_client(kind: ClientKind) -> DownloadClient {
+    match kind {
+        ClientKind::Cargo => DownloadClient::Cargo,
+        ClientKind::Ci => DownloadClient::Ci,
+        ClientKind::Mirror => DownloadClient::Mirror,
+        ClientKind::Other => DownloadClient::Other,
+    }
+}
+
 /// Checks if the given log file has already been processed.
 ///
 /// Acquires a connection from the pool before passing it to the
@@ -409,7 +559,9 @@ async fn save_as_processed(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{crates, version_downloads, versions};
+    use crate::schema::{
+        cargo_version_downloads, client_downloads, crates, version_downloads, versions,
+    };
     use claims::assert_ok;
     use crates_io_test_db::TestDatabase;
     use diesel_async::pooled_connection::AsyncDieselConnectionManager;
@@ -440,6 +592,18 @@ mod tests {
             "tracing-core | 0.1.32 | 1 | 0 | 2024-01-16 | false",
         ]
         "#);
+        assert_debug_snapshot!(all_client_downloads(db_pool.clone()).await, @r#"
+        [
+            "bindgen | cargo/1.74 | 1 | 2024-01-16",
+            "quick-error | cargo/1.74 | 2 | 2024-01-16",
+            "quick-error | cargo/1.74 | 1 | 2024-01-17",
+            "tracing-core | cargo/1.74 | 1 | 2024-01-16",
+            "bindgen | Cargo | 1 | 2024-01-16",
+            "quick-error | Cargo | 2 | 2024-01-16",
+            "quick-error | Cargo | 1 | 2024-01-17",
+            "tracing-core | Cargo | 1 | 2024-01-16",
+        ]
+        "#);
 
         // Check that processing the same log file again does not insert
         // duplicate data.
@@ -541,6 +705,51 @@ mod tests {
             .collect()
     }
 
+    /// Queries all per-cargo-version and per-client downloads from the
+    /// database and returns them as a [`Vec`] of strings for use with
+    /// [`assert_debug_snapshot!()`].
+    async fn all_client_downloads(db_pool: Pool<AsyncPgConnection>) -> Vec<String> {
+        let mut conn = db_pool.get().await.unwrap();
+
+        let cargo_versions: Vec<(String, NaiveDate, String, i32)> = cargo_version_downloads::table
+            .inner_join(crates::table)
+            .select((
+                crates::name,
+                cargo_version_downloads::date,
+                cargo_version_downloads::cargo_version,
+                cargo_version_downloads::downloads,
+            ))
+            .order((crates::name, cargo_version_downloads::date))
+            .load(&mut conn)
+            .await
+            .unwrap();
+
+        let clients: Vec<(String, NaiveDate, DownloadClient, i32)> = client_downloads::table
+            .inner_join(crates::table)
+            .select((
+                crates::name,
+                client_downloads::date,
+                client_downloads::client,
+                client_downloads::downloads,
+            ))
+            .order((crates::name, client_downloads::date))
+            .load(&mut conn)
+            .await
+            .unwrap();
+
+        let cargo_versions = cargo_versions
+            .into_iter()
+            .map(|(name, date, version, downloads)| {
+                format!("{name} | cargo/{version} | {downloads} | {date}")
+            });
+
+        let clients = clients.into_iter().map(|(name, date, client, downloads)| {
+            format!("{name} | {client:?} | {downloads} | {date}")
+        });
+
+        cargo_versions.chain(clients).collect()
+    }
+
     /// Queries all version downloads from the database and returns them as a
     /// [`Vec`] of tuples.
     async fn query_all_version_downloads(
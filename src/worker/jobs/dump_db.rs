@@ -2,7 +2,7 @@ use crate::storage::StorageKey;
 use crate::tasks::spawn_blocking;
 use crate::worker::Environment;
 use crates_io_database::models::CloudFrontDistribution;
-use crates_io_database_dump::{DumpDirectory, create_archives};
+use crates_io_database_dump::{DumpDirectory, create_archives, create_columnar_archives};
 use crates_io_worker::BackgroundJob;
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
@@ -37,7 +37,8 @@ impl BackgroundJob for DumpDb {
     type Context = Arc<Environment>;
 
     /// Creates CSV dumps of the public information in the database, wraps them in a
-    /// tarball and uploads to S3.
+    /// tarball and uploads to S3. The same data is also uploaded as Parquet files
+    /// and as a SQLite database.
     async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
         let db_config = &env.config.db;
         let db_pool_config = db_config.replica.as_ref().unwrap_or(&db_config.primary);
@@ -45,42 +46,44 @@ impl BackgroundJob for DumpDb {
         let postgres_bin_dir = env.config.postgres_bin_dir.clone();
         let schema = self.schema.clone();
 
-        let archives = spawn_blocking(move || {
+        let (archives, columnar_archives) = spawn_blocking(move || {
             let directory = DumpDirectory::create(postgres_bin_dir)?;
 
             info!("Exporting database…");
             directory.populate(database_url.expose_secret(), schema.as_deref())?;
 
+            info!("Converting database dump to Parquet and SQLite…");
+            directory.populate_columnar(database_url.expose_secret())?;
+
             let export_dir = directory.path();
             info!(path = ?export_dir, "Creating tarball…");
             let tarball_prefix = PathBuf::from(directory.timestamp.format("%F-%H%M%S").to_string());
-            create_archives(export_dir, &tarball_prefix)
+            let archives = create_archives(export_dir, &tarball_prefix)?;
+            let columnar_archives = create_columnar_archives(export_dir, &tarball_prefix)?;
+            Ok::<_, anyhow::Error>((archives, columnar_archives))
         })
         .await??;
 
-        info!("Uploading tarball…");
-        let tar_key = StorageKey::DbDumpTar;
-        let tar_file = tokio::fs::File::open(archives.tar.path()).await?;
-        env.storage.upload_stream(&tar_key, tar_file).await?;
-        info!("Database dump tarball uploaded");
-
-        info!("Invalidating CDN caches…");
         let conn = env.deadpool.get().await?;
         let dist = CloudFrontDistribution::Static;
 
-        if let Err(error) = env.invalidate_cdns(&conn, dist, &tar_key.cdn_path()).await {
-            warn!("Failed to invalidate CDN caches: {error}");
-        }
+        let files = [
+            (StorageKey::DbDumpTar, archives.tar),
+            (StorageKey::DbDumpZip, archives.zip),
+            (StorageKey::DbDumpParquet, columnar_archives.parquet),
+            (StorageKey::DbDumpSqlite, columnar_archives.sqlite),
+        ];
 
-        info!("Uploading zip file…");
-        let zip_key = StorageKey::DbDumpZip;
-        let zip_file = tokio::fs::File::open(archives.zip.path()).await?;
-        env.storage.upload_stream(&zip_key, zip_file).await?;
-        info!("Database dump zip file uploaded");
+        for (key, tempfile) in files {
+            info!(path = %key.path(), "Uploading database dump…");
+            let file = tokio::fs::File::open(tempfile.path()).await?;
+            env.storage.upload_stream(&key, file).await?;
+            info!(path = %key.path(), "Database dump uploaded");
 
-        info!("Invalidating CDN caches…");
-        if let Err(error) = env.invalidate_cdns(&conn, dist, &zip_key.cdn_path()).await {
-            warn!("Failed to invalidate CDN caches: {error}");
+            info!("Invalidating CDN caches…");
+            if let Err(error) = env.invalidate_cdns(&conn, dist, &key.cdn_path()).await {
+                warn!("Failed to invalidate CDN caches: {error}");
+            }
         }
 
         Ok(())
@@ -0,0 +1,171 @@
+use crate::storage::StorageKey;
+use crate::tasks::spawn_blocking;
+use crate::worker::Environment;
+use chrono::{DateTime, TimeDelta, Utc};
+use crates_io_database::models::CloudFrontDistribution;
+use crates_io_database_dump::{DeltaState, DumpDirectory, create_archives};
+use crates_io_worker::BackgroundJob;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Delta dumps are listed in the `manifest.json` file, and kept in the
+/// storage backend, for this duration. This has to be longer than the
+/// interval of the full database dumps, so that a delta chain is always
+/// available on top of the latest full dump.
+const DELTA_RETENTION: TimeDelta = TimeDelta::days(7);
+
+/// The contents of the `db-dump-deltas/manifest.json` file, which chains the
+/// available delta dumps.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeltaIndex {
+    /// The available delta dumps, ordered by their timestamp.
+    pub deltas: Vec<DeltaIndexEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeltaIndexEntry {
+    pub timestamp: DateTime<Utc>,
+    pub previous: DateTime<Utc>,
+    pub tar: String,
+    pub zip: String,
+}
+
+/// A background job that creates an incremental dump of the public
+/// information in the database, with the rows that changed or were deleted
+/// since the previous run of this job.
+///
+/// The first run only records the state of the database, which the following
+/// runs are based on.
+#[derive(Default, Serialize, Deserialize, Debug)]
+pub struct DumpDbDelta;
+
+impl BackgroundJob for DumpDbDelta {
+    const JOB_NAME: &'static str = "dump_db_delta";
+    const DEDUPLICATED: bool = true;
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, env: Self::Context) -> anyhow::Result<()> {
+        let db_config = &env.config.db;
+        let db_pool_config = db_config.replica.as_ref().unwrap_or(&db_config.primary);
+        let database_url = db_pool_config.url.clone();
+        let postgres_bin_dir = env.config.postgres_bin_dir.clone();
+
+        info!("Downloading state of the previous delta dump…");
+        let previous_state = match env.storage.download(&StorageKey::DbDumpDeltaState).await {
+            Ok(bytes) => Some(bytes),
+            Err(object_store::Error::NotFound { .. }) => None,
+            Err(error) => return Err(error.into()),
+        };
+
+        let (delta, state_archive) = spawn_blocking(move || {
+            let state_dir = tempfile::tempdir()?;
+            let previous = previous_state
+                .map(|bytes| DeltaState::unpack(&bytes[..], state_dir.path()))
+                .transpose()?;
+
+            let directory = DumpDirectory::create(postgres_bin_dir)?;
+
+            info!("Exporting database changes…");
+            let (manifest, state) =
+                directory.populate_delta(database_url.expose_secret(), previous.as_ref())?;
+
+            let delta = match manifest {
+                Some(manifest) => {
+                    let export_dir = directory.path();
+                    info!(path = ?export_dir, "Creating tarball…");
+                    let name = archive_name(&directory.timestamp);
+                    let archives = create_archives(export_dir, &PathBuf::from(&name))?;
+                    Some((manifest, name, archives))
+                }
+                None => {
+                    info!("No previous delta dump found, only recording the current state");
+                    None
+                }
+            };
+
+            let state_archive = state.archive()?;
+            Ok::<_, anyhow::Error>((delta, state_archive))
+        })
+        .await??;
+
+        if let Some((manifest, name, archives)) = delta {
+            info!("Uploading tarball…");
+            let tar_key = StorageKey::DbDumpDeltaTar { name: &name };
+            let tar_file = tokio::fs::File::open(archives.tar.path()).await?;
+            env.storage.upload_stream(&tar_key, tar_file).await?;
+
+            info!("Uploading zip file…");
+            let zip_key = StorageKey::DbDumpDeltaZip { name: &name };
+            let zip_file = tokio::fs::File::open(archives.zip.path()).await?;
+            env.storage.upload_stream(&zip_key, zip_file).await?;
+            info!("Delta dump archives uploaded");
+
+            let mut index = match env.storage.download(&StorageKey::DbDumpDeltaManifest).await {
+                Ok(bytes) => serde_json::from_slice(&bytes)?,
+                Err(object_store::Error::NotFound { .. }) => DeltaIndex::default(),
+                Err(error) => return Err(error.into()),
+            };
+
+            index.deltas.push(DeltaIndexEntry {
+                timestamp: manifest.timestamp,
+                previous: manifest.previous,
+                tar: tar_key.path().to_string(),
+                zip: zip_key.path().to_string(),
+            });
+
+            let cutoff = manifest.timestamp - DELTA_RETENTION;
+            let (expired, deltas) = index
+                .deltas
+                .into_iter()
+                .partition::<Vec<_>, _>(|entry| entry.timestamp < cutoff);
+            index.deltas = deltas;
+
+            info!("Uploading manifest file…");
+            let manifest_key = StorageKey::DbDumpDeltaManifest;
+            let content = serde_json::to_vec_pretty(&index)?;
+            env.storage.upload(&manifest_key, content.into()).await?;
+
+            info!("Invalidating CDN caches…");
+            let conn = env.deadpool.get().await?;
+            let dist = CloudFrontDistribution::Static;
+            let path = manifest_key.cdn_path();
+            if let Err(error) = env.invalidate_cdns(&conn, dist, &path).await {
+                warn!("Failed to invalidate CDN caches: {error}");
+            }
+
+            for entry in expired {
+                info!(timestamp = %entry.timestamp, "Deleting expired delta dump…");
+                let name = archive_name(&entry.timestamp);
+                let keys = [
+                    StorageKey::DbDumpDeltaTar { name: &name },
+                    StorageKey::DbDumpDeltaZip { name: &name },
+                ];
+                for key in keys {
+                    if let Err(error) = env.storage.delete(&key).await {
+                        warn!("Failed to delete expired delta dump: {error}");
+                    }
+                }
+            }
+        }
+
+        // The state is uploaded last, so that a failed run is repeated with
+        // the same previous state.
+        info!("Uploading state tarball…");
+        let state_file = tokio::fs::File::open(state_archive.path()).await?;
+        env.storage
+            .upload_stream(&StorageKey::DbDumpDeltaState, state_file)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// The name of the archives of the delta dump from `timestamp`, which is also
+/// the name of the directory inside of them.
+fn archive_name(timestamp: &DateTime<Utc>) -> String {
+    timestamp.format("%F-%H%M%S").to_string()
+}
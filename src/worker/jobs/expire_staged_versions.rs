@@ -0,0 +1,111 @@
+use crate::schema::{crates, versions};
+use crate::storage::{StorageKey, release_cache_tag};
+use crate::worker::Environment;
+use crate::worker::jobs::{InvalidateCdns, UpdateDefaultVersion};
+use chrono::{TimeDelta, Utc};
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Staged versions that have not been promoted within this duration after
+/// they were uploaded are deleted.
+const STAGED_VERSION_TTL: TimeDelta = TimeDelta::days(7);
+
+/// A background job that deletes abandoned staged versions from the
+/// database and their files from the storage backend.
+#[derive(Default, Serialize, Deserialize, Debug)]
+pub struct ExpireStagedVersions;
+
+impl BackgroundJob for ExpireStagedVersions {
+    const JOB_NAME: &'static str = "expire_staged_versions";
+    const DEDUPLICATED: bool = true;
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+        let mut conn = ctx.deadpool.get().await?;
+
+        let threshold = Utc::now() - STAGED_VERSION_TTL;
+
+        let candidates: Vec<(i32, i32, String, String)> = versions::table
+            .inner_join(crates::table)
+            .filter(versions::staged_at.lt(threshold))
+            .select((versions::id, crates::id, crates::name, versions::num))
+            .load(&mut conn)
+            .await?;
+
+        if candidates.is_empty() {
+            info!("No expired staged versions found");
+            return Ok(());
+        }
+
+        // The `staged_at` filter is repeated to skip versions that have been
+        // promoted since they were loaded.
+        let ids = candidates.iter().map(|(id, ..)| *id).collect::<Vec<_>>();
+        let deleted_ids: Vec<i32> = diesel::delete(versions::table)
+            .filter(versions::id.eq_any(&ids))
+            .filter(versions::staged_at.lt(threshold))
+            .returning(versions::id)
+            .get_results(&mut conn)
+            .await?;
+
+        info!("Deleted {} expired staged versions", deleted_ids.len());
+
+        let deleted = candidates
+            .iter()
+            .filter(|(id, ..)| deleted_ids.contains(id))
+            .collect::<Vec<_>>();
+
+        let mut paths = Vec::new();
+        let mut cache_tags = Vec::new();
+        for (_, _, name, version) in &deleted {
+            info!("{name}@{version}: Deleting files of expired staged version from S3…");
+
+            let keys = [
+                StorageKey::for_crate_file(name, version),
+                StorageKey::for_crate_zip(name, version),
+                StorageKey::for_crate_zip_manifest(name, version),
+                StorageKey::for_readme(name, version),
+                StorageKey::for_readme_toc(name, version),
+            ];
+
+            for key in keys {
+                match ctx.storage.delete(&key).await {
+                    Ok(()) => paths.push(key.path()),
+                    Err(object_store::Error::NotFound { .. }) => {}
+                    Err(error) => {
+                        warn!(
+                            "{name}@{version}: Failed to delete `{}`: {error}",
+                            key.path()
+                        );
+                    }
+                }
+            }
+
+            cache_tags.push(release_cache_tag(name, version));
+        }
+
+        let job = if ctx.config.features.cache_tag_invalidations_enabled {
+            InvalidateCdns::cache_tags(cache_tags)
+        } else {
+            InvalidateCdns::paths(paths.into_iter())
+        };
+        job.enqueue(&conn).await?;
+
+        let mut crate_ids = deleted
+            .iter()
+            .map(|(_, crate_id, ..)| *crate_id)
+            .collect::<Vec<_>>();
+        crate_ids.sort_unstable();
+        crate_ids.dedup();
+
+        for crate_id in crate_ids {
+            UpdateDefaultVersion::new(crate_id).enqueue(&conn).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
-use crate::models::ApiToken;
+use crate::email::{EmailMessage, Unsubscribe};
+use crate::models::{ApiToken, NotificationCategory, NotificationPreference, NotificationSettings};
 use crate::schema::api_tokens;
-use crate::{Emails, email::EmailMessage, models::User, worker::Environment};
+use crate::{Emails, models::User, worker::Environment};
 use chrono::SecondsFormat;
 use crates_io_worker::BackgroundJob;
 use diesel::dsl::now;
@@ -82,11 +83,22 @@ async fn handle_expiring_token(
     debug!("Looking up user {} for token {}…", token.user_id, token.id);
     let user = User::find(conn, token.user_id).await?;
 
+    let category = NotificationCategory::TokenExpiry;
+    let is_enabled = NotificationPreference::is_enabled(conn, user.id, category, None).await?;
+
     debug!("Looking up email address for user {}…", user.id);
     let recipient = user.email(conn).await?;
-    if let Some(recipient) = recipient {
+    if !is_enabled {
+        info!(
+            "User {} has disabled expiry notifications. Skipping expiry notification.",
+            user.id
+        );
+    } else if let Some(recipient) = recipient {
+        let unsubscribe_token = NotificationSettings::unsubscribe_token(conn, user.id).await?;
+        let unsubscribe = Unsubscribe::new(&emails.domain, &unsubscribe_token, category);
+
         debug!("Sending expiry notification to {}…", recipient);
-        let email = EmailMessage::from_template(
+        let email = EmailMessage::from_template_with_unsubscribe(
             "expiry_notification",
             context! {
                 name => user.gh_login,
@@ -94,6 +106,7 @@ async fn handle_expiring_token(
                 token_name => token.name,
                 expiry_date => token.expired_at.unwrap().to_rfc3339_opts(SecondsFormat::Secs, true)
             },
+            unsubscribe,
         )?;
         emails.send(&recipient, email).await?;
     } else {
@@ -39,6 +39,9 @@ impl GenerateOgImage {
 impl BackgroundJob for GenerateOgImage {
     const JOB_NAME: &'static str = "generate_og_image";
     const DEDUPLICATED: bool = true;
+    // OpenGraph images are cosmetic, so there is no point in retrying a
+    // crate that permanently fails to render forever.
+    const MAX_RETRIES: Option<u32> = Some(5);
 
     type Context = Arc<Environment>;
 
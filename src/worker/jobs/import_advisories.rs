@@ -0,0 +1,148 @@
+use crate::models::NewAdvisory;
+use crate::schema::{advisories, crates, version_advisories, versions};
+use crate::tasks::spawn_blocking;
+use crate::worker::Environment;
+use anyhow::Context;
+use crates_io_rustsec::{Advisory, load_advisories};
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// A background job that imports the security advisories from a local
+/// checkout of a RustSec-format advisory database, and links them to the
+/// affected versions.
+///
+/// Advisories that are no longer part of the database are deleted. Versions
+/// that are published after the import are only linked to the advisories
+/// on the next run of this job.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ImportAdvisories {
+    path: PathBuf,
+}
+
+impl ImportAdvisories {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self { path }
+    }
+}
+
+impl BackgroundJob for ImportAdvisories {
+    const JOB_NAME: &'static str = "import_advisories";
+    const DEDUPLICATED: bool = true;
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+        let path = self.path.clone();
+        info!("Loading advisories from {}…", path.display());
+        let advisories = spawn_blocking(move || load_advisories(&path)).await??;
+
+        // An empty list most likely means that the path is wrong, and we
+        // don't want to delete all previously imported advisories in that
+        // case.
+        if advisories.is_empty() {
+            anyhow::bail!("No advisories found in {}", self.path.display());
+        }
+
+        let mut conn = ctx.deadpool.get().await?;
+
+        let mut num_links = 0;
+        for advisory in &advisories {
+            num_links += import_advisory(advisory, &mut conn)
+                .await
+                .with_context(|| format!("Failed to import {}", advisory.id))?;
+        }
+
+        let ids = advisories.iter().map(|a| &a.id).collect::<Vec<_>>();
+        let num_deleted = diesel::delete(advisories::table)
+            .filter(advisories::id.ne_all(ids))
+            .execute(&mut conn)
+            .await?;
+
+        info!(
+            "Imported {} advisories affecting {num_links} versions, deleted {num_deleted} advisories",
+            advisories.len(),
+        );
+
+        Ok(())
+    }
+}
+
+/// Saves the advisory and replaces its links to the affected versions.
+///
+/// Returns the number of affected versions.
+async fn import_advisory(advisory: &Advisory, conn: &mut AsyncPgConnection) -> QueryResult<usize> {
+    let new_advisory = NewAdvisory {
+        id: &advisory.id,
+        crate_name: &advisory.package,
+        title: &advisory.title,
+        description: &advisory.description,
+        date: advisory.date,
+        url: advisory.url.as_deref(),
+        aliases: &advisory.aliases,
+        informational: advisory.informational.as_deref(),
+        withdrawn: advisory.withdrawn,
+        patched: advisory.patched.iter().map(ToString::to_string).collect(),
+        unaffected: advisory
+            .unaffected
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+    };
+
+    let affected_ids = if advisory.withdrawn.is_some() {
+        vec![]
+    } else {
+        let versions: Vec<(i32, String)> = versions::table
+            .inner_join(crates::table)
+            .filter(crates::name.eq(&advisory.package))
+            .select((versions::id, versions::num))
+            .load(conn)
+            .await?;
+
+        versions
+            .into_iter()
+            .filter(|(_, num)| match num.parse() {
+                Ok(version) => advisory.affects(&version),
+                Err(error) => {
+                    warn!(
+                        "Failed to parse version {num} of {}: {error}",
+                        advisory.package
+                    );
+                    false
+                }
+            })
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    };
+
+    conn.transaction(async |conn| {
+        new_advisory.upsert(conn).await?;
+
+        diesel::delete(version_advisories::table)
+            .filter(version_advisories::advisory_id.eq(&advisory.id))
+            .execute(conn)
+            .await?;
+
+        let links = affected_ids
+            .iter()
+            .map(|version_id| {
+                (
+                    version_advisories::version_id.eq(version_id),
+                    version_advisories::advisory_id.eq(&advisory.id),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(version_advisories::table)
+            .values(&links)
+            .execute(conn)
+            .await
+    })
+    .await
+}
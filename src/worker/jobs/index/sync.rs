@@ -3,9 +3,12 @@ use crate::tasks::spawn_blocking;
 use crate::worker::Environment;
 use crate::worker::jobs::ProcessCloudfrontInvalidationQueue;
 use anyhow::Context;
-use crates_io_database::models::{CloudFrontDistribution, CloudFrontInvalidationQueueItem};
+use crates_io_database::models::{
+    CloudFrontDistribution, CloudFrontInvalidationQueueItem, CrateReader,
+};
 use crates_io_index::Repository;
 use crates_io_worker::BackgroundJob;
+use diesel_async::AsyncPgConnection;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Instant;
@@ -40,7 +43,7 @@ impl BackgroundJob for SyncToGitIndex {
         let crate_name = self.krate.clone();
         let mut conn = env.deadpool.get().await?;
 
-        let new = get_index_data(
+        let new = get_git_index_data(
             &crate_name,
             &mut conn,
             env.config.features.index_include_pubtime,
@@ -85,6 +88,24 @@ impl BackgroundJob for SyncToGitIndex {
     }
 }
 
+/// Returns the content of the git index file of a crate.
+///
+/// Crates with restricted read access are left out of the git index, since
+/// everyone with access to the repository can read all of its files. They
+/// are only available via the sparse index.
+async fn get_git_index_data(
+    name: &str,
+    conn: &mut AsyncPgConnection,
+    include_pubtime: bool,
+) -> anyhow::Result<Option<String>> {
+    if CrateReader::is_restricted_by_name(conn, name).await? {
+        debug!("Leaving out crate with restricted read access");
+        return Ok(None);
+    }
+
+    get_index_data(name, conn, include_pubtime).await
+}
+
 /// Syncs index files for multiple crates in a single commit.
 #[derive(Serialize, Deserialize)]
 pub struct BulkSyncToGitIndex {
@@ -127,7 +148,7 @@ impl BackgroundJob for BulkSyncToGitIndex {
                 let new = handle
                     .block_on(async {
                         let mut conn = env.deadpool.get().await?;
-                        get_index_data(crate_name, &mut conn, include_pubtime).await
+                        get_git_index_data(crate_name, &mut conn, include_pubtime).await
                     })
                     .with_context(|| format!("Failed to get index data for `{crate_name}`"))?;
 
@@ -0,0 +1,695 @@
+use crate::models::{
+    Category, Crate, DependencyKind, Keyword, NewCrate, NewVersion, update_default_version,
+};
+use crate::schema::{crate_owners, crates, dependencies, versions};
+use crate::storage::StorageKey;
+use crate::worker::Environment;
+use crate::worker::jobs::{SyncToGitIndex, SyncToSparseIndex};
+use anyhow::{Context, anyhow};
+use crates_io_index::{Crate as IndexCrate, Dependency as IndexDependency, Repository};
+use crates_io_tarball::{TarballLimits, process_tarball};
+use crates_io_worker::BackgroundJob;
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use hyper::body::Bytes;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// A background job that mirrors crates from an upstream registry with a
+/// sparse index, like `https://index.crates.io/`.
+///
+/// For each crate, all versions that are not available locally yet are
+/// downloaded, checked against the checksum in the upstream index, and
+/// inserted with the metadata from the upstream index and the manifest of the
+/// `.crate` file. The yanked state of previously mirrored versions is
+/// updated, so that running the job again only fetches what has changed.
+///
+/// Dependencies can only point to crates that exist locally, so:
+///
+/// - missing dev-dependencies are left out,
+/// - missing optional dependencies are left out, together with the features
+///   that refer to them,
+/// - versions with other missing dependencies are skipped until the
+///   dependencies are mirrored too.
+///
+/// Crates that were published to this registry are never overwritten.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MirrorCrates {
+    index_url: String,
+    crate_names: Vec<String>,
+}
+
+impl MirrorCrates {
+    pub fn new(index_url: impl Into<String>, crate_names: Vec<String>) -> Self {
+        let index_url = index_url.into();
+        Self {
+            index_url,
+            crate_names,
+        }
+    }
+}
+
+impl BackgroundJob for MirrorCrates {
+    const JOB_NAME: &'static str = "mirror_crates";
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+        let upstream = Upstream::new(&self.index_url).await?;
+        let mut conn = ctx.deadpool.get().await?;
+
+        // All crates are created before any versions are inserted, so that
+        // dependencies between the mirrored crates can be resolved regardless
+        // of their order.
+        let mut mirrored = Vec::with_capacity(self.crate_names.len());
+        for name in &self.crate_names {
+            let Some(entries) = upstream.index_entries(name).await? else {
+                warn!("Crate `{name}` does not exist in the upstream index");
+                continue;
+            };
+
+            if let Some(krate) = find_or_create_crate(&entries, &mut conn).await? {
+                mirrored.push((krate, entries));
+            }
+        }
+
+        let mut num_failed = 0;
+        for (krate, entries) in &mirrored {
+            match mirror_crate(&ctx, &upstream, krate, entries, &mut conn).await {
+                Ok(0) => {}
+                Ok(num_changes) => {
+                    info!("Mirrored {num_changes} changes of `{}`", krate.name);
+                    enqueue_index_sync(&ctx, &krate.name, &conn).await?;
+                }
+                Err(error) => {
+                    warn!("Failed to mirror `{}`: {error:#}", krate.name);
+                    num_failed += 1;
+                }
+            }
+
+            delete_if_empty(krate, &mut conn).await?;
+        }
+
+        if num_failed > 0 {
+            return Err(anyhow!("Failed to mirror {num_failed} crates"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the local crate for the upstream index entries, creating it if
+/// necessary.
+///
+/// Returns `None` if the crate was published to this registry.
+async fn find_or_create_crate(
+    entries: &[IndexCrate],
+    conn: &mut AsyncPgConnection,
+) -> anyhow::Result<Option<Crate>> {
+    let Some(name) = entries.first().map(|entry| entry.name.as_str()) else {
+        return Ok(None);
+    };
+
+    let new_crate = NewCrate {
+        name,
+        description: None,
+        homepage: None,
+        documentation: None,
+        readme: None,
+        repository: None,
+        max_upload_size: None,
+        max_features: None,
+    };
+
+    diesel::insert_into(crates::table)
+        .values(&new_crate)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await?;
+
+    let krate: Crate = Crate::by_name(name).first(conn).await?;
+
+    let has_owners = diesel::select(diesel::dsl::exists(
+        crate_owners::table.filter(crate_owners::crate_id.eq(krate.id)),
+    ))
+    .get_result::<bool>(conn)
+    .await?;
+
+    if has_owners {
+        warn!("Skipping `{name}` since it was published to this registry");
+        return Ok(None);
+    }
+
+    Ok(Some(krate))
+}
+
+/// Deletes a crate again if none of its versions could be mirrored, unless
+/// other mirrored versions already depend on it.
+async fn delete_if_empty(krate: &Crate, conn: &mut AsyncPgConnection) -> QueryResult<()> {
+    use diesel::dsl::{exists, not};
+
+    let has_versions = versions::table.filter(versions::crate_id.eq(krate.id));
+    let has_dependents = dependencies::table.filter(dependencies::crate_id.eq(krate.id));
+
+    let num_deleted = diesel::delete(crates::table)
+        .filter(crates::id.eq(krate.id))
+        .filter(not(exists(has_versions)))
+        .filter(not(exists(has_dependents)))
+        .execute(conn)
+        .await?;
+
+    if num_deleted > 0 {
+        warn!("None of the versions of `{}` could be mirrored", krate.name);
+    }
+
+    Ok(())
+}
+
+/// Mirrors the missing versions of a crate and updates the yanked state of
+/// the existing ones.
+///
+/// Returns the number of inserted and updated versions.
+async fn mirror_crate(
+    ctx: &Environment,
+    upstream: &Upstream,
+    krate: &Crate,
+    entries: &[IndexCrate],
+    conn: &mut AsyncPgConnection,
+) -> anyhow::Result<usize> {
+    let existing: HashMap<String, (i32, bool)> = versions::table
+        .filter(versions::crate_id.eq(krate.id))
+        .select((versions::num, (versions::id, versions::yanked)))
+        .load(conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut num_changes = 0;
+    let mut latest_metadata = None;
+    for entry in entries {
+        let yanked = entry.yanked.unwrap_or_default();
+
+        if let Some(&(version_id, was_yanked)) = existing.get(&entry.vers) {
+            if yanked != was_yanked {
+                diesel::update(versions::table)
+                    .filter(versions::id.eq(version_id))
+                    .set(versions::yanked.eq(yanked))
+                    .execute(conn)
+                    .await?;
+
+                num_changes += 1;
+            }
+            continue;
+        }
+
+        let metadata = mirror_version(ctx, upstream, krate, entry, conn)
+            .await
+            .with_context(|| format!("Failed to mirror version {}", entry.vers))?;
+
+        if let Some(metadata) = metadata {
+            latest_metadata = Some(metadata);
+            num_changes += 1;
+        }
+    }
+
+    if num_changes == 0 {
+        return Ok(0);
+    }
+
+    // The upstream index lists the versions in publishing order, so the
+    // crate metadata is taken from the most recently published version.
+    if let Some(metadata) = latest_metadata {
+        metadata.update_crate(krate, conn).await?;
+    }
+
+    update_default_version(krate.id, conn).await?;
+
+    Ok(num_changes)
+}
+
+/// Downloads and inserts a single version.
+///
+/// Returns `None` if the version was skipped because of missing
+/// dependencies.
+async fn mirror_version(
+    ctx: &Environment,
+    upstream: &Upstream,
+    krate: &Crate,
+    entry: &IndexCrate,
+    conn: &mut AsyncPgConnection,
+) -> anyhow::Result<Option<CrateMetadata>> {
+    let Some(deps) = resolve_dependencies(krate, entry, conn).await? else {
+        return Ok(None);
+    };
+
+    let mut features = entry.features.clone();
+    features.extend(entry.features2.clone().unwrap_or_default());
+    remove_missing_optional_dependencies(&mut features, entry, &deps);
+
+    let tarball_bytes = upstream.download(entry).await?;
+
+    let tar_sha256 = Sha256::digest(&tarball_bytes);
+    if hex::encode(tar_sha256) != entry.cksum {
+        return Err(anyhow!("Checksum mismatch of the downloaded `.crate` file"));
+    }
+
+    let pkg_name = format!("{}-{}", krate.name, entry.vers);
+    let limits = TarballLimits {
+        unpack_size: ctx.config.publish_limits.unpack_size,
+        entries: ctx.config.publish_limits.tarball_entries,
+    };
+    let tarball_info = process_tarball(&pkg_name, &*tarball_bytes, limits).await?;
+
+    // `unwrap()` is safe here since `process_tarball()` validates that the
+    // manifest has a `package` section.
+    let package = tarball_info.manifest.package.unwrap();
+
+    let description = package.description.and_then(|it| it.as_local());
+    let license_file = package.license_file.and_then(|it| it.as_local());
+    let license = package
+        .license
+        .and_then(|it| it.as_local())
+        .or_else(|| license_file.map(|_| String::from("non-standard")));
+    let homepage = package.homepage.and_then(|it| it.as_local());
+    let documentation = package.documentation.and_then(|it| it.as_local());
+    let repository = package.repository.and_then(|it| it.as_local());
+    let edition = package.edition.and_then(|it| it.as_local());
+    let keywords = package
+        .keywords
+        .and_then(|it| it.as_local())
+        .unwrap_or_default();
+    let categories = package
+        .categories
+        .and_then(|it| it.as_local())
+        .unwrap_or_default();
+
+    let lib_name = tarball_info.manifest.lib.as_ref().map(|lib| {
+        lib.name
+            .clone()
+            .unwrap_or_else(|| package.name.replace('-', "_"))
+    });
+
+    let bin_names = tarball_info
+        .manifest
+        .bin
+        .iter()
+        .filter_map(|bin| bin.name.as_deref())
+        .collect::<Vec<_>>();
+
+    let key = StorageKey::for_crate_file(&krate.name, &entry.vers);
+    ctx.storage
+        .upload(&key, tarball_bytes.clone().into())
+        .await
+        .context("Failed to upload the `.crate` file")?;
+
+    let keyword_refs = keywords.iter().map(String::as_str).collect::<Vec<_>>();
+    let category_refs = categories.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let new_version = NewVersion::builder(krate.id, &entry.vers)
+        .maybe_created_at(entry.pubtime.as_ref())
+        .yanked(entry.yanked.unwrap_or_default())
+        .features(serde_json::to_value(&features)?)
+        .maybe_license(license.as_deref())
+        .size(tarball_bytes.len() as i32)
+        .tar_sha256(tar_sha256.as_slice())
+        .maybe_links(entry.links.as_deref())
+        .maybe_rust_version(entry.rust_version.as_deref())
+        .has_lib(tarball_info.manifest.lib.is_some())
+        .maybe_lib_name(lib_name.as_deref())
+        .bin_names(bin_names.as_slice())
+        .maybe_edition(edition.as_ref().map(|edition| edition.as_str()))
+        .maybe_description(description.as_deref())
+        .maybe_homepage(homepage.as_deref())
+        .maybe_documentation(documentation.as_deref())
+        .maybe_repository(repository.as_deref())
+        .categories(&category_refs)
+        .keywords(&keyword_refs)
+        .build();
+
+    conn.transaction(async |conn| {
+        let version = new_version.save(conn).await?;
+
+        let new_dependencies = deps
+            .iter()
+            .map(|(dep, crate_id)| {
+                let kind = dep.kind.map(DependencyKind::from);
+                let explicit_name = dep.package.as_ref().map(|_| &dep.name);
+
+                (
+                    dependencies::version_id.eq(version.id),
+                    dependencies::crate_id.eq(crate_id),
+                    dependencies::req.eq(&dep.req),
+                    dependencies::kind.eq(kind.unwrap_or(DependencyKind::Normal)),
+                    dependencies::optional.eq(dep.optional),
+                    dependencies::default_features.eq(dep.default_features),
+                    dependencies::features.eq(&dep.features),
+                    dependencies::target.eq(dep.target.as_deref()),
+                    dependencies::explicit_name.eq(explicit_name),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(dependencies::table)
+            .values(&new_dependencies)
+            .execute(conn)
+            .await?;
+
+        Ok::<_, anyhow::Error>(())
+    })
+    .await?;
+
+    Ok(Some(CrateMetadata {
+        description,
+        homepage,
+        documentation,
+        repository,
+        keywords,
+        categories,
+    }))
+}
+
+/// Looks up the local crates for the dependencies of an index entry.
+///
+/// Returns `None` if a required dependency is not available locally.
+async fn resolve_dependencies<'a>(
+    krate: &Crate,
+    entry: &'a IndexCrate,
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<Option<Vec<(&'a IndexDependency, i32)>>> {
+    let names = entry.deps.iter().map(dependency_crate_name);
+    let crate_ids: HashMap<String, i32> = crates::table
+        .filter(crates::name.eq_any(names))
+        .select((crates::name, crates::id))
+        .load(conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut deps = Vec::with_capacity(entry.deps.len());
+    for dep in &entry.deps {
+        let name = dependency_crate_name(dep);
+        if let Some(&crate_id) = crate_ids.get(name) {
+            deps.push((dep, crate_id));
+        } else if !dep.optional && dep.kind != Some(crates_io_index::DependencyKind::Dev) {
+            warn!(
+                "Skipping {}@{} since its dependency `{name}` has not been mirrored",
+                krate.name, entry.vers
+            );
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(deps))
+}
+
+/// Returns the name of the crate that a dependency refers to, which differs
+/// from the dependency name for renamed dependencies.
+fn dependency_crate_name(dep: &IndexDependency) -> &str {
+    dep.package.as_deref().unwrap_or(&dep.name)
+}
+
+/// Removes the references to optional dependencies that were left out, so
+/// that the features of the mirrored version stay consistent.
+fn remove_missing_optional_dependencies(
+    features: &mut BTreeMap<String, Vec<String>>,
+    entry: &IndexCrate,
+    deps: &[(&IndexDependency, i32)],
+) {
+    let resolved = deps
+        .iter()
+        .map(|(dep, _)| dep.name.as_str())
+        .collect::<HashSet<_>>();
+
+    let missing = entry
+        .deps
+        .iter()
+        .filter(|dep| dep.optional && !resolved.contains(dep.name.as_str()))
+        .map(|dep| dep.name.as_str())
+        .collect::<HashSet<_>>();
+
+    if missing.is_empty() {
+        return;
+    }
+
+    let refers_to_missing = |value: &str| {
+        let value = value.strip_prefix("dep:").unwrap_or(value);
+        let dep_name = value.split('/').next().unwrap_or(value);
+        let dep_name = dep_name.strip_suffix('?').unwrap_or(dep_name);
+        missing.contains(dep_name)
+    };
+
+    for values in features.values_mut() {
+        values.retain(|value| !refers_to_missing(value));
+    }
+}
+
+/// The crate metadata from the manifest of a mirrored version.
+struct CrateMetadata {
+    description: Option<String>,
+    homepage: Option<String>,
+    documentation: Option<String>,
+    repository: Option<String>,
+    keywords: Vec<String>,
+    categories: Vec<String>,
+}
+
+impl CrateMetadata {
+    async fn update_crate(&self, krate: &Crate, conn: &mut AsyncPgConnection) -> QueryResult<()> {
+        let new_crate = NewCrate {
+            name: &krate.name,
+            description: self.description.as_deref(),
+            homepage: self.homepage.as_deref(),
+            documentation: self.documentation.as_deref(),
+            readme: None,
+            repository: self.repository.as_deref(),
+            max_upload_size: None,
+            max_features: None,
+        };
+        new_crate.update(conn).await?;
+
+        let keywords = self.keywords.iter().map(String::as_str).collect::<Vec<_>>();
+        Keyword::update_crate(conn, krate.id, &keywords).await?;
+
+        // Categories that don't exist locally are ignored.
+        let categories = self
+            .categories
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        Category::update_crate(conn, krate.id, &categories).await?;
+
+        Ok(())
+    }
+}
+
+async fn enqueue_index_sync(
+    ctx: &Environment,
+    crate_name: &str,
+    conn: &AsyncPgConnection,
+) -> anyhow::Result<()> {
+    if ctx.config.sync_git_index {
+        SyncToGitIndex::new(crate_name).enqueue(conn).await?;
+    }
+
+    SyncToSparseIndex::new(crate_name).enqueue(conn).await?;
+
+    Ok(())
+}
+
+/// The `config.json` file of a sparse index.
+#[derive(Deserialize)]
+struct IndexConfig {
+    dl: String,
+}
+
+/// Client for an upstream registry with a sparse index.
+///
+/// See <https://doc.rust-lang.org/cargo/reference/registry-index.html#sparse-protocol>.
+struct Upstream {
+    client: reqwest::Client,
+    index_url: String,
+    dl: String,
+}
+
+impl Upstream {
+    async fn new(index_url: &str) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(crates_io_version::user_agent())
+            .build()?;
+
+        let index_url = index_url.trim_end_matches('/').to_string();
+
+        let config_url = format!("{index_url}/config.json");
+        let config: IndexConfig = client
+            .get(&config_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("Failed to load {config_url}"))?;
+
+        let dl = config.dl;
+        Ok(Self {
+            client,
+            index_url,
+            dl,
+        })
+    }
+
+    /// Returns the entries of the index file of a crate, or `None` if the
+    /// crate does not exist upstream.
+    async fn index_entries(&self, name: &str) -> anyhow::Result<Option<Vec<IndexCrate>>> {
+        let path = Repository::relative_index_file_for_url(name);
+        let url = format!("{}/{path}", self.index_url);
+
+        let response = self.client.get(&url).send().await?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::GONE | StatusCode::FORBIDDEN
+        ) {
+            return Ok(None);
+        }
+
+        let content = response.error_for_status()?.text().await?;
+
+        let entries = content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(error) => {
+                    warn!("Skipping unsupported index entry in {url}: {error}");
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok((!entries.is_empty()).then_some(entries))
+    }
+
+    /// Downloads the `.crate` file of a version.
+    async fn download(&self, entry: &IndexCrate) -> anyhow::Result<Bytes> {
+        let url = download_url(&self.dl, entry);
+
+        let bytes = self
+            .client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok(bytes)
+    }
+}
+
+/// Builds the download URL of a version from the `dl` template of the index
+/// configuration.
+fn download_url(dl: &str, entry: &IndexCrate) -> String {
+    const MARKERS: [&str; 5] = [
+        "{crate}",
+        "{version}",
+        "{prefix}",
+        "{lowerprefix}",
+        "{sha256-checksum}",
+    ];
+
+    if !MARKERS.iter().any(|marker| dl.contains(marker)) {
+        return format!("{dl}/{}/{}/download", entry.name, entry.vers);
+    }
+
+    let prefix = index_prefix(&entry.name);
+    dl.replace("{crate}", &entry.name)
+        .replace("{version}", &entry.vers)
+        .replace("{prefix}", &prefix)
+        .replace("{lowerprefix}", &prefix.to_lowercase())
+        .replace("{sha256-checksum}", &entry.cksum)
+}
+
+/// Returns the directory of the index file of a crate, without changing the
+/// case of the crate name.
+fn index_prefix(name: &str) -> String {
+    match name.len() {
+        1 => "1".to_string(),
+        2 => "2".to_string(),
+        3 => format!("3/{}", &name[..1]),
+        _ => format!("{}/{}", &name[..2], &name[2..4]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, vers: &str) -> IndexCrate {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "vers": vers,
+            "deps": [],
+            "cksum": "abc",
+            "features": {},
+            "yanked": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_download_url() {
+        let entry = entry("Serde", "1.0.0");
+
+        let dl = "https://crates.io/api/v1/crates";
+        assert_eq!(
+            download_url(dl, &entry),
+            "https://crates.io/api/v1/crates/Serde/1.0.0/download"
+        );
+
+        let dl = "https://static.example.com/{lowerprefix}/{crate}/{crate}-{version}.crate";
+        assert_eq!(
+            download_url(dl, &entry),
+            "https://static.example.com/se/rd/Serde/Serde-1.0.0.crate"
+        );
+
+        let dl = "https://static.example.com/{prefix}/{sha256-checksum}";
+        assert_eq!(
+            download_url(dl, &entry),
+            "https://static.example.com/Se/rd/abc"
+        );
+    }
+
+    #[test]
+    fn test_remove_missing_optional_dependencies() {
+        let mut entry = entry("foo", "1.0.0");
+        entry.deps = serde_json::from_value(serde_json::json!([
+            { "name": "serde", "req": "^1", "features": [], "optional": true, "default_features": true, "target": null, "kind": "normal" },
+            { "name": "json", "req": "^1", "features": [], "optional": true, "default_features": true, "target": null, "kind": "normal", "package": "serde_json" },
+        ]))
+        .unwrap();
+
+        let mut features = BTreeMap::from([
+            ("default".to_string(), vec!["std".to_string()]),
+            ("std".to_string(), vec!["serde?/std".to_string()]),
+            (
+                "json".to_string(),
+                vec!["dep:json".to_string(), "serde".to_string()],
+            ),
+        ]);
+
+        let deps = [(&entry.deps[0], 1)];
+        remove_missing_optional_dependencies(&mut features, &entry, &deps);
+
+        assert_eq!(
+            features,
+            BTreeMap::from([
+                ("default".to_string(), vec!["std".to_string()]),
+                ("std".to_string(), vec!["serde?/std".to_string()]),
+                ("json".to_string(), vec!["serde".to_string()]),
+            ])
+        );
+    }
+}
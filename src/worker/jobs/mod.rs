@@ -1,29 +1,37 @@
 mod analyze_crate_file;
 mod archive_version_downloads;
 mod build_crate_zip;
+mod check_compatibility;
 mod daily_db_maintenance;
 mod delete_crate;
 mod docs_rs_queue_rebuild;
 mod downloads;
 pub mod dump_db;
+mod dump_db_delta;
+mod expire_staged_versions;
 mod expiry_notification;
 mod generate_og_image;
+mod import_advisories;
 mod index;
 mod index_version_downloads_archive;
 mod invalidate_cdns;
+mod mirror_crates;
 mod process_cloudfront_invalidation_queue;
 mod readmes;
 pub mod rss;
+mod send_publish_notification_digests;
 mod send_publish_notifications;
 mod sync_admins;
 pub mod trustpub;
 mod typosquat;
 mod update_default_version;
 mod update_user_from_github;
+pub mod webhooks;
 
 pub use self::analyze_crate_file::AnalyzeCrateFile;
 pub use self::archive_version_downloads::ArchiveVersionDownloads;
 pub use self::build_crate_zip::BuildCrateZip;
+pub use self::check_compatibility::CheckCompatibility;
 pub use self::daily_db_maintenance::DailyDbMaintenance;
 pub use self::delete_crate::DeleteCrateFromStorage;
 pub use self::docs_rs_queue_rebuild::DocsRsQueueRebuild;
@@ -31,18 +39,24 @@ pub use self::downloads::{
     CleanProcessedLogFiles, ProcessCdnLog, ProcessCdnLogQueue, UpdateDownloads,
 };
 pub use self::dump_db::DumpDb;
+pub use self::dump_db_delta::DumpDbDelta;
+pub use self::expire_staged_versions::ExpireStagedVersions;
 pub use self::expiry_notification::SendTokenExpiryNotifications;
 pub use self::generate_og_image::GenerateOgImage;
+pub use self::import_advisories::ImportAdvisories;
 pub use self::index::{
     ArchiveIndexBranch, BulkSyncToGitIndex, NormalizeIndex, SquashIndex, SyncToGitIndex,
     SyncToSparseIndex,
 };
 pub use self::index_version_downloads_archive::IndexVersionDownloadsArchive;
 pub use self::invalidate_cdns::InvalidateCdns;
+pub use self::mirror_crates::MirrorCrates;
 pub use self::process_cloudfront_invalidation_queue::ProcessCloudfrontInvalidationQueue;
 pub use self::readmes::RenderAndUploadReadme;
+pub use self::send_publish_notification_digests::SendPublishNotificationDigests;
 pub use self::send_publish_notifications::SendPublishNotificationsJob;
 pub use self::sync_admins::SyncAdmins;
 pub use self::typosquat::CheckTyposquat;
 pub use self::update_default_version::UpdateDefaultVersion;
 pub use self::update_user_from_github::UpdateUserFromGithub;
+pub use self::webhooks::{DeliverWebhook, DispatchWebhookEvent};
@@ -4,7 +4,7 @@ use crate::models::Version;
 use crate::storage::StorageKey;
 use crate::tasks::spawn_blocking;
 use crate::worker::Environment;
-use crates_io_markdown::text_to_html;
+use crates_io_markdown::{RenderOptions, render_readme};
 use crates_io_worker::BackgroundJob;
 use diesel::result::DatabaseErrorKind;
 use diesel::result::Error::DatabaseError;
@@ -54,18 +54,23 @@ impl BackgroundJob for RenderAndUploadReadme {
 
         info!(version_id = ?self.version_id, "Rendering README");
 
+        let options = RenderOptions {
+            syntax_highlighting: env.config.features.readme_syntax_highlighting_enabled,
+        };
+
         let job = self.clone();
         let rendered = spawn_blocking(move || {
-            text_to_html(
+            render_readme(
                 &job.text,
                 &job.readme_path,
                 job.base_url.as_deref(),
                 job.pkg_path_in_vcs.as_ref(),
+                options,
             )
         })
         .await?;
 
-        if rendered.is_empty() {
+        if rendered.html.is_empty() {
             return Ok(());
         }
 
@@ -108,7 +113,11 @@ impl BackgroundJob for RenderAndUploadReadme {
             tracing::Span::current().record("krate.name", tracing::field::display(&crate_name));
 
             let key = StorageKey::for_readme(&crate_name, &vers);
-            env.storage.upload(&key, rendered.into()).await?;
+            env.storage.upload(&key, rendered.html.into()).await?;
+
+            let toc = serde_json::to_vec(&rendered.table_of_contents)?;
+            let key = StorageKey::for_readme_toc(&crate_name, &vers);
+            env.storage.upload(&key, toc.into()).await?;
 
             Ok(())
         })
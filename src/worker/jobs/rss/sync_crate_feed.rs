@@ -2,7 +2,7 @@ use crate::schema::{crates, versions};
 use crate::storage::StorageKey;
 use crate::worker::Environment;
 use chrono::{Duration, Utc};
-use crates_io_database::models::CloudFrontDistribution;
+use crates_io_database::models::{CloudFrontDistribution, CrateReader};
 use crates_io_worker::BackgroundJob;
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
@@ -42,12 +42,28 @@ impl BackgroundJob for SyncCrateFeed {
         let name = &self.name;
         let domain = &ctx.config.domain_name;
 
-        info!("Loading latest {NUM_ITEMS} version updates for `{name}` from the database…");
         let conn = ctx.deadpool.get().await?;
+        let key = StorageKey::CrateFeed { name };
 
-        let version_updates = load_version_updates(name, &conn).await?;
+        // The feed is public, so it is removed instead of updated for crates
+        // with restricted read access.
+        if CrateReader::is_restricted_by_name(&conn, name).await? {
+            info!("Deleting feed of `{name}`, since its read access is restricted…");
+            match ctx.storage.delete(&key).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(error) => return Err(error.into()),
+            }
+
+            let dist = CloudFrontDistribution::Static;
+            if let Err(error) = ctx.invalidate_cdns(&conn, dist, key.path().as_ref()).await {
+                warn!("Failed to invalidate CDN caches: {error}");
+            }
+
+            return Ok(());
+        }
 
-        let key = StorageKey::CrateFeed { name };
+        info!("Loading latest {NUM_ITEMS} version updates for `{name}` from the database…");
+        let version_updates = load_version_updates(name, &conn).await?;
 
         let link = rss::extension::atom::Link {
             href: ctx.storage.location(&key),
@@ -108,6 +124,7 @@ async fn load_version_updates(
     let updates = VersionUpdate::query()
         .filter(crates::name.eq(name))
         .filter(versions::created_at.gt(threshold_dt))
+        .filter(versions::staged_at.is_null())
         .order(versions::created_at.desc())
         .load(&mut conn)
         .await?;
@@ -119,6 +136,7 @@ async fn load_version_updates(
 
     VersionUpdate::query()
         .filter(crates::name.eq(name))
+        .filter(versions::staged_at.is_null())
         .order(versions::created_at.desc())
         .limit(NUM_ITEMS)
         .load(&mut conn)
@@ -2,7 +2,7 @@ use crate::schema::crates;
 use crate::storage::StorageKey;
 use crate::worker::Environment;
 use chrono::{Duration, Utc};
-use crates_io_database::models::CloudFrontDistribution;
+use crates_io_database::models::{CloudFrontDistribution, CrateReader};
 use crates_io_worker::BackgroundJob;
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
@@ -86,10 +86,13 @@ impl BackgroundJob for SyncCratesFeed {
 /// than [`ALWAYS_INCLUDE_AGE`]. If there are less than [`NUM_ITEMS`] crates
 /// then the list will be padded with older crates until [`NUM_ITEMS`] are
 /// returned.
+///
+/// Crates with restricted read access are left out, since the feed is public.
 async fn load_new_crates(mut conn: &AsyncPgConnection) -> QueryResult<Vec<NewCrate>> {
     let threshold_dt = chrono::Utc::now().naive_utc() - ALWAYS_INCLUDE_AGE;
 
     let new_crates = NewCrate::query()
+        .filter(crates::id.ne_all(CrateReader::restricted_crate_ids()))
         .filter(crates::created_at.gt(threshold_dt))
         .order(crates::created_at.desc())
         .load(&mut conn)
@@ -101,6 +104,7 @@ async fn load_new_crates(mut conn: &AsyncPgConnection) -> QueryResult<Vec<NewCra
     }
 
     NewCrate::query()
+        .filter(crates::id.ne_all(CrateReader::restricted_crate_ids()))
         .order(crates::created_at.desc())
         .limit(NUM_ITEMS)
         .load(&mut conn)
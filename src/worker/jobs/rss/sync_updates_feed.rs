@@ -2,7 +2,7 @@ use crate::schema::{crates, versions};
 use crate::storage::StorageKey;
 use crate::worker::Environment;
 use chrono::{Duration, Utc};
-use crates_io_database::models::CloudFrontDistribution;
+use crates_io_database::models::{CloudFrontDistribution, CrateReader};
 use crates_io_worker::BackgroundJob;
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
@@ -86,11 +86,16 @@ impl BackgroundJob for SyncUpdatesFeed {
 /// than [`ALWAYS_INCLUDE_AGE`]. If there are less than [`NUM_ITEMS`] versions
 /// then the list will be padded with older versions until [`NUM_ITEMS`] are
 /// returned.
+///
+/// Versions of crates with restricted read access are left out, since the
+/// feed is public.
 async fn load_version_updates(mut conn: &AsyncPgConnection) -> QueryResult<Vec<VersionUpdate>> {
     let threshold_dt = chrono::Utc::now().naive_utc() - ALWAYS_INCLUDE_AGE;
 
     let updates = VersionUpdate::query()
+        .filter(versions::crate_id.ne_all(CrateReader::restricted_crate_ids()))
         .filter(versions::created_at.gt(threshold_dt))
+        .filter(versions::staged_at.is_null())
         .order(versions::created_at.desc())
         .load(&mut conn)
         .await?;
@@ -101,6 +106,8 @@ async fn load_version_updates(mut conn: &AsyncPgConnection) -> QueryResult<Vec<V
     }
 
     VersionUpdate::query()
+        .filter(versions::crate_id.ne_all(CrateReader::restricted_crate_ids()))
+        .filter(versions::staged_at.is_null())
         .order(versions::created_at.desc())
         .limit(NUM_ITEMS)
         .load(&mut conn)
@@ -0,0 +1,192 @@
+use super::send_publish_notifications::PublishDetails;
+use crate::email::{EmailMessage, Unsubscribe};
+use crate::models::{NotificationCategory, NotificationSettings, PublishDelivery, User};
+use crate::schema::{notification_settings, pending_publish_notifications};
+use crate::worker::Environment;
+use anyhow::anyhow;
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use crates_io_worker::BackgroundJob;
+use diesel::dsl::min;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use minijinja::context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+
+/// Background job that sends the publish notifications that were queued by
+/// [`SendPublishNotificationsJob`](super::SendPublishNotificationsJob) for
+/// owners with a daily or weekly digest.
+///
+/// A digest is sent once the oldest queued notification of a user is a day
+/// or a week old. Notifications of users that have switched back to
+/// immediate delivery are sent on the next run.
+#[derive(Default, Serialize, Deserialize, Debug)]
+pub struct SendPublishNotificationDigests;
+
+impl BackgroundJob for SendPublishNotificationDigests {
+    const JOB_NAME: &'static str = "send_publish_notification_digests";
+    const DEDUPLICATED: bool = true;
+
+    type Context = Arc<Environment>;
+
+    #[instrument(skip(ctx), err)]
+    async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+        let conn = ctx.deadpool.get().await?;
+
+        let user_ids = find_due_digests(&conn, Utc::now()).await?;
+        let num_digests = user_ids.len();
+        if num_digests == 0 {
+            info!("No publish notification digests are due");
+            return Ok(());
+        }
+
+        info!("Sending {num_digests} publish notification digests…");
+
+        let mut num_sent = 0;
+        for user_id in user_ids {
+            match send_digest(&ctx, &conn, user_id).await {
+                Ok(()) => num_sent += 1,
+                Err(error) => {
+                    warn!("Failed to send publish notification digest to user {user_id}: {error}")
+                }
+            }
+        }
+
+        // Notifications that failed to send stay queued for the next run,
+        // but if *none* of the digests could be sent, the job is retried.
+        if num_sent == 0 {
+            return Err(anyhow!("Failed to send publish notification digests"));
+        }
+
+        info!("Sent {num_sent} of {num_digests} publish notification digests");
+
+        Ok(())
+    }
+}
+
+/// Returns the IDs of the users whose digest is due at the given time.
+async fn find_due_digests(
+    mut conn: &AsyncPgConnection,
+    now: DateTime<Utc>,
+) -> QueryResult<Vec<i32>> {
+    let oldest_notifications = pending_publish_notifications::table
+        .group_by(pending_publish_notifications::user_id)
+        .select((
+            pending_publish_notifications::user_id,
+            min(pending_publish_notifications::created_at),
+        ))
+        .order(pending_publish_notifications::user_id)
+        .load::<(i32, Option<DateTime<Utc>>)>(&mut conn)
+        .await?;
+
+    let user_ids = oldest_notifications
+        .iter()
+        .map(|(user_id, _)| *user_id)
+        .collect::<Vec<_>>();
+
+    let deliveries = notification_settings::table
+        .filter(notification_settings::user_id.eq_any(&user_ids))
+        .select((
+            notification_settings::user_id,
+            notification_settings::publish_delivery,
+        ))
+        .load::<(i32, String)>(&mut conn)
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let user_ids = oldest_notifications
+        .into_iter()
+        .filter(|(user_id, oldest)| {
+            let delivery = deliveries
+                .get(user_id)
+                .and_then(|delivery| delivery.parse().ok())
+                .unwrap_or_default();
+
+            let interval = match delivery {
+                PublishDelivery::Immediate => TimeDelta::zero(),
+                PublishDelivery::Daily => TimeDelta::days(1),
+                PublishDelivery::Weekly => TimeDelta::weeks(1),
+            };
+
+            oldest.is_some_and(|oldest| oldest + interval <= now)
+        })
+        .map(|(user_id, _)| user_id)
+        .collect();
+
+    Ok(user_ids)
+}
+
+/// Sends all queued publish notifications of the user in a single email,
+/// and removes them from the queue.
+async fn send_digest(
+    ctx: &Environment,
+    mut conn: &AsyncPgConnection,
+    user_id: i32,
+) -> anyhow::Result<()> {
+    let version_ids = pending_publish_notifications::table
+        .filter(pending_publish_notifications::user_id.eq(user_id))
+        .select(pending_publish_notifications::version_id)
+        .load::<i32>(&mut conn)
+        .await?;
+
+    let user = User::find(conn, user_id).await?;
+    let delivery = NotificationSettings::publish_delivery(conn, user_id).await?;
+
+    if let Some(email_address) = user.verified_email(conn).await? {
+        let domain = &ctx.config.domain_name;
+
+        let notifications = PublishDetails::for_versions(&version_ids, conn)
+            .await?
+            .into_iter()
+            .map(|details| {
+                context! {
+                    krate => details.krate,
+                    version => details.version,
+                    publish_time => details.publish_time.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    publisher_info => details.publisher_info(&user.gh_login, domain),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if !notifications.is_empty() {
+            let unsubscribe_token = NotificationSettings::unsubscribe_token(conn, user_id).await?;
+            let unsubscribe =
+                Unsubscribe::new(domain, &unsubscribe_token, NotificationCategory::Publish);
+
+            // Users that switched back to immediate delivery get the
+            // remaining notifications as a daily digest.
+            let delivery = match delivery {
+                PublishDelivery::Weekly => PublishDelivery::Weekly,
+                _ => PublishDelivery::Daily,
+            };
+
+            let email = EmailMessage::from_template_with_unsubscribe(
+                "publish_notification_digest",
+                context! {
+                    recipient => user.gh_login,
+                    notifications => notifications,
+                    delivery => delivery.as_str(),
+                    domain => domain,
+                },
+                unsubscribe,
+            )?;
+
+            ctx.emails.send(&email_address, email).await?;
+        }
+    } else {
+        info!(
+            "User {user_id} has no verified email address. Skipping publish notification digest."
+        );
+    }
+
+    diesel::delete(pending_publish_notifications::table)
+        .filter(pending_publish_notifications::user_id.eq(user_id))
+        .filter(pending_publish_notifications::version_id.eq_any(&version_ids))
+        .execute(&mut conn)
+        .await?;
+
+    Ok(())
+}
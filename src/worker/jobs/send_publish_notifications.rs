@@ -1,9 +1,16 @@
-use crate::email::EmailMessage;
-use crate::models::{OwnerKind, TrustpubData};
-use crate::schema::{crate_owners, crates, emails, users, versions};
+use crate::email::{EmailMessage, Unsubscribe};
+use crate::models::{
+    NotificationCategory, NotificationSettings, OwnerKind, PublishDelivery, TrustpubData,
+};
+use crate::schema::{
+    crate_owners, crates, emails, notification_preferences, notification_settings,
+    pending_publish_notifications, users, version_compatibility_reports, versions,
+};
 use crate::worker::Environment;
 use anyhow::anyhow;
 use chrono::{DateTime, SecondsFormat, Utc};
+use crates_io_compat_check::Report;
+use crates_io_trustpub::provider::provider_by_issuer;
 use crates_io_worker::BackgroundJob;
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
@@ -49,19 +56,78 @@ impl BackgroundJob for SendPublishNotificationsJob {
             .publish_time
             .to_rfc3339_opts(SecondsFormat::Secs, true);
 
-        // Find names and email addresses of all crate owners
+        // Find names, email addresses and delivery settings of all crate
+        // owners that have not opted out of publish notifications for the
+        // crate
         let recipients = crate_owners::table
             .filter(crate_owners::deleted.eq(false))
             .filter(crate_owners::owner_kind.eq(OwnerKind::User))
             .filter(crate_owners::crate_id.eq(publish_details.crate_id))
             .inner_join(users::table)
-            .filter(users::publish_notifications.eq(true))
+            .left_join(
+                notification_preferences::table.on(notification_preferences::user_id
+                    .eq(users::id)
+                    .and(
+                        notification_preferences::category
+                            .eq(NotificationCategory::Publish.as_str()),
+                    )
+                    .and(notification_preferences::crate_id.eq(crate_owners::crate_id.nullable()))),
+            )
+            .filter(
+                notification_preferences::enabled.nullable().eq(true).or(
+                    notification_preferences::enabled
+                        .nullable()
+                        .is_null()
+                        .and(users::publish_notifications.eq(true)),
+                ),
+            )
+            .left_join(
+                notification_settings::table.on(notification_settings::user_id.eq(users::id)),
+            )
             .inner_join(emails::table.on(users::id.eq(emails::user_id)))
             .filter(emails::verified.eq(true))
-            .select((users::gh_login, emails::email))
-            .load::<(String, String)>(&mut conn)
+            .select((
+                users::id,
+                users::gh_login,
+                emails::email,
+                notification_settings::publish_delivery.nullable(),
+            ))
+            .load::<(i32, String, String, Option<String>)>(&mut conn)
             .await?;
 
+        // Owners that receive digests get the notification with their next
+        // digest email, see `SendPublishNotificationDigests`.
+        let (digest_recipients, recipients): (Vec<_>, Vec<_>) =
+            recipients.into_iter().partition(|(_, _, _, delivery)| {
+                let delivery = delivery
+                    .as_deref()
+                    .and_then(|delivery| delivery.parse().ok());
+                delivery.unwrap_or(PublishDelivery::Immediate) != PublishDelivery::Immediate
+            });
+
+        if !digest_recipients.is_empty() {
+            let pending_notifications = digest_recipients
+                .iter()
+                .map(|(user_id, ..)| {
+                    (
+                        pending_publish_notifications::user_id.eq(user_id),
+                        pending_publish_notifications::version_id.eq(version_id),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let num_queued = diesel::insert_into(pending_publish_notifications::table)
+                .values(&pending_notifications)
+                .on_conflict_do_nothing()
+                .execute(&mut conn)
+                .await?;
+
+            info!(
+                "Queued {num_queued} publish notifications for {}@{} for digests",
+                publish_details.krate, publish_details.version
+            );
+        }
+
         let num_recipients = recipients.len();
         if num_recipients == 0 {
             info!(
@@ -72,44 +138,31 @@ impl BackgroundJob for SendPublishNotificationsJob {
             return Ok(());
         }
 
+        // Owners are warned about changes that are likely to break users of
+        // the crate, if the compatibility check found any.
+        let (previous_version, compatibility_warnings) = publish_details
+            .compatibility_report
+            .clone()
+            .and_then(|report| serde_json::from_value::<Report>(report).ok())
+            .map(|report| (Some(report.previous_version), report.warnings))
+            .unwrap_or_default();
+
         let mut results = Vec::with_capacity(recipients.len());
 
-        for (ref recipient, email_address) in recipients {
+        for (user_id, ref recipient, email_address, _) in recipients {
             let krate = &publish_details.krate;
             let version = &publish_details.version;
 
-            let publisher_info = match (&publish_details.publisher, &publish_details.trustpub_data)
-            {
-                (Some(publisher), _) if publisher == recipient => &format!(
-                    " by your account (https://{domain}/users/{publisher})",
-                    domain = ctx.config.domain_name
-                ),
-                (Some(publisher), _) => &format!(
-                    " by {publisher} (https://{domain}/users/{publisher})",
-                    domain = ctx.config.domain_name
-                ),
-                (
-                    _,
-                    Some(TrustpubData::GitHub {
-                        repository, run_id, ..
-                    }),
-                ) => &format!(
-                    " by GitHub Actions (https://github.com/{repository}/actions/runs/{run_id})",
-                ),
-                (
-                    _,
-                    Some(TrustpubData::GitLab {
-                        project_path,
-                        job_id,
-                        ..
-                    }),
-                ) => {
-                    &format!(" by GitLab CI/CD (https://gitlab.com/{project_path}/-/jobs/{job_id})")
-                }
-                _ => "",
-            };
+            let publisher_info = publish_details.publisher_info(recipient, &ctx.config.domain_name);
+
+            let unsubscribe_token = NotificationSettings::unsubscribe_token(&conn, user_id).await?;
+            let unsubscribe = Unsubscribe::new(
+                &ctx.config.domain_name,
+                &unsubscribe_token,
+                NotificationCategory::Publish,
+            );
 
-            let email = EmailMessage::from_template(
+            let email = EmailMessage::from_template_with_unsubscribe(
                 "publish_notification",
                 context! {
                     recipient => recipient,
@@ -117,8 +170,11 @@ impl BackgroundJob for SendPublishNotificationsJob {
                     version => version,
                     publish_time => publish_time,
                     publisher_info => publisher_info,
+                    previous_version => previous_version,
+                    compatibility_warnings => compatibility_warnings,
                     domain => ctx.config.domain_name
                 },
+                unsubscribe,
             );
 
             debug!("Sending publish notification for {krate}@{version} to {email_address}…");
@@ -170,25 +226,71 @@ impl BackgroundJob for SendPublishNotificationsJob {
 #[diesel(
     base_query = versions::table
         .inner_join(crates::table)
-        .left_join(users::table),
+        .left_join(users::table)
+        .left_join(version_compatibility_reports::table),
 )]
-struct PublishDetails {
+pub(super) struct PublishDetails {
     #[diesel(select_expression = crates::columns::id)]
     crate_id: i32,
     #[diesel(select_expression = crates::columns::name)]
-    krate: String,
+    pub(super) krate: String,
     #[diesel(select_expression = versions::columns::num)]
-    version: String,
+    pub(super) version: String,
     #[diesel(select_expression = versions::columns::created_at)]
-    publish_time: DateTime<Utc>,
+    pub(super) publish_time: DateTime<Utc>,
     #[diesel(select_expression = users::columns::gh_login.nullable())]
     publisher: Option<String>,
     #[diesel(select_expression = versions::columns::trustpub_data.nullable())]
     trustpub_data: Option<TrustpubData>,
+    #[diesel(select_expression = version_compatibility_reports::columns::report.nullable())]
+    compatibility_report: Option<serde_json::Value>,
 }
 
 impl PublishDetails {
-    async fn for_version(
+    /// Describes who published the version, from the perspective of the
+    /// recipient of the notification, e.g. ` by your account (…)`.
+    pub(super) fn publisher_info(&self, recipient: &str, domain: &str) -> String {
+        match (&self.publisher, &self.trustpub_data) {
+            (Some(publisher), _) if publisher == recipient => {
+                format!(" by your account (https://{domain}/users/{publisher})")
+            }
+            (Some(publisher), _) => format!(" by {publisher} (https://{domain}/users/{publisher})"),
+            (
+                _,
+                Some(TrustpubData::GitHub {
+                    repository, run_id, ..
+                }),
+            ) => format!(
+                " by GitHub Actions (https://github.com/{repository}/actions/runs/{run_id})"
+            ),
+            (
+                _,
+                Some(TrustpubData::GitLab {
+                    project_path,
+                    job_id,
+                    ..
+                }),
+            ) => format!(" by GitLab CI/CD (https://gitlab.com/{project_path}/-/jobs/{job_id})"),
+            (
+                _,
+                Some(TrustpubData::Oidc {
+                    issuer,
+                    repository,
+                    run_id,
+                    ..
+                }),
+            ) => {
+                let provider = provider_by_issuer(issuer)
+                    .map(|provider| provider.display_name())
+                    .unwrap_or(issuer);
+
+                format!(" by {provider} (`{repository}`, job {run_id})")
+            }
+            _ => String::new(),
+        }
+    }
+
+    pub(super) async fn for_version(
         version_id: i32,
         mut conn: &AsyncPgConnection,
     ) -> QueryResult<Option<Self>> {
@@ -198,4 +300,15 @@ impl PublishDetails {
             .await
             .optional()
     }
+
+    pub(super) async fn for_versions(
+        version_ids: &[i32],
+        mut conn: &AsyncPgConnection,
+    ) -> QueryResult<Vec<Self>> {
+        PublishDetails::query()
+            .filter(versions::id.eq_any(version_ids))
+            .order((versions::created_at, versions::id))
+            .load(&mut conn)
+            .await
+    }
 }
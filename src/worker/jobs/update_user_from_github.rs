@@ -27,6 +27,8 @@ impl BackgroundJob for UpdateUserFromGithub {
     const DEDUPLICATED: bool = true;
     // These jobs aren't urgent and shouldn't page anyone if they take a long time.
     const PRIORITY: i16 = -15;
+    // The next batch sync picks the user up again anyway.
+    const MAX_RETRIES: Option<u32> = Some(5);
 
     type Context = Arc<Environment>;
 
@@ -0,0 +1,463 @@
+use crate::models::{NewWebhookDelivery, OwnerKind, Webhook, WebhookDelivery, WebhookEvent};
+use crate::schema::{crate_owners, crates, webhook_deliveries, webhooks};
+use crate::worker::Environment;
+use anyhow::{Context, anyhow};
+use bon::Builder;
+use chrono::{DateTime, Utc};
+use crates_io_worker::BackgroundJob;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use hmac::{Hmac, KeyInit, Mac};
+use http::header;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::lookup_host;
+use tracing::{info, warn};
+use url::{Host, Url};
+
+/// The header that contains the type of the event, e.g. `publish`.
+pub const EVENT_HEADER: &str = "X-Crates-Io-Event";
+/// The header that contains the ID of the delivery, which stays the same
+/// across retries.
+pub const DELIVERY_HEADER: &str = "X-Crates-Io-Delivery";
+/// The header that contains the UNIX timestamp that was signed.
+pub const TIMESTAMP_HEADER: &str = "X-Crates-Io-Timestamp";
+/// The header that contains the HMAC-SHA256 signature of the payload.
+pub const SIGNATURE_HEADER: &str = "X-Crates-Io-Signature";
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The error that is recorded for failed requests. The underlying error is
+/// only logged, since it would reveal details about the network of the
+/// target to the owner of the webhook.
+const REQUEST_FAILED: &str = "Failed to send the request";
+
+/// Background job that creates a delivery of a crate event for every webhook
+/// that is subscribed to it, and enqueues a [`DeliverWebhook`] job for each
+/// of them.
+///
+/// The webhooks of all users that own the crate at the time this job runs are
+/// considered, both the ones for this crate and the ones for all of the
+/// user's crates.
+#[derive(Serialize, Deserialize, Debug, Builder)]
+pub struct DispatchWebhookEvent {
+    crate_id: i32,
+    event: WebhookEvent,
+    #[builder(default = Utc::now())]
+    time: DateTime<Utc>,
+    /// The version that was published, yanked or unyanked.
+    #[builder(into)]
+    version: Option<String>,
+    /// The login of the user that caused the event, if any.
+    #[builder(into)]
+    actor: Option<String>,
+    /// The login of the user or team that was added or removed as an owner.
+    #[builder(into)]
+    owner: Option<String>,
+    /// The provider of the Trusted Publishing configuration that was created
+    /// or deleted, e.g. `github`.
+    #[builder(into)]
+    trustpub_provider: Option<String>,
+    /// The ID of the Trusted Publishing configuration that was created or
+    /// deleted.
+    trustpub_config_id: Option<i32>,
+}
+
+/// The JSON payload that is sent to webhooks.
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    event: WebhookEvent,
+    #[serde(rename = "crate")]
+    krate: &'a str,
+    time: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actor: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trustpub_provider: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trustpub_config_id: Option<i32>,
+}
+
+impl BackgroundJob for DispatchWebhookEvent {
+    const JOB_NAME: &'static str = "dispatch_webhook_event";
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+        let mut conn = ctx.deadpool.get().await?;
+
+        let crate_name = crates::table
+            .find(self.crate_id)
+            .select(crates::name)
+            .first::<String>(&mut conn)
+            .await
+            .optional()?;
+
+        let Some(crate_name) = crate_name else {
+            warn!("Skipping `{}` webhooks: crate was deleted", self.event);
+            return Ok(());
+        };
+
+        let owner_ids = crate_owners::table
+            .filter(crate_owners::crate_id.eq(self.crate_id))
+            .filter(crate_owners::deleted.eq(false))
+            .filter(crate_owners::owner_kind.eq(OwnerKind::User))
+            .select(crate_owners::owner_id);
+
+        let webhook_ids = webhooks::table
+            .filter(webhooks::user_id.eq_any(owner_ids))
+            .filter(
+                webhooks::crate_id
+                    .is_null()
+                    .or(webhooks::crate_id.eq(self.crate_id)),
+            )
+            .filter(webhooks::events.contains(vec![self.event.as_str()]))
+            .select(webhooks::id)
+            .load::<i32>(&mut conn)
+            .await?;
+
+        if webhook_ids.is_empty() {
+            return Ok(());
+        }
+
+        let payload = serde_json::to_value(WebhookPayload {
+            event: self.event,
+            krate: &crate_name,
+            time: self.time,
+            version: self.version.as_deref(),
+            actor: self.actor.as_deref(),
+            owner: self.owner.as_deref(),
+            trustpub_provider: self.trustpub_provider.as_deref(),
+            trustpub_config_id: self.trustpub_config_id,
+        })?;
+
+        let deliveries = webhook_ids
+            .iter()
+            .map(|&webhook_id| NewWebhookDelivery {
+                webhook_id,
+                event: self.event.as_str(),
+                payload: &payload,
+                created_at: self.time,
+            })
+            .collect::<Vec<_>>();
+
+        info!(
+            "Delivering `{}` event of `{crate_name}` to {} webhooks",
+            self.event,
+            deliveries.len()
+        );
+
+        conn.transaction(async |conn| {
+            let delivery_ids = diesel::insert_into(webhook_deliveries::table)
+                .values(&deliveries)
+                .returning(webhook_deliveries::id)
+                .get_results::<i64>(conn)
+                .await?;
+
+            for delivery_id in delivery_ids {
+                DeliverWebhook::new(delivery_id).enqueue(conn).await?;
+            }
+
+            Ok::<_, anyhow::Error>(())
+        })
+        .await
+    }
+}
+
+/// Background job that sends an event to a webhook.
+///
+/// The outcome of every attempt is recorded in the `webhook_deliveries`
+/// table. Failed attempts are retried with the default backoff, i.e. the last
+/// attempt happens roughly four hours after the first one.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliverWebhook {
+    delivery_id: i64,
+}
+
+impl DeliverWebhook {
+    pub fn new(delivery_id: i64) -> Self {
+        Self { delivery_id }
+    }
+}
+
+impl BackgroundJob for DeliverWebhook {
+    const JOB_NAME: &'static str = "deliver_webhook";
+    const MAX_RETRIES: Option<u32> = Some(8);
+
+    type Context = Arc<Environment>;
+
+    async fn run(&self, ctx: Self::Context) -> anyhow::Result<()> {
+        let mut conn = ctx.deadpool.get().await?;
+
+        let delivery = WebhookDelivery::query()
+            .find(self.delivery_id)
+            .first(&mut conn)
+            .await
+            .optional()?;
+
+        // The delivery is deleted together with its webhook.
+        let Some(delivery) = delivery else {
+            return Ok(());
+        };
+
+        if delivery.delivered_at.is_some() {
+            return Ok(());
+        }
+
+        let webhook = Webhook::query()
+            .find(delivery.webhook_id)
+            .first(&mut conn)
+            .await?;
+
+        let secret = ctx
+            .config
+            .token_encryption
+            .decrypt(&webhook.encrypted_secret)
+            .context("Failed to decrypt webhook secret")?;
+
+        let body = serde_json::to_vec(&delivery.payload)?;
+        let timestamp = Utc::now().timestamp();
+        let signature = sign(secret.expose_secret().as_bytes(), timestamp, &body);
+
+        // Redirects are not followed, since their targets would bypass the
+        // address checks below.
+        let mut client = reqwest::Client::builder()
+            .user_agent(crates_io_version::user_agent())
+            .timeout(DELIVERY_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none());
+
+        // The host is resolved right before every delivery, since its DNS
+        // records can change after the webhook was created.
+        let target = match ctx.config.insecure_webhooks {
+            true => Ok(None),
+            false => resolve_public_target(&webhook.url).await.map(Some),
+        };
+
+        let response = match target {
+            Ok(target) => {
+                // Connect to the addresses that were checked, instead of
+                // letting reqwest resolve the host again.
+                if let Some(PublicTarget {
+                    domain: Some(domain),
+                    addrs,
+                }) = &target
+                {
+                    client = client.resolve_to_addrs(domain, addrs);
+                }
+
+                client
+                    .build()?
+                    .post(&webhook.url)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(EVENT_HEADER, &delivery.event)
+                    .header(DELIVERY_HEADER, delivery.id.to_string())
+                    .header(TIMESTAMP_HEADER, timestamp.to_string())
+                    .header(SIGNATURE_HEADER, format!("sha256={signature}"))
+                    .body(body)
+                    .send()
+                    .await
+                    .map_err(|error| {
+                        warn!("Failed to send webhook event {}: {error}", delivery.id);
+                        REQUEST_FAILED
+                    })
+            }
+            Err(error) => Err(error),
+        };
+
+        let (response_status, error) = match response {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None)
+            }
+            Ok(response) => {
+                let status = response.status();
+                (
+                    Some(status.as_u16()),
+                    Some(format!("Unexpected status {status}")),
+                )
+            }
+            Err(error) => (None, Some(error.to_string())),
+        };
+
+        let delivered = error.is_none();
+        diesel::update(webhook_deliveries::table.find(delivery.id))
+            .set((
+                webhook_deliveries::attempts.eq(webhook_deliveries::attempts + 1),
+                webhook_deliveries::last_attempt_at.eq(now),
+                webhook_deliveries::response_status.eq(response_status.map(i32::from)),
+                webhook_deliveries::error.eq(&error),
+                webhook_deliveries::delivered_at.eq(delivered.then(Utc::now)),
+            ))
+            .execute(&mut conn)
+            .await?;
+
+        match error {
+            None => Ok(()),
+            Some(error) => Err(anyhow!(
+                "Failed to deliver webhook event {}: {error}",
+                delivery.id
+            )),
+        }
+    }
+}
+
+/// The checked addresses of a webhook URL.
+struct PublicTarget {
+    /// The domain of the URL, or `None` if the host is an IP address.
+    domain: Option<String>,
+    addrs: Vec<SocketAddr>,
+}
+
+/// Resolves the host of a webhook URL, and checks that all of its addresses
+/// are public, so that webhooks can not be used to send requests to internal
+/// services.
+async fn resolve_public_target(url: &str) -> Result<PublicTarget, &'static str> {
+    const INVALID_URL: &str = "Invalid webhook URL";
+    const RESOLVE_FAILED: &str = "Failed to resolve the webhook host";
+
+    let url = Url::parse(url).map_err(|_| INVALID_URL)?;
+    if url.scheme() != "https" {
+        return Err("Webhook URLs must use `https`");
+    }
+
+    let port = url.port_or_known_default().ok_or(INVALID_URL)?;
+    let (domain, addrs) = match url.host().ok_or(INVALID_URL)? {
+        Host::Domain(domain) => {
+            let addrs = lookup_host((domain, port))
+                .await
+                .map_err(|_| RESOLVE_FAILED)?
+                .collect::<Vec<_>>();
+            (Some(domain.to_string()), addrs)
+        }
+        Host::Ipv4(ip) => (None, vec![SocketAddr::new(ip.into(), port)]),
+        Host::Ipv6(ip) => (None, vec![SocketAddr::new(ip.into(), port)]),
+    };
+
+    if addrs.is_empty() {
+        return Err(RESOLVE_FAILED);
+    }
+    if !addrs.iter().all(|addr| is_public_ip(addr.ip())) {
+        return Err("The webhook host does not resolve to a public IP address");
+    }
+
+    Ok(PublicTarget { domain, addrs })
+}
+
+/// Checks that an IP address is not a loopback, private, link-local,
+/// unique-local, unspecified, or otherwise non-global address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    let is_shared = a == 100 && (b & 0b1100_0000) == 64;
+
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || is_shared
+        || a == 0)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    // IPv4-mapped (`::ffff:0:0/96`) and NAT64 (`64:ff9b::/96`) addresses
+    // are checked like the IPv4 addresses that they embed.
+    if let Some(ipv4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(ipv4);
+    }
+    if ip.segments()[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., a, b, c, d] = ip.octets();
+        return is_public_ipv4(Ipv4Addr::new(a, b, c, d));
+    }
+
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local())
+}
+
+/// Signs a webhook payload.
+///
+/// The signature is the hex-encoded HMAC-SHA256 of `{timestamp}.{body}`,
+/// keyed with the secret of the webhook. Receivers should recompute it and
+/// reject payloads with old timestamps to prevent replay attacks.
+pub fn sign(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        let signature = sign(b"secret", 1_700_000_000, br#"{"event":"publish"}"#);
+        assert_eq!(
+            signature,
+            "7260ed341a49d9d84fe51d5d74497c41a2bf5ccfad573a67aecd1ee10381b746"
+        );
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        let is_public = |ip: &str| is_public_ip(ip.parse().unwrap());
+
+        assert!(is_public("93.184.216.34"));
+        assert!(is_public("2606:2800:220:1:248:1893:25c8:1946"));
+
+        assert!(!is_public("127.0.0.1"));
+        assert!(!is_public("10.1.2.3"));
+        assert!(!is_public("172.16.0.1"));
+        assert!(!is_public("192.168.1.1"));
+        assert!(!is_public("169.254.169.254"));
+        assert!(!is_public("100.64.0.1"));
+        assert!(!is_public("0.0.0.0"));
+        assert!(!is_public("::"));
+        assert!(!is_public("::1"));
+        assert!(!is_public("fd00::1"));
+        assert!(!is_public("fe80::1"));
+        assert!(!is_public("::ffff:127.0.0.1"));
+        assert!(!is_public("64:ff9b::a9fe:a9fe"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_public_target() {
+        let target = resolve_public_target("https://93.184.216.34:8443/hook").await;
+        let addrs = target.unwrap().addrs;
+        assert_eq!(addrs, ["93.184.216.34:8443".parse().unwrap()]);
+
+        let error = resolve_public_target("http://93.184.216.34/hook").await;
+        assert_eq!(error.err(), Some("Webhook URLs must use `https`"));
+
+        let error = resolve_public_target("https://169.254.169.254/latest/meta-data").await;
+        let expected = "The webhook host does not resolve to a public IP address";
+        assert_eq!(error.err(), Some(expected));
+
+        let error = resolve_public_target("https://localhost:8080/hook").await;
+        assert_eq!(error.err(), Some(expected));
+
+        let error = resolve_public_target("https://[::1]/hook").await;
+        assert_eq!(error.err(), Some(expected));
+    }
+}
@@ -5,7 +5,7 @@
 //! runner, and the `jobs` submodule contains the application-specific
 //! background job definitions.
 
-use crates_io_worker::Runner;
+use crates_io_worker::{Runner, Schedule};
 use std::sync::Arc;
 
 mod environment;
@@ -15,6 +15,10 @@ pub use self::environment::Environment;
 
 pub trait RunnerExt {
     fn register_crates_io_job_types(self) -> Self;
+
+    /// Schedules the recurring jobs that were previously enqueued by an
+    /// external scheduler. All times are in UTC.
+    fn schedule_crates_io_jobs(self) -> Self;
 }
 
 impl RunnerExt for Runner<Arc<Environment>> {
@@ -24,15 +28,20 @@ impl RunnerExt for Runner<Arc<Environment>> {
             .register_job_type::<jobs::ArchiveVersionDownloads>()
             .register_job_type::<jobs::BuildCrateZip>()
             .register_job_type::<jobs::BulkSyncToGitIndex>()
+            .register_job_type::<jobs::CheckCompatibility>()
             .register_job_type::<jobs::CheckTyposquat>()
             .register_job_type::<jobs::CleanProcessedLogFiles>()
             .register_job_type::<jobs::DailyDbMaintenance>()
             .register_job_type::<jobs::DeleteCrateFromStorage>()
             .register_job_type::<jobs::DocsRsQueueRebuild>()
             .register_job_type::<jobs::DumpDb>()
+            .register_job_type::<jobs::DumpDbDelta>()
+            .register_job_type::<jobs::ExpireStagedVersions>()
             .register_job_type::<jobs::GenerateOgImage>()
+            .register_job_type::<jobs::ImportAdvisories>()
             .register_job_type::<jobs::IndexVersionDownloadsArchive>()
             .register_job_type::<jobs::InvalidateCdns>()
+            .register_job_type::<jobs::MirrorCrates>()
             .register_job_type::<jobs::NormalizeIndex>()
             .register_job_type::<jobs::ProcessCdnLog>()
             .register_job_type::<jobs::ProcessCdnLogQueue>()
@@ -45,12 +54,39 @@ impl RunnerExt for Runner<Arc<Environment>> {
             .register_job_type::<jobs::UpdateDownloads>()
             .register_job_type::<jobs::UpdateDefaultVersion>()
             .register_job_type::<jobs::UpdateUserFromGithub>()
+            .register_job_type::<jobs::DispatchWebhookEvent>()
+            .register_job_type::<jobs::DeliverWebhook>()
             .register_job_type::<jobs::SendTokenExpiryNotifications>()
             .register_job_type::<jobs::SendPublishNotificationsJob>()
+            .register_job_type::<jobs::SendPublishNotificationDigests>()
             .register_job_type::<jobs::rss::SyncCrateFeed>()
             .register_job_type::<jobs::rss::SyncCratesFeed>()
             .register_job_type::<jobs::rss::SyncUpdatesFeed>()
             .register_job_type::<jobs::trustpub::DeleteExpiredJtis>()
             .register_job_type::<jobs::trustpub::DeleteExpiredTokens>()
     }
+
+    fn schedule_crates_io_jobs(self) -> Self {
+        self.schedule_job(schedule("0 0 1 * * *"), jobs::DailyDbMaintenance)
+            .schedule_job(schedule("0 0 2 * * *"), jobs::DumpDb::default())
+            .schedule_job(schedule("0 15 * * * *"), jobs::DumpDbDelta)
+            .schedule_job(schedule("0 0 * * * *"), jobs::SendTokenExpiryNotifications)
+            .schedule_job(schedule("0 30 * * * *"), jobs::SyncAdmins)
+            .schedule_job(schedule("0 45 * * * *"), jobs::ExpireStagedVersions)
+            .schedule_job(
+                schedule("0 50 * * * *"),
+                jobs::SendPublishNotificationDigests,
+            )
+    }
+}
+
+/// Parses a cron expression with a leading seconds field.
+///
+/// # Panics
+///
+/// Panics if the expression is invalid.
+fn schedule(expression: &str) -> Schedule {
+    expression
+        .parse()
+        .unwrap_or_else(|error| panic!("Invalid cron expression `{expression}`: {error}"))
 }
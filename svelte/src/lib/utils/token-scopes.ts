@@ -2,7 +2,10 @@ const DESCRIPTIONS: Record<string, string> = {
   'change-owners': 'Invite new crate owners or remove existing ones',
   'publish-new': 'Publish new crates',
   'publish-update': 'Publish new versions of existing crates',
+  'rebuild-docs': 'Trigger documentation rebuilds on docs.rs',
   'trusted-publishing': 'Manage trusted publishing configurations',
+  'update-crate': 'Update crate settings',
+  'update-version': 'Update crate versions, including their yank status',
   yank: 'Yank and unyank crate versions',
 };
 
@@ -0,0 +1,3 @@
+export function load({ params }) {
+  return { token: params.token, category: params.category };
+}